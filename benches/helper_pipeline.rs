@@ -0,0 +1,188 @@
+//! Events/sec through `handle_saga_event_with_emit` with in-memory storage.
+//!
+//! Each saga delivers three events to one participant: `SagaStarted` (the
+//! participant journals and executes its step), a redelivered `SagaStarted`
+//! (rejected by the dedupe store) and `SagaCompleted` (terminal pruning).
+//! All sagas are started before any completes, so the saga count also sets
+//! how much per-saga state is live while events are handled.
+//!
+//! Run with `cargo bench --bench helper_pipeline`.
+
+use std::sync::Arc;
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use icanact_saga_choreography::{
+    handle_saga_event_with_emit, CompensationError, DependencySpec, HasSagaParticipantSupport,
+    InMemoryDedupe, InMemoryJournal, ParticipantDedupeStore, SagaChoreographyEvent, SagaContext,
+    SagaId, SagaParticipant, SagaParticipantSupport, StepError, StepOutput,
+};
+
+const SAGA_TYPE: &str = "bench_workflow";
+const EVENTS_PER_SAGA: u64 = 3;
+
+struct EchoParticipant<D: ParticipantDedupeStore> {
+    saga: SagaParticipantSupport<InMemoryJournal, D>,
+}
+
+impl<D: ParticipantDedupeStore> EchoParticipant<D> {
+    fn new(dedupe: D) -> Self {
+        Self {
+            saga: SagaParticipantSupport::new(InMemoryJournal::new(), dedupe),
+        }
+    }
+}
+
+impl<D: ParticipantDedupeStore> HasSagaParticipantSupport for EchoParticipant<D> {
+    type Journal = InMemoryJournal;
+    type Dedupe = D;
+
+    fn saga_support(&self) -> &SagaParticipantSupport<Self::Journal, Self::Dedupe> {
+        &self.saga
+    }
+
+    fn saga_support_mut(&mut self) -> &mut SagaParticipantSupport<Self::Journal, Self::Dedupe> {
+        &mut self.saga
+    }
+}
+
+impl<D: ParticipantDedupeStore> SagaParticipant for EchoParticipant<D> {
+    type Error = String;
+
+    fn step_name(&self) -> &str {
+        "reserve"
+    }
+
+    fn saga_types(&self) -> &[&'static str] {
+        &[SAGA_TYPE]
+    }
+
+    fn depends_on(&self) -> DependencySpec {
+        DependencySpec::OnSagaStart
+    }
+
+    fn execute_step(
+        &mut self,
+        _context: &SagaContext,
+        input: &[u8],
+    ) -> Result<StepOutput, StepError> {
+        Ok(StepOutput::Completed {
+            output: input.to_vec(),
+            compensation_data: Vec::new(),
+        })
+    }
+
+    fn compensate_step(
+        &mut self,
+        _context: &SagaContext,
+        _compensation_data: &[u8],
+    ) -> Result<(), CompensationError> {
+        Ok(())
+    }
+}
+
+/// Events for sagas `first_saga..first_saga + sagas`, starts before completions.
+fn saga_events(first_saga: u64, sagas: u64, payload_size: usize) -> Vec<SagaChoreographyEvent> {
+    let payload = vec![0xA5; payload_size];
+    let contexts: Vec<SagaContext> = (first_saga..first_saga + sagas)
+        .map(|id| SagaContext::start(SagaId::new(id), SAGA_TYPE.into(), "reserve".into(), [0; 32]))
+        .collect();
+    let mut events = Vec::with_capacity(contexts.len() * EVENTS_PER_SAGA as usize);
+    for context in &contexts {
+        let started = SagaChoreographyEvent::SagaStarted {
+            context: context.clone(),
+            payload: payload.clone(),
+        };
+        events.push(started.clone());
+        events.push(started);
+    }
+    events.extend(
+        contexts
+            .into_iter()
+            .map(|context| SagaChoreographyEvent::SagaCompleted { context }),
+    );
+    events
+}
+
+fn drive<D: ParticipantDedupeStore>(
+    participant: &mut EchoParticipant<D>,
+    events: Vec<SagaChoreographyEvent>,
+) -> usize {
+    let mut emitted = 0;
+    for event in events {
+        handle_saga_event_with_emit(participant, event, |_| emitted += 1);
+    }
+    emitted
+}
+
+fn single_participant(c: &mut Criterion) {
+    let mut group = c.benchmark_group("helper_pipeline");
+    for payload_size in [64usize, 4 * 1024, 64 * 1024] {
+        for sagas in [1u64, 100, 1_000] {
+            group.throughput(Throughput::Elements(sagas * EVENTS_PER_SAGA));
+            group.bench_with_input(
+                BenchmarkId::new(format!("payload_{payload_size}"), sagas),
+                &sagas,
+                |b, &sagas| {
+                    b.iter_batched(
+                        || {
+                            (
+                                EchoParticipant::new(InMemoryDedupe::new()),
+                                saga_events(1, sagas, payload_size),
+                            )
+                        },
+                        |(mut participant, events)| drive(&mut participant, events),
+                        BatchSize::LargeInput,
+                    )
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+fn shared_dedupe(c: &mut Criterion) {
+    const SAGAS_PER_THREAD: u64 = 500;
+    const PAYLOAD_SIZE: usize = 256;
+
+    let mut group = c.benchmark_group("helper_pipeline_shared_dedupe");
+    for threads in [1u64, 2, 4, 8] {
+        group.throughput(Throughput::Elements(
+            threads * SAGAS_PER_THREAD * EVENTS_PER_SAGA,
+        ));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(threads),
+            &threads,
+            |b, &threads| {
+                b.iter_batched(
+                    || {
+                        let dedupe = Arc::new(InMemoryDedupe::new());
+                        (0..threads)
+                            .map(|thread| {
+                                (
+                                    EchoParticipant::new(Arc::clone(&dedupe)),
+                                    saga_events(
+                                        1 + thread * SAGAS_PER_THREAD,
+                                        SAGAS_PER_THREAD,
+                                        PAYLOAD_SIZE,
+                                    ),
+                                )
+                            })
+                            .collect::<Vec<_>>()
+                    },
+                    |workers| {
+                        std::thread::scope(|scope| {
+                            for (mut participant, events) in workers {
+                                scope.spawn(move || drive(&mut participant, events));
+                            }
+                        })
+                    },
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, single_participant, shared_dedupe);
+criterion_main!(benches);
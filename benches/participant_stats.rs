@@ -0,0 +1,75 @@
+//! Contention of `ParticipantStats` when several actors bump different
+//! counters of one shared instance, against the same counters packed into
+//! adjacent `AtomicU64`s.
+//!
+//! Run with `cargo bench --bench participant_stats`.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use icanact_saga_choreography::ParticipantStats;
+
+const INCREMENTS_PER_THREAD: u64 = 100_000;
+
+/// The pre-padding layout: counters share cache lines.
+#[derive(Default)]
+struct PackedStats {
+    counters: [AtomicU64; 4],
+}
+
+fn run_threads<F>(threads: usize, bump: F)
+where
+    F: Fn(usize) + Sync,
+{
+    std::thread::scope(|scope| {
+        for thread in 0..threads {
+            let bump = &bump;
+            scope.spawn(move || {
+                for _ in 0..INCREMENTS_PER_THREAD {
+                    bump(thread);
+                }
+            });
+        }
+    });
+}
+
+fn shared_stats(c: &mut Criterion) {
+    let mut group = c.benchmark_group("participant_stats_contention");
+    for threads in [1usize, 2, 4] {
+        group.throughput(Throughput::Elements(threads as u64 * INCREMENTS_PER_THREAD));
+
+        let packed = Arc::new(PackedStats::default());
+        group.bench_with_input(
+            BenchmarkId::new("packed", threads),
+            &threads,
+            |b, &threads| {
+                b.iter(|| {
+                    run_threads(threads, |thread| {
+                        packed.counters[thread].fetch_add(1, Ordering::Relaxed);
+                    })
+                })
+            },
+        );
+
+        let padded = Arc::new(ParticipantStats::new());
+        group.bench_with_input(
+            BenchmarkId::new("padded", threads),
+            &threads,
+            |b, &threads| {
+                b.iter(|| {
+                    run_threads(threads, |thread| match thread {
+                        0 => padded.events_received.increment(),
+                        1 => padded.steps_started.increment(),
+                        2 => padded.steps_completed.increment(),
+                        _ => padded.duplicate_events.increment(),
+                    })
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, shared_stats);
+criterion_main!(benches);
@@ -0,0 +1,279 @@
+//! Detection of steps that no participant picks up.
+//!
+//! Local delivery is fire-and-forget: when no participant runs a step, or the
+//! one that does is down, the saga waits until the terminal resolver's
+//! stalled timeout, if one is attached at all. A [`StepAckWatchdog`] watches
+//! the bus for the steps a saga is about to run and reports
+//! [`SagaChoreographyEvent::SagaStalled`] for each one that sees neither an
+//! accepting [`SagaChoreographyEvent::StepAck`] nor any other event of its
+//! own within the ack window. The initiator decides what to do with the
+//! stall: fail the saga, publish the start again, or page someone.
+//!
+//! A saga's first step is due as soon as it starts. With a registered
+//! [`SagaWorkflowContract`] later steps are due once their dependencies
+//! complete; without one only the first step is watched.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use icanact_core::local::EventSubscription;
+
+use crate::workflow_contract::dependency_steps;
+use crate::{
+    AckStatus, SagaBusPublishError, SagaChoreographyBus, SagaChoreographyEvent, SagaContext,
+    SagaId, SagaType, SagaWorkflowContract, SagaWorkflowStepContract, StepName,
+    WorkflowDependencySpec,
+};
+
+struct DueStep {
+    context: SagaContext,
+    due_since_millis: u64,
+}
+
+#[derive(Default)]
+struct WatchedSaga {
+    due: BTreeMap<StepName, DueStep>,
+    /// Steps that were acknowledged or reported; never due again.
+    settled: HashSet<StepName>,
+    completed: HashSet<StepName>,
+}
+
+#[derive(Default)]
+struct WatchdogState {
+    workflows: HashMap<SagaType, &'static [SagaWorkflowStepContract]>,
+    sagas: BTreeMap<SagaId, WatchedSaga>,
+}
+
+/// Reports steps left unacknowledged for longer than the ack window. Cloning
+/// shares the same state.
+#[derive(Clone)]
+pub struct StepAckWatchdog {
+    state: Arc<Mutex<WatchdogState>>,
+    ack_window_millis: u64,
+}
+
+impl StepAckWatchdog {
+    pub fn new(ack_window: Duration) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(WatchdogState::default())),
+            ack_window_millis: ack_window.as_millis() as u64,
+        }
+    }
+
+    /// Declares the steps of `C`'s saga type so steps after the first are
+    /// watched as their dependencies complete.
+    pub fn register_contract<C: SagaWorkflowContract>(&self) {
+        self.lock()
+            .workflows
+            .insert(C::saga_type().into(), C::steps());
+    }
+
+    /// Subscribes the watchdog to every event of `saga_type`.
+    pub fn subscribe(&self, bus: &SagaChoreographyBus, saga_type: &str) -> EventSubscription {
+        let watchdog = self.clone();
+        bus.subscribe_saga_type_fn(saga_type, move |event| {
+            watchdog.ingest(event);
+            true
+        })
+    }
+
+    pub fn ingest(&self, event: &SagaChoreographyEvent) {
+        self.ingest_at(event, SagaContext::now_millis());
+    }
+
+    /// Returns a `SagaStalled` event for every step whose ack window has
+    /// passed. Each step is reported once.
+    pub fn poll_stalled(&self) -> Vec<SagaChoreographyEvent> {
+        self.poll_stalled_at(SagaContext::now_millis())
+    }
+
+    /// Polls for stalled steps and publishes each `SagaStalled` on `bus`.
+    /// Meant to run on the initiator's timer.
+    pub fn publish_stalled(&self, bus: &SagaChoreographyBus) -> Result<usize, SagaBusPublishError> {
+        let stalled = self.poll_stalled();
+        let count = stalled.len();
+        for event in stalled {
+            bus.publish_strict(event)?;
+        }
+        Ok(count)
+    }
+
+    /// Number of steps currently waiting for an ack.
+    pub fn due_len(&self) -> usize {
+        self.lock().sagas.values().map(|saga| saga.due.len()).sum()
+    }
+
+    pub(crate) fn ingest_at(&self, event: &SagaChoreographyEvent, now_millis: u64) {
+        let context = event.context();
+        let mut guard = self.lock();
+        let state = &mut *guard;
+        if event.terminal_outcome().is_some() {
+            state.sagas.remove(&context.saga_id);
+            return;
+        }
+        let workflow = state
+            .workflows
+            .get(context.saga_type.as_ref())
+            .copied()
+            .unwrap_or_default();
+        match event {
+            SagaChoreographyEvent::SagaStarted { .. } => {
+                let saga = state.sagas.entry(context.saga_id).or_default();
+                saga.make_due(context.step_name.clone(), context.clone(), now_millis);
+                saga.make_ready_steps_due(workflow, context, now_millis);
+            }
+            SagaChoreographyEvent::SagaStalled { .. }
+            | SagaChoreographyEvent::InitiatorHeartbeat { .. }
+            | SagaChoreographyEvent::InitiatorTakenOver { .. }
+            | SagaChoreographyEvent::StepAck {
+                status: AckStatus::NotApplicable,
+                ..
+            } => {}
+            _ => {
+                let Some(saga) = state.sagas.get_mut(&context.saga_id) else {
+                    return;
+                };
+                saga.due.remove(&context.step_name);
+                saga.settled.insert(context.step_name.clone());
+                if let SagaChoreographyEvent::StepCompleted { .. } = event {
+                    saga.completed.insert(context.step_name.clone());
+                    saga.make_ready_steps_due(workflow, context, now_millis);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn poll_stalled_at(&self, now_millis: u64) -> Vec<SagaChoreographyEvent> {
+        let mut stalled = Vec::new();
+        let mut guard = self.lock();
+        for saga in guard.sagas.values_mut() {
+            let expired: Vec<StepName> = saga
+                .due
+                .iter()
+                .filter(|(_, due)| {
+                    now_millis.saturating_sub(due.due_since_millis) > self.ack_window_millis
+                })
+                .map(|(step, _)| step.clone())
+                .collect();
+            for step in expired {
+                let Some(due) = saga.due.remove(&step) else {
+                    continue;
+                };
+                saga.settled.insert(step.clone());
+                let waited_millis = now_millis.saturating_sub(due.due_since_millis);
+                let mut context = due.context;
+                context.event_timestamp_millis = now_millis;
+                tracing::warn!(
+                    target: "core::saga",
+                    event = "saga_step_stalled",
+                    saga_id = context.saga_id.get(),
+                    saga_type = context.saga_type.as_ref(),
+                    step_name = step.as_ref(),
+                    waited_millis
+                );
+                stalled.push(SagaChoreographyEvent::SagaStalled {
+                    context,
+                    step,
+                    waited_millis,
+                });
+            }
+        }
+        stalled
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, WatchdogState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl WatchedSaga {
+    fn make_due(&mut self, step: StepName, context: SagaContext, now_millis: u64) {
+        if self.settled.contains(&step) || self.due.contains_key(&step) {
+            return;
+        }
+        self.due.insert(
+            step,
+            DueStep {
+                context,
+                due_since_millis: now_millis,
+            },
+        );
+    }
+
+    fn make_ready_steps_due(
+        &mut self,
+        workflow: &'static [SagaWorkflowStepContract],
+        trigger: &SagaContext,
+        now_millis: u64,
+    ) {
+        for step in workflow {
+            let dependencies = dependency_steps(step.depends_on);
+            let completed = |dependency: &&str| self.completed.contains(*dependency);
+            let ready = match step.depends_on {
+                WorkflowDependencySpec::OnSagaStart => true,
+                WorkflowDependencySpec::AnyOf(_) => dependencies.iter().any(completed),
+                WorkflowDependencySpec::After(_) | WorkflowDependencySpec::AllOf(_) => {
+                    dependencies.iter().all(completed)
+                }
+            };
+            if ready {
+                let context = trigger.next_step(step.step_name.into());
+                self.make_due(step.step_name.into(), context, now_millis);
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for StepAckWatchdog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StepAckWatchdog")
+            .field("ack_window_millis", &self.ack_window_millis)
+            .field("due_len", &self.due_len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{saga_started, DeterministicContextBuilder, PeerId};
+
+    #[test]
+    fn unacknowledged_step_is_reported_once_after_the_window() {
+        let watchdog = StepAckWatchdog::new(Duration::from_millis(500));
+        let context = DeterministicContextBuilder::default().build();
+        watchdog.ingest_at(&saga_started(context.clone(), Vec::new()), 1_000);
+
+        assert!(watchdog.poll_stalled_at(1_400).is_empty());
+        let stalled = watchdog.poll_stalled_at(1_600);
+        let [SagaChoreographyEvent::SagaStalled {
+            step,
+            waited_millis,
+            ..
+        }] = stalled.as_slice()
+        else {
+            panic!("expected one stall: {stalled:?}");
+        };
+        assert_eq!(step, &context.step_name);
+        assert_eq!(*waited_millis, 600);
+        assert!(watchdog.poll_stalled_at(5_000).is_empty());
+
+        let other = DeterministicContextBuilder::default()
+            .with_saga_id(2)
+            .build();
+        watchdog.ingest_at(&saga_started(other.clone(), Vec::new()), 1_000);
+        watchdog.ingest_at(
+            &SagaChoreographyEvent::StepAck {
+                context: other,
+                participant_id: PeerId::default(),
+                status: AckStatus::Accepted,
+            },
+            1_100,
+        );
+        assert_eq!(watchdog.due_len(), 0);
+        assert!(watchdog.poll_stalled_at(5_000).is_empty());
+    }
+}
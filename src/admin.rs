@@ -0,0 +1,498 @@
+//! Operator tooling over a participant journal.
+//!
+//! [`SagaAdmin`] lists the sagas a journal still holds, dumps their event
+//! history, and rebuilds the quarantined ones into a [`QuarantineManager`] so
+//! they can be resolved or have their compensation retried. [`AdminCli`] is
+//! the clap interface used by the `saga-admin` binary; services that want
+//! `retry` to work build their own binary around it and register their
+//! compensators with [`SagaAdmin::with_compensator`].
+//!
+//! Closing a quarantine is written back to the journal so the next run sees
+//! it: a manual `resolve` prunes the saga, a successful `retry` appends
+//! `CompensationCompleted`, and a failed one appends the failure followed by
+//! a new `Quarantined` entry.
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use clap::{Parser, Subcommand};
+
+use crate::journal::last_progress_entry;
+use crate::{
+    CompensationError, InboxEntry, JournalEntry, JournalError, ParticipantEvent,
+    ParticipantJournal, QuarantineError, QuarantineManager, QuarantineResolution, QuarantinedSaga,
+    SagaContext, SagaId, StepName,
+};
+
+type Compensator = Box<dyn Fn(&SagaContext, &[u8]) -> Result<(), CompensationError> + Send>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AdminError {
+    #[error(transparent)]
+    Journal(#[from] JournalError),
+    #[error(transparent)]
+    Quarantine(#[from] QuarantineError),
+    #[error("no compensator registered for step {0}")]
+    NoCompensator(Box<str>),
+    #[error("output error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// One saga held by the journal.
+#[derive(Clone, Debug)]
+pub struct SagaSummary {
+    pub saga_id: SagaId,
+    pub entries: usize,
+    pub last_event: Option<&'static str>,
+    pub last_recorded_at_millis: Option<u64>,
+    pub quarantined: bool,
+}
+
+/// Everything the journal recorded for one saga.
+#[derive(Clone, Debug)]
+pub struct SagaHistory {
+    pub saga_id: SagaId,
+    pub journal: Vec<JournalEntry>,
+    /// Incoming events, when the journal keeps an incoming history.
+    pub incoming: Vec<InboxEntry>,
+}
+
+/// Inspection and quarantine commands over one participant journal.
+pub struct SagaAdmin<J> {
+    journal: J,
+    quarantine: QuarantineManager,
+    participant_id: Box<str>,
+    step: Option<StepName>,
+    compensators: HashMap<Box<str>, Compensator>,
+}
+
+impl<J> SagaAdmin<J>
+where
+    J: ParticipantJournal,
+{
+    pub fn new(journal: J) -> Self {
+        Self {
+            journal,
+            quarantine: QuarantineManager::new(),
+            participant_id: "saga-admin".into(),
+            step: None,
+            compensators: HashMap::new(),
+        }
+    }
+
+    /// Participant id reported on quarantine records.
+    pub fn with_participant_id(mut self, participant_id: &str) -> Self {
+        self.participant_id = participant_id.into();
+        self
+    }
+
+    /// Step the journal's participant runs. Without it the step is taken from
+    /// the saga's last incoming event.
+    pub fn with_step(mut self, step: &str) -> Self {
+        self.step = Some(step.into());
+        self
+    }
+
+    /// Registers the compensation `retry` runs for quarantined sagas of `step`.
+    pub fn with_compensator<F>(mut self, step: &str, compensate: F) -> Self
+    where
+        F: Fn(&SagaContext, &[u8]) -> Result<(), CompensationError> + Send + 'static,
+    {
+        self.compensators.insert(step.into(), Box::new(compensate));
+        self
+    }
+
+    pub fn journal(&self) -> &J {
+        &self.journal
+    }
+
+    pub fn quarantine(&self) -> &QuarantineManager {
+        &self.quarantine
+    }
+
+    pub fn sagas(&self) -> Result<Vec<SagaSummary>, AdminError> {
+        let mut saga_ids = self.journal.list_sagas()?;
+        saga_ids.sort();
+        saga_ids
+            .into_iter()
+            .map(|saga_id| {
+                let entries = self.journal.read(saga_id)?;
+                let last = entries.last();
+                Ok(SagaSummary {
+                    saga_id,
+                    entries: entries.len(),
+                    last_event: last.map(|entry| entry.event.event_type()),
+                    last_recorded_at_millis: last.map(|entry| entry.recorded_at_millis),
+                    quarantined: last_progress_entry(&entries).is_some_and(|entry| {
+                        matches!(entry.event, ParticipantEvent::Quarantined { .. })
+                    }),
+                })
+            })
+            .collect()
+    }
+
+    pub fn history(&self, saga_id: SagaId) -> Result<SagaHistory, AdminError> {
+        Ok(SagaHistory {
+            saga_id,
+            journal: self.journal.read(saga_id)?,
+            incoming: self.journal.incoming_history(saga_id)?,
+        })
+    }
+
+    /// Rebuilds quarantine records from the journal into
+    /// [`Self::quarantine`] and returns them.
+    pub fn load_quarantined(&self) -> Result<Vec<QuarantinedSaga>, AdminError> {
+        for summary in self.sagas()? {
+            if summary.quarantined {
+                self.quarantine
+                    .record(self.quarantined_saga(summary.saga_id)?);
+            }
+        }
+        Ok(self.quarantine.list())
+    }
+
+    /// Closes a quarantine after manual intervention and prunes the saga from
+    /// the journal.
+    pub fn resolve(
+        &self,
+        saga_id: SagaId,
+        operator: &str,
+        note: &str,
+    ) -> Result<QuarantineResolution, AdminError> {
+        self.load_quarantined()?;
+        let step = self.quarantined_saga(saga_id)?.step;
+        let resolution = self.quarantine.resolve(saga_id, &step, operator, note)?;
+        self.journal.prune(saga_id)?;
+        Ok(resolution)
+    }
+
+    /// Retries the failed compensation with the registered compensator for
+    /// the quarantined step and journals the outcome.
+    pub fn retry(
+        &self,
+        saga_id: SagaId,
+        operator: &str,
+    ) -> Result<QuarantineResolution, AdminError> {
+        self.load_quarantined()?;
+        let step = self.quarantined_saga(saga_id)?.step;
+        let saga = self
+            .quarantine
+            .inspect(saga_id, &step)
+            .ok_or(QuarantineError::NotQuarantined(saga_id.get()))?;
+        let compensate = self
+            .compensators
+            .get(saga.step.as_str())
+            .ok_or_else(|| AdminError::NoCompensator(saga.step.clone().into()))?;
+        match self
+            .quarantine
+            .retry_compensation(saga_id, &step, operator, |context, data| {
+                compensate(context, data)
+            }) {
+            Ok(resolution) => {
+                self.journal.append(
+                    saga_id,
+                    ParticipantEvent::CompensationCompleted {
+                        completed_at_millis: SagaContext::now_millis(),
+                    },
+                )?;
+                Ok(resolution)
+            }
+            Err(QuarantineError::RetryFailed(err)) => {
+                let now = SagaContext::now_millis();
+                let reason: Box<str> = err.reason().into();
+                self.journal.append(
+                    saga_id,
+                    ParticipantEvent::CompensationFailed {
+                        error: reason.clone(),
+                        is_ambiguous: err.is_ambiguous(),
+                        failed_at_millis: now,
+                        details: err.details().to_vec(),
+                    },
+                )?;
+                self.journal.append(
+                    saga_id,
+                    ParticipantEvent::Quarantined {
+                        reason,
+                        quarantined_at_millis: now,
+                    },
+                )?;
+                Err(QuarantineError::RetryFailed(err).into())
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn quarantined_saga(&self, saga_id: SagaId) -> Result<QuarantinedSaga, AdminError> {
+        let history = self.journal.inspect(saga_id)?;
+        let incoming = self.journal.incoming_history(saga_id)?;
+        let context = incoming
+            .into_iter()
+            .last()
+            .map(|entry| entry.event.into_context())
+            .unwrap_or_else(|| {
+                SagaContext::start(saga_id, "unknown".into(), "unknown".into(), [0; 32])
+            });
+        let step = self
+            .step
+            .clone()
+            .unwrap_or_else(|| context.step_name.clone());
+        let compensation_data = history.compensation_data().map(<[u8]>::to_vec);
+        let (reason, quarantined_at_millis, times) =
+            history.quarantine.map_or_else(Default::default, |record| {
+                (record.reason, record.quarantined_at_millis, record.times)
+            });
+        Ok(QuarantinedSaga {
+            context,
+            step,
+            participant_id: self.participant_id.clone(),
+            reason,
+            quarantined_at_millis,
+            compensation_data,
+            failed_retries: times.saturating_sub(1),
+        })
+    }
+}
+
+impl<J> std::fmt::Debug for SagaAdmin<J> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SagaAdmin")
+            .field("participant_id", &self.participant_id)
+            .field("step", &self.step)
+            .field("compensators", &self.compensators.len())
+            .finish()
+    }
+}
+
+/// Command line of the `saga-admin` binary.
+#[derive(Debug, Parser)]
+#[command(
+    name = "saga-admin",
+    about = "Inspect a participant journal and manage quarantined sagas"
+)]
+pub struct AdminCli {
+    /// Directory of the participant's LMDB journal.
+    #[arg(long)]
+    pub journal: std::path::PathBuf,
+    /// Participant id reported on quarantine records.
+    #[arg(long, default_value = "saga-admin")]
+    pub participant: String,
+    /// Step the participant runs; defaults to the step of the last incoming event.
+    #[arg(long)]
+    pub step: Option<String>,
+    #[command(subcommand)]
+    pub command: AdminCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AdminCommand {
+    /// List the sagas held by the journal.
+    List,
+    /// Dump the journal entries and incoming events of one saga.
+    History { saga_id: u64 },
+    /// Show quarantined sagas.
+    Quarantined,
+    /// Close a quarantine after manual intervention.
+    Resolve {
+        saga_id: u64,
+        #[arg(long)]
+        operator: String,
+        #[arg(long, default_value = "")]
+        note: String,
+    },
+    /// Retry the failed compensation of a quarantined saga.
+    Retry {
+        saga_id: u64,
+        #[arg(long)]
+        operator: String,
+    },
+}
+
+/// Runs `command` against `admin`, writing a plain-text report to `out`.
+pub fn run_admin_command<J, W>(
+    admin: &SagaAdmin<J>,
+    command: &AdminCommand,
+    out: &mut W,
+) -> Result<(), AdminError>
+where
+    J: ParticipantJournal,
+    W: Write,
+{
+    match command {
+        AdminCommand::List => {
+            for saga in admin.sagas()? {
+                writeln!(
+                    out,
+                    "{}\tentries={}\tlast={}\tat={}{}",
+                    saga.saga_id.get(),
+                    saga.entries,
+                    saga.last_event.unwrap_or("-"),
+                    saga.last_recorded_at_millis.unwrap_or(0),
+                    if saga.quarantined {
+                        "\tQUARANTINED"
+                    } else {
+                        ""
+                    }
+                )?;
+            }
+        }
+        AdminCommand::History { saga_id } => {
+            let history = admin.history(SagaId::new(*saga_id))?;
+            writeln!(out, "saga {} journal:", saga_id)?;
+            for entry in &history.journal {
+                writeln!(
+                    out,
+                    "  #{} at={} {:?}",
+                    entry.sequence, entry.recorded_at_millis, entry.event
+                )?;
+            }
+            writeln!(out, "saga {} incoming:", saga_id)?;
+            for entry in &history.incoming {
+                writeln!(
+                    out,
+                    "  #{} at={} {} step={} attempt={}",
+                    entry.inbox_id,
+                    entry.recorded_at_millis,
+                    entry.event.event_type(),
+                    entry.event.context().step_name,
+                    entry.event.context().attempt
+                )?;
+            }
+        }
+        AdminCommand::Quarantined => {
+            for saga in admin.load_quarantined()? {
+                writeln!(
+                    out,
+                    "{}\tstep={}\tat={}\tretries={}\tcompensation={}\treason={}",
+                    saga.context.saga_id.get(),
+                    saga.step,
+                    saga.quarantined_at_millis,
+                    saga.failed_retries,
+                    saga.compensation_summary(),
+                    saga.reason
+                )?;
+            }
+        }
+        AdminCommand::Resolve {
+            saga_id,
+            operator,
+            note,
+        } => {
+            let resolution = admin.resolve(SagaId::new(*saga_id), operator, note)?;
+            writeln!(out, "saga {} resolved by {}", saga_id, resolution.operator)?;
+        }
+        AdminCommand::Retry { saga_id, operator } => {
+            let resolution = admin.retry(SagaId::new(*saga_id), operator)?;
+            writeln!(
+                out,
+                "saga {} compensation retried by {}",
+                saga_id, resolution.operator
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::InMemoryJournal;
+
+    fn quarantined_journal() -> Arc<InMemoryJournal> {
+        let journal = Arc::new(InMemoryJournal::new());
+        let saga_id = SagaId::new(7);
+        journal
+            .append(
+                saga_id,
+                ParticipantEvent::StepExecutionCompleted {
+                    output: Vec::new(),
+                    compensation_data: vec![1, 2],
+                    completed_at_millis: 10,
+                },
+            )
+            .unwrap();
+        journal
+            .append(
+                saga_id,
+                ParticipantEvent::Quarantined {
+                    reason: "exchange unreachable".into(),
+                    quarantined_at_millis: 20,
+                },
+            )
+            .unwrap();
+        journal
+            .append(
+                SagaId::new(8),
+                ParticipantEvent::StepExecutionStarted {
+                    attempt: 1,
+                    started_at_millis: 30,
+                },
+            )
+            .unwrap();
+        journal
+    }
+
+    fn run(admin: &SagaAdmin<Arc<InMemoryJournal>>, args: &[&str]) -> Result<String, AdminError> {
+        let cli = AdminCli::try_parse_from(
+            ["saga-admin", "--journal", "unused"]
+                .iter()
+                .chain(args.iter()),
+        )
+        .unwrap();
+        let mut out = Vec::new();
+        run_admin_command(admin, &cli.command, &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn lists_sagas_and_quarantines_from_the_journal() {
+        let admin = SagaAdmin::new(quarantined_journal()).with_step("place_order");
+        let listed = run(&admin, &["list"]).unwrap();
+        assert!(listed.contains("7\tentries=2\tlast=quarantined\tat="));
+        assert!(listed.contains("QUARANTINED"));
+        assert_eq!(listed.lines().count(), 2);
+
+        let quarantined = run(&admin, &["quarantined"]).unwrap();
+        assert!(quarantined.starts_with("7\tstep=place_order\tat=20\tretries=0"));
+        assert!(quarantined.contains("compensation=2 bytes"));
+        assert!(run(&admin, &["history", "7"])
+            .unwrap()
+            .contains("Quarantined"));
+
+        run(&admin, &["resolve", "7", "--operator", "ops"]).unwrap();
+        assert!(admin.load_quarantined().unwrap().is_empty());
+        assert!(admin.journal().read(SagaId::new(7)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn retry_runs_the_registered_compensator_and_journals_the_outcome() {
+        let admin = SagaAdmin::new(quarantined_journal()).with_step("place_order");
+        assert!(matches!(
+            run(&admin, &["retry", "7", "--operator", "ops"]),
+            Err(AdminError::NoCompensator(_))
+        ));
+
+        let failing = SagaAdmin::new(quarantined_journal())
+            .with_step("place_order")
+            .with_compensator("place_order", |_, _| {
+                Err(CompensationError::safe_to_retry("still down"))
+            });
+        assert!(failing.retry(SagaId::new(7), "ops").is_err());
+        let saga = &failing.load_quarantined().unwrap()[0];
+        assert_eq!(
+            (saga.failed_retries, saga.reason.as_ref()),
+            (1, "still down")
+        );
+
+        let admin = SagaAdmin::new(Arc::clone(failing.journal()))
+            .with_step("place_order")
+            .with_compensator("place_order", |_, data| {
+                assert_eq!(data, [1, 2]);
+                Ok(())
+            });
+        let out = run(&admin, &["retry", "7", "--operator", "ops"]).unwrap();
+        assert_eq!(out, "saga 7 compensation retried by ops\n");
+        assert!(admin.load_quarantined().unwrap().is_empty());
+    }
+}
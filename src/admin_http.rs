@@ -0,0 +1,376 @@
+//! HTTP admin and introspection endpoints.
+//!
+//! [`SagaAdminHttp`] collects the process-wide views a service already keeps
+//! (a [`SagaActivityTracker`], a [`QuarantineManager`] and participant
+//! journals) and serves them as JSON through an axum [`Router`]. Mount the
+//! router into an existing server or call [`SagaAdminHttp::serve`] on a
+//! dedicated listener.
+//!
+//! | Method | Path | Response |
+//! | --- | --- | --- |
+//! | GET | `/sagas/active` | in-flight sagas |
+//! | GET | `/stats` | started/completed/failed/quarantined/active per saga type |
+//! | GET | `/journals` | registered participant journals |
+//! | GET | `/journals/{participant}/sagas/{saga_id}` | journal entries of one saga |
+//! | GET | `/quarantine` | quarantined steps of all sagas |
+//! | GET | `/quarantine/{saga_id}` | quarantined steps of one saga |
+//! | POST | `/quarantine/{saga_id}/{step}/resolve` | closes a step's quarantine; body `{"operator": "...", "note": "..."}` |
+//!
+//! Endpoints whose source was not registered answer `404`.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde_json::{json, Value};
+
+use crate::{
+    ParticipantJournal, QuarantineError, QuarantineManager, QuarantinedSaga, SagaActivityTracker,
+    SagaId,
+};
+
+type Reply = (StatusCode, Json<Value>);
+
+#[derive(Clone, Default)]
+struct AdminSources {
+    activity: Option<SagaActivityTracker>,
+    quarantine: Option<QuarantineManager>,
+    journals: BTreeMap<Box<str>, Arc<dyn ParticipantJournal>>,
+}
+
+/// Builder for the admin HTTP router.
+#[derive(Clone, Default)]
+pub struct SagaAdminHttp {
+    sources: AdminSources,
+}
+
+#[derive(serde::Deserialize)]
+struct ResolveRequest {
+    operator: String,
+    #[serde(default)]
+    note: String,
+}
+
+impl SagaAdminHttp {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serves `/sagas/active` and `/stats` from `tracker`.
+    pub fn with_activity(mut self, tracker: SagaActivityTracker) -> Self {
+        self.sources.activity = Some(tracker);
+        self
+    }
+
+    /// Serves `/quarantine` from `manager`.
+    pub fn with_quarantine(mut self, manager: QuarantineManager) -> Self {
+        self.sources.quarantine = Some(manager);
+        self
+    }
+
+    /// Serves the journal of `participant` under `/journals/{participant}`.
+    pub fn with_journal<J>(mut self, participant: &str, journal: Arc<J>) -> Self
+    where
+        J: ParticipantJournal,
+    {
+        self.sources.journals.insert(participant.into(), journal);
+        self
+    }
+
+    pub fn router(self) -> Router {
+        Router::new()
+            .route("/sagas/active", get(active_sagas))
+            .route("/stats", get(stats))
+            .route("/journals", get(journals))
+            .route(
+                "/journals/{participant}/sagas/{saga_id}",
+                get(journal_entries),
+            )
+            .route("/quarantine", get(quarantined))
+            .route("/quarantine/{saga_id}", get(quarantined_saga))
+            .route("/quarantine/{saga_id}/{step}/resolve", post(resolve))
+            .with_state(Arc::new(self.sources))
+    }
+
+    /// Serves the router on `listener` until the server fails.
+    pub async fn serve(self, listener: tokio::net::TcpListener) -> std::io::Result<()> {
+        axum::serve(listener, self.router()).await
+    }
+}
+
+impl std::fmt::Debug for SagaAdminHttp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SagaAdminHttp")
+            .field("activity", &self.sources.activity.is_some())
+            .field("quarantine", &self.sources.quarantine.is_some())
+            .field(
+                "journals",
+                &self.sources.journals.keys().collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+fn not_found(message: impl std::fmt::Display) -> Reply {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "error": message.to_string() })),
+    )
+}
+
+fn ok(value: Value) -> Reply {
+    (StatusCode::OK, Json(value))
+}
+
+async fn active_sagas(State(sources): State<Arc<AdminSources>>) -> Reply {
+    let Some(activity) = &sources.activity else {
+        return not_found("activity tracking is not enabled");
+    };
+    let sagas: Vec<Value> = activity
+        .active()
+        .into_iter()
+        .map(|saga| {
+            json!({
+                "saga_id": saga.saga_id.get(),
+                "saga_type": saga.saga_type.as_ref(),
+                "started_at_millis": saga.started_at_millis,
+                "last_event": saga.last_event,
+                "last_event_at_millis": saga.last_event_at_millis,
+            })
+        })
+        .collect();
+    ok(Value::Array(sagas))
+}
+
+async fn stats(State(sources): State<Arc<AdminSources>>) -> Reply {
+    let Some(activity) = &sources.activity else {
+        return not_found("activity tracking is not enabled");
+    };
+    let stats: Vec<Value> = activity
+        .stats()
+        .into_iter()
+        .map(|stats| {
+            json!({
+                "saga_type": stats.saga_type.as_ref(),
+                "started": stats.started,
+                "completed": stats.completed,
+                "failed": stats.failed,
+                "quarantined": stats.quarantined,
+                "active": stats.active,
+            })
+        })
+        .collect();
+    ok(Value::Array(stats))
+}
+
+async fn journals(State(sources): State<Arc<AdminSources>>) -> Reply {
+    let names: Vec<&str> = sources.journals.keys().map(AsRef::as_ref).collect();
+    ok(json!(names))
+}
+
+async fn journal_entries(
+    State(sources): State<Arc<AdminSources>>,
+    Path((participant, saga_id)): Path<(String, u64)>,
+) -> Reply {
+    let Some(journal) = sources.journals.get(participant.as_str()) else {
+        return not_found(format_args!("no journal registered for {participant}"));
+    };
+    match journal.read(SagaId::new(saga_id)) {
+        Ok(entries) => {
+            let entries: Vec<Value> = entries
+                .into_iter()
+                .map(|entry| {
+                    json!({
+                        "sequence": entry.sequence,
+                        "recorded_at_millis": entry.recorded_at_millis,
+                        "event_type": entry.event.event_type(),
+                        "detail": format!("{:?}", entry.event),
+                    })
+                })
+                .collect();
+            ok(Value::Array(entries))
+        }
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": err.to_string() })),
+        ),
+    }
+}
+
+fn quarantined_json(saga: &QuarantinedSaga) -> Value {
+    json!({
+        "saga_id": saga.context.saga_id.get(),
+        "saga_type": saga.context.saga_type.as_ref(),
+        "step": saga.step.as_ref(),
+        "participant_id": saga.participant_id.as_ref(),
+        "reason": saga.reason.as_ref(),
+        "quarantined_at_millis": saga.quarantined_at_millis,
+        "compensation_data": saga.compensation_summary(),
+        "failed_retries": saga.failed_retries,
+    })
+}
+
+async fn quarantined(State(sources): State<Arc<AdminSources>>) -> Reply {
+    let Some(quarantine) = &sources.quarantine else {
+        return not_found("quarantine manager is not registered");
+    };
+    ok(Value::Array(
+        quarantine.list().iter().map(quarantined_json).collect(),
+    ))
+}
+
+async fn quarantined_saga(
+    State(sources): State<Arc<AdminSources>>,
+    Path(saga_id): Path<u64>,
+) -> Reply {
+    let Some(quarantine) = &sources.quarantine else {
+        return not_found("quarantine manager is not registered");
+    };
+    let steps = quarantine.inspect_saga(SagaId::new(saga_id));
+    if steps.is_empty() {
+        return not_found(QuarantineError::NotQuarantined(saga_id));
+    }
+    ok(Value::Array(steps.iter().map(quarantined_json).collect()))
+}
+
+async fn resolve(
+    State(sources): State<Arc<AdminSources>>,
+    Path((saga_id, step)): Path<(u64, String)>,
+    Json(request): Json<ResolveRequest>,
+) -> Reply {
+    let Some(quarantine) = &sources.quarantine else {
+        return not_found("quarantine manager is not registered");
+    };
+    match quarantine.resolve(
+        SagaId::new(saga_id),
+        &step,
+        &request.operator,
+        &request.note,
+    ) {
+        Ok(resolution) => ok(json!({
+            "saga": quarantined_json(&resolution.saga),
+            "operator": resolution.operator.as_ref(),
+            "note": resolution.note.as_ref(),
+        })),
+        Err(err) => not_found(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::{
+        saga_started, DeterministicContextBuilder, InMemoryJournal, ParticipantEvent,
+        SagaChoreographyEvent,
+    };
+
+    async fn call(router: &Router, method: &str, uri: &str, body: Option<Value>) -> (u16, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status().as_u16();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn serves_activity_journal_and_quarantine_resolution() {
+        let tracker = SagaActivityTracker::new();
+        let running = DeterministicContextBuilder::default().build();
+        let quarantined = DeterministicContextBuilder::default()
+            .with_saga_id(2)
+            .build();
+        tracker.observe(&saga_started(running.clone(), Vec::new()));
+        tracker.observe(&saga_started(quarantined.clone(), Vec::new()));
+        let quarantine_event = SagaChoreographyEvent::SagaQuarantined {
+            context: quarantined.clone(),
+            reason: "exchange unreachable".into(),
+            step: "place_order".into(),
+            participant_id: "orders".into(),
+        };
+        tracker.observe(&quarantine_event);
+        let manager = QuarantineManager::new();
+        manager.observe(&quarantine_event);
+        let journal = Arc::new(InMemoryJournal::new());
+        journal
+            .append(
+                running.saga_id,
+                ParticipantEvent::StepExecutionStarted {
+                    attempt: 1,
+                    started_at_millis: 5,
+                },
+            )
+            .unwrap();
+
+        let router = SagaAdminHttp::new()
+            .with_activity(tracker)
+            .with_quarantine(manager.clone())
+            .with_journal("orders", journal)
+            .router();
+
+        let (status, active) = call(&router, "GET", "/sagas/active", None).await;
+        assert_eq!(status, 200);
+        assert_eq!(active.as_array().unwrap().len(), 1);
+        assert_eq!(active[0]["saga_id"], running.saga_id.get());
+
+        let (_, stats) = call(&router, "GET", "/stats", None).await;
+        assert_eq!(stats[0]["started"], 2);
+        assert_eq!(stats[0]["quarantined"], 1);
+        assert_eq!(stats[0]["active"], 1);
+
+        let uri = format!("/journals/orders/sagas/{}", running.saga_id.get());
+        let (_, entries) = call(&router, "GET", &uri, None).await;
+        assert_eq!(entries[0]["event_type"], "step_execution_started");
+        let (status, _) = call(&router, "GET", "/journals/risk/sagas/1", None).await;
+        assert_eq!(status, 404);
+
+        let (_, listed) = call(&router, "GET", "/quarantine", None).await;
+        assert_eq!(listed[0]["step"], "place_order");
+        let (_, saga) = call(&router, "GET", "/quarantine/2", None).await;
+        assert_eq!(saga[0]["step"], "place_order");
+        let body = json!({ "operator": "ops", "note": "cancelled on venue" });
+        let (status, resolved) = call(
+            &router,
+            "POST",
+            "/quarantine/2/place_order/resolve",
+            Some(body.clone()),
+        )
+        .await;
+        assert_eq!(status, 200);
+        assert_eq!(resolved["operator"], "ops");
+        assert!(manager.is_empty());
+        let (status, _) = call(
+            &router,
+            "POST",
+            "/quarantine/2/place_order/resolve",
+            Some(body),
+        )
+        .await;
+        assert_eq!(status, 404);
+    }
+
+    #[tokio::test]
+    async fn unregistered_sources_answer_not_found() {
+        let router = SagaAdminHttp::new().router();
+        for uri in ["/sagas/active", "/stats", "/quarantine", "/quarantine/1"] {
+            let (status, body) = call(&router, "GET", uri, None).await;
+            assert_eq!(status, 404, "{uri}");
+            assert!(body["error"].is_string());
+        }
+        let (_, journals) = call(&router, "GET", "/journals", None).await;
+        assert_eq!(journals, json!([]));
+    }
+}
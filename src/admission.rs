@@ -0,0 +1,389 @@
+//! Admission control for saga starts.
+//!
+//! A [`SagaConcurrencyLimit`] caps how many sagas of one type may be in flight
+//! (started and not yet terminal) at a time, optionally per admission key
+//! derived from the start payload, such as the traded instrument. Starts made
+//! through [`SagaChoreographyBus::start_saga`](crate::SagaChoreographyBus::start_saga)
+//! beyond the cap are rejected or queued until an in-flight saga terminates.
+//!
+//! [`SagaConcurrencyLimit::serialized`] is the common special case: sagas
+//! sharing a [`SerializationKey`] run one at a time, in start order, while
+//! sagas with different keys run concurrently.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use crate::{SagaChoreographyEvent, SagaContext, SagaId};
+
+type AdmissionKeyFn = dyn Fn(&SagaContext, &[u8]) -> Option<Box<str>> + Send + Sync;
+
+/// Key under which sagas are serialized, e.g. the instrument an order trades.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SerializationKey(pub Box<str>);
+
+impl SerializationKey {
+    pub fn new(key: impl Into<Box<str>>) -> Self {
+        Self(key.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for SerializationKey {
+    fn from(key: &str) -> Self {
+        Self(key.into())
+    }
+}
+
+impl From<String> for SerializationKey {
+    fn from(key: String) -> Self {
+        Self(key.into())
+    }
+}
+
+impl std::fmt::Display for SerializationKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// What happens to a start that exceeds the limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SagaAdmissionOverflow {
+    /// Reject the start with [`SagaBusPublishError::AdmissionRejected`](crate::SagaBusPublishError::AdmissionRejected).
+    Reject,
+    /// Hold up to `max_queued` starts per admission key and publish them in
+    /// order as in-flight sagas terminate. Starts beyond that are rejected.
+    Queue { max_queued: usize },
+}
+
+/// Result of an admitted [`SagaChoreographyBus::start_saga`](crate::SagaChoreographyBus::start_saga).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SagaAdmission {
+    /// The `SagaStarted` was published.
+    Started { attempted: u32, delivered: u32 },
+    /// The start is held; `position` is its zero-based place in the queue.
+    Queued { position: usize },
+}
+
+/// Concurrency cap for one saga type.
+#[derive(Clone)]
+pub struct SagaConcurrencyLimit {
+    pub saga_type: Box<str>,
+    pub max_in_flight: usize,
+    pub overflow: SagaAdmissionOverflow,
+    admission_key: Option<Arc<AdmissionKeyFn>>,
+}
+
+impl SagaConcurrencyLimit {
+    /// Caps `saga_type` at `max_in_flight` non-terminal sagas, rejecting
+    /// starts beyond that.
+    pub fn new(saga_type: impl Into<Box<str>>, max_in_flight: usize) -> Self {
+        Self {
+            saga_type: saga_type.into(),
+            max_in_flight,
+            overflow: SagaAdmissionOverflow::Reject,
+            admission_key: None,
+        }
+    }
+
+    /// Runs sagas of `saga_type` that share a key one at a time. Starts for
+    /// a busy key wait in an unbounded queue and are published in order as
+    /// the running saga terminates; starts for which `key` returns `None`
+    /// are not serialized.
+    pub fn serialized<F>(saga_type: impl Into<Box<str>>, key: F) -> Self
+    where
+        F: Fn(&SagaContext, &[u8]) -> Option<SerializationKey> + Send + Sync + 'static,
+    {
+        Self::new(saga_type, 1)
+            .with_overflow(SagaAdmissionOverflow::Queue {
+                max_queued: usize::MAX,
+            })
+            .with_admission_key(move |context, payload| key(context, payload).map(|key| key.0))
+    }
+
+    pub fn with_overflow(mut self, overflow: SagaAdmissionOverflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Applies the cap per key instead of per saga type. Starts for which
+    /// `admission_key` returns `None` are not limited.
+    pub fn with_admission_key<F>(mut self, admission_key: F) -> Self
+    where
+        F: Fn(&SagaContext, &[u8]) -> Option<Box<str>> + Send + Sync + 'static,
+    {
+        self.admission_key = Some(Arc::new(admission_key));
+        self
+    }
+
+    fn key_for(&self, context: &SagaContext, payload: &[u8]) -> Option<Box<str>> {
+        match &self.admission_key {
+            Some(admission_key) => admission_key(context, payload),
+            None => Some(Box::from("")),
+        }
+    }
+
+    pub(crate) fn admission_state(&self) -> SagaAdmissionState {
+        SagaAdmissionState {
+            limit: self.clone(),
+            in_flight: HashMap::new(),
+            counts: HashMap::new(),
+            queued: HashMap::new(),
+        }
+    }
+}
+
+impl std::fmt::Debug for SagaConcurrencyLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SagaConcurrencyLimit")
+            .field("saga_type", &self.saga_type)
+            .field("max_in_flight", &self.max_in_flight)
+            .field("overflow", &self.overflow)
+            .field("keyed", &self.admission_key.is_some())
+            .finish()
+    }
+}
+
+pub(crate) enum AdmissionDecision {
+    Admit,
+    Queued {
+        position: usize,
+    },
+    Rejected {
+        admission_key: Box<str>,
+        in_flight: usize,
+    },
+}
+
+/// Per-bus bookkeeping of an attached [`SagaConcurrencyLimit`].
+pub(crate) struct SagaAdmissionState {
+    limit: SagaConcurrencyLimit,
+    in_flight: HashMap<SagaId, Box<str>>,
+    counts: HashMap<Box<str>, usize>,
+    queued: HashMap<Box<str>, VecDeque<SagaChoreographyEvent>>,
+}
+
+impl SagaAdmissionState {
+    pub(crate) fn limit(&self) -> &SagaConcurrencyLimit {
+        &self.limit
+    }
+
+    /// Decides on a start. An admitted start is counted as in flight right
+    /// away so concurrent callers cannot both take the last slot.
+    pub(crate) fn admit(&mut self, context: &SagaContext, payload: &[u8]) -> AdmissionDecision {
+        if self.in_flight.contains_key(&context.saga_id) {
+            return AdmissionDecision::Admit;
+        }
+        let Some(key) = self.limit.key_for(context, payload) else {
+            return AdmissionDecision::Admit;
+        };
+        let in_flight = self.counts.get(&key).copied().unwrap_or(0);
+        if in_flight < self.limit.max_in_flight {
+            self.reserve(context.saga_id, key);
+            return AdmissionDecision::Admit;
+        }
+        match self.limit.overflow {
+            SagaAdmissionOverflow::Queue { max_queued } => {
+                let queue = self.queued.entry(key.clone()).or_default();
+                if queue.len() < max_queued {
+                    queue.push_back(SagaChoreographyEvent::SagaStarted {
+                        context: context.clone(),
+                        payload: payload.to_vec(),
+                    });
+                    return AdmissionDecision::Queued {
+                        position: queue.len() - 1,
+                    };
+                }
+                AdmissionDecision::Rejected {
+                    admission_key: key,
+                    in_flight,
+                }
+            }
+            SagaAdmissionOverflow::Reject => AdmissionDecision::Rejected {
+                admission_key: key,
+                in_flight,
+            },
+        }
+    }
+
+    /// Feeds one event of the limited saga type and returns the queued starts
+    /// that were admitted because a slot freed up.
+    pub(crate) fn observe(&mut self, event: &SagaChoreographyEvent) -> Vec<SagaChoreographyEvent> {
+        let context = event.context();
+        match event {
+            SagaChoreographyEvent::SagaStarted { payload, .. } => {
+                // Starts published directly on the bus bypass admission but
+                // still occupy a slot.
+                if !self.in_flight.contains_key(&context.saga_id) {
+                    if let Some(key) = self.limit.key_for(context, payload) {
+                        self.reserve(context.saga_id, key);
+                    }
+                }
+                Vec::new()
+            }
+            SagaChoreographyEvent::SagaCompleted { .. }
+            | SagaChoreographyEvent::SagaFailed { .. }
+            | SagaChoreographyEvent::SagaQuarantined { .. } => {
+                self.drop_queued(context.saga_id);
+                let Some(key) = self.in_flight.remove(&context.saga_id) else {
+                    return Vec::new();
+                };
+                if let Some(count) = self.counts.get_mut(&key) {
+                    *count = count.saturating_sub(1);
+                    if *count == 0 {
+                        self.counts.remove(&key);
+                    }
+                }
+                self.drain_queue(key)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    pub(crate) fn in_flight(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    pub(crate) fn queued(&self) -> usize {
+        self.queued.values().map(VecDeque::len).sum()
+    }
+
+    fn reserve(&mut self, saga_id: SagaId, key: Box<str>) {
+        *self.counts.entry(key.clone()).or_insert(0) += 1;
+        self.in_flight.insert(saga_id, key);
+    }
+
+    fn drop_queued(&mut self, saga_id: SagaId) {
+        self.queued.retain(|_, queue| {
+            queue.retain(|event| event.context().saga_id != saga_id);
+            !queue.is_empty()
+        });
+    }
+
+    fn drain_queue(&mut self, key: Box<str>) -> Vec<SagaChoreographyEvent> {
+        let mut admitted = Vec::new();
+        while self.counts.get(&key).copied().unwrap_or(0) < self.limit.max_in_flight {
+            let Some(queue) = self.queued.get_mut(&key) else {
+                break;
+            };
+            let Some(started) = queue.pop_front() else {
+                self.queued.remove(&key);
+                break;
+            };
+            if queue.is_empty() {
+                self.queued.remove(&key);
+            }
+            self.reserve(started.context().saga_id, key.clone());
+            admitted.push(started);
+        }
+        admitted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DeterministicContextBuilder;
+
+    fn ctx(saga_id: u64) -> SagaContext {
+        DeterministicContextBuilder::default()
+            .with_saga_id(saga_id)
+            .with_saga_type("order")
+            .build()
+    }
+
+    fn failed(saga_id: u64) -> SagaChoreographyEvent {
+        SagaChoreographyEvent::SagaFailed {
+            context: ctx(saga_id),
+            reason: "rejected".into(),
+            failure: None,
+        }
+    }
+
+    #[test]
+    fn keyed_limit_queues_per_key_and_admits_on_terminal() {
+        let limit = SagaConcurrencyLimit::new("order", 1)
+            .with_overflow(SagaAdmissionOverflow::Queue { max_queued: 1 })
+            .with_admission_key(|_, payload| std::str::from_utf8(payload).ok().map(Box::from));
+        let mut state = limit.admission_state();
+
+        assert!(matches!(
+            state.admit(&ctx(1), b"BTC"),
+            AdmissionDecision::Admit
+        ));
+        assert!(matches!(
+            state.admit(&ctx(2), b"ETH"),
+            AdmissionDecision::Admit
+        ));
+        assert!(matches!(
+            state.admit(&ctx(3), b"BTC"),
+            AdmissionDecision::Queued { position: 0 }
+        ));
+        let AdmissionDecision::Rejected {
+            admission_key,
+            in_flight,
+        } = state.admit(&ctx(4), b"BTC")
+        else {
+            panic!("full queue should reject");
+        };
+        assert_eq!(admission_key.as_ref(), "BTC");
+        assert_eq!(in_flight, 1);
+
+        assert!(state.observe(&failed(2)).is_empty());
+        let admitted = state.observe(&failed(1));
+        assert_eq!(admitted.len(), 1);
+        assert_eq!(admitted[0].context().saga_id, SagaId::new(3));
+        assert_eq!(state.in_flight(), 1);
+        assert_eq!(state.queued(), 0);
+    }
+
+    #[test]
+    fn serialized_limit_runs_one_saga_per_key_in_start_order() {
+        let limit = SagaConcurrencyLimit::serialized("order", |_, payload| {
+            std::str::from_utf8(payload)
+                .ok()
+                .map(SerializationKey::from)
+        });
+        let mut state = limit.admission_state();
+
+        assert!(matches!(
+            state.admit(&ctx(1), b"BTC"),
+            AdmissionDecision::Admit
+        ));
+        assert!(matches!(
+            state.admit(&ctx(2), b"ETH"),
+            AdmissionDecision::Admit
+        ));
+        for (saga_id, position) in [(3, 0), (4, 1), (5, 2)] {
+            assert!(matches!(
+                state.admit(&ctx(saga_id), b"BTC"),
+                AdmissionDecision::Queued { position: queued } if queued == position
+            ));
+        }
+
+        let admitted = state.observe(&failed(1));
+        assert_eq!(admitted.len(), 1);
+        assert_eq!(admitted[0].context().saga_id, SagaId::new(3));
+        assert_eq!(
+            state.observe(&failed(3))[0].context().saga_id,
+            SagaId::new(4)
+        );
+        assert_eq!((state.in_flight(), state.queued()), (2, 1));
+    }
+
+    #[test]
+    fn direct_starts_occupy_slots() {
+        let mut state = SagaConcurrencyLimit::new("order", 1).admission_state();
+        state.observe(&SagaChoreographyEvent::SagaStarted {
+            context: ctx(1),
+            payload: Vec::new(),
+        });
+        assert!(matches!(
+            state.admit(&ctx(2), &[]),
+            AdmissionDecision::Rejected { in_flight: 1, .. }
+        ));
+    }
+}
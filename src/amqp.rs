@@ -0,0 +1,444 @@
+//! AMQP (RabbitMQ) bridge for the saga choreography bus.
+//!
+//! The in-process [`SagaChoreographyBus`] only reaches participants living in
+//! the same process. `AmqpSagaBus` extends it across processes:
+//!
+//! - every saga type (the bus topic) maps to one durable topic exchange,
+//! - every participant step maps to one durable queue bound to that exchange,
+//! - events that cannot be decoded, or keep failing local delivery after a
+//!   redelivery, are rejected without requeue and dead-lettered into a
+//!   configurable DLQ exchange. Undecodable payloads also go to the
+//!   [`DeadLetterStore`] attached with [`AmqpSagaBus::with_dead_letter_store`].
+//!
+//! Wire encoding is left to the caller through [`SagaEventCodec`] so the crate
+//! does not pick a serialization format for [`SagaChoreographyEvent`].
+
+use futures_util::StreamExt;
+use lapin::options::{
+    BasicAckOptions, BasicConsumeOptions, BasicNackOptions, BasicPublishOptions, BasicQosOptions,
+    ConfirmSelectOptions, ExchangeDeclareOptions, QueueBindOptions, QueueDeclareOptions,
+};
+use lapin::publisher_confirm::Confirmation;
+use lapin::types::{AMQPValue, FieldTable};
+use lapin::{BasicProperties, Channel, Connection, ConnectionProperties, ExchangeKind};
+
+use crate::{dead_letter_raw_payload, DeadLetterStore, SagaChoreographyBus, SagaChoreographyEvent};
+
+/// AMQP delivery mode for messages that must survive a broker restart.
+const PERSISTENT_DELIVERY_MODE: u8 = 2;
+
+/// Encodes and decodes saga events for the AMQP wire.
+pub trait SagaEventCodec: Send + Sync + 'static {
+    fn encode(&self, event: &SagaChoreographyEvent) -> Result<Vec<u8>, String>;
+    fn decode(&self, payload: &[u8]) -> Result<SagaChoreographyEvent, String>;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AmqpSagaBusError {
+    #[error("amqp broker error: {0}")]
+    Broker(#[from] lapin::Error),
+    #[error("saga event codec error: {0}")]
+    Codec(Box<str>),
+    #[error("broker nacked the published saga event")]
+    Nacked,
+    #[error("publisher confirms are not enabled on the channel")]
+    NotConfirmed,
+}
+
+/// Naming and broker settings for [`AmqpSagaBus`].
+#[derive(Clone, Debug)]
+pub struct AmqpSagaBusConfig {
+    pub uri: Box<str>,
+    /// Prefix for per-saga-type topic exchanges (`{prefix}.{saga_type}`).
+    pub exchange_prefix: Box<str>,
+    /// Prefix for per-step queues (`{prefix}.{saga_type}.{step_name}`).
+    pub queue_prefix: Box<str>,
+    /// Exchange receiving rejected (poison) deliveries.
+    pub dead_letter_exchange: Box<str>,
+    /// Durable queue bound to the dead-letter exchange.
+    pub dead_letter_queue: Box<str>,
+    pub prefetch: u16,
+}
+
+impl Default for AmqpSagaBusConfig {
+    fn default() -> Self {
+        Self {
+            uri: "amqp://127.0.0.1:5672/%2f".into(),
+            exchange_prefix: "saga".into(),
+            queue_prefix: "saga.step".into(),
+            dead_letter_exchange: "saga.dlx".into(),
+            dead_letter_queue: "saga.dlq".into(),
+            prefetch: 32,
+        }
+    }
+}
+
+impl AmqpSagaBusConfig {
+    pub fn new(uri: impl Into<Box<str>>) -> Self {
+        Self {
+            uri: uri.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_dead_letter_exchange(mut self, exchange: impl Into<Box<str>>) -> Self {
+        self.dead_letter_exchange = exchange.into();
+        self
+    }
+
+    pub fn with_dead_letter_queue(mut self, queue: impl Into<Box<str>>) -> Self {
+        self.dead_letter_queue = queue.into();
+        self
+    }
+
+    pub fn with_prefetch(mut self, prefetch: u16) -> Self {
+        self.prefetch = prefetch;
+        self
+    }
+
+    pub fn exchange_for_saga_type(&self, saga_type: &str) -> String {
+        format!("{}.{}", self.exchange_prefix, saga_type)
+    }
+
+    pub fn queue_for_step(&self, saga_type: &str, step_name: &str) -> String {
+        format!("{}.{}.{}", self.queue_prefix, saga_type, step_name)
+    }
+
+    /// Routing key is `{emitting_step}.{event_type}` so consumers can bind
+    /// narrower patterns than the default `#` if they want to.
+    pub fn routing_key_for_event(event: &SagaChoreographyEvent) -> String {
+        format!("{}.{}", event.context().step_name, event.event_type())
+    }
+}
+
+pub struct AmqpSagaBus<C: SagaEventCodec> {
+    config: AmqpSagaBusConfig,
+    codec: std::sync::Arc<C>,
+    connection: Connection,
+    channel: Channel,
+    dead_letters: Option<std::sync::Arc<dyn DeadLetterStore>>,
+}
+
+impl<C: SagaEventCodec> AmqpSagaBus<C> {
+    /// Connects to the broker, puts the publishing channel in confirm mode
+    /// and declares the dead-letter topology.
+    pub async fn connect(config: AmqpSagaBusConfig, codec: C) -> Result<Self, AmqpSagaBusError> {
+        let connection =
+            Connection::connect(config.uri.as_ref(), ConnectionProperties::default()).await?;
+        let channel = connection.create_channel().await?;
+        channel
+            .confirm_select(ConfirmSelectOptions::default())
+            .await?;
+        channel
+            .basic_qos(config.prefetch, BasicQosOptions::default())
+            .await?;
+        channel
+            .exchange_declare(
+                config.dead_letter_exchange.as_ref(),
+                ExchangeKind::Fanout,
+                durable_exchange(),
+                FieldTable::default(),
+            )
+            .await?;
+        channel
+            .queue_declare(
+                config.dead_letter_queue.as_ref(),
+                durable_queue(),
+                FieldTable::default(),
+            )
+            .await?;
+        channel
+            .queue_bind(
+                config.dead_letter_queue.as_ref(),
+                config.dead_letter_exchange.as_ref(),
+                "",
+                QueueBindOptions::default(),
+                FieldTable::default(),
+            )
+            .await?;
+        Ok(Self {
+            config,
+            codec: std::sync::Arc::new(codec),
+            connection,
+            channel,
+            dead_letters: None,
+        })
+    }
+
+    /// Keeps the raw payload of every delivery the codec cannot decode in
+    /// `store`, next to the broker-side DLQ.
+    pub fn with_dead_letter_store(mut self, store: std::sync::Arc<dyn DeadLetterStore>) -> Self {
+        self.dead_letters = Some(store);
+        self
+    }
+
+    pub fn config(&self) -> &AmqpSagaBusConfig {
+        &self.config
+    }
+
+    /// Declares the durable topic exchange backing `saga_type`.
+    pub async fn declare_saga_type(&self, saga_type: &str) -> Result<String, AmqpSagaBusError> {
+        let exchange = self.config.exchange_for_saga_type(saga_type);
+        self.channel
+            .exchange_declare(
+                exchange.as_str(),
+                ExchangeKind::Topic,
+                durable_exchange(),
+                FieldTable::default(),
+            )
+            .await?;
+        Ok(exchange)
+    }
+
+    /// Declares the durable queue for one participant step and binds it to
+    /// the saga type exchange. Rejected deliveries go to the DLQ exchange.
+    pub async fn declare_step_queue(
+        &self,
+        saga_type: &str,
+        step_name: &str,
+    ) -> Result<String, AmqpSagaBusError> {
+        let exchange = self.declare_saga_type(saga_type).await?;
+        let queue = self.config.queue_for_step(saga_type, step_name);
+        let mut args = FieldTable::default();
+        args.insert(
+            "x-dead-letter-exchange".into(),
+            AMQPValue::LongString(self.config.dead_letter_exchange.as_ref().into()),
+        );
+        self.channel
+            .queue_declare(queue.as_str(), durable_queue(), args)
+            .await?;
+        self.channel
+            .queue_bind(
+                queue.as_str(),
+                exchange.as_str(),
+                "#",
+                QueueBindOptions::default(),
+                FieldTable::default(),
+            )
+            .await?;
+        Ok(queue)
+    }
+
+    /// Publishes `event` to its saga type exchange and waits for the broker
+    /// confirmation; a nack fails with [`AmqpSagaBusError::Nacked`].
+    pub async fn publish(&self, event: &SagaChoreographyEvent) -> Result<(), AmqpSagaBusError> {
+        let payload = self
+            .codec
+            .encode(event)
+            .map_err(|err| AmqpSagaBusError::Codec(err.into()))?;
+        let exchange = self
+            .config
+            .exchange_for_saga_type(event.context().saga_type.as_ref());
+        let routing_key = AmqpSagaBusConfig::routing_key_for_event(event);
+        let confirmation = self
+            .channel
+            .basic_publish(
+                exchange.as_str(),
+                routing_key.as_str(),
+                BasicPublishOptions::default(),
+                &payload,
+                BasicProperties::default().with_delivery_mode(PERSISTENT_DELIVERY_MODE),
+            )
+            .await?
+            .await?;
+        confirmed(confirmation)
+    }
+
+    /// Consumes the step queue and republishes every decoded event onto the
+    /// local bus. Poison deliveries are dead-lettered instead of requeued.
+    pub async fn bridge_step_to_local(
+        &self,
+        saga_type: &str,
+        step_name: &str,
+        local: SagaChoreographyBus,
+    ) -> Result<tokio::task::JoinHandle<()>, AmqpSagaBusError> {
+        let queue = self.declare_step_queue(saga_type, step_name).await?;
+        let channel = self.connection.create_channel().await?;
+        channel
+            .basic_qos(self.config.prefetch, BasicQosOptions::default())
+            .await?;
+        let consumer_tag = format!("{queue}.consumer");
+        let mut consumer = channel
+            .basic_consume(
+                queue.as_str(),
+                consumer_tag.as_str(),
+                BasicConsumeOptions::default(),
+                FieldTable::default(),
+            )
+            .await?;
+        let codec = self.codec.clone();
+        let dead_letters = self.dead_letters.clone();
+        Ok(tokio::spawn(async move {
+            // Keep the consuming channel alive for as long as the task runs.
+            let _channel = channel;
+            while let Some(delivery) = consumer.next().await {
+                let delivery = match delivery {
+                    Ok(delivery) => delivery,
+                    Err(err) => {
+                        tracing::error!(
+                            target: "core::saga",
+                            event = "saga_amqp_consume_failed",
+                            queue = %queue,
+                            error = %err
+                        );
+                        break;
+                    }
+                };
+                let Some(event) =
+                    decode_delivery(codec.as_ref(), dead_letters.as_deref(), &delivery.data)
+                else {
+                    tracing::warn!(
+                        target: "core::saga",
+                        event = "saga_amqp_poison_event_dead_lettered",
+                        queue = %queue
+                    );
+                    let _ = delivery.nack(dead_letter_nack()).await;
+                    continue;
+                };
+                match local.publish_strict(event) {
+                    Ok(_) => {
+                        let _ = delivery.ack(BasicAckOptions::default()).await;
+                    }
+                    Err(err) if delivery.redelivered => {
+                        tracing::warn!(
+                            target: "core::saga",
+                            event = "saga_amqp_redelivery_failed_dead_lettered",
+                            queue = %queue,
+                            error = ?err
+                        );
+                        let _ = delivery.nack(dead_letter_nack()).await;
+                    }
+                    Err(_) => {
+                        let _ = delivery
+                            .nack(BasicNackOptions {
+                                requeue: true,
+                                ..BasicNackOptions::default()
+                            })
+                            .await;
+                    }
+                }
+            }
+        }))
+    }
+}
+
+/// Decodes a delivery, routing an undecodable payload to `dead_letters`.
+fn decode_delivery<C: SagaEventCodec>(
+    codec: &C,
+    dead_letters: Option<&dyn DeadLetterStore>,
+    payload: &[u8],
+) -> Option<SagaChoreographyEvent> {
+    match codec.decode(payload) {
+        Ok(event) => Some(event),
+        Err(err) => {
+            if let Some(store) = dead_letters {
+                dead_letter_raw_payload(store, &err, payload.to_vec());
+            }
+            None
+        }
+    }
+}
+
+/// Maps the broker's answer to a publish on a confirm-mode channel.
+fn confirmed(confirmation: Confirmation) -> Result<(), AmqpSagaBusError> {
+    match confirmation {
+        Confirmation::Ack(_) => Ok(()),
+        Confirmation::Nack(_) => Err(AmqpSagaBusError::Nacked),
+        Confirmation::NotRequested => Err(AmqpSagaBusError::NotConfirmed),
+    }
+}
+
+fn durable_exchange() -> ExchangeDeclareOptions {
+    ExchangeDeclareOptions {
+        durable: true,
+        ..ExchangeDeclareOptions::default()
+    }
+}
+
+fn durable_queue() -> QueueDeclareOptions {
+    QueueDeclareOptions {
+        durable: true,
+        ..QueueDeclareOptions::default()
+    }
+}
+
+fn dead_letter_nack() -> BasicNackOptions {
+    BasicNackOptions {
+        requeue: false,
+        ..BasicNackOptions::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DeterministicContextBuilder;
+
+    #[test]
+    fn topology_names_follow_saga_type_and_step() {
+        let config = AmqpSagaBusConfig::new("amqp://broker/%2f")
+            .with_dead_letter_exchange("orders.dlx")
+            .with_dead_letter_queue("orders.dlq");
+        assert_eq!(config.exchange_for_saga_type("order"), "saga.order");
+        assert_eq!(
+            config.queue_for_step("order", "reserve_inventory"),
+            "saga.step.order.reserve_inventory"
+        );
+        assert_eq!(config.dead_letter_exchange.as_ref(), "orders.dlx");
+        assert_eq!(config.dead_letter_queue.as_ref(), "orders.dlq");
+    }
+
+    #[test]
+    fn routing_key_combines_step_and_event_type() {
+        let context = DeterministicContextBuilder::default()
+            .with_saga_id(7)
+            .with_saga_type("order")
+            .with_step_name("charge_payment")
+            .build();
+        let event = SagaChoreographyEvent::StepStarted { context };
+        assert_eq!(
+            AmqpSagaBusConfig::routing_key_for_event(&event),
+            "charge_payment.step_started"
+        );
+    }
+
+    #[test]
+    fn publish_fails_unless_the_broker_acks() {
+        assert!(confirmed(Confirmation::Ack(None)).is_ok());
+        assert!(matches!(
+            confirmed(Confirmation::Nack(None)),
+            Err(AmqpSagaBusError::Nacked)
+        ));
+        assert!(matches!(
+            confirmed(Confirmation::NotRequested),
+            Err(AmqpSagaBusError::NotConfirmed)
+        ));
+    }
+
+    struct RejectingCodec;
+
+    impl SagaEventCodec for RejectingCodec {
+        fn encode(&self, _event: &SagaChoreographyEvent) -> Result<Vec<u8>, String> {
+            Err("not used".to_string())
+        }
+
+        fn decode(&self, _payload: &[u8]) -> Result<SagaChoreographyEvent, String> {
+            Err("unknown frame version".to_string())
+        }
+    }
+
+    #[test]
+    fn undecodable_deliveries_are_kept_in_the_dead_letter_store() {
+        let store = crate::InMemoryDeadLetterStore::new();
+        assert!(decode_delivery(&RejectingCodec, Some(&store), &[0xde, 0xad]).is_none());
+
+        let entries = store.list().expect("dead letters should list");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].reason, crate::DeadLetterReason::Deserialization);
+        assert_eq!(entries[0].detail.as_ref(), "unknown frame version");
+        assert!(matches!(
+            &entries[0].payload,
+            crate::DeadLetterPayload::Raw(bytes) if bytes == &[0xde, 0xad]
+        ));
+    }
+}
@@ -0,0 +1,445 @@
+//! A step that waits for a human decision.
+//!
+//! [`ApprovalStep`] parks its step once its dependencies are satisfied,
+//! instead of executing anything, until an operator calls
+//! [`ApprovalStep::approve`] or [`ApprovalStep::reject`] for the saga, e.g.
+//! to sign off on a large order:
+//!
+//! ```ignore
+//! let mut approval = ApprovalStep::new("sign_off", &["order_lifecycle"], journal)
+//!     .after(DependencySpec::after("risk_check"));
+//! // Feed it every saga event from the actor's bus subscription.
+//! approval.handle(&event);
+//! // Later, from the operator's request handler:
+//! bus.publish_strict(approval.approve(saga_id)?)?;
+//! ```
+//!
+//! Approving emits the step's `StepCompleted`, passing its input through as
+//! output. Rejecting emits `CompensationRequested` for the steps completed so
+//! far, which the terminal resolver follows to fail the saga.
+//!
+//! Parking and both decisions are journaled: the parked step as
+//! `StepTriggered` next to the triggering event in the journal inbox, the
+//! decision as `StepExecutionCompleted` or `StepExecutionFailed`. After a
+//! restart [`ApprovalStep::recover`] parks the undecided steps again, and a
+//! step is decided at most once.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::{
+    DedupeKey, DependencySpec, JournalError, ParticipantEvent, ParticipantJournal,
+    SagaChoreographyBus, SagaChoreographyEvent, SagaContext, SagaId, StepName,
+};
+
+/// Error of [`ApprovalStep::approve`] and [`ApprovalStep::reject`].
+#[derive(Debug, thiserror::Error)]
+pub enum ApprovalError {
+    #[error("saga {0} has no step awaiting approval")]
+    NotPending(SagaId),
+    #[error(transparent)]
+    Journal(#[from] JournalError),
+}
+
+/// A step awaiting approval.
+#[derive(Clone, Debug)]
+pub struct PendingApproval {
+    /// Context of the parked step.
+    pub context: SagaContext,
+    pub input: Vec<u8>,
+    pub parked_at_millis: u64,
+}
+
+/// Step that completes or compensates on an operator's decision.
+pub struct ApprovalStep<J: ParticipantJournal> {
+    journal: J,
+    step_name: &'static str,
+    saga_types: &'static [&'static str],
+    depends_on: DependencySpec,
+    pending: BTreeMap<SagaId, PendingApproval>,
+    dependency_completions: HashMap<SagaId, HashSet<StepName>>,
+    compensable_steps: HashMap<SagaId, Vec<StepName>>,
+    bus: Option<SagaChoreographyBus>,
+}
+
+impl<J: ParticipantJournal> ApprovalStep<J> {
+    /// Parks on saga start until [`after`](Self::after) says otherwise.
+    pub fn new(step_name: &'static str, saga_types: &'static [&'static str], journal: J) -> Self {
+        Self {
+            journal,
+            step_name,
+            saga_types,
+            depends_on: DependencySpec::OnSagaStart,
+            pending: BTreeMap::new(),
+            dependency_completions: HashMap::new(),
+            compensable_steps: HashMap::new(),
+            bus: None,
+        }
+    }
+
+    /// Parks the step once `depends_on` is satisfied.
+    pub fn after(mut self, depends_on: DependencySpec) -> Self {
+        self.depends_on = depends_on;
+        self
+    }
+
+    /// Lets the compensation strategy registered on `bus` choose the steps
+    /// a rejection compensates, instead of every compensable step seen.
+    pub fn with_bus(mut self, bus: SagaChoreographyBus) -> Self {
+        self.bus = Some(bus);
+        self
+    }
+
+    /// Feeds one saga event. Returns whether it parked the step.
+    pub fn handle(&mut self, event: &SagaChoreographyEvent) -> bool {
+        let context = event.context();
+        if !self.saga_types.contains(&context.saga_type.as_ref()) {
+            return false;
+        }
+        let saga_id = context.saga_id;
+        match event {
+            SagaChoreographyEvent::SagaStarted { payload, .. }
+                if self.depends_on.is_on_saga_start() =>
+            {
+                self.park(event, context.clone(), payload.clone())
+            }
+            SagaChoreographyEvent::StepCompleted {
+                output,
+                compensation_available,
+                ..
+            } => {
+                if *compensation_available {
+                    self.compensable_steps
+                        .entry(saga_id)
+                        .or_default()
+                        .push(context.step_name.clone());
+                }
+                if self.dependency_satisfied(saga_id, &context.step_name) {
+                    let parked_context = context.next_step(self.step_name.into());
+                    self.park(event, parked_context, output.clone())
+                } else {
+                    false
+                }
+            }
+            SagaChoreographyEvent::SagaCompleted { .. }
+            | SagaChoreographyEvent::SagaFailed { .. }
+            | SagaChoreographyEvent::SagaQuarantined { .. } => {
+                self.forget(saga_id);
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// Completes the parked step of `saga_id`. Returns the `StepCompleted`
+    /// to publish.
+    pub fn approve(&mut self, saga_id: SagaId) -> Result<SagaChoreographyEvent, ApprovalError> {
+        let pending = self
+            .pending
+            .remove(&saga_id)
+            .ok_or(ApprovalError::NotPending(saga_id))?;
+        let now = SagaContext::now_millis();
+        if let Err(err) = self.journal.append(
+            saga_id,
+            ParticipantEvent::StepExecutionCompleted {
+                output: pending.input.clone(),
+                compensation_data: Vec::new(),
+                completed_at_millis: now,
+            },
+        ) {
+            self.pending.insert(saga_id, pending);
+            return Err(err.into());
+        }
+        tracing::info!(
+            target: "core::saga",
+            event = "saga_step_approved",
+            saga_id = saga_id.get(),
+            step_name = self.step_name
+        );
+        let mut context = pending.context;
+        context.event_timestamp_millis = now;
+        Ok(SagaChoreographyEvent::StepCompleted {
+            context,
+            output: pending.input.clone(),
+            saga_input: pending.input,
+            compensation_available: false,
+            completion_ratio: None,
+        })
+    }
+
+    /// Rejects the parked step of `saga_id`. Returns the
+    /// `CompensationRequested` to publish, covering the saga's compensable
+    /// steps seen so far in reverse order.
+    pub fn reject(
+        &mut self,
+        saga_id: SagaId,
+        reason: &str,
+    ) -> Result<SagaChoreographyEvent, ApprovalError> {
+        let pending = self
+            .pending
+            .remove(&saga_id)
+            .ok_or(ApprovalError::NotPending(saga_id))?;
+        let now = SagaContext::now_millis();
+        if let Err(err) = self.journal.append(
+            saga_id,
+            ParticipantEvent::StepExecutionFailed {
+                error: reason.into(),
+                requires_compensation: true,
+                failed_at_millis: now,
+                details: Vec::new(),
+            },
+        ) {
+            self.pending.insert(saga_id, pending);
+            return Err(err.into());
+        }
+        tracing::info!(
+            target: "core::saga",
+            event = "saga_step_rejected",
+            saga_id = saga_id.get(),
+            step_name = self.step_name,
+            reason
+        );
+        let steps_to_compensate = self
+            .compensable_steps
+            .get(&saga_id)
+            .map(|steps| self.steps_to_compensate(&pending.context, steps))
+            .unwrap_or_default();
+        Ok(SagaChoreographyEvent::CompensationRequested {
+            context: pending.context.for_compensation(),
+            failed_step: self.step_name.into(),
+            reason: format!("approval rejected: {reason}").into(),
+            steps_to_compensate,
+        })
+    }
+
+    /// Steps awaiting a decision, by saga id.
+    pub fn pending(&self) -> impl Iterator<Item = &PendingApproval> {
+        self.pending.values()
+    }
+
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Parks again every step the journal shows as parked but undecided.
+    /// Returns how many were parked.
+    pub fn recover(&mut self) -> Result<usize, JournalError> {
+        let mut recovered = 0;
+        for saga_id in self.journal.list_sagas()? {
+            let entries = self.journal.read(saga_id)?;
+            let Some(last) = crate::journal::last_progress_entry(&entries) else {
+                continue;
+            };
+            if !matches!(last.event, ParticipantEvent::StepTriggered { .. }) {
+                continue;
+            }
+            let Some(trigger) = self.journal.incoming_history(saga_id)?.pop() else {
+                continue;
+            };
+            let Some((context, input)) = self.parked_step(&trigger.event) else {
+                continue;
+            };
+            if let SagaChoreographyEvent::StepCompleted {
+                compensation_available: true,
+                completion_ratio: None,
+                context: trigger_context,
+                ..
+            } = &trigger.event
+            {
+                let steps = self.compensable_steps.entry(saga_id).or_default();
+                if !steps.contains(&trigger_context.step_name) {
+                    steps.push(trigger_context.step_name.clone());
+                }
+            }
+            self.pending.insert(
+                saga_id,
+                PendingApproval {
+                    context,
+                    input,
+                    parked_at_millis: last.recorded_at_millis,
+                },
+            );
+            recovered += 1;
+        }
+        Ok(recovered)
+    }
+
+    fn steps_to_compensate(&self, context: &SagaContext, completed: &[StepName]) -> Vec<StepName> {
+        match &self.bus {
+            Some(bus) => bus.steps_to_compensate(context, self.step_name, completed),
+            None => completed.iter().rev().cloned().collect(),
+        }
+    }
+
+    fn parked_step(&self, trigger: &SagaChoreographyEvent) -> Option<(SagaContext, Vec<u8>)> {
+        match trigger {
+            SagaChoreographyEvent::SagaStarted { context, payload } => {
+                Some((context.clone(), payload.clone()))
+            }
+            SagaChoreographyEvent::StepCompleted {
+                context, output, ..
+            } => Some((context.next_step(self.step_name.into()), output.clone())),
+            _ => None,
+        }
+    }
+
+    fn dependency_satisfied(&mut self, saga_id: SagaId, completed_step: &StepName) -> bool {
+        match &self.depends_on {
+            DependencySpec::OnSagaStart => false,
+            DependencySpec::After(step) => completed_step == step,
+            DependencySpec::AnyOf(steps) => steps.iter().any(|step| step == completed_step),
+            DependencySpec::AllOf(steps) => {
+                if !steps.iter().any(|step| step == completed_step) {
+                    return false;
+                }
+                let completed = self.dependency_completions.entry(saga_id).or_default();
+                completed.insert(completed_step.clone());
+                steps.iter().all(|step| completed.contains(step))
+            }
+        }
+    }
+
+    fn park(
+        &mut self,
+        trigger: &SagaChoreographyEvent,
+        context: SagaContext,
+        input: Vec<u8>,
+    ) -> bool {
+        let saga_id = context.saga_id;
+        // A redelivered trigger, or one for a step decided already.
+        let already_parked = self.pending.contains_key(&saga_id)
+            || self.journal.read(saga_id).is_ok_and(|entries| {
+                entries
+                    .iter()
+                    .any(|entry| matches!(entry.event, ParticipantEvent::StepTriggered { .. }))
+            });
+        if already_parked {
+            return false;
+        }
+        let now = SagaContext::now_millis();
+        let journaled = self
+            .journal
+            .record_incoming(saga_id, DedupeKey::from_event(trigger), trigger)
+            .and_then(|inbox_id| match inbox_id {
+                Some(inbox_id) => self.journal.mark_incoming_processed(inbox_id),
+                None => Ok(()),
+            })
+            .and_then(|()| {
+                self.journal.append(
+                    saga_id,
+                    ParticipantEvent::StepTriggered {
+                        triggering_event: trigger.event_type().into(),
+                        triggered_at_millis: now,
+                    },
+                )
+            });
+        if let Err(err) = journaled {
+            tracing::error!(
+                target: "core::saga",
+                event = "saga_approval_park_journal_failed",
+                saga_id = saga_id.get(),
+                step_name = self.step_name,
+                error = ?err
+            );
+        }
+        tracing::info!(
+            target: "core::saga",
+            event = "saga_step_awaiting_approval",
+            saga_id = saga_id.get(),
+            step_name = self.step_name
+        );
+        self.pending.insert(
+            saga_id,
+            PendingApproval {
+                context,
+                input,
+                parked_at_millis: now,
+            },
+        );
+        true
+    }
+
+    fn forget(&mut self, saga_id: SagaId) {
+        self.pending.remove(&saga_id);
+        self.dependency_completions.remove(&saga_id);
+        self.compensable_steps.remove(&saga_id);
+        if let Err(err) = self.journal.prune(saga_id) {
+            tracing::error!(
+                target: "core::saga",
+                event = "saga_approval_prune_failed",
+                saga_id = saga_id.get(),
+                error = ?err
+            );
+        }
+    }
+}
+
+impl<J: ParticipantJournal> std::fmt::Debug for ApprovalStep<J> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApprovalStep")
+            .field("step_name", &self.step_name)
+            .field("depends_on", &self.depends_on)
+            .field("pending_len", &self.pending.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{step_completed, DeterministicContextBuilder, InMemoryJournal};
+
+    #[test]
+    fn parked_step_is_decided_once_and_survives_restart() {
+        let journal = Arc::new(InMemoryJournal::new());
+        let mut approval = ApprovalStep::new("sign_off", &["order_lifecycle"], journal.clone())
+            .after(DependencySpec::after("risk_check"));
+        let risk = DeterministicContextBuilder::default().build();
+        let trigger = step_completed(risk.clone(), b"order-9".to_vec(), Vec::new(), true);
+        assert!(approval.handle(&trigger));
+        assert!(!approval.handle(&trigger));
+
+        // Restart: a fresh step recovers the parked approval from the journal.
+        let mut approval = ApprovalStep::new("sign_off", &["order_lifecycle"], journal.clone())
+            .after(DependencySpec::after("risk_check"));
+        assert_eq!(approval.recover().unwrap(), 1);
+        let Some(SagaChoreographyEvent::CompensationRequested {
+            failed_step,
+            steps_to_compensate,
+            ..
+        }) = approval.reject(risk.saga_id, "too large").ok()
+        else {
+            panic!("expected a compensation request");
+        };
+        assert_eq!(failed_step.as_ref(), "sign_off");
+        assert_eq!(steps_to_compensate, vec![StepName::from("risk_check")]);
+        assert!(matches!(
+            approval.approve(risk.saga_id),
+            Err(ApprovalError::NotPending(_))
+        ));
+
+        let mut approval = ApprovalStep::new("sign_off", &["order_lifecycle"], journal)
+            .after(DependencySpec::after("risk_check"));
+        assert_eq!(approval.recover().unwrap(), 0);
+        assert!(!approval.handle(&trigger));
+
+        let other = DeterministicContextBuilder::default()
+            .with_saga_id(2)
+            .build();
+        approval.handle(&step_completed(
+            other.clone(),
+            b"order-10".to_vec(),
+            Vec::new(),
+            false,
+        ));
+        let SagaChoreographyEvent::StepCompleted {
+            context, output, ..
+        } = approval.approve(other.saga_id).unwrap()
+        else {
+            panic!("expected StepCompleted");
+        };
+        assert_eq!(context.step_name.as_ref(), "sign_off");
+        assert_eq!(output, b"order-10");
+    }
+}
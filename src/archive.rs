@@ -0,0 +1,401 @@
+//! Cold storage for finished sagas.
+//!
+//! Participant journals are operational stores: they only need the sagas
+//! that are still running. An [`ArchiveStore`] keeps the history of finished
+//! sagas for later investigation. [`archive_saga`] copies one saga's journal
+//! and incoming history into the archive and then prunes it from the
+//! journal; [`archive_settled_sagas`] does that for every saga the
+//! participant finished with. A support with an archive attached
+//! ([`crate::SagaParticipantSupport::with_archive_store`]) also archives each
+//! saga before its terminal prune.
+//!
+//! [`InMemoryArchiveStore`] and the file-per-saga [`FileArchiveStore`] ship
+//! with the crate; object stores (S3 and compatible) plug in by implementing
+//! the trait.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::journal::last_progress_entry;
+use crate::{InboxEntry, JournalEntry, JournalError, ParticipantJournal, SagaContext, SagaId};
+
+/// Everything archived for one saga.
+#[derive(Clone, Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct SagaArchiveRecord {
+    pub saga_id: SagaId,
+    /// The Unix timestamp in milliseconds when the saga was archived.
+    pub archived_at_millis: u64,
+    pub journal: Vec<JournalEntry>,
+    /// Incoming events, when the journal keeps an incoming history.
+    pub incoming: Vec<InboxEntry>,
+}
+
+impl SagaArchiveRecord {
+    /// Earliest timestamp of any journal or incoming entry.
+    pub fn first_recorded_at_millis(&self) -> Option<u64> {
+        self.timestamps().min()
+    }
+
+    /// Latest timestamp of any journal or incoming entry.
+    pub fn last_recorded_at_millis(&self) -> Option<u64> {
+        self.timestamps().max()
+    }
+
+    /// Whether the saga's history overlaps `from_millis..=to_millis`.
+    pub fn overlaps(&self, from_millis: u64, to_millis: u64) -> bool {
+        match (
+            self.first_recorded_at_millis(),
+            self.last_recorded_at_millis(),
+        ) {
+            (Some(first), Some(last)) => first <= to_millis && last >= from_millis,
+            _ => false,
+        }
+    }
+
+    fn timestamps(&self) -> impl Iterator<Item = u64> + '_ {
+        self.journal
+            .iter()
+            .map(|entry| entry.recorded_at_millis)
+            .chain(self.incoming.iter().map(|entry| entry.recorded_at_millis))
+    }
+}
+
+/// Errors that can occur during archive operations.
+#[derive(Debug, thiserror::Error)]
+pub enum ArchiveError {
+    #[error("Storage error: {0}")]
+    Storage(Box<str>),
+    #[error(transparent)]
+    Journal(#[from] JournalError),
+}
+
+/// Cold storage for finished sagas.
+///
+/// Implementations must be `Send + Sync + 'static` so one store can be shared
+/// by every participant of a process.
+pub trait ArchiveStore: Send + Sync + 'static {
+    /// Stores `record`, replacing an earlier record of the same saga.
+    fn store(&self, record: SagaArchiveRecord) -> Result<(), ArchiveError>;
+
+    /// Returns the archived record of `saga_id`, if any.
+    fn find(&self, saga_id: SagaId) -> Result<Option<SagaArchiveRecord>, ArchiveError>;
+
+    /// Returns the records whose history overlaps
+    /// `from_millis..=to_millis`, ordered by saga id.
+    fn find_by_time_range(
+        &self,
+        from_millis: u64,
+        to_millis: u64,
+    ) -> Result<Vec<SagaArchiveRecord>, ArchiveError>;
+}
+
+impl<T> ArchiveStore for std::sync::Arc<T>
+where
+    T: ArchiveStore + ?Sized,
+{
+    fn store(&self, record: SagaArchiveRecord) -> Result<(), ArchiveError> {
+        (**self).store(record)
+    }
+
+    fn find(&self, saga_id: SagaId) -> Result<Option<SagaArchiveRecord>, ArchiveError> {
+        (**self).find(saga_id)
+    }
+
+    fn find_by_time_range(
+        &self,
+        from_millis: u64,
+        to_millis: u64,
+    ) -> Result<Vec<SagaArchiveRecord>, ArchiveError> {
+        (**self).find_by_time_range(from_millis, to_millis)
+    }
+}
+
+/// In-memory archive for tests and single-process deployments.
+#[derive(Default)]
+pub struct InMemoryArchiveStore {
+    records: std::sync::RwLock<BTreeMap<u64, SagaArchiveRecord>>,
+}
+
+impl InMemoryArchiveStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ArchiveStore for InMemoryArchiveStore {
+    fn store(&self, record: SagaArchiveRecord) -> Result<(), ArchiveError> {
+        let mut records = self
+            .records
+            .write()
+            .map_err(|e| ArchiveError::Storage(e.to_string().into()))?;
+        records.insert(record.saga_id.get(), record);
+        Ok(())
+    }
+
+    fn find(&self, saga_id: SagaId) -> Result<Option<SagaArchiveRecord>, ArchiveError> {
+        let records = self
+            .records
+            .read()
+            .map_err(|e| ArchiveError::Storage(e.to_string().into()))?;
+        Ok(records.get(&saga_id.get()).cloned())
+    }
+
+    fn find_by_time_range(
+        &self,
+        from_millis: u64,
+        to_millis: u64,
+    ) -> Result<Vec<SagaArchiveRecord>, ArchiveError> {
+        let records = self
+            .records
+            .read()
+            .map_err(|e| ArchiveError::Storage(e.to_string().into()))?;
+        Ok(records
+            .values()
+            .filter(|record| record.overlaps(from_millis, to_millis))
+            .cloned()
+            .collect())
+    }
+}
+
+/// Archive keeping one rkyv-encoded file per saga in a directory.
+///
+/// Records are written to a temporary file and renamed into place, so a
+/// crash never leaves a truncated record. Time-range queries decode every
+/// file; keep the directory per participant and rotate it if it grows large.
+pub struct FileArchiveStore {
+    dir: PathBuf,
+}
+
+impl FileArchiveStore {
+    /// Opens the archive in `dir`, creating the directory if needed.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, ArchiveError> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir).map_err(storage_error)?;
+        Ok(Self { dir })
+    }
+
+    fn record_path(&self, saga_id: SagaId) -> PathBuf {
+        self.dir.join(format!("{:020}.saga", saga_id.get()))
+    }
+
+    fn read_record(path: &Path) -> Result<SagaArchiveRecord, ArchiveError> {
+        let bytes = std::fs::read(path).map_err(storage_error)?;
+        rkyv::from_bytes::<SagaArchiveRecord, rkyv::rancor::Error>(&bytes)
+            .map_err(|err| ArchiveError::Storage(format!("{}: {err}", path.display()).into()))
+    }
+}
+
+impl ArchiveStore for FileArchiveStore {
+    fn store(&self, record: SagaArchiveRecord) -> Result<(), ArchiveError> {
+        let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&record)
+            .map_err(|err| ArchiveError::Storage(err.to_string().into()))?;
+        let path = self.record_path(record.saga_id);
+        let staging = path.with_extension("saga.tmp");
+        std::fs::write(&staging, &bytes).map_err(storage_error)?;
+        std::fs::rename(&staging, &path).map_err(storage_error)
+    }
+
+    fn find(&self, saga_id: SagaId) -> Result<Option<SagaArchiveRecord>, ArchiveError> {
+        let path = self.record_path(saga_id);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Self::read_record(&path).map(Some)
+    }
+
+    fn find_by_time_range(
+        &self,
+        from_millis: u64,
+        to_millis: u64,
+    ) -> Result<Vec<SagaArchiveRecord>, ArchiveError> {
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(&self.dir).map_err(storage_error)? {
+            let path = entry.map_err(storage_error)?.path();
+            if path.extension().is_some_and(|ext| ext == "saga") {
+                paths.push(path);
+            }
+        }
+        // File names are zero-padded saga ids.
+        paths.sort();
+        let mut out = Vec::new();
+        for path in paths {
+            let record = Self::read_record(&path)?;
+            if record.overlaps(from_millis, to_millis) {
+                out.push(record);
+            }
+        }
+        Ok(out)
+    }
+}
+
+impl std::fmt::Debug for FileArchiveStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileArchiveStore")
+            .field("dir", &self.dir)
+            .finish()
+    }
+}
+
+fn storage_error(err: std::io::Error) -> ArchiveError {
+    ArchiveError::Storage(err.to_string().into())
+}
+
+/// Copies `saga_id`'s journal and incoming history into `archive` without
+/// touching the journal. Returns `false` if the journal holds nothing for it.
+pub fn copy_saga_to_archive<J, A>(
+    journal: &J,
+    archive: &A,
+    saga_id: SagaId,
+) -> Result<bool, ArchiveError>
+where
+    J: ParticipantJournal + ?Sized,
+    A: ArchiveStore + ?Sized,
+{
+    let entries = journal.read(saga_id)?;
+    let incoming = journal.incoming_history(saga_id)?;
+    if entries.is_empty() && incoming.is_empty() {
+        return Ok(false);
+    }
+    archive.store(SagaArchiveRecord {
+        saga_id,
+        archived_at_millis: SagaContext::now_millis(),
+        journal: entries,
+        incoming,
+    })?;
+    Ok(true)
+}
+
+/// Moves `saga_id` from the journal into `archive`: copies it, then prunes
+/// the journal. The dedupe store is left alone. Returns `false` if the
+/// journal holds nothing for the saga.
+pub fn archive_saga<J, A>(journal: &J, archive: &A, saga_id: SagaId) -> Result<bool, ArchiveError>
+where
+    J: ParticipantJournal + ?Sized,
+    A: ArchiveStore + ?Sized,
+{
+    if !copy_saga_to_archive(journal, archive, saga_id)? {
+        return Ok(false);
+    }
+    journal.prune(saga_id)?;
+    tracing::info!(
+        target: "core::saga",
+        event = "saga_archived",
+        saga_id = saga_id.get()
+    );
+    Ok(true)
+}
+
+/// Archives every saga the participant has settled (compensated, or failed
+/// without needing compensation) at least `min_age_ms` before `now_ms`.
+/// Running and quarantined sagas stay in the journal. Returns the archived
+/// ids in ascending order.
+pub fn archive_settled_sagas<J, A>(
+    journal: &J,
+    archive: &A,
+    now_ms: u64,
+    min_age_ms: u64,
+) -> Result<Vec<SagaId>, ArchiveError>
+where
+    J: ParticipantJournal + ?Sized,
+    A: ArchiveStore + ?Sized,
+{
+    let mut archived = Vec::new();
+    for saga_id in journal.list_sagas()? {
+        let entries = journal.read(saga_id)?;
+        let settled = last_progress_entry(&entries).is_some_and(|last| {
+            last.event.is_settled() && now_ms.saturating_sub(last.recorded_at_millis) >= min_age_ms
+        });
+        if settled && archive_saga(journal, archive, saga_id)? {
+            archived.push(saga_id);
+        }
+    }
+    Ok(archived)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryJournal, ParticipantEvent};
+
+    fn settle(journal: &InMemoryJournal, saga_id: SagaId) {
+        journal
+            .append(
+                saga_id,
+                ParticipantEvent::StepExecutionStarted {
+                    attempt: 1,
+                    started_at_millis: 0,
+                },
+            )
+            .unwrap();
+        journal
+            .append(
+                saga_id,
+                ParticipantEvent::StepExecutionFailed {
+                    error: "rejected".into(),
+                    requires_compensation: false,
+                    failed_at_millis: 0,
+                    details: Vec::new(),
+                },
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn settled_sagas_move_to_the_archive() {
+        let journal = InMemoryJournal::new();
+        let archive = InMemoryArchiveStore::new();
+        let settled = SagaId::new(1);
+        let running = SagaId::new(2);
+        settle(&journal, settled);
+        journal
+            .append(
+                running,
+                ParticipantEvent::StepExecutionStarted {
+                    attempt: 1,
+                    started_at_millis: 0,
+                },
+            )
+            .unwrap();
+
+        let now = SagaContext::now_millis();
+        assert_eq!(
+            archive_settled_sagas(&journal, &archive, now, 0).unwrap(),
+            vec![settled]
+        );
+        assert_eq!(journal.list_sagas().unwrap(), vec![running]);
+
+        let record = archive.find(settled).unwrap().expect("archived");
+        assert_eq!(record.journal.len(), 2);
+        assert!(archive.find(running).unwrap().is_none());
+        let first = record.first_recorded_at_millis().unwrap();
+        assert_eq!(archive.find_by_time_range(first, first).unwrap().len(), 1);
+        assert!(archive.find_by_time_range(0, first - 1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn file_archive_round_trips_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = InMemoryJournal::new();
+        settle(&journal, SagaId::new(5));
+        settle(&journal, SagaId::new(3));
+
+        let archive = FileArchiveStore::open(dir.path()).unwrap();
+        assert!(archive_saga(&journal, &archive, SagaId::new(5)).unwrap());
+        assert!(archive_saga(&journal, &archive, SagaId::new(3)).unwrap());
+        assert!(!archive_saga(&journal, &archive, SagaId::new(9)).unwrap());
+
+        let reopened = FileArchiveStore::open(dir.path()).unwrap();
+        let record = reopened.find(SagaId::new(5)).unwrap().expect("archived");
+        assert!(matches!(
+            record.journal[1].event,
+            ParticipantEvent::StepExecutionFailed { .. }
+        ));
+        let ids: Vec<SagaId> = reopened
+            .find_by_time_range(0, u64::MAX)
+            .unwrap()
+            .into_iter()
+            .map(|record| record.saga_id)
+            .collect();
+        assert_eq!(ids, vec![SagaId::new(3), SagaId::new(5)]);
+    }
+}
@@ -0,0 +1,600 @@
+//! Async journal and dedupe stores for participants on async actor runtimes.
+//!
+//! Stores whose client is async (a database pool, a remote log) implement
+//! [`AsyncParticipantJournal`] / [`AsyncParticipantDedupeStore`] and are
+//! plugged in through [`AsyncJournalAdapter`] / [`AsyncDedupeAdapter`], so an
+//! `icanact_core::local_async` actor can be a full participant:
+//!
+//! ```ignore
+//! let saga = SagaParticipantSupport::new(
+//!     AsyncJournalAdapter::new(PgJournal::new(pool.clone())),
+//!     AsyncDedupeAdapter::new(PgDedupe::new(pool)),
+//! );
+//! // In the actor's saga event handler:
+//! handle_saga_event_async(&mut actor, event).await;
+//! ```
+//!
+//! The async handlers await the wrapped store directly, on any tokio
+//! runtime. The adapters never block on it: called through the sync traits,
+//! e.g. by [`crate::handle_saga_event`] or a startup integrity check, they
+//! fail with a storage error.
+
+use crate::{
+    DedupeError, DedupeKey, InboxEntry, JournalEntry, JournalError, OutboxEntry,
+    ParticipantDedupeStore, ParticipantEvent, ParticipantJournal, SagaBoxFuture,
+    SagaChoreographyEvent, SagaId,
+};
+
+/// Async counterpart of [`ParticipantJournal`]. The inbox and outbox methods
+/// default to "no table", like the sync trait's.
+pub trait AsyncParticipantJournal: Send + Sync + 'static {
+    fn append(
+        &self,
+        saga_id: SagaId,
+        event: ParticipantEvent,
+    ) -> SagaBoxFuture<'_, Result<u64, JournalError>>;
+
+    fn read(&self, saga_id: SagaId) -> SagaBoxFuture<'_, Result<Vec<JournalEntry>, JournalError>>;
+
+    fn list_sagas(&self) -> SagaBoxFuture<'_, Result<Vec<SagaId>, JournalError>>;
+
+    fn prune(&self, saga_id: SagaId) -> SagaBoxFuture<'_, Result<(), JournalError>>;
+
+    fn record_outgoing<'a>(
+        &'a self,
+        saga_id: SagaId,
+        event: &'a SagaChoreographyEvent,
+    ) -> SagaBoxFuture<'a, Result<Option<u64>, JournalError>> {
+        let _ = (saga_id, event);
+        Box::pin(async { Ok(None) })
+    }
+
+    /// Appends `event` and stages `outgoing` in the outbox in one write, like
+    /// [`ParticipantJournal::append_with_outgoing`]. The default appends,
+    /// then stages each event on its own, logging the ones that fail.
+    fn append_with_outgoing<'a>(
+        &'a self,
+        saga_id: SagaId,
+        event: ParticipantEvent,
+        outgoing: &'a [SagaChoreographyEvent],
+    ) -> SagaBoxFuture<'a, Result<(u64, Vec<Option<u64>>), JournalError>> {
+        Box::pin(async move {
+            let sequence = self.append(saga_id, event).await?;
+            let mut outbox_ids = Vec::with_capacity(outgoing.len());
+            for event in outgoing {
+                let outbox_id = self
+                    .record_outgoing(saga_id, event)
+                    .await
+                    .unwrap_or_else(|err| {
+                        tracing::warn!(
+                            target: "core::saga",
+                            event = "saga_outbox_record_failed",
+                            saga_id = saga_id.get(),
+                            error = %err
+                        );
+                        None
+                    });
+                outbox_ids.push(outbox_id);
+            }
+            Ok((sequence, outbox_ids))
+        })
+    }
+
+    fn pending_outgoing(&self) -> SagaBoxFuture<'_, Result<Vec<OutboxEntry>, JournalError>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+
+    fn mark_outgoing_sent(&self, outbox_id: u64) -> SagaBoxFuture<'_, Result<(), JournalError>> {
+        let _ = outbox_id;
+        Box::pin(async { Ok(()) })
+    }
+
+    fn record_incoming<'a>(
+        &'a self,
+        saga_id: SagaId,
+        dedupe_key: DedupeKey,
+        event: &'a SagaChoreographyEvent,
+    ) -> SagaBoxFuture<'a, Result<Option<u64>, JournalError>> {
+        let _ = (saga_id, dedupe_key, event);
+        Box::pin(async { Ok(None) })
+    }
+
+    fn pending_incoming(&self) -> SagaBoxFuture<'_, Result<Vec<InboxEntry>, JournalError>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+
+    fn mark_incoming_processed(
+        &self,
+        inbox_id: u64,
+    ) -> SagaBoxFuture<'_, Result<(), JournalError>> {
+        let _ = inbox_id;
+        Box::pin(async { Ok(()) })
+    }
+
+    fn incoming_history(
+        &self,
+        saga_id: SagaId,
+    ) -> SagaBoxFuture<'_, Result<Vec<InboxEntry>, JournalError>> {
+        let _ = saga_id;
+        Box::pin(async { Ok(Vec::new()) })
+    }
+}
+
+/// Async counterpart of [`ParticipantDedupeStore`].
+pub trait AsyncParticipantDedupeStore: Send + Sync + 'static {
+    fn check_and_mark(
+        &self,
+        saga_id: SagaId,
+        key: DedupeKey,
+    ) -> SagaBoxFuture<'_, Result<bool, DedupeError>>;
+
+    fn contains(&self, saga_id: SagaId, key: DedupeKey) -> SagaBoxFuture<'_, bool>;
+
+    fn mark_processed(
+        &self,
+        saga_id: SagaId,
+        key: DedupeKey,
+    ) -> SagaBoxFuture<'_, Result<(), DedupeError>>;
+
+    fn prune(&self, saga_id: SagaId) -> SagaBoxFuture<'_, Result<(), DedupeError>>;
+
+    fn list_sagas(&self) -> SagaBoxFuture<'_, Result<Option<Vec<SagaId>>, DedupeError>> {
+        Box::pin(async { Ok(None) })
+    }
+}
+
+impl<T> AsyncParticipantJournal for std::sync::Arc<T>
+where
+    T: AsyncParticipantJournal + ?Sized,
+{
+    fn append(
+        &self,
+        saga_id: SagaId,
+        event: ParticipantEvent,
+    ) -> SagaBoxFuture<'_, Result<u64, JournalError>> {
+        (**self).append(saga_id, event)
+    }
+
+    fn read(&self, saga_id: SagaId) -> SagaBoxFuture<'_, Result<Vec<JournalEntry>, JournalError>> {
+        (**self).read(saga_id)
+    }
+
+    fn list_sagas(&self) -> SagaBoxFuture<'_, Result<Vec<SagaId>, JournalError>> {
+        (**self).list_sagas()
+    }
+
+    fn prune(&self, saga_id: SagaId) -> SagaBoxFuture<'_, Result<(), JournalError>> {
+        (**self).prune(saga_id)
+    }
+
+    fn record_outgoing<'a>(
+        &'a self,
+        saga_id: SagaId,
+        event: &'a SagaChoreographyEvent,
+    ) -> SagaBoxFuture<'a, Result<Option<u64>, JournalError>> {
+        (**self).record_outgoing(saga_id, event)
+    }
+
+    fn append_with_outgoing<'a>(
+        &'a self,
+        saga_id: SagaId,
+        event: ParticipantEvent,
+        outgoing: &'a [SagaChoreographyEvent],
+    ) -> SagaBoxFuture<'a, Result<(u64, Vec<Option<u64>>), JournalError>> {
+        (**self).append_with_outgoing(saga_id, event, outgoing)
+    }
+
+    fn pending_outgoing(&self) -> SagaBoxFuture<'_, Result<Vec<OutboxEntry>, JournalError>> {
+        (**self).pending_outgoing()
+    }
+
+    fn mark_outgoing_sent(&self, outbox_id: u64) -> SagaBoxFuture<'_, Result<(), JournalError>> {
+        (**self).mark_outgoing_sent(outbox_id)
+    }
+
+    fn record_incoming<'a>(
+        &'a self,
+        saga_id: SagaId,
+        dedupe_key: DedupeKey,
+        event: &'a SagaChoreographyEvent,
+    ) -> SagaBoxFuture<'a, Result<Option<u64>, JournalError>> {
+        (**self).record_incoming(saga_id, dedupe_key, event)
+    }
+
+    fn pending_incoming(&self) -> SagaBoxFuture<'_, Result<Vec<InboxEntry>, JournalError>> {
+        (**self).pending_incoming()
+    }
+
+    fn mark_incoming_processed(
+        &self,
+        inbox_id: u64,
+    ) -> SagaBoxFuture<'_, Result<(), JournalError>> {
+        (**self).mark_incoming_processed(inbox_id)
+    }
+
+    fn incoming_history(
+        &self,
+        saga_id: SagaId,
+    ) -> SagaBoxFuture<'_, Result<Vec<InboxEntry>, JournalError>> {
+        (**self).incoming_history(saga_id)
+    }
+}
+
+impl<T> AsyncParticipantDedupeStore for std::sync::Arc<T>
+where
+    T: AsyncParticipantDedupeStore + ?Sized,
+{
+    fn check_and_mark(
+        &self,
+        saga_id: SagaId,
+        key: DedupeKey,
+    ) -> SagaBoxFuture<'_, Result<bool, DedupeError>> {
+        (**self).check_and_mark(saga_id, key)
+    }
+
+    fn contains(&self, saga_id: SagaId, key: DedupeKey) -> SagaBoxFuture<'_, bool> {
+        (**self).contains(saga_id, key)
+    }
+
+    fn mark_processed(
+        &self,
+        saga_id: SagaId,
+        key: DedupeKey,
+    ) -> SagaBoxFuture<'_, Result<(), DedupeError>> {
+        (**self).mark_processed(saga_id, key)
+    }
+
+    fn prune(&self, saga_id: SagaId) -> SagaBoxFuture<'_, Result<(), DedupeError>> {
+        (**self).prune(saga_id)
+    }
+
+    fn list_sagas(&self) -> SagaBoxFuture<'_, Result<Option<Vec<SagaId>>, DedupeError>> {
+        (**self).list_sagas()
+    }
+}
+
+/// Error of every call through the sync traits into an adapter.
+fn sync_call_refused() -> Box<str> {
+    "async store is only reached through the async handlers".into()
+}
+
+/// [`ParticipantJournal`] backed by an [`AsyncParticipantJournal`].
+pub struct AsyncJournalAdapter<J> {
+    inner: J,
+}
+
+impl<J: AsyncParticipantJournal> AsyncJournalAdapter<J> {
+    pub fn new(inner: J) -> Self {
+        Self { inner }
+    }
+
+    pub fn inner(&self) -> &J {
+        &self.inner
+    }
+}
+
+impl<J: AsyncParticipantJournal> ParticipantJournal for AsyncJournalAdapter<J> {
+    fn append(&self, saga_id: SagaId, event: ParticipantEvent) -> Result<u64, JournalError> {
+        let _ = (saga_id, event);
+        Err(JournalError::Storage(sync_call_refused()))
+    }
+
+    fn read(&self, saga_id: SagaId) -> Result<Vec<JournalEntry>, JournalError> {
+        let _ = saga_id;
+        Err(JournalError::Storage(sync_call_refused()))
+    }
+
+    fn list_sagas(&self) -> Result<Vec<SagaId>, JournalError> {
+        Err(JournalError::Storage(sync_call_refused()))
+    }
+
+    fn prune(&self, saga_id: SagaId) -> Result<(), JournalError> {
+        let _ = saga_id;
+        Err(JournalError::Storage(sync_call_refused()))
+    }
+
+    fn record_outgoing(
+        &self,
+        saga_id: SagaId,
+        event: &SagaChoreographyEvent,
+    ) -> Result<Option<u64>, JournalError> {
+        let _ = (saga_id, event);
+        Err(JournalError::Storage(sync_call_refused()))
+    }
+
+    fn pending_outgoing(&self) -> Result<Vec<OutboxEntry>, JournalError> {
+        Err(JournalError::Storage(sync_call_refused()))
+    }
+
+    fn mark_outgoing_sent(&self, outbox_id: u64) -> Result<(), JournalError> {
+        let _ = outbox_id;
+        Err(JournalError::Storage(sync_call_refused()))
+    }
+
+    fn record_incoming(
+        &self,
+        saga_id: SagaId,
+        dedupe_key: DedupeKey,
+        event: &SagaChoreographyEvent,
+    ) -> Result<Option<u64>, JournalError> {
+        let _ = (saga_id, dedupe_key, event);
+        Err(JournalError::Storage(sync_call_refused()))
+    }
+
+    fn pending_incoming(&self) -> Result<Vec<InboxEntry>, JournalError> {
+        Err(JournalError::Storage(sync_call_refused()))
+    }
+
+    fn mark_incoming_processed(&self, inbox_id: u64) -> Result<(), JournalError> {
+        let _ = inbox_id;
+        Err(JournalError::Storage(sync_call_refused()))
+    }
+
+    fn incoming_history(&self, saga_id: SagaId) -> Result<Vec<InboxEntry>, JournalError> {
+        let _ = saga_id;
+        Err(JournalError::Storage(sync_call_refused()))
+    }
+
+    fn as_async(&self) -> Option<&dyn AsyncParticipantJournal> {
+        Some(&self.inner)
+    }
+}
+
+impl<J> std::fmt::Debug for AsyncJournalAdapter<J> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AsyncJournalAdapter")
+            .finish_non_exhaustive()
+    }
+}
+
+/// [`ParticipantDedupeStore`] backed by an [`AsyncParticipantDedupeStore`].
+pub struct AsyncDedupeAdapter<D> {
+    inner: D,
+}
+
+impl<D: AsyncParticipantDedupeStore> AsyncDedupeAdapter<D> {
+    pub fn new(inner: D) -> Self {
+        Self { inner }
+    }
+
+    pub fn inner(&self) -> &D {
+        &self.inner
+    }
+}
+
+impl<D: AsyncParticipantDedupeStore> ParticipantDedupeStore for AsyncDedupeAdapter<D> {
+    fn check_and_mark(&self, saga_id: SagaId, key: DedupeKey) -> Result<bool, DedupeError> {
+        let _ = (saga_id, key);
+        Err(DedupeError::Storage(sync_call_refused()))
+    }
+
+    fn contains(&self, saga_id: SagaId, key: DedupeKey) -> bool {
+        tracing::error!(
+            target: "core::saga",
+            event = "async_dedupe_contains_failed",
+            saga_id = saga_id.get(),
+            key = %key,
+            error = %sync_call_refused()
+        );
+        false
+    }
+
+    fn mark_processed(&self, saga_id: SagaId, key: DedupeKey) -> Result<(), DedupeError> {
+        let _ = (saga_id, key);
+        Err(DedupeError::Storage(sync_call_refused()))
+    }
+
+    fn prune(&self, saga_id: SagaId) -> Result<(), DedupeError> {
+        let _ = saga_id;
+        Err(DedupeError::Storage(sync_call_refused()))
+    }
+
+    fn list_sagas(&self) -> Result<Option<Vec<SagaId>>, DedupeError> {
+        Err(DedupeError::Storage(sync_call_refused()))
+    }
+
+    fn as_async(&self) -> Option<&dyn AsyncParticipantDedupeStore> {
+        Some(&self.inner)
+    }
+}
+
+impl<D> std::fmt::Debug for AsyncDedupeAdapter<D> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AsyncDedupeAdapter").finish_non_exhaustive()
+    }
+}
+
+// The store calls of the async handlers: each awaits the async store behind
+// an adapter and calls any other store directly.
+
+pub(crate) async fn journal_append<J>(
+    journal: &J,
+    saga_id: SagaId,
+    event: ParticipantEvent,
+) -> Result<u64, JournalError>
+where
+    J: ParticipantJournal + ?Sized,
+{
+    match journal.as_async() {
+        Some(journal) => journal.append(saga_id, event).await,
+        None => journal.append(saga_id, event),
+    }
+}
+
+pub(crate) async fn journal_append_with_outgoing<J>(
+    journal: &J,
+    saga_id: SagaId,
+    event: ParticipantEvent,
+    outgoing: &[SagaChoreographyEvent],
+) -> Result<(u64, Vec<Option<u64>>), JournalError>
+where
+    J: ParticipantJournal + ?Sized,
+{
+    match journal.as_async() {
+        Some(journal) => journal.append_with_outgoing(saga_id, event, outgoing).await,
+        None => journal.append_with_outgoing(saga_id, event, outgoing),
+    }
+}
+
+pub(crate) async fn journal_read<J>(
+    journal: &J,
+    saga_id: SagaId,
+) -> Result<Vec<JournalEntry>, JournalError>
+where
+    J: ParticipantJournal + ?Sized,
+{
+    match journal.as_async() {
+        Some(journal) => journal.read(saga_id).await,
+        None => journal.read(saga_id),
+    }
+}
+
+pub(crate) async fn journal_prune<J>(journal: &J, saga_id: SagaId) -> Result<(), JournalError>
+where
+    J: ParticipantJournal + ?Sized,
+{
+    match journal.as_async() {
+        Some(journal) => journal.prune(saga_id).await,
+        None => journal.prune(saga_id),
+    }
+}
+
+pub(crate) async fn journal_record_outgoing<J>(
+    journal: &J,
+    saga_id: SagaId,
+    event: &SagaChoreographyEvent,
+) -> Result<Option<u64>, JournalError>
+where
+    J: ParticipantJournal + ?Sized,
+{
+    match journal.as_async() {
+        Some(journal) => journal.record_outgoing(saga_id, event).await,
+        None => journal.record_outgoing(saga_id, event),
+    }
+}
+
+pub(crate) async fn journal_pending_outgoing<J>(
+    journal: &J,
+) -> Result<Vec<OutboxEntry>, JournalError>
+where
+    J: ParticipantJournal + ?Sized,
+{
+    match journal.as_async() {
+        Some(journal) => journal.pending_outgoing().await,
+        None => journal.pending_outgoing(),
+    }
+}
+
+pub(crate) async fn journal_mark_outgoing_sent<J>(
+    journal: &J,
+    outbox_id: u64,
+) -> Result<(), JournalError>
+where
+    J: ParticipantJournal + ?Sized,
+{
+    match journal.as_async() {
+        Some(journal) => journal.mark_outgoing_sent(outbox_id).await,
+        None => journal.mark_outgoing_sent(outbox_id),
+    }
+}
+
+pub(crate) async fn journal_record_incoming<J>(
+    journal: &J,
+    saga_id: SagaId,
+    dedupe_key: DedupeKey,
+    event: &SagaChoreographyEvent,
+) -> Result<Option<u64>, JournalError>
+where
+    J: ParticipantJournal + ?Sized,
+{
+    match journal.as_async() {
+        Some(journal) => journal.record_incoming(saga_id, dedupe_key, event).await,
+        None => journal.record_incoming(saga_id, dedupe_key, event),
+    }
+}
+
+pub(crate) async fn journal_pending_incoming<J>(
+    journal: &J,
+) -> Result<Vec<InboxEntry>, JournalError>
+where
+    J: ParticipantJournal + ?Sized,
+{
+    match journal.as_async() {
+        Some(journal) => journal.pending_incoming().await,
+        None => journal.pending_incoming(),
+    }
+}
+
+pub(crate) async fn journal_mark_incoming_processed<J>(
+    journal: &J,
+    inbox_id: u64,
+) -> Result<(), JournalError>
+where
+    J: ParticipantJournal + ?Sized,
+{
+    match journal.as_async() {
+        Some(journal) => journal.mark_incoming_processed(inbox_id).await,
+        None => journal.mark_incoming_processed(inbox_id),
+    }
+}
+
+pub(crate) async fn journal_incoming_history<J>(
+    journal: &J,
+    saga_id: SagaId,
+) -> Result<Vec<InboxEntry>, JournalError>
+where
+    J: ParticipantJournal + ?Sized,
+{
+    match journal.as_async() {
+        Some(journal) => journal.incoming_history(saga_id).await,
+        None => journal.incoming_history(saga_id),
+    }
+}
+
+pub(crate) async fn dedupe_check_and_mark<D>(
+    dedupe: &D,
+    saga_id: SagaId,
+    key: DedupeKey,
+) -> Result<bool, DedupeError>
+where
+    D: ParticipantDedupeStore + ?Sized,
+{
+    match dedupe.as_async() {
+        Some(dedupe) => dedupe.check_and_mark(saga_id, key).await,
+        None => dedupe.check_and_mark(saga_id, key),
+    }
+}
+
+pub(crate) async fn dedupe_contains<D>(dedupe: &D, saga_id: SagaId, key: DedupeKey) -> bool
+where
+    D: ParticipantDedupeStore + ?Sized,
+{
+    match dedupe.as_async() {
+        Some(dedupe) => dedupe.contains(saga_id, key).await,
+        None => dedupe.contains(saga_id, key),
+    }
+}
+
+pub(crate) async fn dedupe_mark_processed<D>(
+    dedupe: &D,
+    saga_id: SagaId,
+    key: DedupeKey,
+) -> Result<(), DedupeError>
+where
+    D: ParticipantDedupeStore + ?Sized,
+{
+    match dedupe.as_async() {
+        Some(dedupe) => dedupe.mark_processed(saga_id, key).await,
+        None => dedupe.mark_processed(saga_id, key),
+    }
+}
+
+pub(crate) async fn dedupe_prune<D>(dedupe: &D, saga_id: SagaId) -> Result<(), DedupeError>
+where
+    D: ParticipantDedupeStore + ?Sized,
+{
+    match dedupe.as_async() {
+        Some(dedupe) => dedupe.prune(saga_id).await,
+        None => dedupe.prune(saga_id),
+    }
+}
@@ -0,0 +1,35 @@
+//! `saga-admin`: inspect a participant's LMDB journal and manage its
+//! quarantined sagas.
+//!
+//! `retry` needs the participant's compensation code, which this binary does
+//! not link; it reports the missing compensator. Services build their own
+//! binary around `SagaAdmin::with_compensator` and `run_admin_command` to get
+//! it.
+
+use std::process::ExitCode;
+
+use clap::Parser;
+use icanact_saga_choreography::durability::lmdb::LmdbJournal;
+use icanact_saga_choreography::{run_admin_command, AdminCli, SagaAdmin};
+
+fn main() -> ExitCode {
+    let cli = AdminCli::parse();
+    let journal = match LmdbJournal::open(&cli.journal) {
+        Ok(journal) => journal,
+        Err(err) => {
+            eprintln!("saga-admin: cannot open {}: {err}", cli.journal.display());
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut admin = SagaAdmin::new(journal).with_participant_id(&cli.participant);
+    if let Some(step) = &cli.step {
+        admin = admin.with_step(step);
+    }
+    match run_admin_command(&admin, &cli.command, &mut std::io::stdout().lock()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("saga-admin: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -731,6 +731,9 @@ mod tests {
             initiator_peer_id: [0; 32],
             saga_started_at_millis: now,
             event_timestamp_millis: now,
+            parent_saga_id: None,
+            workflow_version: crate::DEFAULT_WORKFLOW_VERSION,
+            logical_clock: 1,
         }
     }
 
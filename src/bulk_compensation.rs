@@ -0,0 +1,119 @@
+//! Operator-triggered compensation of every in-flight saga of one type.
+//!
+//! When a venue goes down, ops needs "compensate every open
+//! `deribit_order` saga now". [`request_compensation_for_type`] takes the
+//! in-flight sagas from a [`SagaActivityTracker`] subscribed to the saga
+//! type and publishes a `CompensationRequested` for each one, the same way a
+//! failing step would: a fresh trace id caused by the saga's latest event,
+//! and the compensable completed steps in reverse completion order. Sagas
+//! that are already compensating are left alone, so running it twice does
+//! not request twice once the tracker has seen the first requests.
+
+use crate::{SagaActivityTracker, SagaBusPublishError, SagaChoreographyBus, SagaId};
+
+/// Outcome of [`request_compensation_for_type`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BulkCompensationReport {
+    /// Sagas whose `CompensationRequested` was published.
+    pub requested: Vec<SagaId>,
+    /// Sagas whose request could not be published.
+    pub failed: Vec<(SagaId, SagaBusPublishError)>,
+}
+
+/// Publishes a `CompensationRequested` carrying `reason` for every saga of
+/// `saga_type` that `registry` sees in flight and not yet compensating.
+pub fn request_compensation_for_type(
+    bus: &SagaChoreographyBus,
+    registry: &SagaActivityTracker,
+    saga_type: &str,
+    reason: &str,
+) -> BulkCompensationReport {
+    let mut report = BulkCompensationReport::default();
+    for request in registry.compensation_requests(saga_type, reason) {
+        let saga_id = request.context().saga_id;
+        match bus.publish_strict(request) {
+            Ok(_) => report.requested.push(saga_id),
+            Err(err) => {
+                tracing::warn!(
+                    target: "core::saga",
+                    event = "saga_bulk_compensation_publish_failed",
+                    saga_id = saga_id.get(),
+                    saga_type,
+                    error = ?err
+                );
+                report.failed.push((saga_id, err));
+            }
+        }
+    }
+    tracing::warn!(
+        target: "core::saga",
+        event = "saga_bulk_compensation_requested",
+        saga_type,
+        reason,
+        requested = report.requested.len(),
+        failed = report.failed.len()
+    );
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::{saga_started, step_completed, DeterministicContextBuilder, SagaChoreographyEvent};
+
+    #[test]
+    fn open_sagas_of_the_type_get_one_compensation_request_each() {
+        let bus = SagaChoreographyBus::new();
+        let tracker = SagaActivityTracker::new();
+        let _tracked = tracker.subscribe(&bus, "deribit_order");
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&requests);
+        let _listener = bus.subscribe_saga_type_fn("deribit_order", move |event| {
+            if let SagaChoreographyEvent::CompensationRequested {
+                context,
+                steps_to_compensate,
+                ..
+            } = event
+            {
+                sink.lock()
+                    .unwrap()
+                    .push((context.saga_id.get(), steps_to_compensate.clone()));
+            }
+            true
+        });
+        let context = |saga_id, saga_type: &str| {
+            DeterministicContextBuilder::default()
+                .with_saga_id(saga_id)
+                .with_saga_type(saga_type)
+                .build()
+        };
+
+        for (saga_id, saga_type) in [(1, "deribit_order"), (2, "deribit_order"), (3, "rebalance")] {
+            tracker.observe(&saga_started(context(saga_id, saga_type), Vec::new()));
+        }
+        for step in ["reserve_margin", "place_order"] {
+            tracker.observe(&step_completed(
+                context(1, "deribit_order").next_step(step.into()),
+                Vec::new(),
+                Vec::new(),
+                true,
+            ));
+        }
+        tracker.observe(&SagaChoreographyEvent::SagaCompleted {
+            context: context(2, "deribit_order"),
+        });
+
+        let report = request_compensation_for_type(&bus, &tracker, "deribit_order", "outage");
+        assert_eq!(report.requested, vec![SagaId::new(1)]);
+        assert_eq!(
+            *requests.lock().unwrap(),
+            vec![(1, vec!["place_order".into(), "reserve_margin".into()])]
+        );
+
+        // The tracker saw the request, so a second sweep finds nothing.
+        let again = request_compensation_for_type(&bus, &tracker, "deribit_order", "outage");
+        assert!(again.requested.is_empty());
+    }
+}
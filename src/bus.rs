@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::sync::{Arc, Mutex, OnceLock};
 use std::thread;
 use std::time::Duration;
@@ -6,17 +6,22 @@ use std::time::Duration;
 use icanact_core::local::{EventBus, EventSubscription, PublishStats};
 use icanact_core::CorrelationRegistry;
 
+use crate::admission::{AdmissionDecision, SagaAdmissionState};
 use crate::reply_registry::{SagaReplyToHandle, SagaReplyToResult};
 use crate::workflow_contract::required_path_steps_from_success_criteria;
 use crate::{
-    required_steps_from_success_criteria, validate_workflow_contract, HasSagaWorkflowParticipants,
-    SagaChoreographyEvent, SagaId, SagaReplyTo, SagaTerminalOutcome, SagaWorkflowContract,
+    required_steps_from_success_criteria, validate_workflow_contract, CompensationScope,
+    CompensationStrategy, DependencySpec, HasSagaWorkflowParticipants, ParticipantAnnounced,
+    ParticipantRegistry, SagaAdmission, SagaChain, SagaChoreographyEvent, SagaConcurrencyLimit,
+    SagaContext, SagaId, SagaObserver, SagaReplyTo, SagaTerminalOutcome, SagaWorkflowContract,
     SagaWorkflowStepContract, TerminalPolicy, TerminalResolver, TERMINAL_RESOLVER_STEP,
 };
 
 #[derive(Clone, Debug)]
 struct WorkflowContractState {
     first_step: Box<str>,
+    steps: &'static [SagaWorkflowStepContract],
+    terminal_required_steps: HashSet<Box<str>>,
     declared_steps: HashSet<Box<str>>,
     required_path_steps: HashSet<Box<str>>,
     required_path_description: Box<str>,
@@ -26,8 +31,13 @@ type TerminalReplyMap = Arc<Mutex<HashMap<SagaId, SagaReplyTo>>>;
 type TerminalOutcomeMap = Arc<Mutex<HashMap<SagaId, SagaTerminalOutcome>>>;
 type TerminalOrder = Arc<Mutex<VecDeque<SagaId>>>;
 type TerminalPolicyMap = Arc<Mutex<HashMap<Box<str>, Box<str>>>>;
-type WorkflowContractMap = Arc<Mutex<HashMap<Box<str>, WorkflowContractState>>>;
+/// Contracts by saga type, then by workflow version.
+type WorkflowContractMap = Arc<Mutex<HashMap<Box<str>, BTreeMap<u32, WorkflowContractState>>>>;
 type BoundStepMap = Arc<Mutex<HashMap<Box<str>, HashSet<Box<str>>>>>;
+type AdmissionMap = Arc<Mutex<HashMap<Box<str>, SagaAdmissionState>>>;
+type CompensationStrategyMap = Arc<Mutex<HashMap<Box<str>, CompensationStrategy>>>;
+type ObserverSlot = Arc<Mutex<Option<Arc<dyn SagaObserver>>>>;
+type RegistrySlot = Arc<Mutex<Option<ParticipantRegistry>>>;
 
 pub struct SagaChoreographyBus {
     bus: EventBus<SagaChoreographyEvent>,
@@ -38,6 +48,10 @@ pub struct SagaChoreographyBus {
     terminal_policies_by_saga_type: TerminalPolicyMap,
     workflow_contracts_by_saga_type: WorkflowContractMap,
     bound_steps_by_saga_type: BoundStepMap,
+    admission_by_saga_type: AdmissionMap,
+    compensation_strategies: CompensationStrategyMap,
+    observer: ObserverSlot,
+    participant_registry: RegistrySlot,
     owned: bool,
 }
 
@@ -69,27 +83,40 @@ fn required_path_description(
 pub enum SagaBusPublishError {
     PartialDelivery {
         saga_id: SagaId,
-        saga_type: Box<str>,
-        step_name: Box<str>,
+        saga_type: crate::SagaType,
+        step_name: crate::StepName,
         attempted: u32,
         delivered: u32,
     },
     TerminalEscalationPartialDelivery {
         saga_id: SagaId,
-        saga_type: Box<str>,
+        saga_type: crate::SagaType,
         attempted: u32,
         delivered: u32,
     },
     RequiredPathDeliveryShortfall {
         saga_id: SagaId,
-        saga_type: Box<str>,
-        step_name: Box<str>,
+        saga_type: crate::SagaType,
+        step_name: crate::StepName,
         event_type: &'static str,
         attempted: u32,
         delivered: u32,
         required_min_delivered: u32,
         required_path: Box<str>,
     },
+    AdmissionRejected {
+        saga_id: SagaId,
+        saga_type: crate::SagaType,
+        admission_key: Box<str>,
+        in_flight: u32,
+        max_in_flight: u32,
+    },
+    /// No live participant is registered for these steps of the saga.
+    MissingParticipants {
+        saga_id: SagaId,
+        saga_type: crate::SagaType,
+        missing_steps: Vec<crate::StepName>,
+    },
 }
 
 impl SagaChoreographyBus {
@@ -103,6 +130,10 @@ impl SagaChoreographyBus {
             terminal_policies_by_saga_type: Arc::new(Mutex::new(HashMap::new())),
             workflow_contracts_by_saga_type: Arc::new(Mutex::new(HashMap::new())),
             bound_steps_by_saga_type: Arc::new(Mutex::new(HashMap::new())),
+            admission_by_saga_type: Arc::new(Mutex::new(HashMap::new())),
+            compensation_strategies: Arc::new(Mutex::new(HashMap::new())),
+            observer: Arc::new(Mutex::new(None)),
+            participant_registry: Arc::new(Mutex::new(None)),
             owned: true,
         }
     }
@@ -151,19 +182,14 @@ impl SagaChoreographyBus {
                 }
                 return self.bus.publish(terminal);
             }
-            expected_min_delivery =
-                self.saga_start_expected_min_delivery(context.saga_type.as_ref());
-            expected_required_path = self
-                .required_path_description(context.saga_type.as_ref())
-                .into();
+            expected_min_delivery = self.saga_start_expected_min_delivery(context);
+            expected_required_path = self.required_path_description(context).into();
             expected_context = Some(context.clone());
         } else if let Some(required_min_delivery) =
             self.required_path_expected_min_delivery_for_event(&event)
         {
             expected_min_delivery = Some(required_min_delivery);
-            expected_required_path = self
-                .required_path_description(event.context().saga_type.as_ref())
-                .into();
+            expected_required_path = self.required_path_description(event.context()).into();
             expected_context = Some(event.context().clone());
         }
         if let Some(outcome) = event.terminal_outcome() {
@@ -216,9 +242,7 @@ impl SagaChoreographyBus {
                     attempted: stats.attempted,
                     delivered: stats.delivered,
                     required_min_delivered: required_min_delivery,
-                    required_path: self
-                        .required_path_description(context.saga_type.as_ref())
-                        .into(),
+                    required_path: self.required_path_description(context).into(),
                 });
             }
         }
@@ -285,8 +309,13 @@ impl SagaChoreographyBus {
         let policy = C::terminal_policy();
         validate_workflow_contract(C::saga_type(), C::first_step(), C::steps(), &policy)?;
 
+        // Names of registered contracts are the only ones interned; see
+        // `crate::Symbol`.
+        crate::SagaType::intern(C::saga_type());
+        crate::StepName::intern(TERMINAL_RESOLVER_STEP);
         let mut declared_steps: HashSet<Box<str>> = HashSet::new();
         for step in C::steps() {
+            crate::StepName::intern(step.step_name);
             declared_steps.insert(step.step_name.into());
         }
         let required_path_steps =
@@ -294,6 +323,8 @@ impl SagaChoreographyBus {
         let required_path_description = required_path_description(C::steps(), &required_path_steps);
         let contract_state = WorkflowContractState {
             first_step: C::first_step().into(),
+            steps: C::steps(),
+            terminal_required_steps: required_steps_from_success_criteria(&policy.success_criteria),
             declared_steps: declared_steps.clone(),
             required_path_steps,
             required_path_description: required_path_description.into(),
@@ -304,10 +335,26 @@ impl SagaChoreographyBus {
                 .bound_steps_by_saga_type
                 .lock()
                 .unwrap_or_else(|poisoned| poisoned.into_inner());
+            let contracts = self
+                .workflow_contracts_by_saga_type
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            // Participants bound for another registered version keep their
+            // steps while both versions run.
+            let declared_by_other_versions = |step: &str| {
+                contracts.get(C::saga_type()).is_some_and(|versions| {
+                    versions.iter().any(|(version, contract)| {
+                        *version != C::workflow_version() && contract.declared_steps.contains(step)
+                    })
+                })
+            };
             if let Some(bound_for_type) = bound.get(C::saga_type()) {
                 let mut unknown_steps: Vec<&str> = bound_for_type
                     .iter()
-                    .filter(|step| !declared_steps.contains(step.as_ref()))
+                    .filter(|step| {
+                        !declared_steps.contains(step.as_ref())
+                            && !declared_by_other_versions(step.as_ref())
+                    })
                     .map(|step| step.as_ref())
                     .collect();
                 unknown_steps.sort_unstable();
@@ -326,7 +373,10 @@ impl SagaChoreographyBus {
                 .workflow_contracts_by_saga_type
                 .lock()
                 .unwrap_or_else(|poisoned| poisoned.into_inner());
-            contracts.insert(C::saga_type().into(), contract_state);
+            contracts
+                .entry(C::saga_type().into())
+                .or_default()
+                .insert(C::workflow_version(), contract_state);
         }
 
         let required = required_steps_from_success_criteria(&policy.success_criteria);
@@ -343,6 +393,241 @@ impl SagaChoreographyBus {
         Ok(())
     }
 
+    /// Steps declared by the workflow contract registered for `saga_type` at
+    /// `workflow_version`, in declaration order.
+    pub fn workflow_steps(
+        &self,
+        saga_type: &str,
+        workflow_version: u32,
+    ) -> Option<Vec<&'static str>> {
+        self.workflow_contracts_by_saga_type
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(saga_type)?
+            .get(&workflow_version)
+            .map(|contract| contract.steps.iter().map(|step| step.step_name).collect())
+    }
+
+    /// Steps `context`'s `SagaStarted` triggers: those its workflow
+    /// contract runs on saga start, or the context's step without a
+    /// contract.
+    pub fn saga_start_steps(&self, context: &SagaContext) -> Vec<crate::StepName> {
+        let steps: Vec<crate::StepName> = self
+            .contract_steps(context)
+            .iter()
+            .filter(|step| matches!(step.depends_on, crate::WorkflowDependencySpec::OnSagaStart))
+            .map(|step| step.step_name.into())
+            .collect();
+        if steps.is_empty() {
+            return vec![context.step_name.clone()];
+        }
+        steps
+    }
+
+    /// Sets how compensation requests of `saga_type` choose their steps.
+    /// Terminal resolvers pick the strategy up when attached, so set it
+    /// first.
+    pub fn set_compensation_strategy(&self, saga_type: &str, strategy: CompensationStrategy) {
+        self.compensation_strategies
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(saga_type.into(), strategy);
+    }
+
+    /// Strategy set for `saga_type`; `CompensateAllCompleted` if none is.
+    pub fn compensation_strategy(&self, saga_type: &str) -> CompensationStrategy {
+        self.compensation_strategies
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(saga_type)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Steps a compensation request for `failed_step` should name, chosen by
+    /// the strategy of `context`'s saga type from `completed_steps` (in
+    /// completion order) and the workflow contract of the saga's version,
+    /// cascading to the completed steps downstream of those it picks.
+    pub fn steps_to_compensate(
+        &self,
+        context: &crate::SagaContext,
+        failed_step: &str,
+        completed_steps: &[crate::StepName],
+    ) -> Vec<crate::StepName> {
+        let workflow_steps = self.contract_steps(context);
+        self.compensation_strategy(context.saga_type.as_str())
+            .select(&CompensationScope {
+                failed_step,
+                completed_steps,
+                workflow_steps,
+            })
+    }
+
+    /// Completed steps of `context`'s saga that depend on `step`, directly
+    /// or transitively, per the workflow contract of the saga's version;
+    /// latest first. `steps_to_compensate` includes them whenever it names
+    /// `step`.
+    pub fn downstream_completed_steps(
+        &self,
+        context: &crate::SagaContext,
+        step: &str,
+        completed_steps: &[crate::StepName],
+    ) -> Vec<crate::StepName> {
+        let workflow_steps = self.contract_steps(context);
+        crate::compensation_strategy::downstream_completed_steps(
+            &CompensationScope {
+                failed_step: step,
+                completed_steps,
+                workflow_steps,
+            },
+            step,
+        )
+    }
+
+    fn contract_steps(&self, context: &crate::SagaContext) -> &'static [SagaWorkflowStepContract] {
+        self.workflow_contracts_by_saga_type
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(context.saga_type.as_str())
+            .and_then(|versions| versions.get(&context.workflow_version))
+            .map(|contract| contract.steps)
+            .unwrap_or(&[])
+    }
+
+    /// Checks a participant's step names against the registered workflow
+    /// contracts: `step_name` and every step `depends_on` names must be
+    /// declared by some version of the contract of each saga type matching
+    /// one of `saga_types`. A misspelled name would otherwise leave the
+    /// participant waiting for a step that never completes.
+    pub fn validate_step_references(
+        &self,
+        saga_types: &[&str],
+        step_name: &str,
+        depends_on: &DependencySpec,
+    ) -> Result<(), String> {
+        let contracts = self
+            .workflow_contracts_by_saga_type
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        for pattern in saga_types {
+            let mut matched = false;
+            for (saga_type, versions) in contracts
+                .iter()
+                .filter(|(saga_type, _)| crate::saga_type_matches(pattern, saga_type))
+            {
+                matched = true;
+                let declared =
+                    |step: &str| versions.values().any(|c| c.declared_steps.contains(step));
+                let mut unknown_steps: Vec<&str> = std::iter::once(step_name)
+                    .chain(depends_on.steps().iter().map(|step| step.as_str()))
+                    .filter(|step| !declared(step))
+                    .collect();
+                unknown_steps.dedup();
+                if !unknown_steps.is_empty() {
+                    return Err(format!(
+                        "steps not declared by workflow contract: saga_type={} unknown_steps={}",
+                        saga_type,
+                        unknown_steps.join(",")
+                    ));
+                }
+            }
+            if !matched {
+                return Err(format!(
+                    "no workflow contract registered for saga_type={pattern}"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Step declarations of every registered version of each saga type
+    /// matching `pattern`, by saga type in name order.
+    pub(crate) fn workflow_contracts_matching(
+        &self,
+        pattern: &str,
+    ) -> Vec<(Box<str>, Vec<&'static [SagaWorkflowStepContract]>)> {
+        let contracts = self
+            .workflow_contracts_by_saga_type
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut matching: Vec<_> = contracts
+            .iter()
+            .filter(|(saga_type, _)| crate::saga_type_matches(pattern, saga_type))
+            .map(|(saga_type, versions)| {
+                (
+                    saga_type.clone(),
+                    versions.values().map(|contract| contract.steps).collect(),
+                )
+            })
+            .collect();
+        matching.sort_by(|a, b| a.0.cmp(&b.0));
+        matching
+    }
+
+    /// [`Self::validate_step_references`] for `participant`'s saga types,
+    /// step and dependencies.
+    pub fn validate_participant_steps<P: crate::SagaParticipant>(
+        &self,
+        participant: &P,
+    ) -> Result<(), String> {
+        self.validate_step_references(
+            participant.saga_types(),
+            participant.step_name(),
+            &participant.depends_on(),
+        )
+    }
+
+    /// Renders every registered workflow contract as one Mermaid flowchart,
+    /// one subgraph per saga type in saga type order.
+    pub fn workflows_to_mermaid(&self) -> String {
+        self.with_workflow_diagrams(crate::workflow_diagram::render_mermaid)
+    }
+
+    /// Renders every registered workflow contract as one Graphviz DOT
+    /// digraph, one cluster per saga type in saga type order.
+    pub fn workflows_to_dot(&self) -> String {
+        self.with_workflow_diagrams(crate::workflow_diagram::render_dot)
+    }
+
+    fn with_workflow_diagrams(
+        &self,
+        render: fn(&[crate::workflow_diagram::WorkflowDiagram<'_>]) -> String,
+    ) -> String {
+        let contracts = self
+            .workflow_contracts_by_saga_type
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        // Saga types registered only under the default version keep their
+        // plain name; other versions are labelled `saga_type v<N>`.
+        let mut labelled: Vec<(String, &WorkflowContractState)> = contracts
+            .iter()
+            .flat_map(|(saga_type, versions)| {
+                versions.iter().map(move |(version, contract)| {
+                    let label =
+                        if versions.len() == 1 && *version == crate::DEFAULT_WORKFLOW_VERSION {
+                            saga_type.to_string()
+                        } else {
+                            format!("{saga_type} v{version}")
+                        };
+                    (label, contract)
+                })
+            })
+            .collect();
+        labelled.sort_by(|a, b| a.0.cmp(&b.0));
+        let diagrams: Vec<crate::workflow_diagram::WorkflowDiagram<'_>> = labelled
+            .iter()
+            .map(
+                |(label, contract)| crate::workflow_diagram::WorkflowDiagram {
+                    saga_type: label,
+                    first_step: &contract.first_step,
+                    steps: contract.steps,
+                    required_steps: &contract.terminal_required_steps,
+                },
+            )
+            .collect();
+        render(&diagrams)
+    }
+
     pub fn register_bound_workflow_step(
         &self,
         saga_type: &'static str,
@@ -356,8 +641,11 @@ impl SagaChoreographyBus {
                 .workflow_contracts_by_saga_type
                 .lock()
                 .unwrap_or_else(|poisoned| poisoned.into_inner());
-            if let Some(contract) = contracts.get(saga_type) {
-                if !contract.declared_steps.contains(step_name) {
+            if let Some(versions) = contracts.get(saga_type) {
+                if !versions
+                    .values()
+                    .any(|contract| contract.declared_steps.contains(step_name))
+                {
                     return Err(format!(
                         "bound workflow step is not declared by contract: saga_type={} step={}",
                         saga_type, step_name
@@ -366,6 +654,8 @@ impl SagaChoreographyBus {
             }
         }
 
+        crate::SagaType::intern(saga_type);
+        crate::StepName::intern(step_name);
         let mut bound = self
             .bound_steps_by_saga_type
             .lock()
@@ -430,13 +720,84 @@ impl SagaChoreographyBus {
             .is_ok()
     }
 
+    /// Sets the observer that terminal resolvers attached from now on report
+    /// step timeouts to.
+    pub fn attach_observer(&self, observer: Arc<dyn SagaObserver>) {
+        *self
+            .observer
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(observer);
+    }
+
+    pub fn observer(&self) -> Option<Arc<dyn SagaObserver>> {
+        self.observer
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// Sends [`ParticipantAnnounced`] events published with
+    /// [`SagaChoreographyBus::announce`] to `registry`.
+    pub fn attach_participant_registry(&self, registry: ParticipantRegistry) {
+        *self
+            .participant_registry
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(registry);
+    }
+
+    pub fn participant_registry(&self) -> Option<ParticipantRegistry> {
+        self.participant_registry
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// Records `announcement` in the attached participant registry, if any.
+    pub fn announce(&self, announcement: ParticipantAnnounced) {
+        tracing::info!(
+            target: "core::saga",
+            event = "saga_participant_announced",
+            saga_types = ?announcement.saga_types,
+            steps = ?announcement.steps
+        );
+        if let Some(registry) = self.participant_registry() {
+            registry.record(announcement);
+        }
+    }
+
     pub fn attach_terminal_resolver(
         &self,
         policy: TerminalPolicy,
         responder: &'static str,
+    ) -> Result<EventSubscription, String> {
+        self.attach_terminal_resolver_inner(policy, None, responder)
+    }
+
+    /// Attaches a terminal resolver that only sees sagas running under
+    /// `workflow_version`, for saga types with several registered versions
+    /// whose terminal policies differ.
+    pub fn attach_terminal_resolver_for_version(
+        &self,
+        policy: TerminalPolicy,
+        workflow_version: u32,
+        responder: &'static str,
+    ) -> Result<EventSubscription, String> {
+        self.attach_terminal_resolver_inner(policy, Some(workflow_version), responder)
+    }
+
+    fn attach_terminal_resolver_inner(
+        &self,
+        policy: TerminalPolicy,
+        workflow_version: Option<u32>,
+        responder: &'static str,
     ) -> Result<EventSubscription, String> {
         self.register_terminal_policy(&policy);
-        let resolver = Arc::new(Mutex::new(TerminalResolver::new(policy.clone())));
+        let mut resolver = TerminalResolver::new(policy.clone())
+            .with_compensation_strategy(self.compensation_strategy(&policy.saga_type));
+        if let Some(observer) = self.observer() {
+            resolver = resolver.with_observer(observer);
+        }
+        let resolver = Arc::new(Mutex::new(resolver));
         let bus = self.clone();
         let responder: Arc<str> = Arc::from(responder);
         let saga_type_topic = policy.saga_type.clone();
@@ -449,6 +810,11 @@ impl SagaChoreographyBus {
         Ok(self
             .bus
             .subscribe_fn(saga_type_topic.as_ref(), move |event| {
+                if workflow_version
+                    .is_some_and(|version| event.context().workflow_version != version)
+                {
+                    return true;
+                }
                 let terminal_events = {
                     let mut resolver = match resolver.lock() {
                         Ok(guard) => guard,
@@ -474,11 +840,205 @@ impl SagaChoreographyBus {
             }))
     }
 
+    /// Attaches `chain` so every `SagaCompleted` of its source saga type
+    /// starts a saga of its target type on this bus.
+    pub fn attach_saga_chain(&self, chain: SagaChain) -> EventSubscription {
+        let tracker = Arc::new(Mutex::new(chain.tracker()));
+        let chained: Arc<Mutex<HashSet<SagaId>>> = Arc::default();
+        // Dropped with the returned subscription.
+        let release = Mutex::new(self.release_chained_sagas(&chain, Arc::clone(&chained)));
+        let bus = self.clone();
+        let source_topic = chain.source_saga_type.clone();
+        self.bus.subscribe_fn(source_topic.as_ref(), move |event| {
+            let _ = &release;
+            let next = {
+                let mut tracker = match tracker.lock() {
+                    Ok(guard) => guard,
+                    Err(poisoned) => poisoned.into_inner(),
+                };
+                tracker.ingest(event)
+            };
+            if let Some(SagaChoreographyEvent::SagaStarted { context, payload }) = next {
+                let parent_saga_id = event.context().saga_id;
+                let child_saga_id = context.saga_id;
+                chained
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .insert(child_saga_id);
+                if let Err(err) = bus.start_saga(context, payload) {
+                    tracing::error!(
+                        target: "core::saga",
+                        event = "saga_chain_start_publish_failed",
+                        source_saga_type = chain.source_saga_type.as_ref(),
+                        target_saga_type = chain.target_saga_type.as_ref(),
+                        parent_saga_id = parent_saga_id.get(),
+                        saga_id = child_saga_id.get(),
+                        error = ?err
+                    );
+                }
+            }
+            true
+        })
+    }
+
+    /// Releases each saga `chain` started once it completes: its participants
+    /// hold a completion naming a parent (see [`crate::SubSagaStep`]), and a
+    /// chained saga's parent completed before it started.
+    fn release_chained_sagas(
+        &self,
+        chain: &SagaChain,
+        chained: Arc<Mutex<HashSet<SagaId>>>,
+    ) -> EventSubscription {
+        let bus = self.clone();
+        self.bus
+            .subscribe_fn(chain.target_saga_type.as_ref(), move |event| {
+                let context = event.context();
+                if event.terminal_outcome().is_none()
+                    || !chained
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())
+                        .remove(&context.saga_id)
+                {
+                    return true;
+                }
+                if let SagaChoreographyEvent::SagaCompleted { context } = event {
+                    if let Err(err) = bus.publish_strict(crate::sub_saga::child_released(context)) {
+                        tracing::error!(
+                            target: "core::saga",
+                            event = "saga_chain_release_publish_failed",
+                            saga_type = context.saga_type.as_ref(),
+                            saga_id = context.saga_id.get(),
+                            error = ?err
+                        );
+                    }
+                }
+                true
+            })
+    }
+
+    /// Attaches a concurrency cap for `limit.saga_type`. The returned
+    /// subscription frees slots on terminal events and publishes queued
+    /// starts as slots free up; starts go through [`Self::start_saga`].
+    pub fn attach_concurrency_limit(&self, limit: SagaConcurrencyLimit) -> EventSubscription {
+        let saga_type = limit.saga_type.clone();
+        self.admission_by_saga_type
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(saga_type.clone(), limit.admission_state());
+        let admission = Arc::clone(&self.admission_by_saga_type);
+        let bus = self.clone();
+        let topic = saga_type.clone();
+        self.bus.subscribe_fn(topic.as_ref(), move |event| {
+            let admitted = {
+                let mut admission = admission
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                match admission.get_mut(event.context().saga_type.as_ref()) {
+                    Some(state) => state.observe(event),
+                    None => Vec::new(),
+                }
+            };
+            for started in admitted {
+                let saga_id = started.context().saga_id;
+                if let Err(err) = bus.publish_strict(started) {
+                    tracing::error!(
+                        target: "core::saga",
+                        event = "saga_admission_queued_start_failed",
+                        saga_type = saga_type.as_ref(),
+                        saga_id = saga_id.get(),
+                        error = ?err
+                    );
+                }
+            }
+            true
+        })
+    }
+
+    /// Number of admitted, non-terminal sagas of `saga_type` and the number
+    /// of starts waiting in its admission queue.
+    pub fn saga_admission_counts(&self, saga_type: &str) -> Option<(usize, usize)> {
+        self.admission_by_saga_type
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(saga_type)
+            .map(|state| (state.in_flight(), state.queued()))
+    }
+
+    /// Publishes `SagaStarted` for `context` subject to the concurrency limit
+    /// attached for its saga type, if any.
+    pub fn start_saga(
+        &self,
+        context: SagaContext,
+        payload: Vec<u8>,
+    ) -> Result<SagaAdmission, SagaBusPublishError> {
+        let decision = {
+            let mut admission = self
+                .admission_by_saga_type
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            match admission.get_mut(context.saga_type.as_ref()) {
+                Some(state) => {
+                    let max_in_flight = state.limit().max_in_flight;
+                    (state.admit(&context, &payload), max_in_flight)
+                }
+                None => (AdmissionDecision::Admit, 0),
+            }
+        };
+        match decision {
+            (AdmissionDecision::Admit, _) => {
+                let stats =
+                    self.publish_strict(SagaChoreographyEvent::SagaStarted { context, payload })?;
+                Ok(SagaAdmission::Started {
+                    attempted: stats.attempted,
+                    delivered: stats.delivered,
+                })
+            }
+            (AdmissionDecision::Queued { position }, _) => {
+                tracing::info!(
+                    target: "core::saga",
+                    event = "saga_start_queued",
+                    saga_type = context.saga_type.as_ref(),
+                    saga_id = context.saga_id.get(),
+                    position
+                );
+                Ok(SagaAdmission::Queued { position })
+            }
+            (
+                AdmissionDecision::Rejected {
+                    admission_key,
+                    in_flight,
+                },
+                max_in_flight,
+            ) => {
+                tracing::warn!(
+                    target: "core::saga",
+                    event = "saga_start_rejected",
+                    saga_type = context.saga_type.as_ref(),
+                    saga_id = context.saga_id.get(),
+                    admission_key = admission_key.as_ref(),
+                    in_flight,
+                    max_in_flight
+                );
+                Err(SagaBusPublishError::AdmissionRejected {
+                    saga_id: context.saga_id,
+                    saga_type: context.saga_type,
+                    admission_key,
+                    in_flight: saturating_u32_from_usize(in_flight),
+                    max_in_flight: saturating_u32_from_usize(max_in_flight),
+                })
+            }
+        }
+    }
+
     pub fn attach_terminal_resolver_for_contract<C: SagaWorkflowContract>(
         &self,
         responder: &'static str,
     ) -> Result<EventSubscription, String> {
-        self.attach_terminal_resolver(C::terminal_policy(), responder)
+        self.attach_terminal_resolver_for_version(
+            C::terminal_policy(),
+            C::workflow_version(),
+            responder,
+        )
     }
 
     pub fn take_terminal_reply(&self, saga_id: SagaId) -> Option<SagaReplyTo> {
@@ -563,17 +1123,15 @@ impl SagaChoreographyBus {
 
     fn saga_start_contract_violation_reason(&self, context: &crate::SagaContext) -> Option<String> {
         let saga_type = context.saga_type.as_ref();
-        let contract = {
-            let contracts = self
-                .workflow_contracts_by_saga_type
-                .lock()
-                .unwrap_or_else(|poisoned| poisoned.into_inner());
-            contracts.get(saga_type).cloned()
-        };
+        let contract =
+            self.with_workflow_contract(saga_type, context.workflow_version, |contract| {
+                contract.clone()
+            });
         let Some(contract) = contract else {
             return Some(format!(
-                "workflow contract is required before saga start; saga_type={} saga_id={}",
+                "workflow contract is required before saga start; saga_type={} workflow_version={} saga_id={}",
                 saga_type,
+                context.workflow_version,
                 context.saga_id.get()
             ));
         };
@@ -612,27 +1170,39 @@ impl SagaChoreographyBus {
         ))
     }
 
-    fn saga_start_expected_min_delivery(&self, saga_type: &str) -> Option<u32> {
+    fn with_workflow_contract<R>(
+        &self,
+        saga_type: &str,
+        workflow_version: u32,
+        f: impl FnOnce(&WorkflowContractState) -> R,
+    ) -> Option<R> {
         let contracts = self
             .workflow_contracts_by_saga_type
             .lock()
             .unwrap_or_else(|poisoned| poisoned.into_inner());
-        let contract = contracts.get(saga_type)?;
-        let required_path_steps = contract.required_path_steps.len();
-        Some(saturating_u32_from_usize(
-            required_path_steps.saturating_add(1),
-        ))
+        contracts
+            .get(saga_type)
+            .and_then(|versions| versions.get(&workflow_version))
+            .map(f)
+    }
+
+    fn saga_start_expected_min_delivery(&self, context: &SagaContext) -> Option<u32> {
+        self.with_workflow_contract(
+            context.saga_type.as_ref(),
+            context.workflow_version,
+            |contract| {
+                saturating_u32_from_usize(contract.required_path_steps.len().saturating_add(1))
+            },
+        )
     }
 
-    fn required_path_description(&self, saga_type: &str) -> String {
-        let contracts = self
-            .workflow_contracts_by_saga_type
-            .lock()
-            .unwrap_or_else(|poisoned| poisoned.into_inner());
-        let Some(contract) = contracts.get(saga_type) else {
-            return String::new();
-        };
-        contract.required_path_description.to_string()
+    fn required_path_description(&self, context: &SagaContext) -> String {
+        self.with_workflow_contract(
+            context.saga_type.as_ref(),
+            context.workflow_version,
+            |contract| contract.required_path_description.to_string(),
+        )
+        .unwrap_or_default()
     }
 
     fn required_path_expected_min_delivery_for_event(
@@ -652,20 +1222,21 @@ impl SagaChoreographyBus {
         if !self.has_terminal_policy_for_saga_type(context.saga_type.as_ref()) {
             return None;
         }
-        let contracts = self
-            .workflow_contracts_by_saga_type
-            .lock()
-            .unwrap_or_else(|poisoned| poisoned.into_inner());
-        let contract = contracts.get(context.saga_type.as_ref())?;
-        if !contract
-            .required_path_steps
-            .contains(context.step_name.as_ref())
-        {
-            return None;
-        }
-        Some(saturating_u32_from_usize(
-            contract.required_path_steps.len().saturating_add(1),
-        ))
+        self.with_workflow_contract(
+            context.saga_type.as_ref(),
+            context.workflow_version,
+            |contract| {
+                contract
+                    .required_path_steps
+                    .contains(context.step_name.as_ref())
+                    .then(|| {
+                        saturating_u32_from_usize(
+                            contract.required_path_steps.len().saturating_add(1),
+                        )
+                    })
+            },
+        )
+        .flatten()
     }
 
     fn store_terminal_reply(&self, saga_id: SagaId, reply: SagaReplyTo) {
@@ -800,6 +1371,10 @@ impl Clone for SagaChoreographyBus {
             terminal_policies_by_saga_type: Arc::clone(&self.terminal_policies_by_saga_type),
             workflow_contracts_by_saga_type: Arc::clone(&self.workflow_contracts_by_saga_type),
             bound_steps_by_saga_type: Arc::clone(&self.bound_steps_by_saga_type),
+            admission_by_saga_type: Arc::clone(&self.admission_by_saga_type),
+            compensation_strategies: Arc::clone(&self.compensation_strategies),
+            observer: Arc::clone(&self.observer),
+            participant_registry: Arc::clone(&self.participant_registry),
             owned: false,
         }
     }
@@ -834,9 +1409,9 @@ mod tests {
     use icanact_core::local_sync;
 
     use crate::{
-        FailureAuthority, SagaChoreographyEvent, SagaContext, SagaId, SagaReplyToResult,
-        SagaTerminalOutcome, SagaWorkflowContract, SagaWorkflowStepContract, SuccessCriteria,
-        TerminalPolicy, WorkflowDependencySpec, TERMINAL_RESOLVER_STEP,
+        DependencySpec, FailureAuthority, SagaChoreographyEvent, SagaContext, SagaId,
+        SagaReplyToResult, SagaTerminalOutcome, SagaWorkflowContract, SagaWorkflowStepContract,
+        SuccessCriteria, TerminalPolicy, WorkflowDependencySpec, TERMINAL_RESOLVER_STEP,
     };
 
     use super::{SagaChoreographyBus, DEFAULT_TERMINAL_RETENTION_LIMIT};
@@ -855,6 +1430,9 @@ mod tests {
             initiator_peer_id: [0; 32],
             saga_started_at_millis: now,
             event_timestamp_millis: now,
+            parent_saga_id: None,
+            workflow_version: crate::DEFAULT_WORKFLOW_VERSION,
+            logical_clock: 1,
         }
     }
 
@@ -938,6 +1516,7 @@ mod tests {
             output: Vec::new(),
             saga_input: Vec::new(),
             compensation_available: false,
+            completion_ratio: None,
         };
         let _ = bus.publish(step.clone());
         let _ = bus.publish(step);
@@ -1058,6 +1637,7 @@ mod tests {
             output: Vec::new(),
             saga_input: Vec::new(),
             compensation_available: false,
+            completion_ratio: None,
         };
         let _ = bus.publish(step);
 
@@ -1207,6 +1787,32 @@ mod tests {
         }
     }
 
+    /// `MultiStepOrderLifecycleContract` registered as version 2 of the
+    /// saga type next to `OrderLifecycleContract`.
+    struct OrderLifecycleContractV2;
+
+    impl SagaWorkflowContract for OrderLifecycleContractV2 {
+        fn saga_type() -> &'static str {
+            "order_lifecycle"
+        }
+
+        fn workflow_version() -> u32 {
+            2
+        }
+
+        fn first_step() -> &'static str {
+            MultiStepOrderLifecycleContract::first_step()
+        }
+
+        fn steps() -> &'static [SagaWorkflowStepContract] {
+            MultiStepOrderLifecycleContract::steps()
+        }
+
+        fn terminal_policy() -> TerminalPolicy {
+            TerminalPolicy::order_lifecycle_default()
+        }
+    }
+
     struct MismatchedPolicySagaTypeContract;
 
     impl SagaWorkflowContract for MismatchedPolicySagaTypeContract {
@@ -1241,6 +1847,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn registered_workflows_render_as_mermaid_and_dot() {
+        let bus = SagaChoreographyBus::new();
+        assert_eq!(
+            bus.workflows_to_dot(),
+            "digraph saga_workflows {\n    rankdir=LR;\n    node [shape=box];\n}\n"
+        );
+        bus.register_workflow_contract_provider::<MultiStepOrderLifecycleContract>()
+            .expect("contract should register");
+
+        let mermaid = bus.workflows_to_mermaid();
+        assert_eq!(mermaid, MultiStepOrderLifecycleContract::to_mermaid());
+        assert!(mermaid.contains("subgraph order_lifecycle__saga[\"order_lifecycle\"]"));
+        assert!(mermaid.contains(" -.->|compensate| "));
+        assert_eq!(
+            bus.workflows_to_dot(),
+            MultiStepOrderLifecycleContract::to_dot()
+        );
+    }
+
     #[test]
     fn workflow_contract_without_resolver_fails_saga_started() {
         let bus = SagaChoreographyBus::new();
@@ -1291,6 +1917,106 @@ mod tests {
         );
     }
 
+    #[test]
+    fn two_workflow_versions_run_side_by_side() {
+        let bus = SagaChoreographyBus::new();
+        bus.register_workflow_contract_provider::<OrderLifecycleContract>()
+            .expect("v1 registration should succeed");
+        bus.register_bound_workflow_step("order_lifecycle", "create_order")
+            .expect("v1 binding should succeed");
+        bus.register_workflow_contract_provider::<OrderLifecycleContractV2>()
+            .expect("v2 registration should keep v1 bindings valid");
+        bus.register_bound_workflow_step("order_lifecycle", "risk_check")
+            .expect("v2 binding should succeed");
+        let _v1 = bus
+            .attach_terminal_resolver_for_contract::<OrderLifecycleContract>("test-resolver")
+            .expect("v1 resolver should attach");
+        let _v2 = bus
+            .attach_terminal_resolver_for_contract::<OrderLifecycleContractV2>("test-resolver")
+            .expect("v2 resolver should attach");
+        let _participant_sub = bus.subscribe_saga_type_fn("order_lifecycle", |_event| true);
+
+        let v1 = SagaId::new(90041);
+        let _ = bus.publish(SagaChoreographyEvent::SagaStarted {
+            context: context("create_order", v1.get()),
+            payload: Vec::new(),
+        });
+        let v2 = SagaId::new(90042);
+        let _ = bus.publish(SagaChoreographyEvent::SagaStarted {
+            context: context("risk_check", v2.get()).with_workflow_version(2),
+            payload: Vec::new(),
+        });
+        assert!(bus.take_terminal_outcome(v1).is_none());
+        assert!(bus.take_terminal_outcome(v2).is_none());
+
+        let v3 = SagaId::new(90043);
+        let _ = bus.publish(SagaChoreographyEvent::SagaStarted {
+            context: context("risk_check", v3.get()).with_workflow_version(3),
+            payload: Vec::new(),
+        });
+        let Some(SagaTerminalOutcome::Failed { reason, .. }) = bus.take_terminal_outcome(v3) else {
+            panic!("expected immediate terminal failure for unregistered version");
+        };
+        assert!(
+            reason.contains("workflow_version=3"),
+            "unexpected reason: {reason}"
+        );
+    }
+
+    #[test]
+    fn concurrency_limit_queues_starts_until_a_saga_terminates() {
+        let bus = SagaChoreographyBus::new();
+        bus.register_workflow_contract_provider::<OrderLifecycleContract>()
+            .expect("workflow contract registration should succeed");
+        bus.register_bound_workflow_step("order_lifecycle", "create_order")
+            .expect("bound workflow step registration should succeed");
+        let _resolver = bus
+            .attach_terminal_resolver_for_contract::<OrderLifecycleContract>("test-resolver")
+            .expect("terminal resolver should attach");
+        let _limit = bus.attach_concurrency_limit(
+            crate::SagaConcurrencyLimit::new("order_lifecycle", 1)
+                .with_overflow(crate::SagaAdmissionOverflow::Queue { max_queued: 1 }),
+        );
+        let started = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = Arc::clone(&started);
+        let _participant_sub = bus.subscribe_saga_type_fn("order_lifecycle", move |event| {
+            if let SagaChoreographyEvent::SagaStarted { context, .. } = event {
+                seen.lock().unwrap().push(context.saga_id.get());
+            }
+            true
+        });
+
+        assert!(matches!(
+            bus.start_saga(context("create_order", 9101), Vec::new()),
+            Ok(crate::SagaAdmission::Started { .. })
+        ));
+        assert_eq!(
+            bus.start_saga(context("create_order", 9102), Vec::new()),
+            Ok(crate::SagaAdmission::Queued { position: 0 })
+        );
+        let Err(super::SagaBusPublishError::AdmissionRejected {
+            saga_id,
+            in_flight,
+            max_in_flight,
+            ..
+        }) = bus.start_saga(context("create_order", 9103), Vec::new())
+        else {
+            panic!("start beyond the queue should be rejected");
+        };
+        assert_eq!(saga_id, SagaId::new(9103));
+        assert_eq!((in_flight, max_in_flight), (1, 1));
+        assert_eq!(*started.lock().unwrap(), vec![9101]);
+
+        let _ = bus.publish(SagaChoreographyEvent::SagaFailed {
+            context: context("create_order", 9101),
+            reason: "cancelled".into(),
+            failure: None,
+        });
+
+        assert_eq!(*started.lock().unwrap(), vec![9101, 9102]);
+        assert_eq!(bus.saga_admission_counts("order_lifecycle"), Some((1, 0)));
+    }
+
     #[test]
     fn saga_started_with_first_step_mismatch_fails_immediately() {
         let bus = SagaChoreographyBus::new();
@@ -1432,6 +2158,7 @@ mod tests {
             output: Vec::new(),
             saga_input: Vec::new(),
             compensation_available: false,
+            completion_ratio: None,
         });
 
         let Some(SagaTerminalOutcome::Failed { reason, .. }) = bus.take_terminal_outcome(saga_id)
@@ -1477,6 +2204,7 @@ mod tests {
                 output: Vec::new(),
                 saga_input: Vec::new(),
                 compensation_available: false,
+                completion_ratio: None,
             })
             .expect_err("strict publish should report required path shortfall");
         let super::SagaBusPublishError::RequiredPathDeliveryShortfall {
@@ -1677,4 +2405,68 @@ mod tests {
             "expected stalled_timeout reason, got: {reason}"
         );
     }
+
+    #[test]
+    fn step_references_are_validated_against_registered_contracts() {
+        let bus = SagaChoreographyBus::new();
+        bus.register_workflow_contract_provider::<MultiStepOrderLifecycleContract>()
+            .expect("contract should register");
+
+        bus.validate_step_references(
+            &["order_lifecycle"],
+            "create_order",
+            &DependencySpec::after("risk_check"),
+        )
+        .expect("declared steps should validate");
+        let err = bus
+            .validate_step_references(
+                &["order_lifecycle"],
+                "create_order",
+                &DependencySpec::after("risk_chek"),
+            )
+            .expect_err("misspelled dependency should be rejected");
+        assert!(
+            err.contains("unknown_steps=risk_chek"),
+            "unexpected error: {err}"
+        );
+        let err = bus
+            .validate_step_references(
+                &["order_lifecyle"],
+                "create_order",
+                &DependencySpec::OnSagaStart,
+            )
+            .expect_err("unknown saga type should be rejected");
+        assert!(
+            err.contains("saga_type=order_lifecyle"),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[test]
+    fn compensation_strategy_chooses_steps_from_the_registered_contract() {
+        let bus = SagaChoreographyBus::new();
+        bus.register_workflow_contract_provider::<MultiStepOrderLifecycleContract>()
+            .expect("contract should register");
+        let context = crate::DeterministicContextBuilder::default().build();
+        let completed = [
+            crate::StepName::from("risk_check"),
+            crate::StepName::from("audit_trail"),
+        ];
+
+        assert_eq!(
+            bus.steps_to_compensate(&context, "create_order", &completed),
+            vec![
+                crate::StepName::from("audit_trail"),
+                crate::StepName::from("risk_check"),
+            ]
+        );
+        bus.set_compensation_strategy(
+            "order_lifecycle",
+            crate::CompensationStrategy::CompensateUpstreamOnly,
+        );
+        assert_eq!(
+            bus.steps_to_compensate(&context, "create_order", &completed),
+            vec![crate::StepName::from("risk_check")]
+        );
+    }
 }
@@ -0,0 +1,163 @@
+//! Logical time for events exchanged between peers.
+//!
+//! Wall clocks of different peers disagree, so `event_timestamp_millis`
+//! cannot order a saga's events once they come from more than one process.
+//! Every [`SagaContext`] therefore carries a Lamport timestamp in
+//! `logical_clock`: a saga starts at 1, each derived context is one past the
+//! context it was derived from, and each participant's [`LamportClock`]
+//! advances past every event it receives before stamping the events it
+//! emits. The participant helpers do both; an event is thus always later
+//! than the events it causally follows.
+//!
+//! [`merge_saga_histories`] interleaves the histories journaled by different
+//! peers in that order, and [`verify_causality`] checks a history against
+//! the causation links of its contexts.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::{SagaChoreographyEvent, SagaContext};
+
+/// A participant's Lamport clock. Clones share the same counter.
+#[derive(Clone, Debug, Default)]
+pub struct LamportClock(Arc<AtomicU64>);
+
+impl LamportClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The last timestamp seen or issued.
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Acquire)
+    }
+
+    /// Advances the clock to at least `logical_clock`, e.g. that of a
+    /// received event.
+    pub fn observe(&self, logical_clock: u64) {
+        self.0.fetch_max(logical_clock, Ordering::AcqRel);
+    }
+
+    /// Stamps `context` with a timestamp past everything this clock has
+    /// seen, keeping its own if that is later.
+    pub fn stamp(&self, context: &mut SagaContext) {
+        let floor = context.logical_clock;
+        let previous = self
+            .0
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |local| {
+                Some((local + 1).max(floor))
+            })
+            .unwrap_or_else(|local| local);
+        context.logical_clock = (previous + 1).max(floor);
+    }
+}
+
+/// Merges the histories of one saga as journaled by different peers into a
+/// single history in logical order. Events present in several histories
+/// are kept once.
+pub fn merge_saga_histories<I>(histories: I) -> Vec<SagaChoreographyEvent>
+where
+    I: IntoIterator<Item = Vec<SagaChoreographyEvent>>,
+{
+    let mut merged: Vec<SagaChoreographyEvent> = histories.into_iter().flatten().collect();
+    merged.sort_by_key(|event| {
+        let context = event.context();
+        (context.logical_clock, context.trace_id, event.event_type())
+    });
+    merged.dedup_by(|a, b| {
+        a.event_type() == b.event_type()
+            && a.context().trace_id == b.context().trace_id
+            && a.context().logical_clock == b.context().logical_clock
+    });
+    merged
+}
+
+/// An event that is not logically later than its cause.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+#[error(
+    "event {trace_id} at logical clock {logical_clock} does not follow its cause {causation_id} at {cause_logical_clock}"
+)]
+pub struct CausalityViolation {
+    pub trace_id: u64,
+    pub causation_id: u64,
+    pub logical_clock: u64,
+    pub cause_logical_clock: u64,
+}
+
+/// Checks that every event of `history` is logically later than the first
+/// event carrying its cause's trace id. Causes outside `history` are not
+/// checked.
+pub fn verify_causality(history: &[SagaChoreographyEvent]) -> Result<(), CausalityViolation> {
+    let mut first_seen: HashMap<u64, u64> = HashMap::new();
+    for event in history {
+        let context = event.context();
+        first_seen
+            .entry(context.trace_id)
+            .and_modify(|clock| *clock = (*clock).min(context.logical_clock))
+            .or_insert(context.logical_clock);
+    }
+    for event in history {
+        let context = event.context();
+        if context.causation_id == 0 || context.causation_id == context.trace_id {
+            continue;
+        }
+        let Some(&cause_logical_clock) = first_seen.get(&context.causation_id) else {
+            continue;
+        };
+        if context.logical_clock <= cause_logical_clock {
+            return Err(CausalityViolation {
+                trace_id: context.trace_id,
+                causation_id: context.causation_id,
+                logical_clock: context.logical_clock,
+                cause_logical_clock,
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{saga_started, step_completed, DeterministicContextBuilder};
+
+    #[test]
+    fn merged_histories_follow_causality_despite_skewed_wall_clocks() {
+        let start = DeterministicContextBuilder::default().build();
+        let reserve = start.next_step("reserve_funds".into());
+        let mut place = reserve.next_step("place_order".into());
+        // The second peer's wall clock runs a minute behind the first.
+        place.event_timestamp_millis = reserve.event_timestamp_millis - 60_000;
+
+        let receiver = LamportClock::new();
+        receiver.observe(reserve.logical_clock + 5);
+        receiver.stamp(&mut place);
+        assert_eq!(place.logical_clock, reserve.logical_clock + 6);
+
+        let peer_a = vec![
+            saga_started(start.clone(), Vec::new()),
+            step_completed(reserve.clone(), Vec::new(), Vec::new(), false),
+        ];
+        let peer_b = vec![
+            step_completed(reserve.clone(), Vec::new(), Vec::new(), false),
+            step_completed(place.clone(), Vec::new(), Vec::new(), false),
+        ];
+        let merged = merge_saga_histories([peer_b, peer_a]);
+        let steps: Vec<&str> = merged
+            .iter()
+            .map(|event| event.context().step_name.as_ref())
+            .collect();
+        assert_eq!(steps, ["risk_check", "reserve_funds", "place_order"]);
+        assert_eq!(verify_causality(&merged), Ok(()));
+
+        let mut stale = place;
+        stale.logical_clock = reserve.logical_clock;
+        let history = [
+            step_completed(reserve.clone(), Vec::new(), Vec::new(), false),
+            step_completed(stale, Vec::new(), Vec::new(), false),
+        ];
+        let violation = verify_causality(&history).unwrap_err();
+        assert_eq!(violation.causation_id, reserve.trace_id);
+    }
+}
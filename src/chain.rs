@@ -0,0 +1,256 @@
+//! Saga chaining: start one saga type when another completes.
+//!
+//! A [`SagaChain`] watches the source saga type on the bus, keeps the saga
+//! input and step outputs of every running source saga, and on
+//! `SagaCompleted` maps them into the payload of a new `SagaStarted` for the
+//! target saga type. The started saga carries the source saga as
+//! [`SagaContext::parent_saga_id`] and shares its correlation id. Its
+//! participants hold its completion as they do a sub-saga's (see
+//! [`crate::SubSagaStep`]); the attached chain releases it straight away,
+//! the source saga having completed before it started.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+use crate::{SagaChoreographyEvent, SagaContext, SagaId, SagaType, StepName};
+
+/// What a chain mapper sees of the completed source saga.
+#[derive(Debug)]
+pub struct SagaChainInput<'a> {
+    pub context: &'a SagaContext,
+    /// Payload of the source `SagaStarted`.
+    pub saga_input: &'a [u8],
+    /// Output of every completed source step, keyed by step name.
+    pub step_outputs: &'a HashMap<StepName, Vec<u8>>,
+}
+
+type PayloadMapper = dyn Fn(&SagaChainInput<'_>) -> Option<Vec<u8>> + Send + Sync;
+type ChainedSagaIdFn = dyn Fn(&SagaContext) -> SagaId + Send + Sync;
+
+const CHAIN_STARTED_RETENTION: usize = 4096;
+
+/// Registration for "on `SagaCompleted` of `source`, start `target`".
+#[derive(Clone)]
+pub struct SagaChain {
+    pub source_saga_type: SagaType,
+    pub target_saga_type: SagaType,
+    /// First step of the target workflow contract.
+    pub target_first_step: StepName,
+    map_payload: Arc<PayloadMapper>,
+    allocate_saga_id: Arc<ChainedSagaIdFn>,
+}
+
+impl SagaChain {
+    /// `map_payload` returns the target saga payload, or `None` to skip
+    /// chaining for this particular completion.
+    pub fn new<F>(
+        source_saga_type: impl Into<SagaType>,
+        target_saga_type: impl Into<SagaType>,
+        target_first_step: impl Into<StepName>,
+        map_payload: F,
+    ) -> Self
+    where
+        F: Fn(&SagaChainInput<'_>) -> Option<Vec<u8>> + Send + Sync + 'static,
+    {
+        let target_saga_type = target_saga_type.into();
+        let discriminator = target_saga_type.clone();
+        Self {
+            source_saga_type: source_saga_type.into(),
+            target_saga_type,
+            target_first_step: target_first_step.into(),
+            map_payload: Arc::new(map_payload),
+            allocate_saga_id: Arc::new(move |parent| {
+                derived_saga_id(parent.saga_id, &discriminator)
+            }),
+        }
+    }
+
+    /// Overrides how chained saga ids are allocated. The default derives the
+    /// id from the parent saga id and the target saga type, so replays of the
+    /// same completion map to the same chained saga.
+    pub fn with_saga_id_allocator<F>(mut self, allocate: F) -> Self
+    where
+        F: Fn(&SagaContext) -> SagaId + Send + Sync + 'static,
+    {
+        self.allocate_saga_id = Arc::new(allocate);
+        self
+    }
+
+    pub(crate) fn tracker(&self) -> SagaChainTracker {
+        SagaChainTracker {
+            chain: self.clone(),
+            running: HashMap::new(),
+            started: HashSet::new(),
+            started_order: VecDeque::new(),
+        }
+    }
+}
+
+impl std::fmt::Debug for SagaChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SagaChain")
+            .field("source_saga_type", &self.source_saga_type)
+            .field("target_saga_type", &self.target_saga_type)
+            .field("target_first_step", &self.target_first_step)
+            .finish()
+    }
+}
+
+#[derive(Default)]
+struct RunningSourceSaga {
+    saga_input: Vec<u8>,
+    step_outputs: HashMap<StepName, Vec<u8>>,
+}
+
+/// Per-bus state of an attached [`SagaChain`].
+pub(crate) struct SagaChainTracker {
+    chain: SagaChain,
+    running: HashMap<SagaId, RunningSourceSaga>,
+    started: HashSet<SagaId>,
+    started_order: VecDeque<SagaId>,
+}
+
+impl SagaChainTracker {
+    /// Feeds one source saga event and returns the `SagaStarted` to publish
+    /// when it completes the source saga.
+    pub(crate) fn ingest(
+        &mut self,
+        event: &SagaChoreographyEvent,
+    ) -> Option<SagaChoreographyEvent> {
+        let context = event.context();
+        if context.saga_type != self.chain.source_saga_type {
+            return None;
+        }
+        let saga_id = context.saga_id;
+        match event {
+            SagaChoreographyEvent::SagaStarted { payload, .. } => {
+                self.running.insert(
+                    saga_id,
+                    RunningSourceSaga {
+                        saga_input: payload.clone(),
+                        step_outputs: HashMap::new(),
+                    },
+                );
+                None
+            }
+            SagaChoreographyEvent::StepCompleted { output, .. } => {
+                self.running
+                    .entry(saga_id)
+                    .or_default()
+                    .step_outputs
+                    .insert(context.step_name.clone(), output.clone());
+                None
+            }
+            SagaChoreographyEvent::SagaCompleted { .. } => {
+                let running = self.running.remove(&saga_id).unwrap_or_default();
+                if !self.mark_started(saga_id) {
+                    return None;
+                }
+                let input = SagaChainInput {
+                    context,
+                    saga_input: &running.saga_input,
+                    step_outputs: &running.step_outputs,
+                };
+                let payload = (self.chain.map_payload)(&input)?;
+                let chained = context.chained(
+                    (self.chain.allocate_saga_id)(context),
+                    self.chain.target_saga_type.clone(),
+                    self.chain.target_first_step.clone(),
+                );
+                Some(SagaChoreographyEvent::SagaStarted {
+                    context: chained,
+                    payload,
+                })
+            }
+            SagaChoreographyEvent::SagaFailed { .. }
+            | SagaChoreographyEvent::SagaQuarantined { .. } => {
+                self.running.remove(&saga_id);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn mark_started(&mut self, saga_id: SagaId) -> bool {
+        if !self.started.insert(saga_id) {
+            return false;
+        }
+        self.started_order.push_back(saga_id);
+        while self.started_order.len() > CHAIN_STARTED_RETENTION {
+            if let Some(evicted) = self.started_order.pop_front() {
+                self.started.remove(&evicted);
+            }
+        }
+        true
+    }
+}
+
+/// Derives a saga id from a parent saga id, stable across processes so a
+/// replayed trigger maps to the same derived saga.
+pub(crate) fn derived_saga_id(parent: SagaId, discriminator: &str) -> SagaId {
+    // FNV-1a: stable across processes, unlike `DefaultHasher`.
+    let salt = discriminator
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    SagaId::new(parent.get().rotate_left(17) ^ salt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DeterministicContextBuilder;
+
+    fn ctx(step: &str) -> SagaContext {
+        DeterministicContextBuilder::default()
+            .with_saga_id(11)
+            .with_saga_type("order")
+            .with_step_name(step)
+            .build()
+    }
+
+    #[test]
+    fn completion_starts_linked_target_saga_once() {
+        let chain = SagaChain::new("order", "shipping", "book_carrier", |input| {
+            let mut payload = input.saga_input.to_vec();
+            payload.extend(input.step_outputs.get("charge_payment")?);
+            Some(payload)
+        });
+        let mut tracker = chain.tracker();
+
+        let started = SagaChoreographyEvent::SagaStarted {
+            context: ctx("reserve"),
+            payload: vec![1],
+        };
+        let step = SagaChoreographyEvent::StepCompleted {
+            context: ctx("charge_payment"),
+            output: vec![2],
+            saga_input: vec![1],
+            compensation_available: true,
+            completion_ratio: None,
+        };
+        let completed = SagaChoreographyEvent::SagaCompleted {
+            context: ctx("terminal_resolver"),
+        };
+        assert!(tracker.ingest(&started).is_none());
+        assert!(tracker.ingest(&step).is_none());
+
+        let Some(SagaChoreographyEvent::SagaStarted { context, payload }) =
+            tracker.ingest(&completed)
+        else {
+            panic!("completion should start the chained saga");
+        };
+        assert_eq!(payload, vec![1, 2]);
+        assert_eq!(context.saga_type.as_ref(), "shipping");
+        assert_eq!(context.step_name.as_ref(), "book_carrier");
+        assert_eq!(context.parent_saga_id, Some(SagaId::new(11)));
+        assert_eq!(context.correlation_id, ctx("reserve").correlation_id);
+        assert_ne!(context.saga_id, SagaId::new(11));
+
+        assert!(
+            tracker.ingest(&completed).is_none(),
+            "replayed completion must not start a second chained saga"
+        );
+    }
+}
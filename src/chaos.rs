@@ -0,0 +1,441 @@
+//! Seeded delivery chaos for [`SagaTestHarness`].
+//!
+//! A [`ChaosPolicy`] makes the harness drop, duplicate, delay or reorder
+//! events between publish and delivery, driven by a seeded generator so any
+//! failing run can be replayed from its seed. [`run_chaos`] runs a workflow
+//! scenario many times under a policy and checks eventual consistency: every
+//! saga reaches a terminal outcome and completed or failed sagas hold no
+//! resource locks.
+//!
+//! Saga start and terminal events are never dropped (they may still be
+//! duplicated, delayed or reordered). Losing those is covered by the
+//! initiator's and resolver's own retries rather than by participant
+//! choreography.
+
+use std::time::Duration;
+
+use crate::{SagaChoreographyEvent, SagaId, SagaTerminalOutcome, SagaTestHarness};
+
+/// Probabilities of each delivery fault, applied per published event.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChaosPolicy {
+    pub seed: u64,
+    pub drop_probability: f64,
+    pub duplicate_probability: f64,
+    pub delay_probability: f64,
+    pub reorder_probability: f64,
+    /// Upper bound of a delay; delayed events are released by
+    /// [`SagaTestHarness::advance_time`].
+    pub max_delay: Duration,
+    /// How much mock time [`run_chaos`] lets each iteration settle for.
+    pub settle_timeout: Duration,
+    /// Mock-time step [`run_chaos`] advances by while settling.
+    pub settle_tick: Duration,
+}
+
+impl ChaosPolicy {
+    /// A policy that injects nothing until probabilities are set.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            drop_probability: 0.0,
+            duplicate_probability: 0.0,
+            delay_probability: 0.0,
+            reorder_probability: 0.0,
+            max_delay: Duration::from_millis(500),
+            settle_timeout: Duration::from_secs(600),
+            settle_tick: Duration::from_secs(1),
+        }
+    }
+
+    pub fn with_drop_probability(mut self, probability: f64) -> Self {
+        self.drop_probability = probability;
+        self
+    }
+
+    pub fn with_duplicate_probability(mut self, probability: f64) -> Self {
+        self.duplicate_probability = probability;
+        self
+    }
+
+    pub fn with_delay_probability(mut self, probability: f64, max_delay: Duration) -> Self {
+        self.delay_probability = probability;
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub fn with_reorder_probability(mut self, probability: f64) -> Self {
+        self.reorder_probability = probability;
+        self
+    }
+
+    pub fn with_settle(mut self, timeout: Duration, tick: Duration) -> Self {
+        self.settle_timeout = timeout;
+        self.settle_tick = tick;
+        self
+    }
+
+    fn reseeded(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+/// Fault counts of one harness.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChaosStats {
+    pub dropped: u64,
+    pub duplicated: u64,
+    pub delayed: u64,
+    pub reordered: u64,
+}
+
+impl ChaosStats {
+    fn add(&mut self, other: ChaosStats) {
+        self.dropped += other.dropped;
+        self.duplicated += other.duplicated;
+        self.delayed += other.delayed;
+        self.reordered += other.reordered;
+    }
+}
+
+pub(crate) enum ChaosAction {
+    Deliver,
+    Drop,
+    Duplicate,
+    Delay { millis: u64 },
+    Reorder { position: u64 },
+}
+
+/// Per-harness chaos state: the policy and its generator.
+pub(crate) struct ChaosState {
+    policy: ChaosPolicy,
+    rng: u64,
+    pub(crate) stats: ChaosStats,
+}
+
+impl ChaosState {
+    pub(crate) fn new(policy: ChaosPolicy) -> Self {
+        Self {
+            policy,
+            rng: policy.seed,
+            stats: ChaosStats::default(),
+        }
+    }
+
+    pub(crate) fn decide(&mut self, event: &SagaChoreographyEvent) -> ChaosAction {
+        let droppable = !matches!(event, SagaChoreographyEvent::SagaStarted { .. })
+            && event.terminal_outcome().is_none();
+        let roll = self.next_unit();
+        let policy = self.policy;
+        let mut threshold = if droppable {
+            policy.drop_probability
+        } else {
+            0.0
+        };
+        if roll < threshold {
+            self.stats.dropped += 1;
+            return ChaosAction::Drop;
+        }
+        threshold += policy.duplicate_probability;
+        if roll < threshold {
+            self.stats.duplicated += 1;
+            return ChaosAction::Duplicate;
+        }
+        threshold += policy.delay_probability;
+        if roll < threshold {
+            self.stats.delayed += 1;
+            let max = policy.max_delay.as_millis().max(1) as u64;
+            return ChaosAction::Delay {
+                millis: 1 + self.next_u64() % max,
+            };
+        }
+        threshold += policy.reorder_probability;
+        if roll < threshold {
+            self.stats.reordered += 1;
+            return ChaosAction::Reorder {
+                position: self.next_u64(),
+            };
+        }
+        ChaosAction::Deliver
+    }
+
+    // SplitMix64: tiny, seedable and good enough for fault scheduling.
+    fn next_u64(&mut self) -> u64 {
+        self.rng = self.rng.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn next_unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChaosViolationKind {
+    /// The saga had no terminal outcome once the settle timeout passed.
+    NotTerminal,
+    /// The saga completed or failed but still holds these resource locks.
+    OrphanedLocks { resources: Vec<Box<str>> },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChaosViolation {
+    pub iteration: u32,
+    /// Seed that reproduces the iteration with the same policy.
+    pub seed: u64,
+    pub saga_id: SagaId,
+    pub kind: ChaosViolationKind,
+}
+
+/// Aggregate result of [`run_chaos`].
+#[derive(Clone, Debug, Default)]
+pub struct ChaosReport {
+    pub iterations: u32,
+    pub sagas: usize,
+    pub completed: usize,
+    pub failed: usize,
+    pub quarantined: usize,
+    pub faults: ChaosStats,
+    pub violations: Vec<ChaosViolation>,
+}
+
+impl ChaosReport {
+    /// # Panics
+    ///
+    /// Panics listing the first violations and their seeds if any saga did
+    /// not end consistently.
+    #[track_caller]
+    pub fn assert_eventually_consistent(&self) {
+        if self.violations.is_empty() {
+            return;
+        }
+        let shown: Vec<String> = self
+            .violations
+            .iter()
+            .take(5)
+            .map(|violation| {
+                format!(
+                    "iteration {} (seed {}) saga {}: {:?}",
+                    violation.iteration,
+                    violation.seed,
+                    violation.saga_id.get(),
+                    violation.kind
+                )
+            })
+            .collect();
+        panic!(
+            "{} of {} sagas did not end consistently under chaos:\n{}",
+            self.violations.len(),
+            self.sagas,
+            shown.join("\n")
+        );
+    }
+}
+
+/// Runs `scenario` `iterations` times, each on a fresh harness under
+/// `policy` reseeded per iteration, and checks eventual consistency.
+///
+/// `scenario` wires participants, terminal policies and optionally a
+/// [`ResourceLockManager`](crate::ResourceLockManager) into the harness, starts
+/// its sagas and returns their ids. Each iteration then advances mock time by
+/// `policy.settle_tick` until every saga is terminal and no delayed event is
+/// pending, or `policy.settle_timeout` passes.
+pub fn run_chaos<F>(iterations: u32, policy: ChaosPolicy, mut scenario: F) -> ChaosReport
+where
+    F: FnMut(&mut SagaTestHarness) -> Vec<SagaId>,
+{
+    let mut report = ChaosReport {
+        iterations,
+        ..ChaosReport::default()
+    };
+    let settle_tick = policy.settle_tick.as_millis().max(1) as u64;
+    let settle_timeout = policy.settle_timeout.as_millis() as u64;
+    for iteration in 0..iterations {
+        let seed = policy.seed.wrapping_add(u64::from(iteration));
+        let mut harness = SagaTestHarness::new();
+        harness.with_chaos(policy.reseeded(seed));
+        let saga_ids = scenario(&mut harness);
+        harness.drive_until_quiescent();
+
+        let mut settled_for = 0;
+        while settled_for < settle_timeout
+            && (harness.pending_delayed() > 0
+                || saga_ids
+                    .iter()
+                    .any(|saga_id| harness.terminal_outcome(*saga_id).is_none()))
+        {
+            harness.advance_time(settle_tick);
+            settled_for += settle_tick;
+        }
+
+        report.sagas += saga_ids.len();
+        report.faults.add(harness.chaos_stats());
+        for saga_id in saga_ids {
+            let outcome = harness.terminal_outcome(saga_id);
+            match &outcome {
+                Some(SagaTerminalOutcome::Completed { .. }) => report.completed += 1,
+                Some(SagaTerminalOutcome::Failed { .. }) => report.failed += 1,
+                Some(SagaTerminalOutcome::Quarantined { .. }) => report.quarantined += 1,
+                None => report.violations.push(ChaosViolation {
+                    iteration,
+                    seed,
+                    saga_id,
+                    kind: ChaosViolationKind::NotTerminal,
+                }),
+            }
+            let released = matches!(
+                outcome,
+                Some(SagaTerminalOutcome::Completed { .. } | SagaTerminalOutcome::Failed { .. })
+            );
+            if let (true, Some(locks)) = (released, harness.resource_locks()) {
+                let resources = locks.held_by(saga_id);
+                if !resources.is_empty() {
+                    report.violations.push(ChaosViolation {
+                        iteration,
+                        seed,
+                        saga_id,
+                        kind: ChaosViolationKind::OrphanedLocks { resources },
+                    });
+                }
+            }
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::{
+        CompensationError, DependencySpec, FailureAuthority, HasSagaParticipantSupport,
+        InMemoryDedupe, InMemoryJournal, ResourceLockManager, SagaContext, SagaParticipant,
+        SagaParticipantSupport, StepError, StepOutput, SuccessCriteria, TerminalPolicy,
+    };
+
+    struct Step {
+        saga: SagaParticipantSupport<InMemoryJournal, InMemoryDedupe>,
+        name: &'static str,
+        depends_on: DependencySpec,
+        locks: ResourceLockManager,
+    }
+
+    impl HasSagaParticipantSupport for Step {
+        type Journal = InMemoryJournal;
+        type Dedupe = InMemoryDedupe;
+
+        fn saga_support(&self) -> &SagaParticipantSupport<Self::Journal, Self::Dedupe> {
+            &self.saga
+        }
+
+        fn saga_support_mut(&mut self) -> &mut SagaParticipantSupport<Self::Journal, Self::Dedupe> {
+            &mut self.saga
+        }
+    }
+
+    impl SagaParticipant for Step {
+        type Error = String;
+
+        fn step_name(&self) -> &str {
+            self.name
+        }
+
+        fn saga_types(&self) -> &[&'static str] {
+            &["order_lifecycle"]
+        }
+
+        fn depends_on(&self) -> DependencySpec {
+            self.depends_on.clone()
+        }
+
+        fn execute_step(
+            &mut self,
+            context: &SagaContext,
+            _input: &[u8],
+        ) -> Result<StepOutput, StepError> {
+            let account = format!("account:{}", context.saga_id.get());
+            self.locks
+                .try_acquire(context, &account)
+                .map_err(|err| StepError::terminal(err.to_string()))?;
+            Ok(StepOutput::Completed {
+                output: Vec::new(),
+                compensation_data: Vec::new(),
+            })
+        }
+
+        fn compensate_step(
+            &mut self,
+            _context: &SagaContext,
+            _compensation_data: &[u8],
+        ) -> Result<(), CompensationError> {
+            Ok(())
+        }
+    }
+
+    fn scenario(harness: &mut SagaTestHarness) -> Vec<SagaId> {
+        let locks = ResourceLockManager::new();
+        let required: HashSet<Box<str>> = ["risk_check".into(), "place_order".into()].into();
+        harness
+            .with_resource_locks(locks.clone())
+            .with_terminal_policy(TerminalPolicy::new(
+                "order_lifecycle".into(),
+                "order_lifecycle/chaos".into(),
+                FailureAuthority::AnyParticipant,
+                SuccessCriteria::AllOf(required),
+                Duration::from_secs(60),
+                Duration::from_secs(5),
+                &[],
+            ));
+        for (name, depends_on) in [
+            ("risk_check", DependencySpec::OnSagaStart),
+            ("place_order", DependencySpec::after("risk_check")),
+        ] {
+            harness.add_participant(Step {
+                saga: SagaParticipantSupport::new(InMemoryJournal::new(), InMemoryDedupe::new()),
+                name,
+                depends_on,
+                locks: locks.clone(),
+            });
+        }
+        (0..3)
+            .map(|_| harness.start_saga("order_lifecycle", "risk_check", Vec::new()))
+            .collect()
+    }
+
+    #[test]
+    fn workflow_is_eventually_consistent_under_chaos() {
+        let policy = ChaosPolicy::new(7)
+            .with_drop_probability(0.1)
+            .with_duplicate_probability(0.1)
+            .with_delay_probability(0.1, Duration::from_secs(2))
+            .with_reorder_probability(0.1);
+        let report = run_chaos(200, policy, scenario);
+
+        report.assert_eventually_consistent();
+        assert_eq!(report.sagas, 600);
+        assert_eq!(report.completed + report.failed, 600);
+        assert!(report.completed > 0 && report.failed > 0, "{report:?}");
+        assert!(report.faults.dropped > 0 && report.faults.duplicated > 0);
+        assert!(report.faults.delayed > 0 && report.faults.reordered > 0);
+    }
+
+    #[test]
+    fn same_seed_replays_the_same_faults() {
+        let policy = ChaosPolicy::new(42)
+            .with_drop_probability(0.2)
+            .with_delay_probability(0.2, Duration::from_millis(300));
+        let first = run_chaos(10, policy, scenario);
+        let second = run_chaos(10, policy, scenario);
+        assert_eq!(first.faults, second.faults);
+        assert_eq!(first.completed, second.completed);
+        assert_eq!(
+            run_chaos(1, ChaosPolicy::new(1), scenario).faults,
+            ChaosStats::default()
+        );
+    }
+}
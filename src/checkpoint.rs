@@ -0,0 +1,166 @@
+//! Checkpoints inside multi-phase steps.
+//!
+//! A step that submits an order and then waits for its confirmation should
+//! not submit again when it is re-run after a crash between the two phases.
+//! [`checkpoint`] journals each finished phase with what the rest of the step
+//! needs, and [`resume_from_checkpoint`] hands the latest one to the re-run:
+//!
+//! ```ignore
+//! fn execute_step(&mut self, ctx: &SagaContext, input: &[u8]) -> Result<StepOutput, StepError> {
+//!     let order_id = match resume_from_checkpoint(self, ctx)? {
+//!         Some(resumed) if &*resumed.phase == "submitted" => OrderId::from_bytes(&resumed.data),
+//!         _ => {
+//!             let order_id = self.exchange.submit(input)?;
+//!             checkpoint(self, ctx, "submitted", order_id.as_bytes())?;
+//!             order_id
+//!         }
+//!     };
+//!     let fill = self.exchange.await_fill(&order_id)?;
+//!     Ok(StepOutput::Completed { output: fill.encode(), compensation_data: order_id.into() })
+//! }
+//! ```
+//!
+//! Checkpoints live in the participant journal and are pruned with it. They
+//! do not count as progress, so recovery still resumes the step itself, and
+//! the step's completion retires them.
+
+use crate::{
+    CheckpointRecord, JournalError, ParticipantEvent, ParticipantJournal, SagaContext, SagaStateExt,
+};
+
+/// Journals that the running step of `context`'s saga finished `phase`,
+/// with `data` for the phases after it.
+pub fn checkpoint<P>(
+    participant: &P,
+    context: &SagaContext,
+    phase: &str,
+    data: &[u8],
+) -> Result<(), JournalError>
+where
+    P: SagaStateExt,
+{
+    let saga_id = context.saga_id;
+    participant.saga_journal().append(
+        saga_id,
+        ParticipantEvent::StepCheckpointed {
+            phase: phase.into(),
+            data: data.to_vec(),
+            checkpointed_at_millis: participant.now_millis(),
+        },
+    )?;
+    tracing::debug!(
+        target: "core::saga",
+        event = "saga_step_checkpointed",
+        saga_id = saga_id.get(),
+        phase
+    );
+    Ok(())
+}
+
+/// Latest checkpoint of the step's current execution in `context`'s saga;
+/// `None` when the step has not checkpointed since it last completed.
+pub fn resume_from_checkpoint<P>(
+    participant: &P,
+    context: &SagaContext,
+) -> Result<Option<CheckpointRecord>, JournalError>
+where
+    P: SagaStateExt,
+{
+    let mut latest = None;
+    for entry in participant.saga_journal().read(context.saga_id)? {
+        match entry.event {
+            ParticipantEvent::StepCheckpointed {
+                phase,
+                data,
+                checkpointed_at_millis,
+            } => {
+                latest = Some(CheckpointRecord {
+                    phase,
+                    data,
+                    checkpointed_at_millis,
+                });
+            }
+            ParticipantEvent::StepExecutionCompleted { .. } => latest = None,
+            _ => {}
+        }
+    }
+    Ok(latest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        DeterministicContextBuilder, HasSagaParticipantSupport, InMemoryDedupe, InMemoryJournal,
+        SagaParticipantSupport,
+    };
+
+    struct Participant {
+        saga: SagaParticipantSupport<InMemoryJournal, InMemoryDedupe>,
+    }
+
+    impl HasSagaParticipantSupport for Participant {
+        type Journal = InMemoryJournal;
+        type Dedupe = InMemoryDedupe;
+
+        fn saga_support(&self) -> &SagaParticipantSupport<Self::Journal, Self::Dedupe> {
+            &self.saga
+        }
+
+        fn saga_support_mut(&mut self) -> &mut SagaParticipantSupport<Self::Journal, Self::Dedupe> {
+            &mut self.saga
+        }
+    }
+
+    fn started(attempt: u32) -> ParticipantEvent {
+        ParticipantEvent::StepExecutionStarted {
+            attempt,
+            started_at_millis: 1,
+        }
+    }
+
+    #[test]
+    fn rerun_resumes_from_latest_checkpoint_until_the_step_completes() {
+        let participant = Participant {
+            saga: SagaParticipantSupport::new(InMemoryJournal::new(), InMemoryDedupe::new()),
+        };
+        let context = DeterministicContextBuilder::default().build();
+        let saga_id = context.saga_id;
+        participant.record_event(saga_id, started(1));
+        assert_eq!(
+            resume_from_checkpoint(&participant, &context).unwrap(),
+            None
+        );
+
+        checkpoint(&participant, &context, "submitted", b"order-17").unwrap();
+        checkpoint(&participant, &context, "acknowledged", b"order-17").unwrap();
+        // Crash; the redelivered event starts the step again.
+        participant.record_event(saga_id, started(1));
+
+        let resumed = resume_from_checkpoint(&participant, &context)
+            .unwrap()
+            .expect("checkpoint should survive the re-run");
+        assert_eq!(&*resumed.phase, "acknowledged");
+        assert_eq!(resumed.data, b"order-17");
+        let entries = participant.saga_journal().read(saga_id).unwrap();
+        assert!(matches!(
+            crate::journal::last_progress_entry(&entries).map(|entry| &entry.event),
+            Some(ParticipantEvent::StepExecutionStarted { .. })
+        ));
+
+        participant.record_event(
+            saga_id,
+            ParticipantEvent::StepExecutionCompleted {
+                output: Vec::new(),
+                compensation_data: Vec::new(),
+                completed_at_millis: 2,
+            },
+        );
+        assert_eq!(
+            resume_from_checkpoint(&participant, &context).unwrap(),
+            None
+        );
+        let history = participant.saga_journal().inspect(saga_id).unwrap();
+        assert_eq!(history.checkpoints.len(), 2);
+    }
+}
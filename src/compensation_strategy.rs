@@ -0,0 +1,303 @@
+//! Which completed steps a compensation request covers.
+//!
+//! Whoever emits `CompensationRequested` for a failed step has to name the
+//! steps to undo. Rather than have every failing step know the whole
+//! workflow, the bus keeps a [`CompensationStrategy`] per saga type
+//! (`SagaChoreographyBus::set_compensation_strategy`) and answers with
+//! `SagaChoreographyBus::steps_to_compensate`; the terminal resolvers the bus
+//! attaches use the same strategy.
+//!
+//! Whatever a strategy picks, completed steps that depend on a picked step,
+//! directly or transitively per the workflow contract, are compensated with
+//! it: they consumed output that is about to be undone. The request names
+//! them in reverse topological order, dependents before the steps they
+//! depend on.
+//!
+//! ```ignore
+//! bus.set_compensation_strategy("order_lifecycle", CompensationStrategy::CompensateUpstreamOnly);
+//! let steps = bus.steps_to_compensate(&context, "place_order", &completed_steps);
+//! ```
+
+use std::collections::HashSet;
+
+use crate::{SagaWorkflowStepContract, StepName, WorkflowDependencySpec};
+
+/// Picks the steps to compensate; see [`CompensationStrategy::Custom`].
+pub type CompensationSelector = fn(&CompensationScope<'_>) -> Vec<StepName>;
+
+/// What a [`CompensationStrategy`] chooses from.
+#[derive(Clone, Copy, Debug)]
+pub struct CompensationScope<'a> {
+    pub failed_step: &'a str,
+    /// Compensable steps completed so far, in completion order.
+    pub completed_steps: &'a [StepName],
+    /// Steps of the saga's workflow contract; empty when none is registered.
+    pub workflow_steps: &'a [SagaWorkflowStepContract],
+}
+
+/// How the steps of a compensation request are chosen.
+#[derive(Clone, Copy, Debug, Default)]
+pub enum CompensationStrategy {
+    /// Every completed step, latest first.
+    #[default]
+    CompensateAllCompleted,
+    /// Only completed steps the failed step depends on, directly or
+    /// transitively, per the workflow contract; latest first. Parallel
+    /// branches that did not feed the failed step keep their results unless
+    /// they consumed one of those steps' output. Falls back to every
+    /// completed step when the contract does not declare the failed step.
+    CompensateUpstreamOnly,
+    Custom(CompensationSelector),
+}
+
+impl CompensationStrategy {
+    /// The steps to name in `steps_to_compensate`, in compensation order:
+    /// the strategy's pick plus the completed steps downstream of it.
+    pub fn select(&self, scope: &CompensationScope<'_>) -> Vec<StepName> {
+        let selected = match self {
+            Self::CompensateAllCompleted => scope.completed_steps.iter().rev().cloned().collect(),
+            Self::CompensateUpstreamOnly => match upstream_steps(scope) {
+                Some(upstream) => scope
+                    .completed_steps
+                    .iter()
+                    .rev()
+                    .filter(|step| upstream.contains(step.as_str()))
+                    .cloned()
+                    .collect(),
+                None => scope.completed_steps.iter().rev().cloned().collect(),
+            },
+            Self::Custom(select) => select(scope),
+        };
+        cascade(scope, selected)
+    }
+}
+
+/// Completed steps depending on `step`, directly or transitively, latest
+/// first.
+pub(crate) fn downstream_completed_steps(
+    scope: &CompensationScope<'_>,
+    step: &str,
+) -> Vec<StepName> {
+    scope
+        .completed_steps
+        .iter()
+        .rev()
+        .filter(|completed| {
+            completed.as_str() != step
+                && transitive_dependencies(scope.workflow_steps, completed.as_str())
+                    .is_some_and(|upstream| upstream.contains(step))
+        })
+        .cloned()
+        .collect()
+}
+
+/// `selected` plus the completed steps downstream of it, with every step
+/// ahead of the steps it depends on. Otherwise keeps the order it is given,
+/// cascaded steps following the selection.
+fn cascade(scope: &CompensationScope<'_>, selected: Vec<StepName>) -> Vec<StepName> {
+    let mut pending = selected;
+    for step in pending.clone() {
+        for downstream in downstream_completed_steps(scope, step.as_str()) {
+            if !pending.contains(&downstream) {
+                pending.push(downstream);
+            }
+        }
+    }
+    let upstream: Vec<HashSet<&'static str>> = pending
+        .iter()
+        .map(|step| {
+            transitive_dependencies(scope.workflow_steps, step.as_str()).unwrap_or_default()
+        })
+        .collect();
+    let mut remaining: Vec<usize> = (0..pending.len()).collect();
+    let mut ordered = Vec::with_capacity(pending.len());
+    while !remaining.is_empty() {
+        // Steps without a remaining dependent go first; a cycle, which
+        // contract validation rejects, would leave none.
+        let next = remaining
+            .iter()
+            .position(|&candidate| {
+                !remaining.iter().any(|&other| {
+                    other != candidate && upstream[other].contains(pending[candidate].as_str())
+                })
+            })
+            .unwrap_or(0);
+        ordered.push(pending[remaining.remove(next)].clone());
+    }
+    ordered
+}
+
+/// Transitive dependencies of the failed step, or `None` if the contract
+/// does not declare it.
+fn upstream_steps(scope: &CompensationScope<'_>) -> Option<HashSet<&'static str>> {
+    transitive_dependencies(scope.workflow_steps, scope.failed_step)
+}
+
+/// Steps `step_name` depends on, directly or transitively, or `None` if
+/// `workflow_steps` does not declare it.
+fn transitive_dependencies(
+    workflow_steps: &[SagaWorkflowStepContract],
+    step_name: &str,
+) -> Option<HashSet<&'static str>> {
+    let declared = |name: &str| workflow_steps.iter().find(|step| step.step_name == name);
+    let mut pending = vec![declared(step_name)?];
+    let mut upstream = HashSet::new();
+    while let Some(step) = pending.pop() {
+        let depends_on: &[&'static str] = match &step.depends_on {
+            WorkflowDependencySpec::OnSagaStart => &[],
+            WorkflowDependencySpec::After(dependency) => std::slice::from_ref(dependency),
+            WorkflowDependencySpec::AnyOf(dependencies)
+            | WorkflowDependencySpec::AllOf(dependencies) => dependencies,
+        };
+        for dependency in depends_on {
+            if upstream.insert(*dependency) {
+                pending.extend(declared(dependency));
+            }
+        }
+    }
+    Some(upstream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEPS: &[SagaWorkflowStepContract] = &[
+        SagaWorkflowStepContract {
+            step_name: "reserve_funds",
+            participant_id: "funds",
+            depends_on: WorkflowDependencySpec::OnSagaStart,
+        },
+        SagaWorkflowStepContract {
+            step_name: "notify_desk",
+            participant_id: "desk",
+            depends_on: WorkflowDependencySpec::OnSagaStart,
+        },
+        SagaWorkflowStepContract {
+            step_name: "risk_check",
+            participant_id: "risk",
+            depends_on: WorkflowDependencySpec::After("reserve_funds"),
+        },
+        SagaWorkflowStepContract {
+            step_name: "place_order",
+            participant_id: "orders",
+            depends_on: WorkflowDependencySpec::After("risk_check"),
+        },
+    ];
+
+    #[test]
+    fn upstream_only_leaves_unrelated_branches_alone() {
+        let completed: Vec<StepName> = ["reserve_funds", "notify_desk", "risk_check"]
+            .into_iter()
+            .map(StepName::from)
+            .collect();
+        let scope = CompensationScope {
+            failed_step: "place_order",
+            completed_steps: &completed,
+            workflow_steps: STEPS,
+        };
+        assert_eq!(
+            CompensationStrategy::CompensateAllCompleted.select(&scope),
+            vec![
+                StepName::from("risk_check"),
+                StepName::from("notify_desk"),
+                StepName::from("reserve_funds"),
+            ]
+        );
+        assert_eq!(
+            CompensationStrategy::CompensateUpstreamOnly.select(&scope),
+            vec![
+                StepName::from("risk_check"),
+                StepName::from("reserve_funds")
+            ]
+        );
+        let undeclared = CompensationScope {
+            failed_step: "hedge",
+            ..scope
+        };
+        assert_eq!(
+            CompensationStrategy::CompensateUpstreamOnly
+                .select(&undeclared)
+                .len(),
+            3
+        );
+        let last_only: CompensationSelector =
+            |scope| scope.completed_steps.last().cloned().into_iter().collect();
+        assert_eq!(
+            CompensationStrategy::Custom(last_only).select(&scope),
+            vec![StepName::from("risk_check")]
+        );
+    }
+
+    #[test]
+    fn compensation_cascades_to_completed_dependents() {
+        const CASCADE_STEPS: &[SagaWorkflowStepContract] = &[
+            SagaWorkflowStepContract {
+                step_name: "reserve_funds",
+                participant_id: "funds",
+                depends_on: WorkflowDependencySpec::OnSagaStart,
+            },
+            SagaWorkflowStepContract {
+                step_name: "notify_desk",
+                participant_id: "desk",
+                depends_on: WorkflowDependencySpec::OnSagaStart,
+            },
+            SagaWorkflowStepContract {
+                step_name: "hedge",
+                participant_id: "hedger",
+                depends_on: WorkflowDependencySpec::After("reserve_funds"),
+            },
+            SagaWorkflowStepContract {
+                step_name: "risk_check",
+                participant_id: "risk",
+                depends_on: WorkflowDependencySpec::After("reserve_funds"),
+            },
+            SagaWorkflowStepContract {
+                step_name: "place_order",
+                participant_id: "orders",
+                depends_on: WorkflowDependencySpec::AllOf(&["risk_check", "hedge"]),
+            },
+        ];
+        // risk_check completed before hedge, so completion order alone would
+        // undo it first.
+        let completed: Vec<StepName> = ["reserve_funds", "notify_desk", "risk_check", "hedge"]
+            .into_iter()
+            .map(StepName::from)
+            .collect();
+        let scope = CompensationScope {
+            failed_step: "place_order",
+            completed_steps: &completed,
+            workflow_steps: CASCADE_STEPS,
+        };
+        assert_eq!(
+            downstream_completed_steps(&scope, "reserve_funds"),
+            vec![StepName::from("hedge"), StepName::from("risk_check")]
+        );
+
+        let funds_only: CompensationSelector = |_| vec![StepName::from("reserve_funds")];
+        assert_eq!(
+            CompensationStrategy::Custom(funds_only).select(&scope),
+            vec![
+                StepName::from("hedge"),
+                StepName::from("risk_check"),
+                StepName::from("reserve_funds"),
+            ]
+        );
+        let out_of_order: CompensationSelector = |_| {
+            vec![
+                StepName::from("reserve_funds"),
+                StepName::from("notify_desk"),
+                StepName::from("risk_check"),
+            ]
+        };
+        assert_eq!(
+            CompensationStrategy::Custom(out_of_order).select(&scope),
+            vec![
+                StepName::from("notify_desk"),
+                StepName::from("risk_check"),
+                StepName::from("hedge"),
+                StepName::from("reserve_funds"),
+            ]
+        );
+    }
+}
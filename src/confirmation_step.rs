@@ -0,0 +1,559 @@
+//! A step that waits for an external confirmation.
+//!
+//! [`ConfirmationStep`] parks its step once its dependencies are satisfied,
+//! like [`ApprovalStep`](crate::ApprovalStep), but completes it on a
+//! notification from outside the saga instead of an operator's decision,
+//! e.g. an exchange's fill report for the order the saga placed:
+//!
+//! ```ignore
+//! let mut monitor = ConfirmationStep::new(
+//!     "monitor_order",
+//!     &["order_lifecycle"],
+//!     journal,
+//!     |_context, input| {
+//!         let order_id = decode_order_id(input);
+//!         Box::new(move |update: &OrderUpdate| {
+//!             (update.order_id == order_id && update.filled).then(|| update.encode())
+//!         })
+//!     },
+//! )
+//! .after(DependencySpec::after("place_order"))
+//! .with_timeout(Duration::from_secs(60));
+//! // Feed it every saga event from the actor's bus subscription.
+//! monitor.handle(&event);
+//! // And every order update from the exchange feed.
+//! for event in monitor.confirm(&update) {
+//!     bus.publish_strict(event)?;
+//! }
+//! // Periodically, e.g. from a tick:
+//! for event in monitor.expire(SagaContext::now_millis()) {
+//!     bus.publish_strict(event)?;
+//! }
+//! ```
+//!
+//! The matcher of a parked step is built from the step's context and input
+//! when it parks, keyed by saga id, and yields the step's output when a
+//! notification confirms it. A notification that matches no parked step is
+//! ignored. A step still unconfirmed after the timeout fails, emitting
+//! `CompensationRequested` for the steps completed so far.
+//!
+//! Parking is journaled as `StepTriggered` next to the triggering event, the
+//! outcome as `StepExecutionCompleted` or `StepExecutionFailed`. After a
+//! restart [`ConfirmationStep::recover`] parks the unconfirmed steps again,
+//! rebuilding their matchers, and keeps their original deadline.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::Duration;
+
+use crate::{
+    DedupeKey, DependencySpec, JournalError, ParticipantEvent, ParticipantJournal,
+    SagaChoreographyBus, SagaChoreographyEvent, SagaContext, SagaId, StepName,
+};
+
+/// Matcher of one parked step: the step's output if `N` confirms it.
+pub type ConfirmationMatcher<N> = Box<dyn Fn(&N) -> Option<Vec<u8>> + Send + Sync>;
+
+type MatcherFactory<N> = Box<dyn Fn(&SagaContext, &[u8]) -> ConfirmationMatcher<N> + Send + Sync>;
+
+/// A step awaiting confirmation.
+#[derive(Clone, Debug)]
+pub struct PendingConfirmation {
+    /// Context of the parked step.
+    pub context: SagaContext,
+    pub input: Vec<u8>,
+    pub parked_at_millis: u64,
+}
+
+struct Parked<N> {
+    pending: PendingConfirmation,
+    matcher: ConfirmationMatcher<N>,
+}
+
+/// Step that completes when a notification of type `N` confirms it.
+pub struct ConfirmationStep<J: ParticipantJournal, N> {
+    journal: J,
+    step_name: &'static str,
+    saga_types: &'static [&'static str],
+    depends_on: DependencySpec,
+    timeout: Option<Duration>,
+    matcher_for: MatcherFactory<N>,
+    parked: BTreeMap<SagaId, Parked<N>>,
+    dependency_completions: HashMap<SagaId, HashSet<StepName>>,
+    compensable_steps: HashMap<SagaId, Vec<StepName>>,
+    bus: Option<SagaChoreographyBus>,
+}
+
+impl<J: ParticipantJournal, N> ConfirmationStep<J, N> {
+    /// Parks on saga start until [`after`](Self::after) says otherwise, and
+    /// waits without a timeout until [`with_timeout`](Self::with_timeout).
+    pub fn new<F>(
+        step_name: &'static str,
+        saga_types: &'static [&'static str],
+        journal: J,
+        matcher_for: F,
+    ) -> Self
+    where
+        F: Fn(&SagaContext, &[u8]) -> ConfirmationMatcher<N> + Send + Sync + 'static,
+    {
+        Self {
+            journal,
+            step_name,
+            saga_types,
+            depends_on: DependencySpec::OnSagaStart,
+            timeout: None,
+            matcher_for: Box::new(matcher_for),
+            parked: BTreeMap::new(),
+            dependency_completions: HashMap::new(),
+            compensable_steps: HashMap::new(),
+            bus: None,
+        }
+    }
+
+    /// Parks the step once `depends_on` is satisfied.
+    pub fn after(mut self, depends_on: DependencySpec) -> Self {
+        self.depends_on = depends_on;
+        self
+    }
+
+    /// Lets the compensation strategy registered on `bus` choose the steps
+    /// a timeout compensates, instead of every compensable step seen.
+    pub fn with_bus(mut self, bus: SagaChoreographyBus) -> Self {
+        self.bus = Some(bus);
+        self
+    }
+
+    /// Fails a step still unconfirmed `timeout` after it parked.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Feeds one saga event. Returns whether it parked the step.
+    pub fn handle(&mut self, event: &SagaChoreographyEvent) -> bool {
+        let context = event.context();
+        if !self.saga_types.contains(&context.saga_type.as_ref()) {
+            return false;
+        }
+        let saga_id = context.saga_id;
+        match event {
+            SagaChoreographyEvent::SagaStarted { payload, .. }
+                if self.depends_on.is_on_saga_start() =>
+            {
+                self.park(event, context.clone(), payload.clone())
+            }
+            SagaChoreographyEvent::StepCompleted {
+                output,
+                compensation_available,
+                ..
+            } => {
+                if *compensation_available {
+                    self.compensable_steps
+                        .entry(saga_id)
+                        .or_default()
+                        .push(context.step_name.clone());
+                }
+                if self.dependency_satisfied(saga_id, &context.step_name) {
+                    let parked_context = context.next_step(self.step_name.into());
+                    self.park(event, parked_context, output.clone())
+                } else {
+                    false
+                }
+            }
+            SagaChoreographyEvent::SagaCompleted { .. }
+            | SagaChoreographyEvent::SagaFailed { .. }
+            | SagaChoreographyEvent::SagaQuarantined { .. } => {
+                self.forget(saga_id);
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// Completes every parked step `notification` confirms. Returns the
+    /// `StepCompleted`s to publish; a step whose outcome could not be
+    /// journaled stays parked.
+    pub fn confirm(&mut self, notification: &N) -> Vec<SagaChoreographyEvent> {
+        let confirmed: Vec<(SagaId, Vec<u8>)> = self
+            .parked
+            .iter()
+            .filter_map(|(saga_id, parked)| {
+                (parked.matcher)(notification).map(|output| (*saga_id, output))
+            })
+            .collect();
+        let mut events = Vec::with_capacity(confirmed.len());
+        for (saga_id, output) in confirmed {
+            let now = SagaContext::now_millis();
+            if let Err(err) = self.journal.append(
+                saga_id,
+                ParticipantEvent::StepExecutionCompleted {
+                    output: output.clone(),
+                    compensation_data: Vec::new(),
+                    completed_at_millis: now,
+                },
+            ) {
+                tracing::error!(
+                    target: "core::saga",
+                    event = "saga_confirmation_journal_failed",
+                    saga_id = saga_id.get(),
+                    step_name = self.step_name,
+                    error = ?err
+                );
+                continue;
+            }
+            let Some(parked) = self.parked.remove(&saga_id) else {
+                continue;
+            };
+            tracing::info!(
+                target: "core::saga",
+                event = "saga_step_confirmed",
+                saga_id = saga_id.get(),
+                step_name = self.step_name
+            );
+            let mut context = parked.pending.context;
+            context.event_timestamp_millis = now;
+            events.push(SagaChoreographyEvent::StepCompleted {
+                context,
+                output,
+                saga_input: parked.pending.input,
+                compensation_available: false,
+                completion_ratio: None,
+            });
+        }
+        events
+    }
+
+    /// Fails every parked step whose timeout ran out by `now_millis`.
+    /// Returns the `CompensationRequested`s to publish, each covering its
+    /// saga's compensable steps seen so far in reverse order.
+    pub fn expire(&mut self, now_millis: u64) -> Vec<SagaChoreographyEvent> {
+        let Some(timeout) = self.timeout else {
+            return Vec::new();
+        };
+        let timeout_millis = timeout.as_millis() as u64;
+        let expired: Vec<SagaId> = self
+            .parked
+            .iter()
+            .filter(|(_, parked)| {
+                parked
+                    .pending
+                    .parked_at_millis
+                    .saturating_add(timeout_millis)
+                    <= now_millis
+            })
+            .map(|(saga_id, _)| *saga_id)
+            .collect();
+        let mut events = Vec::with_capacity(expired.len());
+        for saga_id in expired {
+            if let Err(err) = self.journal.append(
+                saga_id,
+                ParticipantEvent::StepExecutionFailed {
+                    error: "confirmation timed out".into(),
+                    requires_compensation: true,
+                    failed_at_millis: now_millis,
+                    details: Vec::new(),
+                },
+            ) {
+                tracing::error!(
+                    target: "core::saga",
+                    event = "saga_confirmation_journal_failed",
+                    saga_id = saga_id.get(),
+                    step_name = self.step_name,
+                    error = ?err
+                );
+                continue;
+            }
+            let Some(parked) = self.parked.remove(&saga_id) else {
+                continue;
+            };
+            tracing::warn!(
+                target: "core::saga",
+                event = "saga_confirmation_timed_out",
+                saga_id = saga_id.get(),
+                step_name = self.step_name,
+                waited_ms = now_millis.saturating_sub(parked.pending.parked_at_millis)
+            );
+            let steps_to_compensate = self
+                .compensable_steps
+                .get(&saga_id)
+                .map(|steps| self.steps_to_compensate(&parked.pending.context, steps))
+                .unwrap_or_default();
+            events.push(SagaChoreographyEvent::CompensationRequested {
+                context: parked.pending.context.for_compensation(),
+                failed_step: self.step_name.into(),
+                reason: format!("confirmation timed out after {}ms", timeout_millis).into(),
+                steps_to_compensate,
+            });
+        }
+        events
+    }
+
+    /// Steps awaiting confirmation, by saga id.
+    pub fn pending(&self) -> impl Iterator<Item = &PendingConfirmation> {
+        self.parked.values().map(|parked| &parked.pending)
+    }
+
+    pub fn pending_len(&self) -> usize {
+        self.parked.len()
+    }
+
+    /// Parks again every step the journal shows as parked but unconfirmed,
+    /// with its original park time. Returns how many were parked.
+    pub fn recover(&mut self) -> Result<usize, JournalError> {
+        let mut recovered = 0;
+        for saga_id in self.journal.list_sagas()? {
+            let entries = self.journal.read(saga_id)?;
+            let Some(last) = crate::journal::last_progress_entry(&entries) else {
+                continue;
+            };
+            if !matches!(last.event, ParticipantEvent::StepTriggered { .. }) {
+                continue;
+            }
+            let Some(trigger) = self.journal.incoming_history(saga_id)?.pop() else {
+                continue;
+            };
+            let Some((context, input)) = self.parked_step(&trigger.event) else {
+                continue;
+            };
+            if let SagaChoreographyEvent::StepCompleted {
+                compensation_available: true,
+                context: trigger_context,
+                ..
+            } = &trigger.event
+            {
+                let steps = self.compensable_steps.entry(saga_id).or_default();
+                if !steps.contains(&trigger_context.step_name) {
+                    steps.push(trigger_context.step_name.clone());
+                }
+            }
+            let matcher = (self.matcher_for)(&context, &input);
+            self.parked.insert(
+                saga_id,
+                Parked {
+                    pending: PendingConfirmation {
+                        context,
+                        input,
+                        parked_at_millis: last.recorded_at_millis,
+                    },
+                    matcher,
+                },
+            );
+            recovered += 1;
+        }
+        Ok(recovered)
+    }
+
+    fn steps_to_compensate(&self, context: &SagaContext, completed: &[StepName]) -> Vec<StepName> {
+        match &self.bus {
+            Some(bus) => bus.steps_to_compensate(context, self.step_name, completed),
+            None => completed.iter().rev().cloned().collect(),
+        }
+    }
+
+    fn parked_step(&self, trigger: &SagaChoreographyEvent) -> Option<(SagaContext, Vec<u8>)> {
+        match trigger {
+            SagaChoreographyEvent::SagaStarted { context, payload } => {
+                Some((context.clone(), payload.clone()))
+            }
+            SagaChoreographyEvent::StepCompleted {
+                context, output, ..
+            } => Some((context.next_step(self.step_name.into()), output.clone())),
+            _ => None,
+        }
+    }
+
+    fn dependency_satisfied(&mut self, saga_id: SagaId, completed_step: &StepName) -> bool {
+        match &self.depends_on {
+            DependencySpec::OnSagaStart => false,
+            DependencySpec::After(step) => completed_step == step,
+            DependencySpec::AnyOf(steps) => steps.iter().any(|step| step == completed_step),
+            DependencySpec::AllOf(steps) => {
+                if !steps.iter().any(|step| step == completed_step) {
+                    return false;
+                }
+                let completed = self.dependency_completions.entry(saga_id).or_default();
+                completed.insert(completed_step.clone());
+                steps.iter().all(|step| completed.contains(step))
+            }
+        }
+    }
+
+    fn park(
+        &mut self,
+        trigger: &SagaChoreographyEvent,
+        context: SagaContext,
+        input: Vec<u8>,
+    ) -> bool {
+        let saga_id = context.saga_id;
+        // A redelivered trigger, or one for a step resolved already.
+        let already_parked = self.parked.contains_key(&saga_id)
+            || self.journal.read(saga_id).is_ok_and(|entries| {
+                entries
+                    .iter()
+                    .any(|entry| matches!(entry.event, ParticipantEvent::StepTriggered { .. }))
+            });
+        if already_parked {
+            return false;
+        }
+        let now = SagaContext::now_millis();
+        let journaled = self
+            .journal
+            .record_incoming(saga_id, DedupeKey::from_event(trigger), trigger)
+            .and_then(|inbox_id| match inbox_id {
+                Some(inbox_id) => self.journal.mark_incoming_processed(inbox_id),
+                None => Ok(()),
+            })
+            .and_then(|()| {
+                self.journal.append(
+                    saga_id,
+                    ParticipantEvent::StepTriggered {
+                        triggering_event: trigger.event_type().into(),
+                        triggered_at_millis: now,
+                    },
+                )
+            });
+        if let Err(err) = journaled {
+            tracing::error!(
+                target: "core::saga",
+                event = "saga_confirmation_park_journal_failed",
+                saga_id = saga_id.get(),
+                step_name = self.step_name,
+                error = ?err
+            );
+        }
+        tracing::info!(
+            target: "core::saga",
+            event = "saga_step_awaiting_confirmation",
+            saga_id = saga_id.get(),
+            step_name = self.step_name
+        );
+        let matcher = (self.matcher_for)(&context, &input);
+        self.parked.insert(
+            saga_id,
+            Parked {
+                pending: PendingConfirmation {
+                    context,
+                    input,
+                    parked_at_millis: now,
+                },
+                matcher,
+            },
+        );
+        true
+    }
+
+    fn forget(&mut self, saga_id: SagaId) {
+        self.parked.remove(&saga_id);
+        self.dependency_completions.remove(&saga_id);
+        self.compensable_steps.remove(&saga_id);
+        if let Err(err) = self.journal.prune(saga_id) {
+            tracing::error!(
+                target: "core::saga",
+                event = "saga_confirmation_prune_failed",
+                saga_id = saga_id.get(),
+                error = ?err
+            );
+        }
+    }
+}
+
+impl<J: ParticipantJournal, N> std::fmt::Debug for ConfirmationStep<J, N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConfirmationStep")
+            .field("step_name", &self.step_name)
+            .field("depends_on", &self.depends_on)
+            .field("timeout", &self.timeout)
+            .field("pending_len", &self.parked.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{step_completed, DeterministicContextBuilder, InMemoryJournal};
+
+    struct OrderUpdate {
+        order_id: Vec<u8>,
+        filled: bool,
+    }
+
+    fn monitor(
+        journal: Arc<InMemoryJournal>,
+    ) -> ConfirmationStep<Arc<InMemoryJournal>, OrderUpdate> {
+        ConfirmationStep::new(
+            "monitor_order",
+            &["order_lifecycle"],
+            journal,
+            |_context, input| {
+                let order_id = input.to_vec();
+                Box::new(move |update: &OrderUpdate| {
+                    (update.order_id == order_id && update.filled).then(|| b"filled".to_vec())
+                })
+            },
+        )
+        .after(DependencySpec::after("place_order"))
+        .with_timeout(Duration::from_secs(60))
+    }
+
+    #[test]
+    fn matching_notification_confirms_and_timeout_escalates_after_restart() {
+        let journal = Arc::new(InMemoryJournal::new());
+        let mut step = monitor(journal.clone());
+        let placed = DeterministicContextBuilder::default()
+            .with_step_name("place_order")
+            .build();
+        let trigger = step_completed(placed.clone(), b"order-9".to_vec(), Vec::new(), true);
+        assert!(step.handle(&trigger));
+        assert!(!step.handle(&trigger));
+
+        let unfilled = OrderUpdate {
+            order_id: b"order-9".to_vec(),
+            filled: false,
+        };
+        assert!(step.confirm(&unfilled).is_empty());
+
+        // Restart: the parked step and its matcher come back from the journal.
+        let mut step = monitor(journal.clone());
+        assert_eq!(step.recover().unwrap(), 1);
+        let parked_at = step.pending().next().unwrap().parked_at_millis;
+        assert!(step.expire(parked_at + 59_999).is_empty());
+        let expired = step.expire(parked_at + 60_000);
+        let [SagaChoreographyEvent::CompensationRequested {
+            failed_step,
+            steps_to_compensate,
+            ..
+        }] = expired.as_slice()
+        else {
+            panic!("expected one compensation request");
+        };
+        assert_eq!(failed_step.as_ref(), "monitor_order");
+        assert_eq!(steps_to_compensate, &vec![StepName::from("place_order")]);
+        assert_eq!(step.pending_len(), 0);
+
+        let other = DeterministicContextBuilder::default()
+            .with_saga_id(2)
+            .with_step_name("place_order")
+            .build();
+        step.handle(&step_completed(
+            other,
+            b"order-10".to_vec(),
+            Vec::new(),
+            false,
+        ));
+        let confirmed = step.confirm(&OrderUpdate {
+            order_id: b"order-10".to_vec(),
+            filled: true,
+        });
+        let [SagaChoreographyEvent::StepCompleted {
+            context, output, ..
+        }] = confirmed.as_slice()
+        else {
+            panic!("expected one StepCompleted");
+        };
+        assert_eq!(context.step_name.as_ref(), "monitor_order");
+        assert_eq!(output, b"filled");
+        assert_eq!(step.recover().unwrap(), 0);
+    }
+}
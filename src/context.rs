@@ -1,7 +1,20 @@
 //! Saga context and identity types
 
+use crate::{SagaType, StepName};
+
 /// Unique identifier for a saga execution
-#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
 pub struct SagaId(pub u64);
 
 impl SagaId {
@@ -40,15 +53,25 @@ pub struct StepId {
 /// Peer ID type (matches icanact-core)
 pub type PeerId = [u8; 32];
 
+/// Workflow version of sagas started without an explicit one.
+pub const DEFAULT_WORKFLOW_VERSION: u32 = 1;
+
 /// Correlation context passed with every saga event
-#[derive(Clone)]
+///
+/// Cloning is cheap: the saga type and step name are interned symbols, so a
+/// clone copies the fixed-size fields and bumps two reference counts without
+/// allocating. Event handlers still take the context out of the event by
+/// value (see [`SagaChoreographyEvent::into_context`]) where they own it.
+///
+/// [`SagaChoreographyEvent::into_context`]: crate::SagaChoreographyEvent::into_context
+#[derive(Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub struct SagaContext {
     /// Unique saga execution identifier
     pub saga_id: SagaId,
     /// Type of saga (e.g., "order_workflow")
-    pub saga_type: Box<str>,
+    pub saga_type: SagaType,
     /// Name of the current step
-    pub step_name: Box<str>,
+    pub step_name: StepName,
     /// Correlation ID linking all events in this saga
     pub correlation_id: u64,
     /// ID of the event that caused this one
@@ -65,6 +88,13 @@ pub struct SagaContext {
     pub saga_started_at_millis: u64,
     /// Timestamp of this event (millis since UNIX epoch)
     pub event_timestamp_millis: u64,
+    /// Saga whose completion started this one, when it was chained
+    pub parent_saga_id: Option<SagaId>,
+    /// Version of the saga type's workflow contract this saga runs under
+    pub workflow_version: u32,
+    /// Lamport timestamp: greater than that of every event this one causally
+    /// follows, whatever the peers' wall clocks say
+    pub logical_clock: u64,
 }
 
 impl SagaContext {
@@ -83,8 +113,41 @@ impl SagaContext {
         }
     }
 
+    /// Create the start context of a new saga, correlated by its own id.
+    pub fn start(
+        saga_id: SagaId,
+        saga_type: SagaType,
+        first_step: StepName,
+        initiator_peer_id: PeerId,
+    ) -> Self {
+        let now = Self::now_millis();
+        Self {
+            saga_id,
+            saga_type,
+            step_name: first_step,
+            correlation_id: saga_id.get(),
+            causation_id: 0,
+            trace_id: Self::next_trace_id(),
+            step_index: 0,
+            attempt: 0,
+            initiator_peer_id,
+            saga_started_at_millis: now,
+            event_timestamp_millis: now,
+            parent_saga_id: None,
+            workflow_version: DEFAULT_WORKFLOW_VERSION,
+            logical_clock: 1,
+        }
+    }
+
+    /// Runs the saga under `workflow_version` of its saga type's contract.
+    /// Set on the start context; every derived context inherits it.
+    pub fn with_workflow_version(mut self, workflow_version: u32) -> Self {
+        self.workflow_version = workflow_version;
+        self
+    }
+
     /// Create a context for the next step in sequence
-    pub fn next_step(&self, step_name: Box<str>) -> Self {
+    pub fn next_step(&self, step_name: StepName) -> Self {
         Self {
             step_name,
             causation_id: self.trace_id,
@@ -92,6 +155,7 @@ impl SagaContext {
             step_index: self.step_index + 1,
             attempt: 0,
             event_timestamp_millis: Self::now_millis(),
+            logical_clock: self.logical_clock + 1,
             ..self.clone()
         }
     }
@@ -102,6 +166,7 @@ impl SagaContext {
             attempt: self.attempt + 1,
             trace_id: Self::next_trace_id(),
             event_timestamp_millis: Self::now_millis(),
+            logical_clock: self.logical_clock + 1,
             ..self.clone()
         }
     }
@@ -112,10 +177,35 @@ impl SagaContext {
             causation_id: self.trace_id,
             trace_id: Self::next_trace_id(),
             event_timestamp_millis: Self::now_millis(),
+            logical_clock: self.logical_clock + 1,
             ..self.clone()
         }
     }
 
+    /// Create the start context of a saga chained from this one.
+    ///
+    /// The new saga keeps the correlation id and initiator so the whole
+    /// chain can be traced, and records this saga as its parent.
+    pub fn chained(&self, saga_id: SagaId, saga_type: SagaType, first_step: StepName) -> Self {
+        let now = Self::now_millis();
+        Self {
+            saga_id,
+            saga_type,
+            step_name: first_step,
+            correlation_id: self.correlation_id,
+            causation_id: self.trace_id,
+            trace_id: Self::next_trace_id(),
+            step_index: 0,
+            attempt: 0,
+            initiator_peer_id: self.initiator_peer_id,
+            saga_started_at_millis: now,
+            event_timestamp_millis: now,
+            parent_saga_id: Some(self.saga_id),
+            workflow_version: DEFAULT_WORKFLOW_VERSION,
+            logical_clock: self.logical_clock + 1,
+        }
+    }
+
     /// Calculate elapsed time since saga started
     pub fn elapsed_millis(&self) -> u64 {
         self.event_timestamp_millis
@@ -137,6 +227,119 @@ impl std::fmt::Debug for SagaContext {
             .field("step_name", &self.step_name)
             .field("step_index", &self.step_index)
             .field("attempt", &self.attempt)
+            .field("parent_saga_id", &self.parent_saga_id)
+            .field("workflow_version", &self.workflow_version)
+            .field("logical_clock", &self.logical_clock)
             .finish()
     }
 }
+
+/// How far an event's `event_timestamp_millis` may be from the local clock
+/// for the event to be accepted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EventSkewWindow {
+    /// How long ago an event may have been stamped.
+    pub max_age_millis: u64,
+    /// How far ahead of the local clock an event may be stamped.
+    pub max_ahead_millis: u64,
+}
+
+impl EventSkewWindow {
+    pub const fn symmetric(millis: u64) -> Self {
+        Self {
+            max_age_millis: millis,
+            max_ahead_millis: millis,
+        }
+    }
+
+    pub fn check(
+        &self,
+        event_timestamp_millis: u64,
+        now_millis: u64,
+    ) -> Result<(), EventSkewError> {
+        if event_timestamp_millis < now_millis {
+            let age_millis = now_millis - event_timestamp_millis;
+            if age_millis > self.max_age_millis {
+                return Err(EventSkewError::TooOld {
+                    age_millis,
+                    max_age_millis: self.max_age_millis,
+                });
+            }
+        } else {
+            let ahead_millis = event_timestamp_millis - now_millis;
+            if ahead_millis > self.max_ahead_millis {
+                return Err(EventSkewError::TooFarAhead {
+                    ahead_millis,
+                    max_ahead_millis: self.max_ahead_millis,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum EventSkewError {
+    #[error("event stamped {age_millis}ms ago, window allows {max_age_millis}ms")]
+    TooOld {
+        age_millis: u64,
+        max_age_millis: u64,
+    },
+    #[error("event stamped {ahead_millis}ms ahead of the local clock, window allows {max_ahead_millis}ms")]
+    TooFarAhead {
+        ahead_millis: u64,
+        max_ahead_millis: u64,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skew_window_bounds_both_directions() {
+        let window = EventSkewWindow {
+            max_age_millis: 100,
+            max_ahead_millis: 10,
+        };
+
+        assert_eq!(window.check(1_000, 1_100), Ok(()));
+        assert_eq!(window.check(1_010, 1_000), Ok(()));
+        assert!(matches!(
+            window.check(1_000, 1_101),
+            Err(EventSkewError::TooOld {
+                age_millis: 101,
+                ..
+            })
+        ));
+        assert!(matches!(
+            window.check(1_011, 1_000),
+            Err(EventSkewError::TooFarAhead {
+                ahead_millis: 11,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn clones_share_interned_names() {
+        let context = SagaContext::start(
+            SagaId::new(7),
+            "order_workflow".into(),
+            "reserve".into(),
+            [0; 32],
+        );
+        let next = context.next_step("charge".into());
+        let copy = next.clone();
+        assert!(std::ptr::eq(
+            context.saga_type.as_str(),
+            copy.saga_type.as_str()
+        ));
+        assert!(std::ptr::eq(
+            next.step_name.as_str(),
+            copy.step_name.as_str()
+        ));
+        assert_eq!(copy.step_index, 1);
+        assert_eq!(copy.causation_id, context.trace_id);
+    }
+}
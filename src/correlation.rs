@@ -0,0 +1,180 @@
+//! Lookup of sagas by the identifiers their steps handed to the outside.
+//!
+//! A step that places an order learns an `order_id`, sends a `client_id`,
+//! or passes an effect's idempotency key along; the callback that later
+//! reports on it (an order update, a fill) carries only that identifier.
+//! [`EffectCorrelationIndex`] maps such identifiers back to the saga, so the
+//! callback handler does not need a private map per actor:
+//!
+//! ```ignore
+//! let correlations = Arc::new(EffectCorrelationIndex::new());
+//! let support = SagaParticipantSupport::new(journal, dedupe)
+//!     .with_correlation_index(correlations.clone());
+//! // In the participant:
+//! fn correlation_keys(&self, _context: &SagaContext, output: &[u8]) -> Vec<Box<str>> {
+//!     vec![decode_order_id(output).into()]
+//! }
+//! // In the `OnOrderUpdate` handler:
+//! let Some(saga_id) = correlations.saga_for(&update.order_id) else { return };
+//! ```
+//!
+//! The helpers index a step's keys once its completion is journaled: what
+//! the participant's `correlation_keys` returns for the step output, and
+//! the [`IdempotencyKey::for_effect`] key of a
+//! `StepOutput::CompletedWithEffect`. A saga's keys are dropped when its
+//! state is pruned. The index lives in memory; after a restart it fills up
+//! again as recovered steps complete.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::{IdempotencyKey, SagaId, SagaStateExt};
+
+#[derive(Default)]
+struct CorrelationMaps {
+    by_key: HashMap<Box<str>, SagaId>,
+    by_saga: HashMap<SagaId, Vec<Box<str>>>,
+}
+
+/// External identifier to saga id map, shared between the participants
+/// that index it and the handlers that look sagas up.
+#[derive(Default)]
+pub struct EffectCorrelationIndex {
+    maps: Mutex<CorrelationMaps>,
+}
+
+impl EffectCorrelationIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maps `key` to `saga_id`. Returns the saga the key pointed to before,
+    /// if it was another one.
+    pub fn insert(&self, saga_id: SagaId, key: impl Into<Box<str>>) -> Option<SagaId> {
+        let key = key.into();
+        let mut maps = self
+            .maps
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let previous = maps.by_key.insert(key.clone(), saga_id);
+        match previous {
+            Some(previous) if previous == saga_id => return None,
+            Some(previous) => {
+                if let Some(keys) = maps.by_saga.get_mut(&previous) {
+                    keys.retain(|existing| *existing != key);
+                }
+            }
+            None => {}
+        }
+        maps.by_saga.entry(saga_id).or_default().push(key);
+        previous
+    }
+
+    /// The saga `key` was indexed for.
+    pub fn saga_for(&self, key: &str) -> Option<SagaId> {
+        self.maps
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .by_key
+            .get(key)
+            .copied()
+    }
+
+    /// Keys indexed for `saga_id`, in insertion order.
+    pub fn keys_for(&self, saga_id: SagaId) -> Vec<Box<str>> {
+        self.maps
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .by_saga
+            .get(&saga_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Drops every key of `saga_id`.
+    pub fn remove_saga(&self, saga_id: SagaId) {
+        let mut maps = self
+            .maps
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        for key in maps.by_saga.remove(&saga_id).unwrap_or_default() {
+            maps.by_key.remove(&key);
+        }
+    }
+
+    /// Number of indexed keys.
+    pub fn len(&self) -> usize {
+        self.maps
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .by_key
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl std::fmt::Debug for EffectCorrelationIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EffectCorrelationIndex")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// Indexes the keys of a completed step in the participant's correlation
+/// index, if it has one.
+pub(crate) fn index_step_correlations<P>(
+    participant: &P,
+    saga_id: SagaId,
+    step_name: &str,
+    effect: Option<&str>,
+    keys: Vec<Box<str>>,
+) where
+    P: SagaStateExt,
+{
+    let Some(index) = participant.saga_support().correlations.as_deref() else {
+        return;
+    };
+    let effect_key = effect
+        .map(|effect| IdempotencyKey::for_effect(saga_id, step_name, effect).0)
+        .into_iter();
+    for key in keys.into_iter().chain(effect_key) {
+        if let Some(previous) = index.insert(saga_id, key.clone()) {
+            tracing::warn!(
+                target: "core::saga",
+                event = "saga_correlation_key_rebound",
+                saga_id = saga_id.get(),
+                previous_saga_id = previous.get(),
+                step_name,
+                key = %key
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebinding_a_key_moves_it_and_pruning_drops_the_saga_keys() {
+        let index = EffectCorrelationIndex::new();
+        let (first, second) = (SagaId::new(1), SagaId::new(2));
+        assert_eq!(index.insert(first, "order-9"), None);
+        assert_eq!(index.insert(first, "client-9"), None);
+        assert_eq!(index.insert(first, "order-9"), None);
+        assert_eq!(index.saga_for("order-9"), Some(first));
+
+        assert_eq!(index.insert(second, "client-9"), Some(first));
+        assert_eq!(index.keys_for(first), vec![Box::from("order-9")]);
+        assert_eq!(index.saga_for("client-9"), Some(second));
+
+        index.remove_saga(first);
+        assert_eq!(index.saga_for("order-9"), None);
+        assert_eq!(index.saga_for("client-9"), Some(second));
+        assert_eq!(index.len(), 1);
+    }
+}
@@ -0,0 +1,308 @@
+//! Dead-letter storage for saga events that cannot be processed.
+//!
+//! Events that fail to decode, fail transition validation, or target a saga
+//! type no participant is registered for used to be logged and dropped. A
+//! [`DeadLetterStore`] keeps them, together with the failure reason, so an
+//! operator can inspect and replay them once the cause is fixed.
+
+use crate::{SagaChoreographyEvent, SagaContext, SagaId};
+
+/// Why an event was routed to the dead-letter store.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub enum DeadLetterReason {
+    /// The wire payload could not be decoded into a [`SagaChoreographyEvent`].
+    Deserialization,
+    /// The event decoded but failed transition validation.
+    Validation,
+    /// No participant or workflow handles the event's saga type. Only
+    /// produced for participants that opted in with
+    /// [`crate::SagaParticipantSupport::with_unknown_saga_dead_letters`];
+    /// the ingress helpers drop such events otherwise.
+    UnknownSaga,
+    /// The event arrived before its saga's `SagaStarted`, which did not
+    /// follow within the reorder window.
+    OutOfOrder,
+}
+
+impl DeadLetterReason {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Deserialization => "deserialization",
+            Self::Validation => "validation",
+            Self::UnknownSaga => "unknown_saga",
+            Self::OutOfOrder => "out_of_order",
+        }
+    }
+}
+
+/// The rejected payload: a decoded event when one is available, the raw wire
+/// bytes otherwise.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub enum DeadLetterPayload {
+    Event(SagaChoreographyEvent),
+    Raw(Vec<u8>),
+}
+
+impl DeadLetterPayload {
+    pub fn saga_id(&self) -> Option<SagaId> {
+        match self {
+            Self::Event(event) => Some(event.context().saga_id),
+            Self::Raw(_) => None,
+        }
+    }
+}
+
+/// A dead-lettered event.
+#[derive(Clone, Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct DeadLetterEntry {
+    /// Identifier assigned by the store.
+    pub dead_letter_id: u64,
+    pub reason: DeadLetterReason,
+    /// Human-readable failure detail (decode error, rejected transition, ...).
+    pub detail: Box<str>,
+    /// The Unix timestamp in milliseconds when the event was dead-lettered.
+    pub recorded_at_millis: u64,
+    pub payload: DeadLetterPayload,
+}
+
+/// Errors that can occur during dead-letter store operations.
+#[derive(Debug, thiserror::Error)]
+pub enum DeadLetterError {
+    #[error("Storage error: {0}")]
+    Storage(Box<str>),
+}
+
+/// Storage for events that could not be processed.
+///
+/// Implementations must be `Send + Sync + 'static` so one store can be shared
+/// by every participant of a process.
+pub trait DeadLetterStore: Send + Sync + 'static {
+    /// Stores a rejected payload and returns its dead-letter id.
+    fn push(
+        &self,
+        reason: DeadLetterReason,
+        detail: &str,
+        payload: DeadLetterPayload,
+    ) -> Result<u64, DeadLetterError>;
+
+    /// Lists stored entries ordered by dead-letter id.
+    fn list(&self) -> Result<Vec<DeadLetterEntry>, DeadLetterError>;
+
+    /// Removes an entry, returning it if it was present.
+    fn remove(&self, dead_letter_id: u64) -> Result<Option<DeadLetterEntry>, DeadLetterError>;
+}
+
+impl<T> DeadLetterStore for std::sync::Arc<T>
+where
+    T: DeadLetterStore + ?Sized,
+{
+    fn push(
+        &self,
+        reason: DeadLetterReason,
+        detail: &str,
+        payload: DeadLetterPayload,
+    ) -> Result<u64, DeadLetterError> {
+        (**self).push(reason, detail, payload)
+    }
+
+    fn list(&self) -> Result<Vec<DeadLetterEntry>, DeadLetterError> {
+        (**self).list()
+    }
+
+    fn remove(&self, dead_letter_id: u64) -> Result<Option<DeadLetterEntry>, DeadLetterError> {
+        (**self).remove(dead_letter_id)
+    }
+}
+
+/// In-memory dead-letter store for tests and single-process deployments.
+#[derive(Default)]
+pub struct InMemoryDeadLetterStore {
+    entries: std::sync::RwLock<std::collections::BTreeMap<u64, DeadLetterEntry>>,
+    counter: std::sync::atomic::AtomicU64,
+}
+
+impl InMemoryDeadLetterStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl DeadLetterStore for InMemoryDeadLetterStore {
+    fn push(
+        &self,
+        reason: DeadLetterReason,
+        detail: &str,
+        payload: DeadLetterPayload,
+    ) -> Result<u64, DeadLetterError> {
+        let dead_letter_id = self
+            .counter
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let entry = DeadLetterEntry {
+            dead_letter_id,
+            reason,
+            detail: detail.into(),
+            recorded_at_millis: SagaContext::now_millis(),
+            payload,
+        };
+        let mut entries = self
+            .entries
+            .write()
+            .map_err(|e| DeadLetterError::Storage(e.to_string().into()))?;
+        entries.insert(dead_letter_id, entry);
+        Ok(dead_letter_id)
+    }
+
+    fn list(&self) -> Result<Vec<DeadLetterEntry>, DeadLetterError> {
+        let entries = self
+            .entries
+            .read()
+            .map_err(|e| DeadLetterError::Storage(e.to_string().into()))?;
+        Ok(entries.values().cloned().collect())
+    }
+
+    fn remove(&self, dead_letter_id: u64) -> Result<Option<DeadLetterEntry>, DeadLetterError> {
+        let mut entries = self
+            .entries
+            .write()
+            .map_err(|e| DeadLetterError::Storage(e.to_string().into()))?;
+        Ok(entries.remove(&dead_letter_id))
+    }
+}
+
+/// Routes a rejected event to `store`, logging instead of failing when the
+/// store itself errors.
+pub fn dead_letter_event(
+    store: &dyn DeadLetterStore,
+    reason: DeadLetterReason,
+    detail: &str,
+    event: SagaChoreographyEvent,
+) -> Option<u64> {
+    let saga_id = event.context().saga_id;
+    match store.push(reason, detail, DeadLetterPayload::Event(event)) {
+        Ok(dead_letter_id) => {
+            tracing::warn!(
+                target: "core::saga",
+                event = "saga_event_dead_lettered",
+                saga_id = saga_id.get(),
+                reason = reason.as_str(),
+                detail,
+                dead_letter_id
+            );
+            Some(dead_letter_id)
+        }
+        Err(err) => {
+            tracing::error!(
+                target: "core::saga",
+                event = "saga_dead_letter_push_failed",
+                saga_id = saga_id.get(),
+                reason = reason.as_str(),
+                detail,
+                error = %err
+            );
+            None
+        }
+    }
+}
+
+/// Routes an undecodable wire payload to `store`.
+pub fn dead_letter_raw_payload(
+    store: &dyn DeadLetterStore,
+    detail: &str,
+    payload: Vec<u8>,
+) -> Option<u64> {
+    match store.push(
+        DeadLetterReason::Deserialization,
+        detail,
+        DeadLetterPayload::Raw(payload),
+    ) {
+        Ok(dead_letter_id) => {
+            tracing::warn!(
+                target: "core::saga",
+                event = "saga_payload_dead_lettered",
+                detail,
+                dead_letter_id
+            );
+            Some(dead_letter_id)
+        }
+        Err(err) => {
+            tracing::error!(
+                target: "core::saga",
+                event = "saga_dead_letter_push_failed",
+                reason = DeadLetterReason::Deserialization.as_str(),
+                detail,
+                error = %err
+            );
+            None
+        }
+    }
+}
+
+/// Hands every stored entry to `redeliver` and removes the ones it accepted.
+///
+/// Entries for which `redeliver` returns an error stay in the store, so the
+/// replay can be retried later. Returns the number of removed entries.
+pub fn replay_dead_letters<S, F>(store: &S, mut redeliver: F) -> Result<usize, DeadLetterError>
+where
+    S: DeadLetterStore + ?Sized,
+    F: FnMut(&DeadLetterEntry) -> Result<(), String>,
+{
+    let mut replayed = 0;
+    for entry in store.list()? {
+        match redeliver(&entry) {
+            Ok(()) => {
+                store.remove(entry.dead_letter_id)?;
+                replayed += 1;
+            }
+            Err(err) => {
+                tracing::warn!(
+                    target: "core::saga",
+                    event = "saga_dead_letter_replay_failed",
+                    dead_letter_id = entry.dead_letter_id,
+                    reason = entry.reason.as_str(),
+                    error = %err
+                );
+            }
+        }
+    }
+    Ok(replayed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DeterministicContextBuilder;
+
+    fn started_event() -> SagaChoreographyEvent {
+        SagaChoreographyEvent::SagaStarted {
+            context: DeterministicContextBuilder::default().build(),
+            payload: vec![1],
+        }
+    }
+
+    #[test]
+    fn replay_removes_only_accepted_entries() {
+        let store = InMemoryDeadLetterStore::new();
+        dead_letter_event(
+            &store,
+            DeadLetterReason::UnknownSaga,
+            "no workflow for saga_type",
+            started_event(),
+        )
+        .expect("event should be dead-lettered");
+        dead_letter_raw_payload(&store, "truncated frame", vec![0xde, 0xad])
+            .expect("payload should be dead-lettered");
+
+        let replayed = replay_dead_letters(&store, |entry| match &entry.payload {
+            DeadLetterPayload::Event(_) => Ok(()),
+            DeadLetterPayload::Raw(_) => Err("no decoder".to_string()),
+        })
+        .expect("replay should succeed");
+
+        assert_eq!(replayed, 1);
+        let remaining = store.list().expect("list should succeed");
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].reason, DeadLetterReason::Deserialization);
+        assert_eq!(remaining[0].detail.as_ref(), "truncated frame");
+    }
+}
@@ -12,7 +12,233 @@
 //! determine if it has already processed a given request to maintain exactly-once
 //! semantics despite the possibility of duplicate message delivery.
 
-use super::SagaId;
+use std::sync::Arc;
+
+use super::{SagaChoreographyEvent, SagaId};
+
+const FNV_OFFSET_BASIS: u128 = 0x6c62_272e_07bb_0142_62b8_2175_6295_c58d;
+const FNV_PRIME: u128 = 0x0000_0000_0100_0000_0000_0000_0000_013b;
+
+const EVENT_KEY_TAG: u8 = 0;
+const NAMED_KEY_TAG: u8 = 1;
+const STEP_ATTEMPT_KEY_TAG: u8 = 2;
+const BYTES_KEY_TAG: u8 = 3;
+const RETRY_EVENT_KEY_TAG: u8 = 4;
+
+type DedupeKeyFn = dyn Fn(&SagaChoreographyEvent) -> DedupeKey + Send + Sync;
+
+/// Fixed-size key identifying one processed operation within a saga.
+///
+/// Keys are a 128-bit FNV-1a hash of their parts, computed without
+/// allocating. The hash is stable across processes and releases, so
+/// persisted keys stay valid after a restart. Event keys cover the trace id,
+/// saga start time, event type and step name (plus the failed step of a
+/// `CompensationRequested`); redeliveries of one event share a key while
+/// re-publications under a new trace id do not. Events of a retried attempt
+/// (`attempt > 0`) also cover the attempt, under their own tag, so a retry
+/// published under the trace of the attempt it replaces is not swallowed;
+/// first-attempt keys are unchanged.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DedupeKey(u128);
+
+struct KeyHasher(u128);
+
+impl KeyHasher {
+    fn new(tag: u8) -> Self {
+        let mut hasher = Self(FNV_OFFSET_BASIS);
+        hasher.write(&[tag]);
+        hasher
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u128::from(*byte);
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    /// Length-prefixed so adjacent strings cannot run into each other.
+    fn write_str(&mut self, value: &str) {
+        self.write_u64(value.len() as u64);
+        self.write(value.as_bytes());
+    }
+}
+
+impl DedupeKey {
+    /// Key of an incoming choreography event.
+    pub fn from_event(event: &SagaChoreographyEvent) -> Self {
+        let context = event.context();
+        let failed_step = match event {
+            SagaChoreographyEvent::CompensationRequested { failed_step, .. } => {
+                Some(failed_step.as_str())
+            }
+            _ => None,
+        };
+        Self::event_key(
+            context.attempt,
+            context.trace_id,
+            context.saga_started_at_millis,
+            event.event_type(),
+            &context.step_name,
+            failed_step,
+        )
+    }
+
+    fn event_key(
+        attempt: u32,
+        trace_id: u64,
+        saga_started_at_millis: u64,
+        event_type: &str,
+        step_name: &str,
+        failed_step: Option<&str>,
+    ) -> Self {
+        let mut hasher = if attempt == 0 {
+            KeyHasher::new(EVENT_KEY_TAG)
+        } else {
+            let mut hasher = KeyHasher::new(RETRY_EVENT_KEY_TAG);
+            hasher.write_u64(u64::from(attempt));
+            hasher
+        };
+        hasher.write_u64(trace_id);
+        hasher.write_u64(saga_started_at_millis);
+        hasher.write_str(event_type);
+        hasher.write_str(step_name);
+        if let Some(failed_step) = failed_step {
+            hasher.write_str(failed_step);
+        }
+        Self(hasher.0)
+    }
+
+    /// Key of a string dedupe key written before keys were hashed:
+    /// `{trace_id}:{saga_started_at_millis}:{event_type}:{step_name}`, with
+    /// `:{failed_step}` appended for `compensation_requested`, is the
+    /// [`Self::from_event`] key of that first-attempt event; any other string
+    /// is a [`Self::named`] key.
+    pub fn from_legacy(key: &str) -> Self {
+        let mut parts = key.splitn(4, ':');
+        let (Some(trace_id), Some(started), Some(event_type), Some(rest)) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Self::named(key);
+        };
+        let (Ok(trace_id), Ok(started)) = (trace_id.parse::<u64>(), started.parse::<u64>()) else {
+            return Self::named(key);
+        };
+        let (step_name, failed_step) = match rest.split_once(':') {
+            Some((step_name, failed_step)) if event_type == "compensation_requested" => {
+                (step_name, Some(failed_step))
+            }
+            _ => (rest, None),
+        };
+        Self::event_key(0, trace_id, started, event_type, step_name, failed_step)
+    }
+
+    /// Key of an incoming event by saga id, event type, step name and
+    /// attempt, whatever trace it was published under.
+    pub fn from_step_attempt(event: &SagaChoreographyEvent) -> Self {
+        let context = event.context();
+        let mut hasher = KeyHasher::new(STEP_ATTEMPT_KEY_TAG);
+        hasher.write_u64(context.saga_id.get());
+        hasher.write_str(event.event_type());
+        hasher.write_str(&context.step_name);
+        hasher.write_u64(u64::from(context.attempt));
+        if let SagaChoreographyEvent::CompensationRequested { failed_step, .. } = event {
+            hasher.write_str(failed_step);
+        }
+        Self(hasher.0)
+    }
+
+    /// Key of an event identified by caller-chosen bytes, for
+    /// [`DedupeIdentity::custom`] extractors.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let mut hasher = KeyHasher::new(BYTES_KEY_TAG);
+        hasher.write_u64(bytes.len() as u64);
+        hasher.write(bytes);
+        Self(hasher.0)
+    }
+
+    /// Key of a named operation, e.g. a one-off publish guarded per saga.
+    pub fn named(name: &str) -> Self {
+        let mut hasher = KeyHasher::new(NAMED_KEY_TAG);
+        hasher.write_str(name);
+        Self(hasher.0)
+    }
+
+    pub fn as_u128(self) -> u128 {
+        self.0
+    }
+}
+
+impl From<&str> for DedupeKey {
+    fn from(name: &str) -> Self {
+        Self::named(name)
+    }
+}
+
+impl std::fmt::Display for DedupeKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:032x}", self.0)
+    }
+}
+
+impl std::fmt::Debug for DedupeKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "DedupeKey({self})")
+    }
+}
+
+/// What makes two incoming events the same event to the dedupe check.
+///
+/// Set per participant with
+/// [`SagaParticipantSupport::with_dedupe_identity`](crate::SagaParticipantSupport::with_dedupe_identity);
+/// the ingress helpers and [`restore_dedupe_state`](crate::restore_dedupe_state)
+/// key events with it. Changing it on a participant with sagas in flight
+/// lets redeliveries of their already processed events through once.
+#[derive(Clone, Default)]
+pub enum DedupeIdentity {
+    /// [`DedupeKey::from_event`]: trace id, saga start, event type and step.
+    /// A re-publication under a new trace id is a new event.
+    #[default]
+    TraceAndType,
+    /// [`DedupeKey::from_step_attempt`]: saga id, event type, step and
+    /// attempt. A re-publication under a new trace id is a duplicate; so is
+    /// the `SagaStarted` of a new run reusing a saga id before the old run
+    /// was pruned.
+    SagaStepAttempt,
+    /// Keys computed by the caller.
+    Custom(Arc<DedupeKeyFn>),
+}
+
+impl DedupeIdentity {
+    pub fn custom<F>(key: F) -> Self
+    where
+        F: Fn(&SagaChoreographyEvent) -> DedupeKey + Send + Sync + 'static,
+    {
+        Self::Custom(Arc::new(key))
+    }
+
+    pub fn key(&self, event: &SagaChoreographyEvent) -> DedupeKey {
+        match self {
+            Self::TraceAndType => DedupeKey::from_event(event),
+            Self::SagaStepAttempt => DedupeKey::from_step_attempt(event),
+            Self::Custom(key) => key(event),
+        }
+    }
+}
+
+impl std::fmt::Debug for DedupeIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TraceAndType => f.write_str("TraceAndType"),
+            Self::SagaStepAttempt => f.write_str("SagaStepAttempt"),
+            Self::Custom(_) => f.write_str("Custom"),
+        }
+    }
+}
 
 /// A trait for participant deduplication storage implementations.
 ///
@@ -37,7 +263,7 @@ use super::SagaId;
 /// ```ignore
 /// let dedupe = InMemoryDedupe::new();
 /// let saga_id = SagaId::new(1);
-/// let operation_key = "reserve_inventory";
+/// let operation_key = DedupeKey::named("reserve_inventory");
 ///
 /// // Check and mark atomically - returns true if this is a new operation
 /// if dedupe.check_and_mark(saga_id, operation_key)? {
@@ -69,7 +295,7 @@ pub trait ParticipantDedupeStore: Send + Sync + 'static {
     /// # Errors
     ///
     /// Returns [`DedupeError::Storage`] if the underlying storage fails.
-    fn check_and_mark(&self, saga_id: SagaId, key: &str) -> Result<bool, DedupeError>;
+    fn check_and_mark(&self, saga_id: SagaId, key: DedupeKey) -> Result<bool, DedupeError>;
 
     /// Checks if an operation has already been processed without modifying state.
     ///
@@ -84,7 +310,7 @@ pub trait ParticipantDedupeStore: Send + Sync + 'static {
     /// # Returns
     ///
     /// `true` if the operation has been marked as processed, `false` otherwise.
-    fn contains(&self, saga_id: SagaId, key: &str) -> bool;
+    fn contains(&self, saga_id: SagaId, key: DedupeKey) -> bool;
 
     /// Marks an operation as processed without checking first.
     ///
@@ -99,7 +325,7 @@ pub trait ParticipantDedupeStore: Send + Sync + 'static {
     /// # Errors
     ///
     /// Returns [`DedupeError::Storage`] if the underlying storage fails.
-    fn mark_processed(&self, saga_id: SagaId, key: &str) -> Result<(), DedupeError>;
+    fn mark_processed(&self, saga_id: SagaId, key: DedupeKey) -> Result<(), DedupeError>;
 
     /// Removes all deduplication records for a completed SAGA.
     ///
@@ -115,6 +341,23 @@ pub trait ParticipantDedupeStore: Send + Sync + 'static {
     ///
     /// Returns [`DedupeError::Storage`] if the underlying storage fails.
     fn prune(&self, saga_id: SagaId) -> Result<(), DedupeError>;
+
+    /// Lists every SAGA with at least one deduplication record, in ascending
+    /// id order.
+    ///
+    /// Startup integrity checks compare this against the journal. Returns
+    /// `None` when the store cannot enumerate its records; those checks are
+    /// skipped then.
+    fn list_sagas(&self) -> Result<Option<Vec<SagaId>>, DedupeError> {
+        Ok(None)
+    }
+
+    /// The async store this one wraps, which the async handlers await
+    /// instead of calling this one. `None` for sync stores; only
+    /// [`crate::AsyncDedupeAdapter`] has one.
+    fn as_async(&self) -> Option<&dyn crate::AsyncParticipantDedupeStore> {
+        None
+    }
 }
 
 /// Errors that can occur during deduplication operations.
@@ -144,8 +387,8 @@ pub enum DedupeError {
 ///
 /// Uses `RwLock` internally to provide thread-safe access to the store.
 pub struct InMemoryDedupe {
-    /// The backing store containing tuples of (SAGA ID, operation key).
-    data: std::sync::RwLock<std::collections::HashSet<(u64, Box<str>)>>,
+    /// The backing store containing tuples of (SAGA ID, dedupe key).
+    data: std::sync::RwLock<std::collections::HashSet<(u64, DedupeKey)>>,
 }
 
 impl InMemoryDedupe {
@@ -158,8 +401,8 @@ impl InMemoryDedupe {
 }
 
 impl ParticipantDedupeStore for InMemoryDedupe {
-    fn check_and_mark(&self, saga_id: SagaId, key: &str) -> Result<bool, DedupeError> {
-        let entry = (saga_id.0, key.into());
+    fn check_and_mark(&self, saga_id: SagaId, key: DedupeKey) -> Result<bool, DedupeError> {
+        let entry = (saga_id.0, key);
         let mut data = self
             .data
             .write()
@@ -167,9 +410,9 @@ impl ParticipantDedupeStore for InMemoryDedupe {
         Ok(data.insert(entry))
     }
 
-    fn contains(&self, saga_id: SagaId, key: &str) -> bool {
+    fn contains(&self, saga_id: SagaId, key: DedupeKey) -> bool {
         match self.data.read() {
-            Ok(data) => data.contains(&(saga_id.0, key.into())),
+            Ok(data) => data.contains(&(saga_id.0, key)),
             Err(err) => {
                 tracing::error!(
                     target: "core::saga",
@@ -181,12 +424,12 @@ impl ParticipantDedupeStore for InMemoryDedupe {
         }
     }
 
-    fn mark_processed(&self, saga_id: SagaId, key: &str) -> Result<(), DedupeError> {
+    fn mark_processed(&self, saga_id: SagaId, key: DedupeKey) -> Result<(), DedupeError> {
         let mut data = self
             .data
             .write()
             .map_err(|e| DedupeError::Storage(e.to_string().into()))?;
-        data.insert((saga_id.0, key.into()));
+        data.insert((saga_id.0, key));
         Ok(())
     }
 
@@ -198,6 +441,15 @@ impl ParticipantDedupeStore for InMemoryDedupe {
         data.retain(|(id, _)| *id != saga_id.0);
         Ok(())
     }
+
+    fn list_sagas(&self) -> Result<Option<Vec<SagaId>>, DedupeError> {
+        let data = self
+            .data
+            .read()
+            .map_err(|e| DedupeError::Storage(e.to_string().into()))?;
+        let ids: std::collections::BTreeSet<u64> = data.iter().map(|(id, _)| *id).collect();
+        Ok(Some(ids.into_iter().map(SagaId::new).collect()))
+    }
 }
 
 impl Default for InMemoryDedupe {
@@ -210,19 +462,112 @@ impl<T> ParticipantDedupeStore for std::sync::Arc<T>
 where
     T: ParticipantDedupeStore + ?Sized,
 {
-    fn check_and_mark(&self, saga_id: SagaId, key: &str) -> Result<bool, DedupeError> {
+    fn check_and_mark(&self, saga_id: SagaId, key: DedupeKey) -> Result<bool, DedupeError> {
         (**self).check_and_mark(saga_id, key)
     }
 
-    fn contains(&self, saga_id: SagaId, key: &str) -> bool {
+    fn contains(&self, saga_id: SagaId, key: DedupeKey) -> bool {
         (**self).contains(saga_id, key)
     }
 
-    fn mark_processed(&self, saga_id: SagaId, key: &str) -> Result<(), DedupeError> {
+    fn mark_processed(&self, saga_id: SagaId, key: DedupeKey) -> Result<(), DedupeError> {
         (**self).mark_processed(saga_id, key)
     }
 
     fn prune(&self, saga_id: SagaId) -> Result<(), DedupeError> {
         (**self).prune(saga_id)
     }
+
+    fn list_sagas(&self) -> Result<Option<Vec<SagaId>>, DedupeError> {
+        (**self).list_sagas()
+    }
+
+    fn as_async(&self) -> Option<&dyn crate::AsyncParticipantDedupeStore> {
+        (**self).as_async()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DeterministicContextBuilder;
+
+    #[test]
+    fn event_keys_follow_trace_id_event_type_and_failed_step() {
+        let context = DeterministicContextBuilder::default().build();
+        let started = crate::saga_started(context.clone(), Vec::new());
+        assert_eq!(
+            DedupeKey::from_event(&started),
+            DedupeKey::from_event(&started.clone())
+        );
+
+        let completed = SagaChoreographyEvent::SagaCompleted {
+            context: context.clone(),
+        };
+        assert_ne!(
+            DedupeKey::from_event(&started),
+            DedupeKey::from_event(&completed)
+        );
+
+        let mut republished = context.clone();
+        republished.trace_id += 1;
+        assert_ne!(
+            DedupeKey::from_event(&completed),
+            DedupeKey::from_event(&SagaChoreographyEvent::SagaCompleted {
+                context: republished
+            })
+        );
+
+        let requested = |failed_step: &str| SagaChoreographyEvent::CompensationRequested {
+            context: context.clone(),
+            failed_step: failed_step.into(),
+            reason: "failed".into(),
+            steps_to_compensate: Vec::new(),
+        };
+        assert_ne!(
+            DedupeKey::from_event(&requested("reserve")),
+            DedupeKey::from_event(&requested("charge"))
+        );
+        assert_ne!(
+            DedupeKey::from_event(&started),
+            DedupeKey::named("saga_started")
+        );
+    }
+
+    #[test]
+    fn retried_attempts_under_one_trace_get_their_own_keys() {
+        let context = DeterministicContextBuilder::default().build();
+        let started = |attempt: u32| {
+            let mut context = context.clone();
+            context.attempt = attempt;
+            crate::saga_started(context, Vec::new())
+        };
+        let dedupe = InMemoryDedupe::new();
+        let saga_id = context.saga_id;
+        assert!(dedupe
+            .check_and_mark(saga_id, DedupeKey::from_event(&started(0)))
+            .unwrap());
+        assert!(dedupe
+            .check_and_mark(saga_id, DedupeKey::from_event(&started(1)))
+            .unwrap());
+        assert!(!dedupe
+            .check_and_mark(saga_id, DedupeKey::from_event(&started(1)))
+            .unwrap());
+        assert_ne!(
+            DedupeKey::from_event(&started(1)),
+            DedupeKey::from_event(&started(2))
+        );
+    }
+
+    #[test]
+    fn in_memory_store_marks_keys_per_saga() {
+        let dedupe = InMemoryDedupe::new();
+        let key = DedupeKey::named("reserve_inventory");
+        assert!(dedupe.check_and_mark(SagaId::new(1), key).unwrap());
+        assert!(!dedupe.check_and_mark(SagaId::new(1), key).unwrap());
+        assert!(!dedupe.contains(SagaId::new(2), key));
+        dedupe.prune(SagaId::new(1)).unwrap();
+        assert!(!dedupe.contains(SagaId::new(1), key));
+        assert_eq!(key.to_string().len(), 32);
+    }
 }
@@ -0,0 +1,183 @@
+//! Graceful shutdown for saga participants.
+//!
+//! [`drain`] is meant for a participant's shutdown hook. It stops the
+//! participant from accepting new `SagaStarted` events, keeps replaying the
+//! journal inbox until no saga is left executing or compensating (or the
+//! deadline passes), and journals a [`ParticipantEvent::Parked`] marker for
+//! each saga still in flight. The returned [`DrainReport`] tells the
+//! supervisor whether exiting now leaves work for startup recovery.
+//!
+//! Refused starts are neither deduped nor journaled, so another instance (or
+//! this one after restart) can still take them.
+
+use std::time::{Duration, Instant};
+
+use crate::state_ext::try_record_event_async;
+use crate::{
+    replay_async_saga_inbox_with_emit, replay_saga_inbox_with_emit, AsyncSagaParticipant,
+    ParticipantEvent, SagaChoreographyEvent, SagaId, SagaParticipant, SagaStateEntry, SagaStateExt,
+    StepName,
+};
+
+/// Reason journaled with the [`ParticipantEvent::Parked`] marker.
+pub const DRAIN_PARK_REASON: &str = "participant draining";
+
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InFlightPhase {
+    Executing,
+    Compensating,
+}
+
+/// A saga whose step or compensation had started but not finished.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InFlightSaga {
+    pub saga_id: SagaId,
+    pub step_name: StepName,
+    pub phase: InFlightPhase,
+    /// When the execution or compensation attempt started.
+    pub started_at_millis: u64,
+}
+
+/// Outcome of [`drain`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DrainReport {
+    /// Inbox events re-dispatched while waiting.
+    pub replayed: usize,
+    /// Sagas still in flight at the deadline, each journaled as parked.
+    pub parked: Vec<InFlightSaga>,
+}
+
+impl DrainReport {
+    /// Whether every saga settled before the deadline.
+    pub fn is_clean(&self) -> bool {
+        self.parked.is_empty()
+    }
+}
+
+/// Sagas of `participant` currently executing or compensating.
+pub fn in_flight_sagas<P>(participant: &P) -> Vec<InFlightSaga>
+where
+    P: SagaStateExt,
+{
+    let mut in_flight: Vec<InFlightSaga> = participant
+        .saga_support()
+        .saga_states
+        .values()
+        .filter_map(|entry| match entry {
+            SagaStateEntry::Executing(state) => Some(InFlightSaga {
+                saga_id: state.saga_id,
+                step_name: state.step_name.clone(),
+                phase: InFlightPhase::Executing,
+                started_at_millis: state.state.started_at_millis,
+            }),
+            SagaStateEntry::Compensating(state) => Some(InFlightSaga {
+                saga_id: state.saga_id,
+                step_name: state.step_name.clone(),
+                phase: InFlightPhase::Compensating,
+                started_at_millis: state.state.started_at_millis,
+            }),
+            _ => None,
+        })
+        .collect();
+    in_flight.sort_by_key(|saga| saga.saga_id);
+    in_flight
+}
+
+/// Drains a [`SagaParticipant`] ahead of shutdown.
+///
+/// Blocks the calling actor until `deadline`; events produced by replayed
+/// inbox entries go to `emit` as with [`replay_saga_inbox_with_emit`].
+pub fn drain<P, F>(participant: &mut P, deadline: Instant, mut emit: F) -> DrainReport
+where
+    P: SagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    begin_drain(participant);
+    let mut report = DrainReport::default();
+    loop {
+        report.replayed += replay_saga_inbox_with_emit(participant, &mut emit);
+        if in_flight_sagas(participant).is_empty() || Instant::now() >= deadline {
+            break;
+        }
+        std::thread::sleep(
+            DRAIN_POLL_INTERVAL.min(deadline.saturating_duration_since(Instant::now())),
+        );
+    }
+    report.parked = park_in_flight(participant);
+    report
+}
+
+/// Async counterpart of [`drain`]; waits without blocking the runtime.
+pub async fn drain_async<P, F>(participant: &mut P, deadline: Instant, mut emit: F) -> DrainReport
+where
+    P: AsyncSagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    begin_drain(participant);
+    let mut report = DrainReport::default();
+    loop {
+        report.replayed += replay_async_saga_inbox_with_emit(participant, &mut emit).await;
+        if in_flight_sagas(participant).is_empty() || Instant::now() >= deadline {
+            break;
+        }
+        tokio::time::sleep(
+            DRAIN_POLL_INTERVAL.min(deadline.saturating_duration_since(Instant::now())),
+        )
+        .await;
+    }
+    report.parked = park_in_flight_async(participant).await;
+    report
+}
+
+fn begin_drain<P>(participant: &mut P)
+where
+    P: SagaStateExt,
+{
+    participant.saga_support_mut().draining = true;
+    tracing::info!(
+        target: "core::saga",
+        event = "saga_participant_draining",
+        in_flight = in_flight_sagas(participant).len()
+    );
+}
+
+fn park_in_flight<P>(participant: &P) -> Vec<InFlightSaga>
+where
+    P: SagaStateExt,
+{
+    let in_flight = in_flight_sagas(participant);
+    let now = participant.now_millis();
+    for saga in &in_flight {
+        participant.record_event(saga.saga_id, parked_entry(saga, now));
+    }
+    in_flight
+}
+
+/// [`park_in_flight`] for async participants, awaiting an async journal.
+async fn park_in_flight_async<P>(participant: &P) -> Vec<InFlightSaga>
+where
+    P: SagaStateExt,
+{
+    let in_flight = in_flight_sagas(participant);
+    let now = participant.now_millis();
+    for saga in &in_flight {
+        try_record_event_async(participant, saga.saga_id, parked_entry(saga, now)).await;
+    }
+    in_flight
+}
+
+fn parked_entry(saga: &InFlightSaga, now: u64) -> ParticipantEvent {
+    tracing::warn!(
+        target: "core::saga",
+        event = "saga_parked_for_shutdown",
+        saga_id = saga.saga_id.get(),
+        step_name = saga.step_name.as_ref(),
+        phase = ?saga.phase
+    );
+    ParticipantEvent::Parked {
+        reason: DRAIN_PARK_REASON.into(),
+        parked_at_millis: now,
+    }
+}
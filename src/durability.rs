@@ -1,11 +1,19 @@
 use std::path::{Path, PathBuf};
 
+use crate::correlation::index_step_correlations;
+use crate::effects::dispatch_step_effect;
+use crate::helpers::{journal_outcome, UnjournaledOutcome};
+use crate::journal::last_progress_entry;
+use crate::missing_state::handle_missing_state;
+use crate::payload::{offload_step_output, resolve_step_input};
+use crate::sensitive::{open_compensation_data, seal_compensation_data};
+use crate::SagaSpanExt;
 use crate::{
     handle_async_saga_event_with_emit, handle_saga_event_with_emit, AsyncSagaParticipant,
-    DedupeError, HasSagaParticipantSupport, HasSagaWorkflowParticipants, JournalEntry,
-    JournalError, ParticipantDedupeStore, ParticipantEvent, ParticipantJournal,
-    SagaChoreographyEvent, SagaContext, SagaId, SagaParticipant, SagaParticipantSupport,
-    SagaStateEntry, SagaStateExt, SagaWorkflowParticipant,
+    DeadLetterReason, DedupeError, DedupeKey, HasSagaParticipantSupport,
+    HasSagaWorkflowParticipants, JournalEntry, JournalError, ParticipantDedupeStore,
+    ParticipantEvent, ParticipantJournal, SagaChoreographyEvent, SagaContext, SagaId,
+    SagaParticipant, SagaParticipantSupport, SagaStateEntry, SagaStateExt, SagaWorkflowParticipant,
 };
 
 pub const PANIC_QUARANTINE_REASON_PREFIX: &str = "panic_during_active_";
@@ -73,7 +81,7 @@ pub fn panic_message_from_payload(payload: &(dyn std::any::Any + Send)) -> Box<s
 }
 
 pub fn panic_quarantine_reason_from_entries(entries: &[JournalEntry]) -> Option<Box<str>> {
-    let last = entries.last()?;
+    let last = last_progress_entry(entries)?;
     let ParticipantEvent::Quarantined { reason, .. } = &last.event else {
         return None;
     };
@@ -139,10 +147,13 @@ pub fn apply_sync_participant_saga_ingress_with_hooks<P, FApplyTerminal, FOnInva
 {
     apply_terminal_side_effects(participant, &event);
 
+    // Outcomes are staged in the outbox only when this loop publishes them.
+    let bus_attached = participant.saga_support().bus.is_some();
+    participant.saga_support_mut().stage_emitted = bus_attached;
     let mut emitted = Vec::new();
     handle_saga_event_with_emit(participant, event, |next_event| emitted.push(next_event));
+    participant.saga_support_mut().stage_emitted = false;
 
-    let saga_bus = participant.saga_support().bus.clone();
     for next_event in emitted {
         if !is_valid_emitted_transition(
             participant
@@ -151,21 +162,28 @@ pub fn apply_sync_participant_saga_ingress_with_hooks<P, FApplyTerminal, FOnInva
             &next_event,
         ) {
             on_invalid_transition(&next_event);
+            participant.saga_support().discard_emitted(&next_event);
+            participant.saga_support().dead_letter(
+                DeadLetterReason::Validation,
+                "invalid_emitted_transition",
+                next_event,
+            );
             continue;
         }
 
         on_emitted_transition(participant, &next_event);
 
-        if let Some(bus) = &saga_bus {
-            if let Err(err) = bus.publish_strict(next_event) {
+        if bus_attached {
+            if let Err(err) = participant.saga_support().publish_emitted(next_event) {
                 tracing::error!(
                     target: "core::saga",
                     event = "participant_ingress_emit_publish_failed",
-                    error = ?err
+                    error = %err
                 );
             }
         }
     }
+    participant.saga_support().staged_outgoing.clear();
 }
 
 fn workflow_for_event<A>(
@@ -178,7 +196,7 @@ where
     let mut matches = A::saga_workflows()
         .iter()
         .copied()
-        .filter(|workflow| workflow.saga_types().contains(&saga_type));
+        .filter(|workflow| workflow.matches_saga_type(saga_type));
     let selected = matches.next();
     if matches.next().is_some() {
         return Err(format!(
@@ -228,7 +246,13 @@ pub fn apply_sync_workflow_participant_saga_ingress_with_hooks<
 {
     let workflow = match workflow_for_event::<A>(&event) {
         Ok(Some(workflow)) => workflow,
-        Ok(None) => return,
+        Ok(None) => {
+            // Fan-out of workflows this actor does not join; dropped like
+            // saga types a participant does not list, unless it opted in to
+            // dead-lettering them.
+            crate::helpers::drop_unknown_saga(actor, event);
+            return;
+        }
         Err(err) => {
             let framework_failure = SagaChoreographyEvent::SagaFailed {
                 context: event
@@ -253,33 +277,130 @@ pub fn apply_sync_workflow_participant_saga_ingress_with_hooks<
 
     apply_terminal_side_effects(actor, &event);
 
+    let bus_attached = actor.saga_support().bus.is_some();
+    actor.saga_support_mut().stage_emitted = bus_attached;
     let mut emitted = Vec::new();
     handle_workflow_saga_event_with_emit(actor, workflow, event, |next_event| {
         emitted.push(next_event)
     });
+    actor.saga_support_mut().stage_emitted = false;
+
+    publish_workflow_emitted_transitions(
+        actor,
+        emitted,
+        &mut on_invalid_transition,
+        &mut on_emitted_transition,
+    );
+}
+
+/// Re-processes workflow saga events left pending in the journal inbox.
+///
+/// Pending entries already passed dedupe before the previous process stopped,
+/// so they are dispatched directly. Emitted transitions are validated and
+/// published the same way as live ingress.
+///
+/// Returns the number of replayed events.
+pub fn replay_sync_workflow_participant_saga_inbox<A>(actor: &mut A) -> usize
+where
+    A: HasSagaParticipantSupport + HasSagaWorkflowParticipants + Send + 'static,
+{
+    let pending = crate::helpers::pending_incoming_events(actor);
+    let replayed = pending.len();
+    replay_unjournaled_workflow_outcomes(actor);
+    for entry in pending {
+        let parked = crate::helpers::park_copy(actor, &entry.event);
+        match workflow_for_event::<A>(&entry.event) {
+            Ok(Some(workflow)) => {
+                let bus_attached = actor.saga_support().bus.is_some();
+                actor.saga_support_mut().stage_emitted = bus_attached;
+                let mut emitted = Vec::new();
+                dispatch_workflow_saga_event_with_emit(
+                    actor,
+                    workflow,
+                    entry.event,
+                    &mut |next_event| emitted.push(next_event),
+                );
+                actor.saga_support_mut().stage_emitted = false;
+                publish_workflow_emitted_transitions(
+                    actor,
+                    emitted,
+                    &mut |_event: &SagaChoreographyEvent| {},
+                    &mut |_actor: &mut A, _event: &SagaChoreographyEvent| {},
+                );
+            }
+            Ok(None) => {}
+            Err(err) => {
+                tracing::error!(
+                    target: "core::saga",
+                    event = "workflow_inbox_replay_resolution_failed",
+                    saga_id = entry.saga_id.get(),
+                    error = %err
+                );
+            }
+        }
+        crate::helpers::finish_incoming(actor, entry.saga_id, entry.inbox_id, parked);
+    }
+    replayed
+}
+
+/// Journals the workflow outcomes held back while the journal rejected them
+/// and publishes their events the way live ingress does.
+fn replay_unjournaled_workflow_outcomes<A>(actor: &mut A)
+where
+    A: HasSagaParticipantSupport,
+{
+    let bus_attached = actor.saga_support().bus.is_some();
+    actor.saga_support_mut().stage_emitted = bus_attached;
+    let mut emitted = Vec::new();
+    crate::helpers::flush_unjournaled_outcomes(actor, &mut |event| emitted.push(event));
+    actor.saga_support_mut().stage_emitted = false;
+    publish_workflow_emitted_transitions(
+        actor,
+        emitted,
+        &mut |_event: &SagaChoreographyEvent| {},
+        &mut |_actor: &mut A, _event: &SagaChoreographyEvent| {},
+    );
+}
 
-    let saga_bus = actor.saga_support().bus.clone();
+fn publish_workflow_emitted_transitions<A, FOnInvalid, FOnEmitted>(
+    actor: &mut A,
+    emitted: Vec<SagaChoreographyEvent>,
+    on_invalid_transition: &mut FOnInvalid,
+    on_emitted_transition: &mut FOnEmitted,
+) where
+    A: HasSagaParticipantSupport,
+    FOnInvalid: FnMut(&SagaChoreographyEvent),
+    FOnEmitted: FnMut(&mut A, &SagaChoreographyEvent),
+{
+    let bus_attached = actor.saga_support().bus.is_some();
     for next_event in emitted {
         if !is_valid_emitted_transition(
             actor.saga_states_ref().get(&next_event.context().saga_id),
             &next_event,
         ) {
             on_invalid_transition(&next_event);
+            actor.saga_support().discard_emitted(&next_event);
+            actor.saga_support().dead_letter(
+                DeadLetterReason::Validation,
+                "invalid_emitted_transition",
+                next_event,
+            );
             continue;
         }
 
         on_emitted_transition(actor, &next_event);
 
-        if let Some(bus) = &saga_bus {
-            if let Err(err) = bus.publish_strict(next_event) {
+        if bus_attached {
+            if let Err(err) = actor.saga_support().publish_emitted(next_event) {
                 tracing::error!(
                     target: "core::saga",
                     event = "workflow_participant_ingress_emit_publish_failed",
-                    error = ?err
+                    error = %err
                 );
             }
         }
     }
+    actor.saga_support().staged_outgoing.clear();
 }
 
 fn handle_workflow_saga_event_with_emit<A, F>(
@@ -291,52 +412,94 @@ fn handle_workflow_saga_event_with_emit<A, F>(
     A: HasSagaParticipantSupport,
     F: FnMut(SagaChoreographyEvent),
 {
-    let context = event.context().clone();
-    let now = actor.now_millis();
+    let context = event.context();
+    let saga_id = context.saga_id;
 
-    if !workflow
-        .saga_types()
-        .iter()
-        .any(|t| *t == context.saga_type.as_ref())
-    {
+    if !workflow.matches_saga_type(context.saga_type.as_ref()) {
+        return;
+    }
+    if crate::helpers::filtered_out(actor, &event) {
+        return;
+    }
+    if !workflow.supports_workflow_version(actor, context.workflow_version) {
+        crate::helpers::ignore_unsupported_workflow_version(context);
         return;
     }
 
     let is_saga_started = matches!(event, SagaChoreographyEvent::SagaStarted { .. });
-    if !is_saga_started && actor.is_terminal_saga_latched(context.saga_id) {
+    if !is_saga_started && actor.is_terminal_saga_latched(saga_id) {
+        return;
+    }
+    if is_saga_started && crate::helpers::refuse_saga_start_while_draining(actor, context) {
+        return;
+    }
+
+    if let Err(error) = crate::helpers::check_event_skew(actor, context) {
+        crate::helpers::reject_incoming_event(
+            actor,
+            saga_id,
+            event.event_type(),
+            &error,
+            None,
+            None,
+        );
         return;
     }
 
-    let dedupe_key = workflow_dedupe_key_for_event(&event);
-    if !actor.check_dedupe(context.saga_id, &dedupe_key) {
+    crate::helpers::flush_unjournaled_outcomes(actor, &mut emit);
+    let dedupe_key = actor.saga_support().dedupe_identity.key(&event);
+    let inbox_id = actor.record_incoming(saga_id, dedupe_key, &event);
+    if !crate::helpers::admit_incoming_event(actor, &event, dedupe_key, inbox_id) {
+        return;
+    }
+    if let Err(error) = workflow.authorize_event(actor, context, event.event_type()) {
+        crate::helpers::reject_incoming_event(
+            actor,
+            saga_id,
+            event.event_type(),
+            &error,
+            Some(dedupe_key),
+            inbox_id,
+        );
         return;
     }
+    crate::helpers::apply_projections(actor, &event);
+
+    let parked = crate::helpers::park_copy(actor, &event);
+    dispatch_workflow_saga_event_with_emit(actor, workflow, event, &mut emit);
+    crate::helpers::finish_incoming(actor, saga_id, inbox_id, parked);
+}
+
+fn dispatch_workflow_saga_event_with_emit<A, F>(
+    actor: &mut A,
+    workflow: &'static dyn SagaWorkflowParticipant<A>,
+    event: SagaChoreographyEvent,
+    emit: &mut F,
+) where
+    A: HasSagaParticipantSupport,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let now = actor.now_millis();
 
+    // Each arm takes the context out of the event instead of cloning it.
     match event {
-        SagaChoreographyEvent::SagaStarted { payload, .. }
+        SagaChoreographyEvent::SagaStarted { context, payload }
             if workflow.depends_on().is_on_saga_start() =>
         {
             actor.unlatch_terminal_saga(context.saga_id);
-            actor.saga_states().remove(&context.saga_id);
+            actor.clear_saga_state(context.saga_id);
             actor.dependency_completions().remove(&context.saga_id);
             actor.dependency_fired().remove(&context.saga_id);
-            execute_workflow_step_with_emit(
-                actor,
-                workflow,
-                context.clone(),
-                payload,
-                now,
-                &mut emit,
-            );
+            execute_workflow_step_with_emit(actor, workflow, context, payload, now, emit);
         }
-        SagaChoreographyEvent::SagaStarted { .. } => {
+        SagaChoreographyEvent::SagaStarted { context, .. } => {
             actor.unlatch_terminal_saga(context.saga_id);
-            actor.saga_states().remove(&context.saga_id);
+            actor.clear_saga_state(context.saga_id);
             actor.dependency_completions().remove(&context.saga_id);
             actor.dependency_fired().remove(&context.saga_id);
         }
         SagaChoreographyEvent::StepCompleted {
-            context: step_ctx,
+            context,
             output,
             saga_input,
             ..
@@ -346,7 +509,7 @@ fn handle_workflow_saga_event_with_emit<A, F>(
                 actor,
                 context.saga_id,
                 &dependency_spec,
-                &step_ctx.step_name,
+                &context.step_name,
             );
             if should_fire {
                 let next_context = context.next_step(workflow.step_name().into());
@@ -355,35 +518,37 @@ fn handle_workflow_saga_event_with_emit<A, F>(
                 } else {
                     output
                 };
-                execute_workflow_step_with_emit(
-                    actor,
-                    workflow,
-                    next_context,
-                    input,
-                    now,
-                    &mut emit,
-                );
+                execute_workflow_step_with_emit(actor, workflow, next_context, input, now, emit);
             }
         }
         SagaChoreographyEvent::CompensationRequested {
+            context,
             steps_to_compensate,
             ..
         } => {
-            if steps_to_compensate.contains(&workflow.step_name().into()) {
-                compensate_workflow_with_emit(actor, workflow, &context, now, &mut emit);
+            let step_name = workflow.step_name();
+            if steps_to_compensate.iter().any(|step| *step == step_name) {
+                compensate_workflow_with_emit(actor, workflow, &context, now, emit);
             }
         }
-        SagaChoreographyEvent::SagaCompleted { .. } => {
+        SagaChoreographyEvent::SagaCompleted { context } if context.parent_saga_id.is_some() => {
+            crate::helpers::hold_completion_for_parent(&context, workflow.step_name());
+        }
+        SagaChoreographyEvent::SagaCompleted { context } => {
             actor.latch_terminal_saga(context.saga_id);
             workflow.on_saga_completed(actor, &context);
             actor.prune_saga(context.saga_id);
         }
-        SagaChoreographyEvent::SagaFailed { reason, .. } => {
+        SagaChoreographyEvent::SagaFailed {
+            context, reason, ..
+        } => {
             actor.latch_terminal_saga(context.saga_id);
             workflow.on_saga_failed(actor, &context, &reason);
             actor.prune_saga(context.saga_id);
         }
-        SagaChoreographyEvent::SagaQuarantined { reason, .. } => {
+        SagaChoreographyEvent::SagaQuarantined {
+            context, reason, ..
+        } => {
             actor.latch_terminal_saga(context.saga_id);
             workflow.on_quarantined(actor, &context, &reason);
             actor.prune_saga(context.saga_id);
@@ -410,19 +575,19 @@ where
             actor.dependency_fired().insert(saga_id)
         }
         crate::DependencySpec::AnyOf(steps) => {
-            if !steps.contains(&completed_step) {
+            if !steps.iter().any(|step| *step == completed_step) {
                 return false;
             }
             actor.dependency_fired().insert(saga_id)
         }
         crate::DependencySpec::AllOf(steps) => {
-            if !steps.contains(&completed_step) {
+            if !steps.iter().any(|step| *step == completed_step) {
                 return false;
             }
             {
                 let seen = actor.dependency_completions().entry(saga_id).or_default();
                 seen.insert(completed_step.into());
-                if !steps.iter().all(|step| seen.contains(*step)) {
+                if !steps.iter().all(|step| seen.contains(step.as_str())) {
                     return false;
                 }
             }
@@ -431,27 +596,6 @@ where
     }
 }
 
-fn workflow_dedupe_key_for_event(event: &SagaChoreographyEvent) -> String {
-    let context = event.context();
-    match event {
-        SagaChoreographyEvent::CompensationRequested { failed_step, .. } => format!(
-            "{}:{}:{}:{}:{}",
-            context.trace_id,
-            context.saga_started_at_millis,
-            event.event_type(),
-            context.step_name,
-            failed_step
-        ),
-        _ => format!(
-            "{}:{}:{}:{}",
-            context.trace_id,
-            context.saga_started_at_millis,
-            event.event_type(),
-            context.step_name
-        ),
-    }
-}
-
 fn execute_workflow_step_with_emit<A, F>(
     actor: &mut A,
     workflow: &'static dyn SagaWorkflowParticipant<A>,
@@ -476,27 +620,91 @@ fn execute_workflow_step_with_emit<A, F>(
     .trigger("dependency_satisfied", now)
     .start_execution(now);
 
-    actor.record_event(
-        saga_id,
-        ParticipantEvent::StepExecutionStarted {
-            attempt: 1,
-            started_at_millis: now,
-        },
-    );
-    actor
-        .saga_states()
-        .insert(saga_id, SagaStateEntry::Executing(state));
+    match crate::helpers::gate_step_start(actor, &context, workflow.step_name(), now) {
+        crate::helpers::StepStartGate::Proceed => {}
+        crate::helpers::StepStartGate::Skip | crate::helpers::StepStartGate::Park => return,
+        crate::helpers::StepStartGate::Refuse { reason } => {
+            actor.put_saga_state(saga_id, SagaStateEntry::Executing(state));
+            fail_unstarted_workflow_step(
+                actor,
+                workflow,
+                &context,
+                crate::StepError::require_compensation(reason),
+                now,
+                emit,
+            );
+            return;
+        }
+    }
+    actor.put_saga_state(saga_id, SagaStateEntry::Executing(state));
 
     emit(SagaChoreographyEvent::StepStarted {
         context: context.next_step(workflow.step_name().into()),
     });
 
-    match workflow.execute_step(actor, &context, &input) {
+    let resolved = match resolve_step_input(actor.saga_support().payloads.as_deref(), &input) {
+        Ok(resolved) => resolved,
+        Err(err) => {
+            fail_workflow_step(
+                actor,
+                workflow,
+                &context,
+                crate::StepError::terminal(err.to_string()),
+                now,
+                emit,
+            );
+            return;
+        }
+    };
+    let result = context
+        .step_span(workflow.step_name())
+        .in_scope(|| {
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                workflow.execute_step(actor, &context, &resolved)
+            }))
+        })
+        .unwrap_or_else(|payload| {
+            Err(crate::StepError::terminal(
+                crate::helpers::contained_panic_reason(
+                    actor,
+                    &context,
+                    workflow.step_name(),
+                    payload.as_ref(),
+                ),
+            ))
+        });
+    crate::helpers::hold_step_lease(actor, saga_id, workflow.step_name(), now);
+    match result {
         Ok(output) => complete_workflow_step(actor, workflow, &context, input, output, now, emit),
         Err(error) => fail_workflow_step(actor, workflow, &context, error, now, emit),
     }
 }
 
+/// Workflow twin of the participant helpers' missing state check.
+fn ensure_workflow_saga_state<A, F>(
+    actor: &mut A,
+    workflow: &'static dyn SagaWorkflowParticipant<A>,
+    context: &SagaContext,
+    expected: crate::StateKind,
+    now: u64,
+    emit: &mut F,
+) -> bool
+where
+    A: HasSagaParticipantSupport,
+    F: FnMut(SagaChoreographyEvent),
+{
+    actor.saga_states_ref().contains_key(&context.saga_id)
+        || handle_missing_state(
+            actor,
+            context,
+            workflow.step_name(),
+            workflow.participant_id_owned(),
+            expected,
+            now,
+            emit,
+        )
+}
+
 fn complete_workflow_step<A, F>(
     actor: &mut A,
     workflow: &'static dyn SagaWorkflowParticipant<A>,
@@ -510,47 +718,115 @@ fn complete_workflow_step<A, F>(
     F: FnMut(SagaChoreographyEvent),
 {
     let saga_id = context.saga_id;
-    let (out_data, comp_data, compensation_available) = match output {
+    let noop = matches!(output, crate::StepOutput::NoOp);
+    let completion_ratio = output.completion_ratio();
+    let (out_data, comp_data, effect) = match output {
         crate::StepOutput::Completed {
             output,
             compensation_data,
-        } => {
-            let compensation_available = !compensation_data.is_empty();
-            (output, compensation_data, compensation_available)
         }
-        crate::StepOutput::CompletedWithEffect {
+        | crate::StepOutput::Partial {
             output,
             compensation_data,
             ..
-        } => {
-            let compensation_available = !compensation_data.is_empty();
-            (output, compensation_data, compensation_available)
+        } => (output, compensation_data, None),
+        crate::StepOutput::CompletedWithEffect {
+            output,
+            compensation_data,
+            effect,
+        } => (output, compensation_data, Some(effect)),
+        crate::StepOutput::NoOp => (Vec::new(), Vec::new(), None),
+    };
+    let correlation_keys = workflow.correlation_keys(actor, context, &out_data);
+    let compensation_available = !comp_data.is_empty();
+    let comp_data = match seal_compensation_data(
+        actor.saga_support().compensation_cipher.as_deref(),
+        comp_data,
+    ) {
+        Ok(sealed) => sealed,
+        Err(err) => {
+            fail_workflow_step(
+                actor,
+                workflow,
+                context,
+                crate::StepError::require_compensation(err.to_string()),
+                now,
+                emit,
+            );
+            return;
         }
     };
+    let support = actor.saga_support();
+    let out_data = offload_step_output(
+        support.payloads.as_deref(),
+        support.payload_offload_threshold,
+        out_data,
+        saga_id,
+        workflow.step_name(),
+    );
 
+    if !ensure_workflow_saga_state(
+        actor,
+        workflow,
+        context,
+        crate::StateKind::Executing,
+        now,
+        emit,
+    ) {
+        return;
+    }
     if let Some(SagaStateEntry::Executing(state)) = actor.saga_states().remove(&saga_id) {
-        let new_state = state.complete(out_data.clone(), comp_data, now);
-        actor
-            .saga_states()
-            .insert(saga_id, SagaStateEntry::Completed(new_state));
+        let new_state = match completion_ratio {
+            _ if noop => state.complete_noop(now),
+            Some(ratio) => state.complete_partial(out_data.clone(), comp_data.clone(), ratio, now),
+            None => state.complete(out_data.clone(), comp_data.clone(), now),
+        };
+        actor.put_saga_state(saga_id, SagaStateEntry::Completed(new_state));
     }
 
     let emitted_output = out_data.clone();
-    actor.record_event(
-        saga_id,
+    let step_completed = SagaChoreographyEvent::StepCompleted {
+        context: context.next_step(workflow.step_name().into()),
+        output: emitted_output.clone(),
+        saga_input,
+        compensation_available,
+        completion_ratio,
+    };
+    let outcome = UnjournaledOutcome::new(
+        context,
+        workflow.step_name(),
+        workflow.participant_id_owned(),
         ParticipantEvent::StepExecutionCompleted {
             output: out_data,
-            compensation_data: vec![],
+            compensation_data: comp_data,
             completed_at_millis: now,
         },
+        vec![step_completed],
     );
+    let (journaled, outgoing) = journal_outcome(actor, outcome, now);
+    if journaled {
+        index_step_correlations(
+            actor,
+            saga_id,
+            workflow.step_name(),
+            effect.as_deref(),
+            correlation_keys,
+        );
+    }
+    if let Some(effect) = effect {
+        dispatch_step_effect(
+            actor,
+            context,
+            workflow.step_name(),
+            &effect,
+            &emitted_output,
+            journaled,
+        );
+    }
 
-    emit(SagaChoreographyEvent::StepCompleted {
-        context: context.next_step(workflow.step_name().into()),
-        output: emitted_output,
-        saga_input,
-        compensation_available,
-    });
+    for event in outgoing {
+        emit(event);
+    }
 }
 
 fn fail_workflow_step<A, F>(
@@ -563,36 +839,96 @@ fn fail_workflow_step<A, F>(
 ) where
     A: HasSagaParticipantSupport,
     F: FnMut(SagaChoreographyEvent),
+{
+    record_workflow_step_failure(actor, workflow, context, error, now, emit, true);
+}
+
+/// Fails a workflow step whose start the journal refused. The failure is
+/// journaled on a best-effort basis and always published, as the step never
+/// ran.
+fn fail_unstarted_workflow_step<A, F>(
+    actor: &mut A,
+    workflow: &'static dyn SagaWorkflowParticipant<A>,
+    context: &SagaContext,
+    error: crate::StepError,
+    now: u64,
+    emit: &mut F,
+) where
+    A: HasSagaParticipantSupport,
+    F: FnMut(SagaChoreographyEvent),
+{
+    record_workflow_step_failure(actor, workflow, context, error, now, emit, false);
+}
+
+fn record_workflow_step_failure<A, F>(
+    actor: &mut A,
+    workflow: &'static dyn SagaWorkflowParticipant<A>,
+    context: &SagaContext,
+    error: crate::StepError,
+    now: u64,
+    emit: &mut F,
+    apply_journal_policy: bool,
+) where
+    A: HasSagaParticipantSupport,
+    F: FnMut(SagaChoreographyEvent),
 {
     let saga_id = context.saga_id;
-    let (reason, requires_comp) = match error {
-        crate::StepError::Terminal { reason } => (reason, false),
-        crate::StepError::RequireCompensation { reason } => (reason, true),
-    };
+    let error_code = error.code().error_code();
+    actor.saga_support().stats.record_failure(error.code());
+    let (reason, details, requires_comp) = error.into_parts();
 
+    if !ensure_workflow_saga_state(
+        actor,
+        workflow,
+        context,
+        crate::StateKind::Executing,
+        now,
+        emit,
+    ) {
+        return;
+    }
     if let Some(SagaStateEntry::Executing(state)) = actor.saga_states().remove(&saga_id) {
         let new_state = state.fail(reason.clone(), requires_comp, now);
-        actor
-            .saga_states()
-            .insert(saga_id, SagaStateEntry::Failed(new_state));
+        actor.put_saga_state(saga_id, SagaStateEntry::Failed(new_state));
     }
 
-    actor.record_event(
-        saga_id,
-        ParticipantEvent::StepExecutionFailed {
-            error: reason.clone(),
-            requires_compensation: requires_comp,
-            failed_at_millis: now,
-        },
-    );
-
-    emit(SagaChoreographyEvent::StepFailed {
+    let step_failed = SagaChoreographyEvent::StepFailed {
         context: context.next_step(workflow.step_name().into()),
         participant_id: workflow.participant_id_owned(),
-        error_code: None,
+        error_code,
+        error: reason.clone(),
+        requires_compensation: requires_comp,
+        error_details: details.clone(),
+    };
+    let saga_failed = workflow
+        .is_critical()
+        .then(|| crate::helpers::critical_step_saga_failed(&step_failed))
+        .flatten();
+    let outgoing: Vec<_> = std::iter::once(step_failed).chain(saga_failed).collect();
+    let entry = ParticipantEvent::StepExecutionFailed {
         error: reason,
         requires_compensation: requires_comp,
-    });
+        failed_at_millis: now,
+        details,
+    };
+
+    let outgoing = if apply_journal_policy {
+        let outcome = UnjournaledOutcome::new(
+            context,
+            workflow.step_name(),
+            workflow.participant_id_owned(),
+            entry,
+            outgoing,
+        );
+        journal_outcome(actor, outcome, now).1
+    } else {
+        let mut outgoing = outgoing;
+        actor.try_record_event_with_outgoing(saga_id, entry, &mut outgoing);
+        outgoing
+    };
+    for event in outgoing {
+        emit(event);
+    }
 }
 
 fn compensate_workflow_with_emit<A, F>(
@@ -607,24 +943,86 @@ fn compensate_workflow_with_emit<A, F>(
 {
     let saga_id = context.saga_id;
 
+    if !ensure_workflow_saga_state(
+        actor,
+        workflow,
+        context,
+        crate::StateKind::Completed,
+        now,
+        emit,
+    ) {
+        return;
+    }
     if let Some(SagaStateEntry::Completed(state)) = actor.saga_states().remove(&saga_id) {
+        if !state.state.compensatable {
+            tracing::debug!(
+                target: "core::saga",
+                event = "saga_noop_compensation_skipped",
+                saga_id = saga_id.get(),
+                step_name = workflow.step_name()
+            );
+            actor.put_saga_state(saga_id, SagaStateEntry::Completed(state));
+            return;
+        }
         let comp_data = state.state.compensation_data.clone();
+        let step_name = workflow.step_name();
+        let started = ParticipantEvent::CompensationStarted {
+            attempt: 1,
+            started_at_millis: now,
+        };
+        let gate = crate::helpers::gate_journal_append(actor, context, step_name, started, now);
+        if matches!(
+            gate,
+            crate::helpers::StepStartGate::Skip | crate::helpers::StepStartGate::Park
+        ) {
+            actor.put_saga_state(saga_id, SagaStateEntry::Completed(state));
+            return;
+        }
         let new_state = state.start_compensation(now);
-        actor
-            .saga_states()
-            .insert(saga_id, SagaStateEntry::Compensating(new_state));
+        actor.put_saga_state(saga_id, SagaStateEntry::Compensating(new_state));
 
-        actor.record_event(
-            saga_id,
-            ParticipantEvent::CompensationStarted {
-                attempt: 1,
-                started_at_millis: now,
-            },
-        );
+        if let crate::helpers::StepStartGate::Refuse { reason } = gate {
+            let error = crate::CompensationError::safe_to_retry(reason);
+            fail_workflow_compensation(actor, workflow, context, error, &comp_data, now, emit);
+            return;
+        }
 
-        match workflow.compensate_step(actor, context, &comp_data) {
+        let result = if crate::helpers::compensation_already_done(actor, saga_id, step_name) {
+            Ok(())
+        } else {
+            let result = match open_compensation_data(
+                actor.saga_support().compensation_cipher.as_deref(),
+                &comp_data,
+            ) {
+                Ok(comp_data) => context
+                    .step_span(step_name)
+                    .in_scope(|| {
+                        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                            workflow.compensate_step(actor, context, &comp_data)
+                        }))
+                    })
+                    .unwrap_or_else(|payload| {
+                        Err(crate::CompensationError::terminal(
+                            crate::helpers::contained_panic_reason(
+                                actor,
+                                context,
+                                step_name,
+                                payload.as_ref(),
+                            ),
+                        ))
+                    }),
+                Err(err) => Err(crate::CompensationError::terminal(err.to_string())),
+            };
+            if result.is_ok() {
+                crate::helpers::mark_compensation_done(actor, saga_id, step_name);
+            }
+            result
+        };
+        match result {
             Ok(()) => complete_workflow_compensation(actor, workflow, context, now, emit),
-            Err(error) => fail_workflow_compensation(actor, workflow, context, error, now, emit),
+            Err(error) => {
+                fail_workflow_compensation(actor, workflow, context, error, &comp_data, now, emit)
+            }
         }
     }
 }
@@ -641,23 +1039,36 @@ fn complete_workflow_compensation<A, F>(
 {
     let saga_id = context.saga_id;
 
+    if !ensure_workflow_saga_state(
+        actor,
+        workflow,
+        context,
+        crate::StateKind::Compensating,
+        now,
+        emit,
+    ) {
+        return;
+    }
     if let Some(SagaStateEntry::Compensating(state)) = actor.saga_states().remove(&saga_id) {
         let new_state = state.complete_compensation(now);
-        actor
-            .saga_states()
-            .insert(saga_id, SagaStateEntry::Compensated(new_state));
+        actor.put_saga_state(saga_id, SagaStateEntry::Compensated(new_state));
     }
 
-    actor.record_event(
-        saga_id,
+    let compensation_completed = SagaChoreographyEvent::CompensationCompleted {
+        context: context.next_step(workflow.step_name().into()),
+    };
+    let outcome = UnjournaledOutcome::new(
+        context,
+        workflow.step_name(),
+        workflow.participant_id_owned(),
         ParticipantEvent::CompensationCompleted {
             completed_at_millis: now,
         },
+        vec![compensation_completed],
     );
-
-    emit(SagaChoreographyEvent::CompensationCompleted {
-        context: context.next_step(workflow.step_name().into()),
-    });
+    for event in journal_outcome(actor, outcome, now).1 {
+        emit(event);
+    }
 
     workflow.on_compensation_completed(actor, context);
 }
@@ -667,6 +1078,7 @@ fn fail_workflow_compensation<A, F>(
     workflow: &'static dyn SagaWorkflowParticipant<A>,
     context: &SagaContext,
     error: crate::CompensationError,
+    comp_data: &[u8],
     now: u64,
     emit: &mut F,
 ) where
@@ -674,36 +1086,36 @@ fn fail_workflow_compensation<A, F>(
     F: FnMut(SagaChoreographyEvent),
 {
     let saga_id = context.saga_id;
-    let (reason, is_ambiguous) = match error {
-        crate::CompensationError::SafeToRetry { reason } => (reason, false),
-        crate::CompensationError::Ambiguous { reason } => (reason, true),
-        crate::CompensationError::Terminal { reason } => (reason, false),
-    };
+    actor.saga_support().stats.record_failure(error.code());
+    let (reason, details, is_ambiguous) = error.into_parts();
 
+    if !ensure_workflow_saga_state(
+        actor,
+        workflow,
+        context,
+        crate::StateKind::Compensating,
+        now,
+        emit,
+    ) {
+        return;
+    }
     if let Some(SagaStateEntry::Compensating(state)) = actor.saga_states().remove(&saga_id) {
-        let new_state = state.quarantine(reason.clone(), now);
-        actor
-            .saga_states()
-            .insert(saga_id, SagaStateEntry::Quarantined(new_state));
+        let new_state = state
+            .quarantine(reason.clone(), now)
+            .with_compensation_data(comp_data.to_vec());
+        actor.put_saga_state(saga_id, SagaStateEntry::Quarantined(new_state));
     }
 
-    actor.record_event(
-        saga_id,
-        ParticipantEvent::Quarantined {
-            reason: reason.clone(),
-            quarantined_at_millis: now,
-        },
-    );
-
     let event_context = context.next_step(workflow.step_name().into());
-    emit(SagaChoreographyEvent::CompensationFailed {
+    let mut outgoing = vec![SagaChoreographyEvent::CompensationFailed {
         context: event_context.clone(),
         participant_id: workflow.participant_id_owned(),
         error: reason.clone(),
         is_ambiguous,
-    });
+        error_details: details,
+    }];
     if is_ambiguous {
-        emit(SagaChoreographyEvent::SagaQuarantined {
+        outgoing.push(SagaChoreographyEvent::SagaQuarantined {
             context: event_context,
             reason: reason.clone(),
             step: workflow.step_name().into(),
@@ -711,6 +1123,33 @@ fn fail_workflow_compensation<A, F>(
         });
     }
 
+    let outcome = UnjournaledOutcome::new(
+        context,
+        workflow.step_name(),
+        workflow.participant_id_owned(),
+        ParticipantEvent::Quarantined {
+            reason: reason.clone(),
+            quarantined_at_millis: now,
+        },
+        outgoing,
+    );
+    let outgoing = journal_outcome(actor, outcome, now).1;
+    actor
+        .saga_support()
+        .report_quarantined(crate::QuarantinedSaga {
+            context: context.clone(),
+            step: workflow.step_name().into(),
+            participant_id: workflow.participant_id_owned(),
+            reason: reason.clone(),
+            quarantined_at_millis: now,
+            compensation_data: Some(comp_data.to_vec()),
+            failed_retries: 0,
+        });
+
+    for event in outgoing {
+        emit(event);
+    }
+
     workflow.on_quarantined(actor, context, &reason);
 }
 
@@ -734,6 +1173,30 @@ pub async fn apply_async_participant_saga_ingress<P, FApplyTerminal, FOnInvalid>
     .await;
 }
 
+/// Saga ingress for async actors (`icanact_core::local_async`): handles
+/// `event` and publishes the resulting events on the participant's attached
+/// bus through its outbox. Pair with [`crate::AsyncJournalAdapter`] and
+/// [`crate::AsyncDedupeAdapter`] when the participant's stores are async.
+pub async fn handle_saga_event_async<P>(participant: &mut P, event: SagaChoreographyEvent)
+where
+    P: AsyncSagaParticipant + SagaStateExt,
+{
+    apply_async_participant_saga_ingress(
+        participant,
+        event,
+        |_participant, _event| {},
+        |event| {
+            tracing::warn!(
+                target: "core::saga",
+                event = "async_participant_invalid_emitted_transition",
+                saga_id = event.context().saga_id.get(),
+                event_type = event.event_type()
+            );
+        },
+    )
+    .await;
+}
+
 pub async fn apply_async_participant_saga_ingress_with_hooks<
     P,
     FApplyTerminal,
@@ -753,11 +1216,13 @@ pub async fn apply_async_participant_saga_ingress_with_hooks<
 {
     apply_terminal_side_effects(participant, &event);
 
+    let bus_attached = participant.saga_support().bus.is_some();
+    participant.saga_support_mut().stage_emitted = bus_attached;
     let mut emitted = Vec::new();
     handle_async_saga_event_with_emit(participant, event, |next_event| emitted.push(next_event))
         .await;
+    participant.saga_support_mut().stage_emitted = false;
 
-    let saga_bus = participant.saga_support().bus.clone();
     for next_event in emitted {
         if !is_valid_emitted_transition(
             participant
@@ -766,21 +1231,35 @@ pub async fn apply_async_participant_saga_ingress_with_hooks<
             &next_event,
         ) {
             on_invalid_transition(&next_event);
+            participant
+                .saga_support()
+                .discard_emitted_async(&next_event)
+                .await;
+            participant.saga_support().dead_letter(
+                DeadLetterReason::Validation,
+                "invalid_emitted_transition",
+                next_event,
+            );
             continue;
         }
 
         on_emitted_transition(participant, &next_event);
 
-        if let Some(bus) = &saga_bus {
-            if let Err(err) = bus.publish_strict(next_event) {
+        if bus_attached {
+            let published = participant
+                .saga_support()
+                .publish_emitted_async(next_event)
+                .await;
+            if let Err(err) = published {
                 tracing::error!(
                     target: "core::saga",
                     event = "async_participant_ingress_emit_publish_failed",
-                    error = ?err
+                    error = %err
                 );
             }
         }
     }
+    participant.saga_support().staged_outgoing.clear();
 }
 
 pub fn run_participant_phase_with_panic_quarantine<A, R, F>(
@@ -890,7 +1369,7 @@ pub fn publish_active_saga_panic_quarantine<J, D>(
     let emitted = SagaChoreographyEvent::SagaQuarantined {
         context: context.next_step(step_name.into()),
         reason,
-        step: step_name.to_string().into_boxed_str(),
+        step: step_name.into(),
         participant_id,
     };
 
@@ -898,10 +1377,10 @@ pub fn publish_active_saga_panic_quarantine<J, D>(
         match bus.publish_strict(emitted) {
             Ok(stats) => {
                 if stats.delivered > 0 {
-                    if let Err(err) = saga
-                        .dedupe
-                        .mark_processed(context.saga_id, PANIC_QUARANTINE_PUBLISH_KEY)
-                    {
+                    if let Err(err) = saga.dedupe.mark_processed(
+                        context.saga_id,
+                        DedupeKey::named(PANIC_QUARANTINE_PUBLISH_KEY),
+                    ) {
                         tracing::error!(
                             target: "core::saga",
                             event = "panic_quarantine_dedupe_mark_failed",
@@ -963,7 +1442,7 @@ pub fn classify_recovery(
     now_ms: u64,
     policy: RecoveryPolicy,
 ) -> RecoveryDecision {
-    let Some(last) = entries.last() else {
+    let Some(last) = last_progress_entry(entries) else {
         return RecoveryDecision::TerminalNoAction;
     };
     if matches!(
@@ -1011,8 +1490,8 @@ pub fn collect_startup_recovery_events_for_saga_type<
 >(
     journal: &J,
     dedupe: &D,
-    step_name: &'static str,
-    saga_type: &'static str,
+    step_name: &str,
+    saga_type: &str,
 ) -> Result<Vec<SagaChoreographyEvent>, RecoveryCollectionError> {
     let mut out = Vec::new();
     let policy = RecoveryPolicy::default();
@@ -1022,7 +1501,7 @@ pub fn collect_startup_recovery_events_for_saga_type<
         Err(err) => return Err(RecoveryCollectionError::ListSagas(err)),
     };
     for saga_id in saga_ids {
-        let entries = match journal.read(saga_id) {
+        let entries = match read_recovery_entries(journal, saga_id) {
             Ok(entries) => entries,
             Err(err) => {
                 return Err(RecoveryCollectionError::ReadSaga {
@@ -1042,7 +1521,8 @@ pub fn collect_startup_recovery_events_for_saga_type<
                 ));
             }
             RecoveryDecision::ReplayPanicQuarantine => {
-                let should_emit = match dedupe.check_and_mark(saga_id, PANIC_QUARANTINE_PUBLISH_KEY)
+                let should_emit = match dedupe
+                    .check_and_mark(saga_id, DedupeKey::named(PANIC_QUARANTINE_PUBLISH_KEY))
                 {
                     Ok(value) => value,
                     Err(err) => {
@@ -1069,10 +1549,33 @@ pub fn collect_startup_recovery_events_for_saga_type<
     Ok(out)
 }
 
-fn recovery_context_for_saga_type(
+/// Entries [`classify_recovery`] needs. With the `rkyv` feature only the
+/// last progress entry is deserialized, which is all the classification and
+/// the panic quarantine reason look at.
+#[cfg(feature = "rkyv")]
+fn read_recovery_entries<J: ParticipantJournal>(
+    journal: &J,
     saga_id: SagaId,
-    step_name: &'static str,
-    saga_type: &'static str,
+) -> Result<Vec<JournalEntry>, JournalError> {
+    let mut last = Ok(None);
+    journal.read_archived(saga_id, &mut |rows| {
+        last = crate::journal::archived::last_progress_row(rows);
+    })?;
+    Ok(last?.into_iter().collect())
+}
+
+#[cfg(not(feature = "rkyv"))]
+fn read_recovery_entries<J: ParticipantJournal>(
+    journal: &J,
+    saga_id: SagaId,
+) -> Result<Vec<JournalEntry>, JournalError> {
+    journal.read(saga_id)
+}
+
+pub(crate) fn recovery_context_for_saga_type(
+    saga_id: SagaId,
+    step_name: &str,
+    saga_type: &str,
 ) -> SagaContext {
     let now = SagaContext::now_millis();
     SagaContext {
@@ -1087,6 +1590,9 @@ fn recovery_context_for_saga_type(
         initiator_peer_id: [0; 32],
         saga_started_at_millis: now,
         event_timestamp_millis: now,
+        parent_saga_id: None,
+        workflow_version: crate::DEFAULT_WORKFLOW_VERSION,
+        logical_clock: 0,
     }
 }
 
@@ -1127,6 +1633,7 @@ pub fn open_saga_lmdb_actor<A: SagaLmdbBackedActor>(
 #[cfg(feature = "lmdb")]
 pub mod lmdb {
     use std::path::Path;
+    use std::sync::Arc;
     use std::time::{SystemTime, UNIX_EPOCH};
 
     use heed::types::{Bytes, Str};
@@ -1134,12 +1641,18 @@ pub mod lmdb {
 
     use super::{collect_startup_recovery_events_for_saga_type, DEFAULT_RECOVERY_SAGA_TYPE};
     use crate::{
-        DedupeError, JournalEntry, JournalError, ParticipantDedupeStore, ParticipantEvent,
-        ParticipantJournal, SagaId, SagaParticipantSupport,
+        DeadLetterEntry, DeadLetterError, DeadLetterPayload, DeadLetterReason, DeadLetterStore,
+        DedupeError, DedupeKey, EffectLedger, InboxEntry, JournalEntry, JournalError, OutboxEntry,
+        ParticipantDedupeStore, ParticipantEvent, ParticipantJournal, ParticipantStateStore,
+        ParticipantStateStoreError, ParticipantStatsSnapshot, ResourceLock, ResourceLockError,
+        ResourceLockJournal, SagaChoreographyEvent, SagaId, SagaParticipantSupport, SagaSchedule,
+        SagaScheduleError, SagaScheduleJournal, SagaStateEntry, StatsPersistence,
+        StatsPersistenceError,
     };
 
     const DEFAULT_LMDB_MAP_SIZE_BYTES: usize = 1024 * 1024 * 1024;
     const SAGA_LMDB_MAP_SIZE_ENV: &str = "SAGA_LMDB_MAP_SIZE_BYTES";
+    const STATS_META_KEY: &str = "participant_stats";
 
     fn lmdb_map_size_bytes() -> Result<usize, Box<str>> {
         match std::env::var(SAGA_LMDB_MAP_SIZE_ENV) {
@@ -1153,6 +1666,20 @@ pub mod lmdb {
         }
     }
 
+    /// Opens the environment at `path`, creating the directory, with the
+    /// map size from [`SAGA_LMDB_MAP_SIZE_ENV`] or 1 GiB.
+    fn open_env(path: &Path, max_dbs: u32) -> Result<Env, Box<str>> {
+        std::fs::create_dir_all(path).map_err(|err| err.to_string())?;
+        let map_size = lmdb_map_size_bytes()?;
+        unsafe {
+            EnvOpenOptions::new()
+                .max_dbs(max_dbs)
+                .map_size(map_size)
+                .open(path)
+        }
+        .map_err(|err| err.to_string().into())
+    }
+
     fn now_millis() -> u64 {
         SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -1172,26 +1699,29 @@ pub mod lmdb {
         format!("{:020}", saga_id.get())
     }
 
+    fn key_queue_entry(id: u64) -> String {
+        format!("{id:020}")
+    }
+
     #[derive(Debug)]
     pub struct LmdbJournal {
         env: Env,
         rows: Database<Str, Bytes>,
         saga_index: Database<Str, Str>,
         meta: Database<Str, Str>,
+        outbox: Database<Str, Bytes>,
+        inbox: Database<Str, Bytes>,
+        inbox_history: Database<Str, Bytes>,
     }
 
     impl LmdbJournal {
         pub fn open(path: &Path) -> Result<Self, JournalError> {
-            std::fs::create_dir_all(path)
-                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
-            let map_size = lmdb_map_size_bytes().map_err(JournalError::Storage)?;
-            let env = unsafe {
-                EnvOpenOptions::new()
-                    .max_dbs(16)
-                    .map_size(map_size)
-                    .open(path)
-            }
-            .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            let env = open_env(path, 16).map_err(JournalError::Storage)?;
+            Self::in_env(env)
+        }
+
+        /// Creates or opens the journal's databases in `env`.
+        fn in_env(env: Env) -> Result<Self, JournalError> {
             let mut wtxn = env
                 .write_txn()
                 .map_err(|err| JournalError::Storage(err.to_string().into()))?;
@@ -1204,6 +1734,15 @@ pub mod lmdb {
             let meta = env
                 .create_database::<Str, Str>(&mut wtxn, Some("journal_meta"))
                 .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            let outbox = env
+                .create_database::<Str, Bytes>(&mut wtxn, Some("journal_outbox"))
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            let inbox = env
+                .create_database::<Str, Bytes>(&mut wtxn, Some("journal_inbox"))
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            let inbox_history = env
+                .create_database::<Str, Bytes>(&mut wtxn, Some("journal_inbox_history"))
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
             wtxn.commit()
                 .map_err(|err| JournalError::Storage(err.to_string().into()))?;
             Ok(Self {
@@ -1211,6 +1750,9 @@ pub mod lmdb {
                 rows,
                 saga_index,
                 meta,
+                outbox,
+                inbox,
+                inbox_history,
             })
         }
 
@@ -1230,99 +1772,659 @@ pub mod lmdb {
         }
     }
 
-    impl ParticipantJournal for LmdbJournal {
-        fn append(&self, saga_id: SagaId, event: ParticipantEvent) -> Result<u64, JournalError> {
+    impl ParticipantJournal for LmdbJournal {
+        fn append(&self, saga_id: SagaId, event: ParticipantEvent) -> Result<u64, JournalError> {
+            let mut wtxn = self
+                .env
+                .write_txn()
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            let sequence = Self::next_sequence(&self.meta, &mut wtxn)?;
+            let entry = JournalEntry {
+                sequence,
+                recorded_at_millis: now_millis(),
+                event,
+            };
+            let encoded = crate::encode_journal_entry(&entry)?;
+            self.rows
+                .put(
+                    &mut wtxn,
+                    &key_saga_seq(saga_id, sequence),
+                    encoded.as_ref(),
+                )
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            self.saga_index
+                .put(&mut wtxn, &key_saga_index(saga_id), "1")
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            wtxn.commit()
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            Ok(sequence)
+        }
+
+        fn read(&self, saga_id: SagaId) -> Result<Vec<JournalEntry>, JournalError> {
+            let rtxn = self
+                .env
+                .read_txn()
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            let prefix = key_saga_prefix(saga_id);
+            let mut entries = Vec::new();
+            let iter = self
+                .rows
+                .prefix_iter(&rtxn, &prefix)
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            for row in iter {
+                let (_, v) = row.map_err(|err| JournalError::Storage(err.to_string().into()))?;
+                entries.push(crate::decode_journal_entry(v)?);
+            }
+            entries.sort_by_key(|e| e.sequence);
+            Ok(entries)
+        }
+
+        /// Rows are borrowed from the read transaction; keys sort by
+        /// sequence.
+        #[cfg(feature = "rkyv")]
+        fn read_archived(
+            &self,
+            saga_id: SagaId,
+            visit: &mut dyn FnMut(&[crate::ArchivedJournalRow<'_>]),
+        ) -> Result<(), JournalError> {
+            let rtxn = self
+                .env
+                .read_txn()
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            let prefix = key_saga_prefix(saga_id);
+            let iter = self
+                .rows
+                .prefix_iter(&rtxn, &prefix)
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            let mut rows = Vec::new();
+            for row in iter {
+                let (_, v) = row.map_err(|err| JournalError::Storage(err.to_string().into()))?;
+                rows.push(crate::ArchivedJournalRow::new(v)?);
+            }
+            visit(&rows);
+            Ok(())
+        }
+
+        fn list_sagas(&self) -> Result<Vec<SagaId>, JournalError> {
+            let rtxn = self
+                .env
+                .read_txn()
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            let mut out = Vec::new();
+            let iter = self
+                .saga_index
+                .iter(&rtxn)
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            for row in iter {
+                let (k, _) = row.map_err(|err| JournalError::Storage(err.to_string().into()))?;
+                if let Ok(id) = k.parse::<u64>() {
+                    out.push(SagaId::new(id));
+                }
+            }
+            out.sort_by_key(|id| id.get());
+            Ok(out)
+        }
+
+        fn prune(&self, saga_id: SagaId) -> Result<(), JournalError> {
+            let mut wtxn = self
+                .env
+                .write_txn()
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            let prefix = key_saga_prefix(saga_id);
+            let mut iter = self
+                .rows
+                .prefix_iter_mut(&mut wtxn, &prefix)
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            while iter.next().is_some() {
+                unsafe { iter.del_current() }
+                    .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            }
+            drop(iter);
+            let mut iter = self
+                .inbox_history
+                .prefix_iter_mut(&mut wtxn, &prefix)
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            while iter.next().is_some() {
+                unsafe { iter.del_current() }
+                    .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            }
+            drop(iter);
+            self.saga_index
+                .delete(&mut wtxn, &key_saga_index(saga_id))
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            wtxn.commit()
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            Ok(())
+        }
+
+        fn record_outgoing(
+            &self,
+            saga_id: SagaId,
+            event: &SagaChoreographyEvent,
+        ) -> Result<Option<u64>, JournalError> {
+            let mut wtxn = self
+                .env
+                .write_txn()
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            let outbox_id = Self::next_sequence(&self.meta, &mut wtxn)?;
+            let entry = OutboxEntry {
+                outbox_id,
+                saga_id,
+                recorded_at_millis: now_millis(),
+                event: event.clone(),
+            };
+            let encoded = rkyv::to_bytes::<rkyv::rancor::Error>(&entry)
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            self.outbox
+                .put(&mut wtxn, &key_queue_entry(outbox_id), encoded.as_ref())
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            wtxn.commit()
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            Ok(Some(outbox_id))
+        }
+
+        /// Writes the row and the outbox entries in one transaction.
+        fn append_with_outgoing(
+            &self,
+            saga_id: SagaId,
+            event: ParticipantEvent,
+            outgoing: &[SagaChoreographyEvent],
+        ) -> Result<(u64, Vec<Option<u64>>), JournalError> {
+            let mut wtxn = self
+                .env
+                .write_txn()
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            let now = now_millis();
+            let sequence = Self::next_sequence(&self.meta, &mut wtxn)?;
+            let entry = JournalEntry {
+                sequence,
+                recorded_at_millis: now,
+                event,
+            };
+            let encoded = crate::encode_journal_entry(&entry)?;
+            self.rows
+                .put(
+                    &mut wtxn,
+                    &key_saga_seq(saga_id, sequence),
+                    encoded.as_ref(),
+                )
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            self.saga_index
+                .put(&mut wtxn, &key_saga_index(saga_id), "1")
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            let mut outbox_ids = Vec::with_capacity(outgoing.len());
+            for event in outgoing {
+                let outbox_id = Self::next_sequence(&self.meta, &mut wtxn)?;
+                let entry = OutboxEntry {
+                    outbox_id,
+                    saga_id,
+                    recorded_at_millis: now,
+                    event: event.clone(),
+                };
+                let encoded = rkyv::to_bytes::<rkyv::rancor::Error>(&entry)
+                    .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+                self.outbox
+                    .put(&mut wtxn, &key_queue_entry(outbox_id), encoded.as_ref())
+                    .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+                outbox_ids.push(Some(outbox_id));
+            }
+            wtxn.commit()
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            Ok((sequence, outbox_ids))
+        }
+
+        fn pending_outgoing(&self) -> Result<Vec<OutboxEntry>, JournalError> {
+            let rtxn = self
+                .env
+                .read_txn()
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            let mut entries = Vec::new();
+            let iter = self
+                .outbox
+                .iter(&rtxn)
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            for row in iter {
+                let (_, v) = row.map_err(|err| JournalError::Storage(err.to_string().into()))?;
+                let owned = v.to_vec();
+                let decoded: OutboxEntry =
+                    rkyv::from_bytes::<OutboxEntry, rkyv::rancor::Error>(&owned)
+                        .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+                entries.push(decoded);
+            }
+            entries.sort_by_key(|e| e.outbox_id);
+            Ok(entries)
+        }
+
+        fn mark_outgoing_sent(&self, outbox_id: u64) -> Result<(), JournalError> {
+            let mut wtxn = self
+                .env
+                .write_txn()
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            self.outbox
+                .delete(&mut wtxn, &key_queue_entry(outbox_id))
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            wtxn.commit()
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            Ok(())
+        }
+
+        fn record_incoming(
+            &self,
+            saga_id: SagaId,
+            dedupe_key: DedupeKey,
+            event: &SagaChoreographyEvent,
+        ) -> Result<Option<u64>, JournalError> {
+            let mut wtxn = self
+                .env
+                .write_txn()
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            let inbox_id = Self::next_sequence(&self.meta, &mut wtxn)?;
+            let entry = InboxEntry {
+                inbox_id,
+                saga_id,
+                dedupe_key: dedupe_key.to_string().into(),
+                recorded_at_millis: now_millis(),
+                event: event.clone(),
+            };
+            let encoded = rkyv::to_bytes::<rkyv::rancor::Error>(&entry)
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            self.inbox
+                .put(&mut wtxn, &key_queue_entry(inbox_id), encoded.as_ref())
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            self.inbox_history
+                .put(
+                    &mut wtxn,
+                    &key_saga_seq(saga_id, inbox_id),
+                    encoded.as_ref(),
+                )
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            wtxn.commit()
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            Ok(Some(inbox_id))
+        }
+
+        fn pending_incoming(&self) -> Result<Vec<InboxEntry>, JournalError> {
+            let rtxn = self
+                .env
+                .read_txn()
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            let mut entries = Vec::new();
+            let iter = self
+                .inbox
+                .iter(&rtxn)
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            for row in iter {
+                let (_, v) = row.map_err(|err| JournalError::Storage(err.to_string().into()))?;
+                let owned = v.to_vec();
+                let decoded: InboxEntry =
+                    rkyv::from_bytes::<InboxEntry, rkyv::rancor::Error>(&owned)
+                        .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+                entries.push(decoded);
+            }
+            entries.sort_by_key(|e| e.inbox_id);
+            Ok(entries)
+        }
+
+        fn mark_incoming_processed(&self, inbox_id: u64) -> Result<(), JournalError> {
+            let mut wtxn = self
+                .env
+                .write_txn()
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            self.inbox
+                .delete(&mut wtxn, &key_queue_entry(inbox_id))
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            wtxn.commit()
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            Ok(())
+        }
+
+        fn incoming_history(&self, saga_id: SagaId) -> Result<Vec<InboxEntry>, JournalError> {
+            let rtxn = self
+                .env
+                .read_txn()
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            let prefix = key_saga_prefix(saga_id);
+            let mut entries = Vec::new();
+            let iter = self
+                .inbox_history
+                .prefix_iter(&rtxn, &prefix)
+                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+            for row in iter {
+                let (_, v) = row.map_err(|err| JournalError::Storage(err.to_string().into()))?;
+                let owned = v.to_vec();
+                let decoded: InboxEntry =
+                    rkyv::from_bytes::<InboxEntry, rkyv::rancor::Error>(&owned)
+                        .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+                entries.push(decoded);
+            }
+            entries.sort_by_key(|e| e.inbox_id);
+            Ok(entries)
+        }
+    }
+
+    /// Keeps the participant's stats snapshot in the journal's metadata.
+    impl StatsPersistence for LmdbJournal {
+        fn save_stats(
+            &self,
+            snapshot: &ParticipantStatsSnapshot,
+        ) -> Result<(), StatsPersistenceError> {
+            let mut wtxn = self
+                .env
+                .write_txn()
+                .map_err(|err| StatsPersistenceError::Storage(err.to_string().into()))?;
+            self.meta
+                .put(&mut wtxn, STATS_META_KEY, &snapshot.encode())
+                .map_err(|err| StatsPersistenceError::Storage(err.to_string().into()))?;
+            wtxn.commit()
+                .map_err(|err| StatsPersistenceError::Storage(err.to_string().into()))
+        }
+
+        fn load_stats(&self) -> Result<Option<ParticipantStatsSnapshot>, StatsPersistenceError> {
+            let rtxn = self
+                .env
+                .read_txn()
+                .map_err(|err| StatsPersistenceError::Storage(err.to_string().into()))?;
+            let Some(encoded) = self
+                .meta
+                .get(&rtxn, STATS_META_KEY)
+                .map_err(|err| StatsPersistenceError::Storage(err.to_string().into()))?
+            else {
+                return Ok(None);
+            };
+            ParticipantStatsSnapshot::decode(encoded)
+                .map(Some)
+                .ok_or_else(|| StatsPersistenceError::Storage("malformed stats snapshot".into()))
+        }
+    }
+
+    /// LMDB-backed [`DeadLetterStore`], usually opened next to the
+    /// participant journal so poison events survive restarts.
+    #[derive(Debug)]
+    pub struct LmdbDeadLetterStore {
+        env: Env,
+        entries: Database<Str, Bytes>,
+        meta: Database<Str, Str>,
+    }
+
+    impl LmdbDeadLetterStore {
+        pub fn open(path: &Path) -> Result<Self, DeadLetterError> {
+            std::fs::create_dir_all(path)
+                .map_err(|err| DeadLetterError::Storage(err.to_string().into()))?;
+            let map_size = lmdb_map_size_bytes().map_err(DeadLetterError::Storage)?;
+            let env = unsafe {
+                EnvOpenOptions::new()
+                    .max_dbs(8)
+                    .map_size(map_size)
+                    .open(path)
+            }
+            .map_err(|err| DeadLetterError::Storage(err.to_string().into()))?;
+            let mut wtxn = env
+                .write_txn()
+                .map_err(|err| DeadLetterError::Storage(err.to_string().into()))?;
+            let entries = env
+                .create_database::<Str, Bytes>(&mut wtxn, Some("dead_letter_entries"))
+                .map_err(|err| DeadLetterError::Storage(err.to_string().into()))?;
+            let meta = env
+                .create_database::<Str, Str>(&mut wtxn, Some("dead_letter_meta"))
+                .map_err(|err| DeadLetterError::Storage(err.to_string().into()))?;
+            wtxn.commit()
+                .map_err(|err| DeadLetterError::Storage(err.to_string().into()))?;
+            Ok(Self { env, entries, meta })
+        }
+    }
+
+    impl DeadLetterStore for LmdbDeadLetterStore {
+        fn push(
+            &self,
+            reason: DeadLetterReason,
+            detail: &str,
+            payload: DeadLetterPayload,
+        ) -> Result<u64, DeadLetterError> {
+            let mut wtxn = self
+                .env
+                .write_txn()
+                .map_err(|err| DeadLetterError::Storage(err.to_string().into()))?;
+            let current = self
+                .meta
+                .get(&wtxn, "next_id")
+                .map_err(|err| DeadLetterError::Storage(err.to_string().into()))?
+                .and_then(|raw| raw.parse::<u64>().ok())
+                .unwrap_or(0);
+            let next = current.saturating_add(1).to_string();
+            self.meta
+                .put(&mut wtxn, "next_id", next.as_str())
+                .map_err(|err| DeadLetterError::Storage(err.to_string().into()))?;
+            let entry = DeadLetterEntry {
+                dead_letter_id: current,
+                reason,
+                detail: detail.into(),
+                recorded_at_millis: now_millis(),
+                payload,
+            };
+            let encoded = rkyv::to_bytes::<rkyv::rancor::Error>(&entry)
+                .map_err(|err| DeadLetterError::Storage(err.to_string().into()))?;
+            self.entries
+                .put(&mut wtxn, &key_queue_entry(current), encoded.as_ref())
+                .map_err(|err| DeadLetterError::Storage(err.to_string().into()))?;
+            wtxn.commit()
+                .map_err(|err| DeadLetterError::Storage(err.to_string().into()))?;
+            Ok(current)
+        }
+
+        fn list(&self) -> Result<Vec<DeadLetterEntry>, DeadLetterError> {
+            let rtxn = self
+                .env
+                .read_txn()
+                .map_err(|err| DeadLetterError::Storage(err.to_string().into()))?;
+            let iter = self
+                .entries
+                .iter(&rtxn)
+                .map_err(|err| DeadLetterError::Storage(err.to_string().into()))?;
+            let mut entries = Vec::new();
+            for row in iter {
+                let (_, v) = row.map_err(|err| DeadLetterError::Storage(err.to_string().into()))?;
+                let owned = v.to_vec();
+                let decoded: DeadLetterEntry =
+                    rkyv::from_bytes::<DeadLetterEntry, rkyv::rancor::Error>(&owned)
+                        .map_err(|err| DeadLetterError::Storage(err.to_string().into()))?;
+                entries.push(decoded);
+            }
+            Ok(entries)
+        }
+
+        fn remove(&self, dead_letter_id: u64) -> Result<Option<DeadLetterEntry>, DeadLetterError> {
+            let key = key_queue_entry(dead_letter_id);
+            let mut wtxn = self
+                .env
+                .write_txn()
+                .map_err(|err| DeadLetterError::Storage(err.to_string().into()))?;
+            let existing = self
+                .entries
+                .get(&wtxn, &key)
+                .map_err(|err| DeadLetterError::Storage(err.to_string().into()))?
+                .map(|v| v.to_vec());
+            let Some(owned) = existing else {
+                return Ok(None);
+            };
+            let decoded: DeadLetterEntry =
+                rkyv::from_bytes::<DeadLetterEntry, rkyv::rancor::Error>(&owned)
+                    .map_err(|err| DeadLetterError::Storage(err.to_string().into()))?;
+            self.entries
+                .delete(&mut wtxn, &key)
+                .map_err(|err| DeadLetterError::Storage(err.to_string().into()))?;
+            wtxn.commit()
+                .map_err(|err| DeadLetterError::Storage(err.to_string().into()))?;
+            Ok(Some(decoded))
+        }
+    }
+
+    /// LMDB-backed [`ResourceLockJournal`], keyed by resource.
+    #[derive(Debug)]
+    pub struct LmdbResourceLockJournal {
+        env: Env,
+        locks: Database<Str, Bytes>,
+    }
+
+    impl LmdbResourceLockJournal {
+        pub fn open(path: &Path) -> Result<Self, ResourceLockError> {
+            std::fs::create_dir_all(path)
+                .map_err(|err| ResourceLockError::Storage(err.to_string().into()))?;
+            let map_size = lmdb_map_size_bytes().map_err(ResourceLockError::Storage)?;
+            let env = unsafe {
+                EnvOpenOptions::new()
+                    .max_dbs(8)
+                    .map_size(map_size)
+                    .open(path)
+            }
+            .map_err(|err| ResourceLockError::Storage(err.to_string().into()))?;
+            let mut wtxn = env
+                .write_txn()
+                .map_err(|err| ResourceLockError::Storage(err.to_string().into()))?;
+            let locks = env
+                .create_database::<Str, Bytes>(&mut wtxn, Some("resource_locks"))
+                .map_err(|err| ResourceLockError::Storage(err.to_string().into()))?;
+            wtxn.commit()
+                .map_err(|err| ResourceLockError::Storage(err.to_string().into()))?;
+            Ok(Self { env, locks })
+        }
+    }
+
+    impl ResourceLockJournal for LmdbResourceLockJournal {
+        fn record_acquired(&self, lock: &ResourceLock) -> Result<(), ResourceLockError> {
+            let encoded = rkyv::to_bytes::<rkyv::rancor::Error>(lock)
+                .map_err(|err| ResourceLockError::Storage(err.to_string().into()))?;
+            let mut wtxn = self
+                .env
+                .write_txn()
+                .map_err(|err| ResourceLockError::Storage(err.to_string().into()))?;
+            self.locks
+                .put(&mut wtxn, lock.resource.as_ref(), encoded.as_ref())
+                .map_err(|err| ResourceLockError::Storage(err.to_string().into()))?;
+            wtxn.commit()
+                .map_err(|err| ResourceLockError::Storage(err.to_string().into()))?;
+            Ok(())
+        }
+
+        fn record_released(&self, resource: &str) -> Result<(), ResourceLockError> {
+            let mut wtxn = self
+                .env
+                .write_txn()
+                .map_err(|err| ResourceLockError::Storage(err.to_string().into()))?;
+            self.locks
+                .delete(&mut wtxn, resource)
+                .map_err(|err| ResourceLockError::Storage(err.to_string().into()))?;
+            wtxn.commit()
+                .map_err(|err| ResourceLockError::Storage(err.to_string().into()))?;
+            Ok(())
+        }
+
+        fn held_locks(&self) -> Result<Vec<ResourceLock>, ResourceLockError> {
+            let rtxn = self
+                .env
+                .read_txn()
+                .map_err(|err| ResourceLockError::Storage(err.to_string().into()))?;
+            let iter = self
+                .locks
+                .iter(&rtxn)
+                .map_err(|err| ResourceLockError::Storage(err.to_string().into()))?;
+            let mut locks = Vec::new();
+            for row in iter {
+                let (_, v) =
+                    row.map_err(|err| ResourceLockError::Storage(err.to_string().into()))?;
+                let owned = v.to_vec();
+                let decoded: ResourceLock =
+                    rkyv::from_bytes::<ResourceLock, rkyv::rancor::Error>(&owned)
+                        .map_err(|err| ResourceLockError::Storage(err.to_string().into()))?;
+                locks.push(decoded);
+            }
+            Ok(locks)
+        }
+    }
+
+    /// LMDB-backed [`SagaScheduleJournal`], keyed by schedule id.
+    #[derive(Debug)]
+    pub struct LmdbSagaScheduleJournal {
+        env: Env,
+        schedules: Database<Str, Bytes>,
+    }
+
+    impl LmdbSagaScheduleJournal {
+        pub fn open(path: &Path) -> Result<Self, SagaScheduleError> {
+            std::fs::create_dir_all(path)
+                .map_err(|err| SagaScheduleError::Storage(err.to_string().into()))?;
+            let map_size = lmdb_map_size_bytes().map_err(SagaScheduleError::Storage)?;
+            let env = unsafe {
+                EnvOpenOptions::new()
+                    .max_dbs(8)
+                    .map_size(map_size)
+                    .open(path)
+            }
+            .map_err(|err| SagaScheduleError::Storage(err.to_string().into()))?;
+            let mut wtxn = env
+                .write_txn()
+                .map_err(|err| SagaScheduleError::Storage(err.to_string().into()))?;
+            let schedules = env
+                .create_database::<Str, Bytes>(&mut wtxn, Some("saga_schedules"))
+                .map_err(|err| SagaScheduleError::Storage(err.to_string().into()))?;
+            wtxn.commit()
+                .map_err(|err| SagaScheduleError::Storage(err.to_string().into()))?;
+            Ok(Self { env, schedules })
+        }
+    }
+
+    impl SagaScheduleJournal for LmdbSagaScheduleJournal {
+        fn save(&self, schedule: &SagaSchedule) -> Result<(), SagaScheduleError> {
+            let encoded = rkyv::to_bytes::<rkyv::rancor::Error>(schedule)
+                .map_err(|err| SagaScheduleError::Storage(err.to_string().into()))?;
             let mut wtxn = self
                 .env
                 .write_txn()
-                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
-            let sequence = Self::next_sequence(&self.meta, &mut wtxn)?;
-            let entry = JournalEntry {
-                sequence,
-                recorded_at_millis: now_millis(),
-                event,
-            };
-            let encoded = rkyv::to_bytes::<rkyv::rancor::Error>(&entry)
-                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
-            self.rows
+                .map_err(|err| SagaScheduleError::Storage(err.to_string().into()))?;
+            self.schedules
                 .put(
                     &mut wtxn,
-                    &key_saga_seq(saga_id, sequence),
+                    &key_queue_entry(schedule.schedule_id),
                     encoded.as_ref(),
                 )
-                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
-            self.saga_index
-                .put(&mut wtxn, &key_saga_index(saga_id), "1")
-                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+                .map_err(|err| SagaScheduleError::Storage(err.to_string().into()))?;
             wtxn.commit()
-                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
-            Ok(sequence)
+                .map_err(|err| SagaScheduleError::Storage(err.to_string().into()))?;
+            Ok(())
         }
 
-        fn read(&self, saga_id: SagaId) -> Result<Vec<JournalEntry>, JournalError> {
-            let rtxn = self
+        fn remove(&self, schedule_id: u64) -> Result<(), SagaScheduleError> {
+            let mut wtxn = self
                 .env
-                .read_txn()
-                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
-            let prefix = key_saga_prefix(saga_id);
-            let mut entries = Vec::new();
-            let iter = self
-                .rows
-                .prefix_iter(&rtxn, &prefix)
-                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
-            for row in iter {
-                let (_, v) = row.map_err(|err| JournalError::Storage(err.to_string().into()))?;
-                let owned = v.to_vec();
-                let decoded: JournalEntry =
-                    rkyv::from_bytes::<JournalEntry, rkyv::rancor::Error>(&owned)
-                        .map_err(|err| JournalError::Storage(err.to_string().into()))?;
-                entries.push(decoded);
-            }
-            entries.sort_by_key(|e| e.sequence);
-            Ok(entries)
+                .write_txn()
+                .map_err(|err| SagaScheduleError::Storage(err.to_string().into()))?;
+            self.schedules
+                .delete(&mut wtxn, &key_queue_entry(schedule_id))
+                .map_err(|err| SagaScheduleError::Storage(err.to_string().into()))?;
+            wtxn.commit()
+                .map_err(|err| SagaScheduleError::Storage(err.to_string().into()))?;
+            Ok(())
         }
 
-        fn list_sagas(&self) -> Result<Vec<SagaId>, JournalError> {
+        fn pending(&self) -> Result<Vec<SagaSchedule>, SagaScheduleError> {
             let rtxn = self
                 .env
                 .read_txn()
-                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
-            let mut out = Vec::new();
+                .map_err(|err| SagaScheduleError::Storage(err.to_string().into()))?;
             let iter = self
-                .saga_index
+                .schedules
                 .iter(&rtxn)
-                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+                .map_err(|err| SagaScheduleError::Storage(err.to_string().into()))?;
+            let mut schedules = Vec::new();
             for row in iter {
-                let (k, _) = row.map_err(|err| JournalError::Storage(err.to_string().into()))?;
-                if let Ok(id) = k.parse::<u64>() {
-                    out.push(SagaId::new(id));
-                }
-            }
-            out.sort_by_key(|id| id.get());
-            Ok(out)
-        }
-
-        fn prune(&self, saga_id: SagaId) -> Result<(), JournalError> {
-            let mut wtxn = self
-                .env
-                .write_txn()
-                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
-            let prefix = key_saga_prefix(saga_id);
-            let mut iter = self
-                .rows
-                .prefix_iter_mut(&mut wtxn, &prefix)
-                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
-            while iter.next().is_some() {
-                unsafe { iter.del_current() }
-                    .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+                let (_, v) =
+                    row.map_err(|err| SagaScheduleError::Storage(err.to_string().into()))?;
+                let owned = v.to_vec();
+                let decoded: SagaSchedule =
+                    rkyv::from_bytes::<SagaSchedule, rkyv::rancor::Error>(&owned)
+                        .map_err(|err| SagaScheduleError::Storage(err.to_string().into()))?;
+                schedules.push(decoded);
             }
-            drop(iter);
-            self.saga_index
-                .delete(&mut wtxn, &key_saga_index(saga_id))
-                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
-            wtxn.commit()
-                .map_err(|err| JournalError::Storage(err.to_string().into()))?;
-            Ok(())
+            Ok(schedules)
         }
     }
 
@@ -1334,34 +2436,90 @@ pub mod lmdb {
 
     impl LmdbDedupe {
         pub fn open(path: &Path) -> Result<Self, DedupeError> {
-            std::fs::create_dir_all(path)
-                .map_err(|err| DedupeError::Storage(err.to_string().into()))?;
-            let map_size = lmdb_map_size_bytes().map_err(DedupeError::Storage)?;
-            let env = unsafe {
-                EnvOpenOptions::new()
-                    .max_dbs(8)
-                    .map_size(map_size)
-                    .open(path)
-            }
-            .map_err(|err| DedupeError::Storage(err.to_string().into()))?;
+            let env = open_env(path, 8).map_err(DedupeError::Storage)?;
+            Self::in_env(env)
+        }
+
+        /// Creates or opens the dedupe database in `env`, rewriting rows
+        /// left by releases that stored string keys.
+        fn in_env(env: Env) -> Result<Self, DedupeError> {
             let mut wtxn = env
                 .write_txn()
                 .map_err(|err| DedupeError::Storage(err.to_string().into()))?;
             let entries = env
                 .create_database::<Str, Str>(&mut wtxn, Some("dedupe_entries"))
                 .map_err(|err| DedupeError::Storage(err.to_string().into()))?;
+            Self::migrate_legacy_keys(&mut wtxn, entries)?;
             wtxn.commit()
                 .map_err(|err| DedupeError::Storage(err.to_string().into()))?;
             Ok(Self { env, entries })
         }
 
-        fn key(saga_id: SagaId, key: &str) -> String {
-            format!("{:020}:{key}", saga_id.get())
+        /// Rewrites `{saga_id:020}:{string key}` rows to the hashed key
+        /// [`DedupeKey::from_legacy`] maps them to, so events already
+        /// processed before the upgrade stay duplicates.
+        fn migrate_legacy_keys(
+            wtxn: &mut heed::RwTxn<'_>,
+            entries: Database<Str, Str>,
+        ) -> Result<(), DedupeError> {
+            let mut legacy = Vec::new();
+            for row in entries
+                .iter(wtxn)
+                .map_err(|err| DedupeError::Storage(err.to_string().into()))?
+            {
+                let (key, _) = row.map_err(|err| DedupeError::Storage(err.to_string().into()))?;
+                if !is_hashed_dedupe_key(key) {
+                    legacy.push(key.to_owned());
+                }
+            }
+            for key in legacy {
+                let Some((saga_id, legacy_key)) = key
+                    .split_once(':')
+                    .and_then(|(id, rest)| Some((id.parse::<u64>().ok()?, rest)))
+                else {
+                    continue;
+                };
+                let migrated = Self::key(SagaId::new(saga_id), DedupeKey::from_legacy(legacy_key));
+                entries
+                    .put(wtxn, migrated.as_str(), "1")
+                    .map_err(|err| DedupeError::Storage(err.to_string().into()))?;
+                entries
+                    .delete(wtxn, key.as_str())
+                    .map_err(|err| DedupeError::Storage(err.to_string().into()))?;
+            }
+            Ok(())
+        }
+
+        fn key(saga_id: SagaId, key: DedupeKey) -> LmdbDedupeKey {
+            use std::io::Write;
+
+            let mut encoded = [0u8; LMDB_DEDUPE_KEY_LEN];
+            let _ = write!(&mut encoded[..], "{:020}:{key}", saga_id.get());
+            LmdbDedupeKey(encoded)
+        }
+    }
+
+    /// `{saga_id:020}:{key:032x}`, formatted on the stack.
+    const LMDB_DEDUPE_KEY_LEN: usize = 20 + 1 + 32;
+
+    struct LmdbDedupeKey([u8; LMDB_DEDUPE_KEY_LEN]);
+
+    fn is_hashed_dedupe_key(key: &str) -> bool {
+        let bytes = key.as_bytes();
+        bytes.len() == LMDB_DEDUPE_KEY_LEN
+            && bytes[20] == b':'
+            && bytes[..20].iter().all(u8::is_ascii_digit)
+            && bytes[21..].iter().all(u8::is_ascii_hexdigit)
+    }
+
+    impl LmdbDedupeKey {
+        fn as_str(&self) -> &str {
+            std::str::from_utf8(&self.0).unwrap_or_default()
         }
     }
 
     impl ParticipantDedupeStore for LmdbDedupe {
-        fn check_and_mark(&self, saga_id: SagaId, key: &str) -> Result<bool, DedupeError> {
+        fn check_and_mark(&self, saga_id: SagaId, key: DedupeKey) -> Result<bool, DedupeError> {
             let full_key = Self::key(saga_id, key);
             let mut wtxn = self
                 .env
@@ -1369,37 +2527,37 @@ pub mod lmdb {
                 .map_err(|err| DedupeError::Storage(err.to_string().into()))?;
             if self
                 .entries
-                .get(&wtxn, &full_key)
+                .get(&wtxn, full_key.as_str())
                 .map_err(|err| DedupeError::Storage(err.to_string().into()))?
                 .is_some()
             {
                 return Ok(false);
             }
             self.entries
-                .put(&mut wtxn, &full_key, "1")
+                .put(&mut wtxn, full_key.as_str(), "1")
                 .map_err(|err| DedupeError::Storage(err.to_string().into()))?;
             wtxn.commit()
                 .map_err(|err| DedupeError::Storage(err.to_string().into()))?;
             Ok(true)
         }
 
-        fn contains(&self, saga_id: SagaId, key: &str) -> bool {
+        fn contains(&self, saga_id: SagaId, key: DedupeKey) -> bool {
             let Ok(rtxn) = self.env.read_txn() else {
                 return false;
             };
             self.entries
-                .get(&rtxn, &Self::key(saga_id, key))
+                .get(&rtxn, Self::key(saga_id, key).as_str())
                 .map(|v| v.is_some())
                 .unwrap_or(false)
         }
 
-        fn mark_processed(&self, saga_id: SagaId, key: &str) -> Result<(), DedupeError> {
+        fn mark_processed(&self, saga_id: SagaId, key: DedupeKey) -> Result<(), DedupeError> {
             let mut wtxn = self
                 .env
                 .write_txn()
                 .map_err(|err| DedupeError::Storage(err.to_string().into()))?;
             self.entries
-                .put(&mut wtxn, &Self::key(saga_id, key), "1")
+                .put(&mut wtxn, Self::key(saga_id, key).as_str(), "1")
                 .map_err(|err| DedupeError::Storage(err.to_string().into()))?;
             wtxn.commit()
                 .map_err(|err| DedupeError::Storage(err.to_string().into()))?;
@@ -1425,6 +2583,146 @@ pub mod lmdb {
                 .map_err(|err| DedupeError::Storage(err.to_string().into()))?;
             Ok(())
         }
+
+        fn list_sagas(&self) -> Result<Option<Vec<SagaId>>, DedupeError> {
+            let rtxn = self
+                .env
+                .read_txn()
+                .map_err(|err| DedupeError::Storage(err.to_string().into()))?;
+            let iter = self
+                .entries
+                .iter(&rtxn)
+                .map_err(|err| DedupeError::Storage(err.to_string().into()))?;
+            let mut out: Vec<SagaId> = Vec::new();
+            for row in iter {
+                let (k, _) = row.map_err(|err| DedupeError::Storage(err.to_string().into()))?;
+                let Some(id) = k.split(':').next().and_then(|id| id.parse::<u64>().ok()) else {
+                    continue;
+                };
+                // Keys sort by the zero-padded saga id, so duplicates are adjacent.
+                if out.last().map(|last| last.get()) != Some(id) {
+                    out.push(SagaId::new(id));
+                }
+            }
+            Ok(Some(out))
+        }
+    }
+
+    /// Current state of each saga, as rkyv archives of [`SagaStateEntry`]
+    /// keyed by saga id. Rows are a cache of the journal: after an upgrade
+    /// that changes the state types, clear the database and let recovery
+    /// rebuild it.
+    #[derive(Debug)]
+    pub struct LmdbStateStore {
+        env: Env,
+        states: Database<Str, Bytes>,
+    }
+
+    impl LmdbStateStore {
+        pub fn open(path: &Path) -> Result<Self, ParticipantStateStoreError> {
+            let env = open_env(path, 8).map_err(ParticipantStateStoreError::Storage)?;
+            Self::in_env(env)
+        }
+
+        /// Creates or opens the state database in `env`.
+        fn in_env(env: Env) -> Result<Self, ParticipantStateStoreError> {
+            let storage =
+                |err: heed::Error| ParticipantStateStoreError::Storage(err.to_string().into());
+            let mut wtxn = env.write_txn().map_err(storage)?;
+            let states = env
+                .create_database::<Str, Bytes>(&mut wtxn, Some("participant_states"))
+                .map_err(storage)?;
+            wtxn.commit().map_err(storage)?;
+            Ok(Self { env, states })
+        }
+    }
+
+    impl ParticipantStateStore for LmdbStateStore {
+        fn put(
+            &self,
+            saga_id: SagaId,
+            entry: &SagaStateEntry,
+        ) -> Result<(), ParticipantStateStoreError> {
+            let storage =
+                |err: heed::Error| ParticipantStateStoreError::Storage(err.to_string().into());
+            let encoded = rkyv::to_bytes::<rkyv::rancor::Error>(entry)
+                .map_err(|err| ParticipantStateStoreError::Storage(err.to_string().into()))?;
+            let mut wtxn = self.env.write_txn().map_err(storage)?;
+            self.states
+                .put(&mut wtxn, &key_saga_index(saga_id), encoded.as_ref())
+                .map_err(storage)?;
+            wtxn.commit().map_err(storage)
+        }
+
+        fn get(
+            &self,
+            saga_id: SagaId,
+        ) -> Result<Option<SagaStateEntry>, ParticipantStateStoreError> {
+            let storage =
+                |err: heed::Error| ParticipantStateStoreError::Storage(err.to_string().into());
+            let rtxn = self.env.read_txn().map_err(storage)?;
+            let Some(row) = self
+                .states
+                .get(&rtxn, &key_saga_index(saga_id))
+                .map_err(storage)?
+            else {
+                return Ok(None);
+            };
+            let owned = row.to_vec();
+            rkyv::from_bytes::<SagaStateEntry, rkyv::rancor::Error>(&owned)
+                .map(Some)
+                .map_err(|err| ParticipantStateStoreError::Storage(err.to_string().into()))
+        }
+
+        fn delete(&self, saga_id: SagaId) -> Result<(), ParticipantStateStoreError> {
+            let storage =
+                |err: heed::Error| ParticipantStateStoreError::Storage(err.to_string().into());
+            let mut wtxn = self.env.write_txn().map_err(storage)?;
+            self.states
+                .delete(&mut wtxn, &key_saga_index(saga_id))
+                .map_err(storage)?;
+            wtxn.commit().map_err(storage)
+        }
+    }
+
+    /// Everything a participant persists, in one LMDB environment: the
+    /// journal (which also holds the effect ledger), the dedupe store and
+    /// the state store, each under the named databases its own `open`
+    /// uses.
+    ///
+    /// ```ignore
+    /// let storage = HeedSagaStorage::open(Path::new("/var/lib/orders/saga"))?;
+    /// let saga = storage.into_participant_support();
+    /// ```
+    #[derive(Debug)]
+    pub struct HeedSagaStorage {
+        pub journal: LmdbJournal,
+        pub dedupe: LmdbDedupe,
+        pub state_store: Arc<LmdbStateStore>,
+    }
+
+    impl HeedSagaStorage {
+        /// Opens or creates the environment at `path`, with the map size
+        /// from `SAGA_LMDB_MAP_SIZE_BYTES` or 1 GiB.
+        pub fn open(path: &Path) -> Result<Self, String> {
+            let env = open_env(path, 16).map_err(String::from)?;
+            Ok(Self {
+                journal: LmdbJournal::in_env(env.clone()).map_err(|err| err.to_string())?,
+                dedupe: LmdbDedupe::in_env(env.clone()).map_err(|err| err.to_string())?,
+                state_store: Arc::new(LmdbStateStore::in_env(env).map_err(|err| err.to_string())?),
+            })
+        }
+
+        pub fn effect_ledger(&self, saga_id: SagaId) -> EffectLedger<'_, LmdbJournal> {
+            EffectLedger::new(&self.journal, saga_id)
+        }
+
+        /// Support over the journal and dedupe store, with the state store
+        /// attached.
+        pub fn into_participant_support(self) -> SagaParticipantSupport<LmdbJournal, LmdbDedupe> {
+            SagaParticipantSupport::new(self.journal, self.dedupe)
+                .with_state_store(self.state_store)
+        }
     }
 
     pub fn open_lmdb_participant_support(
@@ -1477,21 +2775,147 @@ pub mod lmdb {
             };
             let saga_id = SagaId::new(404);
             dedupe
-                .mark_processed(saga_id, "probe")
+                .mark_processed(saga_id, "probe".into())
                 .expect("mark_processed should succeed");
 
             let held_reader = env.read_txn().expect("held reader should open");
             assert!(
-                !dedupe.contains(saga_id, "probe"),
+                !dedupe.contains(saga_id, "probe".into()),
                 "contains should return false when read_txn cannot reserve a reader slot"
             );
             drop(held_reader);
 
             assert!(
-                dedupe.contains(saga_id, "probe"),
+                dedupe.contains(saga_id, "probe".into()),
                 "contains should recover once reader slot pressure is released"
             );
         }
+
+        #[test]
+        fn string_keys_from_before_the_upgrade_still_dedupe() {
+            let temp = tempfile::tempdir().expect("tempdir should open");
+            let path = temp.path().join("lmdb-dedupe-legacy");
+            let context = crate::DeterministicContextBuilder::default()
+                .with_saga_id(9)
+                .build();
+            let started = crate::saga_started(context.clone(), Vec::new());
+            let legacy_event_key = format!(
+                "{:020}:{}:{}:saga_started:{}",
+                9, context.trace_id, context.saga_started_at_millis, context.step_name
+            );
+            let legacy_named_key =
+                format!("{:020}:{}", 9, super::super::PANIC_QUARANTINE_PUBLISH_KEY);
+            let env = open_env(&path, 8).expect("env should open");
+            let mut wtxn = env.write_txn().expect("write txn should open");
+            let entries = env
+                .create_database::<Str, Str>(&mut wtxn, Some("dedupe_entries"))
+                .expect("dedupe database should be created");
+            for key in [&legacy_event_key, &legacy_named_key] {
+                entries
+                    .put(&mut wtxn, key, "1")
+                    .expect("legacy row should be written");
+            }
+            wtxn.commit().expect("legacy rows should commit");
+
+            let dedupe = LmdbDedupe::in_env(env).expect("dedupe should open");
+            let saga_id = SagaId::new(9);
+            assert!(!dedupe
+                .check_and_mark(saga_id, DedupeKey::from_event(&started))
+                .expect("check should succeed"));
+            assert!(dedupe.contains(
+                saga_id,
+                DedupeKey::named(super::super::PANIC_QUARANTINE_PUBLISH_KEY)
+            ));
+            let rtxn = dedupe.env.read_txn().expect("read txn should open");
+            assert!(dedupe
+                .entries
+                .iter(&rtxn)
+                .expect("iter should open")
+                .all(|row| is_hashed_dedupe_key(row.expect("row should decode").0)));
+        }
+
+        #[test]
+        fn storage_bundle_reopens_every_store_from_one_environment() {
+            let temp = tempfile::tempdir().expect("tempdir should open");
+            let path = temp.path().join("saga");
+            let saga_id = SagaId::new(42);
+            let key = crate::IdempotencyKey::for_step(saga_id, "place_order", 1);
+            let triggered = crate::SagaParticipantState::new(
+                saga_id,
+                "order_lifecycle".into(),
+                "place_order".into(),
+                7,
+                8,
+                [1; 32],
+                1_000,
+            )
+            .trigger("saga_started", 1_001);
+            {
+                let storage = HeedSagaStorage::open(&path).expect("storage should open");
+                assert!(storage
+                    .dedupe
+                    .check_and_mark(saga_id, "probe".into())
+                    .expect("dedupe should mark"));
+                storage
+                    .state_store
+                    .put(saga_id, &SagaStateEntry::Triggered(triggered))
+                    .expect("state should be stored");
+                let ledger = storage.effect_ledger(saga_id);
+                assert!(ledger
+                    .begin_effect(&key)
+                    .expect("effect should begin")
+                    .is_fresh());
+                ledger
+                    .confirm(&key, b"order-1")
+                    .expect("effect should confirm");
+            }
+
+            let saga = HeedSagaStorage::open(&path)
+                .expect("storage should reopen")
+                .into_participant_support();
+            assert!(saga.dedupe.contains(saga_id, "probe".into()));
+            assert_eq!(
+                saga.journal.list_sagas().expect("sagas should list"),
+                vec![saga_id]
+            );
+            assert!(matches!(
+                saga.effect_ledger(saga_id).status(&key),
+                Ok(crate::EffectGuard::Confirmed { result, .. }) if result == b"order-1"
+            ));
+            let Some(SagaStateEntry::Triggered(restored)) = saga
+                .state_store
+                .as_ref()
+                .expect("state store should be attached")
+                .get(saga_id)
+                .expect("state should load")
+            else {
+                panic!("state should be restored as triggered");
+            };
+            assert_eq!(restored.correlation_id, 7);
+            assert_eq!(&*restored.state.triggering_event, "saga_started");
+        }
+
+        #[test]
+        fn journal_keeps_stats_snapshot_across_reopen() {
+            let temp = tempfile::tempdir().expect("tempdir should open");
+            let path = temp.path().join("journal");
+            let mut snapshot = ParticipantStatsSnapshot {
+                steps_completed: 7,
+                duplicate_events: 2,
+                ..ParticipantStatsSnapshot::default()
+            };
+            snapshot.duplicate_events_by_type.insert("saga_started", 2);
+            {
+                let journal = LmdbJournal::open(&path).expect("journal should open");
+                assert!(journal.load_stats().expect("load should succeed").is_none());
+                journal.save_stats(&snapshot).expect("save should succeed");
+            }
+            let journal = LmdbJournal::open(&path).expect("journal should reopen");
+            assert_eq!(
+                journal.load_stats().expect("load should succeed"),
+                Some(snapshot)
+            );
+        }
     }
 }
 
@@ -1502,9 +2926,9 @@ mod tests {
         ActiveSagaExecution, HasActiveSagaExecution,
     };
     use crate::{
-        DependencySpec, DeterministicContextBuilder, HasSagaParticipantSupport,
-        HasSagaWorkflowParticipants, InMemoryDedupe, InMemoryJournal, SagaParticipantSupport,
-        SagaWorkflowParticipant, StepOutput,
+        DeadLetterStore, DependencySpec, DeterministicContextBuilder, HasSagaParticipantSupport,
+        HasSagaWorkflowParticipants, InMemoryDeadLetterStore, InMemoryDedupe, InMemoryJournal,
+        ParticipantJournal, SagaParticipantSupport, SagaWorkflowParticipant, StepOutput,
     };
 
     struct WorkflowTestActor {
@@ -1739,6 +3163,115 @@ mod tests {
         assert_eq!(actor.beta_calls, 1);
     }
 
+    #[test]
+    fn workflow_ingress_stages_step_completion_with_its_journal_entry() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let bus = crate::SagaChoreographyBus::new();
+        let accepting = std::sync::Arc::new(AtomicBool::new(false));
+        let accepting_clone = std::sync::Arc::clone(&accepting);
+        let _sub = bus.subscribe_saga_type_fn("beta_workflow", move |_event| {
+            accepting_clone.load(Ordering::Relaxed)
+        });
+        let mut actor = WorkflowTestActor::default();
+        actor.saga.attach_bus(bus);
+        let event = crate::SagaChoreographyEvent::SagaStarted {
+            context: DeterministicContextBuilder::default()
+                .with_saga_id(79)
+                .with_saga_type("beta_workflow")
+                .with_step_name("beta_step")
+                .build(),
+            payload: Vec::new(),
+        };
+
+        apply_sync_workflow_participant_saga_ingress(
+            &mut actor,
+            event,
+            |_actor, _event| {},
+            |_| {},
+        );
+
+        // The bus rejected the completion; it stays staged beside the entry
+        // that decided it.
+        let journal = &actor.saga.journal;
+        assert!(journal
+            .read(crate::SagaId::new(79))
+            .expect("journal should read")
+            .iter()
+            .any(|entry| matches!(
+                entry.event,
+                crate::ParticipantEvent::StepExecutionCompleted { .. }
+            )));
+        let pending = journal
+            .pending_outgoing()
+            .expect("outbox should be readable");
+        assert!(pending.iter().any(|entry| matches!(
+            entry.event,
+            crate::SagaChoreographyEvent::StepCompleted { .. }
+        )));
+
+        accepting.store(true, Ordering::Relaxed);
+        assert_eq!(actor.saga.relay_outbox(), Ok(pending.len()));
+        assert!(actor
+            .saga
+            .journal
+            .pending_outgoing()
+            .expect("outbox should be readable")
+            .is_empty());
+    }
+
+    #[test]
+    fn workflow_ingress_dead_letters_unknown_saga_type_when_opted_in() {
+        let store = std::sync::Arc::new(InMemoryDeadLetterStore::new());
+        let mut actor = WorkflowTestActor::default();
+        actor.saga.attach_dead_letter_store(store.clone());
+        actor.saga.dead_letter_unknown_sagas = true;
+        let event = crate::SagaChoreographyEvent::SagaStarted {
+            context: DeterministicContextBuilder::default()
+                .with_saga_id(78)
+                .with_saga_type("unregistered_workflow")
+                .build(),
+            payload: Vec::new(),
+        };
+
+        apply_sync_workflow_participant_saga_ingress(
+            &mut actor,
+            event,
+            |_actor, _event| {},
+            |_| {},
+        );
+
+        assert_eq!(actor.alpha_calls + actor.beta_calls, 0);
+        let entries = store.list().expect("dead letters should list");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].reason, crate::DeadLetterReason::UnknownSaga);
+        assert_eq!(entries[0].payload.saga_id(), Some(crate::SagaId::new(78)));
+    }
+
+    #[test]
+    fn workflow_ingress_drops_unknown_saga_type_quietly() {
+        let store = std::sync::Arc::new(InMemoryDeadLetterStore::new());
+        let mut actor = WorkflowTestActor::default();
+        actor.saga.attach_dead_letter_store(store.clone());
+        let event = crate::SagaChoreographyEvent::SagaStarted {
+            context: DeterministicContextBuilder::default()
+                .with_saga_id(78)
+                .with_saga_type("unregistered_workflow")
+                .build(),
+            payload: Vec::new(),
+        };
+
+        apply_sync_workflow_participant_saga_ingress(
+            &mut actor,
+            event,
+            |_actor, _event| {},
+            |_| {},
+        );
+
+        assert_eq!(actor.alpha_calls + actor.beta_calls, 0);
+        assert!(store.list().expect("dead letters should list").is_empty());
+    }
+
     #[test]
     fn workflow_lookup_rejects_duplicate_saga_type_registration() {
         let event = crate::SagaChoreographyEvent::SagaStarted {
@@ -0,0 +1,179 @@
+//! Journaled record of external side effects.
+//!
+//! An order placed on an exchange cannot be taken back by replaying the
+//! step. When a participant crashes between sending the order and journaling
+//! the step's completion, the inbox replay runs `execute_step` again and,
+//! without a record, would send it twice. The [`EffectLedger`] journals an
+//! `EffectBegun` entry before the call and an `EffectConfirmed` entry with
+//! its result afterwards, so the re-run learns what happened:
+//!
+//! ```ignore
+//! let key = IdempotencyKey::for_step(ctx.saga_id, "place_order", ctx.attempt);
+//! let ledger = self.saga_support().effect_ledger(ctx.saga_id);
+//! let order_id = match ledger.begin_effect(&key)? {
+//!     EffectGuard::Fresh => {
+//!         let order_id = self.exchange.place(&order, key.as_str())?;
+//!         ledger.confirm(&key, order_id.as_bytes())?;
+//!         order_id
+//!     }
+//!     // Sent before the crash, result not recorded: ask the exchange.
+//!     EffectGuard::InDoubt { .. } => self.exchange.order_by_label(key.as_str())?,
+//!     EffectGuard::Confirmed { result, .. } => OrderId::from_bytes(&result),
+//! };
+//! ```
+//!
+//! Ledger entries live in the participant journal and are pruned with it.
+
+use crate::{
+    IdempotencyKey, JournalError, ParticipantEvent, ParticipantJournal, SagaContext, SagaId,
+};
+
+/// What the ledger knows about an effect when a step is about to dispatch it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EffectGuard {
+    /// No earlier attempt; dispatch the effect, then [`EffectLedger::confirm`].
+    Fresh,
+    /// An earlier attempt began but never confirmed. The effect may or may
+    /// not have reached the external system; reconcile before retrying.
+    InDoubt { begun_at_millis: u64 },
+    /// The effect happened; `result` is what was confirmed.
+    Confirmed {
+        result: Vec<u8>,
+        confirmed_at_millis: u64,
+    },
+}
+
+impl EffectGuard {
+    /// Whether the effect can be dispatched without reconciling first.
+    pub fn is_fresh(&self) -> bool {
+        matches!(self, Self::Fresh)
+    }
+}
+
+/// Effect ledger of one saga, backed by the participant journal.
+pub struct EffectLedger<'a, J: ?Sized> {
+    journal: &'a J,
+    saga_id: SagaId,
+}
+
+impl<'a, J> EffectLedger<'a, J>
+where
+    J: ParticipantJournal + ?Sized,
+{
+    pub fn new(journal: &'a J, saga_id: SagaId) -> Self {
+        Self { journal, saga_id }
+    }
+
+    /// Looks `key` up and, if the ledger has never seen it, journals that
+    /// its dispatch begins.
+    pub fn begin_effect(&self, key: &IdempotencyKey) -> Result<EffectGuard, JournalError> {
+        let guard = self.status(key)?;
+        if guard.is_fresh() {
+            self.journal.append(
+                self.saga_id,
+                ParticipantEvent::EffectBegun {
+                    key: key.0.clone(),
+                    begun_at_millis: SagaContext::now_millis(),
+                },
+            )?;
+        }
+        Ok(guard)
+    }
+
+    /// Journals that the effect for `key` happened with `result`.
+    pub fn confirm(&self, key: &IdempotencyKey, result: &[u8]) -> Result<(), JournalError> {
+        self.journal.append(
+            self.saga_id,
+            ParticipantEvent::EffectConfirmed {
+                key: key.0.clone(),
+                result: result.to_vec(),
+                confirmed_at_millis: SagaContext::now_millis(),
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Current state of `key` without journaling anything.
+    pub fn status(&self, key: &IdempotencyKey) -> Result<EffectGuard, JournalError> {
+        let mut guard = EffectGuard::Fresh;
+        for entry in self.journal.read(self.saga_id)? {
+            match entry.event {
+                ParticipantEvent::EffectBegun {
+                    key: recorded,
+                    begun_at_millis,
+                } if recorded == key.0 && guard.is_fresh() => {
+                    guard = EffectGuard::InDoubt { begun_at_millis };
+                }
+                ParticipantEvent::EffectConfirmed {
+                    key: recorded,
+                    result,
+                    confirmed_at_millis,
+                } if recorded == key.0 => {
+                    return Ok(EffectGuard::Confirmed {
+                        result,
+                        confirmed_at_millis,
+                    });
+                }
+                _ => {}
+            }
+        }
+        Ok(guard)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryJournal;
+
+    #[test]
+    fn unconfirmed_effect_is_in_doubt_after_restart() {
+        let journal = InMemoryJournal::new();
+        let saga_id = SagaId::new(9);
+        let key = IdempotencyKey::for_step(saga_id, "place_order", 1);
+
+        assert_eq!(
+            EffectLedger::new(&journal, saga_id)
+                .begin_effect(&key)
+                .unwrap(),
+            EffectGuard::Fresh
+        );
+        // Crash before confirm; the re-run sees the begun record.
+        let ledger = EffectLedger::new(&journal, saga_id);
+        assert!(matches!(
+            ledger.begin_effect(&key).unwrap(),
+            EffectGuard::InDoubt { .. }
+        ));
+
+        ledger.confirm(&key, b"order-17").unwrap();
+        assert!(matches!(
+            ledger.begin_effect(&key).unwrap(),
+            EffectGuard::Confirmed { result, .. } if result == b"order-17"
+        ));
+        assert_eq!(journal.read(saga_id).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn ledger_records_do_not_count_as_progress() {
+        let journal = InMemoryJournal::new();
+        let saga_id = SagaId::new(9);
+        journal
+            .append(
+                saga_id,
+                ParticipantEvent::StepExecutionStarted {
+                    attempt: 1,
+                    started_at_millis: 1,
+                },
+            )
+            .unwrap();
+        EffectLedger::new(&journal, saga_id)
+            .begin_effect(&IdempotencyKey::for_step(saga_id, "place_order", 1))
+            .unwrap();
+
+        let entries = journal.read(saga_id).unwrap();
+        assert!(matches!(
+            crate::journal::last_progress_entry(&entries).map(|entry| &entry.event),
+            Some(ParticipantEvent::StepExecutionStarted { .. })
+        ));
+    }
+}
@@ -0,0 +1,280 @@
+//! Dispatch of effects requested through [`StepOutput::CompletedWithEffect`].
+//!
+//! A step that completes with an effect names it (`effect: "notify_risk"`);
+//! the participant's [`EffectDispatcher`] maps that name to whatever should
+//! happen, usually a message to another actor. The helpers invoke the
+//! dispatcher once the completion is journaled, guarded by
+//! [`IdempotencyKey::for_effect`] in the dedupe store so a redelivered or
+//! replayed completion does not fire the effect again.
+//!
+//! The key is marked only after the dispatcher returns `Ok`, so a crash in
+//! between can repeat an effect. Handlers forward
+//! [`EffectInvocation::idempotency_key`] to receivers that must not see it
+//! twice.
+//!
+//! [`StepOutput::CompletedWithEffect`]: crate::StepOutput::CompletedWithEffect
+
+use std::collections::HashMap;
+
+use crate::async_storage::{dedupe_contains, dedupe_mark_processed};
+use crate::{
+    DedupeError, DedupeKey, IdempotencyKey, ParticipantDedupeStore, SagaContext, SagaStateExt,
+};
+
+/// One effect to carry out.
+#[derive(Clone, Copy, Debug)]
+pub struct EffectInvocation<'a> {
+    /// Context of the event whose step requested the effect.
+    pub context: &'a SagaContext,
+    pub step_name: &'a str,
+    pub effect: &'a str,
+    /// The step's output.
+    pub output: &'a [u8],
+    pub idempotency_key: &'a IdempotencyKey,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum EffectError {
+    #[error("no handler registered for effect {0}")]
+    Unknown(Box<str>),
+    #[error("effect {effect} failed: {reason}")]
+    Failed { effect: Box<str>, reason: Box<str> },
+}
+
+/// Carries out effects named by completed steps.
+pub trait EffectDispatcher: Send + Sync + 'static {
+    fn dispatch(&self, invocation: &EffectInvocation<'_>) -> Result<(), EffectError>;
+}
+
+impl<T> EffectDispatcher for std::sync::Arc<T>
+where
+    T: EffectDispatcher + ?Sized,
+{
+    fn dispatch(&self, invocation: &EffectInvocation<'_>) -> Result<(), EffectError> {
+        (**self).dispatch(invocation)
+    }
+}
+
+type EffectHandler = Box<dyn Fn(&EffectInvocation<'_>) -> Result<(), Box<str>> + Send + Sync>;
+
+/// [`EffectDispatcher`] that looks effects up by name.
+///
+/// ```ignore
+/// let risk = risk_actor_ref.clone();
+/// let effects = EffectRegistry::new().with_handler("notify_risk", move |effect| {
+///     risk.tell(RiskMsg::OrderFilled {
+///         saga_id: effect.context.saga_id,
+///         key: effect.idempotency_key.clone(),
+///     })
+///     .map_err(|err| err.to_string().into())
+/// });
+/// ```
+#[derive(Default)]
+pub struct EffectRegistry {
+    handlers: HashMap<Box<str>, EffectHandler>,
+}
+
+impl EffectRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_handler<F>(mut self, effect: &str, handler: F) -> Self
+    where
+        F: Fn(&EffectInvocation<'_>) -> Result<(), Box<str>> + Send + Sync + 'static,
+    {
+        self.register(effect, handler);
+        self
+    }
+
+    /// Registers `handler` for `effect`, replacing an earlier one.
+    pub fn register<F>(&mut self, effect: &str, handler: F)
+    where
+        F: Fn(&EffectInvocation<'_>) -> Result<(), Box<str>> + Send + Sync + 'static,
+    {
+        self.handlers.insert(effect.into(), Box::new(handler));
+    }
+
+    pub fn handles(&self, effect: &str) -> bool {
+        self.handlers.contains_key(effect)
+    }
+}
+
+impl EffectDispatcher for EffectRegistry {
+    fn dispatch(&self, invocation: &EffectInvocation<'_>) -> Result<(), EffectError> {
+        let handler = self
+            .handlers
+            .get(invocation.effect)
+            .ok_or_else(|| EffectError::Unknown(invocation.effect.into()))?;
+        handler(invocation).map_err(|reason| EffectError::Failed {
+            effect: invocation.effect.into(),
+            reason,
+        })
+    }
+}
+
+impl std::fmt::Debug for EffectRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut effects: Vec<&str> = self.handlers.keys().map(AsRef::as_ref).collect();
+        effects.sort_unstable();
+        f.debug_struct("EffectRegistry")
+            .field("effects", &effects)
+            .finish()
+    }
+}
+
+/// Runs `effect` through the participant's dispatcher unless its key is
+/// already marked. `journaled` tells whether the completion reached the
+/// journal; effects of unjournaled completions are skipped.
+pub(crate) fn dispatch_step_effect<P>(
+    participant: &P,
+    context: &SagaContext,
+    step_name: &str,
+    effect: &str,
+    output: &[u8],
+    journaled: bool,
+) where
+    P: SagaStateExt,
+{
+    let saga_id = context.saga_id;
+    let Some(dispatcher) = effect_dispatcher(participant, context, step_name, effect, journaled)
+    else {
+        return;
+    };
+    let idempotency_key = IdempotencyKey::for_effect(saga_id, step_name, effect);
+    let dedupe_key = DedupeKey::named(idempotency_key.as_str());
+    if participant.saga_dedupe().contains(saga_id, dedupe_key) {
+        effect_duplicate_skipped(context, step_name, effect);
+        return;
+    }
+    let invocation = EffectInvocation {
+        context,
+        step_name,
+        effect,
+        output,
+        idempotency_key: &idempotency_key,
+    };
+    if !run_effect(dispatcher.as_ref(), &invocation) {
+        return;
+    }
+    let marked = participant
+        .saga_dedupe()
+        .mark_processed(saga_id, dedupe_key);
+    effect_marked(context, step_name, effect, marked);
+}
+
+/// [`dispatch_step_effect`] for async handlers, which await an async dedupe
+/// store.
+pub(crate) async fn dispatch_step_effect_async<P>(
+    participant: &P,
+    context: &SagaContext,
+    step_name: &str,
+    effect: &str,
+    output: &[u8],
+    journaled: bool,
+) where
+    P: SagaStateExt,
+{
+    let saga_id = context.saga_id;
+    let Some(dispatcher) = effect_dispatcher(participant, context, step_name, effect, journaled)
+    else {
+        return;
+    };
+    let idempotency_key = IdempotencyKey::for_effect(saga_id, step_name, effect);
+    let dedupe_key = DedupeKey::named(idempotency_key.as_str());
+    if dedupe_contains(participant.saga_dedupe(), saga_id, dedupe_key).await {
+        effect_duplicate_skipped(context, step_name, effect);
+        return;
+    }
+    let invocation = EffectInvocation {
+        context,
+        step_name,
+        effect,
+        output,
+        idempotency_key: &idempotency_key,
+    };
+    if !run_effect(dispatcher.as_ref(), &invocation) {
+        return;
+    }
+    let marked = dedupe_mark_processed(participant.saga_dedupe(), saga_id, dedupe_key).await;
+    effect_marked(context, step_name, effect, marked);
+}
+
+/// The participant's dispatcher, unless the effect is to be skipped.
+fn effect_dispatcher<P>(
+    participant: &P,
+    context: &SagaContext,
+    step_name: &str,
+    effect: &str,
+    journaled: bool,
+) -> Option<std::sync::Arc<dyn EffectDispatcher>>
+where
+    P: SagaStateExt,
+{
+    let saga_id = context.saga_id;
+    let Some(dispatcher) = participant.saga_support().effects.clone() else {
+        tracing::warn!(
+            target: "core::saga",
+            event = "saga_effect_without_dispatcher",
+            saga_id = saga_id.get(),
+            step_name,
+            effect
+        );
+        return None;
+    };
+    if !journaled {
+        tracing::error!(
+            target: "core::saga",
+            event = "saga_effect_skipped_unjournaled",
+            saga_id = saga_id.get(),
+            step_name,
+            effect
+        );
+        return None;
+    }
+    Some(dispatcher)
+}
+
+fn effect_duplicate_skipped(context: &SagaContext, step_name: &str, effect: &str) {
+    tracing::debug!(
+        target: "core::saga",
+        event = "saga_effect_duplicate_skipped",
+        saga_id = context.saga_id.get(),
+        step_name,
+        effect
+    );
+}
+
+/// Dispatches `invocation`; returns whether the dispatcher accepted it.
+fn run_effect(dispatcher: &dyn EffectDispatcher, invocation: &EffectInvocation<'_>) -> bool {
+    let Err(err) = dispatcher.dispatch(invocation) else {
+        return true;
+    };
+    tracing::error!(
+        target: "core::saga",
+        event = "saga_effect_dispatch_failed",
+        saga_id = invocation.context.saga_id.get(),
+        step_name = invocation.step_name,
+        effect = invocation.effect,
+        error = %err
+    );
+    false
+}
+
+fn effect_marked(
+    context: &SagaContext,
+    step_name: &str,
+    effect: &str,
+    marked: Result<(), DedupeError>,
+) {
+    if let Err(err) = marked {
+        tracing::error!(
+            target: "core::saga",
+            event = "saga_effect_dedupe_mark_failed",
+            saga_id = context.saga_id.get(),
+            step_name,
+            effect,
+            error = %err
+        );
+    }
+}
@@ -0,0 +1,293 @@
+//! Mapping client and transport errors onto saga error variants.
+//!
+//! Participants talk to exchanges, databases and other actors whose errors
+//! carry no notion of compensation. An [`ErrorClassifier`] decides once how
+//! each failure should be treated, and [`ErrorClass`] turns the decision into
+//! a [`StepError`] or [`CompensationError`], so every step of a participant
+//! maps the same failure the same way:
+//!
+//! ```ignore
+//! let classifier = classify_by_code(|err: &VenueError| err.code(), &[
+//!     (10_009, ErrorClass::Terminal),            // insufficient funds
+//!     (10_028, ErrorClass::RequireCompensation), // accepted, fill unknown
+//! ])
+//! .or(transport_errors())
+//! .with_default(ErrorClass::RequireCompensation);
+//!
+//! venue.place(order).map_err(|err| classifier.step_error(&err))?;
+//! ```
+
+use std::fmt::Display;
+
+use crate::{CompensationError, StepError};
+
+/// How a failed call should be treated by the saga.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorClass {
+    /// Nothing was applied and trying again may succeed (timeouts,
+    /// disconnects).
+    Retriable,
+    /// Nothing was applied and trying again will not help.
+    Terminal,
+    /// Something may have been applied and must be undone.
+    RequireCompensation,
+}
+
+impl ErrorClass {
+    /// Step errors have no retry variant: a retriable failure applied
+    /// nothing, so it fails the saga without compensation, like `Terminal`.
+    pub fn step_error(self, reason: impl Into<Box<str>>) -> StepError {
+        match self {
+            Self::Retriable | Self::Terminal => StepError::terminal(reason),
+            Self::RequireCompensation => StepError::require_compensation(reason),
+        }
+    }
+
+    /// A failed compensation that may have applied something is ambiguous.
+    pub fn compensation_error(self, reason: impl Into<Box<str>>) -> CompensationError {
+        match self {
+            Self::Retriable => CompensationError::safe_to_retry(reason),
+            Self::Terminal => CompensationError::terminal(reason),
+            Self::RequireCompensation => CompensationError::ambiguous(reason),
+        }
+    }
+}
+
+/// Decides the [`ErrorClass`] of an error, or `None` when it has no opinion.
+pub trait ErrorClassifier<E: ?Sized> {
+    fn classify(&self, error: &E) -> Option<ErrorClass>;
+
+    /// Asks `fallback` when this classifier has no opinion.
+    fn or<C>(self, fallback: C) -> OrClassifier<Self, C>
+    where
+        Self: Sized,
+        C: ErrorClassifier<E>,
+    {
+        OrClassifier {
+            first: self,
+            fallback,
+        }
+    }
+
+    /// Uses `class` when this classifier has no opinion.
+    fn with_default(self, class: ErrorClass) -> DefaultClassifier<Self>
+    where
+        Self: Sized,
+    {
+        DefaultClassifier {
+            inner: self,
+            default: class,
+        }
+    }
+
+    /// Classifies `error` into a [`StepError`] carrying its message;
+    /// unclassified errors require compensation.
+    fn step_error(&self, error: &E) -> StepError
+    where
+        E: Display,
+    {
+        self.classify(error)
+            .unwrap_or(ErrorClass::RequireCompensation)
+            .step_error(error.to_string())
+    }
+
+    /// Classifies `error` into a [`CompensationError`] carrying its message;
+    /// unclassified errors are ambiguous.
+    fn compensation_error(&self, error: &E) -> CompensationError
+    where
+        E: Display,
+    {
+        self.classify(error)
+            .unwrap_or(ErrorClass::RequireCompensation)
+            .compensation_error(error.to_string())
+    }
+}
+
+impl<E, F> ErrorClassifier<E> for F
+where
+    E: ?Sized,
+    F: Fn(&E) -> Option<ErrorClass>,
+{
+    fn classify(&self, error: &E) -> Option<ErrorClass> {
+        self(error)
+    }
+}
+
+/// See [`ErrorClassifier::or`].
+#[derive(Clone, Debug)]
+pub struct OrClassifier<A, B> {
+    first: A,
+    fallback: B,
+}
+
+impl<E, A, B> ErrorClassifier<E> for OrClassifier<A, B>
+where
+    E: ?Sized,
+    A: ErrorClassifier<E>,
+    B: ErrorClassifier<E>,
+{
+    fn classify(&self, error: &E) -> Option<ErrorClass> {
+        self.first
+            .classify(error)
+            .or_else(|| self.fallback.classify(error))
+    }
+}
+
+/// See [`ErrorClassifier::with_default`].
+#[derive(Clone, Debug)]
+pub struct DefaultClassifier<C> {
+    inner: C,
+    default: ErrorClass,
+}
+
+impl<E, C> ErrorClassifier<E> for DefaultClassifier<C>
+where
+    E: ?Sized,
+    C: ErrorClassifier<E>,
+{
+    fn classify(&self, error: &E) -> Option<ErrorClass> {
+        Some(self.inner.classify(error).unwrap_or(self.default))
+    }
+}
+
+/// Classifies by the first pattern found in the error's message, compared
+/// case-insensitively.
+pub fn classify_by_substring<E>(
+    patterns: &'static [(&'static str, ErrorClass)],
+) -> impl ErrorClassifier<E> + Clone
+where
+    E: Display + ?Sized,
+{
+    move |error: &E| {
+        let message = error.to_string().to_ascii_lowercase();
+        patterns
+            .iter()
+            .find(|(pattern, _)| message.contains(&pattern.to_ascii_lowercase()))
+            .map(|(_, class)| *class)
+    }
+}
+
+/// Classifies by the code `code_of` extracts from the error.
+pub fn classify_by_code<E, K, F>(
+    code_of: F,
+    codes: &'static [(K, ErrorClass)],
+) -> impl ErrorClassifier<E> + Clone
+where
+    E: ?Sized,
+    K: PartialEq + 'static,
+    F: Fn(&E) -> Option<K> + Clone,
+{
+    move |error: &E| {
+        let code = code_of(error)?;
+        codes
+            .iter()
+            .find(|(known, _)| *known == code)
+            .map(|(_, class)| *class)
+    }
+}
+
+/// Message fragments of failed asks and dropped connections; the call never
+/// reached its target or its reply was lost before anything was decided.
+pub const TRANSPORT_ERROR_PATTERNS: &[(&str, ErrorClass)] = &[
+    ("timed out", ErrorClass::Retriable),
+    ("timeout", ErrorClass::Retriable),
+    ("mailbox full", ErrorClass::Retriable),
+    ("mailbox closed", ErrorClass::Retriable),
+    ("connection refused", ErrorClass::Retriable),
+    ("connection reset", ErrorClass::Retriable),
+    ("unavailable", ErrorClass::Retriable),
+];
+
+/// Classifies ask timeouts and transport failures as retriable.
+pub fn transport_errors<E>() -> impl ErrorClassifier<E> + Clone
+where
+    E: Display + ?Sized,
+{
+    classify_by_substring(TRANSPORT_ERROR_PATTERNS)
+}
+
+impl From<std::io::Error> for StepError {
+    fn from(error: std::io::Error) -> Self {
+        io_error_class(&error).step_error(error.to_string())
+    }
+}
+
+impl From<std::io::Error> for CompensationError {
+    fn from(error: std::io::Error) -> Self {
+        io_error_class(&error).compensation_error(error.to_string())
+    }
+}
+
+fn io_error_class(error: &std::io::Error) -> ErrorClass {
+    use std::io::ErrorKind;
+
+    match error.kind() {
+        ErrorKind::TimedOut
+        | ErrorKind::ConnectionRefused
+        | ErrorKind::ConnectionReset
+        | ErrorKind::ConnectionAborted
+        | ErrorKind::NotConnected
+        | ErrorKind::Interrupted
+        | ErrorKind::WouldBlock => ErrorClass::Retriable,
+        ErrorKind::NotFound | ErrorKind::PermissionDenied | ErrorKind::InvalidInput => {
+            ErrorClass::Terminal
+        }
+        _ => ErrorClass::RequireCompensation,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct VenueError {
+        code: u32,
+        message: &'static str,
+    }
+
+    impl Display for VenueError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "venue error {}: {}", self.code, self.message)
+        }
+    }
+
+    #[test]
+    fn combinators_classify_codes_then_transport_then_default() {
+        let classifier = classify_by_code(
+            |err: &VenueError| Some(err.code),
+            &[
+                (10_009, ErrorClass::Terminal),
+                (10_028, ErrorClass::RequireCompensation),
+            ],
+        )
+        .or(transport_errors())
+        .with_default(ErrorClass::Terminal);
+
+        let venue = |code, message| VenueError { code, message };
+        assert!(matches!(
+            classifier.step_error(&venue(10_028, "fill unknown")),
+            StepError::RequireCompensation { reason, .. } if reason.contains("fill unknown")
+        ));
+        assert_eq!(
+            classifier.classify(&venue(1, "Request Timed Out")),
+            Some(ErrorClass::Retriable)
+        );
+        assert!(classifier
+            .compensation_error(&venue(1, "ask timeout"))
+            .is_safe_to_retry());
+        assert_eq!(
+            classifier.classify(&venue(2, "bad symbol")),
+            Some(ErrorClass::Terminal)
+        );
+    }
+
+    #[test]
+    fn io_errors_convert_by_kind() {
+        let timed_out = std::io::Error::new(std::io::ErrorKind::TimedOut, "ask timed out");
+        assert!(!StepError::from(timed_out).requires_compensation());
+        let reset = std::io::Error::from(std::io::ErrorKind::ConnectionReset);
+        assert!(CompensationError::from(reset).is_safe_to_retry());
+        let other = std::io::Error::other("partial write");
+        assert!(StepError::from(other).requires_compensation());
+    }
+}
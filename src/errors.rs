@@ -1,7 +1,10 @@
 //! Error types for saga execution and compensation
 
+use crate::sensitive::Redacted;
+use crate::PeerId;
+
 /// Output from step execution
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub enum StepOutput {
     /// Step completed successfully
     Completed {
@@ -19,51 +22,452 @@ pub enum StepOutput {
         /// Effect identifier (actor message to send)
         effect: Box<str>,
     },
+    /// Step completed only part of its work, e.g. an order that was half
+    /// filled. `compensation_data` should describe the part that was done,
+    /// so compensation undoes exactly that; `completion_ratio` is published
+    /// on `StepCompleted` for downstream steps to size their own work.
+    Partial {
+        /// Output data
+        output: Vec<u8>,
+        /// Compensation data for the completed part
+        compensation_data: Vec<u8>,
+        /// Share of the requested work that was done, in `0.0..=1.0`.
+        completion_ratio: f64,
+    },
+    /// Step completed without producing output, e.g. a read-only validation.
+    /// Nothing is stored and the step is never compensated.
+    NoOp,
+}
+
+impl StepOutput {
+    /// Partial completion; `completion_ratio` is clamped to `0.0..=1.0`.
+    pub fn partial(output: Vec<u8>, compensation_data: Vec<u8>, completion_ratio: f64) -> Self {
+        Self::Partial {
+            output,
+            compensation_data,
+            completion_ratio: if completion_ratio.is_nan() {
+                0.0
+            } else {
+                completion_ratio.clamp(0.0, 1.0)
+            },
+        }
+    }
+
+    /// Share of the requested work that was done; `None` when the step
+    /// completed in full.
+    pub fn completion_ratio(&self) -> Option<f64> {
+        match self {
+            Self::Partial {
+                completion_ratio, ..
+            } => Some(*completion_ratio),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Debug for StepOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Completed {
+                output,
+                compensation_data,
+            } => f
+                .debug_struct("Completed")
+                .field("output", output)
+                .field("compensation_data", &Redacted(compensation_data))
+                .finish(),
+            Self::CompletedWithEffect {
+                output,
+                compensation_data,
+                effect,
+            } => f
+                .debug_struct("CompletedWithEffect")
+                .field("output", output)
+                .field("compensation_data", &Redacted(compensation_data))
+                .field("effect", effect)
+                .finish(),
+            Self::Partial {
+                output,
+                compensation_data,
+                completion_ratio,
+            } => f
+                .debug_struct("Partial")
+                .field("output", output)
+                .field("compensation_data", &Redacted(compensation_data))
+                .field("completion_ratio", completion_ratio)
+                .finish(),
+            Self::NoOp => f.write_str("NoOp"),
+        }
+    }
+}
+
+/// Class of a failure or quarantine reason, for aggregating reasons that
+/// are otherwise free text.
+///
+/// Events and journal entries keep their reason as text; a classified
+/// reason is written `[code] message` there, which
+/// [`ReasonCode::split`] reads back. Reasons without the prefix, including
+/// everything journaled before codes existed, are [`ReasonCode::Unclassified`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ReasonCode {
+    /// No answer within the deadline.
+    Timeout,
+    /// The remote system could not be reached or refused service.
+    Unavailable,
+    /// The remote system rejected the request on business grounds.
+    Rejected,
+    /// The request or its payload was invalid.
+    InvalidInput,
+    /// The caller is not allowed to do this.
+    Unauthorized,
+    /// The request conflicts with the current state, e.g. a version clash.
+    Conflict,
+    /// A bug or broken invariant on this side.
+    Internal,
+    #[default]
+    Unclassified,
+    /// Application-defined code, e.g. an exchange error code. Use letters,
+    /// digits, `_`, `-` and `.` only, so it survives [`ReasonCode::tag`].
+    Custom(Box<str>),
+}
+
+impl ReasonCode {
+    pub fn custom(code: impl Into<Box<str>>) -> Self {
+        Self::from_code(&code.into())
+    }
+
+    /// Parses [`as_str`](Self::as_str) output; unknown codes are custom.
+    pub fn from_code(code: &str) -> Self {
+        match code {
+            "timeout" => Self::Timeout,
+            "unavailable" => Self::Unavailable,
+            "rejected" => Self::Rejected,
+            "invalid_input" => Self::InvalidInput,
+            "unauthorized" => Self::Unauthorized,
+            "conflict" => Self::Conflict,
+            "internal" => Self::Internal,
+            "unclassified" => Self::Unclassified,
+            custom => Self::Custom(custom.into()),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Timeout => "timeout",
+            Self::Unavailable => "unavailable",
+            Self::Rejected => "rejected",
+            Self::InvalidInput => "invalid_input",
+            Self::Unauthorized => "unauthorized",
+            Self::Conflict => "conflict",
+            Self::Internal => "internal",
+            Self::Unclassified => "unclassified",
+            Self::Custom(code) => code,
+        }
+    }
+
+    /// `message` as carried in events and journal entries: prefixed with
+    /// `[code] ` unless unclassified.
+    pub fn tag(&self, message: &str) -> Box<str> {
+        match self {
+            Self::Unclassified => message.into(),
+            code => format!("[{}] {message}", code.as_str()).into(),
+        }
+    }
+
+    /// The code as carried in `error_code` fields; `None` when unclassified.
+    pub(crate) fn error_code(&self) -> Option<Box<str>> {
+        (*self != Self::Unclassified).then(|| self.as_str().into())
+    }
+
+    /// Splits a reason written by [`tag`](Self::tag) into its code and
+    /// message.
+    pub fn split(reason: &str) -> (Self, &str) {
+        let tagged = reason.strip_prefix('[').and_then(|rest| {
+            let (code, message) = rest.split_once("] ")?;
+            let valid = !code.is_empty()
+                && code
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+            valid.then(|| (Self::from_code(code), message))
+        });
+        tagged.unwrap_or((Self::Unclassified, reason))
+    }
+}
+
+impl std::fmt::Display for ReasonCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 /// Error from step execution
+///
+/// `details` carries structured error data (e.g. an exchange error code)
+/// into the `StepFailed` event and the journal; it is empty when unset.
 #[derive(Clone, Debug)]
 pub enum StepError {
     /// Permanent error - fail saga without compensation
     Terminal {
         /// Error description
         reason: Box<str>,
+        /// Class of the reason
+        code: ReasonCode,
+        /// Serialized error details
+        details: Vec<u8>,
     },
     /// Error that requires compensation
     RequireCompensation {
         /// Error description
         reason: Box<str>,
+        /// Class of the reason
+        code: ReasonCode,
+        /// Serialized error details
+        details: Vec<u8>,
     },
 }
 
 impl StepError {
+    /// A reason already tagged `[code] message`, e.g. one received from a
+    /// remote participant, keeps its code.
+    pub fn terminal(reason: impl Into<Box<str>>) -> Self {
+        let (code, reason) = split_reason(reason.into());
+        Self::Terminal {
+            reason,
+            code,
+            details: Vec::new(),
+        }
+    }
+
+    pub fn require_compensation(reason: impl Into<Box<str>>) -> Self {
+        let (code, reason) = split_reason(reason.into());
+        Self::RequireCompensation {
+            reason,
+            code,
+            details: Vec::new(),
+        }
+    }
+
     /// Check if this error requires compensation
     pub fn requires_compensation(&self) -> bool {
         matches!(self, Self::RequireCompensation { .. })
     }
+
+    pub fn reason(&self) -> &str {
+        match self {
+            Self::Terminal { reason, .. } | Self::RequireCompensation { reason, .. } => reason,
+        }
+    }
+
+    pub fn code(&self) -> &ReasonCode {
+        match self {
+            Self::Terminal { code, .. } | Self::RequireCompensation { code, .. } => code,
+        }
+    }
+
+    pub fn with_code(mut self, reason_code: ReasonCode) -> Self {
+        match &mut self {
+            Self::Terminal { code, .. } | Self::RequireCompensation { code, .. } => {
+                *code = reason_code;
+            }
+        }
+        self
+    }
+
+    pub fn details(&self) -> &[u8] {
+        match self {
+            Self::Terminal { details, .. } | Self::RequireCompensation { details, .. } => details,
+        }
+    }
+
+    pub fn with_details(mut self, bytes: Vec<u8>) -> Self {
+        match &mut self {
+            Self::Terminal { details, .. } | Self::RequireCompensation { details, .. } => {
+                *details = bytes;
+            }
+        }
+        self
+    }
+
+    /// Attaches `value` archived with rkyv; read it back with
+    /// [`decode_error_details`].
+    pub fn with_typed_details<T>(self, value: &T) -> Self
+    where
+        T: for<'a> rkyv::Serialize<ErrorDetailsSerializer<'a>>,
+    {
+        self.with_details(encode_error_details(value))
+    }
+
+    /// Splits the error into its reason, tagged with its code (see
+    /// [`ReasonCode::tag`]), details and whether it requires compensation.
+    pub fn into_parts(self) -> (Box<str>, Vec<u8>, bool) {
+        match self {
+            Self::Terminal {
+                reason,
+                code,
+                details,
+            } => (code.tag(&reason), details, false),
+            Self::RequireCompensation {
+                reason,
+                code,
+                details,
+            } => (code.tag(&reason), details, true),
+        }
+    }
+}
+
+/// Rejection of an incoming saga event by an `authorize_event` hook
+#[derive(Clone, Debug, thiserror::Error)]
+pub enum AuthError {
+    /// The saga's initiator peer is not trusted for this event
+    #[error("initiator peer is not trusted")]
+    UntrustedPeer {
+        /// The rejected initiator
+        peer: PeerId,
+    },
+    /// Rejected for any other reason
+    #[error("{reason}")]
+    Denied {
+        /// Error description
+        reason: Box<str>,
+    },
 }
 
 /// Error from compensation execution
+///
+/// `details` travels into the `CompensationFailed` event and the journal,
+/// as with [`StepError`].
 #[derive(Clone, Debug)]
 pub enum CompensationError {
     /// Safe to retry - no side effects were applied
     SafeToRetry {
         /// Error description
         reason: Box<str>,
+        /// Class of the reason
+        code: ReasonCode,
+        /// Serialized error details
+        details: Vec<u8>,
     },
     /// Ambiguous state - compensation may or may not have applied
     Ambiguous {
         /// Error description
         reason: Box<str>,
+        /// Class of the reason
+        code: ReasonCode,
+        /// Serialized error details
+        details: Vec<u8>,
     },
     /// Terminal failure - cannot compensate
     Terminal {
         /// Error description
         reason: Box<str>,
+        /// Class of the reason
+        code: ReasonCode,
+        /// Serialized error details
+        details: Vec<u8>,
     },
 }
 
 impl CompensationError {
+    pub fn safe_to_retry(reason: impl Into<Box<str>>) -> Self {
+        let (code, reason) = split_reason(reason.into());
+        Self::SafeToRetry {
+            reason,
+            code,
+            details: Vec::new(),
+        }
+    }
+
+    pub fn ambiguous(reason: impl Into<Box<str>>) -> Self {
+        let (code, reason) = split_reason(reason.into());
+        Self::Ambiguous {
+            reason,
+            code,
+            details: Vec::new(),
+        }
+    }
+
+    pub fn terminal(reason: impl Into<Box<str>>) -> Self {
+        let (code, reason) = split_reason(reason.into());
+        Self::Terminal {
+            reason,
+            code,
+            details: Vec::new(),
+        }
+    }
+
+    pub fn reason(&self) -> &str {
+        match self {
+            Self::SafeToRetry { reason, .. }
+            | Self::Ambiguous { reason, .. }
+            | Self::Terminal { reason, .. } => reason,
+        }
+    }
+
+    pub fn code(&self) -> &ReasonCode {
+        match self {
+            Self::SafeToRetry { code, .. }
+            | Self::Ambiguous { code, .. }
+            | Self::Terminal { code, .. } => code,
+        }
+    }
+
+    pub fn with_code(mut self, reason_code: ReasonCode) -> Self {
+        match &mut self {
+            Self::SafeToRetry { code, .. }
+            | Self::Ambiguous { code, .. }
+            | Self::Terminal { code, .. } => *code = reason_code,
+        }
+        self
+    }
+
+    pub fn details(&self) -> &[u8] {
+        match self {
+            Self::SafeToRetry { details, .. }
+            | Self::Ambiguous { details, .. }
+            | Self::Terminal { details, .. } => details,
+        }
+    }
+
+    pub fn with_details(mut self, bytes: Vec<u8>) -> Self {
+        match &mut self {
+            Self::SafeToRetry { details, .. }
+            | Self::Ambiguous { details, .. }
+            | Self::Terminal { details, .. } => *details = bytes,
+        }
+        self
+    }
+
+    /// Attaches `value` archived with rkyv; read it back with
+    /// [`decode_error_details`].
+    pub fn with_typed_details<T>(self, value: &T) -> Self
+    where
+        T: for<'a> rkyv::Serialize<ErrorDetailsSerializer<'a>>,
+    {
+        self.with_details(encode_error_details(value))
+    }
+
+    /// Splits the error into its reason, tagged with its code (see
+    /// [`ReasonCode::tag`]), details and whether the outcome is ambiguous.
+    pub fn into_parts(self) -> (Box<str>, Vec<u8>, bool) {
+        match self {
+            Self::Ambiguous {
+                reason,
+                code,
+                details,
+            } => (code.tag(&reason), details, true),
+            Self::SafeToRetry {
+                reason,
+                code,
+                details,
+            }
+            | Self::Terminal {
+                reason,
+                code,
+                details,
+            } => (code.tag(&reason), details, false),
+        }
+    }
+
     /// Check if safe to retry
     pub fn is_safe_to_retry(&self) -> bool {
         matches!(self, Self::SafeToRetry { .. })
@@ -74,3 +478,50 @@ impl CompensationError {
         matches!(self, Self::Ambiguous { .. })
     }
 }
+
+fn split_reason(reason: Box<str>) -> (ReasonCode, Box<str>) {
+    match ReasonCode::split(&reason) {
+        (ReasonCode::Unclassified, _) => (ReasonCode::Unclassified, reason),
+        (code, message) => (code, message.into()),
+    }
+}
+
+/// Serializer used for typed error details.
+pub type ErrorDetailsSerializer<'a> = rkyv::api::high::HighSerializer<
+    rkyv::util::AlignedVec,
+    rkyv::ser::allocator::ArenaHandle<'a>,
+    rkyv::rancor::Error,
+>;
+
+/// Archives `value` as error details.
+pub fn encode_error_details<T>(value: &T) -> Vec<u8>
+where
+    T: for<'a> rkyv::Serialize<ErrorDetailsSerializer<'a>>,
+{
+    match rkyv::to_bytes::<rkyv::rancor::Error>(value) {
+        Ok(bytes) => bytes.into_vec(),
+        Err(err) => {
+            tracing::error!(
+                target: "core::saga",
+                event = "saga_error_details_encode_failed",
+                error = %err
+            );
+            Vec::new()
+        }
+    }
+}
+
+/// Reads error details written by `with_typed_details`, e.g. from the
+/// `error_details` of a `StepFailed` event. `None` when empty or of another
+/// type.
+pub fn decode_error_details<T>(details: &[u8]) -> Option<T>
+where
+    T: rkyv::Archive,
+    T::Archived: for<'a> rkyv::bytecheck::CheckBytes<rkyv::api::high::HighValidator<'a, rkyv::rancor::Error>>
+        + rkyv::Deserialize<T, rkyv::api::high::HighDeserializer<rkyv::rancor::Error>>,
+{
+    if details.is_empty() {
+        return None;
+    }
+    crate::journal::migrate::archived(details).ok()
+}
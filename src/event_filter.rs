@@ -0,0 +1,190 @@
+//! Event filtering for actors that only care about part of the saga stream.
+//!
+//! A [`SagaEventFilter`] set on [`crate::SagaParticipantSupport`] drops
+//! unwanted events in the handlers before anything is journaled or deduped.
+//! Actors that never run a step (dashboards, risk monitors) implement the
+//! lighter [`SagaObserverParticipant`] instead of [`crate::SagaParticipant`]
+//! and need no journal or dedupe store at all:
+//!
+//! ```ignore
+//! impl SagaObserverParticipant for RiskManager {
+//!     fn saga_types(&self) -> &[&'static str] { &["order_lifecycle"] }
+//!     fn event_filter(&self) -> SagaEventFilter { SagaEventFilter::terminal() }
+//!     fn on_saga_event(&mut self, event: &SagaChoreographyEvent) {
+//!         self.release_exposure(event.context().saga_id);
+//!     }
+//! }
+//! // In the actor's handler:
+//! SagaChoreographyMsg(event) => observe_saga_event(self, &event),
+//! ```
+
+use std::collections::HashSet;
+
+use crate::SagaChoreographyEvent;
+
+/// Event types of [`SagaEventFilter::terminal`].
+pub const TERMINAL_EVENT_TYPES: &[&str] = &["saga_completed", "saga_failed", "saga_quarantined"];
+
+/// Selects saga events by event type and saga type; an unset dimension
+/// matches everything.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SagaEventFilter {
+    event_types: Option<HashSet<Box<str>>>,
+    saga_types: Option<HashSet<Box<str>>>,
+}
+
+impl SagaEventFilter {
+    /// Matches every event.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Matches only terminal saga events.
+    pub fn terminal() -> Self {
+        Self::new().with_event_types(TERMINAL_EVENT_TYPES.iter().copied())
+    }
+
+    /// Restricts the filter to these `SagaChoreographyEvent::event_type`
+    /// names.
+    pub fn with_event_types<I, S>(mut self, event_types: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<Box<str>>,
+    {
+        self.event_types = Some(event_types.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn with_saga_types<I, S>(mut self, saga_types: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<Box<str>>,
+    {
+        self.saga_types = Some(saga_types.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn matches(&self, event: &SagaChoreographyEvent) -> bool {
+        let event_type_matches = self
+            .event_types
+            .as_ref()
+            .is_none_or(|types| types.contains(event.event_type()));
+        let saga_type_matches = self
+            .saga_types
+            .as_ref()
+            .is_none_or(|types| types.contains(event.context().saga_type.as_ref()));
+        event_type_matches && saga_type_matches
+    }
+}
+
+/// Listen-only role for actors that react to saga events without executing
+/// a step. Observed events are neither journaled nor deduped, so handlers
+/// must tolerate redelivery.
+pub trait SagaObserverParticipant {
+    /// Saga types this actor listens to; entries may be patterns such as
+    /// `"*"` (see [`crate::saga_type_matches`]).
+    fn saga_types(&self) -> &[&'static str];
+
+    /// Whether events of `saga_type` reach this observer.
+    /// Default: `saga_type` matches one of the [`SagaObserverParticipant::saga_types`]
+    fn matches_saga_type(&self, saga_type: &str) -> bool {
+        self.saga_types()
+            .iter()
+            .any(|pattern| crate::saga_type_matches(pattern, saga_type))
+    }
+
+    /// Events of the listened saga types that reach
+    /// [`SagaObserverParticipant::on_saga_event`]; everything by default.
+    fn event_filter(&self) -> SagaEventFilter {
+        SagaEventFilter::new()
+    }
+
+    fn on_saga_event(&mut self, event: &SagaChoreographyEvent);
+}
+
+/// Hands `event` to `observer` if it listens to the saga type and the event
+/// passes its filter. Returns whether it was delivered.
+pub fn observe_saga_event<O>(observer: &mut O, event: &SagaChoreographyEvent) -> bool
+where
+    O: SagaObserverParticipant + ?Sized,
+{
+    let saga_type = event.context().saga_type.as_ref();
+    if !observer.matches_saga_type(saga_type) || !observer.event_filter().matches(event) {
+        return false;
+    }
+    observer.on_saga_event(event);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{saga_started, DeterministicContextBuilder};
+
+    struct RiskManager {
+        released: Vec<&'static str>,
+    }
+
+    impl SagaObserverParticipant for RiskManager {
+        fn saga_types(&self) -> &[&'static str] {
+            &["order_lifecycle"]
+        }
+
+        fn event_filter(&self) -> SagaEventFilter {
+            SagaEventFilter::terminal()
+        }
+
+        fn on_saga_event(&mut self, event: &SagaChoreographyEvent) {
+            self.released.push(event.event_type());
+        }
+    }
+
+    #[test]
+    fn observer_participant_sees_only_filtered_events() {
+        let context = DeterministicContextBuilder::default().build();
+        let mut risk = RiskManager {
+            released: Vec::new(),
+        };
+
+        assert!(!observe_saga_event(
+            &mut risk,
+            &saga_started(context.clone(), Vec::new())
+        ));
+        assert!(observe_saga_event(
+            &mut risk,
+            &SagaChoreographyEvent::SagaCompleted {
+                context: context.clone(),
+            }
+        ));
+        assert_eq!(risk.released, vec!["saga_completed"]);
+
+        let other_saga = SagaEventFilter::terminal().with_saga_types(["hedge"]);
+        assert!(!other_saga.matches(&SagaChoreographyEvent::SagaCompleted { context }));
+    }
+
+    struct AuditLog {
+        seen: Vec<String>,
+    }
+
+    impl SagaObserverParticipant for AuditLog {
+        fn saga_types(&self) -> &[&'static str] {
+            &["deribit_*"]
+        }
+
+        fn on_saga_event(&mut self, event: &SagaChoreographyEvent) {
+            self.seen.push(event.context().saga_type.to_string());
+        }
+    }
+
+    #[test]
+    fn observer_participant_joins_saga_types_by_pattern() {
+        let mut audit = AuditLog { seen: Vec::new() };
+        for saga_type in ["deribit_order", "binance_order", "deribit_hedge"] {
+            let context = DeterministicContextBuilder::default()
+                .with_saga_type(saga_type)
+                .build();
+            observe_saga_event(&mut audit, &saga_started(context, Vec::new()));
+        }
+        assert_eq!(audit.seen, vec!["deribit_order", "deribit_hedge"]);
+    }
+}
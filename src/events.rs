@@ -1,19 +1,22 @@
 //! Saga events
 
-use super::SagaContext;
+use super::{SagaContext, SagaType, StepName};
+use crate::sensitive::Redacted;
 use icanact_core::ActorId;
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub struct SagaFailureDetails {
-    pub step_name: Box<str>,
+    pub step_name: StepName,
     pub participant_id: Box<str>,
     pub error_code: Option<Box<str>>,
     pub error_message: Box<str>,
+    /// `error_details` of the `StepFailed` event that failed the saga.
+    pub error_details: Vec<u8>,
     pub at_millis: u64,
 }
 
 /// Events published via the local saga event bus.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub enum SagaChoreographyEvent {
     /// Emitted when a new SAGA orchestration begins.
     SagaStarted {
@@ -52,6 +55,9 @@ pub enum SagaChoreographyEvent {
         saga_input: Vec<u8>,
         /// Whether compensation logic is available for this step if rollback is needed.
         compensation_available: bool,
+        /// Share of its work the step did when it completed only partially;
+        /// `None` when it completed in full.
+        completion_ratio: Option<f64>,
     },
     /// Emitted when a step fails during execution.
     StepFailed {
@@ -65,6 +71,8 @@ pub enum SagaChoreographyEvent {
         error: Box<str>,
         /// Whether compensation is required due to this failure.
         requires_compensation: bool,
+        /// Serialized `StepError` details; empty when the step attached none.
+        error_details: Vec<u8>,
     },
 
     /// Emitted when compensation is requested for one or more steps.
@@ -72,11 +80,11 @@ pub enum SagaChoreographyEvent {
         /// The saga context containing identifiers and metadata.
         context: SagaContext,
         /// The name of the step that triggered the compensation request.
-        failed_step: Box<str>,
+        failed_step: StepName,
         /// The reason compensation was requested.
         reason: Box<str>,
         /// The list of step names that need to be compensated, in reverse execution order.
-        steps_to_compensate: Vec<Box<str>>,
+        steps_to_compensate: Vec<StepName>,
     },
     /// Emitted when compensation begins execution.
     CompensationStarted {
@@ -98,6 +106,8 @@ pub enum SagaChoreographyEvent {
         error: Box<str>,
         /// Whether the system state is ambiguous (partial compensation may have occurred).
         is_ambiguous: bool,
+        /// Serialized `CompensationError` details; empty when none were attached.
+        error_details: Vec<u8>,
     },
     /// Emitted when a saga is quarantined due to unrecoverable errors.
     SagaQuarantined {
@@ -106,7 +116,7 @@ pub enum SagaChoreographyEvent {
         /// The reason the saga was quarantined.
         reason: Box<str>,
         /// The step during which the quarantine occurred.
-        step: Box<str>,
+        step: StepName,
         /// The participant that caused quarantine.
         participant_id: Box<str>,
     },
@@ -120,6 +130,43 @@ pub enum SagaChoreographyEvent {
         /// The status of the acknowledgment.
         status: AckStatus,
     },
+
+    /// Emitted when a restarted participant resumes a saga it had not
+    /// finished, so peers can re-send events it may have missed.
+    ParticipantRecovered {
+        /// The saga context containing identifiers and metadata.
+        context: SagaContext,
+        /// The participant that recovered.
+        participant_id: Box<str>,
+    },
+
+    /// Emitted by a [`crate::StepAckWatchdog`] when `step` was due to start
+    /// but no participant acknowledged it within the ack window.
+    SagaStalled {
+        /// The saga context, with `step_name` set to the stalled step.
+        context: SagaContext,
+        /// The step nobody acknowledged.
+        step: StepName,
+        /// How long the step had been waiting when the stall was reported.
+        waited_millis: u64,
+    },
+
+    /// Emitted periodically by the initiator of a saga still in flight, so a
+    /// [`crate::InitiatorTakeover`] backup knows it is alive. The initiator
+    /// is `context.initiator_peer_id`.
+    InitiatorHeartbeat {
+        /// The saga context containing identifiers and metadata.
+        context: SagaContext,
+    },
+
+    /// Emitted by the backup that took over tracking the saga's completion
+    /// after its initiator stopped sending heartbeats.
+    InitiatorTakenOver {
+        /// The saga context containing identifiers and metadata.
+        context: SagaContext,
+        /// The backup now resolving the saga.
+        successor_id: Box<str>,
+    },
 }
 
 #[derive(Clone, Debug)]
@@ -135,7 +182,7 @@ pub enum SagaTerminalOutcome {
     Quarantined {
         context: SagaContext,
         reason: Box<str>,
-        step: Box<str>,
+        step: StepName,
         participant_id: Box<str>,
     },
 }
@@ -160,7 +207,7 @@ impl SagaChoreographyEvent {
         error: Box<str>,
         requires_compensation: bool,
     ) -> Self {
-        let participant_id = context.step_name.clone();
+        let participant_id = context.step_name.clone().into();
         Self::step_failed_for_participant(
             context,
             participant_id,
@@ -183,6 +230,7 @@ impl SagaChoreographyEvent {
             error_code,
             error,
             requires_compensation,
+            error_details: Vec::new(),
         }
     }
 
@@ -219,9 +267,77 @@ impl SagaChoreographyEvent {
             Self::CompensationFailed { context, .. } => context,
             Self::SagaQuarantined { context, .. } => context,
             Self::StepAck { context, .. } => context,
+            Self::ParticipantRecovered { context, .. } => context,
+            Self::SagaStalled { context, .. } => context,
+            Self::InitiatorHeartbeat { context } => context,
+            Self::InitiatorTakenOver { context, .. } => context,
+        }
+    }
+
+    /// Returns a mutable reference to the event's saga context.
+    pub fn context_mut(&mut self) -> &mut SagaContext {
+        match self {
+            Self::SagaStarted { context, .. } => context,
+            Self::SagaCompleted { context } => context,
+            Self::SagaFailed { context, .. } => context,
+            Self::StepStarted { context } => context,
+            Self::StepCompleted { context, .. } => context,
+            Self::StepFailed { context, .. } => context,
+            Self::CompensationRequested { context, .. } => context,
+            Self::CompensationStarted { context } => context,
+            Self::CompensationCompleted { context } => context,
+            Self::CompensationFailed { context, .. } => context,
+            Self::SagaQuarantined { context, .. } => context,
+            Self::StepAck { context, .. } => context,
+            Self::ParticipantRecovered { context, .. } => context,
+            Self::SagaStalled { context, .. } => context,
+            Self::InitiatorHeartbeat { context } => context,
+            Self::InitiatorTakenOver { context, .. } => context,
+        }
+    }
+
+    /// Consumes the event and returns its saga context without cloning it.
+    pub fn into_context(self) -> SagaContext {
+        match self {
+            Self::SagaStarted { context, .. } => context,
+            Self::SagaCompleted { context } => context,
+            Self::SagaFailed { context, .. } => context,
+            Self::StepStarted { context } => context,
+            Self::StepCompleted { context, .. } => context,
+            Self::StepFailed { context, .. } => context,
+            Self::CompensationRequested { context, .. } => context,
+            Self::CompensationStarted { context } => context,
+            Self::CompensationCompleted { context } => context,
+            Self::CompensationFailed { context, .. } => context,
+            Self::SagaQuarantined { context, .. } => context,
+            Self::StepAck { context, .. } => context,
+            Self::ParticipantRecovered { context, .. } => context,
+            Self::SagaStalled { context, .. } => context,
+            Self::InitiatorHeartbeat { context } => context,
+            Self::InitiatorTakenOver { context, .. } => context,
         }
     }
 
+    /// Every value [`event_type`](Self::event_type) returns.
+    pub const EVENT_TYPES: &'static [&'static str] = &[
+        "saga_started",
+        "saga_completed",
+        "saga_failed",
+        "step_started",
+        "step_completed",
+        "step_failed",
+        "compensation_requested",
+        "compensation_started",
+        "compensation_completed",
+        "compensation_failed",
+        "saga_quarantined",
+        "step_ack",
+        "participant_recovered",
+        "saga_stalled",
+        "initiator_heartbeat",
+        "initiator_taken_over",
+    ];
+
     /// Returns a static string identifier for this event type.
     ///
     /// @return A `&'static str` representing the event type name (e.g., "saga_started", "step_completed").
@@ -239,6 +355,10 @@ impl SagaChoreographyEvent {
             Self::CompensationFailed { .. } => "compensation_failed",
             Self::SagaQuarantined { .. } => "saga_quarantined",
             Self::StepAck { .. } => "step_ack",
+            Self::ParticipantRecovered { .. } => "participant_recovered",
+            Self::SagaStalled { .. } => "saga_stalled",
+            Self::InitiatorHeartbeat { .. } => "initiator_heartbeat",
+            Self::InitiatorTakenOver { .. } => "initiator_taken_over",
         }
     }
 
@@ -270,6 +390,34 @@ impl SagaChoreographyEvent {
             _ => None,
         }
     }
+
+    /// Class of the failure this event reports: `error_code` when set,
+    /// otherwise the `[code]` tag of the reason (see
+    /// [`crate::ReasonCode::split`]). `None` for events that report no
+    /// failure.
+    pub fn reason_code(&self) -> Option<crate::ReasonCode> {
+        let (error_code, reason) = match self {
+            Self::StepFailed {
+                error_code, error, ..
+            } => (error_code.as_deref(), error),
+            Self::SagaFailed {
+                reason, failure, ..
+            } => (
+                failure
+                    .as_ref()
+                    .and_then(|failure| failure.error_code.as_deref()),
+                reason,
+            ),
+            Self::CompensationRequested { reason, .. }
+            | Self::SagaQuarantined { reason, .. }
+            | Self::CompensationFailed { error: reason, .. } => (None, reason),
+            _ => return None,
+        };
+        Some(error_code.map_or_else(
+            || crate::ReasonCode::split(reason).0,
+            crate::ReasonCode::from_code,
+        ))
+    }
 }
 
 impl icanact_core::local::EventTopic for SagaChoreographyEvent {
@@ -279,7 +427,7 @@ impl icanact_core::local::EventTopic for SagaChoreographyEvent {
 }
 
 /// Acknowledgment status for step processing responses.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub enum AckStatus {
     /// The step has been accepted and queued for processing.
     Accepted,
@@ -294,14 +442,14 @@ pub enum AckStatus {
 }
 
 /// Events stored in participant's local journal for durability and recovery.
-#[derive(Clone, Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[derive(Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub enum ParticipantEvent {
     /// Emitted when a participant registers to handle a step in a saga type.
     SagaRegistered {
         /// The type of saga this participant is registered for.
-        saga_type: Box<str>,
+        saga_type: SagaType,
         /// The name of the step this participant will handle.
-        step_name: Box<str>,
+        step_name: StepName,
         /// The timestamp (in milliseconds since epoch) when registration occurred.
         registered_at_millis: u64,
     },
@@ -336,6 +484,8 @@ pub enum ParticipantEvent {
         requires_compensation: bool,
         /// The timestamp (in milliseconds since epoch) when execution failed.
         failed_at_millis: u64,
+        /// Serialized `StepError` details.
+        details: Vec<u8>,
     },
     /// Emitted when compensation execution begins.
     CompensationStarted {
@@ -357,6 +507,8 @@ pub enum ParticipantEvent {
         is_ambiguous: bool,
         /// The timestamp (in milliseconds since epoch) when compensation failed.
         failed_at_millis: u64,
+        /// Serialized `CompensationError` details.
+        details: Vec<u8>,
     },
     /// Emitted when a participant is quarantined due to unrecoverable errors.
     Quarantined {
@@ -365,4 +517,271 @@ pub enum ParticipantEvent {
         /// The timestamp (in milliseconds since epoch) when quarantine occurred.
         quarantined_at_millis: u64,
     },
+    /// Emitted when `authorize_event` rejects an incoming choreography event.
+    /// Audit record only; it does not change the participant's progress.
+    EventRejected {
+        /// The rejected event's type (e.g., "compensation_requested").
+        event_type: Box<str>,
+        /// Why the event was rejected.
+        reason: Box<str>,
+        /// The timestamp (in milliseconds since epoch) when the event was rejected.
+        rejected_at_millis: u64,
+        /// The rejected event's dedupe key; `None` when it was rejected before
+        /// reaching the journal inbox, or by a release that did not record it.
+        dedupe_key: Option<Box<str>>,
+    },
+    /// Emitted by the effect ledger before a step dispatches an external side effect.
+    EffectBegun {
+        /// The effect's idempotency key.
+        key: Box<str>,
+        /// The timestamp (in milliseconds since epoch) when dispatch began.
+        begun_at_millis: u64,
+    },
+    /// Emitted by the effect ledger once an external side effect is known to have happened.
+    EffectConfirmed {
+        /// The effect's idempotency key.
+        key: Box<str>,
+        /// What the external system returned (e.g., an exchange order id).
+        result: Vec<u8>,
+        /// The timestamp (in milliseconds since epoch) when the effect was confirmed.
+        confirmed_at_millis: u64,
+    },
+    /// Emitted when a draining participant shuts down with the saga still
+    /// executing or compensating. Marker only; startup recovery resumes the
+    /// saga from the entry before it.
+    Parked {
+        /// Why the saga was parked (e.g., "participant draining").
+        reason: Box<str>,
+        /// The timestamp (in milliseconds since epoch) when the saga was parked.
+        parked_at_millis: u64,
+    },
+    /// Emitted by [`crate::checkpoint`] when a multi-phase step finishes a
+    /// phase. Not progress; a re-run of the step resumes after it.
+    StepCheckpointed {
+        /// The phase the step finished (e.g., "order_submitted").
+        phase: Box<str>,
+        /// What the step needs to carry on after the phase.
+        data: Vec<u8>,
+        /// The timestamp (in milliseconds since epoch) when the phase finished.
+        checkpointed_at_millis: u64,
+    },
+    /// Emitted by [`crate::JournalSagaIdAllocator`] when it reserves saga ids.
+    /// Journaled under [`crate::SAGA_ID_ALLOCATOR_SAGA`] only.
+    SagaIdsReserved {
+        /// The highest saga id reserved so far.
+        through: u64,
+        /// The timestamp (in milliseconds since epoch) when the ids were reserved.
+        reserved_at_millis: u64,
+    },
+}
+
+impl std::fmt::Debug for ParticipantEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SagaRegistered {
+                saga_type,
+                step_name,
+                registered_at_millis,
+            } => f
+                .debug_struct("SagaRegistered")
+                .field("saga_type", saga_type)
+                .field("step_name", step_name)
+                .field("registered_at_millis", registered_at_millis)
+                .finish(),
+            Self::StepTriggered {
+                triggering_event,
+                triggered_at_millis,
+            } => f
+                .debug_struct("StepTriggered")
+                .field("triggering_event", triggering_event)
+                .field("triggered_at_millis", triggered_at_millis)
+                .finish(),
+            Self::StepExecutionStarted {
+                attempt,
+                started_at_millis,
+            } => f
+                .debug_struct("StepExecutionStarted")
+                .field("attempt", attempt)
+                .field("started_at_millis", started_at_millis)
+                .finish(),
+            Self::StepExecutionCompleted {
+                output,
+                compensation_data,
+                completed_at_millis,
+            } => f
+                .debug_struct("StepExecutionCompleted")
+                .field("output", output)
+                .field("compensation_data", &Redacted(compensation_data))
+                .field("completed_at_millis", completed_at_millis)
+                .finish(),
+            Self::StepExecutionFailed {
+                error,
+                requires_compensation,
+                failed_at_millis,
+                details,
+            } => f
+                .debug_struct("StepExecutionFailed")
+                .field("error", error)
+                .field("requires_compensation", requires_compensation)
+                .field("failed_at_millis", failed_at_millis)
+                .field("details", details)
+                .finish(),
+            Self::CompensationStarted {
+                attempt,
+                started_at_millis,
+            } => f
+                .debug_struct("CompensationStarted")
+                .field("attempt", attempt)
+                .field("started_at_millis", started_at_millis)
+                .finish(),
+            Self::CompensationCompleted {
+                completed_at_millis,
+            } => f
+                .debug_struct("CompensationCompleted")
+                .field("completed_at_millis", completed_at_millis)
+                .finish(),
+            Self::CompensationFailed {
+                error,
+                is_ambiguous,
+                failed_at_millis,
+                details,
+            } => f
+                .debug_struct("CompensationFailed")
+                .field("error", error)
+                .field("is_ambiguous", is_ambiguous)
+                .field("failed_at_millis", failed_at_millis)
+                .field("details", details)
+                .finish(),
+            Self::Quarantined {
+                reason,
+                quarantined_at_millis,
+            } => f
+                .debug_struct("Quarantined")
+                .field("reason", reason)
+                .field("quarantined_at_millis", quarantined_at_millis)
+                .finish(),
+            Self::EventRejected {
+                event_type,
+                reason,
+                rejected_at_millis,
+                dedupe_key,
+            } => f
+                .debug_struct("EventRejected")
+                .field("event_type", event_type)
+                .field("reason", reason)
+                .field("rejected_at_millis", rejected_at_millis)
+                .field("dedupe_key", dedupe_key)
+                .finish(),
+            Self::EffectBegun {
+                key,
+                begun_at_millis,
+            } => f
+                .debug_struct("EffectBegun")
+                .field("key", key)
+                .field("begun_at_millis", begun_at_millis)
+                .finish(),
+            Self::EffectConfirmed {
+                key,
+                result,
+                confirmed_at_millis,
+            } => f
+                .debug_struct("EffectConfirmed")
+                .field("key", key)
+                .field("result", result)
+                .field("confirmed_at_millis", confirmed_at_millis)
+                .finish(),
+            Self::Parked {
+                reason,
+                parked_at_millis,
+            } => f
+                .debug_struct("Parked")
+                .field("reason", reason)
+                .field("parked_at_millis", parked_at_millis)
+                .finish(),
+            Self::StepCheckpointed {
+                phase,
+                data,
+                checkpointed_at_millis,
+            } => f
+                .debug_struct("StepCheckpointed")
+                .field("phase", phase)
+                .field("data", data)
+                .field("checkpointed_at_millis", checkpointed_at_millis)
+                .finish(),
+            Self::SagaIdsReserved {
+                through,
+                reserved_at_millis,
+            } => f
+                .debug_struct("SagaIdsReserved")
+                .field("through", through)
+                .field("reserved_at_millis", reserved_at_millis)
+                .finish(),
+        }
+    }
+}
+
+impl ParticipantEvent {
+    /// Returns a static string identifier for this journal event
+    /// (e.g., "step_execution_completed", "quarantined").
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            Self::SagaRegistered { .. } => "saga_registered",
+            Self::StepTriggered { .. } => "step_triggered",
+            Self::StepExecutionStarted { .. } => "step_execution_started",
+            Self::StepExecutionCompleted { .. } => "step_execution_completed",
+            Self::StepExecutionFailed { .. } => "step_execution_failed",
+            Self::CompensationStarted { .. } => "compensation_started",
+            Self::CompensationCompleted { .. } => "compensation_completed",
+            Self::CompensationFailed { .. } => "compensation_failed",
+            Self::Quarantined { .. } => "quarantined",
+            Self::EventRejected { .. } => "event_rejected",
+            Self::EffectBegun { .. } => "effect_begun",
+            Self::EffectConfirmed { .. } => "effect_confirmed",
+            Self::Parked { .. } => "parked",
+            Self::StepCheckpointed { .. } => "step_checkpointed",
+            Self::SagaIdsReserved { .. } => "saga_ids_reserved",
+        }
+    }
+
+    /// Class of the failure or rejection this journal entry records, read
+    /// from the `[code]` tag of its reason. `None` for entries without one.
+    pub fn reason_code(&self) -> Option<crate::ReasonCode> {
+        match self {
+            Self::StepExecutionFailed { error: reason, .. }
+            | Self::CompensationFailed { error: reason, .. }
+            | Self::Quarantined { reason, .. }
+            | Self::EventRejected { reason, .. }
+            | Self::Parked { reason, .. } => Some(crate::ReasonCode::split(reason).0),
+            _ => None,
+        }
+    }
+
+    /// Whether this event moves the participant's step or compensation
+    /// forward. Rejections, effect ledger records, shutdown markers, step
+    /// checkpoints and saga id reservations do not.
+    pub(crate) fn records_progress(&self) -> bool {
+        !matches!(
+            self,
+            Self::EventRejected { .. }
+                | Self::EffectBegun { .. }
+                | Self::EffectConfirmed { .. }
+                | Self::Parked { .. }
+                | Self::StepCheckpointed { .. }
+                | Self::SagaIdsReserved { .. }
+        )
+    }
+
+    /// Whether the participant is done with the saga after this event and
+    /// only awaits terminal cleanup. Quarantine is not settled: it waits for
+    /// an operator.
+    pub(crate) fn is_settled(&self) -> bool {
+        matches!(
+            self,
+            Self::CompensationCompleted { .. }
+                | Self::StepExecutionFailed {
+                    requires_compensation: false,
+                    ..
+                }
+        )
+    }
 }
@@ -0,0 +1,462 @@
+//! Fault-injection decorators for journals, dedupe stores and the bus.
+//!
+//! Each decorator consults a [`FaultSchedule`] on every faultable call and
+//! either delegates or returns an injected storage error (or drops the event,
+//! for [`FaultyBus`]). The schedule can be swapped mid-test through the
+//! decorator's [`FaultController`], so a test can break a store, observe the
+//! quarantine or retry path, then heal it and exercise recovery.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use icanact_core::local::PublishStats;
+
+use crate::{
+    DedupeError, DedupeKey, InboxEntry, JournalEntry, JournalError, OutboxEntry,
+    ParticipantDedupeStore, ParticipantEvent, ParticipantJournal, SagaBusPublishError,
+    SagaChoreographyBus, SagaChoreographyEvent, SagaId,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FailurePattern {
+    Never,
+    Always,
+    EveryNth(u64),
+    FirstN(u64),
+}
+
+/// When a decorated call fails, and how long each call is delayed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FaultSchedule {
+    failure: FailurePattern,
+    delay: Option<Duration>,
+}
+
+impl Default for FaultSchedule {
+    fn default() -> Self {
+        Self::never()
+    }
+}
+
+impl FaultSchedule {
+    /// Every call succeeds.
+    pub fn never() -> Self {
+        Self {
+            failure: FailurePattern::Never,
+            delay: None,
+        }
+    }
+
+    /// Every call fails.
+    pub fn always() -> Self {
+        Self {
+            failure: FailurePattern::Always,
+            delay: None,
+        }
+    }
+
+    /// Calls `n`, `2n`, `3n`, ... fail. `n` of zero never fails.
+    pub fn every_nth(n: u64) -> Self {
+        Self {
+            failure: if n == 0 {
+                FailurePattern::Never
+            } else {
+                FailurePattern::EveryNth(n)
+            },
+            delay: None,
+        }
+    }
+
+    /// The first `n` calls fail, later calls succeed.
+    pub fn fail_first(n: u64) -> Self {
+        Self {
+            failure: FailurePattern::FirstN(n),
+            delay: None,
+        }
+    }
+
+    /// The first call fails, later calls succeed.
+    pub fn fail_once() -> Self {
+        Self::fail_first(1)
+    }
+
+    /// Sleeps for `delay` before every call, failing or not.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    fn fails(&self, call: u64) -> bool {
+        match self.failure {
+            FailurePattern::Never => false,
+            FailurePattern::Always => true,
+            FailurePattern::EveryNth(n) => call.is_multiple_of(n),
+            FailurePattern::FirstN(n) => call <= n,
+        }
+    }
+}
+
+/// Error returned by a call the schedule chose to fail.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("injected fault on call {call}")]
+pub struct InjectedFault {
+    /// One-based number of the faultable call since the schedule was set.
+    pub call: u64,
+}
+
+#[derive(Debug, Default)]
+struct FaultState {
+    schedule: Mutex<FaultSchedule>,
+    calls: AtomicU64,
+    injected: AtomicU64,
+}
+
+/// Shared handle that reprograms and inspects one decorator's faults.
+#[derive(Clone, Debug, Default)]
+pub struct FaultController {
+    state: Arc<FaultState>,
+}
+
+impl FaultController {
+    pub fn new(schedule: FaultSchedule) -> Self {
+        let controller = Self::default();
+        controller.set_schedule(schedule);
+        controller
+    }
+
+    /// Replaces the schedule and restarts call numbering at one.
+    pub fn set_schedule(&self, schedule: FaultSchedule) {
+        *self
+            .state
+            .schedule
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = schedule;
+        self.state.calls.store(0, Ordering::SeqCst);
+    }
+
+    /// Stops injecting faults and delays.
+    pub fn heal(&self) {
+        self.set_schedule(FaultSchedule::never());
+    }
+
+    /// Faultable calls seen since the schedule was last set.
+    pub fn calls(&self) -> u64 {
+        self.state.calls.load(Ordering::SeqCst)
+    }
+
+    /// Faults injected over the decorator's lifetime.
+    pub fn injected(&self) -> u64 {
+        self.state.injected.load(Ordering::SeqCst)
+    }
+
+    fn check(&self) -> Result<(), InjectedFault> {
+        let schedule = *self
+            .state
+            .schedule
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(delay) = schedule.delay {
+            std::thread::sleep(delay);
+        }
+        let call = self.state.calls.fetch_add(1, Ordering::SeqCst) + 1;
+        if schedule.fails(call) {
+            self.state.injected.fetch_add(1, Ordering::SeqCst);
+            return Err(InjectedFault { call });
+        }
+        Ok(())
+    }
+}
+
+/// [`ParticipantJournal`] decorator. Writes (`append`, `prune` and the
+/// outbox/inbox writes) follow the schedule; reads always pass through.
+pub struct FaultyJournal<J> {
+    inner: J,
+    faults: FaultController,
+}
+
+impl<J: ParticipantJournal> FaultyJournal<J> {
+    pub fn new(inner: J, schedule: FaultSchedule) -> Self {
+        Self {
+            inner,
+            faults: FaultController::new(schedule),
+        }
+    }
+
+    pub fn faults(&self) -> FaultController {
+        self.faults.clone()
+    }
+
+    pub fn inner(&self) -> &J {
+        &self.inner
+    }
+
+    fn check(&self) -> Result<(), JournalError> {
+        self.faults
+            .check()
+            .map_err(|fault| JournalError::Storage(fault.to_string().into()))
+    }
+}
+
+impl<J: ParticipantJournal> ParticipantJournal for FaultyJournal<J> {
+    fn append(&self, saga_id: SagaId, event: ParticipantEvent) -> Result<u64, JournalError> {
+        self.check()?;
+        self.inner.append(saga_id, event)
+    }
+
+    fn read(&self, saga_id: SagaId) -> Result<Vec<JournalEntry>, JournalError> {
+        self.inner.read(saga_id)
+    }
+
+    fn list_sagas(&self) -> Result<Vec<SagaId>, JournalError> {
+        self.inner.list_sagas()
+    }
+
+    fn prune(&self, saga_id: SagaId) -> Result<(), JournalError> {
+        self.check()?;
+        self.inner.prune(saga_id)
+    }
+
+    fn record_outgoing(
+        &self,
+        saga_id: SagaId,
+        event: &SagaChoreographyEvent,
+    ) -> Result<Option<u64>, JournalError> {
+        self.check()?;
+        self.inner.record_outgoing(saga_id, event)
+    }
+
+    fn pending_outgoing(&self) -> Result<Vec<OutboxEntry>, JournalError> {
+        self.inner.pending_outgoing()
+    }
+
+    fn mark_outgoing_sent(&self, outbox_id: u64) -> Result<(), JournalError> {
+        self.check()?;
+        self.inner.mark_outgoing_sent(outbox_id)
+    }
+
+    fn record_incoming(
+        &self,
+        saga_id: SagaId,
+        dedupe_key: DedupeKey,
+        event: &SagaChoreographyEvent,
+    ) -> Result<Option<u64>, JournalError> {
+        self.check()?;
+        self.inner.record_incoming(saga_id, dedupe_key, event)
+    }
+
+    fn pending_incoming(&self) -> Result<Vec<InboxEntry>, JournalError> {
+        self.inner.pending_incoming()
+    }
+
+    fn mark_incoming_processed(&self, inbox_id: u64) -> Result<(), JournalError> {
+        self.check()?;
+        self.inner.mark_incoming_processed(inbox_id)
+    }
+
+    fn incoming_history(&self, saga_id: SagaId) -> Result<Vec<InboxEntry>, JournalError> {
+        self.inner.incoming_history(saga_id)
+    }
+}
+
+/// [`ParticipantDedupeStore`] decorator. `check_and_mark`, `mark_processed`
+/// and `prune` follow the schedule; `contains` always passes through.
+pub struct FaultyDedupe<D> {
+    inner: D,
+    faults: FaultController,
+}
+
+impl<D: ParticipantDedupeStore> FaultyDedupe<D> {
+    pub fn new(inner: D, schedule: FaultSchedule) -> Self {
+        Self {
+            inner,
+            faults: FaultController::new(schedule),
+        }
+    }
+
+    pub fn faults(&self) -> FaultController {
+        self.faults.clone()
+    }
+
+    pub fn inner(&self) -> &D {
+        &self.inner
+    }
+
+    fn check(&self) -> Result<(), DedupeError> {
+        self.faults
+            .check()
+            .map_err(|fault| DedupeError::Storage(fault.to_string().into()))
+    }
+}
+
+impl<D: ParticipantDedupeStore> ParticipantDedupeStore for FaultyDedupe<D> {
+    fn check_and_mark(&self, saga_id: SagaId, key: DedupeKey) -> Result<bool, DedupeError> {
+        self.check()?;
+        self.inner.check_and_mark(saga_id, key)
+    }
+
+    fn contains(&self, saga_id: SagaId, key: DedupeKey) -> bool {
+        self.inner.contains(saga_id, key)
+    }
+
+    fn mark_processed(&self, saga_id: SagaId, key: DedupeKey) -> Result<(), DedupeError> {
+        self.check()?;
+        self.inner.mark_processed(saga_id, key)
+    }
+
+    fn prune(&self, saga_id: SagaId) -> Result<(), DedupeError> {
+        self.check()?;
+        self.inner.prune(saga_id)
+    }
+
+    fn list_sagas(&self) -> Result<Option<Vec<SagaId>>, DedupeError> {
+        self.check()?;
+        self.inner.list_sagas()
+    }
+}
+
+/// [`SagaChoreographyBus`] decorator whose publishes follow the schedule.
+/// A failed publish drops the event without delivering it to anyone.
+#[derive(Clone)]
+pub struct FaultyBus {
+    inner: SagaChoreographyBus,
+    faults: FaultController,
+}
+
+impl FaultyBus {
+    pub fn new(inner: SagaChoreographyBus, schedule: FaultSchedule) -> Self {
+        Self {
+            inner,
+            faults: FaultController::new(schedule),
+        }
+    }
+
+    pub fn faults(&self) -> FaultController {
+        self.faults.clone()
+    }
+
+    /// The wrapped bus, for subscribing and for publishes that must not fault.
+    pub fn inner(&self) -> &SagaChoreographyBus {
+        &self.inner
+    }
+
+    pub fn publish(&self, event: SagaChoreographyEvent) -> Result<PublishStats, InjectedFault> {
+        self.faults
+            .check()
+            .inspect_err(|fault| drop_event(&event, fault))?;
+        Ok(self.inner.publish(event))
+    }
+
+    /// Like [`SagaChoreographyBus::publish_strict`]; a dropped event is
+    /// reported as a delivery to no one.
+    pub fn publish_strict(
+        &self,
+        event: SagaChoreographyEvent,
+    ) -> Result<PublishStats, SagaBusPublishError> {
+        if let Err(fault) = self.faults.check() {
+            drop_event(&event, &fault);
+            let context = event.context();
+            return Err(SagaBusPublishError::PartialDelivery {
+                saga_id: context.saga_id,
+                saga_type: context.saga_type.clone(),
+                step_name: context.step_name.clone(),
+                attempted: 1,
+                delivered: 0,
+            });
+        }
+        self.inner.publish_strict(event)
+    }
+
+    pub fn publish_to_saga_type(
+        &self,
+        saga_type: &str,
+        event: SagaChoreographyEvent,
+    ) -> Result<PublishStats, InjectedFault> {
+        self.faults
+            .check()
+            .inspect_err(|fault| drop_event(&event, fault))?;
+        Ok(self.inner.publish_to_saga_type(saga_type, event))
+    }
+}
+
+impl std::fmt::Debug for FaultyBus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FaultyBus")
+            .field("faults", &self.faults)
+            .finish()
+    }
+}
+
+fn drop_event(event: &SagaChoreographyEvent, fault: &InjectedFault) {
+    tracing::debug!(
+        target: "core::saga",
+        event = "saga_fault_injected_publish_dropped",
+        saga_id = event.context().saga_id.get(),
+        event_type = event.event_type(),
+        call = fault.call
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryDedupe, InMemoryJournal};
+
+    fn started() -> ParticipantEvent {
+        ParticipantEvent::StepExecutionStarted {
+            attempt: 0,
+            started_at_millis: 0,
+        }
+    }
+
+    #[test]
+    fn schedules_fail_the_expected_calls() {
+        let journal = FaultyJournal::new(InMemoryJournal::new(), FaultSchedule::every_nth(3));
+        let saga_id = SagaId::new(1);
+        let outcomes: Vec<bool> = (0..6)
+            .map(|_| journal.append(saga_id, started()).is_ok())
+            .collect();
+        assert_eq!(outcomes, vec![true, true, false, true, true, false]);
+        assert_eq!(journal.read(saga_id).unwrap().len(), 4);
+
+        let dedupe = FaultyDedupe::new(InMemoryDedupe::new(), FaultSchedule::fail_once());
+        assert!(matches!(
+            dedupe.check_and_mark(saga_id, "k".into()),
+            Err(DedupeError::Storage(_))
+        ));
+        assert!(dedupe.check_and_mark(saga_id, "k".into()).unwrap());
+        assert!(!dedupe.check_and_mark(saga_id, "k".into()).unwrap());
+
+        let faults = dedupe.faults();
+        faults.set_schedule(FaultSchedule::always().with_delay(Duration::from_millis(5)));
+        let begin = std::time::Instant::now();
+        assert!(dedupe.mark_processed(saga_id, "j".into()).is_err());
+        assert!(begin.elapsed() >= Duration::from_millis(5));
+        faults.heal();
+        assert!(dedupe.mark_processed(saga_id, "j".into()).is_ok());
+        assert_eq!(faults.injected(), 2);
+    }
+
+    #[test]
+    fn faulty_bus_drops_failed_publishes() {
+        let bus = FaultyBus::new(SagaChoreographyBus::new(), FaultSchedule::fail_once());
+        let seen = Arc::new(AtomicU64::new(0));
+        let counter = Arc::clone(&seen);
+        let _sub = bus
+            .inner()
+            .subscribe_saga_type_fn("order_lifecycle", move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                true
+            });
+        let event = || SagaChoreographyEvent::SagaCompleted {
+            context: crate::DeterministicContextBuilder::default().build(),
+        };
+
+        match bus.publish_to_saga_type("order_lifecycle", event()) {
+            Err(fault) => assert_eq!(fault, InjectedFault { call: 1 }),
+            Ok(_) => panic!("first publish should be dropped"),
+        }
+        assert!(bus.publish_to_saga_type("order_lifecycle", event()).is_ok());
+        assert_eq!(seen.load(Ordering::SeqCst), 1);
+    }
+}
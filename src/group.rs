@@ -0,0 +1,441 @@
+//! Both-or-neither execution of several sagas.
+//!
+//! A strategy placing legs on two venues runs one saga per leg, but wants
+//! either every leg to stick or none. A [`SagaGroup`] collects the start
+//! contexts of such sagas under a group id, which every member carries as
+//! its `correlation_id`. [`GroupCoordinator::start_group`] starts them and,
+//! subscribed to the members' saga types, requests compensation of every
+//! member still running as soon as one member fails:
+//!
+//! ```ignore
+//! let coordinator = GroupCoordinator::new(bus);
+//! let _sub = coordinator.subscribe("place_leg");
+//! let group = SagaGroup::new(group_id)
+//!     .with_member(leg_context(venue_a), leg_a)
+//!     .with_member(leg_context(venue_b), leg_b);
+//! coordinator.start_group(group)?;
+//! ```
+//!
+//! Participants prune a saga once it completes, so a member that already
+//! completed when another fails cannot be compensated through the bus. It is
+//! listed in [`SagaGroupStatus::Aborted`] for the caller to reverse, e.g. by
+//! starting a closing saga.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use icanact_core::local::EventSubscription;
+
+use crate::{
+    SagaAdmission, SagaBusPublishError, SagaChoreographyBus, SagaChoreographyEvent, SagaContext,
+    SagaId, StepName,
+};
+
+const GROUP_FINISHED_RETENTION: usize = 4096;
+
+/// Sagas that succeed or fail together.
+#[derive(Clone, Debug)]
+pub struct SagaGroup {
+    group_id: u64,
+    members: Vec<(SagaContext, Vec<u8>)>,
+}
+
+impl SagaGroup {
+    pub fn new(group_id: u64) -> Self {
+        Self {
+            group_id,
+            members: Vec::new(),
+        }
+    }
+
+    /// Adds a saga with its start context and payload. The context's
+    /// correlation id is replaced by the group id.
+    pub fn with_member(mut self, mut context: SagaContext, payload: Vec<u8>) -> Self {
+        context.correlation_id = self.group_id;
+        self.members.push((context, payload));
+        self
+    }
+
+    pub fn group_id(&self) -> u64 {
+        self.group_id
+    }
+
+    pub fn member_ids(&self) -> Vec<SagaId> {
+        self.members
+            .iter()
+            .map(|(context, _)| context.saga_id)
+            .collect()
+    }
+}
+
+/// Where a group stands, as seen by its [`GroupCoordinator`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SagaGroupStatus {
+    Running,
+    /// Every member completed.
+    Completed,
+    /// `failed_member` failed; compensation was requested for the members in
+    /// `compensating`, while those in `completed` had already completed.
+    Aborted {
+        failed_member: SagaId,
+        compensating: Vec<SagaId>,
+        completed: Vec<SagaId>,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum MemberOutcome {
+    Running,
+    Completed,
+    Failed,
+}
+
+struct GroupMember {
+    last_context: SagaContext,
+    compensable_steps: Vec<StepName>,
+    outcome: MemberOutcome,
+}
+
+struct GroupState {
+    members: BTreeMap<SagaId, GroupMember>,
+    status: SagaGroupStatus,
+}
+
+#[derive(Default)]
+struct CoordinatorState {
+    groups: HashMap<u64, GroupState>,
+    group_of: HashMap<SagaId, u64>,
+    finished: HashMap<u64, SagaGroupStatus>,
+    finished_order: VecDeque<u64>,
+}
+
+/// Starts [`SagaGroup`]s and aborts a group when one of its members fails.
+/// Cloning shares the same state.
+#[derive(Clone)]
+pub struct GroupCoordinator {
+    bus: SagaChoreographyBus,
+    state: Arc<Mutex<CoordinatorState>>,
+}
+
+impl GroupCoordinator {
+    pub fn new(bus: SagaChoreographyBus) -> Self {
+        Self {
+            bus,
+            state: Arc::new(Mutex::new(CoordinatorState::default())),
+        }
+    }
+
+    /// Starts every member of `group`. When a start is refused, the members
+    /// started before it are compensated and the error is returned.
+    pub fn start_group(&self, group: SagaGroup) -> Result<Vec<SagaAdmission>, SagaBusPublishError> {
+        let group_id = group.group_id;
+        self.track(&group);
+        let mut admissions = Vec::with_capacity(group.members.len());
+        for (context, payload) in group.members {
+            let saga_id = context.saga_id;
+            match self.bus.start_saga(context, payload) {
+                Ok(admission) => admissions.push(admission),
+                Err(err) => {
+                    tracing::warn!(
+                        target: "core::saga",
+                        event = "saga_group_member_start_failed",
+                        group_id,
+                        saga_id = saga_id.get(),
+                        error = ?err
+                    );
+                    for event in self.member_failed(group_id, saga_id) {
+                        self.bus.publish(event);
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        Ok(admissions)
+    }
+
+    /// Subscribes the coordinator to every event of `saga_type` and publishes
+    /// the compensation requests of aborted groups.
+    pub fn subscribe(&self, saga_type: &str) -> EventSubscription {
+        let coordinator = self.clone();
+        self.bus.subscribe_saga_type_fn(saga_type, move |event| {
+            for request in coordinator.ingest(event) {
+                coordinator.bus.publish(request);
+            }
+            true
+        })
+    }
+
+    /// Feeds one member event and returns the `CompensationRequested` events
+    /// to publish when it aborts the member's group.
+    pub fn ingest(&self, event: &SagaChoreographyEvent) -> Vec<SagaChoreographyEvent> {
+        let context = event.context();
+        let saga_id = context.saga_id;
+        let Some(group_id) = self.lock().group_of.get(&saga_id).copied() else {
+            return Vec::new();
+        };
+        match event {
+            SagaChoreographyEvent::SagaCompleted { .. } => {
+                self.member_completed(group_id, saga_id);
+                Vec::new()
+            }
+            SagaChoreographyEvent::SagaFailed { .. }
+            | SagaChoreographyEvent::SagaQuarantined { .. } => {
+                self.member_failed(group_id, saga_id)
+            }
+            _ => {
+                let mut state = self.lock();
+                if let Some(member) = state
+                    .groups
+                    .get_mut(&group_id)
+                    .and_then(|group| group.members.get_mut(&saga_id))
+                {
+                    if let SagaChoreographyEvent::StepCompleted {
+                        compensation_available: true,
+                        ..
+                    } = event
+                    {
+                        member.compensable_steps.push(context.step_name.clone());
+                    }
+                    member.last_context = context.clone();
+                }
+                Vec::new()
+            }
+        }
+    }
+
+    /// Status of a running group, or of one of the most recently finished.
+    pub fn group_status(&self, group_id: u64) -> Option<SagaGroupStatus> {
+        let state = self.lock();
+        state
+            .groups
+            .get(&group_id)
+            .map(|group| group.status.clone())
+            .or_else(|| state.finished.get(&group_id).cloned())
+    }
+
+    /// Number of groups with members still running.
+    pub fn running_len(&self) -> usize {
+        self.lock().groups.len()
+    }
+
+    pub(crate) fn track(&self, group: &SagaGroup) {
+        let mut state = self.lock();
+        let members = group
+            .members
+            .iter()
+            .map(|(context, _)| {
+                (
+                    context.saga_id,
+                    GroupMember {
+                        last_context: context.clone(),
+                        compensable_steps: Vec::new(),
+                        outcome: MemberOutcome::Running,
+                    },
+                )
+            })
+            .collect();
+        for (context, _) in &group.members {
+            state.group_of.insert(context.saga_id, group.group_id);
+        }
+        state.groups.insert(
+            group.group_id,
+            GroupState {
+                members,
+                status: SagaGroupStatus::Running,
+            },
+        );
+    }
+
+    fn member_completed(&self, group_id: u64, saga_id: SagaId) {
+        let mut state = self.lock();
+        let Some(group) = state.groups.get_mut(&group_id) else {
+            return;
+        };
+        if let Some(member) = group.members.get_mut(&saga_id) {
+            member.outcome = MemberOutcome::Completed;
+        }
+        if let SagaGroupStatus::Aborted {
+            compensating,
+            completed,
+            ..
+        } = &mut group.status
+        {
+            // Compensation was requested too late to stop it.
+            if let Some(index) = compensating.iter().position(|id| *id == saga_id) {
+                compensating.remove(index);
+                completed.push(saga_id);
+                tracing::warn!(
+                    target: "core::saga",
+                    event = "saga_group_member_completed_after_abort",
+                    group_id,
+                    saga_id = saga_id.get()
+                );
+            }
+        }
+        state.finish_if_settled(group_id);
+    }
+
+    fn member_failed(&self, group_id: u64, saga_id: SagaId) -> Vec<SagaChoreographyEvent> {
+        let mut state = self.lock();
+        let Some(group) = state.groups.get_mut(&group_id) else {
+            return Vec::new();
+        };
+        let Some(failed) = group.members.get_mut(&saga_id) else {
+            return Vec::new();
+        };
+        failed.outcome = MemberOutcome::Failed;
+        let failed_step = failed.last_context.step_name.clone();
+        let mut requests = Vec::new();
+        if group.status == SagaGroupStatus::Running {
+            let reason: Box<str> =
+                format!("saga group {group_id} aborted: member saga {saga_id} failed").into();
+            let mut compensating = Vec::new();
+            let mut completed = Vec::new();
+            for (member_id, member) in &group.members {
+                match member.outcome {
+                    MemberOutcome::Running => {
+                        compensating.push(*member_id);
+                        requests.push(SagaChoreographyEvent::CompensationRequested {
+                            context: member.last_context.for_compensation(),
+                            failed_step: failed_step.clone(),
+                            reason: reason.clone(),
+                            steps_to_compensate: member
+                                .compensable_steps
+                                .iter()
+                                .rev()
+                                .cloned()
+                                .collect(),
+                        });
+                    }
+                    MemberOutcome::Completed => completed.push(*member_id),
+                    MemberOutcome::Failed => {}
+                }
+            }
+            tracing::warn!(
+                target: "core::saga",
+                event = "saga_group_aborted",
+                group_id,
+                failed_member = saga_id.get(),
+                compensating = compensating.len(),
+                completed = completed.len()
+            );
+            group.status = SagaGroupStatus::Aborted {
+                failed_member: saga_id,
+                compensating,
+                completed,
+            };
+        }
+        state.finish_if_settled(group_id);
+        requests
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CoordinatorState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl CoordinatorState {
+    fn finish_if_settled(&mut self, group_id: u64) {
+        let settled = self.groups.get(&group_id).is_some_and(|group| {
+            group
+                .members
+                .values()
+                .all(|member| member.outcome != MemberOutcome::Running)
+        });
+        if !settled {
+            return;
+        }
+        let Some(mut group) = self.groups.remove(&group_id) else {
+            return;
+        };
+        for saga_id in group.members.keys() {
+            self.group_of.remove(saga_id);
+        }
+        if group.status == SagaGroupStatus::Running {
+            group.status = SagaGroupStatus::Completed;
+        }
+        self.finished.insert(group_id, group.status);
+        self.finished_order.push_back(group_id);
+        while self.finished_order.len() > GROUP_FINISHED_RETENTION {
+            if let Some(evicted) = self.finished_order.pop_front() {
+                self.finished.remove(&evicted);
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for GroupCoordinator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.lock();
+        f.debug_struct("GroupCoordinator")
+            .field("groups_len", &state.groups.len())
+            .field("finished_len", &state.finished.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{step_completed, DeterministicContextBuilder};
+
+    fn leg(saga_id: u64) -> SagaContext {
+        DeterministicContextBuilder::default()
+            .with_saga_id(saga_id)
+            .with_step_name("place_leg")
+            .build()
+    }
+
+    fn saga_failed(context: SagaContext) -> SagaChoreographyEvent {
+        SagaChoreographyEvent::SagaFailed {
+            context,
+            reason: "venue rejected".into(),
+            failure: None,
+        }
+    }
+
+    #[test]
+    fn failing_member_aborts_the_rest_of_the_group() {
+        let coordinator = GroupCoordinator::new(SagaChoreographyBus::new());
+        let group = SagaGroup::new(77)
+            .with_member(leg(1), Vec::new())
+            .with_member(leg(2), Vec::new())
+            .with_member(leg(3), Vec::new());
+        coordinator.track(&group);
+
+        let placed = leg(2);
+        assert!(coordinator
+            .ingest(&step_completed(placed, Vec::new(), Vec::new(), true))
+            .is_empty());
+        coordinator.ingest(&SagaChoreographyEvent::SagaCompleted { context: leg(3) });
+
+        let requests = coordinator.ingest(&saga_failed(leg(1)));
+        let [SagaChoreographyEvent::CompensationRequested {
+            context,
+            steps_to_compensate,
+            ..
+        }] = requests.as_slice()
+        else {
+            panic!("expected one compensation request: {requests:?}");
+        };
+        assert_eq!(context.saga_id, SagaId::new(2));
+        assert_eq!(steps_to_compensate, &[StepName::from("place_leg")]);
+        assert_eq!(
+            coordinator.group_status(77),
+            Some(SagaGroupStatus::Aborted {
+                failed_member: SagaId::new(1),
+                compensating: vec![SagaId::new(2)],
+                completed: vec![SagaId::new(3)],
+            })
+        );
+
+        coordinator.ingest(&saga_failed(leg(2)));
+        assert_eq!(coordinator.running_len(), 0);
+        assert!(matches!(
+            coordinator.group_status(77),
+            Some(SagaGroupStatus::Aborted { .. })
+        ));
+    }
+}
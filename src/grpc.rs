@@ -0,0 +1,744 @@
+//! gRPC participant bridge.
+//!
+//! A service outside the actor system runs a saga step by implementing the
+//! `icanact.saga.v1.SagaParticipant` service of `proto/saga_participant.proto`.
+//! Inside the saga, [`GrpcParticipantClient`] stands in for it: it is an
+//! [`AsyncSagaParticipant`] with its own [`SagaParticipantSupport`], so
+//! journaling, dedupe and event emission stay local and only
+//! `execute_step`/`compensate_step` cross the wire. A Rust service can serve
+//! any [`AsyncSagaParticipant`] with [`GrpcParticipantServer`]:
+//!
+//! ```ignore
+//! tonic::transport::Server::builder()
+//!     .add_service(GrpcParticipantServer::new(RiskCheck::default()))
+//!     .serve(addr)
+//!     .await?;
+//! ```
+//!
+//! Requests carry the step's idempotency key (`IdempotencyKey::for_step`,
+//! attempt counted from 1, or `IdempotencyKey::for_compensation`). Outcomes
+//! map one to one onto [`StepOutput`], [`StepError`] and
+//! [`CompensationError`]. A call that fails with a gRPC status is classified
+//! by its code: codes that mean the request was refused before it ran
+//! (`INVALID_ARGUMENT`, `FAILED_PRECONDITION`, `NOT_FOUND`,
+//! `PERMISSION_DENIED`, `UNAUTHENTICATED`, `UNIMPLEMENTED`) fail the step
+//! without compensation and the compensation terminally; `UNAVAILABLE` lets
+//! a compensation be retried; anything else may have applied, so the step
+//! requires compensation and the compensation is ambiguous.
+
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::codegen::{http, Body, BoxFuture, Context, Poll, Service, StdError};
+use tonic::server::NamedService;
+use tonic::transport::Channel;
+use tonic::{Code, Status};
+use tonic_prost::ProstCodec;
+
+use crate::{
+    AsyncSagaParticipant, CompensationError, DependencySpec, HasSagaParticipantSupport,
+    IdempotencyKey, ParticipantDedupeStore, ParticipantJournal, SagaBoxFuture, SagaContext, SagaId,
+    SagaParticipantSupport, StepError, StepOutput,
+};
+
+const SERVICE_NAME: &str = "icanact.saga.v1.SagaParticipant";
+const EXECUTE_STEP_PATH: &str = "/icanact.saga.v1.SagaParticipant/ExecuteStep";
+const COMPENSATE_STEP_PATH: &str = "/icanact.saga.v1.SagaParticipant/CompensateStep";
+
+/// Messages of `proto/saga_participant.proto`.
+mod wire {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SagaContext {
+        #[prost(uint64, tag = "1")]
+        pub saga_id: u64,
+        #[prost(string, tag = "2")]
+        pub saga_type: String,
+        #[prost(string, tag = "3")]
+        pub step_name: String,
+        #[prost(uint64, tag = "4")]
+        pub correlation_id: u64,
+        #[prost(uint64, tag = "5")]
+        pub causation_id: u64,
+        #[prost(uint64, tag = "6")]
+        pub trace_id: u64,
+        #[prost(uint64, tag = "7")]
+        pub step_index: u64,
+        #[prost(uint32, tag = "8")]
+        pub attempt: u32,
+        #[prost(bytes = "vec", tag = "9")]
+        pub initiator_peer_id: Vec<u8>,
+        #[prost(uint64, tag = "10")]
+        pub saga_started_at_millis: u64,
+        #[prost(uint64, tag = "11")]
+        pub event_timestamp_millis: u64,
+        #[prost(uint64, optional, tag = "12")]
+        pub parent_saga_id: Option<u64>,
+        #[prost(uint32, tag = "13")]
+        pub workflow_version: u32,
+        #[prost(uint64, tag = "14")]
+        pub logical_clock: u64,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ExecuteStepRequest {
+        #[prost(message, optional, tag = "1")]
+        pub context: Option<SagaContext>,
+        #[prost(bytes = "vec", tag = "2")]
+        pub input: Vec<u8>,
+        #[prost(string, tag = "3")]
+        pub idempotency_key: String,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum ExecuteOutcome {
+        Completed = 0,
+        NoOp = 1,
+        Terminal = 2,
+        RequireCompensation = 3,
+        Partial = 4,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ExecuteStepResponse {
+        #[prost(enumeration = "ExecuteOutcome", tag = "1")]
+        pub outcome: i32,
+        #[prost(bytes = "vec", tag = "2")]
+        pub output: Vec<u8>,
+        #[prost(bytes = "vec", tag = "3")]
+        pub compensation_data: Vec<u8>,
+        #[prost(string, tag = "4")]
+        pub effect: String,
+        #[prost(string, tag = "5")]
+        pub reason: String,
+        #[prost(bytes = "vec", tag = "6")]
+        pub details: Vec<u8>,
+        #[prost(double, tag = "7")]
+        pub completion_ratio: f64,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct CompensateStepRequest {
+        #[prost(message, optional, tag = "1")]
+        pub context: Option<SagaContext>,
+        #[prost(bytes = "vec", tag = "2")]
+        pub compensation_data: Vec<u8>,
+        #[prost(string, tag = "3")]
+        pub idempotency_key: String,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum CompensateOutcome {
+        Compensated = 0,
+        SafeToRetry = 1,
+        Ambiguous = 2,
+        Terminal = 3,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct CompensateStepResponse {
+        #[prost(enumeration = "CompensateOutcome", tag = "1")]
+        pub outcome: i32,
+        #[prost(string, tag = "2")]
+        pub reason: String,
+        #[prost(bytes = "vec", tag = "3")]
+        pub details: Vec<u8>,
+    }
+}
+
+fn context_to_wire(context: &SagaContext) -> wire::SagaContext {
+    wire::SagaContext {
+        saga_id: context.saga_id.get(),
+        saga_type: context.saga_type.as_str().to_owned(),
+        step_name: context.step_name.as_str().to_owned(),
+        correlation_id: context.correlation_id,
+        causation_id: context.causation_id,
+        trace_id: context.trace_id,
+        step_index: context.step_index as u64,
+        attempt: context.attempt,
+        initiator_peer_id: context.initiator_peer_id.to_vec(),
+        saga_started_at_millis: context.saga_started_at_millis,
+        event_timestamp_millis: context.event_timestamp_millis,
+        parent_saga_id: context.parent_saga_id.map(|parent| parent.get()),
+        workflow_version: context.workflow_version,
+        logical_clock: context.logical_clock,
+    }
+}
+
+fn context_from_wire(context: Option<wire::SagaContext>) -> Result<SagaContext, Status> {
+    let context = context.ok_or_else(|| Status::invalid_argument("missing saga context"))?;
+    let initiator_peer_id = context
+        .initiator_peer_id
+        .as_slice()
+        .try_into()
+        .map_err(|_| Status::invalid_argument("initiator_peer_id must be 32 bytes"))?;
+    Ok(SagaContext {
+        saga_id: SagaId::new(context.saga_id),
+        saga_type: context.saga_type.into(),
+        step_name: context.step_name.into(),
+        correlation_id: context.correlation_id,
+        causation_id: context.causation_id,
+        trace_id: context.trace_id,
+        step_index: context.step_index as usize,
+        attempt: context.attempt,
+        initiator_peer_id,
+        saga_started_at_millis: context.saga_started_at_millis,
+        event_timestamp_millis: context.event_timestamp_millis,
+        parent_saga_id: context.parent_saga_id.map(SagaId::new),
+        workflow_version: context.workflow_version,
+        logical_clock: context.logical_clock,
+    })
+}
+
+/// Whether `code` says the call was refused before it ran.
+fn refused(code: Code) -> bool {
+    matches!(
+        code,
+        Code::InvalidArgument
+            | Code::FailedPrecondition
+            | Code::NotFound
+            | Code::PermissionDenied
+            | Code::Unauthenticated
+            | Code::Unimplemented
+    )
+}
+
+/// Serves an [`AsyncSagaParticipant`] as `icanact.saga.v1.SagaParticipant`.
+///
+/// Calls are handled one at a time, as the participant's actor would.
+/// Requests for another step or saga type are refused with
+/// `FAILED_PRECONDITION`.
+pub struct GrpcParticipantServer<P> {
+    participant: Arc<tokio::sync::Mutex<P>>,
+}
+
+impl<P> GrpcParticipantServer<P>
+where
+    P: AsyncSagaParticipant + Send + 'static,
+{
+    pub fn new(participant: P) -> Self {
+        Self {
+            participant: Arc::new(tokio::sync::Mutex::new(participant)),
+        }
+    }
+
+    async fn execute(
+        &self,
+        request: wire::ExecuteStepRequest,
+    ) -> Result<wire::ExecuteStepResponse, Status> {
+        let context = context_from_wire(request.context)?;
+        let mut participant = self.participant.lock().await;
+        accepts(&*participant, &context)?;
+        let mut response = wire::ExecuteStepResponse::default();
+        match participant.execute_step(&context, &request.input).await {
+            Ok(StepOutput::Completed {
+                output,
+                compensation_data,
+            }) => {
+                response.output = output;
+                response.compensation_data = compensation_data;
+            }
+            Ok(StepOutput::CompletedWithEffect {
+                output,
+                compensation_data,
+                effect,
+            }) => {
+                response.output = output;
+                response.compensation_data = compensation_data;
+                response.effect = effect.into();
+            }
+            Ok(StepOutput::Partial {
+                output,
+                compensation_data,
+                completion_ratio,
+            }) => {
+                response.outcome = wire::ExecuteOutcome::Partial as i32;
+                response.output = output;
+                response.compensation_data = compensation_data;
+                response.completion_ratio = completion_ratio;
+            }
+            Ok(StepOutput::NoOp) => {
+                response.outcome = wire::ExecuteOutcome::NoOp as i32;
+            }
+            Err(error) => {
+                let (reason, details, requires_compensation) = error.into_parts();
+                response.outcome = if requires_compensation {
+                    wire::ExecuteOutcome::RequireCompensation
+                } else {
+                    wire::ExecuteOutcome::Terminal
+                } as i32;
+                response.reason = reason.into();
+                response.details = details;
+            }
+        }
+        Ok(response)
+    }
+
+    async fn compensate(
+        &self,
+        request: wire::CompensateStepRequest,
+    ) -> Result<wire::CompensateStepResponse, Status> {
+        let context = context_from_wire(request.context)?;
+        let mut participant = self.participant.lock().await;
+        accepts(&*participant, &context)?;
+        let mut response = wire::CompensateStepResponse::default();
+        if let Err(error) = participant
+            .compensate_step(&context, &request.compensation_data)
+            .await
+        {
+            response.outcome = match &error {
+                CompensationError::SafeToRetry { .. } => wire::CompensateOutcome::SafeToRetry,
+                CompensationError::Ambiguous { .. } => wire::CompensateOutcome::Ambiguous,
+                CompensationError::Terminal { .. } => wire::CompensateOutcome::Terminal,
+            } as i32;
+            let (reason, details, _) = error.into_parts();
+            response.reason = reason.into();
+            response.details = details;
+        }
+        Ok(response)
+    }
+}
+
+fn accepts<P>(participant: &P, context: &SagaContext) -> Result<(), Status>
+where
+    P: AsyncSagaParticipant,
+{
+    if context.step_name.as_str() != participant.step_name() {
+        return Err(Status::failed_precondition(format!(
+            "this participant runs {}, not {}",
+            participant.step_name(),
+            context.step_name
+        )));
+    }
+    if !participant.matches_saga_type(context.saga_type.as_str()) {
+        return Err(Status::failed_precondition(format!(
+            "saga type {} is not handled here",
+            context.saga_type
+        )));
+    }
+    Ok(())
+}
+
+impl<P> Clone for GrpcParticipantServer<P> {
+    fn clone(&self) -> Self {
+        Self {
+            participant: Arc::clone(&self.participant),
+        }
+    }
+}
+
+impl<P> NamedService for GrpcParticipantServer<P> {
+    const NAME: &'static str = SERVICE_NAME;
+}
+
+struct ExecuteStepSvc<P>(GrpcParticipantServer<P>);
+
+impl<P> tonic::server::UnaryService<wire::ExecuteStepRequest> for ExecuteStepSvc<P>
+where
+    P: AsyncSagaParticipant + Send + 'static,
+{
+    type Response = wire::ExecuteStepResponse;
+    type Future = BoxFuture<tonic::Response<Self::Response>, Status>;
+
+    fn call(&mut self, request: tonic::Request<wire::ExecuteStepRequest>) -> Self::Future {
+        let server = self.0.clone();
+        Box::pin(async move {
+            server
+                .execute(request.into_inner())
+                .await
+                .map(tonic::Response::new)
+        })
+    }
+}
+
+struct CompensateStepSvc<P>(GrpcParticipantServer<P>);
+
+impl<P> tonic::server::UnaryService<wire::CompensateStepRequest> for CompensateStepSvc<P>
+where
+    P: AsyncSagaParticipant + Send + 'static,
+{
+    type Response = wire::CompensateStepResponse;
+    type Future = BoxFuture<tonic::Response<Self::Response>, Status>;
+
+    fn call(&mut self, request: tonic::Request<wire::CompensateStepRequest>) -> Self::Future {
+        let server = self.0.clone();
+        Box::pin(async move {
+            server
+                .compensate(request.into_inner())
+                .await
+                .map(tonic::Response::new)
+        })
+    }
+}
+
+impl<P, B> Service<http::Request<B>> for GrpcParticipantServer<P>
+where
+    P: AsyncSagaParticipant + Send + 'static,
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<tonic::body::Body>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let server = self.clone();
+        match request.uri().path() {
+            EXECUTE_STEP_PATH => Box::pin(async move {
+                let mut grpc = tonic::server::Grpc::new(ProstCodec::default());
+                Ok(grpc.unary(ExecuteStepSvc(server), request).await)
+            }),
+            COMPENSATE_STEP_PATH => Box::pin(async move {
+                let mut grpc = tonic::server::Grpc::new(ProstCodec::default());
+                Ok(grpc.unary(CompensateStepSvc(server), request).await)
+            }),
+            _ => Box::pin(async move {
+                Ok(Status::unimplemented("unknown saga participant method").into_http())
+            }),
+        }
+    }
+}
+
+impl<P> std::fmt::Debug for GrpcParticipantServer<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GrpcParticipantServer")
+            .finish_non_exhaustive()
+    }
+}
+
+/// Local stand-in for a participant served over gRPC.
+pub struct GrpcParticipantClient<J, D>
+where
+    J: ParticipantJournal,
+    D: ParticipantDedupeStore,
+{
+    saga: SagaParticipantSupport<J, D>,
+    step_name: &'static str,
+    saga_types: &'static [&'static str],
+    depends_on: DependencySpec,
+    channel: Channel,
+}
+
+impl<J, D> GrpcParticipantClient<J, D>
+where
+    J: ParticipantJournal,
+    D: ParticipantDedupeStore,
+{
+    /// Runs on saga start until [`after`](Self::after) says otherwise.
+    /// `channel` may connect lazily, e.g.
+    /// `Endpoint::from_static(url).connect_lazy()`.
+    pub fn new(
+        step_name: &'static str,
+        saga_types: &'static [&'static str],
+        channel: Channel,
+        saga: SagaParticipantSupport<J, D>,
+    ) -> Self {
+        Self {
+            saga,
+            step_name,
+            saga_types,
+            depends_on: DependencySpec::OnSagaStart,
+            channel,
+        }
+    }
+
+    /// Calls the remote participant once `depends_on` is satisfied.
+    pub fn after(mut self, depends_on: DependencySpec) -> Self {
+        self.depends_on = depends_on;
+        self
+    }
+
+    async fn call<Req, Res>(
+        channel: Channel,
+        path: &'static str,
+        request: Req,
+    ) -> Result<Res, Status>
+    where
+        Req: prost::Message + Send + Sync + 'static,
+        Res: prost::Message + Default + Send + Sync + 'static,
+    {
+        let mut grpc = tonic::client::Grpc::new(channel);
+        grpc.ready().await.map_err(|error| {
+            Status::unavailable(format!("saga participant unreachable: {error}"))
+        })?;
+        let response = grpc
+            .unary(
+                tonic::Request::new(request),
+                PathAndQuery::from_static(path),
+                ProstCodec::default(),
+            )
+            .await?;
+        Ok(response.into_inner())
+    }
+}
+
+impl<J, D> HasSagaParticipantSupport for GrpcParticipantClient<J, D>
+where
+    J: ParticipantJournal,
+    D: ParticipantDedupeStore,
+{
+    type Journal = J;
+    type Dedupe = D;
+
+    fn saga_support(&self) -> &SagaParticipantSupport<J, D> {
+        &self.saga
+    }
+
+    fn saga_support_mut(&mut self) -> &mut SagaParticipantSupport<J, D> {
+        &mut self.saga
+    }
+}
+
+impl<J, D> AsyncSagaParticipant for GrpcParticipantClient<J, D>
+where
+    J: ParticipantJournal,
+    D: ParticipantDedupeStore,
+{
+    type Error = StepError;
+
+    fn step_name(&self) -> &str {
+        self.step_name
+    }
+
+    fn saga_types(&self) -> &[&'static str] {
+        self.saga_types
+    }
+
+    fn depends_on(&self) -> DependencySpec {
+        self.depends_on.clone()
+    }
+
+    fn execute_step<'a>(
+        &'a mut self,
+        context: &'a SagaContext,
+        input: &'a [u8],
+    ) -> SagaBoxFuture<'a, Result<StepOutput, StepError>> {
+        let request = wire::ExecuteStepRequest {
+            context: Some(context_to_wire(context)),
+            input: input.to_vec(),
+            idempotency_key: IdempotencyKey::for_step(
+                context.saga_id,
+                self.step_name,
+                context.attempt + 1,
+            )
+            .as_str()
+            .to_owned(),
+        };
+        let channel = self.channel.clone();
+        Box::pin(async move {
+            let response: wire::ExecuteStepResponse =
+                match Self::call(channel, EXECUTE_STEP_PATH, request).await {
+                    Ok(response) => response,
+                    Err(status) if refused(status.code()) => {
+                        return Err(StepError::terminal(status.message()));
+                    }
+                    Err(status) => {
+                        return Err(StepError::require_compensation(status.message()));
+                    }
+                };
+            match wire::ExecuteOutcome::try_from(response.outcome) {
+                Ok(wire::ExecuteOutcome::Completed) if response.effect.is_empty() => {
+                    Ok(StepOutput::Completed {
+                        output: response.output,
+                        compensation_data: response.compensation_data,
+                    })
+                }
+                Ok(wire::ExecuteOutcome::Completed) => Ok(StepOutput::CompletedWithEffect {
+                    output: response.output,
+                    compensation_data: response.compensation_data,
+                    effect: response.effect.into(),
+                }),
+                Ok(wire::ExecuteOutcome::Partial) => Ok(StepOutput::partial(
+                    response.output,
+                    response.compensation_data,
+                    response.completion_ratio,
+                )),
+                Ok(wire::ExecuteOutcome::NoOp) => Ok(StepOutput::NoOp),
+                Ok(wire::ExecuteOutcome::Terminal) => {
+                    Err(StepError::terminal(response.reason).with_details(response.details))
+                }
+                Ok(wire::ExecuteOutcome::RequireCompensation) => {
+                    Err(StepError::require_compensation(response.reason)
+                        .with_details(response.details))
+                }
+                Err(_) => Err(StepError::require_compensation(format!(
+                    "unknown step outcome {}",
+                    response.outcome
+                ))),
+            }
+        })
+    }
+
+    fn compensate_step<'a>(
+        &'a mut self,
+        context: &'a SagaContext,
+        compensation_data: &'a [u8],
+    ) -> SagaBoxFuture<'a, Result<(), CompensationError>> {
+        let request = wire::CompensateStepRequest {
+            context: Some(context_to_wire(context)),
+            compensation_data: compensation_data.to_vec(),
+            idempotency_key: IdempotencyKey::for_compensation(context.saga_id, self.step_name)
+                .as_str()
+                .to_owned(),
+        };
+        let channel = self.channel.clone();
+        Box::pin(async move {
+            let response: wire::CompensateStepResponse =
+                match Self::call(channel, COMPENSATE_STEP_PATH, request).await {
+                    Ok(response) => response,
+                    Err(status) if status.code() == Code::Unavailable => {
+                        return Err(CompensationError::safe_to_retry(status.message()));
+                    }
+                    Err(status) if refused(status.code()) => {
+                        return Err(CompensationError::terminal(status.message()));
+                    }
+                    Err(status) => return Err(CompensationError::ambiguous(status.message())),
+                };
+            let error = match wire::CompensateOutcome::try_from(response.outcome) {
+                Ok(wire::CompensateOutcome::Compensated) => return Ok(()),
+                Ok(wire::CompensateOutcome::SafeToRetry) => {
+                    CompensationError::safe_to_retry(response.reason)
+                }
+                Ok(wire::CompensateOutcome::Terminal) => {
+                    CompensationError::terminal(response.reason)
+                }
+                Ok(wire::CompensateOutcome::Ambiguous) | Err(_) => {
+                    CompensationError::ambiguous(response.reason)
+                }
+            };
+            Err(error.with_details(response.details))
+        })
+    }
+}
+
+impl<J, D> std::fmt::Debug for GrpcParticipantClient<J, D>
+where
+    J: ParticipantJournal,
+    D: ParticipantDedupeStore,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GrpcParticipantClient")
+            .field("step_name", &self.step_name)
+            .field("depends_on", &self.depends_on)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tonic::transport::server::TcpIncoming;
+    use tonic::transport::{Endpoint, Server};
+
+    use super::*;
+    use crate::{
+        handle_async_saga_event_with_emit, saga_started, DeterministicContextBuilder,
+        InMemoryDedupe, InMemoryJournal, SagaChoreographyEvent,
+    };
+
+    /// Remote risk check: rejects empty orders, compensates anything.
+    #[derive(Default)]
+    struct RemoteRiskCheck {
+        compensated: Vec<Vec<u8>>,
+    }
+
+    impl AsyncSagaParticipant for RemoteRiskCheck {
+        type Error = StepError;
+
+        fn step_name(&self) -> &str {
+            "risk_check"
+        }
+
+        fn saga_types(&self) -> &[&'static str] {
+            &["order_lifecycle"]
+        }
+
+        fn execute_step<'a>(
+            &'a mut self,
+            _context: &'a SagaContext,
+            input: &'a [u8],
+        ) -> SagaBoxFuture<'a, Result<StepOutput, StepError>> {
+            Box::pin(async move {
+                if input.is_empty() {
+                    return Err(StepError::terminal("empty order").with_details(b"E1".to_vec()));
+                }
+                Ok(StepOutput::Completed {
+                    output: [input, b":approved"].concat(),
+                    compensation_data: b"limit-7".to_vec(),
+                })
+            })
+        }
+
+        fn compensate_step<'a>(
+            &'a mut self,
+            _context: &'a SagaContext,
+            compensation_data: &'a [u8],
+        ) -> SagaBoxFuture<'a, Result<(), CompensationError>> {
+            self.compensated.push(compensation_data.to_vec());
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn remote_participant_runs_the_step_behind_the_local_bridge() {
+        let server = GrpcParticipantServer::new(RemoteRiskCheck::default());
+        let incoming = TcpIncoming::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = incoming.local_addr().unwrap();
+        tokio::spawn(
+            Server::builder()
+                .add_service(server.clone())
+                .serve_with_incoming(incoming),
+        );
+        let channel = Endpoint::from_shared(format!("http://{addr}"))
+            .unwrap()
+            .connect_lazy();
+        let mut client = GrpcParticipantClient::new(
+            "risk_check",
+            &["order_lifecycle"],
+            channel,
+            SagaParticipantSupport::new(InMemoryJournal::new(), InMemoryDedupe::new()),
+        );
+        let context = DeterministicContextBuilder::default().build();
+
+        let mut emitted = Vec::new();
+        handle_async_saga_event_with_emit(
+            &mut client,
+            saga_started(context.clone(), b"order-7".to_vec()),
+            |event| emitted.push(event),
+        )
+        .await;
+        let Some(SagaChoreographyEvent::StepCompleted {
+            output,
+            compensation_available,
+            ..
+        }) = emitted.last()
+        else {
+            panic!("expected the remote step to complete: {emitted:?}");
+        };
+        assert_eq!(output, b"order-7:approved");
+        assert!(compensation_available);
+
+        let rejected = client.execute_step(&context, b"").await.unwrap_err();
+        assert!(!rejected.requires_compensation());
+        assert_eq!(rejected.reason(), "empty order");
+        assert_eq!(rejected.details(), b"E1");
+
+        client.compensate_step(&context, b"limit-7").await.unwrap();
+        assert_eq!(
+            server.participant.lock().await.compensated,
+            [b"limit-7".to_vec()]
+        );
+
+        let other_step = context.next_step("place_order".into());
+        let refused = client
+            .execute_step(&other_step, b"order-7")
+            .await
+            .unwrap_err();
+        assert!(!refused.requires_compensation());
+    }
+}
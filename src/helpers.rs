@@ -1,10 +1,32 @@
 //! Helper functions for saga handling
 
+use std::collections::HashMap;
+
+use crate::async_storage::{
+    dedupe_contains, dedupe_mark_processed, journal_incoming_history, journal_pending_incoming,
+};
+use crate::correlation::index_step_correlations;
+use crate::effects::{dispatch_step_effect, dispatch_step_effect_async};
+use crate::journal::last_progress_entry;
+use crate::missing_state::{handle_missing_state, handle_missing_state_async};
+use crate::payload::{offload_step_output, resolve_step_input};
+use crate::reorder::{HeldEvent, ReorderExpiry};
+use crate::sensitive::{open_compensation_data, seal_compensation_data};
+use crate::state_ext::{
+    check_dedupe_strict_async, mark_incoming_processed_async, prune_saga_async,
+    record_event_strict_async, record_event_with_outgoing_strict_async, record_incoming_async,
+    try_record_event_async, try_record_event_with_outgoing_async, SagaStateStoreError,
+};
+use crate::step_executor::{RunningStep, StepResult, TakenSteps};
+use crate::SagaSpanExt;
 use crate::{
-    AsyncSagaParticipant, CompensationError, DependencySpec, ParticipantEvent,
-    SagaChoreographyEvent, SagaContext, SagaId, SagaParticipant, SagaParticipantState,
-    SagaStateEntry, SagaStateExt, StepError, StepOutput,
+    AsyncSagaParticipant, Compensating, CompensationError, DeadLetterReason, DedupeKey,
+    DependencySpec, InboxEntry, JournalFailurePolicy, ParticipantDedupeStore, ParticipantEvent,
+    ParticipantJournal, QuarantineError, SagaChoreographyEvent, SagaContext, SagaId,
+    SagaJournalHistory, SagaParticipant, SagaParticipantState, SagaStateEntry, SagaStateExt,
+    StateKind, StepError, StepLeaseError, StepName, StepOutput,
 };
+use tracing::Instrument;
 
 /// Saga event handler with an explicit emit sink for produced choreography events.
 pub fn handle_saga_event_with_emit<P, F>(
@@ -15,51 +37,690 @@ pub fn handle_saga_event_with_emit<P, F>(
     P: SagaParticipant + SagaStateExt,
     F: FnMut(SagaChoreographyEvent),
 {
-    let context = event.context().clone();
-    let now = participant.now_millis();
+    let context = event.context();
+    let saga_id = context.saga_id;
 
     // Check saga type
-    if !participant
-        .saga_types()
-        .iter()
-        .any(|t| *t == context.saga_type.as_ref())
-    {
+    if !participant.matches_saga_type(context.saga_type.as_ref()) {
+        drop_unknown_saga(participant, event);
+        return;
+    }
+    if filtered_out(participant, &event) {
+        return;
+    }
+    if !participant.supports_workflow_version(context.workflow_version) {
+        ignore_unsupported_workflow_version(context);
         return;
     }
 
     let is_saga_started = matches!(event, SagaChoreographyEvent::SagaStarted { .. });
-    if !is_saga_started && participant.is_terminal_saga_latched(context.saga_id) {
+    if !is_saga_started && participant.is_terminal_saga_latched(saga_id) {
+        return;
+    }
+    if is_saga_started && refuse_saga_start_while_draining(participant, context) {
+        return;
+    }
+
+    // Checked before the dedupe key is marked so a redelivery after the
+    // clocks agree again is still processed.
+    if let Err(error) = check_event_skew(participant, context) {
+        reject_incoming_event(participant, saga_id, event.event_type(), &error, None, None);
         return;
     }
 
+    sweep_terminal_states_if_due(participant);
+    settle_offloaded_steps(participant, &mut emit);
+    flush_unjournaled_outcomes(participant, &mut emit);
+    // Persist the raw event before the dedupe key is marked so a crash while
+    // processing leaves it in the inbox for `replay_saga_inbox_with_emit`.
+    let dedupe_key = participant.saga_support().dedupe_identity.key(&event);
+    let inbox_id = participant.record_incoming(saga_id, dedupe_key, &event);
+
     // Idempotency check
-    let dedupe_key = dedupe_key_for_event(&event);
-    if !participant.check_dedupe(context.saga_id, &dedupe_key) {
+    if !admit_incoming_event(participant, &event, dedupe_key, inbox_id) {
         return; // Already processed
     }
+    if let Err(error) = participant.authorize_event(context, event.event_type()) {
+        reject_incoming_event(
+            participant,
+            saga_id,
+            event.event_type(),
+            &error,
+            Some(dedupe_key),
+            inbox_id,
+        );
+        return;
+    }
+    apply_projections(participant, &event);
+
+    let Some(event) = hold_early_event(participant, event, inbox_id) else {
+        return;
+    };
+    let parked = park_copy(participant, &event);
+    dispatch_saga_event_with_emit(participant, event, &mut emit);
+    finish_incoming(participant, saga_id, inbox_id, parked);
+    if is_saga_started {
+        for held in release_held_events(participant, saga_id) {
+            let parked = park_copy(participant, &held.event);
+            dispatch_saga_event_with_emit(participant, held.event, &mut emit);
+            finish_incoming(participant, saga_id, held.inbox_id, parked);
+        }
+    }
+    if in_flight_slot_freed(participant) {
+        replay_in_flight_parked_with_emit(participant, &mut emit);
+    }
+}
+
+/// Drops an event of a saga type the participant does not handle, or
+/// dead-letters it as [`DeadLetterReason::UnknownSaga`] when the participant
+/// opted in with
+/// [`crate::SagaParticipantSupport::with_unknown_saga_dead_letters`].
+pub(crate) fn drop_unknown_saga<P>(participant: &P, event: SagaChoreographyEvent)
+where
+    P: SagaStateExt,
+{
+    let support = participant.saga_support();
+    if support.dead_letter_unknown_sagas {
+        support.dead_letter(
+            DeadLetterReason::UnknownSaga,
+            "no participant for saga_type",
+            event,
+        );
+        return;
+    }
+    tracing::trace!(
+        target: "core::saga",
+        event = "saga_type_not_handled",
+        saga_id = event.context().saga_id.get(),
+        saga_type = event.context().saga_type.as_str()
+    );
+}
+
+/// Whether the participant's [`crate::SagaEventFilter`] rejects `event`, or
+/// its saga belongs to another replica.
+pub(crate) fn filtered_out<P>(participant: &P, event: &SagaChoreographyEvent) -> bool
+where
+    P: SagaStateExt,
+{
+    let filtered_out = participant
+        .saga_support()
+        .event_filter
+        .as_ref()
+        .is_some_and(|filter| !filter.matches(event));
+    if filtered_out {
+        tracing::trace!(
+            target: "core::saga",
+            event = "saga_event_filtered",
+            saga_id = event.context().saga_id.get(),
+            event_type = event.event_type()
+        );
+        return true;
+    }
+    let saga_id = event.context().saga_id;
+    let foreign = participant
+        .saga_support()
+        .partitions
+        .as_ref()
+        .is_some_and(|partitions| !partitions.owns(saga_id));
+    if foreign {
+        tracing::trace!(
+            target: "core::saga",
+            event = "saga_event_outside_partition",
+            saga_id = saga_id.get(),
+            event_type = event.event_type()
+        );
+    }
+    foreign
+}
+
+pub(crate) fn apply_projections<P>(participant: &P, event: &SagaChoreographyEvent)
+where
+    P: SagaStateExt,
+{
+    for projection in &participant.saga_support().projections {
+        projection
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .apply(event);
+    }
+}
+
+/// Drops a `SagaStarted` arriving after [`crate::drain`] began. Nothing is
+/// journaled or deduped, so the start stays deliverable elsewhere.
+pub(crate) fn refuse_saga_start_while_draining<P>(participant: &P, context: &SagaContext) -> bool
+where
+    P: SagaStateExt,
+{
+    if !participant.saga_support().draining {
+        return false;
+    }
+    tracing::info!(
+        target: "core::saga",
+        event = "saga_start_refused_draining",
+        saga_id = context.saga_id.get(),
+        saga_type = context.saga_type.as_ref()
+    );
+    true
+}
+
+/// Logs an event skipped because the participant does not support the
+/// saga's workflow version. Nothing is journaled: the saga belongs to the
+/// participants of that version.
+pub(crate) fn ignore_unsupported_workflow_version(context: &SagaContext) {
+    tracing::debug!(
+        target: "core::saga",
+        event = "saga_event_workflow_version_unsupported",
+        saga_id = context.saga_id.get(),
+        saga_type = context.saga_type.as_ref(),
+        workflow_version = context.workflow_version
+    );
+}
+
+pub(crate) fn check_event_skew<P>(
+    participant: &P,
+    context: &SagaContext,
+) -> Result<(), crate::EventSkewError>
+where
+    P: SagaStateExt,
+{
+    match participant.saga_support().event_skew_window {
+        Some(window) => window.check(context.event_timestamp_millis, participant.now_millis()),
+        None => Ok(()),
+    }
+}
+
+/// Journals a rejected incoming event and closes its inbox entry.
+/// `dedupe_key` is `None` for events rejected before the inbox recorded them.
+pub(crate) fn reject_incoming_event<P>(
+    participant: &P,
+    saga_id: SagaId,
+    event_type: &str,
+    error: &dyn std::fmt::Display,
+    dedupe_key: Option<DedupeKey>,
+    inbox_id: Option<u64>,
+) where
+    P: SagaStateExt,
+{
+    let entry = rejected_event_entry(participant, saga_id, event_type, error, dedupe_key);
+    participant.record_event(saga_id, entry);
+    participant.mark_incoming_processed(saga_id, inbox_id);
+}
+
+/// [`reject_incoming_event`] for async handlers.
+pub(crate) async fn reject_incoming_event_async<P>(
+    participant: &P,
+    saga_id: SagaId,
+    event_type: &str,
+    error: &(dyn std::fmt::Display + Sync),
+    dedupe_key: Option<DedupeKey>,
+    inbox_id: Option<u64>,
+) where
+    P: SagaStateExt,
+{
+    let entry = rejected_event_entry(participant, saga_id, event_type, error, dedupe_key);
+    try_record_event_async(participant, saga_id, entry).await;
+    mark_incoming_processed_async(participant, saga_id, inbox_id).await;
+}
+
+fn rejected_event_entry<P>(
+    participant: &P,
+    saga_id: SagaId,
+    event_type: &str,
+    error: &dyn std::fmt::Display,
+    dedupe_key: Option<DedupeKey>,
+) -> ParticipantEvent
+where
+    P: SagaStateExt,
+{
+    tracing::warn!(
+        target: "core::saga",
+        event = "saga_event_rejected",
+        saga_id = saga_id.get(),
+        event_type,
+        error = %error
+    );
+    ParticipantEvent::EventRejected {
+        event_type: event_type.into(),
+        reason: error.to_string().into(),
+        rejected_at_millis: participant.now_millis(),
+        dedupe_key: dedupe_key.map(|key| key.to_string().into()),
+    }
+}
+
+/// Marks the dedupe key of an incoming event; returns `false` when the
+/// event is to be dropped. Duplicates are counted per type in the stats and
+/// reported to the observer; either way the inbox entry is closed.
+pub(crate) fn admit_incoming_event<P>(
+    participant: &P,
+    event: &SagaChoreographyEvent,
+    dedupe_key: DedupeKey,
+    inbox_id: Option<u64>,
+) -> bool
+where
+    P: SagaStateExt,
+{
+    let saga_id = event.context().saga_id;
+    participant.saga_support().persist_stats_if_due();
+    let checked = participant.check_dedupe_strict(saga_id, dedupe_key);
+    if dedupe_admits(participant, event, dedupe_key, checked) {
+        return true;
+    }
+    participant.mark_incoming_processed(saga_id, inbox_id);
+    false
+}
+
+/// [`admit_incoming_event`] for async handlers.
+pub(crate) async fn admit_incoming_event_async<P>(
+    participant: &P,
+    event: &SagaChoreographyEvent,
+    dedupe_key: DedupeKey,
+    inbox_id: Option<u64>,
+) -> bool
+where
+    P: SagaStateExt,
+{
+    let saga_id = event.context().saga_id;
+    participant.saga_support().persist_stats_if_due();
+    let checked = check_dedupe_strict_async(participant, saga_id, dedupe_key).await;
+    if dedupe_admits(participant, event, dedupe_key, checked) {
+        return true;
+    }
+    mark_incoming_processed_async(participant, saga_id, inbox_id).await;
+    false
+}
+
+fn dedupe_admits<P>(
+    participant: &P,
+    event: &SagaChoreographyEvent,
+    dedupe_key: DedupeKey,
+    checked: Result<bool, SagaStateStoreError>,
+) -> bool
+where
+    P: SagaStateExt,
+{
+    let context = event.context();
+    match checked {
+        Ok(true) => return true,
+        Ok(false) => {
+            let support = participant.saga_support();
+            support.stats.record_duplicate(event.event_type());
+            if let Some(observer) = &support.observer {
+                observer.on_duplicate_event(context, event.event_type());
+            }
+        }
+        Err(err) => {
+            tracing::error!(
+                target: "core::saga",
+                event = "saga_state_dedupe_check_failed",
+                saga_id = context.saga_id.get(),
+                key = %dedupe_key,
+                error = ?err
+            );
+        }
+    }
+    false
+}
+
+/// Whether `step_name` of `saga_id` was already compensated, going by its
+/// [`IdempotencyKey::for_compensation`] in the dedupe store.
+///
+/// [`IdempotencyKey::for_compensation`]: crate::IdempotencyKey::for_compensation
+pub(crate) fn compensation_already_done<P>(
+    participant: &P,
+    saga_id: SagaId,
+    step_name: &str,
+) -> bool
+where
+    P: SagaStateExt,
+{
+    let done = participant
+        .saga_dedupe()
+        .contains(saga_id, compensation_dedupe_key(saga_id, step_name));
+    compensation_duplicate(saga_id, step_name, done)
+}
+
+/// [`compensation_already_done`] for async handlers.
+async fn compensation_already_done_async<P>(
+    participant: &P,
+    saga_id: SagaId,
+    step_name: &str,
+) -> bool
+where
+    P: SagaStateExt,
+{
+    let key = compensation_dedupe_key(saga_id, step_name);
+    let done = dedupe_contains(participant.saga_dedupe(), saga_id, key).await;
+    compensation_duplicate(saga_id, step_name, done)
+}
+
+fn compensation_duplicate(saga_id: SagaId, step_name: &str, done: bool) -> bool {
+    if done {
+        tracing::warn!(
+            target: "core::saga",
+            event = "saga_compensation_duplicate_skipped",
+            saga_id = saga_id.get(),
+            step_name
+        );
+    }
+    done
+}
+
+/// Records that `step_name` of `saga_id` was compensated. Called only after
+/// `compensate_step` succeeded, so a failed compensation can be retried; a
+/// crash in between can repeat it.
+pub(crate) fn mark_compensation_done<P>(participant: &P, saga_id: SagaId, step_name: &str)
+where
+    P: SagaStateExt,
+{
+    let marked = participant
+        .saga_dedupe()
+        .mark_processed(saga_id, compensation_dedupe_key(saga_id, step_name));
+    compensation_marked(saga_id, step_name, marked);
+}
+
+/// [`mark_compensation_done`] for async handlers.
+async fn mark_compensation_done_async<P>(participant: &P, saga_id: SagaId, step_name: &str)
+where
+    P: SagaStateExt,
+{
+    let key = compensation_dedupe_key(saga_id, step_name);
+    let marked = dedupe_mark_processed(participant.saga_dedupe(), saga_id, key).await;
+    compensation_marked(saga_id, step_name, marked);
+}
+
+fn compensation_marked(saga_id: SagaId, step_name: &str, marked: Result<(), crate::DedupeError>) {
+    if let Err(err) = marked {
+        tracing::error!(
+            target: "core::saga",
+            event = "saga_compensation_dedupe_mark_failed",
+            saga_id = saga_id.get(),
+            step_name,
+            error = %err
+        );
+    }
+}
+
+fn compensation_dedupe_key(saga_id: SagaId, step_name: &str) -> DedupeKey {
+    DedupeKey::named(crate::IdempotencyKey::for_compensation(saga_id, step_name).as_str())
+}
+
+/// Reason a panic out of `execute_step` or `compensate_step` is failed
+/// with; the panic is logged and reported to the observer.
+pub(crate) fn contained_panic_reason<P>(
+    participant: &P,
+    context: &SagaContext,
+    step_name: &str,
+    payload: &(dyn std::any::Any + Send),
+) -> String
+where
+    P: SagaStateExt,
+{
+    let message = crate::panic_message_from_payload(payload);
+    tracing::error!(
+        target: "core::saga",
+        event = "saga_step_panic_contained",
+        saga_id = context.saga_id.get(),
+        step_name,
+        message = %message
+    );
+    if let Some(observer) = &participant.saga_support().observer {
+        observer.on_step_panicked(context, step_name, &message);
+    }
+    format!("panicked: {message}")
+}
+
+/// Future that resolves to `Err` with the panic payload when polling the
+/// inner future panics.
+pub(crate) struct CatchUnwind<F>(pub(crate) F);
+
+impl<F> std::future::Future for CatchUnwind<F>
+where
+    F: std::future::Future + Unpin,
+{
+    type Output = std::thread::Result<F::Output>;
+
+    fn poll(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        let inner = &mut self.0;
+        match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            std::pin::Pin::new(inner).poll(cx)
+        })) {
+            Ok(std::task::Poll::Pending) => std::task::Poll::Pending,
+            Ok(std::task::Poll::Ready(output)) => std::task::Poll::Ready(Ok(output)),
+            Err(payload) => std::task::Poll::Ready(Err(payload)),
+        }
+    }
+}
+
+/// Holds `event` in the reorder buffer if its saga has not been seen
+/// started; hands it back when it can be processed now.
+pub(crate) fn hold_early_event<P>(
+    participant: &mut P,
+    event: SagaChoreographyEvent,
+    inbox_id: Option<u64>,
+) -> Option<SagaChoreographyEvent>
+where
+    P: SagaStateExt,
+{
+    if participant.saga_support().reorder_window.is_none() {
+        return Some(event);
+    }
+    let saga_id = event.context().saga_id;
+    let event_type = event.event_type();
+    let now = participant.now_millis();
+    let event = participant
+        .saga_support_mut()
+        .reorder
+        .hold(event, inbox_id, now);
+    if event.is_none() {
+        tracing::debug!(
+            target: "core::saga",
+            event = "saga_event_held_for_start",
+            saga_id = saga_id.get(),
+            event_type
+        );
+    }
+    event
+}
+
+pub(crate) fn release_held_events<P>(participant: &mut P, saga_id: SagaId) -> Vec<HeldEvent>
+where
+    P: SagaStateExt,
+{
+    if participant.saga_support().reorder_window.is_none() {
+        return Vec::new();
+    }
+    participant.saga_support_mut().reorder.release(saga_id)
+}
+
+/// Takes the held events whose reorder window ran out. Under
+/// [`ReorderExpiry::DeadLetter`] they are dead-lettered here and nothing is
+/// returned.
+fn take_expired_held_events<P>(participant: &mut P) -> Vec<HeldEvent>
+where
+    P: SagaStateExt,
+{
+    let (expired, dead_lettered) = expire_held_events(participant);
+    for (saga_id, inbox_id) in dead_lettered {
+        participant.mark_incoming_processed(saga_id, inbox_id);
+    }
+    expired
+}
+
+/// [`take_expired_held_events`] for async handlers.
+async fn take_expired_held_events_async<P>(participant: &mut P) -> Vec<HeldEvent>
+where
+    P: SagaStateExt,
+{
+    let (expired, dead_lettered) = expire_held_events(participant);
+    for (saga_id, inbox_id) in dead_lettered {
+        mark_incoming_processed_async(participant, saga_id, inbox_id).await;
+    }
+    expired
+}
+
+/// The expired held events to process, and the inbox entries of those
+/// dead-lettered here, to close.
+fn expire_held_events<P>(participant: &mut P) -> (Vec<HeldEvent>, Vec<(SagaId, Option<u64>)>)
+where
+    P: SagaStateExt,
+{
+    let Some(window) = participant.saga_support().reorder_window else {
+        return (Vec::new(), Vec::new());
+    };
+    let now = participant.now_millis();
+    let process = window.on_expiry == ReorderExpiry::Process;
+    let expired =
+        participant
+            .saga_support_mut()
+            .reorder
+            .take_expired(now, window.hold_millis, process);
+    if process {
+        return (expired, Vec::new());
+    }
+    let dead_lettered = expired
+        .into_iter()
+        .map(|held| {
+            let saga_id = held.event.context().saga_id;
+            participant.saga_support().dead_letter(
+                DeadLetterReason::OutOfOrder,
+                "SagaStarted did not arrive within the reorder window",
+                held.event,
+            );
+            (saga_id, held.inbox_id)
+        })
+        .collect();
+    (Vec::new(), dead_lettered)
+}
+
+/// Resolves events held for their saga's `SagaStarted` longer than the
+/// participant's [`SagaReorderWindow`](crate::SagaReorderWindow): they are
+/// processed, or dead-lettered under [`ReorderExpiry::DeadLetter`]. Run it
+/// on the participant's timer.
+///
+/// Returns the number of events processed.
+pub fn flush_reorder_buffer_with_emit<P, F>(participant: &mut P, mut emit: F) -> usize
+where
+    P: SagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let expired = take_expired_held_events(participant);
+    let flushed = expired.len();
+    for held in expired {
+        let saga_id = held.event.context().saga_id;
+        let parked = park_copy(participant, &held.event);
+        dispatch_saga_event_with_emit(participant, held.event, &mut emit);
+        finish_incoming(participant, saga_id, held.inbox_id, parked);
+    }
+    flushed
+}
+
+/// Async counterpart of [`flush_reorder_buffer_with_emit`].
+pub async fn flush_async_reorder_buffer_with_emit<P, F>(participant: &mut P, mut emit: F) -> usize
+where
+    P: AsyncSagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let expired = take_expired_held_events_async(participant).await;
+    let flushed = expired.len();
+    for held in expired {
+        let saga_id = held.event.context().saga_id;
+        let parked = park_copy(participant, &held.event);
+        dispatch_async_saga_event_with_emit(participant, held.event, &mut emit).await;
+        finish_incoming_async(participant, saga_id, held.inbox_id, parked).await;
+    }
+    flushed
+}
+
+/// Re-processes every event left pending in the journal inbox, except those
+/// the reorder buffer still holds.
+///
+/// Call this on startup, before new events are delivered. A pending entry may
+/// have been interrupted before or after its dedupe key was marked, so the
+/// dedupe store cannot tell it from a duplicate; the saga's inbox history can.
+/// An entry whose key an earlier inbox entry of the saga carries is a
+/// duplicate and is closed without being dispatched. The others are
+/// dispatched directly, with their keys marked in case the crash came first.
+/// Outcomes held back while the journal rejected them are journaled and
+/// emitted first.
+///
+/// Returns the number of replayed events.
+pub fn replay_saga_inbox_with_emit<P, F>(participant: &mut P, mut emit: F) -> usize
+where
+    P: SagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let pending = pending_incoming_events(participant);
+    let replayed = pending.len();
+    flush_unjournaled_outcomes(participant, &mut emit);
+    for entry in pending {
+        let parked = park_copy(participant, &entry.event);
+        dispatch_saga_event_with_emit(participant, entry.event, &mut emit);
+        finish_incoming(participant, entry.saga_id, entry.inbox_id, parked);
+    }
+    replayed
+}
+
+/// Re-dispatches the triggers [`gate_in_flight`] parked, in arrival order;
+/// those still over the cap park again.
+fn replay_in_flight_parked_with_emit<P, F>(participant: &mut P, emit: &mut F)
+where
+    P: SagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let parked = std::mem::take(&mut participant.saga_support_mut().in_flight_parked);
+    for entry in parked {
+        let parked = park_copy(participant, &entry.event);
+        dispatch_saga_event_with_emit(participant, entry.event, emit);
+        finish_incoming(participant, entry.saga_id, entry.inbox_id, parked);
+    }
+}
+
+fn dispatch_saga_event_with_emit<P, F>(
+    participant: &mut P,
+    event: SagaChoreographyEvent,
+    emit: &mut F,
+) where
+    P: SagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let now = participant.now_millis();
+    let clock = participant.saga_support().logical_clock.clone();
+    clock.observe(event.context().logical_clock);
+    // Events staged in the outbox were stamped before they were staged.
+    let staged = participant.saga_support().staged_outgoing.clone();
+    let emit = &mut |mut event: SagaChoreographyEvent| {
+        if !staged.contains(&event) {
+            clock.stamp(event.context_mut());
+        }
+        emit(event);
+    };
 
+    // Each arm takes the context out of the event instead of cloning it.
     match event {
-        SagaChoreographyEvent::SagaStarted { payload, .. }
+        SagaChoreographyEvent::SagaStarted { context, payload }
             if participant.depends_on().is_on_saga_start() =>
         {
             // A new saga run may legitimately reuse a saga_id after process restart.
             // Reset per-saga in-memory dependency/state tracking so old runs cannot
             // satisfy dependencies for the new run.
             participant.unlatch_terminal_saga(context.saga_id);
-            participant.saga_states().remove(&context.saga_id);
+            participant.clear_saga_state(context.saga_id);
             participant
                 .dependency_completions()
                 .remove(&context.saga_id);
             participant.dependency_fired().remove(&context.saga_id);
-            execute_step_wrapper_with_emit(participant, context.clone(), payload, now, &mut emit);
+            execute_step_wrapper_with_emit(participant, context, payload, now, emit);
         }
 
-        SagaChoreographyEvent::SagaStarted { .. } => {
+        SagaChoreographyEvent::SagaStarted { context, .. } => {
             // Even when this participant does not execute on saga start, clear stale
             // dependency/state entries for this saga id so downstream dependency checks
             // are scoped to the current run.
             participant.unlatch_terminal_saga(context.saga_id);
-            participant.saga_states().remove(&context.saga_id);
+            participant.clear_saga_state(context.saga_id);
             participant
                 .dependency_completions()
                 .remove(&context.saga_id);
@@ -67,7 +728,7 @@ pub fn handle_saga_event_with_emit<P, F>(
         }
 
         SagaChoreographyEvent::StepCompleted {
-            context: step_ctx,
+            context,
             output,
             saga_input,
             ..
@@ -77,7 +738,7 @@ pub fn handle_saga_event_with_emit<P, F>(
                 participant,
                 context.saga_id,
                 &dependency_spec,
-                &step_ctx.step_name,
+                &context.step_name,
             );
             if should_fire {
                 let next_context = context.next_step(participant.step_name().into());
@@ -86,32 +747,42 @@ pub fn handle_saga_event_with_emit<P, F>(
                 } else {
                     output
                 };
-                execute_step_wrapper_with_emit(participant, next_context, input, now, &mut emit);
+                execute_step_wrapper_with_emit(participant, next_context, input, now, emit);
             }
         }
 
         SagaChoreographyEvent::CompensationRequested {
+            context,
             steps_to_compensate,
             ..
         } => {
-            if steps_to_compensate.contains(&participant.step_name().into()) {
-                compensate_wrapper_with_emit(participant, &context, now, &mut emit);
+            let step_name = participant.step_name();
+            if steps_to_compensate.iter().any(|step| *step == step_name) {
+                compensate_wrapper_with_emit(participant, &context, now, emit);
             }
         }
 
-        SagaChoreographyEvent::SagaCompleted { .. } => {
+        SagaChoreographyEvent::SagaCompleted { context } if context.parent_saga_id.is_some() => {
+            hold_completion_for_parent(&context, participant.step_name());
+        }
+
+        SagaChoreographyEvent::SagaCompleted { context } => {
             participant.latch_terminal_saga(context.saga_id);
             participant.on_saga_completed(&context);
             participant.prune_saga(context.saga_id);
         }
 
-        SagaChoreographyEvent::SagaFailed { reason, .. } => {
+        SagaChoreographyEvent::SagaFailed {
+            context, reason, ..
+        } => {
             participant.latch_terminal_saga(context.saga_id);
             participant.on_saga_failed(&context, &reason);
             participant.prune_saga(context.saga_id);
         }
 
-        SagaChoreographyEvent::SagaQuarantined { reason, .. } => {
+        SagaChoreographyEvent::SagaQuarantined {
+            context, reason, ..
+        } => {
             participant.latch_terminal_saga(context.saga_id);
             participant.on_quarantined(&context, &reason);
             participant.prune_saga(context.saga_id);
@@ -129,56 +800,148 @@ pub async fn handle_async_saga_event_with_emit<P, F>(
     P: AsyncSagaParticipant + SagaStateExt,
     F: FnMut(SagaChoreographyEvent),
 {
-    let context = event.context().clone();
-    let now = participant.now_millis();
+    let context = event.context();
+    let saga_id = context.saga_id;
 
-    if !participant
-        .saga_types()
-        .iter()
-        .any(|t| *t == context.saga_type.as_ref())
-    {
+    if !participant.matches_saga_type(context.saga_type.as_ref()) {
+        drop_unknown_saga(participant, event);
+        return;
+    }
+    if filtered_out(participant, &event) {
+        return;
+    }
+    if !participant.supports_workflow_version(context.workflow_version) {
+        ignore_unsupported_workflow_version(context);
         return;
     }
 
     let is_saga_started = matches!(event, SagaChoreographyEvent::SagaStarted { .. });
-    if !is_saga_started && participant.is_terminal_saga_latched(context.saga_id) {
+    if !is_saga_started && participant.is_terminal_saga_latched(saga_id) {
+        return;
+    }
+    if is_saga_started && refuse_saga_start_while_draining(participant, context) {
         return;
     }
 
-    let dedupe_key = dedupe_key_for_event(&event);
-    if !participant.check_dedupe(context.saga_id, &dedupe_key) {
+    if let Err(error) = check_event_skew(participant, context) {
+        let event_type = event.event_type();
+        reject_incoming_event_async(participant, saga_id, event_type, &error, None, None).await;
         return;
     }
 
-    match event {
-        SagaChoreographyEvent::SagaStarted { payload, .. }
-            if participant.depends_on().is_on_saga_start() =>
-        {
-            participant.unlatch_terminal_saga(context.saga_id);
-            participant.saga_states().remove(&context.saga_id);
-            participant
-                .dependency_completions()
-                .remove(&context.saga_id);
-            participant.dependency_fired().remove(&context.saga_id);
-            execute_step_wrapper_with_emit_async(
-                participant,
-                context.clone(),
-                payload,
-                now,
-                &mut emit,
-            )
-            .await;
-        }
-        SagaChoreographyEvent::SagaStarted { .. } => {
-            participant.unlatch_terminal_saga(context.saga_id);
-            participant.saga_states().remove(&context.saga_id);
+    sweep_terminal_states_if_due(participant);
+    settle_offloaded_steps_async(participant, &mut emit).await;
+    flush_unjournaled_outcomes_async(participant, &mut emit).await;
+    let dedupe_key = participant.saga_support().dedupe_identity.key(&event);
+    let inbox_id = record_incoming_async(participant, saga_id, dedupe_key, &event).await;
+    if !admit_incoming_event_async(participant, &event, dedupe_key, inbox_id).await {
+        return;
+    }
+    if let Err(error) = participant.authorize_event(context, event.event_type()) {
+        reject_incoming_event_async(
+            participant,
+            saga_id,
+            event.event_type(),
+            &error,
+            Some(dedupe_key),
+            inbox_id,
+        )
+        .await;
+        return;
+    }
+    apply_projections(participant, &event);
+
+    let Some(event) = hold_early_event(participant, event, inbox_id) else {
+        return;
+    };
+    let parked = park_copy(participant, &event);
+    dispatch_async_saga_event_with_emit(participant, event, &mut emit).await;
+    finish_incoming_async(participant, saga_id, inbox_id, parked).await;
+    if is_saga_started {
+        for held in release_held_events(participant, saga_id) {
+            let parked = park_copy(participant, &held.event);
+            dispatch_async_saga_event_with_emit(participant, held.event, &mut emit).await;
+            finish_incoming_async(participant, saga_id, held.inbox_id, parked).await;
+        }
+    }
+    if in_flight_slot_freed(participant) {
+        replay_async_in_flight_parked_with_emit(participant, &mut emit).await;
+    }
+}
+
+/// Async counterpart of [`replay_saga_inbox_with_emit`].
+pub async fn replay_async_saga_inbox_with_emit<P, F>(participant: &mut P, mut emit: F) -> usize
+where
+    P: AsyncSagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let pending = pending_incoming_events_async(participant).await;
+    let replayed = pending.len();
+    flush_unjournaled_outcomes_async(participant, &mut emit).await;
+    for entry in pending {
+        let parked = park_copy(participant, &entry.event);
+        dispatch_async_saga_event_with_emit(participant, entry.event, &mut emit).await;
+        finish_incoming_async(participant, entry.saga_id, entry.inbox_id, parked).await;
+    }
+    replayed
+}
+
+/// Async counterpart of [`replay_in_flight_parked_with_emit`].
+async fn replay_async_in_flight_parked_with_emit<P, F>(participant: &mut P, emit: &mut F)
+where
+    P: AsyncSagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let parked = std::mem::take(&mut participant.saga_support_mut().in_flight_parked);
+    for entry in parked {
+        let parked = park_copy(participant, &entry.event);
+        dispatch_async_saga_event_with_emit(participant, entry.event, emit).await;
+        finish_incoming_async(participant, entry.saga_id, entry.inbox_id, parked).await;
+    }
+}
+
+async fn dispatch_async_saga_event_with_emit<P, F>(
+    participant: &mut P,
+    event: SagaChoreographyEvent,
+    emit: &mut F,
+) where
+    P: AsyncSagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let now = participant.now_millis();
+    let clock = participant.saga_support().logical_clock.clone();
+    clock.observe(event.context().logical_clock);
+    let staged = participant.saga_support().staged_outgoing.clone();
+    let emit = &mut |mut event: SagaChoreographyEvent| {
+        if !staged.contains(&event) {
+            clock.stamp(event.context_mut());
+        }
+        emit(event);
+    };
+
+    // Each arm takes the context out of the event instead of cloning it.
+    match event {
+        SagaChoreographyEvent::SagaStarted { context, payload }
+            if participant.depends_on().is_on_saga_start() =>
+        {
+            participant.unlatch_terminal_saga(context.saga_id);
+            participant.clear_saga_state(context.saga_id);
+            participant
+                .dependency_completions()
+                .remove(&context.saga_id);
+            participant.dependency_fired().remove(&context.saga_id);
+            execute_step_wrapper_with_emit_async(participant, context, payload, now, emit).await;
+        }
+        SagaChoreographyEvent::SagaStarted { context, .. } => {
+            participant.unlatch_terminal_saga(context.saga_id);
+            participant.clear_saga_state(context.saga_id);
             participant
                 .dependency_completions()
                 .remove(&context.saga_id);
             participant.dependency_fired().remove(&context.saga_id);
         }
         SagaChoreographyEvent::StepCompleted {
-            context: step_ctx,
+            context,
             output,
             saga_input,
             ..
@@ -188,7 +951,7 @@ pub async fn handle_async_saga_event_with_emit<P, F>(
                 participant,
                 context.saga_id,
                 &dependency_spec,
-                &step_ctx.step_name,
+                &context.step_name,
             );
             if should_fire {
                 let next_context = context.next_step(participant.step_name().into());
@@ -197,611 +960,1071 @@ pub async fn handle_async_saga_event_with_emit<P, F>(
                 } else {
                     output
                 };
-                execute_step_wrapper_with_emit_async(
-                    participant,
-                    next_context,
-                    input,
-                    now,
-                    &mut emit,
-                )
-                .await;
+                execute_step_wrapper_with_emit_async(participant, next_context, input, now, emit)
+                    .await;
             }
         }
         SagaChoreographyEvent::CompensationRequested {
+            context,
             steps_to_compensate,
             ..
         } => {
-            if steps_to_compensate.contains(&participant.step_name().into()) {
-                compensate_wrapper_with_emit_async(participant, &context, now, &mut emit).await;
+            let step_name = participant.step_name();
+            if steps_to_compensate.iter().any(|step| *step == step_name) {
+                compensate_wrapper_with_emit_async(participant, &context, now, emit).await;
             }
         }
-        SagaChoreographyEvent::SagaCompleted { .. } => {
+        SagaChoreographyEvent::SagaCompleted { context } if context.parent_saga_id.is_some() => {
+            hold_completion_for_parent(&context, participant.step_name());
+        }
+        SagaChoreographyEvent::SagaCompleted { context } => {
             participant.latch_terminal_saga(context.saga_id);
             participant.on_saga_completed(&context);
-            participant.prune_saga(context.saga_id);
+            prune_saga_async(participant, context.saga_id).await;
         }
-        SagaChoreographyEvent::SagaFailed { reason, .. } => {
+        SagaChoreographyEvent::SagaFailed {
+            context, reason, ..
+        } => {
             participant.latch_terminal_saga(context.saga_id);
             participant.on_saga_failed(&context, &reason);
-            participant.prune_saga(context.saga_id);
+            prune_saga_async(participant, context.saga_id).await;
         }
-        SagaChoreographyEvent::SagaQuarantined { reason, .. } => {
+        SagaChoreographyEvent::SagaQuarantined {
+            context, reason, ..
+        } => {
             participant.latch_terminal_saga(context.saga_id);
             participant.on_quarantined(&context, &reason);
-            participant.prune_saga(context.saga_id);
+            prune_saga_async(participant, context.saga_id).await;
         }
         _ => {}
     }
 }
 
-fn dependency_should_fire<P>(
-    participant: &mut P,
-    saga_id: SagaId,
-    dependency_spec: &DependencySpec,
-    completed_step: &str,
-) -> bool
+/// Leaves a completed child saga unlatched and unpruned, its compensation
+/// data included, so its parent can still roll it back. The parent releases
+/// it once settled, with a `SagaCompleted` no longer naming the parent; see
+/// [`crate::SubSagaStep`].
+pub(crate) fn hold_completion_for_parent(context: &SagaContext, step_name: &str) {
+    tracing::debug!(
+        target: "core::saga",
+        event = "saga_completion_held_for_parent",
+        saga_id = context.saga_id.get(),
+        parent_saga_id = context.parent_saga_id.map(|parent| parent.get()),
+        step_name
+    );
+}
+
+/// An event awaiting redelivery.
+pub(crate) struct PendingIncoming {
+    pub(crate) saga_id: SagaId,
+    /// `None` for events parked in memory because the inbox could not record
+    /// them.
+    pub(crate) inbox_id: Option<u64>,
+    pub(crate) event: SagaChoreographyEvent,
+}
+
+/// Pending journal inbox entries the reorder buffer does not hold, followed
+/// by events parked in memory.
+pub(crate) fn pending_incoming_events<P>(participant: &mut P) -> Vec<PendingIncoming>
 where
-    P: SagaParticipant + SagaStateExt,
+    P: SagaStateExt,
 {
-    match dependency_spec {
-        DependencySpec::OnSagaStart => false,
-        DependencySpec::After(step) => {
-            if completed_step != *step {
-                return false;
-            }
-            participant.dependency_fired().insert(saga_id)
+    let pending = match participant.saga_journal().pending_incoming() {
+        Ok(entries) => {
+            let admitted = admitted_inbox_entries(participant, entries);
+            unheld_inbox_entries(participant, admitted)
         }
-        DependencySpec::AnyOf(steps) => {
-            if !steps.contains(&completed_step) {
-                return false;
-            }
-            participant.dependency_fired().insert(saga_id)
+        Err(err) => {
+            inbox_replay_read_failed(&err);
+            Vec::new()
         }
-        DependencySpec::AllOf(steps) => {
-            if !steps.contains(&completed_step) {
-                return false;
-            }
-            {
-                let seen = participant
-                    .dependency_completions()
-                    .entry(saga_id)
-                    .or_default();
-                seen.insert(completed_step.into());
-                if !steps.iter().all(|step| seen.contains(*step)) {
-                    return false;
-                }
-            }
-            participant.dependency_fired().insert(saga_id)
+    };
+    with_parked_incoming(participant, pending)
+}
+
+/// [`pending_incoming_events`] for async handlers.
+pub(crate) async fn pending_incoming_events_async<P>(participant: &mut P) -> Vec<PendingIncoming>
+where
+    P: SagaStateExt,
+{
+    let pending = match journal_pending_incoming(participant.saga_journal()).await {
+        Ok(entries) => {
+            let admitted = admitted_inbox_entries_async(participant, entries).await;
+            unheld_inbox_entries(participant, admitted)
         }
-    }
+        Err(err) => {
+            inbox_replay_read_failed(&err);
+            Vec::new()
+        }
+    };
+    with_parked_incoming(participant, pending)
 }
 
-fn dependency_should_fire_async<P>(
+fn inbox_replay_read_failed(err: &crate::JournalError) {
+    tracing::error!(
+        target: "core::saga",
+        event = "saga_inbox_replay_read_failed",
+        error = %err
+    );
+}
+
+fn unheld_inbox_entries<P>(participant: &P, entries: Vec<InboxEntry>) -> Vec<PendingIncoming>
+where
+    P: SagaStateExt,
+{
+    entries
+        .into_iter()
+        .filter(|entry| !participant.saga_support().reorder.holds(entry.inbox_id))
+        .map(|entry| PendingIncoming {
+            saga_id: entry.saga_id,
+            inbox_id: Some(entry.inbox_id),
+            event: entry.event,
+        })
+        .collect()
+}
+
+/// `pending` followed by the events parked in memory.
+fn with_parked_incoming<P>(
     participant: &mut P,
-    saga_id: SagaId,
-    dependency_spec: &DependencySpec,
-    completed_step: &str,
-) -> bool
+    mut pending: Vec<PendingIncoming>,
+) -> Vec<PendingIncoming>
 where
-    P: AsyncSagaParticipant + SagaStateExt,
+    P: SagaStateExt,
 {
-    match dependency_spec {
-        DependencySpec::OnSagaStart => false,
-        DependencySpec::After(step) => {
-            if completed_step != *step {
+    // Steps still rate limited, over the in-flight cap or retrying their
+    // journal append park again and reset these.
+    participant.saga_support_mut().rate_limited_until = None;
+    participant.saga_support_mut().journal_retry_at = None;
+    // Triggers parked by the in-flight cap with an inbox entry are in
+    // `pending` already.
+    let in_flight_parked = std::mem::take(&mut participant.saga_support_mut().in_flight_parked);
+    pending.extend(
+        in_flight_parked
+            .into_iter()
+            .filter(|entry| entry.inbox_id.is_none()),
+    );
+    let parked = std::mem::take(&mut participant.saga_support_mut().parked_events);
+    pending.extend(parked.into_iter().map(|event| PendingIncoming {
+        saga_id: event.context().saga_id,
+        inbox_id: None,
+        event,
+    }));
+    pending
+}
+
+/// Drops the pending inbox entries that duplicate an earlier inbox entry of
+/// their saga, closing them, and marks the dedupe keys of the rest.
+///
+/// Entries of a saga whose inbox history cannot be read stay pending for the
+/// next replay rather than risk dispatching a duplicate. Inbox history is
+/// pruned with its saga, so only duplicates of entries still in the journal
+/// are recognized: a redelivery recorded after its saga was pruned (e.g.
+/// once a restart cleared the terminal latch) replays as a new event.
+fn admitted_inbox_entries<P>(participant: &P, entries: Vec<InboxEntry>) -> Vec<InboxEntry>
+where
+    P: SagaStateExt,
+{
+    let mut histories: HashMap<SagaId, Option<Vec<InboxEntry>>> = HashMap::new();
+    entries
+        .into_iter()
+        .filter(|entry| {
+            let history = histories.entry(entry.saga_id).or_insert_with(|| {
+                let history = participant.saga_journal().incoming_history(entry.saga_id);
+                inbox_history_or_log(entry.saga_id, history)
+            });
+            let Some(history) = history else {
                 return false;
+            };
+            let duplicate = replayed_duplicate(history, entry);
+            if duplicate {
+                participant.mark_incoming_processed(entry.saga_id, Some(entry.inbox_id));
+            } else {
+                let key = participant.saga_support().dedupe_identity.key(&entry.event);
+                participant.check_dedupe(entry.saga_id, key);
             }
-            participant.dependency_fired().insert(saga_id)
+            !duplicate
+        })
+        .collect()
+}
+
+/// [`admitted_inbox_entries`] for async handlers.
+async fn admitted_inbox_entries_async<P>(
+    participant: &P,
+    entries: Vec<InboxEntry>,
+) -> Vec<InboxEntry>
+where
+    P: SagaStateExt,
+{
+    let mut histories: HashMap<SagaId, Option<Vec<InboxEntry>>> = HashMap::new();
+    let mut admitted = Vec::with_capacity(entries.len());
+    for entry in entries {
+        if !histories.contains_key(&entry.saga_id) {
+            let history = journal_incoming_history(participant.saga_journal(), entry.saga_id).await;
+            let history = inbox_history_or_log(entry.saga_id, history);
+            histories.insert(entry.saga_id, history);
         }
-        DependencySpec::AnyOf(steps) => {
-            if !steps.contains(&completed_step) {
-                return false;
-            }
-            participant.dependency_fired().insert(saga_id)
+        let Some(Some(history)) = histories.get(&entry.saga_id) else {
+            continue;
+        };
+        if replayed_duplicate(history, &entry) {
+            mark_incoming_processed_async(participant, entry.saga_id, Some(entry.inbox_id)).await;
+            continue;
         }
-        DependencySpec::AllOf(steps) => {
-            if !steps.contains(&completed_step) {
-                return false;
-            }
-            {
-                let seen = participant
-                    .dependency_completions()
-                    .entry(saga_id)
-                    .or_default();
-                seen.insert(completed_step.into());
-                if !steps.iter().all(|step| seen.contains(*step)) {
-                    return false;
-                }
-            }
-            participant.dependency_fired().insert(saga_id)
+        let key = participant.saga_support().dedupe_identity.key(&entry.event);
+        if let Err(err) = check_dedupe_strict_async(participant, entry.saga_id, key).await {
+            tracing::error!(
+                target: "core::saga",
+                event = "saga_state_dedupe_check_failed",
+                saga_id = entry.saga_id.get(),
+                key = %key,
+                error = ?err
+            );
         }
+        admitted.push(entry);
     }
+    admitted
 }
 
-fn dedupe_key_for_event(event: &SagaChoreographyEvent) -> String {
-    let context = event.context();
-    match event {
-        SagaChoreographyEvent::SagaStarted { .. } => {
-            format!(
-                "{}:{}:{}:{}",
-                context.trace_id,
-                context.saga_started_at_millis,
-                event.event_type(),
-                context.step_name
-            )
-        }
-        SagaChoreographyEvent::StepCompleted { .. }
-        | SagaChoreographyEvent::StepFailed { .. }
-        | SagaChoreographyEvent::CompensationStarted { .. }
-        | SagaChoreographyEvent::CompensationCompleted { .. }
-        | SagaChoreographyEvent::CompensationFailed { .. }
-        | SagaChoreographyEvent::SagaCompleted { .. }
-        | SagaChoreographyEvent::SagaFailed { .. }
-        | SagaChoreographyEvent::SagaQuarantined { .. }
-        | SagaChoreographyEvent::StepStarted { .. }
-        | SagaChoreographyEvent::StepAck { .. } => {
-            format!(
-                "{}:{}:{}:{}",
-                context.trace_id,
-                context.saga_started_at_millis,
-                event.event_type(),
-                context.step_name
-            )
+fn inbox_history_or_log(
+    saga_id: SagaId,
+    history: Result<Vec<InboxEntry>, crate::JournalError>,
+) -> Option<Vec<InboxEntry>> {
+    match history {
+        Ok(history) => Some(history),
+        Err(err) => {
+            tracing::error!(
+                target: "core::saga",
+                event = "saga_inbox_replay_history_read_failed",
+                saga_id = saga_id.get(),
+                error = %err
+            );
+            None
         }
-        SagaChoreographyEvent::CompensationRequested { failed_step, .. } => format!(
-            "{}:{}:{}:{}:{}",
-            context.trace_id,
-            context.saga_started_at_millis,
-            event.event_type(),
-            context.step_name,
-            failed_step
-        ),
     }
 }
 
-fn execute_step_wrapper_with_emit<P, F>(
+/// Whether `entry` carries the dedupe key of an earlier entry of `history`.
+fn replayed_duplicate(history: &[InboxEntry], entry: &InboxEntry) -> bool {
+    let duplicate = history
+        .iter()
+        .any(|earlier| earlier.inbox_id < entry.inbox_id && earlier.dedupe_key == entry.dedupe_key);
+    if duplicate {
+        tracing::debug!(
+            target: "core::saga",
+            event = "saga_inbox_replay_duplicate_dropped",
+            saga_id = entry.saga_id.get(),
+            inbox_id = entry.inbox_id,
+            key = %entry.dedupe_key
+        );
+    }
+    duplicate
+}
+
+/// Outcome of claiming the step lease and journaling a step or compensation
+/// start under the participant's [`JournalFailurePolicy`].
+pub(crate) enum StepStartGate {
+    Proceed,
+    /// Another replica holds the step lease and executes the step. The
+    /// triggering event stays pending in the inbox, so an inbox replay after
+    /// the lease expires takes the step over.
+    Skip,
+    Park,
+    Refuse {
+        reason: Box<str>,
+    },
+}
+
+pub(crate) fn gate_step_start<P>(
     participant: &mut P,
-    context: SagaContext,
-    input: Vec<u8>,
+    context: &SagaContext,
+    step_name: &str,
     now: u64,
-    emit: &mut F,
-) where
-    P: SagaParticipant + SagaStateExt,
-    F: FnMut(SagaChoreographyEvent),
+) -> StepStartGate
+where
+    P: SagaStateExt,
 {
-    let saga_id = context.saga_id;
-
-    // Build state: Idle -> Triggered -> Executing
-    let state = SagaParticipantState::new(
-        saga_id,
-        context.saga_type.clone(),
-        participant.step_name().into(),
-        context.correlation_id,
-        context.trace_id,
-        context.initiator_peer_id,
-        context.saga_started_at_millis,
-    )
-    .trigger("dependency_satisfied", now)
-    .start_execution(now);
-
-    // Persist
-    participant.record_event(
-        saga_id,
-        ParticipantEvent::StepExecutionStarted {
-            attempt: 1,
-            started_at_millis: now,
-        },
-    );
-
-    // Store state
-    participant
-        .saga_states()
-        .insert(saga_id, SagaStateEntry::Executing(state));
-
-    emit(SagaChoreographyEvent::StepStarted {
-        context: context.next_step(participant.step_name().into()),
-    });
-
-    // Execute
-    match participant.execute_step(&context, &input) {
-        Ok(output) => {
-            complete_step(participant, &context, input, output, now, emit);
-        }
-        Err(error) => {
-            fail_step(participant, &context, error, now, emit);
-        }
+    if let Some(gate) = claim_step_lease(participant, context, step_name, now) {
+        return gate;
+    }
+    let started = step_started_entry(context, now);
+    let gate = gate_journal_append(participant, context, step_name, started, now);
+    if matches!(gate, StepStartGate::Park) {
+        // Lets the redelivered trigger satisfy the dependency again.
+        participant
+            .saga_support_mut()
+            .dependency_fired
+            .remove(&context.saga_id);
     }
+    gate
 }
 
-async fn execute_step_wrapper_with_emit_async<P, F>(
+/// [`gate_step_start`] for async handlers.
+pub(crate) async fn gate_step_start_async<P>(
     participant: &mut P,
-    context: SagaContext,
-    input: Vec<u8>,
+    context: &SagaContext,
+    step_name: &str,
     now: u64,
-    emit: &mut F,
-) where
-    P: AsyncSagaParticipant + SagaStateExt,
-    F: FnMut(SagaChoreographyEvent),
+) -> StepStartGate
+where
+    P: SagaStateExt,
 {
-    let saga_id = context.saga_id;
-
-    let state = SagaParticipantState::new(
-        saga_id,
-        context.saga_type.clone(),
-        participant.step_name().into(),
-        context.correlation_id,
-        context.trace_id,
-        context.initiator_peer_id,
-        context.saga_started_at_millis,
-    )
-    .trigger("dependency_satisfied", now)
-    .start_execution(now);
-
-    participant.record_event(
-        saga_id,
-        ParticipantEvent::StepExecutionStarted {
-            attempt: 1,
-            started_at_millis: now,
-        },
-    );
-
-    participant
-        .saga_states()
-        .insert(saga_id, SagaStateEntry::Executing(state));
-
-    emit(SagaChoreographyEvent::StepStarted {
-        context: context.next_step(participant.step_name().into()),
-    });
+    if let Some(gate) = claim_step_lease(participant, context, step_name, now) {
+        return gate;
+    }
+    let started = step_started_entry(context, now);
+    let gate = gate_journal_append_async(participant, context, step_name, started).await;
+    if matches!(gate, StepStartGate::Park) {
+        participant
+            .saga_support_mut()
+            .dependency_fired
+            .remove(&context.saga_id);
+    }
+    gate
+}
 
-    match participant.execute_step(&context, &input).await {
-        Ok(output) => complete_step_async(participant, &context, input, output, now, emit),
-        Err(error) => fail_step_async(participant, &context, error, now, emit),
+fn step_started_entry(context: &SagaContext, now: u64) -> ParticipantEvent {
+    ParticipantEvent::StepExecutionStarted {
+        attempt: context.attempt.saturating_add(1),
+        started_at_millis: now,
     }
 }
 
-/// Complete a step with state transition
-fn complete_step<P, F>(
+/// Journals `entry`, the start of a step or of its compensation, under the
+/// participant's [`JournalFailurePolicy`].
+pub(crate) fn gate_journal_append<P>(
     participant: &mut P,
     context: &SagaContext,
-    saga_input: Vec<u8>,
-    output: StepOutput,
+    step_name: &str,
+    entry: ParticipantEvent,
     now: u64,
-    emit: &mut F,
-) where
-    P: SagaParticipant + SagaStateExt,
-    F: FnMut(SagaChoreographyEvent),
+) -> StepStartGate
+where
+    P: SagaStateExt,
 {
+    let Err(err) = journal_start(participant, context.saga_id, entry) else {
+        return StepStartGate::Proceed;
+    };
     let saga_id = context.saga_id;
-    let (out_data, comp_data, compensation_available) = match output {
-        StepOutput::Completed {
-            output,
-            compensation_data,
-        } => {
-            let compensation_available = !compensation_data.is_empty();
-            (output, compensation_data, compensation_available)
-        }
-        StepOutput::CompletedWithEffect {
-            output,
-            compensation_data,
-            ..
-        } => {
-            let compensation_available = !compensation_data.is_empty();
-            (output, compensation_data, compensation_available)
-        }
+    let JournalFailurePolicy::Retry { attempts, backoff } =
+        participant.saga_support().journal_failure_policy
+    else {
+        return start_journal_failed(participant, context, err);
     };
-
-    // State: Executing -> Completed
-    if let Some(SagaStateEntry::Executing(state)) = participant.saga_states().remove(&saga_id) {
-        let new_state = state.complete(out_data.clone(), comp_data, now);
+    // Blocking here would stall the handler, so a retry parks the trigger and
+    // the inbox replay at `journal_retry_at` runs it.
+    let retry = participant
+        .saga_support()
+        .journal_retries
+        .get(&saga_id)
+        .map_or(1, |retries| retries.saturating_add(1));
+    if retry > attempts {
         participant
-            .saga_states()
-            .insert(saga_id, SagaStateEntry::Completed(new_state));
+            .saga_support_mut()
+            .journal_retries
+            .remove(&saga_id);
+        return start_journal_failed(participant, context, err);
     }
-
-    // Persist
-    let emitted_output = out_data.clone();
-    participant.record_event(
-        saga_id,
-        ParticipantEvent::StepExecutionCompleted {
-            output: out_data,
-            compensation_data: vec![],
-            completed_at_millis: now,
-        },
+    tracing::warn!(
+        target: "core::saga",
+        event = "saga_step_start_journal_retry",
+        saga_id = saga_id.get(),
+        step_name,
+        retry,
+        backoff_ms = backoff.as_millis() as u64,
+        error = ?err
     );
-
-    emit(SagaChoreographyEvent::StepCompleted {
-        context: context.next_step(participant.step_name().into()),
-        output: emitted_output,
-        saga_input,
-        compensation_available,
-    });
+    if let Some(observer) = &participant.saga_support().observer {
+        observer.on_journal_append_retry(context, step_name, retry, backoff);
+    }
+    let retry_at = now.saturating_add(backoff.as_millis() as u64);
+    let support = participant.saga_support_mut();
+    support.journal_retries.insert(saga_id, retry);
+    support.journal_retry_at = Some(
+        support
+            .journal_retry_at
+            .map_or(retry_at, |at| at.min(retry_at)),
+    );
+    park_start(participant)
 }
 
-fn complete_step_async<P, F>(
+/// [`gate_journal_append`] for async handlers, which wait out the
+/// [`JournalFailurePolicy::Retry`] backoff on the runtime timer instead of
+/// parking the trigger.
+pub(crate) async fn gate_journal_append_async<P>(
     participant: &mut P,
     context: &SagaContext,
-    saga_input: Vec<u8>,
-    output: StepOutput,
-    now: u64,
-    emit: &mut F,
-) where
-    P: AsyncSagaParticipant + SagaStateExt,
-    F: FnMut(SagaChoreographyEvent),
+    step_name: &str,
+    entry: ParticipantEvent,
+) -> StepStartGate
+where
+    P: SagaStateExt,
 {
-    let saga_id = context.saga_id;
-    let (out_data, comp_data, compensation_available) = match output {
-        StepOutput::Completed {
-            output,
-            compensation_data,
-        } => {
-            let compensation_available = !compensation_data.is_empty();
-            (output, compensation_data, compensation_available)
-        }
-        StepOutput::CompletedWithEffect {
-            output,
-            compensation_data,
-            ..
-        } => {
-            let compensation_available = !compensation_data.is_empty();
-            (output, compensation_data, compensation_available)
+    let mut retry = 0;
+    loop {
+        let Err(err) = journal_start_async(participant, context.saga_id, entry.clone()).await
+        else {
+            return StepStartGate::Proceed;
+        };
+        match participant.saga_support().journal_failure_policy {
+            JournalFailurePolicy::Retry { attempts, backoff } if retry < attempts => {
+                retry += 1;
+                if let Some(observer) = &participant.saga_support().observer {
+                    observer.on_journal_append_retry(context, step_name, retry, backoff);
+                }
+                tokio::time::sleep(backoff).await;
+            }
+            _ => return start_journal_failed(participant, context, err),
         }
-    };
-
-    if let Some(SagaStateEntry::Executing(state)) = participant.saga_states().remove(&saga_id) {
-        let new_state = state.complete(out_data.clone(), comp_data, now);
-        participant
-            .saga_states()
-            .insert(saga_id, SagaStateEntry::Completed(new_state));
     }
-
-    let emitted_output = out_data.clone();
-    participant.record_event(
-        saga_id,
-        ParticipantEvent::StepExecutionCompleted {
-            output: out_data,
-            compensation_data: vec![],
-            completed_at_millis: now,
-        },
-    );
-
-    emit(SagaChoreographyEvent::StepCompleted {
-        context: context.next_step(participant.step_name().into()),
-        output: emitted_output,
-        saga_input,
-        compensation_available,
-    });
 }
 
-/// Fail a step with state transition
-fn fail_step<P, F>(
+/// Claims the step lease, returning the gate outcome when the step must not
+/// start.
+fn claim_step_lease<P>(
     participant: &mut P,
     context: &SagaContext,
-    error: StepError,
+    step_name: &str,
     now: u64,
-    emit: &mut F,
-) where
-    P: SagaParticipant + SagaStateExt,
-    F: FnMut(SagaChoreographyEvent),
+) -> Option<StepStartGate>
+where
+    P: SagaStateExt,
 {
     let saga_id = context.saga_id;
-    let (reason, requires_comp) = match error {
-        StepError::Terminal { reason } => (reason, false),
-        StepError::RequireCompensation { reason } => (reason, true),
-    };
+    let claimed = participant
+        .saga_support()
+        .step_leases
+        .as_ref()
+        .map(|leases| leases.claim(saga_id, step_name, now));
+    match claimed {
+        None | Some(Ok(_)) => None,
+        Some(Err(StepLeaseError::Held {
+            holder,
+            expires_at_millis,
+        })) => {
+            tracing::info!(
+                target: "core::saga",
+                event = "saga_step_lease_held",
+                saga_id = saga_id.get(),
+                step_name,
+                holder = %holder,
+                expires_at_millis
+            );
+            let support = participant.saga_support_mut();
+            support.park_requested = true;
+            // Lets the replayed trigger satisfy the dependency again.
+            support.dependency_fired.remove(&saga_id);
+            Some(StepStartGate::Skip)
+        }
+        Some(Err(err)) => Some(StepStartGate::Refuse {
+            reason: format!("step_lease_unavailable: {err}").into(),
+        }),
+    }
+}
 
-    // State: Executing -> Failed
-    if let Some(SagaStateEntry::Executing(state)) = participant.saga_states().remove(&saga_id) {
-        let new_state = state.fail(reason.clone(), requires_comp, now);
+fn journal_start<P>(
+    participant: &mut P,
+    saga_id: SagaId,
+    entry: ParticipantEvent,
+) -> Result<(), SagaStateStoreError>
+where
+    P: SagaStateExt,
+{
+    let result = participant.record_event_strict(saga_id, entry);
+    if result.is_ok() {
         participant
-            .saga_states()
-            .insert(saga_id, SagaStateEntry::Failed(new_state));
+            .saga_support_mut()
+            .journal_retries
+            .remove(&saga_id);
     }
-
-    // Persist
-    participant.record_event(
-        saga_id,
-        ParticipantEvent::StepExecutionFailed {
-            error: reason.clone(),
-            requires_compensation: requires_comp,
-            failed_at_millis: now,
-        },
-    );
-
-    emit(SagaChoreographyEvent::StepFailed {
-        context: context.next_step(participant.step_name().into()),
-        participant_id: participant.participant_id_owned(),
-        error_code: None,
-        error: reason,
-        requires_compensation: requires_comp,
-    });
+    result
 }
 
-fn fail_step_async<P, F>(
+async fn journal_start_async<P>(
     participant: &mut P,
-    context: &SagaContext,
-    error: StepError,
-    now: u64,
-    emit: &mut F,
-) where
-    P: AsyncSagaParticipant + SagaStateExt,
-    F: FnMut(SagaChoreographyEvent),
+    saga_id: SagaId,
+    entry: ParticipantEvent,
+) -> Result<(), SagaStateStoreError>
+where
+    P: SagaStateExt,
 {
-    let saga_id = context.saga_id;
-    let (reason, requires_comp) = match error {
-        StepError::Terminal { reason } => (reason, false),
-        StepError::RequireCompensation { reason } => (reason, true),
-    };
-
-    if let Some(SagaStateEntry::Executing(state)) = participant.saga_states().remove(&saga_id) {
-        let new_state = state.fail(reason.clone(), requires_comp, now);
+    let result = record_event_strict_async(participant, saga_id, entry).await;
+    if result.is_ok() {
         participant
-            .saga_states()
-            .insert(saga_id, SagaStateEntry::Failed(new_state));
+            .saga_support_mut()
+            .journal_retries
+            .remove(&saga_id);
     }
-
-    participant.record_event(
-        saga_id,
-        ParticipantEvent::StepExecutionFailed {
-            error: reason.clone(),
-            requires_compensation: requires_comp,
-            failed_at_millis: now,
-        },
-    );
-
-    emit(SagaChoreographyEvent::StepFailed {
-        context: context.next_step(participant.step_name().into()),
-        participant_id: participant.participant_id_owned(),
-        error_code: None,
-        error: reason,
-        requires_compensation: requires_comp,
-    });
+    result
 }
 
-fn compensate_wrapper_with_emit<P, F>(
+/// Maps a start the journal rejected for good onto the participant's
+/// [`JournalFailurePolicy`].
+fn start_journal_failed<P>(
     participant: &mut P,
     context: &SagaContext,
-    now: u64,
-    emit: &mut F,
-) where
-    P: SagaParticipant + SagaStateExt,
-    F: FnMut(SagaChoreographyEvent),
+    err: SagaStateStoreError,
+) -> StepStartGate
+where
+    P: SagaStateExt,
 {
-    let saga_id = context.saga_id;
-
-    // Get compensation data from Completed state
-    if let Some(SagaStateEntry::Completed(state)) = participant.saga_states().remove(&saga_id) {
-        let comp_data = state.state.compensation_data.clone();
+    let policy = participant.saga_support().journal_failure_policy;
+    tracing::error!(
+        target: "core::saga",
+        event = "saga_step_start_journal_failed",
+        saga_id = context.saga_id.get(),
+        policy = ?policy,
+        error = ?err
+    );
+    match policy {
+        JournalFailurePolicy::Continue => StepStartGate::Proceed,
+        JournalFailurePolicy::Park => park_start(participant),
+        JournalFailurePolicy::Retry { .. } | JournalFailurePolicy::Refuse => {
+            StepStartGate::Refuse {
+                reason: format!("journal_unavailable: {err:?}").into(),
+            }
+        }
+    }
+}
 
-        // State: Completed -> Compensating
-        let new_state = state.start_compensation(now);
-        participant
-            .saga_states()
-            .insert(saga_id, SagaStateEntry::Compensating(new_state));
+fn park_start<P>(participant: &mut P) -> StepStartGate
+where
+    P: SagaStateExt,
+{
+    participant.saga_support_mut().park_requested = true;
+    StepStartGate::Park
+}
 
-        // Persist
-        participant.record_event(
-            saga_id,
-            ParticipantEvent::CompensationStarted {
-                attempt: 1,
-                started_at_millis: now,
-            },
-        );
+/// A step or compensation outcome: the journal entry recording it and the
+/// events it publishes. Held in
+/// `SagaParticipantSupport::unjournaled_outcomes` while the journal rejects
+/// the entry.
+pub(crate) struct UnjournaledOutcome {
+    /// Context of the event that triggered the step or compensation.
+    pub(crate) context: SagaContext,
+    pub(crate) step: StepName,
+    pub(crate) participant_id: Box<str>,
+    pub(crate) entry: ParticipantEvent,
+    pub(crate) outgoing: Vec<SagaChoreographyEvent>,
+    /// Appends retried under [`JournalFailurePolicy::Retry`].
+    pub(crate) retries: u32,
+}
 
-        // Execute compensation
-        match participant.compensate_step(context, &comp_data) {
-            Ok(()) => {
-                complete_compensation(participant, context, now, emit);
-            }
-            Err(error) => {
-                fail_compensation(participant, context, error, now, emit);
-            }
+impl UnjournaledOutcome {
+    pub(crate) fn new(
+        context: &SagaContext,
+        step: &str,
+        participant_id: Box<str>,
+        entry: ParticipantEvent,
+        outgoing: Vec<SagaChoreographyEvent>,
+    ) -> Self {
+        Self {
+            context: context.clone(),
+            step: step.into(),
+            participant_id,
+            entry,
+            outgoing,
+            retries: 0,
         }
     }
 }
 
-async fn compensate_wrapper_with_emit_async<P, F>(
+/// Journals a step or compensation outcome under the participant's
+/// [`JournalFailurePolicy`], staging its events with the entry. Returns
+/// whether the entry was journaled and the events to emit now.
+///
+/// `Continue` emits the events of a rejected entry anyway. `Park` and
+/// `Retry` hold them back until [`flush_unjournaled_outcomes`] journals the
+/// entry. `Refuse`, or `Retry` once its attempts run out, quarantines the
+/// saga instead of publishing an outcome recovery cannot see.
+pub(crate) fn journal_outcome<P>(
     participant: &mut P,
-    context: &SagaContext,
+    mut outcome: UnjournaledOutcome,
     now: u64,
-    emit: &mut F,
-) where
-    P: AsyncSagaParticipant + SagaStateExt,
-    F: FnMut(SagaChoreographyEvent),
+) -> (bool, Vec<SagaChoreographyEvent>)
+where
+    P: SagaStateExt,
 {
-    let saga_id = context.saga_id;
+    let saga_id = outcome.context.saga_id;
+    let entry = outcome.entry.clone();
+    match participant.record_event_with_outgoing_strict(saga_id, entry, &mut outcome.outgoing) {
+        Ok(()) => (true, outcome.outgoing),
+        Err(err) => (
+            false,
+            outcome_journal_failed(participant, outcome, err, now),
+        ),
+    }
+}
 
-    if let Some(SagaStateEntry::Completed(state)) = participant.saga_states().remove(&saga_id) {
-        let comp_data = state.state.compensation_data.clone();
+/// [`journal_outcome`] for async handlers.
+pub(crate) async fn journal_outcome_async<P>(
+    participant: &mut P,
+    mut outcome: UnjournaledOutcome,
+    now: u64,
+) -> (bool, Vec<SagaChoreographyEvent>)
+where
+    P: SagaStateExt,
+{
+    let saga_id = outcome.context.saga_id;
+    let entry = outcome.entry.clone();
+    let recorded =
+        record_event_with_outgoing_strict_async(participant, saga_id, entry, &mut outcome.outgoing)
+            .await;
+    match recorded {
+        Ok(()) => (true, outcome.outgoing),
+        Err(err) => (
+            false,
+            outcome_journal_failed(participant, outcome, err, now),
+        ),
+    }
+}
 
-        let new_state = state.start_compensation(now);
+/// Applies the participant's [`JournalFailurePolicy`] to an outcome the
+/// journal rejected; returns the events to emit now.
+fn outcome_journal_failed<P>(
+    participant: &mut P,
+    mut outcome: UnjournaledOutcome,
+    err: SagaStateStoreError,
+    now: u64,
+) -> Vec<SagaChoreographyEvent>
+where
+    P: SagaStateExt,
+{
+    let saga_id = outcome.context.saga_id;
+    let policy = participant.saga_support().journal_failure_policy;
+    tracing::error!(
+        target: "core::saga",
+        event = "saga_outcome_journal_failed",
+        saga_id = saga_id.get(),
+        step_name = %outcome.step,
+        policy = ?policy,
+        retries = outcome.retries,
+        error = ?err
+    );
+    match policy {
+        JournalFailurePolicy::Continue => outcome.outgoing,
+        JournalFailurePolicy::Park => {
+            participant
+                .saga_support_mut()
+                .unjournaled_outcomes
+                .push(outcome);
+            Vec::new()
+        }
+        JournalFailurePolicy::Retry { attempts, backoff } if outcome.retries < attempts => {
+            outcome.retries += 1;
+            if let Some(observer) = &participant.saga_support().observer {
+                observer.on_journal_append_retry(
+                    &outcome.context,
+                    &outcome.step,
+                    outcome.retries,
+                    backoff,
+                );
+            }
+            let retry_at = now.saturating_add(backoff.as_millis() as u64);
+            let support = participant.saga_support_mut();
+            support.journal_retry_at = Some(match support.journal_retry_at {
+                Some(at) if at > now => at.min(retry_at),
+                _ => retry_at,
+            });
+            support.unjournaled_outcomes.push(outcome);
+            Vec::new()
+        }
+        JournalFailurePolicy::Retry { .. } | JournalFailurePolicy::Refuse => {
+            refuse_outcome(participant, outcome, &err, now)
+        }
+    }
+}
+
+/// Quarantines the saga of an outcome the journal rejected for good.
+fn refuse_outcome<P>(
+    participant: &P,
+    outcome: UnjournaledOutcome,
+    err: &SagaStateStoreError,
+    now: u64,
+) -> Vec<SagaChoreographyEvent>
+where
+    P: SagaStateExt,
+{
+    let reason: Box<str> = format!("journal_unavailable: {err:?}").into();
+    tracing::error!(
+        target: "core::saga",
+        event = "saga_outcome_refused",
+        saga_id = outcome.context.saga_id.get(),
+        step_name = %outcome.step,
+        reason = %reason
+    );
+    // A failed compensation reports its own quarantine.
+    if !matches!(outcome.entry, ParticipantEvent::Quarantined { .. }) {
+        let compensation_data = match &outcome.entry {
+            ParticipantEvent::StepExecutionCompleted {
+                compensation_data, ..
+            } => Some(compensation_data.clone()),
+            _ => None,
+        };
         participant
-            .saga_states()
-            .insert(saga_id, SagaStateEntry::Compensating(new_state));
+            .saga_support()
+            .report_quarantined(crate::QuarantinedSaga {
+                context: outcome.context.clone(),
+                step: outcome.step.clone(),
+                participant_id: outcome.participant_id.clone(),
+                reason: reason.clone(),
+                quarantined_at_millis: now,
+                compensation_data,
+                failed_retries: 0,
+            });
+    }
+    vec![SagaChoreographyEvent::SagaQuarantined {
+        context: outcome.context.next_step(outcome.step.clone()),
+        reason,
+        step: outcome.step,
+        participant_id: outcome.participant_id,
+    }]
+}
 
-        participant.record_event(
-            saga_id,
-            ParticipantEvent::CompensationStarted {
-                attempt: 1,
-                started_at_millis: now,
-            },
-        );
+/// Journals the outcomes [`journal_outcome`] held back and emits their
+/// events, once a [`JournalFailurePolicy::Retry`] backoff is due. Outcomes
+/// the journal still rejects are held back again.
+pub(crate) fn flush_unjournaled_outcomes<P, F>(participant: &mut P, emit: &mut F)
+where
+    P: SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let now = participant.now_millis();
+    let support = participant.saga_support_mut();
+    if support.unjournaled_outcomes.is_empty()
+        || support.journal_retry_at.is_some_and(|at| now < at)
+    {
+        return;
+    }
+    let outcomes = std::mem::take(&mut support.unjournaled_outcomes);
+    for outcome in outcomes {
+        let (_, outgoing) = journal_outcome(participant, outcome, now);
+        emit_flushed_outcome(participant, outgoing, emit);
+    }
+}
 
-        match participant.compensate_step(context, &comp_data).await {
-            Ok(()) => complete_compensation_async(participant, context, now, emit),
-            Err(error) => fail_compensation_async(participant, context, error, now, emit),
-        }
+/// [`flush_unjournaled_outcomes`] for async handlers.
+pub(crate) async fn flush_unjournaled_outcomes_async<P, F>(participant: &mut P, emit: &mut F)
+where
+    P: SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let now = participant.now_millis();
+    let support = participant.saga_support_mut();
+    if support.unjournaled_outcomes.is_empty()
+        || support.journal_retry_at.is_some_and(|at| now < at)
+    {
+        return;
+    }
+    let outcomes = std::mem::take(&mut support.unjournaled_outcomes);
+    for outcome in outcomes {
+        let (_, outgoing) = journal_outcome_async(participant, outcome, now).await;
+        emit_flushed_outcome(participant, outgoing, emit);
     }
 }
 
-/// Complete compensation
-fn complete_compensation<P, F>(participant: &mut P, context: &SagaContext, now: u64, emit: &mut F)
+/// Emits the events of a flushed outcome, stamping those not staged.
+fn emit_flushed_outcome<P, F>(participant: &P, outgoing: Vec<SagaChoreographyEvent>, emit: &mut F)
 where
-    P: SagaParticipant + SagaStateExt,
+    P: SagaStateExt,
     F: FnMut(SagaChoreographyEvent),
 {
-    let saga_id = context.saga_id;
+    let support = participant.saga_support();
+    for mut event in outgoing {
+        if !support.staged_outgoing.contains(&event) {
+            support.logical_clock.stamp(event.context_mut());
+        }
+        emit(event);
+    }
+}
 
-    // State: Compensating -> Compensated
-    if let Some(SagaStateEntry::Compensating(state)) = participant.saga_states().remove(&saga_id) {
-        let new_state = state.complete_compensation(now);
-        participant
-            .saga_states()
-            .insert(saga_id, SagaStateEntry::Compensated(new_state));
+/// Prunes terminal entries past `terminal_state_ttl_millis`, at most once
+/// per TTL.
+pub(crate) fn sweep_terminal_states_if_due<P>(participant: &mut P)
+where
+    P: SagaStateExt,
+{
+    let Some(ttl) = participant.saga_support().terminal_state_ttl_millis else {
+        return;
+    };
+    let now = participant.now_millis();
+    if now.saturating_sub(participant.saga_support().terminal_swept_at_millis) < ttl {
+        return;
     }
+    participant.saga_support_mut().terminal_swept_at_millis = now;
+    participant.prune_terminal_older_than(ttl);
+}
 
-    // Persist
-    participant.record_event(
-        saga_id,
-        ParticipantEvent::CompensationCompleted {
-            completed_at_millis: now,
-        },
+/// Caps the offloaded steps running at once at `max_in_flight_steps`. A step
+/// over the cap stays `Triggered` with its trigger parked; the parked triggers
+/// are replayed once a running step settles or times out.
+pub(crate) fn gate_in_flight<P>(participant: &mut P, context: &SagaContext, step_name: &str) -> bool
+where
+    P: SagaStateExt,
+{
+    let support = participant.saga_support();
+    let Some(max) = support.max_in_flight_steps else {
+        return true;
+    };
+    let in_flight = support.offloaded_steps();
+    if in_flight < max {
+        return true;
+    }
+    tracing::info!(
+        target: "core::saga",
+        event = "saga_step_in_flight_capped",
+        saga_id = context.saga_id.get(),
+        step_name,
+        in_flight,
+        max_in_flight = max
     );
+    let support = participant.saga_support_mut();
+    support.park_in_flight = true;
+    support.park_requested = true;
+    support.dependency_fired.remove(&context.saga_id);
+    false
+}
 
-    emit(SagaChoreographyEvent::CompensationCompleted {
-        context: context.next_step(participant.step_name().into()),
-    });
-
-    // Notify
-    participant.on_compensation_completed(context);
+/// Whether steps parked by [`gate_in_flight`] can run again.
+fn in_flight_slot_freed<P>(participant: &P) -> bool
+where
+    P: SagaStateExt,
+{
+    let support = participant.saga_support();
+    !support.in_flight_parked.is_empty()
+        && support
+            .max_in_flight_steps
+            .is_none_or(|max| support.offloaded_steps() < max)
 }
 
-fn complete_compensation_async<P, F>(
+/// Takes a rate limit token for the step. A denied step stays `Triggered`
+/// with its trigger left pending, so the next inbox replay asks again.
+pub(crate) fn gate_rate_limit<P>(
     participant: &mut P,
     context: &SagaContext,
+    step_name: &str,
+    input: &[u8],
     now: u64,
-    emit: &mut F,
-) where
-    P: AsyncSagaParticipant + SagaStateExt,
-    F: FnMut(SagaChoreographyEvent),
+) -> bool
+where
+    P: SagaStateExt,
 {
-    let saga_id = context.saga_id;
+    let Some(gate) = participant.saga_support().rate_limit.clone() else {
+        return true;
+    };
+    let Err(limited) = gate.try_acquire(step_name, context, input, now) else {
+        return true;
+    };
+    tracing::info!(
+        target: "core::saga",
+        event = "saga_step_rate_limited",
+        saga_id = context.saga_id.get(),
+        step_name,
+        key = %limited.key,
+        retry_at_millis = limited.retry_at_millis
+    );
+    let support = participant.saga_support_mut();
+    support.rate_limited_until = Some(
+        support
+            .rate_limited_until
+            .map_or(limited.retry_at_millis, |until| {
+                until.min(limited.retry_at_millis)
+            }),
+    );
+    support.park_requested = true;
+    // Lets the redelivered trigger satisfy the dependency again.
+    support.dependency_fired.remove(&context.saga_id);
+    false
+}
 
-    if let Some(SagaStateEntry::Compensating(state)) = participant.saga_states().remove(&saga_id) {
-        let new_state = state.complete_compensation(now);
-        participant
-            .saga_states()
-            .insert(saga_id, SagaStateEntry::Compensated(new_state));
+/// Holds the lease of a step that finished executing until the saga is
+/// pruned, so replicas replaying the trigger never run it again.
+pub(crate) fn hold_step_lease<P>(participant: &P, saga_id: SagaId, step_name: &str, now: u64)
+where
+    P: SagaStateExt,
+{
+    let Some(leases) = &participant.saga_support().step_leases else {
+        return;
+    };
+    if let Err(err) = leases.hold(saga_id, step_name, now) {
+        tracing::warn!(
+            target: "core::saga",
+            event = "saga_step_lease_hold_failed",
+            saga_id = saga_id.get(),
+            step_name,
+            error = %err
+        );
+    }
+}
+
+/// Copy of `event` to park if handling it gets parked; only taken under
+/// [`JournalFailurePolicy::Park`], with a rate limit gate attached or with
+/// in-flight steps capped.
+pub(crate) fn park_copy<P>(
+    participant: &P,
+    event: &SagaChoreographyEvent,
+) -> Option<SagaChoreographyEvent>
+where
+    P: SagaStateExt,
+{
+    let support = participant.saga_support();
+    (matches!(
+        support.journal_failure_policy,
+        JournalFailurePolicy::Park | JournalFailurePolicy::Retry { .. }
+    ) || support.rate_limit.is_some()
+        || support.max_in_flight_steps.is_some())
+    .then(|| event.clone())
+}
+
+/// Marks an incoming event processed, unless handling parked it. A parked
+/// event stays pending in the inbox, or is held in memory when the inbox
+/// never recorded it; one parked by the in-flight cap also joins the queue
+/// replayed when a slot frees up.
+pub(crate) fn finish_incoming<P>(
+    participant: &mut P,
+    saga_id: SagaId,
+    inbox_id: Option<u64>,
+    parked: Option<SagaChoreographyEvent>,
+) where
+    P: SagaStateExt,
+{
+    if !std::mem::take(&mut participant.saga_support_mut().park_requested) {
+        participant.mark_incoming_processed(saga_id, inbox_id);
+        return;
     }
+    park_incoming(participant, saga_id, inbox_id, parked);
+}
 
-    participant.record_event(
-        saga_id,
-        ParticipantEvent::CompensationCompleted {
-            completed_at_millis: now,
-        },
+/// [`finish_incoming`] for async handlers.
+pub(crate) async fn finish_incoming_async<P>(
+    participant: &mut P,
+    saga_id: SagaId,
+    inbox_id: Option<u64>,
+    parked: Option<SagaChoreographyEvent>,
+) where
+    P: SagaStateExt,
+{
+    if !std::mem::take(&mut participant.saga_support_mut().park_requested) {
+        mark_incoming_processed_async(participant, saga_id, inbox_id).await;
+        return;
+    }
+    park_incoming(participant, saga_id, inbox_id, parked);
+}
+
+fn park_incoming<P>(
+    participant: &mut P,
+    saga_id: SagaId,
+    inbox_id: Option<u64>,
+    parked: Option<SagaChoreographyEvent>,
+) where
+    P: SagaStateExt,
+{
+    tracing::warn!(
+        target: "core::saga",
+        event = "saga_event_parked",
+        saga_id = saga_id.get(),
+        inbox_id
     );
+    let support = participant.saga_support_mut();
+    let in_flight = std::mem::take(&mut support.park_in_flight);
+    match (in_flight, inbox_id, parked) {
+        (true, _, Some(event)) => support.in_flight_parked.push(PendingIncoming {
+            saga_id,
+            inbox_id,
+            event,
+        }),
+        (false, None, Some(event)) => support.parked_events.push(event),
+        _ => {}
+    }
+}
 
-    emit(SagaChoreographyEvent::CompensationCompleted {
-        context: context.next_step(participant.step_name().into()),
-    });
+fn dependency_should_fire<P>(
+    participant: &mut P,
+    saga_id: SagaId,
+    dependency_spec: &DependencySpec,
+    completed_step: &str,
+) -> bool
+where
+    P: SagaParticipant + SagaStateExt,
+{
+    match dependency_spec {
+        DependencySpec::OnSagaStart => false,
+        DependencySpec::After(step) => {
+            if completed_step != *step {
+                return false;
+            }
+            participant.dependency_fired().insert(saga_id)
+        }
+        DependencySpec::AnyOf(steps) => {
+            if !steps.iter().any(|step| *step == completed_step) {
+                return false;
+            }
+            participant.dependency_fired().insert(saga_id)
+        }
+        DependencySpec::AllOf(steps) => {
+            if !steps.iter().any(|step| *step == completed_step) {
+                return false;
+            }
+            {
+                let seen = participant
+                    .dependency_completions()
+                    .entry(saga_id)
+                    .or_default();
+                seen.insert(completed_step.into());
+                if !steps.iter().all(|step| seen.contains(step.as_str())) {
+                    return false;
+                }
+            }
+            participant.dependency_fired().insert(saga_id)
+        }
+    }
+}
 
-    participant.on_compensation_completed(context);
+fn dependency_should_fire_async<P>(
+    participant: &mut P,
+    saga_id: SagaId,
+    dependency_spec: &DependencySpec,
+    completed_step: &str,
+) -> bool
+where
+    P: AsyncSagaParticipant + SagaStateExt,
+{
+    match dependency_spec {
+        DependencySpec::OnSagaStart => false,
+        DependencySpec::After(step) => {
+            if completed_step != *step {
+                return false;
+            }
+            participant.dependency_fired().insert(saga_id)
+        }
+        DependencySpec::AnyOf(steps) => {
+            if !steps.iter().any(|step| *step == completed_step) {
+                return false;
+            }
+            participant.dependency_fired().insert(saga_id)
+        }
+        DependencySpec::AllOf(steps) => {
+            if !steps.iter().any(|step| *step == completed_step) {
+                return false;
+            }
+            {
+                let seen = participant
+                    .dependency_completions()
+                    .entry(saga_id)
+                    .or_default();
+                seen.insert(completed_step.into());
+                if !steps.iter().all(|step| seen.contains(step.as_str())) {
+                    return false;
+                }
+            }
+            participant.dependency_fired().insert(saga_id)
+        }
+    }
 }
 
-/// Fail compensation (quarantine)
-fn fail_compensation<P, F>(
+fn execute_step_wrapper_with_emit<P, F>(
     participant: &mut P,
-    context: &SagaContext,
-    error: CompensationError,
+    context: SagaContext,
+    input: Vec<u8>,
     now: u64,
     emit: &mut F,
 ) where
@@ -809,264 +2032,2868 @@ fn fail_compensation<P, F>(
     F: FnMut(SagaChoreographyEvent),
 {
     let saga_id = context.saga_id;
-    let (reason, is_ambiguous) = match error {
-        CompensationError::SafeToRetry { reason } => (reason, false),
-        CompensationError::Ambiguous { reason } => (reason, true),
-        CompensationError::Terminal { reason } => (reason, false),
-    };
+    let step_name: StepName = participant.step_name().into();
+    if participant
+        .saga_support()
+        .offloaded
+        .as_ref()
+        .is_some_and(|offloaded| offloaded.is_running(saga_id))
+    {
+        return;
+    }
 
-    // State: Compensating -> Quarantined
-    if let Some(SagaStateEntry::Compensating(state)) = participant.saga_states().remove(&saga_id) {
-        let new_state = state.quarantine(reason.clone(), now);
-        participant
-            .saga_states()
-            .insert(saga_id, SagaStateEntry::Quarantined(new_state));
+    // Build state: Idle -> Triggered -> Executing
+    let triggered = SagaParticipantState::new(
+        saga_id,
+        context.saga_type.clone(),
+        step_name.clone(),
+        context.correlation_id,
+        context.trace_id,
+        context.initiator_peer_id,
+        context.saga_started_at_millis,
+    )
+    .trigger("dependency_satisfied", now);
+    if !gate_in_flight(participant, &context, &step_name)
+        || !gate_rate_limit(participant, &context, &step_name, &input, now)
+    {
+        participant.put_saga_state(saga_id, SagaStateEntry::Triggered(triggered));
+        return;
     }
+    let state = triggered.start_execution(now);
 
     // Persist
-    participant.record_event(
-        saga_id,
-        ParticipantEvent::Quarantined {
-            reason: reason.clone(),
-            quarantined_at_millis: now,
-        },
-    );
+    match gate_step_start(participant, &context, &step_name, now) {
+        StepStartGate::Proceed => {}
+        StepStartGate::Skip | StepStartGate::Park => return,
+        StepStartGate::Refuse { reason } => {
+            participant.put_saga_state(saga_id, SagaStateEntry::Executing(state));
+            fail_unstarted_step(
+                participant,
+                &context,
+                StepError::require_compensation(reason),
+                now,
+                emit,
+            );
+            return;
+        }
+    }
 
-    let event_context = context.next_step(participant.step_name().into());
-    emit(SagaChoreographyEvent::CompensationFailed {
-        context: event_context.clone(),
-        participant_id: participant.participant_id_owned(),
-        error: reason.clone(),
-        is_ambiguous,
+    // Store state
+    participant.put_saga_state(saga_id, SagaStateEntry::Executing(state));
+
+    emit(SagaChoreographyEvent::StepStarted {
+        context: context.next_step(participant.step_name().into()),
     });
-    if is_ambiguous {
-        emit(SagaChoreographyEvent::SagaQuarantined {
-            context: event_context,
-            reason: reason.clone(),
-            step: participant.step_name().into(),
-            participant_id: participant.participant_id_owned(),
+
+    let resolved = match resolve_step_input(participant.saga_support().payloads.as_deref(), &input)
+    {
+        Ok(resolved) => resolved,
+        Err(err) => {
+            fail_step(
+                participant,
+                &context,
+                StepError::terminal(err.to_string()),
+                now,
+                emit,
+            );
+            return;
+        }
+    };
+
+    if offload_step(participant, &context, &input, &resolved, now) {
+        return;
+    }
+
+    // Execute
+    #[cfg(feature = "hdr")]
+    let started = std::time::Instant::now();
+    let result = context
+        .step_span(&step_name)
+        .in_scope(|| {
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                participant.execute_step(&context, &resolved)
+            }))
+        })
+        .unwrap_or_else(|payload| {
+            Err(StepError::terminal(contained_panic_reason(
+                participant,
+                &context,
+                &step_name,
+                payload.as_ref(),
+            )))
         });
+    #[cfg(feature = "hdr")]
+    if let Some(latency) = &mut participant.saga_support_mut().step_latency {
+        latency.record(started.elapsed());
+    }
+    hold_step_lease(participant, saga_id, &step_name, now);
+    match result {
+        Ok(output) => {
+            complete_step(participant, &context, input, output, now, emit);
+        }
+        Err(error) => {
+            fail_step(participant, &context, error, now, emit);
+        }
     }
+}
 
-    // Notify
-    participant.on_quarantined(context, &reason);
+/// Hands the step to the support's [`crate::StepExecutor`] if the
+/// participant offloads its work. The step stays `Executing` until
+/// [`settle_offloaded_steps`] picks its result up.
+fn offload_step<P>(
+    participant: &mut P,
+    context: &SagaContext,
+    input: &[u8],
+    resolved: &[u8],
+    now: u64,
+) -> bool
+where
+    P: SagaParticipant + SagaStateExt,
+{
+    if participant.saga_support().offloaded.is_none() {
+        return false;
+    }
+    let span = context.step_span(participant.step_name());
+    let Some(job) = span.in_scope(|| participant.offload_step(context, resolved)) else {
+        return false;
+    };
+    let step_name: StepName = participant.step_name().into();
+    spawn_offloaded_step(participant, context, &step_name, input, job, span, now)
 }
 
-fn fail_compensation_async<P, F>(
+fn offload_step_async<P>(
     participant: &mut P,
     context: &SagaContext,
-    error: CompensationError,
+    input: &[u8],
+    resolved: &[u8],
     now: u64,
-    emit: &mut F,
-) where
+) -> bool
+where
     P: AsyncSagaParticipant + SagaStateExt,
-    F: FnMut(SagaChoreographyEvent),
 {
-    let saga_id = context.saga_id;
-    let (reason, is_ambiguous) = match error {
-        CompensationError::SafeToRetry { reason } => (reason, false),
-        CompensationError::Ambiguous { reason } => (reason, true),
-        CompensationError::Terminal { reason } => (reason, false),
+    if participant.saga_support().offloaded.is_none() {
+        return false;
+    }
+    let span = context.step_span(participant.step_name());
+    let Some(job) = span.in_scope(|| participant.offload_step(context, resolved)) else {
+        return false;
+    };
+    let step_name: StepName = participant.step_name().into();
+    spawn_offloaded_step(participant, context, &step_name, input, job, span, now)
+}
+
+fn spawn_offloaded_step<P>(
+    participant: &mut P,
+    context: &SagaContext,
+    step_name: &str,
+    input: &[u8],
+    job: crate::OffloadedStep,
+    span: tracing::Span,
+    now: u64,
+) -> bool
+where
+    P: SagaStateExt,
+{
+    let job: crate::OffloadedStep = Box::new(move || span.in_scope(job));
+    let Some(offloaded) = participant.saga_support_mut().offloaded.as_mut() else {
+        return false;
     };
+    offloaded.spawn(context.clone(), input.to_vec(), job, now);
+    tracing::debug!(
+        target: "core::saga",
+        event = "saga_step_offloaded",
+        saga_id = context.saga_id.get(),
+        step_name
+    );
+    true
+}
+
+/// Offloaded steps due for settling at `now`, and the step timeout.
+fn take_offloaded_steps<P>(participant: &mut P, now: u64) -> Option<(u64, TakenSteps)>
+where
+    P: SagaStateExt,
+{
+    let offloaded = participant.saga_support_mut().offloaded.as_mut()?;
+    Some((offloaded.timeout_millis, offloaded.take(now)))
+}
+
+/// The outcome to settle an offloaded step with, or `None` when its saga
+/// moved on meanwhile, e.g. was compensated.
+fn offloaded_step_result<P>(
+    participant: &mut P,
+    step: &RunningStep,
+    step_name: &str,
+    result: Option<StepResult>,
+    timeout_millis: u64,
+) -> Option<Result<StepOutput, StepError>>
+where
+    P: SagaStateExt,
+{
+    let saga_id = step.context.saga_id;
+    if !matches!(
+        participant.saga_states().get(&saga_id),
+        Some(SagaStateEntry::Executing(_))
+    ) {
+        tracing::debug!(
+            target: "core::saga",
+            event = "saga_step_offloaded_result_dropped",
+            saga_id = saga_id.get(),
+            step_name
+        );
+        return None;
+    }
+    Some(match result {
+        Some(Ok(result)) => result,
+        Some(Err(payload)) => Err(StepError::terminal(contained_panic_reason(
+            participant,
+            &step.context,
+            step_name,
+            payload.as_ref(),
+        ))),
+        None => {
+            tracing::warn!(
+                target: "core::saga",
+                event = "saga_step_timed_out",
+                saga_id = saga_id.get(),
+                step_name,
+                timeout_millis
+            );
+            Err(StepError::require_compensation(format!(
+                "step did not finish within {timeout_millis}ms"
+            ))
+            .with_code(crate::ReasonCode::Timeout))
+        }
+    })
+}
+
+/// Journals the late success of a step its timeout already failed as the
+/// step's completion and leaves it `Completed`, ready to be compensated.
+/// `StepCompleted` is not emitted: the saga already moved on. Returns
+/// `false` for results that applied nothing.
+fn complete_late_offloaded_step<P>(
+    participant: &mut P,
+    step: &RunningStep,
+    step_name: &str,
+    result: StepResult,
+    now: u64,
+) -> bool
+where
+    P: SagaStateExt,
+{
+    let Some(entry) = late_offloaded_step_completion(participant, step, step_name, result, now)
+    else {
+        return false;
+    };
+    participant.record_event(step.context.saga_id, entry);
+    true
+}
+
+/// [`complete_late_offloaded_step`] for async handlers.
+async fn complete_late_offloaded_step_async<P>(
+    participant: &mut P,
+    step: &RunningStep,
+    step_name: &str,
+    result: StepResult,
+    now: u64,
+) -> bool
+where
+    P: SagaStateExt,
+{
+    let Some(entry) = late_offloaded_step_completion(participant, step, step_name, result, now)
+    else {
+        return false;
+    };
+    try_record_event_async(participant, step.context.saga_id, entry).await;
+    true
+}
+
+/// Leaves a timed-out step whose late result succeeded `Completed` and
+/// returns the journal entry recording it.
+fn late_offloaded_step_completion<P>(
+    participant: &mut P,
+    step: &RunningStep,
+    step_name: &str,
+    result: StepResult,
+    now: u64,
+) -> Option<ParticipantEvent>
+where
+    P: SagaStateExt,
+{
+    let saga_id = step.context.saga_id;
+    let (output, comp_data) = match result {
+        Ok(Ok(
+            StepOutput::Completed {
+                output,
+                compensation_data,
+            }
+            | StepOutput::Partial {
+                output,
+                compensation_data,
+                ..
+            }
+            | StepOutput::CompletedWithEffect {
+                output,
+                compensation_data,
+                ..
+            },
+        )) => (output, compensation_data),
+        _ => {
+            tracing::debug!(
+                target: "core::saga",
+                event = "saga_step_late_result_dropped",
+                saga_id = saga_id.get(),
+                step_name
+            );
+            return None;
+        }
+    };
+    let comp_data = match seal_compensation_data(
+        participant.saga_support().compensation_cipher.as_deref(),
+        comp_data,
+    ) {
+        Ok(sealed) => sealed,
+        Err(err) => {
+            tracing::error!(
+                target: "core::saga",
+                event = "saga_step_late_result_unsealable",
+                saga_id = saga_id.get(),
+                step_name,
+                error = %err
+            );
+            return None;
+        }
+    };
+    tracing::warn!(
+        target: "core::saga",
+        event = "saga_step_late_result_compensating",
+        saga_id = saga_id.get(),
+        step_name
+    );
+    let context = &step.context;
+    let state = SagaParticipantState::new(
+        saga_id,
+        context.saga_type.clone(),
+        step_name.into(),
+        context.correlation_id,
+        context.trace_id,
+        context.initiator_peer_id,
+        context.saga_started_at_millis,
+    )
+    .trigger("late_result", now)
+    .start_execution(now)
+    .complete(output.clone(), comp_data.clone(), now);
+    participant.put_saga_state(saga_id, SagaStateEntry::Completed(state));
+    Some(ParticipantEvent::StepExecutionCompleted {
+        output,
+        compensation_data: comp_data,
+        completed_at_millis: now,
+    })
+}
+
+/// Completes or fails offloaded steps that finished, and fails those past
+/// their timeout. Results of sagas that moved on meanwhile, e.g. were
+/// compensated, are dropped, except a late success of a timed-out step,
+/// which is compensated.
+fn settle_offloaded_steps<P, F>(participant: &mut P, emit: &mut F) -> usize
+where
+    P: SagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let now = participant.now_millis();
+    let Some((timeout_millis, taken)) = take_offloaded_steps(participant, now) else {
+        return 0;
+    };
+    let count = taken.settled.len() + taken.late.len();
+    let step_name: StepName = participant.step_name().into();
+    for (step, result) in taken.settled {
+        let Some(result) =
+            offloaded_step_result(participant, &step, &step_name, result, timeout_millis)
+        else {
+            continue;
+        };
+        let saga_id = step.context.saga_id;
+        hold_step_lease(participant, saga_id, &step_name, now);
+        match result {
+            Ok(output) => complete_step(participant, &step.context, step.input, output, now, emit),
+            Err(error) => fail_step(participant, &step.context, error, now, emit),
+        }
+    }
+    for (step, result) in taken.late {
+        if complete_late_offloaded_step(participant, &step, &step_name, result, now) {
+            compensate_wrapper_with_emit(participant, &step.context, now, emit);
+        }
+    }
+    count
+}
+
+async fn settle_offloaded_steps_async<P, F>(participant: &mut P, emit: &mut F) -> usize
+where
+    P: AsyncSagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let now = participant.now_millis();
+    let Some((timeout_millis, taken)) = take_offloaded_steps(participant, now) else {
+        return 0;
+    };
+    let count = taken.settled.len() + taken.late.len();
+    let step_name: StepName = participant.step_name().into();
+    for (step, result) in taken.settled {
+        let Some(result) =
+            offloaded_step_result(participant, &step, &step_name, result, timeout_millis)
+        else {
+            continue;
+        };
+        let saga_id = step.context.saga_id;
+        hold_step_lease(participant, saga_id, &step_name, now);
+        match result {
+            Ok(output) => {
+                complete_step_async(participant, &step.context, step.input, output, now, emit).await
+            }
+            Err(error) => fail_step_async(participant, &step.context, error, now, emit).await,
+        }
+    }
+    for (step, result) in taken.late {
+        if complete_late_offloaded_step_async(participant, &step, &step_name, result, now).await {
+            compensate_wrapper_with_emit_async(participant, &step.context, now, emit).await;
+        }
+    }
+    count
+}
+
+/// Picks up the results of steps run on the participant's
+/// [`crate::StepExecutor`] and fails those past their timeout; returns how
+/// many were settled. Handling any event does this too, so actors only call
+/// it from a timer, to hear back while no events come in.
+pub fn poll_offloaded_steps_with_emit<P, F>(participant: &mut P, mut emit: F) -> usize
+where
+    P: SagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let settled = settle_offloaded_steps(participant, &mut emit);
+    if settled > 0 && in_flight_slot_freed(participant) {
+        replay_in_flight_parked_with_emit(participant, &mut emit);
+    }
+    settled
+}
+
+/// [`poll_offloaded_steps_with_emit`] for async participants.
+pub async fn poll_offloaded_steps_with_emit_async<P, F>(participant: &mut P, mut emit: F) -> usize
+where
+    P: AsyncSagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let settled = settle_offloaded_steps_async(participant, &mut emit).await;
+    if settled > 0 && in_flight_slot_freed(participant) {
+        replay_async_in_flight_parked_with_emit(participant, &mut emit).await;
+    }
+    settled
+}
+
+async fn execute_step_wrapper_with_emit_async<P, F>(
+    participant: &mut P,
+    context: SagaContext,
+    input: Vec<u8>,
+    now: u64,
+    emit: &mut F,
+) where
+    P: AsyncSagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let saga_id = context.saga_id;
+    let step_name: StepName = participant.step_name().into();
+    if participant
+        .saga_support()
+        .offloaded
+        .as_ref()
+        .is_some_and(|offloaded| offloaded.is_running(saga_id))
+    {
+        return;
+    }
+
+    let triggered = SagaParticipantState::new(
+        saga_id,
+        context.saga_type.clone(),
+        step_name.clone(),
+        context.correlation_id,
+        context.trace_id,
+        context.initiator_peer_id,
+        context.saga_started_at_millis,
+    )
+    .trigger("dependency_satisfied", now);
+    if !gate_in_flight(participant, &context, &step_name)
+        || !gate_rate_limit(participant, &context, &step_name, &input, now)
+    {
+        participant.put_saga_state(saga_id, SagaStateEntry::Triggered(triggered));
+        return;
+    }
+    let state = triggered.start_execution(now);
+
+    match gate_step_start_async(participant, &context, &step_name, now).await {
+        StepStartGate::Proceed => {}
+        StepStartGate::Skip | StepStartGate::Park => return,
+        StepStartGate::Refuse { reason } => {
+            participant.put_saga_state(saga_id, SagaStateEntry::Executing(state));
+            fail_unstarted_step_async(
+                participant,
+                &context,
+                StepError::require_compensation(reason),
+                now,
+                emit,
+            )
+            .await;
+            return;
+        }
+    }
+
+    participant.put_saga_state(saga_id, SagaStateEntry::Executing(state));
+
+    emit(SagaChoreographyEvent::StepStarted {
+        context: context.next_step(participant.step_name().into()),
+    });
+
+    let resolved = match resolve_step_input(participant.saga_support().payloads.as_deref(), &input)
+    {
+        Ok(resolved) => resolved,
+        Err(err) => {
+            fail_step_async(
+                participant,
+                &context,
+                StepError::terminal(err.to_string()),
+                now,
+                emit,
+            )
+            .await;
+            return;
+        }
+    };
+
+    if offload_step_async(participant, &context, &input, &resolved, now) {
+        return;
+    }
+
+    #[cfg(feature = "hdr")]
+    let started = std::time::Instant::now();
+    let span = context.step_span(&step_name);
+    let result = CatchUnwind(
+        participant
+            .execute_step(&context, &resolved)
+            .instrument(span),
+    )
+    .await
+    .unwrap_or_else(|payload| {
+        Err(StepError::terminal(contained_panic_reason(
+            participant,
+            &context,
+            &step_name,
+            payload.as_ref(),
+        )))
+    });
+    #[cfg(feature = "hdr")]
+    if let Some(latency) = &mut participant.saga_support_mut().step_latency {
+        latency.record(started.elapsed());
+    }
+    hold_step_lease(participant, saga_id, &step_name, now);
+    match result {
+        Ok(output) => complete_step_async(participant, &context, input, output, now, emit).await,
+        Err(error) => fail_step_async(participant, &context, error, now, emit).await,
+    }
+}
+
+/// Whether a transition of `context`'s saga expecting `expected` may go
+/// ahead; a missing state entry is handled by the participant's
+/// [`crate::MissingStatePolicy`].
+fn ensure_saga_state<P, F>(
+    participant: &mut P,
+    context: &SagaContext,
+    expected: StateKind,
+    now: u64,
+    emit: &mut F,
+) -> bool
+where
+    P: SagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    if participant.saga_states_ref().contains_key(&context.saga_id) {
+        return true;
+    }
+    let step_name = participant.step_name().to_owned();
+    let participant_id = participant.participant_id_owned();
+    handle_missing_state(
+        participant,
+        context,
+        &step_name,
+        participant_id,
+        expected,
+        now,
+        emit,
+    )
+}
+
+async fn ensure_saga_state_async<P, F>(
+    participant: &mut P,
+    context: &SagaContext,
+    expected: StateKind,
+    now: u64,
+    emit: &mut F,
+) -> bool
+where
+    P: AsyncSagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    if participant.saga_states_ref().contains_key(&context.saga_id) {
+        return true;
+    }
+    let step_name = participant.step_name().to_owned();
+    let participant_id = participant.participant_id_owned();
+    handle_missing_state_async(
+        participant,
+        context,
+        &step_name,
+        participant_id,
+        expected,
+        now,
+        emit,
+    )
+    .await
+}
+
+/// Complete a step with state transition
+fn complete_step<P, F>(
+    participant: &mut P,
+    context: &SagaContext,
+    saga_input: Vec<u8>,
+    output: StepOutput,
+    now: u64,
+    emit: &mut F,
+) where
+    P: SagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let saga_id = context.saga_id;
+    let noop = matches!(output, StepOutput::NoOp);
+    let completion_ratio = output.completion_ratio();
+    let (out_data, comp_data, effect) = match output {
+        StepOutput::Completed {
+            output,
+            compensation_data,
+        }
+        | StepOutput::Partial {
+            output,
+            compensation_data,
+            ..
+        } => (output, compensation_data, None),
+        StepOutput::CompletedWithEffect {
+            output,
+            compensation_data,
+            effect,
+        } => (output, compensation_data, Some(effect)),
+        StepOutput::NoOp => (Vec::new(), Vec::new(), None),
+    };
+    let correlation_keys = participant.correlation_keys(context, &out_data);
+    let compensation_available = !comp_data.is_empty();
+    let comp_data = match seal_compensation_data(
+        participant.saga_support().compensation_cipher.as_deref(),
+        comp_data,
+    ) {
+        Ok(sealed) => sealed,
+        Err(err) => {
+            fail_step(
+                participant,
+                context,
+                StepError::require_compensation(err.to_string()),
+                now,
+                emit,
+            );
+            return;
+        }
+    };
+    let support = participant.saga_support();
+    let out_data = offload_step_output(
+        support.payloads.as_deref(),
+        support.payload_offload_threshold,
+        out_data,
+        saga_id,
+        participant.step_name(),
+    );
+
+    if !ensure_saga_state(participant, context, StateKind::Executing, now, emit) {
+        return;
+    }
+    // State: Executing -> Completed
+    if let Some(SagaStateEntry::Executing(state)) = participant.saga_states().remove(&saga_id) {
+        let new_state = match completion_ratio {
+            _ if noop => state.complete_noop(now),
+            Some(ratio) => state.complete_partial(out_data.clone(), comp_data.clone(), ratio, now),
+            None => state.complete(out_data.clone(), comp_data.clone(), now),
+        };
+        participant.put_saga_state(saga_id, SagaStateEntry::Completed(new_state));
+    }
+
+    // Persist
+    let emitted_output = out_data.clone();
+    let step_completed = SagaChoreographyEvent::StepCompleted {
+        context: context.next_step(participant.step_name().into()),
+        output: emitted_output.clone(),
+        saga_input,
+        compensation_available,
+        completion_ratio,
+    };
+    let outcome = UnjournaledOutcome::new(
+        context,
+        participant.step_name(),
+        participant.participant_id_owned(),
+        ParticipantEvent::StepExecutionCompleted {
+            output: out_data,
+            compensation_data: comp_data,
+            completed_at_millis: now,
+        },
+        vec![step_completed],
+    );
+    let (journaled, outgoing) = journal_outcome(participant, outcome, now);
+    if journaled {
+        index_step_correlations(
+            participant,
+            saga_id,
+            participant.step_name(),
+            effect.as_deref(),
+            correlation_keys,
+        );
+    }
+    if let Some(effect) = effect {
+        dispatch_step_effect(
+            participant,
+            context,
+            participant.step_name(),
+            &effect,
+            &emitted_output,
+            journaled,
+        );
+    }
+
+    for event in outgoing {
+        emit(event);
+    }
+}
+
+async fn complete_step_async<P, F>(
+    participant: &mut P,
+    context: &SagaContext,
+    saga_input: Vec<u8>,
+    output: StepOutput,
+    now: u64,
+    emit: &mut F,
+) where
+    P: AsyncSagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let saga_id = context.saga_id;
+    let noop = matches!(output, StepOutput::NoOp);
+    let completion_ratio = output.completion_ratio();
+    let (out_data, comp_data, effect) = match output {
+        StepOutput::Completed {
+            output,
+            compensation_data,
+        }
+        | StepOutput::Partial {
+            output,
+            compensation_data,
+            ..
+        } => (output, compensation_data, None),
+        StepOutput::CompletedWithEffect {
+            output,
+            compensation_data,
+            effect,
+        } => (output, compensation_data, Some(effect)),
+        StepOutput::NoOp => (Vec::new(), Vec::new(), None),
+    };
+    let correlation_keys = participant.correlation_keys(context, &out_data);
+    let compensation_available = !comp_data.is_empty();
+    let comp_data = match seal_compensation_data(
+        participant.saga_support().compensation_cipher.as_deref(),
+        comp_data,
+    ) {
+        Ok(sealed) => sealed,
+        Err(err) => {
+            fail_step_async(
+                participant,
+                context,
+                StepError::require_compensation(err.to_string()),
+                now,
+                emit,
+            )
+            .await;
+            return;
+        }
+    };
+    let support = participant.saga_support();
+    let out_data = offload_step_output(
+        support.payloads.as_deref(),
+        support.payload_offload_threshold,
+        out_data,
+        saga_id,
+        participant.step_name(),
+    );
+
+    if !ensure_saga_state_async(participant, context, StateKind::Executing, now, emit).await {
+        return;
+    }
+    if let Some(SagaStateEntry::Executing(state)) = participant.saga_states().remove(&saga_id) {
+        let new_state = match completion_ratio {
+            _ if noop => state.complete_noop(now),
+            Some(ratio) => state.complete_partial(out_data.clone(), comp_data.clone(), ratio, now),
+            None => state.complete(out_data.clone(), comp_data.clone(), now),
+        };
+        participant.put_saga_state(saga_id, SagaStateEntry::Completed(new_state));
+    }
+
+    let emitted_output = out_data.clone();
+    let step_completed = SagaChoreographyEvent::StepCompleted {
+        context: context.next_step(participant.step_name().into()),
+        output: emitted_output.clone(),
+        saga_input,
+        compensation_available,
+        completion_ratio,
+    };
+    let outcome = UnjournaledOutcome::new(
+        context,
+        participant.step_name(),
+        participant.participant_id_owned(),
+        ParticipantEvent::StepExecutionCompleted {
+            output: out_data,
+            compensation_data: comp_data,
+            completed_at_millis: now,
+        },
+        vec![step_completed],
+    );
+    let (journaled, outgoing) = journal_outcome_async(participant, outcome, now).await;
+    if journaled {
+        index_step_correlations(
+            participant,
+            saga_id,
+            participant.step_name(),
+            effect.as_deref(),
+            correlation_keys,
+        );
+    }
+    if let Some(effect) = effect {
+        dispatch_step_effect_async(
+            participant,
+            context,
+            participant.step_name(),
+            &effect,
+            &emitted_output,
+            journaled,
+        )
+        .await;
+    }
+
+    for event in outgoing {
+        emit(event);
+    }
+}
+
+/// `SagaFailed` for a terminal (no compensation) `StepFailed` of a critical
+/// step, so the saga fails even when no terminal resolver is watching.
+pub(crate) fn critical_step_saga_failed(
+    step_failed: &SagaChoreographyEvent,
+) -> Option<SagaChoreographyEvent> {
+    let SagaChoreographyEvent::StepFailed {
+        context,
+        participant_id,
+        error_code,
+        error,
+        requires_compensation: false,
+        error_details,
+    } = step_failed
+    else {
+        return None;
+    };
+    tracing::warn!(
+        target: "core::saga",
+        event = "saga_failed_by_critical_step",
+        saga_id = context.saga_id.get(),
+        step_name = %context.step_name,
+        error = %error
+    );
+    Some(SagaChoreographyEvent::SagaFailed {
+        context: context.next_step(crate::TERMINAL_RESOLVER_STEP.into()),
+        reason: error.clone(),
+        failure: Some(crate::SagaFailureDetails {
+            step_name: context.step_name.clone(),
+            participant_id: participant_id.clone(),
+            error_code: error_code.clone(),
+            error_message: error.clone(),
+            error_details: error_details.clone(),
+            at_millis: context.event_timestamp_millis,
+        }),
+    })
+}
+
+/// Fail a step with state transition
+fn fail_step<P, F>(
+    participant: &mut P,
+    context: &SagaContext,
+    error: StepError,
+    now: u64,
+    emit: &mut F,
+) where
+    P: SagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    record_step_failure(participant, context, error, now, emit, true);
+}
+
+/// Fails a step whose start the journal refused. The failure is journaled
+/// on a best-effort basis and always published, as the step never ran.
+fn fail_unstarted_step<P, F>(
+    participant: &mut P,
+    context: &SagaContext,
+    error: StepError,
+    now: u64,
+    emit: &mut F,
+) where
+    P: SagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    record_step_failure(participant, context, error, now, emit, false);
+}
+
+fn record_step_failure<P, F>(
+    participant: &mut P,
+    context: &SagaContext,
+    error: StepError,
+    now: u64,
+    emit: &mut F,
+    apply_journal_policy: bool,
+) where
+    P: SagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let saga_id = context.saga_id;
+    let error_code = error.code().error_code();
+    participant
+        .saga_support()
+        .stats
+        .record_failure(error.code());
+    let (reason, details, requires_comp) = error.into_parts();
+
+    if !ensure_saga_state(participant, context, StateKind::Executing, now, emit) {
+        return;
+    }
+    // State: Executing -> Failed
+    if let Some(SagaStateEntry::Executing(state)) = participant.saga_states().remove(&saga_id) {
+        let new_state = state.fail(reason.clone(), requires_comp, now);
+        participant.put_saga_state(saga_id, SagaStateEntry::Failed(new_state));
+    }
+
+    let step_failed = SagaChoreographyEvent::StepFailed {
+        context: context.next_step(participant.step_name().into()),
+        participant_id: participant.participant_id_owned(),
+        error_code,
+        error: reason.clone(),
+        requires_compensation: requires_comp,
+        error_details: details.clone(),
+    };
+    let saga_failed = participant
+        .is_critical()
+        .then(|| critical_step_saga_failed(&step_failed))
+        .flatten();
+    let outgoing: Vec<_> = std::iter::once(step_failed).chain(saga_failed).collect();
+    let entry = ParticipantEvent::StepExecutionFailed {
+        error: reason,
+        requires_compensation: requires_comp,
+        failed_at_millis: now,
+        details,
+    };
+
+    // Persist
+    let outgoing = if apply_journal_policy {
+        let outcome = UnjournaledOutcome::new(
+            context,
+            participant.step_name(),
+            participant.participant_id_owned(),
+            entry,
+            outgoing,
+        );
+        journal_outcome(participant, outcome, now).1
+    } else {
+        let mut outgoing = outgoing;
+        participant.try_record_event_with_outgoing(saga_id, entry, &mut outgoing);
+        outgoing
+    };
+    for event in outgoing {
+        emit(event);
+    }
+}
+
+async fn fail_step_async<P, F>(
+    participant: &mut P,
+    context: &SagaContext,
+    error: StepError,
+    now: u64,
+    emit: &mut F,
+) where
+    P: AsyncSagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    record_step_failure_async(participant, context, error, now, emit, true).await;
+}
+
+async fn fail_unstarted_step_async<P, F>(
+    participant: &mut P,
+    context: &SagaContext,
+    error: StepError,
+    now: u64,
+    emit: &mut F,
+) where
+    P: AsyncSagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    record_step_failure_async(participant, context, error, now, emit, false).await;
+}
+
+async fn record_step_failure_async<P, F>(
+    participant: &mut P,
+    context: &SagaContext,
+    error: StepError,
+    now: u64,
+    emit: &mut F,
+    apply_journal_policy: bool,
+) where
+    P: AsyncSagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let saga_id = context.saga_id;
+    let error_code = error.code().error_code();
+    participant
+        .saga_support()
+        .stats
+        .record_failure(error.code());
+    let (reason, details, requires_comp) = error.into_parts();
+
+    if !ensure_saga_state_async(participant, context, StateKind::Executing, now, emit).await {
+        return;
+    }
+    if let Some(SagaStateEntry::Executing(state)) = participant.saga_states().remove(&saga_id) {
+        let new_state = state.fail(reason.clone(), requires_comp, now);
+        participant.put_saga_state(saga_id, SagaStateEntry::Failed(new_state));
+    }
+
+    let step_failed = SagaChoreographyEvent::StepFailed {
+        context: context.next_step(participant.step_name().into()),
+        participant_id: participant.participant_id_owned(),
+        error_code,
+        error: reason.clone(),
+        requires_compensation: requires_comp,
+        error_details: details.clone(),
+    };
+    let saga_failed = participant
+        .is_critical()
+        .then(|| critical_step_saga_failed(&step_failed))
+        .flatten();
+    let outgoing: Vec<_> = std::iter::once(step_failed).chain(saga_failed).collect();
+    let entry = ParticipantEvent::StepExecutionFailed {
+        error: reason,
+        requires_compensation: requires_comp,
+        failed_at_millis: now,
+        details,
+    };
+
+    let outgoing = if apply_journal_policy {
+        let outcome = UnjournaledOutcome::new(
+            context,
+            participant.step_name(),
+            participant.participant_id_owned(),
+            entry,
+            outgoing,
+        );
+        journal_outcome_async(participant, outcome, now).await.1
+    } else {
+        let mut outgoing = outgoing;
+        try_record_event_with_outgoing_async(participant, saga_id, entry, &mut outgoing).await;
+        outgoing
+    };
+    for event in outgoing {
+        emit(event);
+    }
+}
+
+fn compensate_wrapper_with_emit<P, F>(
+    participant: &mut P,
+    context: &SagaContext,
+    now: u64,
+    emit: &mut F,
+) where
+    P: SagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let saga_id = context.saga_id;
+
+    // Get compensation data from Completed state
+    if !ensure_saga_state(participant, context, StateKind::Completed, now, emit) {
+        return;
+    }
+    if let Some(SagaStateEntry::Completed(state)) = participant.saga_states().remove(&saga_id) {
+        if !state.state.compensatable {
+            tracing::debug!(
+                target: "core::saga",
+                event = "saga_noop_compensation_skipped",
+                saga_id = saga_id.get(),
+                step_name = participant.step_name()
+            );
+            participant.put_saga_state(saga_id, SagaStateEntry::Completed(state));
+            return;
+        }
+        let comp_data = state.state.compensation_data.clone();
+
+        // Persist
+        let step_name: StepName = participant.step_name().into();
+        let started = ParticipantEvent::CompensationStarted {
+            attempt: 1,
+            started_at_millis: now,
+        };
+        let gate = gate_journal_append(participant, context, &step_name, started, now);
+        if matches!(gate, StepStartGate::Skip | StepStartGate::Park) {
+            participant.put_saga_state(saga_id, SagaStateEntry::Completed(state));
+            return;
+        }
+
+        // State: Completed -> Compensating
+        let new_state = state.start_compensation(now);
+        participant.put_saga_state(saga_id, SagaStateEntry::Compensating(new_state));
+
+        if let StepStartGate::Refuse { reason } = gate {
+            let error = CompensationError::safe_to_retry(reason);
+            fail_compensation(participant, context, error, &comp_data, now, emit);
+            return;
+        }
+        run_compensation(participant, context, &comp_data, now, emit);
+    }
+}
+
+/// Runs `compensate_step` for a saga already moved to `Compensating` and
+/// records the outcome.
+fn run_compensation<P, F>(
+    participant: &mut P,
+    context: &SagaContext,
+    comp_data: &[u8],
+    now: u64,
+    emit: &mut F,
+) where
+    P: SagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let saga_id = context.saga_id;
+    #[cfg(feature = "hdr")]
+    let started = std::time::Instant::now();
+    let result = if compensation_already_done(participant, saga_id, participant.step_name()) {
+        Ok(())
+    } else {
+        let span = context.step_span(participant.step_name());
+        let result = match open_compensation_data(
+            participant.saga_support().compensation_cipher.as_deref(),
+            comp_data,
+        ) {
+            Ok(comp_data) => span
+                .in_scope(|| {
+                    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                        participant.compensate_step(context, &comp_data)
+                    }))
+                })
+                .unwrap_or_else(|payload| {
+                    let step_name = participant.step_name().to_owned();
+                    Err(CompensationError::terminal(contained_panic_reason(
+                        participant,
+                        context,
+                        &step_name,
+                        payload.as_ref(),
+                    )))
+                }),
+            Err(err) => Err(CompensationError::terminal(err.to_string())),
+        };
+        if result.is_ok() {
+            mark_compensation_done(participant, saga_id, participant.step_name());
+        }
+        result
+    };
+    #[cfg(feature = "hdr")]
+    if let Some(latency) = &mut participant.saga_support_mut().compensation_latency {
+        latency.record(started.elapsed());
+    }
+    match result {
+        Ok(()) => {
+            complete_compensation(participant, context, now, emit);
+        }
+        Err(error) => {
+            fail_compensation(participant, context, error, comp_data, now, emit);
+        }
+    }
+}
+
+/// Re-attempts the compensation of a quarantined saga, e.g. after the
+/// external issue that made it fail was fixed.
+///
+/// The saga moves from `Quarantined` back to `Compensating` (journaled as
+/// the next `CompensationStarted` attempt) and `compensate_step` runs again
+/// with the compensation data of the completed step: kept on the
+/// `Quarantined` state, else journaled, else reported to the attached
+/// [`crate::QuarantineManager`]. A saga already pruned from memory is
+/// retried as long as its journal still ends in a quarantine.
+///
+/// Returns the events to publish: `CompensationCompleted`, or
+/// `CompensationFailed` when the saga is quarantined again. Unless the
+/// [`JournalFailurePolicy`] is `Continue`, a retry whose start the journal
+/// rejects fails with [`QuarantineError::Journal`] and leaves the saga
+/// quarantined.
+pub fn retry_compensation<P>(
+    participant: &mut P,
+    saga_id: SagaId,
+) -> Result<Vec<SagaChoreographyEvent>, QuarantineError>
+where
+    P: SagaParticipant + SagaStateExt,
+{
+    let quarantined = match participant.saga_states_ref().get(&saga_id) {
+        Some(SagaStateEntry::Quarantined(state)) => Some(state.clone()),
+        Some(_) => return Err(QuarantineError::NotQuarantined(saga_id.get())),
+        None => None,
+    };
+    let entries = participant.saga_journal().read(saga_id)?;
+    if quarantined.is_none()
+        && !last_progress_entry(&entries)
+            .is_some_and(|entry| matches!(entry.event, ParticipantEvent::Quarantined { .. }))
+    {
+        return Err(QuarantineError::NotQuarantined(saga_id.get()));
+    }
+    let history = SagaJournalHistory::from_entries(saga_id, &entries);
+    let reported = || {
+        participant
+            .saga_support()
+            .quarantine
+            .as_ref()?
+            .inspect(saga_id, participant.step_name())?
+            .compensation_data
+    };
+    let comp_data = quarantined
+        .as_ref()
+        .map(|state| state.state.compensation_data.clone())
+        .filter(|data| !data.is_empty())
+        .or_else(|| history.compensation_data().map(<[u8]>::to_vec))
+        .filter(|data| !data.is_empty())
+        .or_else(reported)
+        .ok_or(QuarantineError::MissingCompensationData(saga_id.get()))?;
+    let context = match participant.saga_journal().incoming_history(saga_id)?.pop() {
+        Some(entry) => entry.event.into_context().for_compensation(),
+        None => {
+            let mut context = SagaContext::start(
+                saga_id,
+                history.registration.as_ref().map_or_else(
+                    || "unknown".into(),
+                    |registration| registration.saga_type.clone(),
+                ),
+                participant.step_name().into(),
+                [0; 32],
+            );
+            if let Some(state) = &quarantined {
+                context.saga_type = state.saga_type.clone();
+                context.correlation_id = state.correlation_id;
+                context.initiator_peer_id = state.initiator_peer_id;
+                context.saga_started_at_millis = state.saga_started_at_millis;
+            }
+            context
+        }
+    };
+
+    let now = participant.now_millis();
+    let attempt = history
+        .compensations
+        .last()
+        .map_or(1, |compensation| compensation.attempt + 1);
+    let compensating = match quarantined {
+        Some(state) => state.retry_compensation(attempt, now),
+        None => SagaParticipantState {
+            saga_id,
+            saga_type: context.saga_type.clone(),
+            step_name: participant.step_name().into(),
+            correlation_id: context.correlation_id,
+            trace_id: context.trace_id,
+            initiator_peer_id: context.initiator_peer_id,
+            saga_started_at_millis: context.saga_started_at_millis,
+            last_updated_at_millis: now,
+            state: Compensating {
+                started_at_millis: now,
+                attempt,
+            },
+            events: Vec::new(),
+        },
+    };
+    let started = ParticipantEvent::CompensationStarted {
+        attempt,
+        started_at_millis: now,
+    };
+    if let Err(err) = participant.saga_journal().append(saga_id, started) {
+        // Only `Continue` compensates without a journaled start; otherwise
+        // the saga stays quarantined for a retry once the journal recovers.
+        if participant.saga_support().journal_failure_policy != JournalFailurePolicy::Continue {
+            return Err(err.into());
+        }
+        tracing::error!(
+            target: "core::saga",
+            event = "saga_state_journal_append_failed",
+            saga_id = saga_id.get(),
+            error = ?err
+        );
+    }
+    participant.put_saga_state(saga_id, SagaStateEntry::Compensating(compensating));
+    tracing::info!(
+        target: "core::saga",
+        event = "saga_compensation_retried",
+        saga_id = saga_id.get(),
+        step_name = participant.step_name(),
+        attempt
+    );
+
+    let mut emitted = Vec::new();
+    run_compensation(participant, &context, &comp_data, now, &mut |event| {
+        emitted.push(event)
+    });
+    if matches!(
+        participant.saga_states_ref().get(&saga_id),
+        Some(SagaStateEntry::Compensated(_))
+    ) {
+        if let Some(manager) = &participant.saga_support().quarantine {
+            manager.close_retried(
+                saga_id,
+                participant.step_name(),
+                participant.participant_id(),
+            );
+        }
+    }
+    Ok(emitted)
+}
+
+async fn compensate_wrapper_with_emit_async<P, F>(
+    participant: &mut P,
+    context: &SagaContext,
+    now: u64,
+    emit: &mut F,
+) where
+    P: AsyncSagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let saga_id = context.saga_id;
+
+    if !ensure_saga_state_async(participant, context, StateKind::Completed, now, emit).await {
+        return;
+    }
+    if let Some(SagaStateEntry::Completed(state)) = participant.saga_states().remove(&saga_id) {
+        if !state.state.compensatable {
+            tracing::debug!(
+                target: "core::saga",
+                event = "saga_noop_compensation_skipped",
+                saga_id = saga_id.get(),
+                step_name = participant.step_name()
+            );
+            participant.put_saga_state(saga_id, SagaStateEntry::Completed(state));
+            return;
+        }
+        let comp_data = state.state.compensation_data.clone();
+
+        let step_name: StepName = participant.step_name().into();
+        let started = ParticipantEvent::CompensationStarted {
+            attempt: 1,
+            started_at_millis: now,
+        };
+        let gate = gate_journal_append_async(participant, context, &step_name, started).await;
+        if matches!(gate, StepStartGate::Skip | StepStartGate::Park) {
+            participant.put_saga_state(saga_id, SagaStateEntry::Completed(state));
+            return;
+        }
+
+        let new_state = state.start_compensation(now);
+        participant.put_saga_state(saga_id, SagaStateEntry::Compensating(new_state));
+
+        if let StepStartGate::Refuse { reason } = gate {
+            let error = CompensationError::safe_to_retry(reason);
+            fail_compensation_async(participant, context, error, &comp_data, now, emit).await;
+            return;
+        }
+
+        #[cfg(feature = "hdr")]
+        let started = std::time::Instant::now();
+        let result = if compensation_already_done_async(participant, saga_id, &step_name).await {
+            Ok(())
+        } else {
+            let span = context.step_span(participant.step_name());
+            let result = match open_compensation_data(
+                participant.saga_support().compensation_cipher.as_deref(),
+                &comp_data,
+            ) {
+                Ok(comp_data) => CatchUnwind(
+                    participant
+                        .compensate_step(context, &comp_data)
+                        .instrument(span),
+                )
+                .await
+                .unwrap_or_else(|payload| {
+                    let step_name = participant.step_name().to_owned();
+                    Err(CompensationError::terminal(contained_panic_reason(
+                        participant,
+                        context,
+                        &step_name,
+                        payload.as_ref(),
+                    )))
+                }),
+                Err(err) => Err(CompensationError::terminal(err.to_string())),
+            };
+            if result.is_ok() {
+                mark_compensation_done_async(participant, saga_id, &step_name).await;
+            }
+            result
+        };
+        #[cfg(feature = "hdr")]
+        if let Some(latency) = &mut participant.saga_support_mut().compensation_latency {
+            latency.record(started.elapsed());
+        }
+        match result {
+            Ok(()) => complete_compensation_async(participant, context, now, emit).await,
+            Err(error) => {
+                fail_compensation_async(participant, context, error, &comp_data, now, emit).await
+            }
+        }
+    }
+}
+
+/// Complete compensation
+fn complete_compensation<P, F>(participant: &mut P, context: &SagaContext, now: u64, emit: &mut F)
+where
+    P: SagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let saga_id = context.saga_id;
+
+    if !ensure_saga_state(participant, context, StateKind::Compensating, now, emit) {
+        return;
+    }
+    // State: Compensating -> Compensated
+    if let Some(SagaStateEntry::Compensating(state)) = participant.saga_states().remove(&saga_id) {
+        let new_state = state.complete_compensation(now);
+        participant.put_saga_state(saga_id, SagaStateEntry::Compensated(new_state));
+    }
+
+    let compensation_completed = SagaChoreographyEvent::CompensationCompleted {
+        context: context.next_step(participant.step_name().into()),
+    };
+    // Persist
+    let outcome = UnjournaledOutcome::new(
+        context,
+        participant.step_name(),
+        participant.participant_id_owned(),
+        ParticipantEvent::CompensationCompleted {
+            completed_at_millis: now,
+        },
+        vec![compensation_completed],
+    );
+    for event in journal_outcome(participant, outcome, now).1 {
+        emit(event);
+    }
+
+    // Notify
+    participant.on_compensation_completed(context);
+}
+
+async fn complete_compensation_async<P, F>(
+    participant: &mut P,
+    context: &SagaContext,
+    now: u64,
+    emit: &mut F,
+) where
+    P: AsyncSagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let saga_id = context.saga_id;
+
+    if !ensure_saga_state_async(participant, context, StateKind::Compensating, now, emit).await {
+        return;
+    }
+    if let Some(SagaStateEntry::Compensating(state)) = participant.saga_states().remove(&saga_id) {
+        let new_state = state.complete_compensation(now);
+        participant.put_saga_state(saga_id, SagaStateEntry::Compensated(new_state));
+    }
+
+    let compensation_completed = SagaChoreographyEvent::CompensationCompleted {
+        context: context.next_step(participant.step_name().into()),
+    };
+    let outcome = UnjournaledOutcome::new(
+        context,
+        participant.step_name(),
+        participant.participant_id_owned(),
+        ParticipantEvent::CompensationCompleted {
+            completed_at_millis: now,
+        },
+        vec![compensation_completed],
+    );
+    for event in journal_outcome_async(participant, outcome, now).await.1 {
+        emit(event);
+    }
+
+    participant.on_compensation_completed(context);
+}
+
+/// Fail compensation (quarantine)
+fn fail_compensation<P, F>(
+    participant: &mut P,
+    context: &SagaContext,
+    error: CompensationError,
+    comp_data: &[u8],
+    now: u64,
+    emit: &mut F,
+) where
+    P: SagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let saga_id = context.saga_id;
+    participant
+        .saga_support()
+        .stats
+        .record_failure(error.code());
+    let (reason, details, is_ambiguous) = error.into_parts();
+
+    if !ensure_saga_state(participant, context, StateKind::Compensating, now, emit) {
+        return;
+    }
+    // State: Compensating -> Quarantined
+    if let Some(SagaStateEntry::Compensating(state)) = participant.saga_states().remove(&saga_id) {
+        let new_state = state
+            .quarantine(reason.clone(), now)
+            .with_compensation_data(comp_data.to_vec());
+        participant.put_saga_state(saga_id, SagaStateEntry::Quarantined(new_state));
+    }
+
+    let event_context = context.next_step(participant.step_name().into());
+    let mut outgoing = vec![SagaChoreographyEvent::CompensationFailed {
+        context: event_context.clone(),
+        participant_id: participant.participant_id_owned(),
+        error: reason.clone(),
+        is_ambiguous,
+        error_details: details,
+    }];
+    if is_ambiguous {
+        outgoing.push(SagaChoreographyEvent::SagaQuarantined {
+            context: event_context,
+            reason: reason.clone(),
+            step: participant.step_name().into(),
+            participant_id: participant.participant_id_owned(),
+        });
+    }
+
+    // Persist
+    let outcome = UnjournaledOutcome::new(
+        context,
+        participant.step_name(),
+        participant.participant_id_owned(),
+        ParticipantEvent::Quarantined {
+            reason: reason.clone(),
+            quarantined_at_millis: now,
+        },
+        outgoing,
+    );
+    let outgoing = journal_outcome(participant, outcome, now).1;
+    participant
+        .saga_support()
+        .report_quarantined(crate::QuarantinedSaga {
+            context: context.clone(),
+            step: participant.step_name().into(),
+            participant_id: participant.participant_id_owned(),
+            reason: reason.clone(),
+            quarantined_at_millis: now,
+            compensation_data: Some(comp_data.to_vec()),
+            failed_retries: 0,
+        });
+
+    for event in outgoing {
+        emit(event);
+    }
+
+    // Notify
+    participant.on_quarantined(context, &reason);
+}
+
+async fn fail_compensation_async<P, F>(
+    participant: &mut P,
+    context: &SagaContext,
+    error: CompensationError,
+    comp_data: &[u8],
+    now: u64,
+    emit: &mut F,
+) where
+    P: AsyncSagaParticipant + SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let saga_id = context.saga_id;
+    participant
+        .saga_support()
+        .stats
+        .record_failure(error.code());
+    let (reason, details, is_ambiguous) = error.into_parts();
+
+    if !ensure_saga_state_async(participant, context, StateKind::Compensating, now, emit).await {
+        return;
+    }
+    if let Some(SagaStateEntry::Compensating(state)) = participant.saga_states().remove(&saga_id) {
+        let new_state = state
+            .quarantine(reason.clone(), now)
+            .with_compensation_data(comp_data.to_vec());
+        participant.put_saga_state(saga_id, SagaStateEntry::Quarantined(new_state));
+    }
+
+    let event_context = context.next_step(participant.step_name().into());
+    let mut outgoing = vec![SagaChoreographyEvent::CompensationFailed {
+        context: event_context.clone(),
+        participant_id: participant.participant_id_owned(),
+        error: reason.clone(),
+        is_ambiguous,
+        error_details: details,
+    }];
+    if is_ambiguous {
+        outgoing.push(SagaChoreographyEvent::SagaQuarantined {
+            context: event_context,
+            reason: reason.clone(),
+            step: participant.step_name().into(),
+            participant_id: participant.participant_id_owned(),
+        });
+    }
+
+    let outcome = UnjournaledOutcome::new(
+        context,
+        participant.step_name(),
+        participant.participant_id_owned(),
+        ParticipantEvent::Quarantined {
+            reason: reason.clone(),
+            quarantined_at_millis: now,
+        },
+        outgoing,
+    );
+    let outgoing = journal_outcome_async(participant, outcome, now).await.1;
+    participant
+        .saga_support()
+        .report_quarantined(crate::QuarantinedSaga {
+            context: context.clone(),
+            step: participant.step_name().into(),
+            participant_id: participant.participant_id_owned(),
+            reason: reason.clone(),
+            quarantined_at_millis: now,
+            compensation_data: Some(comp_data.to_vec()),
+            failed_retries: 0,
+        });
+
+    for event in outgoing {
+        emit(event);
+    }
+
+    participant.on_quarantined(context, &reason);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        DeterministicContextBuilder, HasSagaParticipantSupport, InMemoryDedupe, InMemoryJournal,
+        InboxEntry, JournalEntry, JournalError, OutboxEntry, SagaContext, SagaParticipantSupport,
+    };
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use super::*;
+
+    #[derive(Clone, Copy)]
+    enum ExecuteMode {
+        Completed,
+        CompletedWithEffect,
+        Partial,
+        NoOp,
+        TerminalFail,
+        Panic,
+    }
+
+    /// In-memory journal whose participant-event appends, outcome appends
+    /// alone, and inbox history reads can be made to fail.
+    #[derive(Default)]
+    struct FlakyJournal {
+        inner: InMemoryJournal,
+        fail_appends: AtomicBool,
+        /// Rejects every append but step and compensation starts.
+        fail_outcomes: AtomicBool,
+        fail_history_reads: AtomicBool,
+    }
+
+    impl ParticipantJournal for FlakyJournal {
+        fn append(&self, saga_id: SagaId, event: ParticipantEvent) -> Result<u64, JournalError> {
+            let start = matches!(
+                event,
+                ParticipantEvent::StepExecutionStarted { .. }
+                    | ParticipantEvent::CompensationStarted { .. }
+            );
+            if self.fail_appends.load(Ordering::Relaxed)
+                || (!start && self.fail_outcomes.load(Ordering::Relaxed))
+            {
+                return Err(JournalError::Storage("disk full".into()));
+            }
+            self.inner.append(saga_id, event)
+        }
+
+        fn read(&self, saga_id: SagaId) -> Result<Vec<JournalEntry>, JournalError> {
+            self.inner.read(saga_id)
+        }
+
+        fn list_sagas(&self) -> Result<Vec<SagaId>, JournalError> {
+            self.inner.list_sagas()
+        }
+
+        fn prune(&self, saga_id: SagaId) -> Result<(), JournalError> {
+            self.inner.prune(saga_id)
+        }
+
+        fn record_outgoing(
+            &self,
+            saga_id: SagaId,
+            event: &SagaChoreographyEvent,
+        ) -> Result<Option<u64>, JournalError> {
+            self.inner.record_outgoing(saga_id, event)
+        }
+
+        fn pending_outgoing(&self) -> Result<Vec<OutboxEntry>, JournalError> {
+            self.inner.pending_outgoing()
+        }
+
+        fn mark_outgoing_sent(&self, outbox_id: u64) -> Result<(), JournalError> {
+            self.inner.mark_outgoing_sent(outbox_id)
+        }
+
+        fn record_incoming(
+            &self,
+            saga_id: SagaId,
+            dedupe_key: DedupeKey,
+            event: &SagaChoreographyEvent,
+        ) -> Result<Option<u64>, JournalError> {
+            self.inner.record_incoming(saga_id, dedupe_key, event)
+        }
+
+        fn pending_incoming(&self) -> Result<Vec<InboxEntry>, JournalError> {
+            self.inner.pending_incoming()
+        }
+
+        fn mark_incoming_processed(&self, inbox_id: u64) -> Result<(), JournalError> {
+            self.inner.mark_incoming_processed(inbox_id)
+        }
+
+        fn incoming_history(&self, saga_id: SagaId) -> Result<Vec<InboxEntry>, JournalError> {
+            if self.fail_history_reads.load(Ordering::Relaxed) {
+                return Err(JournalError::Storage("read failed".into()));
+            }
+            self.inner.incoming_history(saga_id)
+        }
+    }
+
+    struct TestParticipant {
+        saga: SagaParticipantSupport<FlakyJournal, InMemoryDedupe>,
+        execute_mode: ExecuteMode,
+        compensation_error: Option<CompensationError>,
+        executed: usize,
+        observed_inputs: Vec<Vec<u8>>,
+        compensated_with: Vec<Vec<u8>>,
+        /// Span current in each `execute_step` and `compensate_step` call.
+        entered_spans: Vec<Option<&'static str>>,
+        trusted_compensation_peer: Option<crate::PeerId>,
+        supported_workflow_version: Option<u32>,
+        dependency_spec: DependencySpec,
+        critical: bool,
+        /// Hands the step to the support's executor instead of running it.
+        offload: bool,
+    }
+
+    impl Default for TestParticipant {
+        fn default() -> Self {
+            Self {
+                saga: SagaParticipantSupport::new(FlakyJournal::default(), InMemoryDedupe::new()),
+                execute_mode: ExecuteMode::Completed,
+                compensation_error: None,
+                executed: 0,
+                observed_inputs: Vec::new(),
+                compensated_with: Vec::new(),
+                entered_spans: Vec::new(),
+                trusted_compensation_peer: None,
+                supported_workflow_version: None,
+                dependency_spec: DependencySpec::OnSagaStart,
+                critical: false,
+                offload: false,
+            }
+        }
+    }
+
+    impl HasSagaParticipantSupport for TestParticipant {
+        type Journal = FlakyJournal;
+        type Dedupe = InMemoryDedupe;
+
+        fn saga_support(&self) -> &SagaParticipantSupport<Self::Journal, Self::Dedupe> {
+            &self.saga
+        }
+
+        fn saga_support_mut(&mut self) -> &mut SagaParticipantSupport<Self::Journal, Self::Dedupe> {
+            &mut self.saga
+        }
+    }
+
+    impl SagaParticipant for TestParticipant {
+        type Error = String;
+
+        fn step_name(&self) -> &str {
+            "risk_check"
+        }
+
+        fn saga_types(&self) -> &[&'static str] {
+            &["order_lifecycle"]
+        }
+
+        fn depends_on(&self) -> DependencySpec {
+            self.dependency_spec.clone()
+        }
+
+        fn is_critical(&self) -> bool {
+            self.critical
+        }
+
+        fn correlation_keys(&self, context: &SagaContext, _output: &[u8]) -> Vec<Box<str>> {
+            vec![format!("order-{}", context.saga_id.get()).into()]
+        }
+
+        fn authorize_event(
+            &self,
+            context: &SagaContext,
+            event_type: &str,
+        ) -> Result<(), crate::AuthError> {
+            match self.trusted_compensation_peer {
+                Some(peer)
+                    if event_type == "compensation_requested"
+                        && context.initiator_peer_id != peer =>
+                {
+                    Err(crate::AuthError::UntrustedPeer {
+                        peer: context.initiator_peer_id,
+                    })
+                }
+                _ => Ok(()),
+            }
+        }
+
+        fn supports_workflow_version(&self, workflow_version: u32) -> bool {
+            self.supported_workflow_version
+                .is_none_or(|supported| supported == workflow_version)
+        }
+
+        fn offload_step(
+            &self,
+            _context: &SagaContext,
+            _input: &[u8],
+        ) -> Option<crate::OffloadedStep> {
+            self.offload.then(|| -> crate::OffloadedStep {
+                Box::new(|| {
+                    Ok(StepOutput::Completed {
+                        output: vec![1, 2, 3],
+                        compensation_data: vec![9],
+                    })
+                })
+            })
+        }
+
+        fn execute_step(
+            &mut self,
+            _context: &SagaContext,
+            _input: &[u8],
+        ) -> Result<StepOutput, StepError> {
+            self.executed = self.executed.saturating_add(1);
+            self.observed_inputs.push(_input.to_vec());
+            self.entered_spans
+                .push(tracing::Span::current().metadata().map(|meta| meta.name()));
+            match self.execute_mode {
+                ExecuteMode::Completed => Ok(StepOutput::Completed {
+                    output: vec![1, 2, 3],
+                    compensation_data: vec![9],
+                }),
+                ExecuteMode::CompletedWithEffect => Ok(StepOutput::CompletedWithEffect {
+                    output: vec![1, 2, 3],
+                    compensation_data: vec![9],
+                    effect: "notify_risk".into(),
+                }),
+                ExecuteMode::Partial => Ok(StepOutput::partial(vec![1], vec![4], 0.5)),
+                ExecuteMode::NoOp => Ok(StepOutput::NoOp),
+                ExecuteMode::TerminalFail => Err(StepError::terminal("terminal failure")
+                    .with_code(crate::ReasonCode::Rejected)
+                    .with_typed_details(&10_009_u32)),
+                ExecuteMode::Panic => panic!("order book missing"),
+            }
+        }
+
+        fn compensate_step(
+            &mut self,
+            _context: &SagaContext,
+            compensation_data: &[u8],
+        ) -> Result<(), CompensationError> {
+            self.compensated_with.push(compensation_data.to_vec());
+            self.entered_spans
+                .push(tracing::Span::current().metadata().map(|meta| meta.name()));
+            if let Some(err) = self.compensation_error.clone() {
+                return Err(err);
+            }
+            Ok(())
+        }
+    }
+
+    fn started_event() -> SagaChoreographyEvent {
+        SagaChoreographyEvent::SagaStarted {
+            context: DeterministicContextBuilder::default().build(),
+            payload: vec![7],
+        }
+    }
+
+    #[test]
+    fn handle_saga_event_with_emit_emits_step_completed() {
+        let mut participant = TestParticipant::default();
+        let mut emitted = Vec::new();
+
+        handle_saga_event_with_emit(&mut participant, started_event(), |event| {
+            emitted.push(event)
+        });
+
+        assert_eq!(participant.executed, 1);
+        assert_eq!(emitted.len(), 2);
+        assert!(matches!(
+            emitted.first(),
+            Some(SagaChoreographyEvent::StepStarted { .. })
+        ));
+        assert!(matches!(
+            emitted.get(1),
+            Some(SagaChoreographyEvent::StepCompleted {
+                compensation_available: true,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn direct_handling_does_not_stage_outcomes_in_the_outbox() {
+        let mut participant = TestParticipant::default();
+        participant
+            .saga
+            .attach_bus(crate::SagaChoreographyBus::new());
+
+        handle_saga_event_with_emit(&mut participant, started_event(), |_| {});
+
+        // The caller publishes what it is handed; nothing is left for the
+        // relay to publish a second time.
+        assert!(participant
+            .saga
+            .journal
+            .pending_outgoing()
+            .expect("outbox should be readable")
+            .is_empty());
+    }
+
+    #[test]
+    fn ingress_relays_the_stamped_outcome_it_first_published() {
+        let bus = crate::SagaChoreographyBus::new();
+        let accepting = std::sync::Arc::new(AtomicBool::new(false));
+        let accepting_clone = std::sync::Arc::clone(&accepting);
+        let completions = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = std::sync::Arc::clone(&completions);
+        let _sub = bus.subscribe_saga_type_fn("order_lifecycle", move |event| {
+            if matches!(event, SagaChoreographyEvent::StepCompleted { .. }) {
+                seen.lock().unwrap().push(event.context().logical_clock);
+            }
+            accepting_clone.load(Ordering::Relaxed)
+        });
+        let mut participant = TestParticipant::default();
+        participant.saga.attach_bus(bus);
+
+        crate::durability::apply_sync_participant_saga_ingress(
+            &mut participant,
+            started_event(),
+            |_participant, _event| {},
+            |_| {},
+        );
+
+        let pending = participant
+            .saga
+            .journal
+            .pending_outgoing()
+            .expect("outbox should be readable");
+        let staged = pending
+            .iter()
+            .find(|entry| matches!(entry.event, SagaChoreographyEvent::StepCompleted { .. }))
+            .expect("rejected completion should stay staged");
+        let first = completions.lock().unwrap()[0];
+        assert_eq!(staged.event.context().logical_clock, first);
+
+        accepting.store(true, Ordering::Relaxed);
+        assert_eq!(participant.saga.relay_outbox(), Ok(pending.len()));
+        assert_eq!(*completions.lock().unwrap(), vec![first, first]);
+    }
+
+    #[cfg(feature = "hdr")]
+    #[test]
+    fn handle_saga_event_with_emit_records_step_latency() {
+        let recorder = crate::LatencyRecorder::new();
+        let mut participant = TestParticipant {
+            saga: SagaParticipantSupport::new(FlakyJournal::default(), InMemoryDedupe::new())
+                .with_step_latency(&recorder),
+            ..TestParticipant::default()
+        };
+
+        handle_saga_event_with_emit(&mut participant, started_event(), |_| {});
+        participant.saga.flush_latency();
+
+        assert_eq!(recorder.snapshot().count, 1);
+    }
+
+    #[test]
+    fn completed_effect_is_dispatched_once_after_journaling() {
+        let calls = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = std::sync::Arc::clone(&calls);
+        let effects = crate::EffectRegistry::new().with_handler("notify_risk", move |effect| {
+            seen.lock()
+                .unwrap()
+                .push((effect.idempotency_key.clone(), effect.output.to_vec()));
+            Ok(())
+        });
+        let mut participant = TestParticipant {
+            saga: SagaParticipantSupport::new(FlakyJournal::default(), InMemoryDedupe::new())
+                .with_effect_dispatcher(std::sync::Arc::new(effects)),
+            execute_mode: ExecuteMode::CompletedWithEffect,
+            ..TestParticipant::default()
+        };
+
+        handle_saga_event_with_emit(&mut participant, started_event(), |_| {});
+        let context = started_event().into_context();
+        assert!(participant
+            .saga_journal()
+            .read(context.saga_id)
+            .unwrap()
+            .iter()
+            .any(|entry| matches!(entry.event, ParticipantEvent::StepExecutionCompleted { .. })));
+        // A replayed completion finds the effect key marked.
+        dispatch_step_effect(
+            &participant,
+            &context,
+            "risk_check",
+            "notify_risk",
+            &[],
+            true,
+        );
+
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(
+            calls[0].0,
+            crate::IdempotencyKey::for_effect(context.saga_id, "risk_check", "notify_risk")
+        );
+        assert_eq!(calls[0].1, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn handle_saga_event_with_emit_emits_step_failed_on_terminal_failure() {
+        let mut participant = TestParticipant {
+            execute_mode: ExecuteMode::TerminalFail,
+            ..TestParticipant::default()
+        };
+        let mut emitted = Vec::new();
+
+        handle_saga_event_with_emit(&mut participant, started_event(), |event| {
+            emitted.push(event)
+        });
+
+        assert_eq!(participant.executed, 1);
+        assert_eq!(emitted.len(), 2);
+        assert!(matches!(
+            emitted.first(),
+            Some(SagaChoreographyEvent::StepStarted { .. })
+        ));
+        assert!(matches!(
+            emitted.get(1),
+            Some(SagaChoreographyEvent::StepFailed {
+                requires_compensation: false,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn step_error_details_reach_step_failed_and_journal() {
+        let mut participant = TestParticipant {
+            execute_mode: ExecuteMode::TerminalFail,
+            ..TestParticipant::default()
+        };
+        let started = started_event();
+        let saga_id = started.context().saga_id;
+        let mut emitted = Vec::new();
+
+        handle_saga_event_with_emit(&mut participant, started, |event| emitted.push(event));
+
+        let Some(SagaChoreographyEvent::StepFailed { error_details, .. }) = emitted.get(1) else {
+            panic!("expected StepFailed, got: {emitted:?}");
+        };
+        assert_eq!(
+            crate::decode_error_details::<u32>(error_details),
+            Some(10_009)
+        );
+        let entries = participant.saga_journal().read(saga_id).unwrap();
+        assert!(entries.iter().any(|entry| matches!(
+            &entry.event,
+            ParticipantEvent::StepExecutionFailed { details, .. } if details == error_details
+        )));
+    }
+
+    #[test]
+    fn step_error_reason_code_reaches_step_failed_journal_and_stats() {
+        let mut participant = TestParticipant {
+            execute_mode: ExecuteMode::TerminalFail,
+            ..TestParticipant::default()
+        };
+        let started = started_event();
+        let saga_id = started.context().saga_id;
+        let mut emitted = Vec::new();
+
+        handle_saga_event_with_emit(&mut participant, started, |event| emitted.push(event));
+
+        let Some(
+            step_failed @ SagaChoreographyEvent::StepFailed {
+                error_code, error, ..
+            },
+        ) = emitted.get(1)
+        else {
+            panic!("expected StepFailed, got: {emitted:?}");
+        };
+        assert_eq!(error_code.as_deref(), Some("rejected"));
+        assert_eq!(error.as_ref(), "[rejected] terminal failure");
+        assert_eq!(step_failed.reason_code(), Some(crate::ReasonCode::Rejected));
+        let entries = participant.saga_journal().read(saga_id).unwrap();
+        assert!(entries.iter().any(|entry| matches!(
+            &entry.event,
+            ParticipantEvent::StepExecutionFailed { .. }
+        ) && entry.event.reason_code()
+            == Some(crate::ReasonCode::Rejected)));
+        let stats = participant.saga.stats.snapshot();
+        assert_eq!(
+            stats.failures_by_reason_code.get("rejected").copied(),
+            Some(1)
+        );
+        assert_eq!(
+            crate::ParticipantStatsSnapshot::decode(&stats.encode()),
+            Some(stats)
+        );
+    }
+
+    #[test]
+    fn handle_saga_event_with_emit_dedupes_replayed_input() {
+        let mut participant = TestParticipant::default();
+        let input = started_event();
+        let mut emitted = Vec::new();
+
+        handle_saga_event_with_emit(&mut participant, input.clone(), |event| emitted.push(event));
+        handle_saga_event_with_emit(&mut participant, input, |event| emitted.push(event));
+
+        assert_eq!(participant.executed, 1);
+        assert_eq!(emitted.len(), 2);
+        assert!(participant
+            .saga_journal()
+            .pending_incoming()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn replay_saga_inbox_with_emit_processes_event_interrupted_after_dedupe() {
+        let mut participant = TestParticipant::default();
+        let input = started_event();
+        let saga_id = input.context().saga_id;
+        let dedupe_key = DedupeKey::from_event(&input);
+
+        // Simulate a crash between marking the dedupe key and processing.
+        participant.record_incoming(saga_id, dedupe_key, &input);
+        assert!(participant.check_dedupe(saga_id, dedupe_key));
+
+        let mut emitted = Vec::new();
+        handle_saga_event_with_emit(&mut participant, input, |event| emitted.push(event));
+        assert_eq!(participant.executed, 0);
+
+        let replayed = replay_saga_inbox_with_emit(&mut participant, |event| emitted.push(event));
+
+        assert_eq!(replayed, 1);
+        assert_eq!(participant.executed, 1);
+        assert_eq!(emitted.len(), 2);
+        assert!(participant
+            .saga_journal()
+            .pending_incoming()
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            replay_saga_inbox_with_emit(&mut participant, |event| emitted.push(event)),
+            0
+        );
+
+        // A redelivery recorded right before a crash, ahead of its dedupe
+        // check, is closed without running the step again.
+        participant.record_incoming(saga_id, dedupe_key, &started_event());
+        assert_eq!(
+            replay_saga_inbox_with_emit(&mut participant, |event| emitted.push(event)),
+            0
+        );
+        assert_eq!(participant.executed, 1);
+        assert!(participant
+            .saga_journal()
+            .pending_incoming()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn inbox_replay_keeps_entries_parked_while_their_history_is_unreadable() {
+        let mut participant = TestParticipant::default();
+        let input = started_event();
+        let saga_id = input.context().saga_id;
+        participant.record_incoming(saga_id, DedupeKey::from_event(&input), &input);
+
+        participant
+            .saga
+            .journal
+            .fail_history_reads
+            .store(true, Ordering::Relaxed);
+        let mut emitted = Vec::new();
+        assert_eq!(
+            replay_saga_inbox_with_emit(&mut participant, |event| emitted.push(event)),
+            0
+        );
+        assert_eq!(participant.executed, 0);
+        assert_eq!(
+            participant.saga_journal().pending_incoming().unwrap().len(),
+            1
+        );
+
+        participant
+            .saga
+            .journal
+            .fail_history_reads
+            .store(false, Ordering::Relaxed);
+        assert_eq!(
+            replay_saga_inbox_with_emit(&mut participant, |event| emitted.push(event)),
+            1
+        );
+        assert_eq!(participant.executed, 1);
+    }
+
+    #[test]
+    fn refuse_policy_fails_step_without_executing_when_journal_rejects_start() {
+        let mut participant = TestParticipant::default();
+        participant.saga.journal_failure_policy = JournalFailurePolicy::Refuse;
+        participant
+            .saga
+            .journal
+            .fail_appends
+            .store(true, Ordering::Relaxed);
+        let mut emitted = Vec::new();
+
+        handle_saga_event_with_emit(&mut participant, started_event(), |event| {
+            emitted.push(event)
+        });
+
+        assert_eq!(participant.executed, 0);
+        assert!(matches!(
+            emitted.as_slice(),
+            [SagaChoreographyEvent::StepFailed {
+                requires_compensation: true,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn park_policy_keeps_event_for_inbox_replay_until_journal_recovers() {
+        let mut participant = TestParticipant::default();
+        participant.saga.journal_failure_policy = JournalFailurePolicy::Park;
+        participant
+            .saga
+            .journal
+            .fail_appends
+            .store(true, Ordering::Relaxed);
+        let mut emitted = Vec::new();
+
+        handle_saga_event_with_emit(&mut participant, started_event(), |event| {
+            emitted.push(event)
+        });
+        assert_eq!(participant.executed, 0);
+        assert!(emitted.is_empty());
+        assert_eq!(
+            participant.saga_journal().pending_incoming().unwrap().len(),
+            1
+        );
+
+        // Still failing: the replayed event is parked again.
+        assert_eq!(
+            replay_saga_inbox_with_emit(&mut participant, |event| emitted.push(event)),
+            1
+        );
+        assert_eq!(participant.executed, 0);
+
+        participant
+            .saga
+            .journal
+            .fail_appends
+            .store(false, Ordering::Relaxed);
+        assert_eq!(
+            replay_saga_inbox_with_emit(&mut participant, |event| emitted.push(event)),
+            1
+        );
+        assert_eq!(participant.executed, 1);
+        assert!(participant
+            .saga_journal()
+            .pending_incoming()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn park_policy_lets_a_replayed_dependency_trigger_fire_again() {
+        let mut participant = TestParticipant {
+            dependency_spec: DependencySpec::after("place_order"),
+            ..TestParticipant::default()
+        };
+        participant.saga.journal_failure_policy = JournalFailurePolicy::Park;
+        participant
+            .saga
+            .journal
+            .fail_appends
+            .store(true, Ordering::Relaxed);
+        let placed = crate::step_completed(
+            DeterministicContextBuilder::default()
+                .with_step_name("place_order")
+                .build(),
+            vec![7],
+            vec![7],
+            false,
+        );
+        let saga_id = placed.context().saga_id;
+        let mut emitted = Vec::new();
+
+        handle_saga_event_with_emit(&mut participant, placed, |event| emitted.push(event));
+        assert_eq!(participant.executed, 0);
+        assert!(!participant.saga.dependency_fired.contains(&saga_id));
 
-    if let Some(SagaStateEntry::Compensating(state)) = participant.saga_states().remove(&saga_id) {
-        let new_state = state.quarantine(reason.clone(), now);
         participant
-            .saga_states()
-            .insert(saga_id, SagaStateEntry::Quarantined(new_state));
+            .saga
+            .journal
+            .fail_appends
+            .store(false, Ordering::Relaxed);
+        assert_eq!(
+            replay_saga_inbox_with_emit(&mut participant, |event| emitted.push(event)),
+            1
+        );
+        assert_eq!(participant.executed, 1);
     }
 
-    participant.record_event(
-        saga_id,
-        ParticipantEvent::Quarantined {
-            reason: reason.clone(),
-            quarantined_at_millis: now,
-        },
-    );
+    #[test]
+    fn retry_policy_parks_the_trigger_until_journal_retry_at() {
+        let now = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(10_000));
+        let mut participant = TestParticipant::default();
+        let clock = now.clone();
+        participant.saga.clock = Some(std::sync::Arc::new(move || clock.load(Ordering::Relaxed)));
+        participant.saga.journal_failure_policy = JournalFailurePolicy::Retry {
+            attempts: 2,
+            backoff: std::time::Duration::from_secs(1),
+        };
+        participant
+            .saga
+            .journal
+            .fail_appends
+            .store(true, Ordering::Relaxed);
+        let mut emitted = Vec::new();
 
-    let event_context = context.next_step(participant.step_name().into());
-    emit(SagaChoreographyEvent::CompensationFailed {
-        context: event_context.clone(),
-        participant_id: participant.participant_id_owned(),
-        error: reason.clone(),
-        is_ambiguous,
-    });
-    if is_ambiguous {
-        emit(SagaChoreographyEvent::SagaQuarantined {
-            context: event_context,
-            reason: reason.clone(),
-            step: participant.step_name().into(),
-            participant_id: participant.participant_id_owned(),
+        handle_saga_event_with_emit(&mut participant, started_event(), |event| {
+            emitted.push(event)
         });
+        assert_eq!(participant.executed, 0);
+        assert!(emitted.is_empty());
+        assert_eq!(participant.saga.journal_retry_at(), Some(11_000));
+
+        now.store(11_000, Ordering::Relaxed);
+        participant
+            .saga
+            .journal
+            .fail_appends
+            .store(false, Ordering::Relaxed);
+        replay_saga_inbox_with_emit(&mut participant, |event| emitted.push(event));
+        assert_eq!(participant.executed, 1);
+        assert_eq!(participant.saga.journal_retry_at(), None);
+        assert!(participant.saga.journal_retries.is_empty());
     }
 
-    participant.on_quarantined(context, &reason);
-}
+    #[test]
+    fn retry_policy_refuses_the_step_once_retries_run_out() {
+        let mut participant = TestParticipant::default();
+        participant.saga.journal_failure_policy = JournalFailurePolicy::Retry {
+            attempts: 1,
+            backoff: std::time::Duration::from_millis(10),
+        };
+        participant
+            .saga
+            .journal
+            .fail_appends
+            .store(true, Ordering::Relaxed);
+        let mut emitted = Vec::new();
 
-#[cfg(test)]
-mod tests {
-    use crate::{
-        DeterministicContextBuilder, HasSagaParticipantSupport, InMemoryDedupe, InMemoryJournal,
-        SagaContext, SagaParticipantSupport,
-    };
+        handle_saga_event_with_emit(&mut participant, started_event(), |event| {
+            emitted.push(event)
+        });
+        assert!(emitted.is_empty());
+        replay_saga_inbox_with_emit(&mut participant, |event| emitted.push(event));
 
-    use super::*;
+        assert_eq!(participant.executed, 0);
+        assert!(matches!(
+            emitted.as_slice(),
+            [SagaChoreographyEvent::StepFailed {
+                requires_compensation: true,
+                ..
+            }]
+        ));
+    }
 
-    #[derive(Clone, Copy)]
-    enum ExecuteMode {
-        Completed,
-        TerminalFail,
+    fn compensation_requested(context: SagaContext) -> SagaChoreographyEvent {
+        SagaChoreographyEvent::CompensationRequested {
+            context,
+            failed_step: "ship_order".into(),
+            reason: "failed downstream".into(),
+            steps_to_compensate: vec!["risk_check".into()],
+        }
     }
 
-    struct TestParticipant {
-        saga: SagaParticipantSupport<InMemoryJournal, InMemoryDedupe>,
-        execute_mode: ExecuteMode,
-        compensation_error: Option<CompensationError>,
-        executed: usize,
-        observed_inputs: Vec<Vec<u8>>,
-        dependency_spec: DependencySpec,
+    fn journaled_events(participant: &TestParticipant, saga_id: SagaId) -> Vec<&'static str> {
+        participant
+            .saga_journal()
+            .read(saga_id)
+            .unwrap()
+            .into_iter()
+            .map(|entry| match entry.event {
+                ParticipantEvent::StepExecutionStarted { .. } => "step_started",
+                ParticipantEvent::StepExecutionCompleted { .. } => "step_completed",
+                ParticipantEvent::StepExecutionFailed { .. } => "step_failed",
+                ParticipantEvent::CompensationStarted { .. } => "compensation_started",
+                ParticipantEvent::CompensationCompleted { .. } => "compensation_completed",
+                ParticipantEvent::Quarantined { .. } => "quarantined",
+                _ => "other",
+            })
+            .collect()
     }
 
-    impl Default for TestParticipant {
-        fn default() -> Self {
-            Self {
-                saga: SagaParticipantSupport::new(InMemoryJournal::new(), InMemoryDedupe::new()),
-                execute_mode: ExecuteMode::Completed,
-                compensation_error: None,
-                executed: 0,
-                observed_inputs: Vec::new(),
-                dependency_spec: DependencySpec::OnSagaStart,
-            }
-        }
+    #[test]
+    fn park_policy_holds_step_completion_until_the_journal_records_it() {
+        let mut participant = TestParticipant::default();
+        participant.saga.journal_failure_policy = JournalFailurePolicy::Park;
+        participant
+            .saga
+            .journal
+            .fail_outcomes
+            .store(true, Ordering::Relaxed);
+        let started = started_event();
+        let saga_id = started.context().saga_id;
+        let mut emitted = Vec::new();
+
+        handle_saga_event_with_emit(&mut participant, started, |event| emitted.push(event));
+        assert_eq!(participant.executed, 1);
+        assert!(matches!(
+            emitted.as_slice(),
+            [SagaChoreographyEvent::StepStarted { .. }]
+        ));
+
+        // Still failing: the completion is held back again.
+        replay_saga_inbox_with_emit(&mut participant, |event| emitted.push(event));
+        assert_eq!(emitted.len(), 1);
+
+        participant
+            .saga
+            .journal
+            .fail_outcomes
+            .store(false, Ordering::Relaxed);
+        replay_saga_inbox_with_emit(&mut participant, |event| emitted.push(event));
+        assert_eq!(participant.executed, 1);
+        assert!(matches!(
+            emitted.as_slice(),
+            [
+                SagaChoreographyEvent::StepStarted { .. },
+                SagaChoreographyEvent::StepCompleted { .. }
+            ]
+        ));
+        assert_eq!(
+            journaled_events(&participant, saga_id),
+            ["step_started", "step_completed"]
+        );
     }
 
-    impl HasSagaParticipantSupport for TestParticipant {
-        type Journal = InMemoryJournal;
-        type Dedupe = InMemoryDedupe;
+    #[test]
+    fn refuse_policy_quarantines_the_saga_over_an_unjournaled_step_failure() {
+        let mut participant = TestParticipant {
+            execute_mode: ExecuteMode::TerminalFail,
+            ..TestParticipant::default()
+        };
+        participant.saga.journal_failure_policy = JournalFailurePolicy::Refuse;
+        participant
+            .saga
+            .journal
+            .fail_outcomes
+            .store(true, Ordering::Relaxed);
+        let mut emitted = Vec::new();
 
-        fn saga_support(&self) -> &SagaParticipantSupport<Self::Journal, Self::Dedupe> {
-            &self.saga
-        }
+        handle_saga_event_with_emit(&mut participant, started_event(), |event| {
+            emitted.push(event)
+        });
 
-        fn saga_support_mut(&mut self) -> &mut SagaParticipantSupport<Self::Journal, Self::Dedupe> {
-            &mut self.saga
-        }
+        assert_eq!(participant.executed, 1);
+        assert!(matches!(
+            emitted.as_slice(),
+            [
+                SagaChoreographyEvent::StepStarted { .. },
+                SagaChoreographyEvent::SagaQuarantined { reason, step, .. }
+            ] if reason.starts_with("journal_unavailable") && &**step == "risk_check"
+        ));
     }
 
-    impl SagaParticipant for TestParticipant {
-        type Error = String;
+    #[test]
+    fn retry_policy_quarantines_the_saga_once_outcome_retries_run_out() {
+        let mut participant = TestParticipant::default();
+        participant.saga.journal_failure_policy = JournalFailurePolicy::Retry {
+            attempts: 1,
+            backoff: std::time::Duration::from_millis(10),
+        };
+        participant
+            .saga
+            .journal
+            .fail_outcomes
+            .store(true, Ordering::Relaxed);
+        let mut emitted = Vec::new();
 
-        fn step_name(&self) -> &str {
-            "risk_check"
-        }
+        handle_saga_event_with_emit(&mut participant, started_event(), |event| {
+            emitted.push(event)
+        });
+        assert_eq!(emitted.len(), 1);
+        assert!(participant.saga.journal_retry_at().is_some());
 
-        fn saga_types(&self) -> &[&'static str] {
-            &["order_lifecycle"]
-        }
+        replay_saga_inbox_with_emit(&mut participant, |event| emitted.push(event));
+        assert!(matches!(
+            emitted.as_slice(),
+            [
+                SagaChoreographyEvent::StepStarted { .. },
+                SagaChoreographyEvent::SagaQuarantined { .. }
+            ]
+        ));
+        assert!(participant.saga.unjournaled_outcomes.is_empty());
+    }
 
-        fn depends_on(&self) -> DependencySpec {
-            self.dependency_spec.clone()
-        }
+    #[test]
+    fn park_policy_keeps_compensation_request_until_its_start_is_journaled() {
+        let mut participant = TestParticipant::default();
+        participant.saga.journal_failure_policy = JournalFailurePolicy::Park;
+        let started = started_event();
+        let context = started.context().clone();
+        let saga_id = context.saga_id;
+        handle_saga_event_with_emit(&mut participant, started, |_| {});
+        participant
+            .saga
+            .journal
+            .fail_appends
+            .store(true, Ordering::Relaxed);
+        let mut emitted = Vec::new();
 
-        fn execute_step(
-            &mut self,
-            _context: &SagaContext,
-            _input: &[u8],
-        ) -> Result<StepOutput, StepError> {
-            self.executed = self.executed.saturating_add(1);
-            self.observed_inputs.push(_input.to_vec());
-            match self.execute_mode {
-                ExecuteMode::Completed => Ok(StepOutput::Completed {
-                    output: vec![1, 2, 3],
-                    compensation_data: vec![9],
-                }),
-                ExecuteMode::TerminalFail => Err(StepError::Terminal {
-                    reason: "terminal failure".into(),
-                }),
-            }
-        }
+        handle_saga_event_with_emit(&mut participant, compensation_requested(context), |event| {
+            emitted.push(event)
+        });
+        assert!(participant.compensated_with.is_empty());
+        assert!(emitted.is_empty());
+        assert!(matches!(
+            participant.saga_states_ref().get(&saga_id),
+            Some(SagaStateEntry::Completed(_))
+        ));
 
-        fn compensate_step(
-            &mut self,
-            _context: &SagaContext,
-            _compensation_data: &[u8],
-        ) -> Result<(), CompensationError> {
-            if let Some(err) = self.compensation_error.clone() {
-                return Err(err);
-            }
-            Ok(())
-        }
+        participant
+            .saga
+            .journal
+            .fail_appends
+            .store(false, Ordering::Relaxed);
+        assert_eq!(
+            replay_saga_inbox_with_emit(&mut participant, |event| emitted.push(event)),
+            1
+        );
+        assert_eq!(participant.compensated_with, vec![vec![9]]);
+        assert!(matches!(
+            emitted.as_slice(),
+            [SagaChoreographyEvent::CompensationCompleted { .. }]
+        ));
     }
 
-    fn started_event() -> SagaChoreographyEvent {
-        SagaChoreographyEvent::SagaStarted {
-            context: DeterministicContextBuilder::default().build(),
-            payload: vec![7],
-        }
+    #[test]
+    fn refuse_policy_quarantines_compensation_whose_start_the_journal_rejects() {
+        let mut participant = TestParticipant::default();
+        participant.saga.journal_failure_policy = JournalFailurePolicy::Refuse;
+        let started = started_event();
+        let context = started.context().clone();
+        let saga_id = context.saga_id;
+        handle_saga_event_with_emit(&mut participant, started, |_| {});
+        participant
+            .saga
+            .journal
+            .fail_appends
+            .store(true, Ordering::Relaxed);
+        let mut emitted = Vec::new();
+
+        handle_saga_event_with_emit(&mut participant, compensation_requested(context), |event| {
+            emitted.push(event)
+        });
+
+        assert!(participant.compensated_with.is_empty());
+        assert!(matches!(
+            participant.saga_states_ref().get(&saga_id),
+            Some(SagaStateEntry::Quarantined(_))
+        ));
+        assert!(matches!(
+            emitted.as_slice(),
+            [SagaChoreographyEvent::SagaQuarantined { reason, .. }]
+                if reason.starts_with("journal_unavailable")
+        ));
     }
 
     #[test]
-    fn handle_saga_event_with_emit_emits_step_completed() {
+    fn park_policy_holds_compensation_completion_until_the_journal_records_it() {
         let mut participant = TestParticipant::default();
+        participant.saga.journal_failure_policy = JournalFailurePolicy::Park;
+        let started = started_event();
+        let context = started.context().clone();
+        let saga_id = context.saga_id;
+        handle_saga_event_with_emit(&mut participant, started, |_| {});
+        participant
+            .saga
+            .journal
+            .fail_outcomes
+            .store(true, Ordering::Relaxed);
         let mut emitted = Vec::new();
 
-        handle_saga_event_with_emit(&mut participant, started_event(), |event| {
+        handle_saga_event_with_emit(&mut participant, compensation_requested(context), |event| {
             emitted.push(event)
         });
+        assert_eq!(participant.compensated_with, vec![vec![9]]);
+        assert!(emitted.is_empty());
 
-        assert_eq!(participant.executed, 1);
-        assert_eq!(emitted.len(), 2);
+        participant
+            .saga
+            .journal
+            .fail_outcomes
+            .store(false, Ordering::Relaxed);
+        replay_saga_inbox_with_emit(&mut participant, |event| emitted.push(event));
+        assert_eq!(participant.compensated_with, vec![vec![9]]);
         assert!(matches!(
-            emitted.first(),
-            Some(SagaChoreographyEvent::StepStarted { .. })
+            emitted.as_slice(),
+            [SagaChoreographyEvent::CompensationCompleted { .. }]
+        ));
+        assert_eq!(
+            journaled_events(&participant, saga_id),
+            [
+                "step_started",
+                "step_completed",
+                "compensation_started",
+                "compensation_completed"
+            ]
+        );
+    }
+
+    #[test]
+    fn refuse_policy_quarantines_the_saga_when_its_quarantine_record_is_rejected() {
+        let mut participant = TestParticipant {
+            compensation_error: Some(CompensationError::terminal("cannot compensate")),
+            ..TestParticipant::default()
+        };
+        participant.saga.journal_failure_policy = JournalFailurePolicy::Refuse;
+        let started = started_event();
+        let context = started.context().clone();
+        handle_saga_event_with_emit(&mut participant, started, |_| {});
+        participant
+            .saga
+            .journal
+            .fail_outcomes
+            .store(true, Ordering::Relaxed);
+        let mut emitted = Vec::new();
+
+        handle_saga_event_with_emit(&mut participant, compensation_requested(context), |event| {
+            emitted.push(event)
+        });
+
+        // The CompensationFailed the journal could not record is not published.
+        assert!(matches!(
+            emitted.as_slice(),
+            [SagaChoreographyEvent::SagaQuarantined { reason, .. }]
+                if reason.starts_with("journal_unavailable")
         ));
+    }
+
+    #[test]
+    fn rate_limited_step_parks_in_triggered_until_a_token_frees() {
+        let now = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(10_000));
+        let mut participant = TestParticipant {
+            dependency_spec: DependencySpec::after("place_order"),
+            ..TestParticipant::default()
+        };
+        let clock = now.clone();
+        participant.saga.clock = Some(std::sync::Arc::new(move || clock.load(Ordering::Relaxed)));
+        participant.saga.rate_limit = Some(std::sync::Arc::new(crate::TokenBucketGate::new(
+            1,
+            std::time::Duration::from_secs(1),
+        )));
+        let placed = |saga_id| {
+            crate::step_completed(
+                DeterministicContextBuilder::default()
+                    .with_saga_id(saga_id)
+                    .with_step_name("place_order")
+                    .build(),
+                vec![7],
+                vec![7],
+                false,
+            )
+        };
+        let mut emitted = Vec::new();
+
+        handle_saga_event_with_emit(&mut participant, placed(1), |event| emitted.push(event));
+        handle_saga_event_with_emit(&mut participant, placed(2), |event| emitted.push(event));
+        assert_eq!(participant.executed, 1);
         assert!(matches!(
-            emitted.get(1),
-            Some(SagaChoreographyEvent::StepCompleted {
-                compensation_available: true,
-                ..
-            })
+            participant.saga.saga_states.get(&SagaId::new(2)),
+            Some(SagaStateEntry::Triggered(_))
         ));
+        assert_eq!(participant.saga.rate_limited_until(), Some(11_000));
+        assert_eq!(
+            participant.saga_journal().pending_incoming().unwrap().len(),
+            1
+        );
+
+        // No token yet: the step parks again.
+        replay_saga_inbox_with_emit(&mut participant, |event| emitted.push(event));
+        assert_eq!(participant.executed, 1);
+
+        now.store(11_000, Ordering::Relaxed);
+        replay_saga_inbox_with_emit(&mut participant, |event| emitted.push(event));
+        assert_eq!(participant.executed, 2);
+        assert_eq!(participant.saga.rate_limited_until(), None);
+        assert!(participant
+            .saga_journal()
+            .pending_incoming()
+            .unwrap()
+            .is_empty());
     }
 
     #[test]
-    fn handle_saga_event_with_emit_emits_step_failed_on_terminal_failure() {
+    fn early_events_wait_for_saga_started_or_the_reorder_window() {
+        let now = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(10_000));
+        let dead_letters = std::sync::Arc::new(crate::InMemoryDeadLetterStore::default());
         let mut participant = TestParticipant {
-            execute_mode: ExecuteMode::TerminalFail,
+            dependency_spec: DependencySpec::after("place_order"),
             ..TestParticipant::default()
         };
+        let clock = now.clone();
+        participant.saga.clock = Some(std::sync::Arc::new(move || clock.load(Ordering::Relaxed)));
+        participant.saga.reorder_window =
+            Some(crate::SagaReorderWindow::new(500).dead_letter_on_expiry());
+        participant.saga.dead_letters = Some(dead_letters.clone());
+        let context = |saga_id| {
+            DeterministicContextBuilder::default()
+                .with_saga_id(saga_id)
+                .build()
+        };
+        let placed = |saga_id| {
+            let placed = context(saga_id).next_step("place_order".into());
+            crate::step_completed(placed, vec![7], vec![7], false)
+        };
         let mut emitted = Vec::new();
 
-        handle_saga_event_with_emit(&mut participant, started_event(), |event| {
-            emitted.push(event)
-        });
+        handle_saga_event_with_emit(&mut participant, placed(1), |event| emitted.push(event));
+        handle_saga_event_with_emit(&mut participant, placed(2), |event| emitted.push(event));
+        assert_eq!(participant.executed, 0);
+        assert_eq!(participant.saga.held_out_of_order_len(), 2);
+        assert_eq!(
+            participant.saga_journal().pending_incoming().unwrap().len(),
+            2
+        );
 
+        // The start releases the completion held for its saga.
+        handle_saga_event_with_emit(
+            &mut participant,
+            crate::saga_started(context(1), vec![7]),
+            |event| emitted.push(event),
+        );
         assert_eq!(participant.executed, 1);
-        assert_eq!(emitted.len(), 2);
-        assert!(matches!(
-            emitted.first(),
-            Some(SagaChoreographyEvent::StepStarted { .. })
-        ));
-        assert!(matches!(
-            emitted.get(1),
-            Some(SagaChoreographyEvent::StepFailed {
-                requires_compensation: false,
-                ..
-            })
-        ));
+        assert_eq!(participant.saga.held_out_of_order_len(), 1);
+
+        assert_eq!(
+            flush_reorder_buffer_with_emit(&mut participant, |event| emitted.push(event)),
+            0
+        );
+        assert_eq!(participant.saga.held_out_of_order_len(), 1);
+        now.store(10_500, Ordering::Relaxed);
+        flush_reorder_buffer_with_emit(&mut participant, |event| emitted.push(event));
+        assert_eq!(participant.executed, 1);
+        assert_eq!(participant.saga.held_out_of_order_len(), 0);
+        let dead = crate::DeadLetterStore::list(dead_letters.as_ref()).unwrap();
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].reason, DeadLetterReason::OutOfOrder);
+        assert!(participant
+            .saga_journal()
+            .pending_incoming()
+            .unwrap()
+            .is_empty());
     }
 
     #[test]
-    fn handle_saga_event_with_emit_dedupes_replayed_input() {
+    fn drain_parks_in_flight_sagas_and_refuses_new_starts() {
         let mut participant = TestParticipant::default();
-        let input = started_event();
-        let mut emitted = Vec::new();
+        let saga_id = SagaId::new(41);
+        let executing = SagaParticipantState::new(
+            saga_id,
+            "order_lifecycle".into(),
+            "risk_check".into(),
+            41,
+            41,
+            crate::PeerId::default(),
+            100,
+        )
+        .trigger("saga_started", 100)
+        .start_execution(110);
+        participant
+            .saga
+            .saga_states
+            .insert(saga_id, SagaStateEntry::Executing(executing));
 
-        handle_saga_event_with_emit(&mut participant, input.clone(), |event| emitted.push(event));
-        handle_saga_event_with_emit(&mut participant, input, |event| emitted.push(event));
+        let report = crate::drain(&mut participant, std::time::Instant::now(), |_| {});
+        assert!(!report.is_clean());
+        assert_eq!(report.parked[0].saga_id, saga_id);
+        assert_eq!(report.parked[0].phase, crate::InFlightPhase::Executing);
+        let entries = participant.saga_journal().read(saga_id).unwrap();
+        assert!(matches!(
+            entries.last().map(|entry| &entry.event),
+            Some(ParticipantEvent::Parked { reason, .. }) if &**reason == crate::DRAIN_PARK_REASON
+        ));
+        assert!(crate::journal::last_progress_entry(&entries).is_none());
 
+        let mut emitted = Vec::new();
+        handle_saga_event_with_emit(&mut participant, started_event(), |event| {
+            emitted.push(event)
+        });
+        assert_eq!(participant.executed, 0);
+        assert!(emitted.is_empty());
+
+        // The refused start was not deduped.
+        participant.saga.draining = false;
+        handle_saga_event_with_emit(&mut participant, started_event(), |event| {
+            emitted.push(event)
+        });
         assert_eq!(participant.executed, 1);
-        assert_eq!(emitted.len(), 2);
     }
 
     #[test]
@@ -1094,7 +4921,10 @@ mod tests {
     #[test]
     fn handle_saga_event_with_emit_resets_allof_dependencies_on_new_saga_started() {
         let mut participant = TestParticipant {
-            dependency_spec: DependencySpec::AllOf(&["risk_check", "positions_check"]),
+            dependency_spec: DependencySpec::AllOf(crate::step_names![
+                "risk_check",
+                "positions_check"
+            ]),
             ..TestParticipant::default()
         };
         let first_context = DeterministicContextBuilder::default().build();
@@ -1112,6 +4942,7 @@ mod tests {
                 output: vec![9],
                 saga_input: vec![7],
                 compensation_available: false,
+                completion_ratio: None,
             },
             |_| {},
         );
@@ -1130,6 +4961,7 @@ mod tests {
                 output: vec![8],
                 saga_input: vec![7],
                 compensation_available: false,
+                completion_ratio: None,
             },
             |event| emitted.push(event),
         );
@@ -1141,7 +4973,10 @@ mod tests {
     #[test]
     fn handle_saga_event_with_emit_allof_triggers_once_after_full_dependency_set() {
         let mut participant = TestParticipant {
-            dependency_spec: DependencySpec::AllOf(&["risk_check", "positions_check"]),
+            dependency_spec: DependencySpec::AllOf(crate::step_names![
+                "risk_check",
+                "positions_check"
+            ]),
             ..TestParticipant::default()
         };
         let mut emitted = Vec::new();
@@ -1154,6 +4989,7 @@ mod tests {
                 output: vec![9],
                 saga_input: vec![7],
                 compensation_available: false,
+                completion_ratio: None,
             },
             |event| emitted.push(event),
         );
@@ -1164,51 +5000,492 @@ mod tests {
                 output: vec![8],
                 saga_input: vec![7],
                 compensation_available: false,
+                completion_ratio: None,
+            },
+            |event| emitted.push(event),
+        );
+
+        assert_eq!(participant.executed, 1);
+    }
+
+    #[test]
+    fn handle_saga_event_with_emit_allof_uses_original_saga_input() {
+        let mut participant = TestParticipant {
+            dependency_spec: DependencySpec::AllOf(crate::step_names![
+                "risk_check",
+                "positions_check"
+            ]),
+            ..TestParticipant::default()
+        };
+        let context = DeterministicContextBuilder::default().build();
+
+        handle_saga_event_with_emit(
+            &mut participant,
+            SagaChoreographyEvent::StepCompleted {
+                context: context.next_step("risk_check".into()),
+                output: vec![9],
+                saga_input: vec![7, 7, 7],
+                compensation_available: false,
+                completion_ratio: None,
+            },
+            |_| {},
+        );
+        handle_saga_event_with_emit(
+            &mut participant,
+            SagaChoreographyEvent::StepCompleted {
+                context: context.next_step("positions_check".into()),
+                output: vec![8],
+                saga_input: vec![7, 7, 7],
+                compensation_available: false,
+                completion_ratio: None,
+            },
+            |_| {},
+        );
+
+        assert_eq!(participant.observed_inputs, vec![vec![7, 7, 7]]);
+    }
+
+    #[test]
+    fn offloaded_payloads_are_resolved_for_execute_and_outputs_offloaded() {
+        let store = std::sync::Arc::new(crate::InMemoryPayloadStore::new());
+        let mut participant = TestParticipant {
+            saga: SagaParticipantSupport::new(FlakyJournal::default(), InMemoryDedupe::new())
+                .with_payload_store(store.clone())
+                .with_payload_offload_threshold(2),
+            ..TestParticipant::default()
+        };
+        let saga_input = crate::offload_payload(store.as_ref(), vec![7; 16], 2).unwrap();
+        let mut emitted = Vec::new();
+
+        handle_saga_event_with_emit(
+            &mut participant,
+            SagaChoreographyEvent::SagaStarted {
+                context: DeterministicContextBuilder::default().build(),
+                payload: saga_input.clone(),
+            },
+            |event| emitted.push(event),
+        );
+
+        assert_eq!(participant.observed_inputs, vec![vec![7; 16]]);
+        let Some(SagaChoreographyEvent::StepCompleted {
+            output,
+            saga_input: forwarded,
+            ..
+        }) = emitted.get(1)
+        else {
+            panic!("expected StepCompleted");
+        };
+        assert_eq!(forwarded, &saga_input);
+        assert_eq!(
+            crate::resolve_payload(store.as_ref(), output)
+                .unwrap()
+                .as_ref(),
+            [1, 2, 3]
+        );
+        assert_eq!(store.len(), 2);
+    }
+
+    #[test]
+    fn compensation_data_is_sealed_in_state_and_opened_for_compensate() {
+        struct XorCipher;
+
+        impl crate::PayloadCipher for XorCipher {
+            fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, crate::SensitivePayloadError> {
+                Ok(plaintext.iter().map(|byte| byte ^ 0xff).collect())
+            }
+
+            fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, crate::SensitivePayloadError> {
+                self.encrypt(ciphertext)
+            }
+        }
+
+        let mut participant = TestParticipant {
+            saga: SagaParticipantSupport::new(FlakyJournal::default(), InMemoryDedupe::new())
+                .with_compensation_cipher(std::sync::Arc::new(XorCipher)),
+            ..TestParticipant::default()
+        };
+        let started = started_event();
+        let context = started.context().clone();
+
+        handle_saga_event_with_emit(&mut participant, started, |_| {});
+        let Some(SagaStateEntry::Completed(state)) =
+            participant.saga_states_ref().get(&context.saga_id)
+        else {
+            panic!("step should be completed");
+        };
+        assert_eq!(state.state.compensation_data, vec![9 ^ 0xff]);
+
+        handle_saga_event_with_emit(
+            &mut participant,
+            SagaChoreographyEvent::CompensationRequested {
+                context,
+                failed_step: "execute_order".into(),
+                reason: "failed downstream".into(),
+                steps_to_compensate: vec!["risk_check".into()],
+            },
+            |_| {},
+        );
+
+        assert_eq!(participant.compensated_with, vec![vec![9]]);
+    }
+
+    #[test]
+    fn step_and_compensation_run_inside_the_saga_step_span() {
+        let mut participant = TestParticipant::default();
+        let started = started_event();
+        let context = started.context().clone();
+
+        tracing::subscriber::with_default(tracing_subscriber::registry(), || {
+            handle_saga_event_with_emit(&mut participant, started, |_| {});
+            handle_saga_event_with_emit(
+                &mut participant,
+                SagaChoreographyEvent::CompensationRequested {
+                    context,
+                    failed_step: "execute_order".into(),
+                    reason: "failed downstream".into(),
+                    steps_to_compensate: vec!["risk_check".into()],
+                },
+                |_| {},
+            );
+        });
+
+        assert_eq!(
+            participant.entered_spans,
+            vec![Some("saga_step"), Some("saga_step")]
+        );
+    }
+
+    /// Queues offloaded steps until the test runs them.
+    #[derive(Default)]
+    struct ManualExecutor {
+        jobs: std::sync::Mutex<Vec<Box<dyn FnOnce() + Send + 'static>>>,
+    }
+
+    impl ManualExecutor {
+        fn run_all(&self) {
+            let jobs = std::mem::take(&mut *self.jobs.lock().unwrap());
+            for job in jobs {
+                job();
+            }
+        }
+    }
+
+    impl crate::StepExecutor for ManualExecutor {
+        fn spawn(&self, job: Box<dyn FnOnce() + Send + 'static>) {
+            self.jobs.lock().unwrap().push(job);
+        }
+    }
+
+    #[test]
+    fn offloaded_step_settles_later_and_times_out() {
+        let executor = std::sync::Arc::new(ManualExecutor::default());
+        let now = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(1_000));
+        let clock = now.clone();
+        let mut participant = TestParticipant {
+            saga: SagaParticipantSupport::new(FlakyJournal::default(), InMemoryDedupe::new())
+                .with_step_executor(executor.clone(), std::time::Duration::from_secs(5))
+                .with_clock(std::sync::Arc::new(move || clock.load(Ordering::Relaxed))),
+            offload: true,
+            ..TestParticipant::default()
+        };
+        let mut emitted = Vec::new();
+
+        handle_saga_event_with_emit(&mut participant, started_event(), |event| {
+            emitted.push(event)
+        });
+        assert_eq!(participant.executed, 0);
+        assert_eq!(participant.saga_support().offloaded_steps(), 1);
+        assert!(matches!(
+            emitted.as_slice(),
+            [SagaChoreographyEvent::StepStarted { .. }]
+        ));
+        assert_eq!(
+            poll_offloaded_steps_with_emit(&mut participant, |event| emitted.push(event)),
+            0
+        );
+
+        executor.run_all();
+        assert_eq!(
+            poll_offloaded_steps_with_emit(&mut participant, |event| emitted.push(event)),
+            1
+        );
+        assert!(matches!(
+            emitted.last(),
+            Some(SagaChoreographyEvent::StepCompleted { output, .. }) if output == &vec![1, 2, 3]
+        ));
+
+        // A second saga whose worker never returns in time.
+        let stuck = SagaChoreographyEvent::SagaStarted {
+            context: DeterministicContextBuilder::default()
+                .with_saga_id(2)
+                .build(),
+            payload: vec![7],
+        };
+        handle_saga_event_with_emit(&mut participant, stuck, |event| emitted.push(event));
+        now.store(6_000, Ordering::Relaxed);
+        assert_eq!(
+            poll_offloaded_steps_with_emit(&mut participant, |event| emitted.push(event)),
+            1
+        );
+        assert!(matches!(
+            emitted.last(),
+            Some(SagaChoreographyEvent::StepFailed {
+                error_code: Some(code),
+                requires_compensation: true,
+                ..
+            }) if code.as_ref() == "timeout"
+        ));
+
+        // Its worker still applied the step, so the late result is
+        // compensated.
+        executor.run_all();
+        assert_eq!(
+            poll_offloaded_steps_with_emit(&mut participant, |event| emitted.push(event)),
+            1
+        );
+        assert_eq!(participant.compensated_with, vec![vec![9]]);
+        assert!(matches!(
+            emitted.last(),
+            Some(SagaChoreographyEvent::CompensationCompleted { context })
+                if context.saga_id == SagaId::new(2)
+        ));
+        assert!(matches!(
+            participant.saga.saga_states.get(&SagaId::new(2)),
+            Some(SagaStateEntry::Compensated(_))
+        ));
+        assert_eq!(participant.saga_support().offloaded_steps(), 0);
+    }
+
+    #[test]
+    fn late_offloaded_result_that_cannot_be_compensated_quarantines_the_saga() {
+        let executor = std::sync::Arc::new(ManualExecutor::default());
+        let now = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(1_000));
+        let clock = now.clone();
+        let mut participant = TestParticipant {
+            saga: SagaParticipantSupport::new(FlakyJournal::default(), InMemoryDedupe::new())
+                .with_step_executor(executor.clone(), std::time::Duration::from_secs(5))
+                .with_clock(std::sync::Arc::new(move || clock.load(Ordering::Relaxed))),
+            offload: true,
+            compensation_error: Some(CompensationError::ambiguous("order state unknown")),
+            ..TestParticipant::default()
+        };
+        let saga_id = started_event().context().saga_id;
+        let mut emitted = Vec::new();
+
+        handle_saga_event_with_emit(&mut participant, started_event(), |_| {});
+        now.store(6_000, Ordering::Relaxed);
+        poll_offloaded_steps_with_emit(&mut participant, |_| {});
+        executor.run_all();
+        poll_offloaded_steps_with_emit(&mut participant, |event| emitted.push(event));
+
+        assert!(matches!(
+            participant.saga.saga_states.get(&saga_id),
+            Some(SagaStateEntry::Quarantined(_))
+        ));
+        assert!(matches!(
+            emitted.last(),
+            Some(SagaChoreographyEvent::SagaQuarantined { .. })
+        ));
+    }
+
+    #[test]
+    fn unauthorized_compensation_request_is_journaled_and_ignored() {
+        let mut participant = TestParticipant {
+            trusted_compensation_peer: Some([7; 32]),
+            ..TestParticipant::default()
+        };
+        let started = started_event();
+        let context = started.context().clone();
+        let mut emitted = Vec::new();
+
+        handle_saga_event_with_emit(&mut participant, started, |_| {});
+        handle_saga_event_with_emit(
+            &mut participant,
+            SagaChoreographyEvent::CompensationRequested {
+                context: context.clone(),
+                failed_step: "execute_order".into(),
+                reason: "failed downstream".into(),
+                steps_to_compensate: vec!["risk_check".into()],
             },
             |event| emitted.push(event),
         );
 
+        assert!(emitted.is_empty());
+        assert!(participant.compensated_with.is_empty());
+        assert!(matches!(
+            participant.saga_states_ref().get(&context.saga_id),
+            Some(SagaStateEntry::Completed(_))
+        ));
+        let entries = participant.saga_journal().read(context.saga_id).unwrap();
+        assert!(matches!(
+            &entries.last().unwrap().event,
+            ParticipantEvent::EventRejected { event_type, .. }
+                if event_type.as_ref() == "compensation_requested"
+        ));
+    }
+
+    #[test]
+    fn filtered_events_skip_journal_and_dedupe() {
+        let mut participant = TestParticipant {
+            saga: SagaParticipantSupport::new(FlakyJournal::default(), InMemoryDedupe::new())
+                .with_event_filter(crate::SagaEventFilter::terminal()),
+            ..TestParticipant::default()
+        };
+        let started = started_event();
+        let saga_id = started.context().saga_id;
+
+        handle_saga_event_with_emit(&mut participant, started.clone(), |_| {});
+        assert_eq!(participant.executed, 0);
+        assert!(participant.saga_journal().read(saga_id).unwrap().is_empty());
+
+        participant.saga.event_filter = None;
+        handle_saga_event_with_emit(&mut participant, started, |_| {});
+        assert_eq!(participant.executed, 1);
+    }
+
+    #[test]
+    fn events_outside_skew_window_are_rejected_without_consuming_dedupe() {
+        use std::sync::atomic::AtomicU64;
+
+        let started = started_event();
+        let stamped_at = started.context().event_timestamp_millis;
+        let clock = std::sync::Arc::new(AtomicU64::new(stamped_at + 5_000));
+        let now = std::sync::Arc::clone(&clock);
+        let mut participant = TestParticipant {
+            saga: SagaParticipantSupport::new(FlakyJournal::default(), InMemoryDedupe::new())
+                .with_event_skew_window(crate::EventSkewWindow::symmetric(1_000))
+                .with_clock(std::sync::Arc::new(move || now.load(Ordering::Relaxed))),
+            ..TestParticipant::default()
+        };
+        let saga_id = started.context().saga_id;
+
+        handle_saga_event_with_emit(&mut participant, started.clone(), |_| {});
+        assert_eq!(participant.executed, 0);
+        assert!(matches!(
+            &participant.saga_journal().read(saga_id).unwrap()[0].event,
+            ParticipantEvent::EventRejected { event_type, .. } if event_type.as_ref() == "saga_started"
+        ));
+
+        clock.store(stamped_at + 500, Ordering::Relaxed);
+        handle_saga_event_with_emit(&mut participant, started, |_| {});
         assert_eq!(participant.executed, 1);
     }
 
     #[test]
-    fn handle_saga_event_with_emit_allof_uses_original_saga_input() {
+    fn events_of_unsupported_workflow_version_are_ignored() {
         let mut participant = TestParticipant {
-            dependency_spec: DependencySpec::AllOf(&["risk_check", "positions_check"]),
+            supported_workflow_version: Some(1),
             ..TestParticipant::default()
         };
-        let context = DeterministicContextBuilder::default().build();
+        let v2 = DeterministicContextBuilder::default()
+            .with_workflow_version(2)
+            .build();
+        let saga_id = v2.saga_id;
 
         handle_saga_event_with_emit(
             &mut participant,
-            SagaChoreographyEvent::StepCompleted {
-                context: context.next_step("risk_check".into()),
-                output: vec![9],
-                saga_input: vec![7, 7, 7],
-                compensation_available: false,
+            SagaChoreographyEvent::SagaStarted {
+                context: v2,
+                payload: vec![7],
             },
             |_| {},
         );
-        handle_saga_event_with_emit(
-            &mut participant,
-            SagaChoreographyEvent::StepCompleted {
-                context: context.next_step("positions_check".into()),
-                output: vec![8],
-                saga_input: vec![7, 7, 7],
-                compensation_available: false,
-            },
-            |_| {},
+        assert_eq!(participant.executed, 0);
+        assert!(participant.saga_journal().read(saga_id).unwrap().is_empty());
+
+        handle_saga_event_with_emit(&mut participant, started_event(), |_| {});
+        assert_eq!(participant.executed, 1);
+    }
+
+    #[test]
+    fn replica_skips_leased_step_and_takes_it_over_after_expiry() {
+        use std::sync::atomic::AtomicU64;
+        use std::sync::Arc;
+
+        let store = Arc::new(crate::InMemoryStepLeaseStore::new());
+        let started = started_event();
+        let saga_id = started.context().saga_id;
+        let t0 = started.context().event_timestamp_millis;
+        let clock = Arc::new(AtomicU64::new(t0));
+        let leases = |holder: &str| {
+            crate::StepLeases::new(store.clone(), holder, std::time::Duration::from_secs(1))
+        };
+        let now = Arc::clone(&clock);
+        let mut replica = TestParticipant {
+            saga: SagaParticipantSupport::new(FlakyJournal::default(), InMemoryDedupe::new())
+                .with_step_leases(leases("replica-b"))
+                .with_clock(Arc::new(move || now.load(Ordering::Relaxed))),
+            ..TestParticipant::default()
+        };
+        // Replica A claimed the step and crashed while executing it.
+        leases("replica-a")
+            .claim(saga_id, "risk_check", t0)
+            .unwrap();
+
+        let mut emitted = Vec::new();
+        handle_saga_event_with_emit(&mut replica, started, |event| emitted.push(event));
+        assert_eq!(replica.executed, 0);
+        assert!(emitted.is_empty());
+        assert_eq!(replay_saga_inbox_with_emit(&mut replica, |_| {}), 1);
+        assert_eq!(replica.executed, 0);
+
+        clock.store(t0 + 1_000, Ordering::Relaxed);
+        assert_eq!(replay_saga_inbox_with_emit(&mut replica, |_| {}), 1);
+        assert_eq!(replica.executed, 1);
+        assert_eq!(replay_saga_inbox_with_emit(&mut replica, |_| {}), 0);
+
+        // The finished step keeps its lease past the TTL.
+        clock.store(t0 + 10_000, Ordering::Relaxed);
+        assert!(leases("replica-a")
+            .claim(saga_id, "risk_check", t0 + 10_000)
+            .is_err());
+    }
+
+    #[test]
+    fn replica_takes_over_a_leased_dependency_step_after_expiry() {
+        use std::sync::atomic::AtomicU64;
+        use std::sync::Arc;
+
+        let store = Arc::new(crate::InMemoryStepLeaseStore::new());
+        let placed = crate::step_completed(
+            DeterministicContextBuilder::default()
+                .with_step_name("place_order")
+                .build(),
+            vec![7],
+            vec![7],
+            false,
         );
+        let saga_id = placed.context().saga_id;
+        let t0 = placed.context().event_timestamp_millis;
+        let clock = Arc::new(AtomicU64::new(t0));
+        let leases = |holder: &str| {
+            crate::StepLeases::new(store.clone(), holder, std::time::Duration::from_secs(1))
+        };
+        let now = Arc::clone(&clock);
+        let mut replica = TestParticipant {
+            dependency_spec: DependencySpec::after("place_order"),
+            saga: SagaParticipantSupport::new(FlakyJournal::default(), InMemoryDedupe::new())
+                .with_step_leases(leases("replica-b"))
+                .with_clock(Arc::new(move || now.load(Ordering::Relaxed))),
+            ..TestParticipant::default()
+        };
+        leases("replica-a")
+            .claim(saga_id, "risk_check", t0)
+            .unwrap();
 
-        assert_eq!(participant.observed_inputs, vec![vec![7, 7, 7]]);
+        handle_saga_event_with_emit(&mut replica, placed, |_| {});
+        assert_eq!(replica.executed, 0);
+        assert!(!replica.saga.dependency_fired.contains(&saga_id));
+
+        clock.store(t0 + 1_000, Ordering::Relaxed);
+        assert_eq!(replay_saga_inbox_with_emit(&mut replica, |_| {}), 1);
+        assert_eq!(replica.executed, 1);
     }
 
     #[test]
     fn handle_saga_event_with_emit_emits_non_ambiguous_compensation_failure_only() {
         let mut participant = TestParticipant {
-            compensation_error: Some(CompensationError::Terminal {
-                reason: "cannot compensate".into(),
-            }),
+            compensation_error: Some(CompensationError::terminal("cannot compensate")),
             ..TestParticipant::default()
         };
         let started = started_event();
@@ -1234,12 +5511,62 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn noop_step_completes_without_payload_and_skips_compensation() {
+        let mut participant = TestParticipant {
+            execute_mode: ExecuteMode::NoOp,
+            ..TestParticipant::default()
+        };
+        let started = started_event();
+        let context = started.context().clone();
+        let mut emitted = Vec::new();
+
+        handle_saga_event_with_emit(&mut participant, started, |event| emitted.push(event));
+        assert!(matches!(
+            emitted.get(1),
+            Some(SagaChoreographyEvent::StepCompleted {
+                output,
+                compensation_available: false,
+                ..
+            }) if output.is_empty()
+        ));
+        let Some(SagaStateEntry::Completed(state)) =
+            participant.saga_states_ref().get(&context.saga_id)
+        else {
+            panic!("noop step should be completed");
+        };
+        assert!(!state.state.compensatable);
+        assert!(state.state.output.is_empty());
+
+        emitted.clear();
+        handle_saga_event_with_emit(
+            &mut participant,
+            SagaChoreographyEvent::CompensationRequested {
+                context: context.clone(),
+                failed_step: "execute_order".into(),
+                reason: "failed downstream".into(),
+                steps_to_compensate: vec!["risk_check".into()],
+            },
+            |event| emitted.push(event),
+        );
+
+        assert!(emitted.is_empty());
+        assert!(matches!(
+            participant.saga_states_ref().get(&context.saga_id),
+            Some(SagaStateEntry::Completed(_))
+        ));
+        assert!(!participant
+            .saga_journal()
+            .read(context.saga_id)
+            .unwrap()
+            .iter()
+            .any(|entry| matches!(entry.event, ParticipantEvent::CompensationStarted { .. })));
+    }
+
     #[test]
     fn handle_saga_event_with_emit_emits_quarantine_for_ambiguous_compensation_failure() {
         let mut participant = TestParticipant {
-            compensation_error: Some(CompensationError::Ambiguous {
-                reason: "cannot confirm rollback".into(),
-            }),
+            compensation_error: Some(CompensationError::ambiguous("cannot confirm rollback")),
             ..TestParticipant::default()
         };
         let started = started_event();
@@ -1308,6 +5635,7 @@ mod tests {
                 output: vec![1],
                 saga_input: vec![1],
                 compensation_available: false,
+                completion_ratio: None,
             },
             |_| {},
         );
@@ -1317,4 +5645,467 @@ mod tests {
             "post-quarantine replay should be ignored once the saga is terminal-latched"
         );
     }
+
+    #[test]
+    fn duplicates_are_counted_per_type_and_reported_to_the_observer() {
+        let observer = std::sync::Arc::new(crate::RecordingObserver::new());
+        let mut participant = TestParticipant::default();
+        participant.saga.observer = Some(observer.clone());
+
+        for _ in 0..3 {
+            handle_saga_event_with_emit(&mut participant, started_event(), |_| {});
+        }
+
+        assert_eq!(participant.executed, 1);
+        let stats = participant.saga.stats.snapshot();
+        assert_eq!(stats.duplicate_events, 2);
+        assert_eq!(stats.duplicate_events_by_type.get("saga_started"), Some(&2));
+        observer.assert_snapshot(
+            "
+            #1 order_lifecycle duplicate_event type=saga_started
+            #1 order_lifecycle duplicate_event type=saga_started
+            ",
+        );
+        assert!(participant
+            .saga_journal()
+            .pending_incoming()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn missing_state_policy_decides_transitions_without_a_state_entry() {
+        let restarted_compensation = |policy: crate::MissingStatePolicy| {
+            let observer = std::sync::Arc::new(crate::RecordingObserver::new());
+            let mut participant = TestParticipant {
+                saga: SagaParticipantSupport::new(FlakyJournal::default(), InMemoryDedupe::new())
+                    .with_missing_state_policy(policy)
+                    .with_observer(observer.clone()),
+                ..TestParticipant::default()
+            };
+            let started = started_event();
+            let context = started.context().clone();
+            handle_saga_event_with_emit(&mut participant, started, |_| {});
+            participant.saga_states().remove(&context.saga_id);
+
+            let mut emitted = Vec::new();
+            handle_saga_event_with_emit(
+                &mut participant,
+                SagaChoreographyEvent::CompensationRequested {
+                    context: context.clone(),
+                    failed_step: "execute_order".into(),
+                    reason: "failed downstream".into(),
+                    steps_to_compensate: vec!["risk_check".into()],
+                },
+                |event| emitted.push(event),
+            );
+            observer.assert_snapshot(
+                "
+                #1 order_lifecycle missing_state step=risk_check expected=Completed
+                ",
+            );
+            let kind = participant
+                .saga_states_ref()
+                .get(&context.saga_id)
+                .map(SagaStateEntry::kind);
+            let emitted = emitted
+                .iter()
+                .map(SagaChoreographyEvent::event_type)
+                .collect::<Vec<_>>();
+            (kind, emitted, participant.compensated_with.len())
+        };
+
+        assert_eq!(
+            restarted_compensation(crate::MissingStatePolicy::Ignore),
+            (None, vec![], 0)
+        );
+        assert_eq!(
+            restarted_compensation(crate::MissingStatePolicy::RebuildFromJournal),
+            (
+                Some(StateKind::Compensated),
+                vec!["compensation_completed"],
+                1
+            )
+        );
+        assert_eq!(
+            restarted_compensation(crate::MissingStatePolicy::Quarantine),
+            (Some(StateKind::Quarantined), vec!["saga_quarantined"], 0)
+        );
+    }
+
+    #[test]
+    fn dedupe_identity_decides_whether_a_republication_is_a_duplicate() {
+        let republish = |participant: &mut TestParticipant| {
+            for trace_id in [1, 2] {
+                let context = DeterministicContextBuilder::default()
+                    .with_trace_id(trace_id)
+                    .build();
+                handle_saga_event_with_emit(
+                    participant,
+                    crate::saga_started(context, vec![7]),
+                    |_| {},
+                );
+            }
+        };
+
+        let mut by_trace = TestParticipant::default();
+        republish(&mut by_trace);
+        assert_eq!(by_trace.executed, 2);
+
+        let mut by_attempt = TestParticipant::default();
+        by_attempt.saga.dedupe_identity = crate::DedupeIdentity::SagaStepAttempt;
+        republish(&mut by_attempt);
+        assert_eq!(by_attempt.executed, 1);
+
+        let mut by_saga = TestParticipant::default();
+        by_saga.saga.dedupe_identity = crate::DedupeIdentity::custom(|event| {
+            DedupeKey::from_bytes(&event.context().saga_id.get().to_le_bytes())
+        });
+        republish(&mut by_saga);
+        assert_eq!(by_saga.executed, 1);
+    }
+
+    #[test]
+    fn partial_completion_publishes_its_ratio_and_compensates_the_filled_part() {
+        let mut participant = TestParticipant {
+            execute_mode: ExecuteMode::Partial,
+            ..TestParticipant::default()
+        };
+        let started = started_event();
+        let context = started.context().clone();
+        let mut emitted = Vec::new();
+
+        handle_saga_event_with_emit(&mut participant, started, |event| emitted.push(event));
+        let Some(SagaChoreographyEvent::StepCompleted {
+            output,
+            compensation_available,
+            completion_ratio,
+            ..
+        }) = emitted.last()
+        else {
+            panic!("expected a step completion: {emitted:?}");
+        };
+        assert_eq!(output, &vec![1]);
+        assert!(compensation_available);
+        assert_eq!(*completion_ratio, Some(0.5));
+        let Some(SagaStateEntry::Completed(state)) =
+            participant.saga_states_ref().get(&context.saga_id)
+        else {
+            panic!("step should be completed");
+        };
+        assert_eq!(state.state.completion_ratio, Some(0.5));
+
+        handle_saga_event_with_emit(
+            &mut participant,
+            SagaChoreographyEvent::CompensationRequested {
+                context,
+                failed_step: "execute_order".into(),
+                reason: "failed downstream".into(),
+                steps_to_compensate: vec!["risk_check".into()],
+            },
+            |_| {},
+        );
+        assert_eq!(participant.compensated_with, vec![vec![4]]);
+    }
+
+    #[test]
+    fn redelivered_compensation_request_does_not_compensate_twice() {
+        let mut participant = TestParticipant::default();
+        let context = started_event().into_context();
+        handle_saga_event_with_emit(&mut participant, started_event(), |_| {});
+        let completed = participant
+            .saga_states_ref()
+            .get(&context.saga_id)
+            .cloned()
+            .unwrap();
+        let request = |trace_id| SagaChoreographyEvent::CompensationRequested {
+            context: SagaContext {
+                trace_id,
+                ..context.clone()
+            },
+            failed_step: "execute_order".into(),
+            reason: "failed downstream".into(),
+            steps_to_compensate: vec!["risk_check".into()],
+        };
+
+        handle_saga_event_with_emit(&mut participant, request(10), |_| {});
+        // A crash before the Compensated state was persisted leaves the step
+        // Completed, and the request arrives again under a new trace id.
+        participant.saga_states().insert(context.saga_id, completed);
+        let mut emitted = Vec::new();
+        handle_saga_event_with_emit(&mut participant, request(11), |event| emitted.push(event));
+
+        assert_eq!(participant.compensated_with, vec![vec![9]]);
+        assert!(emitted
+            .iter()
+            .any(|event| matches!(event, SagaChoreographyEvent::CompensationCompleted { .. })));
+    }
+
+    #[test]
+    fn panicking_step_fails_terminally_instead_of_unwinding() {
+        let observer = std::sync::Arc::new(crate::RecordingObserver::new());
+        let mut participant = TestParticipant {
+            execute_mode: ExecuteMode::Panic,
+            ..TestParticipant::default()
+        };
+        participant.saga.observer = Some(observer.clone());
+        let context = started_event().into_context();
+        let mut emitted = Vec::new();
+
+        handle_saga_event_with_emit(&mut participant, started_event(), |event| {
+            emitted.push(event)
+        });
+
+        let Some(SagaChoreographyEvent::StepFailed {
+            error,
+            requires_compensation,
+            ..
+        }) = emitted.last()
+        else {
+            panic!("expected a step failure: {emitted:?}");
+        };
+        assert_eq!(error.as_ref(), "panicked: order book missing");
+        assert!(!requires_compensation);
+        assert!(participant
+            .saga_journal()
+            .read(context.saga_id)
+            .unwrap()
+            .iter()
+            .any(|entry| matches!(
+                &entry.event,
+                ParticipantEvent::StepExecutionFailed { error, .. }
+                    if error.as_ref() == "panicked: order book missing"
+            )));
+        observer.assert_snapshot(
+            r#"
+            #1 order_lifecycle step_panicked step=risk_check message="order book missing"
+            "#,
+        );
+    }
+
+    #[test]
+    fn completed_step_indexes_its_correlation_keys_until_pruned() {
+        let index = std::sync::Arc::new(crate::EffectCorrelationIndex::new());
+        let mut participant = TestParticipant {
+            saga: SagaParticipantSupport::new(FlakyJournal::default(), InMemoryDedupe::new())
+                .with_correlation_index(index.clone()),
+            execute_mode: ExecuteMode::CompletedWithEffect,
+            ..TestParticipant::default()
+        };
+
+        handle_saga_event_with_emit(&mut participant, started_event(), |_| {});
+        let saga_id = started_event().into_context().saga_id;
+        let effect_key = crate::IdempotencyKey::for_effect(saga_id, "risk_check", "notify_risk");
+        assert_eq!(index.saga_for("order-1"), Some(saga_id));
+        assert_eq!(index.saga_for(effect_key.as_str()), Some(saga_id));
+
+        participant.prune_saga(saga_id);
+        assert!(index.is_empty());
+    }
+
+    #[test]
+    fn terminal_failure_of_critical_step_also_fails_the_saga() {
+        let mut participant = TestParticipant {
+            execute_mode: ExecuteMode::TerminalFail,
+            critical: true,
+            ..TestParticipant::default()
+        };
+        let mut emitted = Vec::new();
+
+        handle_saga_event_with_emit(&mut participant, started_event(), |event| {
+            emitted.push(event)
+        });
+
+        assert!(matches!(
+            emitted.get(1),
+            Some(SagaChoreographyEvent::StepFailed { .. })
+        ));
+        let Some(SagaChoreographyEvent::SagaFailed {
+            context,
+            failure: Some(failure),
+            ..
+        }) = emitted.get(2)
+        else {
+            panic!("expected SagaFailed after StepFailed, got {emitted:?}");
+        };
+        assert_eq!(context.step_name.as_ref(), crate::TERMINAL_RESOLVER_STEP);
+        assert_eq!(failure.step_name.as_ref(), "risk_check");
+    }
+
+    #[test]
+    fn retry_compensation_resumes_a_quarantined_saga_with_its_compensation_data() {
+        let mut participant = TestParticipant {
+            compensation_error: Some(CompensationError::terminal("venue down")),
+            ..TestParticipant::default()
+        };
+        let started = started_event();
+        let context = started.context().clone();
+        let saga_id = context.saga_id;
+        handle_saga_event_with_emit(&mut participant, started, |_| {});
+        handle_saga_event_with_emit(
+            &mut participant,
+            SagaChoreographyEvent::CompensationRequested {
+                context,
+                failed_step: "execute_order".into(),
+                reason: "failed downstream".into(),
+                steps_to_compensate: vec!["risk_check".into()],
+            },
+            |_| {},
+        );
+        assert!(matches!(
+            participant.saga_states_ref().get(&saga_id),
+            Some(SagaStateEntry::Quarantined(_))
+        ));
+
+        participant.compensation_error = None;
+        let emitted = retry_compensation(&mut participant, saga_id).unwrap();
+        assert!(matches!(
+            emitted.as_slice(),
+            [SagaChoreographyEvent::CompensationCompleted { .. }]
+        ));
+        assert_eq!(participant.compensated_with.len(), 2);
+        assert_eq!(
+            participant.compensated_with[0],
+            participant.compensated_with[1]
+        );
+        assert!(matches!(
+            participant.saga_states_ref().get(&saga_id),
+            Some(SagaStateEntry::Compensated(_))
+        ));
+        let history = participant.saga_journal().inspect(saga_id).unwrap();
+        assert_eq!(history.compensations.len(), 2);
+        assert_eq!(history.compensations[1].attempt, 2);
+        assert!(matches!(
+            retry_compensation(&mut participant, saga_id),
+            Err(QuarantineError::NotQuarantined(_))
+        ));
+    }
+
+    #[test]
+    fn retry_compensation_reads_journaled_compensation_data_after_state_pruned() {
+        let mut participant = TestParticipant {
+            compensation_error: Some(CompensationError::terminal("venue down")),
+            ..TestParticipant::default()
+        };
+        let started = started_event();
+        let context = started.context().clone();
+        let saga_id = context.saga_id;
+        handle_saga_event_with_emit(&mut participant, started, |_| {});
+        handle_saga_event_with_emit(
+            &mut participant,
+            SagaChoreographyEvent::CompensationRequested {
+                context,
+                failed_step: "execute_order".into(),
+                reason: "failed downstream".into(),
+                steps_to_compensate: vec!["risk_check".into()],
+            },
+            |_| {},
+        );
+        participant.saga.saga_states.remove(&saga_id);
+
+        participant.compensation_error = None;
+        let emitted = retry_compensation(&mut participant, saga_id).unwrap();
+        assert!(matches!(
+            emitted.as_slice(),
+            [SagaChoreographyEvent::CompensationCompleted { .. }]
+        ));
+        assert_eq!(participant.compensated_with, vec![vec![9], vec![9]]);
+    }
+
+    #[test]
+    fn offloaded_steps_over_the_in_flight_cap_park_until_one_settles() {
+        let executor = std::sync::Arc::new(ManualExecutor::default());
+        let now = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(1_000));
+        let clock = now.clone();
+        let mut participant = TestParticipant {
+            saga: SagaParticipantSupport::new(FlakyJournal::default(), InMemoryDedupe::new())
+                .with_step_executor(executor.clone(), std::time::Duration::from_secs(5))
+                .with_max_in_flight_steps(1)
+                .with_clock(std::sync::Arc::new(move || clock.load(Ordering::Relaxed))),
+            offload: true,
+            ..TestParticipant::default()
+        };
+        // Restored from the journal with no worker behind it; takes no slot.
+        let restored = SagaParticipantState::new(
+            SagaId::new(100),
+            "order_lifecycle".into(),
+            "risk_check".into(),
+            100,
+            100,
+            crate::PeerId::default(),
+            100,
+        )
+        .trigger("saga_started", 100)
+        .start_execution(110);
+        participant
+            .saga
+            .saga_states
+            .insert(SagaId::new(100), SagaStateEntry::Executing(restored));
+        let started = |saga_id| SagaChoreographyEvent::SagaStarted {
+            context: DeterministicContextBuilder::default()
+                .with_saga_id(saga_id)
+                .build(),
+            payload: vec![7],
+        };
+        let state = |participant: &TestParticipant, saga_id| {
+            participant
+                .saga
+                .saga_states
+                .get(&SagaId::new(saga_id))
+                .map(SagaStateEntry::kind)
+        };
+        let mut emitted = Vec::new();
+
+        handle_saga_event_with_emit(&mut participant, started(1), |event| emitted.push(event));
+        handle_saga_event_with_emit(&mut participant, started(2), |event| emitted.push(event));
+        assert_eq!(participant.saga_support().offloaded_steps(), 1);
+        assert_eq!(state(&participant, 1), Some(crate::StateKind::Executing));
+        assert_eq!(state(&participant, 2), Some(crate::StateKind::Triggered));
+        assert_eq!(
+            participant.saga_journal().pending_incoming().unwrap().len(),
+            1
+        );
+        // Left pending by something other than the cap, e.g. a step lease
+        // held elsewhere; a freed slot must not replay it.
+        let unrelated = started(3);
+        participant
+            .saga_journal()
+            .record_incoming(
+                SagaId::new(3),
+                DedupeKey::from_event(&unrelated),
+                &unrelated,
+            )
+            .unwrap();
+
+        // The first worker returns; settling it frees the slot for the
+        // parked trigger.
+        executor.run_all();
+        poll_offloaded_steps_with_emit(&mut participant, |event| emitted.push(event));
+        assert_eq!(state(&participant, 1), Some(crate::StateKind::Completed));
+        assert_eq!(state(&participant, 2), Some(crate::StateKind::Executing));
+        assert_eq!(participant.saga_support().offloaded_steps(), 1);
+
+        // A timed-out step releases its slot too, though its worker has not
+        // returned.
+        handle_saga_event_with_emit(&mut participant, started(4), |event| emitted.push(event));
+        assert_eq!(state(&participant, 4), Some(crate::StateKind::Triggered));
+        now.store(6_000, Ordering::Relaxed);
+        poll_offloaded_steps_with_emit(&mut participant, |event| emitted.push(event));
+        assert!(emitted.iter().any(|event| matches!(
+            event,
+            SagaChoreographyEvent::StepFailed { context, .. } if context.saga_id == SagaId::new(2)
+        )));
+        assert_eq!(state(&participant, 4), Some(crate::StateKind::Executing));
+        assert_eq!(participant.saga_support().offloaded_steps(), 1);
+
+        executor.run_all();
+        poll_offloaded_steps_with_emit(&mut participant, |event| emitted.push(event));
+        assert_eq!(state(&participant, 4), Some(crate::StateKind::Completed));
+        assert_eq!(participant.saga_support().offloaded_steps(), 0);
+        assert_eq!(participant.executed, 0);
+        let pending = participant.saga_journal().pending_incoming().unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].saga_id, SagaId::new(3));
+        assert!(!participant.saga.saga_states.contains_key(&SagaId::new(3)));
+    }
 }
@@ -0,0 +1,381 @@
+//! Saga steps served by plain HTTP endpoints.
+//!
+//! [`HttpStepAdapter`] lets a service that is not an actor take part in a
+//! saga: it is a [`SagaParticipant`] whose `execute_step` POSTs the step
+//! input to a configured URL and whose `compensate_step` POSTs the
+//! compensation data to an optional second URL. Each request carries an
+//! `Idempotency-Key` header, so the endpoint can drop the duplicates that
+//! redelivery produces:
+//!
+//! | Request | `Idempotency-Key` |
+//! | --- | --- |
+//! | step | `saga:{id}:step:{name}:attempt:{n}` |
+//! | compensation | `saga:{id}:compensate:{name}` |
+//!
+//! `X-Saga-Id`, `X-Saga-Type` and `X-Saga-Step` identify the saga as well.
+//!
+//! Responses map onto the step outcome as follows:
+//!
+//! | Response | Step | Compensation |
+//! | --- | --- | --- |
+//! | 2xx | completed, the body is the output | done |
+//! | 4xx | [`StepError::Terminal`]: rejected, nothing to undo | [`CompensationError::Terminal`] |
+//! | 5xx | [`StepError::RequireCompensation`]: it may have applied | [`CompensationError::Ambiguous`] |
+//! | no response | [`StepError::RequireCompensation`] | [`CompensationError::SafeToRetry`] if the request was never sent, else ambiguous |
+//!
+//! Error responses travel as the error details. The body of a successful
+//! step response is also its compensation data when a compensation URL is
+//! set. Requests block the calling thread for up to the configured timeout.
+
+use std::io::Read;
+use std::time::Duration;
+
+use crate::{
+    CompensationError, DependencySpec, HasSagaParticipantSupport, IdempotencyKey,
+    ParticipantDedupeStore, ParticipantJournal, SagaContext, SagaParticipant,
+    SagaParticipantSupport, StepError, StepOutput,
+};
+
+/// Time a request may take unless set with
+/// [`with_timeout`](HttpStepAdapter::with_timeout).
+const DEFAULT_HTTP_STEP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Participant that runs `step_name` by calling an HTTP endpoint.
+pub struct HttpStepAdapter<J, D>
+where
+    J: ParticipantJournal,
+    D: ParticipantDedupeStore,
+{
+    saga: SagaParticipantSupport<J, D>,
+    step_name: &'static str,
+    saga_types: &'static [&'static str],
+    depends_on: DependencySpec,
+    url: Box<str>,
+    compensate_url: Option<Box<str>>,
+    timeout: Duration,
+    agent: ureq::Agent,
+}
+
+impl<J, D> HttpStepAdapter<J, D>
+where
+    J: ParticipantJournal,
+    D: ParticipantDedupeStore,
+{
+    /// Runs on saga start until [`after`](Self::after) says otherwise.
+    pub fn new(
+        step_name: &'static str,
+        saga_types: &'static [&'static str],
+        url: impl Into<Box<str>>,
+        saga: SagaParticipantSupport<J, D>,
+    ) -> Self {
+        Self {
+            saga,
+            step_name,
+            saga_types,
+            depends_on: DependencySpec::OnSagaStart,
+            url: url.into(),
+            compensate_url: None,
+            timeout: DEFAULT_HTTP_STEP_TIMEOUT,
+            agent: agent(DEFAULT_HTTP_STEP_TIMEOUT),
+        }
+    }
+
+    /// Calls the endpoint once `depends_on` is satisfied.
+    pub fn after(mut self, depends_on: DependencySpec) -> Self {
+        self.depends_on = depends_on;
+        self
+    }
+
+    /// Undoes completed steps by POSTing their compensation data to `url`.
+    /// Without it compensation is a no-op.
+    pub fn with_compensation_url(mut self, url: impl Into<Box<str>>) -> Self {
+        self.compensate_url = Some(url.into());
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self.agent = agent(timeout);
+        self
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn compensate_url(&self) -> Option<&str> {
+        self.compensate_url.as_deref()
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn post(
+        &self,
+        url: &str,
+        context: &SagaContext,
+        key: &IdempotencyKey,
+        body: &[u8],
+    ) -> Result<Vec<u8>, Box<ureq::Error>> {
+        let response = self
+            .agent
+            .post(url)
+            .set("Content-Type", "application/octet-stream")
+            .set("Idempotency-Key", key.as_str())
+            .set("X-Saga-Id", &context.saga_id.0.to_string())
+            .set("X-Saga-Type", &context.saga_type)
+            .set("X-Saga-Step", self.step_name)
+            .send_bytes(body)
+            .map_err(Box::new)?;
+        Ok(read_body(response))
+    }
+}
+
+fn agent(timeout: Duration) -> ureq::Agent {
+    ureq::AgentBuilder::new().timeout(timeout).build()
+}
+
+fn read_body(response: ureq::Response) -> Vec<u8> {
+    let mut body = Vec::new();
+    // A body cut short still tells more than none.
+    let _ = response.into_reader().read_to_end(&mut body);
+    body
+}
+
+impl<J, D> HasSagaParticipantSupport for HttpStepAdapter<J, D>
+where
+    J: ParticipantJournal,
+    D: ParticipantDedupeStore,
+{
+    type Journal = J;
+    type Dedupe = D;
+
+    fn saga_support(&self) -> &SagaParticipantSupport<J, D> {
+        &self.saga
+    }
+
+    fn saga_support_mut(&mut self) -> &mut SagaParticipantSupport<J, D> {
+        &mut self.saga
+    }
+}
+
+impl<J, D> SagaParticipant for HttpStepAdapter<J, D>
+where
+    J: ParticipantJournal,
+    D: ParticipantDedupeStore,
+{
+    type Error = StepError;
+
+    fn step_name(&self) -> &str {
+        self.step_name
+    }
+
+    fn saga_types(&self) -> &[&'static str] {
+        self.saga_types
+    }
+
+    fn depends_on(&self) -> DependencySpec {
+        self.depends_on.clone()
+    }
+
+    fn execute_step(
+        &mut self,
+        context: &SagaContext,
+        input: &[u8],
+    ) -> Result<StepOutput, StepError> {
+        let key = IdempotencyKey::for_step(context.saga_id, self.step_name, context.attempt + 1);
+        match self
+            .post(&self.url, context, &key, input)
+            .map_err(|error| *error)
+        {
+            Ok(output) => {
+                let compensation_data = if self.compensate_url.is_some() {
+                    output.clone()
+                } else {
+                    Vec::new()
+                };
+                Ok(StepOutput::Completed {
+                    output,
+                    compensation_data,
+                })
+            }
+            Err(ureq::Error::Status(status, response)) if status < 500 => Err(StepError::terminal(
+                format!("{} rejected the step with {status}", self.url),
+            )
+            .with_details(read_body(response))),
+            Err(ureq::Error::Status(status, response)) => Err(StepError::require_compensation(
+                format!("{} failed the step with {status}", self.url),
+            )
+            .with_details(read_body(response))),
+            Err(ureq::Error::Transport(transport)) => Err(StepError::require_compensation(
+                format!("{} unreachable: {transport}", self.url),
+            )),
+        }
+    }
+
+    fn compensate_step(
+        &mut self,
+        context: &SagaContext,
+        compensation_data: &[u8],
+    ) -> Result<(), CompensationError> {
+        let Some(url) = self.compensate_url.as_deref() else {
+            return Ok(());
+        };
+        let key = IdempotencyKey::for_compensation(context.saga_id, self.step_name);
+        match self
+            .post(url, context, &key, compensation_data)
+            .map_err(|error| *error)
+        {
+            Ok(_) => Ok(()),
+            Err(ureq::Error::Status(status, response)) if status < 500 => {
+                Err(CompensationError::terminal(format!(
+                    "{url} rejected the compensation with {status}"
+                ))
+                .with_details(read_body(response)))
+            }
+            Err(ureq::Error::Status(status, response)) => Err(CompensationError::ambiguous(
+                format!("{url} failed the compensation with {status}"),
+            )
+            .with_details(read_body(response))),
+            Err(ureq::Error::Transport(transport))
+                if transport.kind() == ureq::ErrorKind::ConnectionFailed
+                    || transport.kind() == ureq::ErrorKind::Dns =>
+            {
+                Err(CompensationError::safe_to_retry(format!(
+                    "{url} unreachable: {transport}"
+                )))
+            }
+            Err(ureq::Error::Transport(transport)) => Err(CompensationError::ambiguous(format!(
+                "{url} did not answer: {transport}"
+            ))),
+        }
+    }
+}
+
+impl<J, D> std::fmt::Debug for HttpStepAdapter<J, D>
+where
+    J: ParticipantJournal,
+    D: ParticipantDedupeStore,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HttpStepAdapter")
+            .field("step_name", &self.step_name)
+            .field("depends_on", &self.depends_on)
+            .field("url", &self.url)
+            .field("compensate_url", &self.compensate_url)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    use super::*;
+    use crate::{DeterministicContextBuilder, InMemoryDedupe, InMemoryJournal};
+
+    /// Serves one request per listed `(status, body)` and reports each
+    /// request's `Idempotency-Key` and body.
+    fn stub_endpoint(
+        replies: Vec<(u16, &'static str)>,
+    ) -> (String, mpsc::Receiver<(String, Vec<u8>)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/step", listener.local_addr().unwrap());
+        let (requests, received) = mpsc::channel();
+        std::thread::spawn(move || {
+            for (status, reply) in replies {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut key = String::new();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(": ") {
+                        match name.to_ascii_lowercase().as_str() {
+                            "idempotency-key" => key = value.to_owned(),
+                            "content-length" => length = value.parse().unwrap(),
+                            _ => {}
+                        }
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                requests.send((key, body)).unwrap();
+                let mut stream = reader.into_inner();
+                write!(
+                    stream,
+                    "HTTP/1.1 {status} X\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{reply}",
+                    reply.len()
+                )
+                .unwrap();
+            }
+        });
+        (url, received)
+    }
+
+    #[test]
+    fn http_status_maps_onto_the_step_outcome() {
+        let (url, requests) = stub_endpoint(vec![
+            (200, "order-7"),
+            (200, ""),
+            (422, "insufficient margin"),
+            (503, "try later"),
+        ]);
+        let mut adapter = HttpStepAdapter::new(
+            "place_order",
+            &["order_lifecycle"],
+            url.clone(),
+            SagaParticipantSupport::new(InMemoryJournal::new(), InMemoryDedupe::new()),
+        )
+        .with_compensation_url(url);
+        let context = DeterministicContextBuilder::default()
+            .with_step_name("place_order")
+            .build();
+
+        let output = adapter.execute_step(&context, b"buy 1").unwrap();
+        assert!(matches!(
+            output,
+            StepOutput::Completed { ref output, ref compensation_data }
+                if output == b"order-7" && compensation_data == b"order-7"
+        ));
+        assert_eq!(
+            requests.recv().unwrap(),
+            (
+                "saga:1:step:place_order:attempt:1".to_owned(),
+                b"buy 1".to_vec()
+            )
+        );
+
+        adapter.compensate_step(&context, b"order-7").unwrap();
+        assert_eq!(
+            requests.recv().unwrap(),
+            (
+                "saga:1:compensate:place_order".to_owned(),
+                b"order-7".to_vec()
+            )
+        );
+
+        let rejected = adapter
+            .execute_step(&context.retry(), b"buy 1")
+            .unwrap_err();
+        assert!(!rejected.requires_compensation());
+        assert_eq!(rejected.details(), b"insufficient margin");
+        assert_eq!(
+            requests.recv().unwrap().0,
+            "saga:1:step:place_order:attempt:2"
+        );
+
+        let failed = adapter.execute_step(&context, b"buy 1").unwrap_err();
+        assert!(failed.requires_compensation());
+        assert_eq!(failed.details(), b"try later");
+    }
+}
@@ -101,6 +101,20 @@ impl IdempotencyKey {
         Self(format!("saga:{}:compensate:{}", saga_id.0, step_name).into_boxed_str())
     }
 
+    /// Creates an idempotency key for an effect requested by a completed
+    /// step.
+    ///
+    /// The key is the same for every delivery of the completion, so an
+    /// effect handler that forwards it downstream lets the receiver drop
+    /// duplicates.
+    ///
+    /// # Returns
+    ///
+    /// An `IdempotencyKey` in the format `saga:{id}:effect:{step}:{effect}`
+    pub fn for_effect(saga_id: SagaId, step_name: &str, effect: &str) -> Self {
+        Self(format!("saga:{}:effect:{}:{}", saga_id.0, step_name, effect).into_boxed_str())
+    }
+
     /// Returns the key as a string slice.
     ///
     /// This provides borrowed access to the underlying key string for
@@ -0,0 +1,1074 @@
+//! Start-side helper for actors that initiate sagas.
+//!
+//! Upstream signals (fills, webhooks, retried RPCs) often arrive twice within
+//! milliseconds, each asking to start the same business process. A
+//! [`SagaInitiator`] wraps [`SagaChoreographyBus::start_saga`] and, once
+//! [`SagaInitiator::suppress_duplicates_within`] is set, drops a start whose
+//! key was already started within the window:
+//!
+//! ```ignore
+//! let initiator = SagaInitiator::new(bus, InMemoryDedupe::new())
+//!     .suppress_duplicates_within(Duration::from_secs(2), |_, payload| {
+//!         Some(client_order_id(payload).into())
+//!     });
+//! match initiator.start_saga(context, payload)? {
+//!     SagaInitiation::Admitted(_) => {}
+//!     SagaInitiation::Suppressed { .. } => return Ok(()),
+//! }
+//! ```
+//!
+//! With [`SagaInitiator::require_live_participants`] a start is refused when
+//! the participant registry attached to the bus knows no live peer for one of
+//! the saga's steps; see [`crate::ParticipantRegistry`].
+//!
+//! Local delivery is fire-and-forget, so a dropped `SagaStarted` strands the
+//! saga where nobody sees it. [`SagaInitiator::with_redelivery`] stages each
+//! start in a journal outbox and keeps it there until the saga shows any
+//! other event on the bus (see [`SagaInitiator::subscribe_acks`]).
+//! [`SagaInitiator::redeliver_unacknowledged`], run on the initiator's timer,
+//! publishes starts that stayed unacknowledged past the ack timeout again,
+//! unchanged, so participants dedupe them like any redelivery.
+//!
+//! A start is acknowledged step by step: it awaits every step it triggers
+//! (see [`SagaChoreographyBus::saga_start_steps`]), and an event of a later
+//! step acknowledges all of them. Each ack is recorded in the journal inbox,
+//! so after a restart [`SagaInitiator::resume_redelivery`] rebuilds the
+//! awaiting-acks table from the outbox and the inbox history and tracks only
+//! the starts some step has not acknowledged;
+//! [`SagaInitiator::outstanding_acks`] lists them.
+//!
+//! With [`SagaInitiator::with_saga_index`], every admitted start is recorded
+//! in a [`crate::SagaIndex`] under the business fields registered with
+//! [`SagaInitiator::index_field`], so sagas can be found by, say, client
+//! order id.
+//!
+//! [`SagaInitiator::new_saga_context`] builds start contexts with ids from a
+//! [`crate::SagaIdAllocator`]: random ids by default, or journal-backed
+//! monotonic ones through [`SagaInitiator::with_saga_id_allocator`].
+//!
+//! Windows are fixed buckets of `duration` recorded in the dedupe store, so
+//! a repeat less than `duration` after the first start is always suppressed
+//! and one up to twice that apart may be. Bucket entries are kept under the
+//! bucket number as the saga id and pruned two buckets later; the store must
+//! therefore be dedicated to the initiator, not shared with a participant.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use icanact_core::local::EventSubscription;
+
+use crate::{
+    AckStatus, DedupeKey, JournalError, ParticipantDedupeStore, ParticipantJournal, PeerId,
+    RandomSagaIdAllocator, SagaAdmission, SagaBusPublishError, SagaChoreographyBus,
+    SagaChoreographyEvent, SagaContext, SagaId, SagaIdAllocator, SagaIdAllocatorError, SagaIndex,
+    SagaType, StatCounter, StepName,
+};
+
+type DuplicateKeyFn = dyn Fn(&SagaContext, &[u8]) -> Option<Box<str>> + Send + Sync;
+type IndexFieldFn = dyn Fn(&SagaContext, &[u8]) -> Option<Box<str>> + Send + Sync;
+
+/// Result of [`SagaInitiator::start_saga`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SagaInitiation {
+    /// The start went to the bus; see [`SagaAdmission`].
+    Admitted(SagaAdmission),
+    /// A start with the same duplicate key was made within the window.
+    Suppressed { duplicate_key: Box<str> },
+}
+
+/// Counters of a [`SagaInitiator`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SagaInitiatorStats {
+    pub started: u64,
+    pub queued: u64,
+    pub suppressed: u64,
+    /// Starts refused for missing participants.
+    pub refused: u64,
+    /// Unacknowledged starts published again.
+    pub redelivered: u64,
+    /// Starts given up on after the last redelivery went unacknowledged.
+    pub abandoned: u64,
+}
+
+/// When [`SagaInitiator::redeliver_unacknowledged`] publishes a start again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SagaRedeliveryPolicy {
+    /// How long a published start may go without any other event of its
+    /// saga on the bus.
+    pub ack_timeout: Duration,
+    /// Redeliveries before the start is abandoned.
+    pub max_redeliveries: u32,
+}
+
+impl Default for SagaRedeliveryPolicy {
+    fn default() -> Self {
+        Self {
+            ack_timeout: Duration::from_secs(5),
+            max_redeliveries: 3,
+        }
+    }
+}
+
+/// A published start and the steps it still awaits an ack from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutstandingStart {
+    pub saga_id: SagaId,
+    pub acknowledged: Vec<StepName>,
+    pub awaiting: Vec<StepName>,
+    pub redeliveries: u32,
+}
+
+struct UnackedStart {
+    event: SagaChoreographyEvent,
+    outbox_id: Option<u64>,
+    published_at_millis: u64,
+    redeliveries: u32,
+    /// Steps the start triggers.
+    expected: Vec<StepName>,
+    awaiting: Vec<StepName>,
+}
+
+impl UnackedStart {
+    fn new(
+        event: SagaChoreographyEvent,
+        outbox_id: Option<u64>,
+        published_at_millis: u64,
+        expected: Vec<StepName>,
+    ) -> Self {
+        Self {
+            event,
+            outbox_id,
+            published_at_millis,
+            redeliveries: 0,
+            awaiting: expected.clone(),
+            expected,
+        }
+    }
+
+    /// Folds `event` in; whether it acknowledged a step still awaited.
+    fn acknowledge(&mut self, event: &SagaChoreographyEvent) -> bool {
+        let before = self.awaiting.len();
+        let step = &event.context().step_name;
+        if self.expected.contains(step) {
+            self.awaiting.retain(|awaited| awaited != step);
+        } else {
+            // A later step ran, so every triggered step saw the start.
+            self.awaiting.clear();
+        }
+        self.awaiting.len() != before
+    }
+
+    fn outstanding(&self, saga_id: SagaId) -> OutstandingStart {
+        OutstandingStart {
+            saga_id,
+            acknowledged: self
+                .expected
+                .iter()
+                .filter(|step| !self.awaiting.contains(step))
+                .cloned()
+                .collect(),
+            awaiting: self.awaiting.clone(),
+            redeliveries: self.redeliveries,
+        }
+    }
+}
+
+/// Whether `event` shows a participant received its saga's start.
+fn acknowledges_start(event: &SagaChoreographyEvent) -> bool {
+    !matches!(
+        event,
+        SagaChoreographyEvent::SagaStarted { .. }
+            | SagaChoreographyEvent::SagaStalled { .. }
+            | SagaChoreographyEvent::InitiatorHeartbeat { .. }
+            | SagaChoreographyEvent::InitiatorTakenOver { .. }
+            | SagaChoreographyEvent::StepAck {
+                status: AckStatus::NotApplicable,
+                ..
+            }
+    )
+}
+
+/// Starts awaiting an ack, shared with the ack subscription.
+#[derive(Clone)]
+struct Redelivery {
+    journal: Arc<dyn ParticipantJournal>,
+    policy: SagaRedeliveryPolicy,
+    unacked: Arc<Mutex<BTreeMap<SagaId, UnackedStart>>>,
+}
+
+impl Redelivery {
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<SagaId, UnackedStart>> {
+        self.unacked
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn acknowledge(&self, event: &SagaChoreographyEvent) {
+        if !acknowledges_start(event) {
+            return;
+        }
+        let saga_id = event.context().saga_id;
+        let acked = {
+            let mut unacked = self.lock();
+            let Some(start) = unacked.get_mut(&saga_id) else {
+                return;
+            };
+            if !start.acknowledge(event) {
+                return;
+            }
+            if start.awaiting.is_empty() {
+                unacked.remove(&saga_id).map(|start| start.outbox_id)
+            } else {
+                None
+            }
+        };
+        match acked {
+            Some(outbox_id) => self.mark_sent(saga_id, outbox_id),
+            None => self.record_ack(saga_id, event),
+        }
+    }
+
+    /// Records a partial ack in the journal inbox for
+    /// [`SagaInitiator::resume_redelivery`]; processed at once, so inbox
+    /// replay never delivers it.
+    fn record_ack(&self, saga_id: SagaId, event: &SagaChoreographyEvent) {
+        let recorded = self
+            .journal
+            .record_incoming(saga_id, DedupeKey::from_event(event), event)
+            .and_then(|inbox_id| match inbox_id {
+                Some(inbox_id) => self.journal.mark_incoming_processed(inbox_id),
+                None => Ok(()),
+            });
+        if let Err(err) = recorded {
+            tracing::error!(
+                target: "core::saga",
+                event = "saga_start_ack_record_failed",
+                saga_id = saga_id.get(),
+                error = %err
+            );
+        }
+    }
+
+    fn mark_sent(&self, saga_id: SagaId, outbox_id: Option<u64>) {
+        let Some(outbox_id) = outbox_id else {
+            return;
+        };
+        if let Err(err) = self.journal.mark_outgoing_sent(outbox_id) {
+            tracing::error!(
+                target: "core::saga",
+                event = "saga_outbox_mark_sent_failed",
+                saga_id = saga_id.get(),
+                outbox_id,
+                error = %err
+            );
+        }
+    }
+}
+
+struct DuplicateWindow {
+    window_millis: u64,
+    key: Arc<DuplicateKeyFn>,
+}
+
+/// Starts sagas on a bus, suppressing fast duplicate starts.
+pub struct SagaInitiator<D> {
+    bus: SagaChoreographyBus,
+    dedupe: D,
+    duplicate_window: Option<DuplicateWindow>,
+    clock: Option<Arc<dyn Fn() -> u64 + Send + Sync>>,
+    require_live_participants: bool,
+    redelivery: Option<Redelivery>,
+    saga_index: Option<Arc<dyn SagaIndex>>,
+    index_fields: Vec<(Box<str>, Arc<IndexFieldFn>)>,
+    saga_ids: Arc<dyn SagaIdAllocator>,
+    started: StatCounter,
+    queued: StatCounter,
+    suppressed: StatCounter,
+    refused: StatCounter,
+    redelivered: StatCounter,
+    abandoned: StatCounter,
+}
+
+impl<D: ParticipantDedupeStore> SagaInitiator<D> {
+    pub fn new(bus: SagaChoreographyBus, dedupe: D) -> Self {
+        Self {
+            bus,
+            dedupe,
+            duplicate_window: None,
+            clock: None,
+            require_live_participants: false,
+            redelivery: None,
+            saga_index: None,
+            index_fields: Vec::new(),
+            saga_ids: Arc::new(RandomSagaIdAllocator::new()),
+            started: StatCounter::new(0),
+            queued: StatCounter::new(0),
+            suppressed: StatCounter::new(0),
+            refused: StatCounter::new(0),
+            redelivered: StatCounter::new(0),
+            abandoned: StatCounter::new(0),
+        }
+    }
+
+    /// Suppresses a start when `key` returns the same key as a start made
+    /// less than `duration` earlier, for the same saga type. Starts for which
+    /// `key` returns `None` are never suppressed.
+    pub fn suppress_duplicates_within<F>(mut self, duration: Duration, key: F) -> Self
+    where
+        F: Fn(&SagaContext, &[u8]) -> Option<Box<str>> + Send + Sync + 'static,
+    {
+        self.duplicate_window = Some(DuplicateWindow {
+            window_millis: (duration.as_millis() as u64).max(1),
+            key: Arc::new(key),
+        });
+        self
+    }
+
+    /// Stages every start in `journal`'s outbox and redelivers it under
+    /// `policy` until acknowledged. Starts queued by admission control are
+    /// not tracked; the bus publishes them when they are admitted. Partial
+    /// acks go to the journal's inbox, so it must be dedicated to the
+    /// initiator.
+    pub fn with_redelivery(
+        mut self,
+        journal: Arc<dyn ParticipantJournal>,
+        policy: SagaRedeliveryPolicy,
+    ) -> Self {
+        self.redelivery = Some(Redelivery {
+            journal,
+            policy,
+            unacked: Arc::new(Mutex::new(BTreeMap::new())),
+        });
+        self
+    }
+
+    /// Time source for the duplicate window and redelivery; wall clock when
+    /// unset.
+    pub fn with_clock(mut self, clock: Arc<dyn Fn() -> u64 + Send + Sync>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Refuses starts with [`SagaBusPublishError::MissingParticipants`] unless
+    /// the bus's participant registry has a live peer for every step of the
+    /// saga's workflow contract, or for its first step when no contract is
+    /// registered. Without an attached registry every start is refused.
+    pub fn require_live_participants(mut self) -> Self {
+        self.require_live_participants = true;
+        self
+    }
+
+    /// Indexes every admitted start in `index` under the fields registered
+    /// with [`SagaInitiator::index_field`]; see [`crate::SagaIndex`].
+    pub fn with_saga_index(mut self, index: Arc<dyn SagaIndex>) -> Self {
+        self.saga_index = Some(index);
+        self
+    }
+
+    /// Indexes each admitted start under `field` with the value `extract`
+    /// returns for its payload; `None` leaves the saga out.
+    pub fn index_field<F>(mut self, field: &str, extract: F) -> Self
+    where
+        F: Fn(&SagaContext, &[u8]) -> Option<Box<str>> + Send + Sync + 'static,
+    {
+        self.index_fields.push((field.into(), Arc::new(extract)));
+        self
+    }
+
+    /// Allocates the ids of [`SagaInitiator::new_saga_context`]; random ids
+    /// when unset.
+    pub fn with_saga_id_allocator(mut self, allocator: Arc<dyn SagaIdAllocator>) -> Self {
+        self.saga_ids = allocator;
+        self
+    }
+
+    /// Start context of a new saga, with a freshly allocated id.
+    pub fn new_saga_context(
+        &self,
+        saga_type: impl Into<SagaType>,
+        first_step: impl Into<StepName>,
+        initiator_peer_id: PeerId,
+    ) -> Result<SagaContext, SagaIdAllocatorError> {
+        Ok(SagaContext::start(
+            self.saga_ids.allocate()?,
+            saga_type.into(),
+            first_step.into(),
+            initiator_peer_id,
+        ))
+    }
+
+    pub fn bus(&self) -> &SagaChoreographyBus {
+        &self.bus
+    }
+
+    /// Starts the saga through [`SagaChoreographyBus::start_saga`] unless it
+    /// duplicates a recent start. A dedupe store failure suppresses the start,
+    /// as a failed dedupe check does in the participant handlers.
+    pub fn start_saga(
+        &self,
+        context: SagaContext,
+        payload: Vec<u8>,
+    ) -> Result<SagaInitiation, SagaBusPublishError> {
+        if self.require_live_participants {
+            let missing_steps = self.missing_participants(&context);
+            if !missing_steps.is_empty() {
+                self.refused.increment();
+                tracing::warn!(
+                    target: "core::saga",
+                    event = "saga_start_missing_participants",
+                    saga_type = context.saga_type.as_ref(),
+                    saga_id = context.saga_id.get(),
+                    missing_steps = ?missing_steps
+                );
+                return Err(SagaBusPublishError::MissingParticipants {
+                    saga_id: context.saga_id,
+                    saga_type: context.saga_type,
+                    missing_steps,
+                });
+            }
+        }
+        if let Some(duplicate_key) = self.duplicate_of_recent_start(&context, &payload) {
+            self.suppressed.increment();
+            tracing::info!(
+                target: "core::saga",
+                event = "saga_start_suppressed",
+                saga_type = context.saga_type.as_ref(),
+                saga_id = context.saga_id.get(),
+                duplicate_key = duplicate_key.as_ref()
+            );
+            return Ok(SagaInitiation::Suppressed { duplicate_key });
+        }
+        let indexed = self.index_values(&context, &payload);
+        let Some(redelivery) = &self.redelivery else {
+            let saga_id = context.saga_id;
+            let admission = self.bus.start_saga(context, payload)?;
+            self.admitted(saga_id, admission, indexed);
+            return Ok(SagaInitiation::Admitted(admission));
+        };
+
+        let saga_id = context.saga_id;
+        let expected = self.bus.saga_start_steps(&context);
+        let event = SagaChoreographyEvent::SagaStarted {
+            context: context.clone(),
+            payload: payload.clone(),
+        };
+        let outbox_id = match redelivery.journal.record_outgoing(saga_id, &event) {
+            Ok(outbox_id) => outbox_id,
+            Err(err) => {
+                tracing::error!(
+                    target: "core::saga",
+                    event = "saga_outbox_record_failed",
+                    saga_id = saga_id.get(),
+                    error = %err
+                );
+                None
+            }
+        };
+        // Tracked before publishing: a participant may answer before
+        // `start_saga` returns.
+        redelivery.lock().insert(
+            saga_id,
+            UnackedStart::new(event, outbox_id, self.now_millis(), expected),
+        );
+        let result = self.bus.start_saga(context, payload);
+        if !matches!(result, Ok(SagaAdmission::Started { .. })) {
+            redelivery.lock().remove(&saga_id);
+            redelivery.mark_sent(saga_id, outbox_id);
+        }
+        let admission = result?;
+        self.admitted(saga_id, admission, indexed);
+        Ok(SagaInitiation::Admitted(admission))
+    }
+
+    /// Subscribes to `saga_type` so that any event of a started saga other
+    /// than its `SagaStarted` acknowledges the start. `None` without
+    /// redelivery.
+    pub fn subscribe_acks(&self, saga_type: &str) -> Option<EventSubscription> {
+        let redelivery = self.redelivery.clone()?;
+        Some(self.bus.subscribe_saga_type_fn(saga_type, move |event| {
+            redelivery.acknowledge(event);
+            true
+        }))
+    }
+
+    /// Acknowledges the start of `event`'s saga, for initiators that feed bus
+    /// events themselves instead of calling
+    /// [`SagaInitiator::subscribe_acks`].
+    pub fn ingest(&self, event: &SagaChoreographyEvent) {
+        if let Some(redelivery) = &self.redelivery {
+            redelivery.acknowledge(event);
+        }
+    }
+
+    /// Publishes every start unacknowledged for longer than the ack timeout
+    /// again and abandons those past the redelivery limit. Returns the
+    /// number republished.
+    pub fn redeliver_unacknowledged(&self) -> usize {
+        let Some(redelivery) = &self.redelivery else {
+            return 0;
+        };
+        let now = self.now_millis();
+        let timeout_millis = redelivery.policy.ack_timeout.as_millis() as u64;
+        let mut due = Vec::new();
+        let mut abandoned = Vec::new();
+        {
+            let mut unacked = redelivery.lock();
+            unacked.retain(|saga_id, start| {
+                if now.saturating_sub(start.published_at_millis) <= timeout_millis {
+                    return true;
+                }
+                if start.redeliveries >= redelivery.policy.max_redeliveries {
+                    abandoned.push((*saga_id, start.outbox_id));
+                    return false;
+                }
+                start.redeliveries += 1;
+                start.published_at_millis = now;
+                due.push((start.event.clone(), start.redeliveries));
+                true
+            });
+        }
+        for (saga_id, outbox_id) in abandoned {
+            self.abandoned.increment();
+            tracing::error!(
+                target: "core::saga",
+                event = "saga_start_redelivery_exhausted",
+                saga_id = saga_id.get(),
+                max_redeliveries = redelivery.policy.max_redeliveries
+            );
+            redelivery.mark_sent(saga_id, outbox_id);
+        }
+        let redelivered = due.len();
+        for (event, attempt) in due {
+            self.redelivered.increment();
+            let saga_id = event.context().saga_id;
+            tracing::warn!(
+                target: "core::saga",
+                event = "saga_start_redelivered",
+                saga_id = saga_id.get(),
+                attempt
+            );
+            if let Err(err) = self.bus.publish_strict(event) {
+                tracing::warn!(
+                    target: "core::saga",
+                    event = "saga_start_redelivery_publish_failed",
+                    saga_id = saga_id.get(),
+                    error = ?err
+                );
+            }
+        }
+        redelivered
+    }
+
+    /// Tracks the starts still staged in the outbox, e.g. after a restart,
+    /// as if they had just been published, with the acks recorded for them
+    /// folded in. Starts every step acknowledged are marked sent instead.
+    /// The next [`SagaInitiator::redeliver_unacknowledged`] past the ack
+    /// timeout publishes the rest again. Returns the number resumed.
+    pub fn resume_redelivery(&self) -> Result<usize, JournalError> {
+        let Some(redelivery) = &self.redelivery else {
+            return Ok(0);
+        };
+        let now = self.now_millis();
+        let mut acked = Vec::new();
+        let mut resumed = 0;
+        {
+            let mut unacked = redelivery.lock();
+            for entry in redelivery.journal.pending_outgoing()? {
+                if !matches!(entry.event, SagaChoreographyEvent::SagaStarted { .. })
+                    || unacked.contains_key(&entry.saga_id)
+                {
+                    continue;
+                }
+                let expected = self.bus.saga_start_steps(entry.event.context());
+                let mut start =
+                    UnackedStart::new(entry.event, Some(entry.outbox_id), now, expected);
+                for ack in redelivery.journal.incoming_history(entry.saga_id)? {
+                    if acknowledges_start(&ack.event) {
+                        start.acknowledge(&ack.event);
+                    }
+                }
+                if start.awaiting.is_empty() {
+                    acked.push((entry.saga_id, entry.outbox_id));
+                    continue;
+                }
+                tracing::info!(
+                    target: "core::saga",
+                    event = "saga_start_redelivery_resumed",
+                    saga_id = entry.saga_id.get(),
+                    awaiting = ?start.awaiting
+                );
+                unacked.insert(entry.saga_id, start);
+                resumed += 1;
+            }
+        }
+        for (saga_id, outbox_id) in acked {
+            redelivery.mark_sent(saga_id, Some(outbox_id));
+        }
+        Ok(resumed)
+    }
+
+    /// Published starts some step has not acknowledged yet, by saga id.
+    pub fn outstanding_acks(&self) -> Vec<OutstandingStart> {
+        self.redelivery
+            .as_ref()
+            .map_or_else(Vec::new, |redelivery| {
+                redelivery
+                    .lock()
+                    .iter()
+                    .map(|(saga_id, start)| start.outstanding(*saga_id))
+                    .collect()
+            })
+    }
+
+    /// Starts published and not acknowledged yet.
+    pub fn unacknowledged_len(&self) -> usize {
+        self.redelivery
+            .as_ref()
+            .map_or(0, |redelivery| redelivery.lock().len())
+    }
+
+    pub fn stats(&self) -> SagaInitiatorStats {
+        SagaInitiatorStats {
+            started: self.started.get(),
+            queued: self.queued.get(),
+            suppressed: self.suppressed.get(),
+            refused: self.refused.get(),
+            redelivered: self.redelivered.get(),
+            abandoned: self.abandoned.get(),
+        }
+    }
+
+    fn admitted(&self, saga_id: SagaId, admission: SagaAdmission, indexed: Vec<(&str, Box<str>)>) {
+        match admission {
+            SagaAdmission::Started { .. } => self.started.increment(),
+            SagaAdmission::Queued { .. } => self.queued.increment(),
+        }
+        let Some(index) = &self.saga_index else {
+            return;
+        };
+        for (field, value) in indexed {
+            if let Err(err) = index.index(saga_id, field, &value) {
+                tracing::warn!(
+                    target: "core::saga",
+                    event = "saga_index_write_failed",
+                    saga_id = saga_id.get(),
+                    field,
+                    error = %err
+                );
+            }
+        }
+    }
+
+    /// Values of the registered index fields for a start, extracted before
+    /// the payload moves to the bus.
+    fn index_values(&self, context: &SagaContext, payload: &[u8]) -> Vec<(&str, Box<str>)> {
+        if self.saga_index.is_none() {
+            return Vec::new();
+        }
+        self.index_fields
+            .iter()
+            .filter_map(|(field, extract)| {
+                extract(context, payload).map(|value| (field.as_ref(), value))
+            })
+            .collect()
+    }
+
+    fn now_millis(&self) -> u64 {
+        self.clock
+            .as_ref()
+            .map_or_else(SagaContext::now_millis, |clock| clock())
+    }
+
+    fn missing_participants(&self, context: &SagaContext) -> Vec<StepName> {
+        let steps = self
+            .bus
+            .workflow_steps(&context.saga_type, context.workflow_version);
+        let steps: Vec<&str> = match &steps {
+            Some(steps) => steps.clone(),
+            None => vec![context.step_name.as_ref()],
+        };
+        match self.bus.participant_registry() {
+            Some(registry) => registry.missing_steps(&context.saga_type, &steps),
+            None => steps.into_iter().map(StepName::from).collect(),
+        }
+    }
+
+    fn duplicate_of_recent_start(&self, context: &SagaContext, payload: &[u8]) -> Option<Box<str>> {
+        let window = self.duplicate_window.as_ref()?;
+        let duplicate_key = (window.key)(context, payload)?;
+        let now = self.now_millis();
+        let bucket = now / window.window_millis;
+        let key = DedupeKey::named(&format!("{}/{}", context.saga_type, duplicate_key));
+
+        if bucket > 0 && self.dedupe.contains(SagaId::new(bucket - 1), key) {
+            return Some(duplicate_key);
+        }
+        let first = match self.dedupe.check_and_mark(SagaId::new(bucket), key) {
+            Ok(first) => first,
+            Err(err) => {
+                tracing::error!(
+                    target: "core::saga",
+                    event = "saga_start_dedupe_failed",
+                    saga_type = context.saga_type.as_ref(),
+                    saga_id = context.saga_id.get(),
+                    error = %err
+                );
+                false
+            }
+        };
+        if bucket > 1 {
+            if let Err(err) = self.dedupe.prune(SagaId::new(bucket - 2)) {
+                tracing::warn!(
+                    target: "core::saga",
+                    event = "saga_start_dedupe_prune_failed",
+                    bucket = bucket - 2,
+                    error = %err
+                );
+            }
+        }
+        (!first).then_some(duplicate_key)
+    }
+}
+
+impl<D> std::fmt::Debug for SagaInitiator<D> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SagaInitiator")
+            .field(
+                "duplicate_window_millis",
+                &self
+                    .duplicate_window
+                    .as_ref()
+                    .map(|window| window.window_millis),
+            )
+            .field("suppressed", &self.suppressed.get())
+            .field(
+                "unacknowledged_len",
+                &self
+                    .redelivery
+                    .as_ref()
+                    .map_or(0, |redelivery| redelivery.lock().len()),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use super::*;
+    use crate::{
+        DeterministicContextBuilder, InMemoryDedupe, InMemoryJournal, ParticipantAnnounced,
+        ParticipantRegistry, SagaWorkflowContract, SagaWorkflowStepContract, TerminalPolicy,
+        WorkflowDependencySpec,
+    };
+
+    struct OrderLifecycle;
+
+    impl SagaWorkflowContract for OrderLifecycle {
+        fn saga_type() -> &'static str {
+            "order_lifecycle"
+        }
+
+        fn first_step() -> &'static str {
+            "create_order"
+        }
+
+        fn steps() -> &'static [SagaWorkflowStepContract] {
+            &[SagaWorkflowStepContract {
+                step_name: "create_order",
+                participant_id: "order-manager",
+                depends_on: WorkflowDependencySpec::OnSagaStart,
+            }]
+        }
+
+        fn terminal_policy() -> TerminalPolicy {
+            TerminalPolicy::order_lifecycle_default()
+        }
+    }
+
+    /// A bus that admits `order_lifecycle` starts, with its resolver attached.
+    fn order_lifecycle_bus() -> (SagaChoreographyBus, EventSubscription) {
+        let bus = SagaChoreographyBus::new();
+        bus.register_workflow_contract_provider::<OrderLifecycle>()
+            .unwrap();
+        bus.register_bound_workflow_step("order_lifecycle", "create_order")
+            .unwrap();
+        let resolver = bus
+            .attach_terminal_resolver_for_contract::<OrderLifecycle>("test-resolver")
+            .unwrap();
+        (bus, resolver)
+    }
+
+    fn order_context(saga_id: u64) -> SagaContext {
+        DeterministicContextBuilder::default()
+            .with_saga_id(saga_id)
+            .with_step_name("create_order")
+            .build()
+    }
+
+    #[test]
+    fn duplicate_starts_within_window_are_suppressed() {
+        let (bus, _resolver) = order_lifecycle_bus();
+        let delivered = Arc::new(AtomicU64::new(0));
+        let count = Arc::clone(&delivered);
+        let _sub = bus.subscribe_saga_type_fn("order_lifecycle", move |event| {
+            if let SagaChoreographyEvent::SagaStarted { .. } = event {
+                count.fetch_add(1, Ordering::Relaxed);
+            }
+            true
+        });
+        let now = Arc::new(AtomicU64::new(10_000));
+        let clock = Arc::clone(&now);
+        let initiator = SagaInitiator::new(bus, InMemoryDedupe::new())
+            .suppress_duplicates_within(Duration::from_secs(1), |_, payload| {
+                Some(String::from_utf8_lossy(payload).into())
+            })
+            .with_clock(Arc::new(move || clock.load(Ordering::Relaxed)));
+        let start = |initiator: &SagaInitiator<InMemoryDedupe>, payload: &[u8]| {
+            initiator
+                .start_saga(order_context(1), payload.to_vec())
+                .unwrap()
+        };
+
+        assert!(matches!(
+            start(&initiator, b"order-1"),
+            SagaInitiation::Admitted(SagaAdmission::Started { .. })
+        ));
+        now.store(10_900, Ordering::Relaxed);
+        assert_eq!(
+            start(&initiator, b"order-1"),
+            SagaInitiation::Suppressed {
+                duplicate_key: "order-1".into()
+            }
+        );
+        assert!(matches!(
+            start(&initiator, b"order-2"),
+            SagaInitiation::Admitted(_)
+        ));
+        now.store(13_000, Ordering::Relaxed);
+        assert!(matches!(
+            start(&initiator, b"order-1"),
+            SagaInitiation::Admitted(_)
+        ));
+
+        assert_eq!(delivered.load(Ordering::Relaxed), 3);
+        assert_eq!(
+            initiator.stats(),
+            SagaInitiatorStats {
+                started: 3,
+                queued: 0,
+                suppressed: 1,
+                refused: 0,
+                redelivered: 0,
+                abandoned: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn starts_refused_until_first_step_is_announced() {
+        let (bus, _resolver) = order_lifecycle_bus();
+        let _participant = bus.subscribe_saga_type_fn("order_lifecycle", |_| true);
+        let registry = ParticipantRegistry::new();
+        bus.attach_participant_registry(registry.clone());
+        let initiator =
+            SagaInitiator::new(bus.clone(), InMemoryDedupe::new()).require_live_participants();
+        let context = order_context(1);
+
+        let Err(SagaBusPublishError::MissingParticipants { missing_steps, .. }) =
+            initiator.start_saga(context.clone(), Vec::new())
+        else {
+            panic!("expected the start to be refused");
+        };
+        assert_eq!(missing_steps, vec![StepName::from("create_order")]);
+
+        bus.announce(ParticipantAnnounced {
+            peer_id: [1; 32],
+            saga_types: vec![context.saga_type.clone()],
+            steps: vec![context.step_name.clone()],
+            announced_at_millis: SagaContext::now_millis(),
+        });
+        assert!(matches!(
+            initiator.start_saga(context, Vec::new()),
+            Ok(SagaInitiation::Admitted(_))
+        ));
+        assert_eq!(initiator.stats().refused, 1);
+    }
+
+    #[test]
+    fn unacknowledged_start_is_redelivered_until_acked_or_abandoned() {
+        let (bus, _resolver) = order_lifecycle_bus();
+        let delivered = Arc::new(AtomicU64::new(0));
+        let count = Arc::clone(&delivered);
+        let _sub = bus.subscribe_saga_type_fn("order_lifecycle", move |event| {
+            if let SagaChoreographyEvent::SagaStarted { .. } = event {
+                count.fetch_add(1, Ordering::Relaxed);
+            }
+            true
+        });
+        let journal = Arc::new(InMemoryJournal::new());
+        let now = Arc::new(AtomicU64::new(0));
+        let clock = Arc::clone(&now);
+        let initiator = SagaInitiator::new(bus.clone(), InMemoryDedupe::new())
+            .with_redelivery(
+                journal.clone(),
+                SagaRedeliveryPolicy {
+                    ack_timeout: Duration::from_millis(100),
+                    max_redeliveries: 1,
+                },
+            )
+            .with_clock(Arc::new(move || clock.load(Ordering::Relaxed)));
+        let _acks = initiator.subscribe_acks("order_lifecycle").unwrap();
+
+        let acked = order_context(1);
+        initiator.start_saga(acked.clone(), Vec::new()).unwrap();
+        initiator.start_saga(order_context(2), Vec::new()).unwrap();
+        assert_eq!(journal.pending_outgoing().unwrap().len(), 2);
+        bus.publish(SagaChoreographyEvent::StepStarted { context: acked });
+        assert_eq!(initiator.unacknowledged_len(), 1);
+
+        now.store(150, Ordering::Relaxed);
+        assert_eq!(initiator.redeliver_unacknowledged(), 1);
+        now.store(300, Ordering::Relaxed);
+        assert_eq!(initiator.redeliver_unacknowledged(), 0);
+
+        assert_eq!(delivered.load(Ordering::Relaxed), 3);
+        assert_eq!(initiator.unacknowledged_len(), 0);
+        assert!(journal.pending_outgoing().unwrap().is_empty());
+        let stats = initiator.stats();
+        assert_eq!((stats.redelivered, stats.abandoned), (1, 1));
+    }
+
+    struct OrderFanOut;
+
+    impl SagaWorkflowContract for OrderFanOut {
+        fn saga_type() -> &'static str {
+            "order_fanout"
+        }
+
+        fn first_step() -> &'static str {
+            "create_order"
+        }
+
+        fn steps() -> &'static [SagaWorkflowStepContract] {
+            &[
+                SagaWorkflowStepContract {
+                    step_name: "create_order",
+                    participant_id: "order-manager",
+                    depends_on: WorkflowDependencySpec::OnSagaStart,
+                },
+                SagaWorkflowStepContract {
+                    step_name: "hedge",
+                    participant_id: "hedger",
+                    depends_on: WorkflowDependencySpec::OnSagaStart,
+                },
+                SagaWorkflowStepContract {
+                    step_name: "settle",
+                    participant_id: "settlement",
+                    depends_on: WorkflowDependencySpec::After("create_order"),
+                },
+            ]
+        }
+
+        fn terminal_policy() -> TerminalPolicy {
+            TerminalPolicy::new(
+                "order_fanout".into(),
+                "order_fanout/default".into(),
+                crate::FailureAuthority::AnyParticipant,
+                crate::SuccessCriteria::AllOf(["settle".into()].into_iter().collect()),
+                Duration::from_secs(30),
+                Duration::from_secs(30),
+                &[],
+            )
+        }
+    }
+
+    #[test]
+    fn awaited_acks_survive_an_initiator_restart() {
+        let bus = SagaChoreographyBus::new();
+        bus.register_workflow_contract_provider::<OrderFanOut>()
+            .unwrap();
+        for step in ["create_order", "hedge", "settle"] {
+            bus.register_bound_workflow_step("order_fanout", step)
+                .unwrap();
+        }
+        let _resolver = bus
+            .attach_terminal_resolver_for_contract::<OrderFanOut>("test-resolver")
+            .unwrap();
+        let _participant = bus.subscribe_saga_type_fn("order_fanout", |_| true);
+        let journal = Arc::new(InMemoryJournal::new());
+        let initiator = |bus: &SagaChoreographyBus| {
+            SagaInitiator::new(bus.clone(), InMemoryDedupe::new())
+                .with_redelivery(journal.clone(), SagaRedeliveryPolicy::default())
+        };
+        let context = |saga_id: u64, step_name: &str| {
+            DeterministicContextBuilder::default()
+                .with_saga_id(saga_id)
+                .with_saga_type("order_fanout")
+                .with_step_name(step_name)
+                .build()
+        };
+        let step_started = |saga_id: u64, step_name: &str| SagaChoreographyEvent::StepStarted {
+            context: context(saga_id, step_name),
+        };
+
+        let before_restart = initiator(&bus);
+        let acks = before_restart.subscribe_acks("order_fanout").unwrap();
+        for saga_id in [1, 2] {
+            before_restart
+                .start_saga(context(saga_id, "create_order"), Vec::new())
+                .unwrap();
+        }
+        bus.publish(step_started(1, "create_order"));
+        bus.publish(step_started(1, "create_order"));
+        // A later step implies both triggered steps saw the start.
+        bus.publish(step_started(2, "settle"));
+        drop(acks);
+        drop(before_restart);
+
+        let after_restart = initiator(&bus);
+        assert_eq!(after_restart.resume_redelivery().unwrap(), 1);
+        assert_eq!(
+            after_restart.outstanding_acks(),
+            vec![OutstandingStart {
+                saga_id: SagaId::new(1),
+                acknowledged: vec!["create_order".into()],
+                awaiting: vec!["hedge".into()],
+                redeliveries: 0,
+            }]
+        );
+
+        after_restart.ingest(&step_started(1, "hedge"));
+        assert_eq!(after_restart.unacknowledged_len(), 0);
+        assert!(journal.pending_outgoing().unwrap().is_empty());
+        assert_eq!(initiator(&bus).resume_redelivery().unwrap(), 0);
+    }
+
+    #[test]
+    fn admitted_starts_are_indexed_by_payload_fields() {
+        let (bus, _resolver) = order_lifecycle_bus();
+        let _participant = bus.subscribe_saga_type_fn("order_lifecycle", |_| true);
+        let index = Arc::new(crate::InMemorySagaIndex::new());
+        let initiator = SagaInitiator::new(bus, InMemoryDedupe::new())
+            .with_saga_index(index.clone())
+            .index_field("client_order_id", |_, payload| {
+                Some(String::from_utf8_lossy(payload).into())
+            })
+            .index_field("venue", |_, _| None);
+
+        for (saga_id, client_order_id) in [(1, "my_client-42"), (2, "my_client-43")] {
+            initiator
+                .start_saga(order_context(saga_id), client_order_id.as_bytes().to_vec())
+                .unwrap();
+        }
+
+        assert_eq!(
+            index.find("client_order_id", "my_client-42").unwrap(),
+            vec![SagaId::new(1)]
+        );
+        assert!(index.find("venue", "binance").unwrap().is_empty());
+        assert_eq!(index.len(), 2);
+    }
+}
@@ -0,0 +1,449 @@
+//! Startup integrity check across a participant journal and dedupe store.
+//!
+//! The journal and the dedupe store are written separately, so a crash can
+//! leave them disagreeing: a saga pruned from the journal but not from the
+//! dedupe store, journal history whose dedupe records were lost, or a
+//! terminal saga whose cleanup never ran. [`verify_consistency`] lists these
+//! as [`ConsistencyIssue`]s together with a [`RepairPlan`]; apply the plan
+//! with [`RepairPlan::apply`] before collecting startup recovery events.
+
+use std::collections::BTreeSet;
+
+use crate::journal::last_progress_entry;
+use crate::{
+    DedupeError, DedupeKey, JournalEntry, JournalError, ParticipantDedupeStore, ParticipantEvent,
+    ParticipantJournal, RecoveryPolicy, SagaContext, SagaId,
+};
+
+/// Reason recorded on sagas quarantined because their journal has a gap.
+pub const JOURNAL_GAP_QUARANTINE_REASON: &str = "integrity_check_journal_gap";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConsistencyError {
+    #[error("journal error: {0}")]
+    Journal(#[from] JournalError),
+    #[error("dedupe error: {0}")]
+    Dedupe(#[from] DedupeError),
+}
+
+/// A disagreement found by [`verify_consistency`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConsistencyIssue {
+    /// The journal holds history for the saga but the dedupe store has no
+    /// record of it, so redelivered events would be processed again.
+    MissingDedupe { saga_id: SagaId },
+    /// The dedupe store holds records for a saga the journal no longer
+    /// knows, typically a terminal prune interrupted between the two stores.
+    OrphanedDedupe { saga_id: SagaId },
+    /// A journal entry is missing the entry it must follow.
+    JournalGap {
+        saga_id: SagaId,
+        sequence: u64,
+        event_type: &'static str,
+        missing: &'static str,
+    },
+    /// Sequence numbers of a saga's entries do not increase.
+    SequenceRegression {
+        saga_id: SagaId,
+        sequence: u64,
+        previous: u64,
+    },
+    /// The saga ended for this participant longer ago than the recovery
+    /// policy allows, yet neither store was pruned.
+    StaleTerminal { saga_id: SagaId },
+}
+
+impl ConsistencyIssue {
+    pub fn saga_id(&self) -> SagaId {
+        match self {
+            Self::MissingDedupe { saga_id }
+            | Self::OrphanedDedupe { saga_id }
+            | Self::JournalGap { saga_id, .. }
+            | Self::SequenceRegression { saga_id, .. }
+            | Self::StaleTerminal { saga_id } => *saga_id,
+        }
+    }
+}
+
+/// One step of a [`RepairPlan`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RepairAction {
+    /// Re-mark the dedupe keys of the saga's recorded incoming events.
+    RestoreDedupe {
+        saga_id: SagaId,
+        keys: Vec<DedupeKey>,
+    },
+    /// Drop the saga's dedupe records.
+    PruneDedupe { saga_id: SagaId },
+    /// Drop the saga from both stores, as terminal cleanup would have.
+    PruneSaga { saga_id: SagaId },
+    /// Append a `Quarantined` entry so recovery leaves the saga to an
+    /// operator instead of resuming it.
+    Quarantine { saga_id: SagaId, reason: Box<str> },
+}
+
+/// Repairs for the issues of a [`ConsistencyReport`], in saga id order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RepairPlan {
+    pub actions: Vec<RepairAction>,
+}
+
+impl RepairPlan {
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// Applies every action and returns how many were applied. Stops at the
+    /// first storage error; actions are idempotent, so a rerun of
+    /// [`verify_consistency`] picks up the rest.
+    pub fn apply<J, D>(&self, journal: &J, dedupe: &D) -> Result<usize, ConsistencyError>
+    where
+        J: ParticipantJournal + ?Sized,
+        D: ParticipantDedupeStore + ?Sized,
+    {
+        for action in &self.actions {
+            match action {
+                RepairAction::RestoreDedupe { saga_id, keys } => {
+                    for key in keys {
+                        dedupe.mark_processed(*saga_id, *key)?;
+                    }
+                }
+                RepairAction::PruneDedupe { saga_id } => dedupe.prune(*saga_id)?,
+                RepairAction::PruneSaga { saga_id } => {
+                    journal.prune(*saga_id)?;
+                    dedupe.prune(*saga_id)?;
+                }
+                RepairAction::Quarantine { saga_id, reason } => {
+                    journal.append(
+                        *saga_id,
+                        ParticipantEvent::Quarantined {
+                            reason: reason.clone(),
+                            quarantined_at_millis: SagaContext::now_millis(),
+                        },
+                    )?;
+                }
+            }
+            tracing::warn!(
+                target: "core::saga",
+                event = "saga_integrity_repair_applied",
+                ?action
+            );
+        }
+        Ok(self.actions.len())
+    }
+}
+
+/// Outcome of [`verify_consistency`].
+#[derive(Clone, Debug, Default)]
+pub struct ConsistencyReport {
+    pub issues: Vec<ConsistencyIssue>,
+    pub plan: RepairPlan,
+    /// Whether the dedupe store could list its sagas. Without a listing,
+    /// only journal gaps are checked.
+    pub dedupe_checked: bool,
+}
+
+impl ConsistencyReport {
+    pub fn is_consistent(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Compares `journal` and `dedupe` using the default [`RecoveryPolicy`].
+pub fn verify_consistency<J, D>(
+    journal: &J,
+    dedupe: &D,
+) -> Result<ConsistencyReport, ConsistencyError>
+where
+    J: ParticipantJournal + ?Sized,
+    D: ParticipantDedupeStore + ?Sized,
+{
+    verify_consistency_at(
+        journal,
+        dedupe,
+        SagaContext::now_millis(),
+        RecoveryPolicy::default(),
+    )
+}
+
+/// Compares `journal` and `dedupe` as of `now_ms`. Terminal sagas count as
+/// stale once older than `policy.stale_after_ms`.
+pub fn verify_consistency_at<J, D>(
+    journal: &J,
+    dedupe: &D,
+    now_ms: u64,
+    policy: RecoveryPolicy,
+) -> Result<ConsistencyReport, ConsistencyError>
+where
+    J: ParticipantJournal + ?Sized,
+    D: ParticipantDedupeStore + ?Sized,
+{
+    let journaled: BTreeSet<SagaId> = journal.list_sagas()?.into_iter().collect();
+    let deduped: Option<BTreeSet<SagaId>> =
+        dedupe.list_sagas()?.map(|ids| ids.into_iter().collect());
+    let mut report = ConsistencyReport {
+        dedupe_checked: deduped.is_some(),
+        ..ConsistencyReport::default()
+    };
+
+    let all: BTreeSet<SagaId> = journaled
+        .iter()
+        .chain(deduped.iter().flatten())
+        .copied()
+        .collect();
+    for saga_id in all {
+        if !journaled.contains(&saga_id) {
+            report
+                .issues
+                .push(ConsistencyIssue::OrphanedDedupe { saga_id });
+            report
+                .plan
+                .actions
+                .push(RepairAction::PruneDedupe { saga_id });
+            continue;
+        }
+        let entries = journal.read(saga_id)?;
+        let in_dedupe = deduped.as_ref().map(|ids| ids.contains(&saga_id));
+
+        if in_dedupe == Some(true) && is_stale_terminal(&entries, now_ms, policy) {
+            report
+                .issues
+                .push(ConsistencyIssue::StaleTerminal { saga_id });
+            report
+                .plan
+                .actions
+                .push(RepairAction::PruneSaga { saga_id });
+            continue;
+        }
+
+        if in_dedupe == Some(false) && !entries.is_empty() {
+            report
+                .issues
+                .push(ConsistencyIssue::MissingDedupe { saga_id });
+            let keys: Vec<DedupeKey> = journal
+                .incoming_history(saga_id)?
+                .iter()
+                .map(|entry| DedupeKey::from_event(&entry.event))
+                .collect();
+            // Without an incoming history there is nothing to restore from;
+            // the issue stays in the report for an operator.
+            if !keys.is_empty() {
+                report
+                    .plan
+                    .actions
+                    .push(RepairAction::RestoreDedupe { saga_id, keys });
+            }
+        }
+
+        let gaps = journal_gaps(saga_id, &entries);
+        let already_quarantined = matches!(
+            last_progress_entry(&entries).map(|entry| &entry.event),
+            Some(ParticipantEvent::Quarantined { .. })
+        );
+        if !gaps.is_empty() && !already_quarantined {
+            report.plan.actions.push(RepairAction::Quarantine {
+                saga_id,
+                reason: JOURNAL_GAP_QUARANTINE_REASON.into(),
+            });
+        }
+        report.issues.extend(gaps);
+    }
+
+    for issue in &report.issues {
+        tracing::warn!(
+            target: "core::saga",
+            event = "saga_integrity_issue",
+            saga_id = issue.saga_id().get(),
+            ?issue
+        );
+    }
+    Ok(report)
+}
+
+fn is_stale_terminal(entries: &[JournalEntry], now_ms: u64, policy: RecoveryPolicy) -> bool {
+    let Some(last) = last_progress_entry(entries) else {
+        return false;
+    };
+    last.event.is_settled()
+        && now_ms.saturating_sub(last.recorded_at_millis) > policy.stale_after_ms
+}
+
+fn journal_gaps(saga_id: SagaId, entries: &[JournalEntry]) -> Vec<ConsistencyIssue> {
+    let mut issues = Vec::new();
+    let mut previous: Option<u64> = None;
+    let mut step_started = false;
+    let mut step_completed = false;
+    let mut compensation_started = false;
+    let mut quarantined = false;
+    for entry in entries {
+        if let Some(previous) = previous.filter(|previous| entry.sequence <= *previous) {
+            issues.push(ConsistencyIssue::SequenceRegression {
+                saga_id,
+                sequence: entry.sequence,
+                previous,
+            });
+        }
+        previous = Some(entry.sequence);
+
+        let missing = match &entry.event {
+            ParticipantEvent::StepExecutionCompleted { .. } if !step_started => {
+                Some("step_execution_started")
+            }
+            ParticipantEvent::CompensationStarted { .. } if !step_completed => {
+                Some("step_execution_completed")
+            }
+            // Operator retries of a quarantined compensation append the
+            // outcome directly after `Quarantined`.
+            ParticipantEvent::CompensationCompleted { .. }
+            | ParticipantEvent::CompensationFailed { .. }
+                if !compensation_started && !quarantined =>
+            {
+                Some("compensation_started")
+            }
+            _ => None,
+        };
+        if let Some(missing) = missing {
+            issues.push(ConsistencyIssue::JournalGap {
+                saga_id,
+                sequence: entry.sequence,
+                event_type: entry.event.event_type(),
+                missing,
+            });
+        }
+
+        match &entry.event {
+            ParticipantEvent::StepExecutionStarted { .. } => step_started = true,
+            ParticipantEvent::StepExecutionCompleted { .. }
+            | ParticipantEvent::StepExecutionFailed {
+                requires_compensation: true,
+                ..
+            } => step_completed = true,
+            ParticipantEvent::CompensationStarted { .. } => compensation_started = true,
+            ParticipantEvent::Quarantined { .. } => quarantined = true,
+            _ => {}
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{saga_started, DeterministicContextBuilder, InMemoryDedupe, InMemoryJournal};
+
+    const POLICY: RecoveryPolicy = RecoveryPolicy {
+        stale_after_ms: 60_000,
+    };
+
+    fn started(at: u64) -> ParticipantEvent {
+        ParticipantEvent::StepExecutionStarted {
+            attempt: 1,
+            started_at_millis: at,
+        }
+    }
+
+    #[test]
+    fn consistent_stores_produce_an_empty_plan() {
+        let journal = InMemoryJournal::new();
+        let dedupe = InMemoryDedupe::new();
+        let saga_id = SagaId::new(1);
+        journal.append(saga_id, started(0)).unwrap();
+        dedupe
+            .mark_processed(saga_id, DedupeKey::named("seen"))
+            .unwrap();
+
+        let report =
+            verify_consistency_at(&journal, &dedupe, SagaContext::now_millis(), POLICY).unwrap();
+        assert!(report.dedupe_checked);
+        assert!(report.is_consistent());
+        assert!(report.plan.is_empty());
+    }
+
+    #[test]
+    fn plan_repairs_orphaned_missing_and_gapped_sagas() {
+        let journal = InMemoryJournal::new();
+        let dedupe = InMemoryDedupe::new();
+
+        let orphaned = SagaId::new(1);
+        dedupe
+            .mark_processed(orphaned, DedupeKey::named("seen"))
+            .unwrap();
+
+        let context = DeterministicContextBuilder::default()
+            .with_saga_id(2)
+            .build();
+        let missing = context.saga_id;
+        let incoming = saga_started(context, vec![1]);
+        journal
+            .record_incoming(missing, DedupeKey::from_event(&incoming), &incoming)
+            .unwrap();
+        journal.append(missing, started(0)).unwrap();
+
+        let gapped = SagaId::new(3);
+        dedupe
+            .mark_processed(gapped, DedupeKey::named("seen"))
+            .unwrap();
+        journal
+            .append(
+                gapped,
+                ParticipantEvent::StepExecutionCompleted {
+                    output: Vec::new(),
+                    compensation_data: Vec::new(),
+                    completed_at_millis: 0,
+                },
+            )
+            .unwrap();
+
+        let report =
+            verify_consistency_at(&journal, &dedupe, SagaContext::now_millis(), POLICY).unwrap();
+        assert_eq!(report.issues.len(), 3);
+        assert!(matches!(
+            report.issues[2],
+            ConsistencyIssue::JournalGap {
+                missing: "step_execution_started",
+                ..
+            }
+        ));
+
+        assert_eq!(report.plan.apply(&journal, &dedupe).unwrap(), 3);
+        assert!(!dedupe.contains(orphaned, DedupeKey::named("seen")));
+        assert!(dedupe.contains(missing, DedupeKey::from_event(&incoming)));
+        let after =
+            verify_consistency_at(&journal, &dedupe, SagaContext::now_millis(), POLICY).unwrap();
+        // The gap stays visible, but the saga is now quarantined.
+        assert!(after.plan.is_empty());
+    }
+
+    #[test]
+    fn stale_terminal_saga_is_pruned_from_both_stores() {
+        let journal = InMemoryJournal::new();
+        let dedupe = InMemoryDedupe::new();
+        let saga_id = SagaId::new(7);
+        journal.append(saga_id, started(0)).unwrap();
+        journal
+            .append(
+                saga_id,
+                ParticipantEvent::StepExecutionFailed {
+                    error: "rejected".into(),
+                    requires_compensation: false,
+                    failed_at_millis: 0,
+                    details: Vec::new(),
+                },
+            )
+            .unwrap();
+        dedupe
+            .mark_processed(saga_id, DedupeKey::named("seen"))
+            .unwrap();
+
+        // The journal stamps entries with the wall clock.
+        let later = SagaContext::now_millis() + POLICY.stale_after_ms + 1;
+        let report = verify_consistency_at(&journal, &dedupe, later, POLICY).unwrap();
+        assert_eq!(
+            report.issues,
+            vec![ConsistencyIssue::StaleTerminal { saga_id }]
+        );
+        report.plan.apply(&journal, &dedupe).unwrap();
+        assert!(journal.list_sagas().unwrap().is_empty());
+        assert_eq!(dedupe.list_sagas().unwrap(), Some(Vec::new()));
+    }
+}
@@ -11,7 +11,50 @@
 //! In the choreography-based SAGA pattern, each participant maintains its own
 //! journal of events, allowing for independent recovery and replay.
 
-use super::{ParticipantEvent, SagaId};
+use super::{
+    DedupeKey, ParticipantEvent, SagaChoreographyEvent, SagaContext, SagaId, SagaJournalHistory,
+};
+
+#[cfg(feature = "rkyv")]
+pub mod archived;
+pub mod dual_write;
+pub mod history;
+pub mod migrate;
+pub mod routing;
+
+/// What a participant does when the journal rejects a record of a step's
+/// execution or compensation: the `StepExecutionStarted` or
+/// `CompensationStarted` record written before the work runs, and the
+/// completion, failure or quarantine record of its outcome.
+///
+/// Running work whose start was not journaled risks side effects that
+/// recovery cannot see, and publishing an outcome that was not journaled
+/// moves the saga past a step recovery would run again, so anything other
+/// than [`Self::Continue`] holds the work back until the journal accepts the
+/// record.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum JournalFailurePolicy {
+    /// Log the failure and run the work or publish the outcome anyway.
+    #[default]
+    Continue,
+    /// Retry the append up to `attempts` more times, `backoff` apart, then
+    /// refuse. Async handlers wait out a start's backoff on the runtime
+    /// timer. Sync handlers park the start's trigger, and every handler
+    /// holds an outcome back, until the inbox replay at
+    /// `SagaParticipantSupport::journal_retry_at` retries it.
+    Retry {
+        attempts: u32,
+        backoff: std::time::Duration,
+    },
+    /// Skip the step or compensation and keep the triggering event for
+    /// redelivery by the inbox replay helpers. An outcome is held back until
+    /// the next handled event or inbox replay journals it.
+    Park,
+    /// Fail the step, or quarantine the compensation, without running it.
+    /// An outcome the journal rejects quarantines the saga instead of being
+    /// published.
+    Refuse,
+}
 
 /// A trait for participant journal storage implementations.
 ///
@@ -84,6 +127,30 @@ pub trait ParticipantJournal: Send + Sync + 'static {
     /// to read the events.
     fn read(&self, saga_id: SagaId) -> Result<Vec<JournalEntry>, JournalError>;
 
+    /// Passes the entries of `saga_id`, in sequence order, to `visit` as
+    /// zero-copy [`ArchivedJournalRow`](archived::ArchivedJournalRow)s.
+    ///
+    /// The default re-encodes [`read`](Self::read); journals that store
+    /// encoded rows override it to borrow them from storage.
+    #[cfg(feature = "rkyv")]
+    fn read_archived(
+        &self,
+        saga_id: SagaId,
+        visit: &mut dyn FnMut(&[archived::ArchivedJournalRow<'_>]),
+    ) -> Result<(), JournalError> {
+        let encoded = self
+            .read(saga_id)?
+            .iter()
+            .map(crate::encode_journal_entry)
+            .collect::<Result<Vec<_>, _>>()?;
+        let rows = encoded
+            .iter()
+            .map(|row| archived::ArchivedJournalRow::new(row))
+            .collect::<Result<Vec<_>, _>>()?;
+        visit(&rows);
+        Ok(())
+    }
+
     /// Lists all SAGA IDs that have at least one journal entry.
     ///
     /// This is useful for recovery scenarios where you need to identify
@@ -104,6 +171,133 @@ pub trait ParticipantJournal: Send + Sync + 'static {
     /// bounded. Active, non-terminal SAGAs remain journaled for startup
     /// recovery until they reach a terminal event.
     fn prune(&self, saga_id: SagaId) -> Result<(), JournalError>;
+
+    /// Stages an outgoing saga event in the journal outbox before it is
+    /// published.
+    ///
+    /// Emit paths call this first, publish, then call
+    /// [`mark_outgoing_sent`](Self::mark_outgoing_sent). Anything still
+    /// pending after a crash is re-published by the outbox relay.
+    ///
+    /// # Returns
+    ///
+    /// The outbox id assigned to the staged event, or `None` when this
+    /// journal has no outbox table. Callers then publish directly.
+    fn record_outgoing(
+        &self,
+        saga_id: SagaId,
+        event: &SagaChoreographyEvent,
+    ) -> Result<Option<u64>, JournalError> {
+        let _ = (saga_id, event);
+        Ok(None)
+    }
+
+    /// Appends `event` and stages `outgoing` in the outbox in one write, so
+    /// a crash never keeps the decision without the events it produced.
+    ///
+    /// The default appends, then stages each event on its own, logging the
+    /// ones that fail; journals with an outbox table override it with a
+    /// single transaction.
+    ///
+    /// # Returns
+    ///
+    /// The sequence of the appended entry and, for each event of
+    /// `outgoing`, its outbox id, or `None` when it was not staged.
+    fn append_with_outgoing(
+        &self,
+        saga_id: SagaId,
+        event: ParticipantEvent,
+        outgoing: &[SagaChoreographyEvent],
+    ) -> Result<(u64, Vec<Option<u64>>), JournalError> {
+        let sequence = self.append(saga_id, event)?;
+        let outbox_ids = outgoing
+            .iter()
+            .map(|event| {
+                self.record_outgoing(saga_id, event).unwrap_or_else(|err| {
+                    tracing::warn!(
+                        target: "core::saga",
+                        event = "saga_outbox_record_failed",
+                        saga_id = saga_id.get(),
+                        error = %err
+                    );
+                    None
+                })
+            })
+            .collect();
+        Ok((sequence, outbox_ids))
+    }
+
+    /// Lists staged outbox events that have not been marked sent, ordered by
+    /// outbox id.
+    fn pending_outgoing(&self) -> Result<Vec<OutboxEntry>, JournalError> {
+        Ok(Vec::new())
+    }
+
+    /// Marks a staged outbox event as published so the relay skips it.
+    fn mark_outgoing_sent(&self, outbox_id: u64) -> Result<(), JournalError> {
+        let _ = outbox_id;
+        Ok(())
+    }
+
+    /// Persists a raw incoming saga event in the journal inbox together with
+    /// its dedupe key, before the dedupe store is consulted.
+    ///
+    /// The entry stays pending until
+    /// [`mark_incoming_processed`](Self::mark_incoming_processed) is called, so
+    /// an event whose dedupe key was marked right before a crash can still be
+    /// replayed on restart.
+    ///
+    /// # Returns
+    ///
+    /// The inbox id assigned to the event, or `None` when this journal has no
+    /// inbox table.
+    fn record_incoming(
+        &self,
+        saga_id: SagaId,
+        dedupe_key: DedupeKey,
+        event: &SagaChoreographyEvent,
+    ) -> Result<Option<u64>, JournalError> {
+        let _ = (saga_id, dedupe_key, event);
+        Ok(None)
+    }
+
+    /// Lists inbox events that were recorded but never marked processed,
+    /// ordered by inbox id.
+    fn pending_incoming(&self) -> Result<Vec<InboxEntry>, JournalError> {
+        Ok(Vec::new())
+    }
+
+    /// Marks an inbox event as fully processed.
+    fn mark_incoming_processed(&self, inbox_id: u64) -> Result<(), JournalError> {
+        let _ = inbox_id;
+        Ok(())
+    }
+
+    /// Lists every inbox event recorded for `saga_id`, processed or not,
+    /// ordered by inbox id. History is dropped by [`prune`](Self::prune).
+    ///
+    /// [`replay_saga`](crate::replay_saga) feeds these back through a
+    /// sandboxed participant. Journals without an inbox return an empty list.
+    fn incoming_history(&self, saga_id: SagaId) -> Result<Vec<InboxEntry>, JournalError> {
+        let _ = saga_id;
+        Ok(Vec::new())
+    }
+
+    /// Reads the entries of `saga_id` folded into a [`SagaJournalHistory`]:
+    /// execution and compensation attempts, effects and quarantine.
+    fn inspect(&self, saga_id: SagaId) -> Result<SagaJournalHistory, JournalError> {
+        Ok(SagaJournalHistory::from_entries(
+            saga_id,
+            &self.read(saga_id)?,
+        ))
+    }
+
+    /// The async journal this one wraps, which the async handlers await
+    /// instead of calling this one. `None` for sync journals; only
+    /// [`crate::AsyncJournalAdapter`] has one.
+    fn as_async(&self) -> Option<&dyn crate::AsyncParticipantJournal> {
+        None
+    }
 }
 
 /// A single entry in the participant's journal.
@@ -134,6 +328,53 @@ pub struct JournalEntry {
     pub event: ParticipantEvent,
 }
 
+/// Last entry that reflects the participant's progress on the saga, skipping
+/// rejection and effect ledger records.
+pub(crate) fn last_progress_entry(entries: &[JournalEntry]) -> Option<&JournalEntry> {
+    entries
+        .iter()
+        .rev()
+        .find(|entry| entry.event.records_progress())
+}
+
+/// An outgoing saga event staged in the journal outbox.
+///
+/// Outbox entries live beside the participant's journal rows. The helpers
+/// stage an event in the same write as the journal append that decided it
+/// ([`ParticipantJournal::append_with_outgoing`]) and before it is published.
+/// Entries are removed from [`ParticipantJournal::pending_outgoing`] once the
+/// event has been published.
+#[derive(Clone, Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct OutboxEntry {
+    /// Identifier assigned when the event was staged.
+    pub outbox_id: u64,
+    /// The SAGA the outgoing event belongs to.
+    pub saga_id: SagaId,
+    /// The Unix timestamp in milliseconds when the event was staged.
+    pub recorded_at_millis: u64,
+    /// The event to publish.
+    pub event: SagaChoreographyEvent,
+}
+
+/// An incoming saga event persisted in the journal inbox.
+///
+/// Inbox entries are written before an event is deduped and processed, and
+/// removed once processing finished. Entries still present at startup belong
+/// to events that were interrupted mid-processing.
+#[derive(Clone, Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct InboxEntry {
+    /// Identifier assigned when the event was recorded.
+    pub inbox_id: u64,
+    /// The SAGA the incoming event belongs to.
+    pub saga_id: SagaId,
+    /// The dedupe key derived for the event when it was received.
+    pub dedupe_key: Box<str>,
+    /// The Unix timestamp in milliseconds when the event was recorded.
+    pub recorded_at_millis: u64,
+    /// The raw incoming event.
+    pub event: SagaChoreographyEvent,
+}
+
 /// Errors that can occur during journal operations.
 #[derive(Debug, thiserror::Error)]
 pub enum JournalError {
@@ -166,6 +407,12 @@ pub enum JournalError {
 pub struct InMemoryJournal {
     /// The backing store mapping SAGA IDs to their journal entries.
     data: std::sync::RwLock<std::collections::HashMap<u64, Vec<JournalEntry>>>,
+    /// Staged outgoing events keyed by outbox id, removed once sent.
+    outbox: std::sync::RwLock<std::collections::BTreeMap<u64, OutboxEntry>>,
+    /// Recorded incoming events keyed by inbox id, removed once processed.
+    inbox: std::sync::RwLock<std::collections::BTreeMap<u64, InboxEntry>>,
+    /// Every recorded incoming event per SAGA, kept until the SAGA is pruned.
+    inbox_history: std::sync::RwLock<std::collections::HashMap<u64, Vec<InboxEntry>>>,
     /// Atomic counter for generating monotonically increasing sequence numbers.
     counter: std::sync::atomic::AtomicU64,
 }
@@ -175,6 +422,9 @@ impl InMemoryJournal {
     pub fn new() -> Self {
         Self {
             data: std::sync::RwLock::new(std::collections::HashMap::new()),
+            outbox: std::sync::RwLock::new(std::collections::BTreeMap::new()),
+            inbox: std::sync::RwLock::new(std::collections::BTreeMap::new()),
+            inbox_history: std::sync::RwLock::new(std::collections::HashMap::new()),
             counter: std::sync::atomic::AtomicU64::new(1),
         }
     }
@@ -185,21 +435,9 @@ impl ParticipantJournal for InMemoryJournal {
         let seq = self
             .counter
             .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-        let recorded_at_millis =
-            match std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
-                Ok(duration) => duration.as_millis() as u64,
-                Err(err) => {
-                    tracing::error!(
-                        target: "core::saga",
-                        event = "in_memory_journal_now_millis_failed",
-                        error = %err
-                    );
-                    0
-                }
-            };
         let entry = JournalEntry {
             sequence: seq,
-            recorded_at_millis,
+            recorded_at_millis: SagaContext::now_millis(),
             event,
         };
 
@@ -237,8 +475,157 @@ impl ParticipantJournal for InMemoryJournal {
             .write()
             .map_err(|e| JournalError::Storage(e.to_string().into()))?;
         data.remove(&saga_id.0);
+        drop(data);
+        let mut inbox_history = self
+            .inbox_history
+            .write()
+            .map_err(|e| JournalError::Storage(e.to_string().into()))?;
+        inbox_history.remove(&saga_id.0);
+        Ok(())
+    }
+
+    fn record_outgoing(
+        &self,
+        saga_id: SagaId,
+        event: &SagaChoreographyEvent,
+    ) -> Result<Option<u64>, JournalError> {
+        let outbox_id = self
+            .counter
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let entry = OutboxEntry {
+            outbox_id,
+            saga_id,
+            recorded_at_millis: SagaContext::now_millis(),
+            event: event.clone(),
+        };
+        let mut outbox = self
+            .outbox
+            .write()
+            .map_err(|e| JournalError::Storage(e.to_string().into()))?;
+        outbox.insert(outbox_id, entry);
+        Ok(Some(outbox_id))
+    }
+
+    /// Holds the journal and outbox locks together, so readers never see
+    /// the entry without its staged events.
+    fn append_with_outgoing(
+        &self,
+        saga_id: SagaId,
+        event: ParticipantEvent,
+        outgoing: &[SagaChoreographyEvent],
+    ) -> Result<(u64, Vec<Option<u64>>), JournalError> {
+        let mut data = self
+            .data
+            .write()
+            .map_err(|e| JournalError::Storage(e.to_string().into()))?;
+        let mut outbox = self
+            .outbox
+            .write()
+            .map_err(|e| JournalError::Storage(e.to_string().into()))?;
+        let now = SagaContext::now_millis();
+        let sequence = self
+            .counter
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        data.entry(saga_id.0).or_default().push(JournalEntry {
+            sequence,
+            recorded_at_millis: now,
+            event,
+        });
+        let outbox_ids = outgoing
+            .iter()
+            .map(|event| {
+                let outbox_id = self
+                    .counter
+                    .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                outbox.insert(
+                    outbox_id,
+                    OutboxEntry {
+                        outbox_id,
+                        saga_id,
+                        recorded_at_millis: now,
+                        event: event.clone(),
+                    },
+                );
+                Some(outbox_id)
+            })
+            .collect();
+        Ok((sequence, outbox_ids))
+    }
+
+    fn pending_outgoing(&self) -> Result<Vec<OutboxEntry>, JournalError> {
+        let outbox = self
+            .outbox
+            .read()
+            .map_err(|e| JournalError::Storage(e.to_string().into()))?;
+        Ok(outbox.values().cloned().collect())
+    }
+
+    fn mark_outgoing_sent(&self, outbox_id: u64) -> Result<(), JournalError> {
+        let mut outbox = self
+            .outbox
+            .write()
+            .map_err(|e| JournalError::Storage(e.to_string().into()))?;
+        outbox.remove(&outbox_id);
+        Ok(())
+    }
+
+    fn record_incoming(
+        &self,
+        saga_id: SagaId,
+        dedupe_key: DedupeKey,
+        event: &SagaChoreographyEvent,
+    ) -> Result<Option<u64>, JournalError> {
+        let inbox_id = self
+            .counter
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let entry = InboxEntry {
+            inbox_id,
+            saga_id,
+            dedupe_key: dedupe_key.to_string().into(),
+            recorded_at_millis: SagaContext::now_millis(),
+            event: event.clone(),
+        };
+        let mut inbox_history = self
+            .inbox_history
+            .write()
+            .map_err(|e| JournalError::Storage(e.to_string().into()))?;
+        inbox_history
+            .entry(saga_id.0)
+            .or_default()
+            .push(entry.clone());
+        drop(inbox_history);
+        let mut inbox = self
+            .inbox
+            .write()
+            .map_err(|e| JournalError::Storage(e.to_string().into()))?;
+        inbox.insert(inbox_id, entry);
+        Ok(Some(inbox_id))
+    }
+
+    fn pending_incoming(&self) -> Result<Vec<InboxEntry>, JournalError> {
+        let inbox = self
+            .inbox
+            .read()
+            .map_err(|e| JournalError::Storage(e.to_string().into()))?;
+        Ok(inbox.values().cloned().collect())
+    }
+
+    fn mark_incoming_processed(&self, inbox_id: u64) -> Result<(), JournalError> {
+        let mut inbox = self
+            .inbox
+            .write()
+            .map_err(|e| JournalError::Storage(e.to_string().into()))?;
+        inbox.remove(&inbox_id);
         Ok(())
     }
+
+    fn incoming_history(&self, saga_id: SagaId) -> Result<Vec<InboxEntry>, JournalError> {
+        let inbox_history = self
+            .inbox_history
+            .read()
+            .map_err(|e| JournalError::Storage(e.to_string().into()))?;
+        Ok(inbox_history.get(&saga_id.0).cloned().unwrap_or_default())
+    }
 }
 
 impl Default for InMemoryJournal {
@@ -259,6 +646,15 @@ where
         (**self).read(saga_id)
     }
 
+    #[cfg(feature = "rkyv")]
+    fn read_archived(
+        &self,
+        saga_id: SagaId,
+        visit: &mut dyn FnMut(&[archived::ArchivedJournalRow<'_>]),
+    ) -> Result<(), JournalError> {
+        (**self).read_archived(saga_id, visit)
+    }
+
     fn list_sagas(&self) -> Result<Vec<SagaId>, JournalError> {
         (**self).list_sagas()
     }
@@ -266,4 +662,54 @@ where
     fn prune(&self, saga_id: SagaId) -> Result<(), JournalError> {
         (**self).prune(saga_id)
     }
+
+    fn record_outgoing(
+        &self,
+        saga_id: SagaId,
+        event: &SagaChoreographyEvent,
+    ) -> Result<Option<u64>, JournalError> {
+        (**self).record_outgoing(saga_id, event)
+    }
+
+    fn append_with_outgoing(
+        &self,
+        saga_id: SagaId,
+        event: ParticipantEvent,
+        outgoing: &[SagaChoreographyEvent],
+    ) -> Result<(u64, Vec<Option<u64>>), JournalError> {
+        (**self).append_with_outgoing(saga_id, event, outgoing)
+    }
+
+    fn pending_outgoing(&self) -> Result<Vec<OutboxEntry>, JournalError> {
+        (**self).pending_outgoing()
+    }
+
+    fn mark_outgoing_sent(&self, outbox_id: u64) -> Result<(), JournalError> {
+        (**self).mark_outgoing_sent(outbox_id)
+    }
+
+    fn record_incoming(
+        &self,
+        saga_id: SagaId,
+        dedupe_key: DedupeKey,
+        event: &SagaChoreographyEvent,
+    ) -> Result<Option<u64>, JournalError> {
+        (**self).record_incoming(saga_id, dedupe_key, event)
+    }
+
+    fn pending_incoming(&self) -> Result<Vec<InboxEntry>, JournalError> {
+        (**self).pending_incoming()
+    }
+
+    fn mark_incoming_processed(&self, inbox_id: u64) -> Result<(), JournalError> {
+        (**self).mark_incoming_processed(inbox_id)
+    }
+
+    fn incoming_history(&self, saga_id: SagaId) -> Result<Vec<InboxEntry>, JournalError> {
+        (**self).incoming_history(saga_id)
+    }
+
+    fn as_async(&self) -> Option<&dyn crate::AsyncParticipantJournal> {
+        (**self).as_async()
+    }
 }
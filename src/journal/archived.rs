@@ -0,0 +1,181 @@
+//! Zero-copy access to journal rows (`rkyv` feature).
+//!
+//! [`decode_journal_entry`] rebuilds every [`JournalEntry`] on the heap, which
+//! dominates startup recovery of journals with many entries. An
+//! [`ArchivedJournalRow`] instead validates the row once and hands out the
+//! [`ArchivedJournalEntry`] in place. Read paths scan archived rows and
+//! deserialize only the entries they keep:
+//!
+//! ```ignore
+//! journal.read_archived(saga_id, &mut |rows| {
+//!     let quarantined = rows.iter().any(|row| {
+//!         matches!(row.entry().event, ArchivedParticipantEvent::Quarantined { .. })
+//!     });
+//! })?;
+//! ```
+//!
+//! [`ParticipantJournal::read_archived`](crate::ParticipantJournal::read_archived)
+//! borrows straight from the storage of journals that keep encoded rows
+//! (`LmdbJournal`); others re-encode their entries. Rows are borrowed when
+//! they sit on the alignment rkyv needs and copied once otherwise. Rows of
+//! older schema versions are decoded and re-encoded, so they work but gain
+//! nothing; [`crate::migrate_store`] brings them to the current schema.
+
+use rkyv::util::AlignedVec;
+
+use super::migrate::HEADER_LEN;
+use crate::{
+    decode_journal_entry, journal_row_schema_version, ArchivedJournalEntry,
+    ArchivedParticipantEvent, JournalEntry, JournalError, JOURNAL_SCHEMA_VERSION,
+};
+
+/// Alignment archives are written with; see [`rkyv::to_bytes`].
+const ARCHIVE_ALIGN: usize = 16;
+
+/// A validated journal row, read in place where possible.
+pub struct ArchivedJournalRow<'a> {
+    bytes: RowBytes<'a>,
+}
+
+enum RowBytes<'a> {
+    Borrowed(&'a [u8]),
+    Owned(AlignedVec<ARCHIVE_ALIGN>),
+}
+
+impl<'a> ArchivedJournalRow<'a> {
+    /// Validates a row written by [`crate::encode_journal_entry`] (any known
+    /// schema version).
+    pub fn new(row: &'a [u8]) -> Result<Self, JournalError> {
+        let bytes = if journal_row_schema_version(row) == JOURNAL_SCHEMA_VERSION {
+            let archive = &row[HEADER_LEN..];
+            if (archive.as_ptr() as usize).is_multiple_of(ARCHIVE_ALIGN) {
+                RowBytes::Borrowed(archive)
+            } else {
+                let mut aligned = AlignedVec::with_capacity(archive.len());
+                aligned.extend_from_slice(archive);
+                RowBytes::Owned(aligned)
+            }
+        } else {
+            let entry = decode_journal_entry(row)?;
+            RowBytes::Owned(
+                rkyv::to_bytes::<rkyv::rancor::Error>(&entry)
+                    .map_err(|err| JournalError::Storage(err.to_string().into()))?,
+            )
+        };
+        let row = Self { bytes };
+        rkyv::access::<ArchivedJournalEntry, rkyv::rancor::Error>(row.archive())
+            .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+        Ok(row)
+    }
+
+    /// The archived entry.
+    pub fn entry(&self) -> &ArchivedJournalEntry {
+        // SAFETY: `new` validated these bytes and they are never mutated.
+        unsafe { rkyv::access_unchecked::<ArchivedJournalEntry>(self.archive()) }
+    }
+
+    /// Deserializes the entry.
+    pub fn to_entry(&self) -> Result<JournalEntry, JournalError> {
+        rkyv::deserialize::<JournalEntry, rkyv::rancor::Error>(self.entry())
+            .map_err(|err| JournalError::Storage(err.to_string().into()))
+    }
+
+    /// Whether the entry is read from the row itself rather than a copy.
+    pub fn is_zero_copy(&self) -> bool {
+        matches!(self.bytes, RowBytes::Borrowed(_))
+    }
+
+    fn archive(&self) -> &[u8] {
+        match &self.bytes {
+            RowBytes::Borrowed(bytes) => bytes,
+            RowBytes::Owned(bytes) => bytes,
+        }
+    }
+}
+
+impl std::fmt::Debug for ArchivedJournalRow<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ArchivedJournalRow")
+            .field("sequence", &self.entry().sequence.to_native())
+            .field("zero_copy", &self.is_zero_copy())
+            .finish()
+    }
+}
+
+impl ArchivedParticipantEvent {
+    /// Archived twin of [`crate::ParticipantEvent`]'s progress check.
+    pub(crate) fn records_progress(&self) -> bool {
+        !matches!(
+            self,
+            Self::EventRejected { .. }
+                | Self::EffectBegun { .. }
+                | Self::EffectConfirmed { .. }
+                | Self::Parked { .. }
+                | Self::StepCheckpointed { .. }
+                | Self::SagaIdsReserved { .. }
+        )
+    }
+}
+
+/// Last progress entry of `rows`, deserialized; see
+/// [`crate::journal::last_progress_entry`].
+pub(crate) fn last_progress_row(
+    rows: &[ArchivedJournalRow<'_>],
+) -> Result<Option<JournalEntry>, JournalError> {
+    rows.iter()
+        .rev()
+        .find(|row| row.entry().event.records_progress())
+        .map(ArchivedJournalRow::to_entry)
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encode_journal_entry, ParticipantEvent};
+
+    fn entry() -> JournalEntry {
+        JournalEntry {
+            sequence: 7,
+            recorded_at_millis: 1_000,
+            event: ParticipantEvent::Quarantined {
+                reason: "venue down".into(),
+                quarantined_at_millis: 1_000,
+            },
+        }
+    }
+
+    #[test]
+    fn aligned_rows_are_read_in_place_and_misaligned_ones_copied() {
+        let row = encode_journal_entry(&entry()).unwrap();
+        // Place the archive on, then one byte off, the archive alignment.
+        let mut buffer = AlignedVec::<ARCHIVE_ALIGN>::new();
+        buffer.extend_from_slice(&[0; ARCHIVE_ALIGN - HEADER_LEN]);
+        buffer.extend_from_slice(&row);
+        buffer.extend_from_slice(&[0]);
+        let aligned = &buffer[ARCHIVE_ALIGN - HEADER_LEN..][..row.len()];
+        let mut shifted = AlignedVec::<ARCHIVE_ALIGN>::new();
+        shifted.extend_from_slice(&[0; ARCHIVE_ALIGN - HEADER_LEN + 1]);
+        shifted.extend_from_slice(&row);
+        let misaligned = &shifted[ARCHIVE_ALIGN - HEADER_LEN + 1..];
+
+        for (row, zero_copy) in [(aligned, true), (misaligned, false)] {
+            let archived = ArchivedJournalRow::new(row).unwrap();
+            assert_eq!(archived.is_zero_copy(), zero_copy);
+            assert_eq!(archived.entry().sequence, 7);
+            assert!(matches!(
+                &archived.entry().event,
+                ArchivedParticipantEvent::Quarantined { reason, .. } if reason.as_ref() == "venue down"
+            ));
+            let decoded = archived.to_entry().unwrap();
+            assert_eq!(decoded.recorded_at_millis, 1_000);
+        }
+    }
+
+    #[test]
+    fn corrupt_rows_are_rejected() {
+        let mut row = encode_journal_entry(&entry()).unwrap();
+        row.truncate(row.len() - 4);
+        assert!(ArchivedJournalRow::new(&row).is_err());
+    }
+}
@@ -0,0 +1,321 @@
+//! Transition period between two journal backends.
+//!
+//! Switching a participant to a new journal store in production goes
+//! through three phases:
+//!
+//! ```ignore
+//! // 1. Write to both stores, keep reading from the old one.
+//! let journal = Arc::new(DualWriteJournal::new(old.clone(), new.clone()));
+//! // 2. Copy sagas that predate the switch, and repair divergence.
+//! let report = backfill(&*old, &*new)?;
+//! assert!(journal.diverged_sagas().is_empty());
+//! // 3. Restart on `new` alone.
+//! ```
+//!
+//! Appends and prunes go to both backends; reads, the outbox and the inbox
+//! use the primary only. A secondary failure does not fail the call: the saga
+//! is reported as diverged (`saga_journal_dual_write_diverged`) and stays in
+//! [`DualWriteJournal::diverged_sagas`] until a [`backfill`] or
+//! [`DualWriteJournal::verify`] finds both copies equal again.
+//!
+//! As with [`crate::migrate_store`], drain the inbox and outbox before the
+//! final switch; they are not copied.
+
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    DedupeKey, InboxEntry, JournalEntry, JournalError, OutboxEntry, ParticipantEvent,
+    ParticipantJournal, SagaChoreographyEvent, SagaId,
+};
+
+/// [`ParticipantJournal`] appending to a primary and a secondary backend.
+pub struct DualWriteJournal {
+    primary: Arc<dyn ParticipantJournal>,
+    secondary: Arc<dyn ParticipantJournal>,
+    diverged: Mutex<BTreeSet<SagaId>>,
+}
+
+impl DualWriteJournal {
+    pub fn new(
+        primary: Arc<dyn ParticipantJournal>,
+        secondary: Arc<dyn ParticipantJournal>,
+    ) -> Self {
+        Self {
+            primary,
+            secondary,
+            diverged: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    /// Sagas whose secondary copy missed a write, ordered by id.
+    pub fn diverged_sagas(&self) -> Vec<SagaId> {
+        self.diverged().iter().copied().collect()
+    }
+
+    /// Compares the entries of `saga_id` in both backends, ignoring sequence
+    /// numbers and recording times. Equal copies are no longer reported as
+    /// diverged; unequal ones are.
+    pub fn verify(&self, saga_id: SagaId) -> Result<bool, JournalError> {
+        let in_sync = same_events(&self.primary.read(saga_id)?, &self.secondary.read(saga_id)?)?;
+        if in_sync {
+            self.diverged().remove(&saga_id);
+        } else {
+            self.diverged().insert(saga_id);
+        }
+        Ok(in_sync)
+    }
+
+    fn secondary_failed(&self, saga_id: SagaId, operation: &'static str, err: &JournalError) {
+        tracing::warn!(
+            target: "core::saga",
+            event = "saga_journal_dual_write_diverged",
+            saga_id = saga_id.get(),
+            operation,
+            error = %err
+        );
+        self.diverged().insert(saga_id);
+    }
+
+    fn diverged(&self) -> std::sync::MutexGuard<'_, BTreeSet<SagaId>> {
+        self.diverged
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl ParticipantJournal for DualWriteJournal {
+    fn append(&self, saga_id: SagaId, event: ParticipantEvent) -> Result<u64, JournalError> {
+        let sequence = self.primary.append(saga_id, event.clone())?;
+        if let Err(err) = self.secondary.append(saga_id, event) {
+            self.secondary_failed(saga_id, "append", &err);
+        }
+        Ok(sequence)
+    }
+
+    fn read(&self, saga_id: SagaId) -> Result<Vec<JournalEntry>, JournalError> {
+        self.primary.read(saga_id)
+    }
+
+    #[cfg(feature = "rkyv")]
+    fn read_archived(
+        &self,
+        saga_id: SagaId,
+        visit: &mut dyn FnMut(&[crate::ArchivedJournalRow<'_>]),
+    ) -> Result<(), JournalError> {
+        self.primary.read_archived(saga_id, visit)
+    }
+
+    fn list_sagas(&self) -> Result<Vec<SagaId>, JournalError> {
+        self.primary.list_sagas()
+    }
+
+    fn prune(&self, saga_id: SagaId) -> Result<(), JournalError> {
+        self.primary.prune(saga_id)?;
+        match self.secondary.prune(saga_id) {
+            Ok(()) => {
+                self.diverged().remove(&saga_id);
+            }
+            Err(err) => self.secondary_failed(saga_id, "prune", &err),
+        }
+        Ok(())
+    }
+
+    fn record_outgoing(
+        &self,
+        saga_id: SagaId,
+        event: &SagaChoreographyEvent,
+    ) -> Result<Option<u64>, JournalError> {
+        self.primary.record_outgoing(saga_id, event)
+    }
+
+    /// The outbox lives on the primary only.
+    fn append_with_outgoing(
+        &self,
+        saga_id: SagaId,
+        event: ParticipantEvent,
+        outgoing: &[SagaChoreographyEvent],
+    ) -> Result<(u64, Vec<Option<u64>>), JournalError> {
+        let staged = self
+            .primary
+            .append_with_outgoing(saga_id, event.clone(), outgoing)?;
+        if let Err(err) = self.secondary.append(saga_id, event) {
+            self.secondary_failed(saga_id, "append", &err);
+        }
+        Ok(staged)
+    }
+
+    fn pending_outgoing(&self) -> Result<Vec<OutboxEntry>, JournalError> {
+        self.primary.pending_outgoing()
+    }
+
+    fn mark_outgoing_sent(&self, outbox_id: u64) -> Result<(), JournalError> {
+        self.primary.mark_outgoing_sent(outbox_id)
+    }
+
+    fn record_incoming(
+        &self,
+        saga_id: SagaId,
+        dedupe_key: DedupeKey,
+        event: &SagaChoreographyEvent,
+    ) -> Result<Option<u64>, JournalError> {
+        self.primary.record_incoming(saga_id, dedupe_key, event)
+    }
+
+    fn pending_incoming(&self) -> Result<Vec<InboxEntry>, JournalError> {
+        self.primary.pending_incoming()
+    }
+
+    fn mark_incoming_processed(&self, inbox_id: u64) -> Result<(), JournalError> {
+        self.primary.mark_incoming_processed(inbox_id)
+    }
+
+    fn incoming_history(&self, saga_id: SagaId) -> Result<Vec<InboxEntry>, JournalError> {
+        self.primary.incoming_history(saga_id)
+    }
+}
+
+impl std::fmt::Debug for DualWriteJournal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DualWriteJournal")
+            .field("diverged_len", &self.diverged().len())
+            .finish()
+    }
+}
+
+/// Outcome of [`backfill`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BackfillReport {
+    /// Sagas missing from the target, copied in full.
+    pub copied: usize,
+    /// Sagas whose target copy differed, pruned and copied again.
+    pub replaced: usize,
+    /// Sagas already equal in both journals.
+    pub in_sync: usize,
+    /// Entries appended to the target.
+    pub entries: usize,
+}
+
+/// Copies every saga of `from` that `to` lacks or holds differently, so `to`
+/// can take over from `from`.
+///
+/// Unlike [`crate::migrate_store`], sagas the target already has are
+/// compared (see [`DualWriteJournal::verify`]) and replaced when they differ,
+/// which repairs sagas that were running when dual writes started. A saga
+/// appended to while it is being replaced may still differ afterwards; run
+/// the backfill again until it reports no replacements.
+pub fn backfill<F, T>(from: &F, to: &T) -> Result<BackfillReport, JournalError>
+where
+    F: ParticipantJournal + ?Sized,
+    T: ParticipantJournal + ?Sized,
+{
+    let mut report = BackfillReport::default();
+    for saga_id in from.list_sagas()? {
+        let source = from.read(saga_id)?;
+        let target = to.read(saga_id)?;
+        if target.is_empty() {
+            report.copied += 1;
+        } else if same_events(&source, &target)? {
+            report.in_sync += 1;
+            continue;
+        } else {
+            to.prune(saga_id)?;
+            report.replaced += 1;
+        }
+        for entry in source {
+            to.append(saga_id, entry.event)?;
+            report.entries += 1;
+        }
+    }
+    tracing::info!(
+        target: "core::saga",
+        event = "saga_journal_backfilled",
+        copied = report.copied,
+        replaced = report.replaced,
+        in_sync = report.in_sync,
+        entries = report.entries
+    );
+    Ok(report)
+}
+
+/// Whether both lists hold the same events in the same order.
+fn same_events(a: &[JournalEntry], b: &[JournalEntry]) -> Result<bool, JournalError> {
+    if a.len() != b.len() {
+        return Ok(false);
+    }
+    let encode = |event: &ParticipantEvent| {
+        rkyv::to_bytes::<rkyv::rancor::Error>(event)
+            .map_err(|err| JournalError::Storage(err.to_string().into()))
+    };
+    for (a, b) in a.iter().zip(b) {
+        if encode(&a.event)?.as_slice() != encode(&b.event)?.as_slice() {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FaultSchedule, FaultyJournal, InMemoryJournal};
+
+    fn triggered(at: u64) -> ParticipantEvent {
+        ParticipantEvent::StepTriggered {
+            triggering_event: "saga_started".into(),
+            triggered_at_millis: at,
+        }
+    }
+
+    #[test]
+    fn diverged_sagas_are_reported_and_repaired_by_backfill() {
+        let primary = Arc::new(InMemoryJournal::new());
+        let secondary = Arc::new(InMemoryJournal::new());
+        let old = SagaId::new(1);
+        let running = SagaId::new(2);
+        let fresh = SagaId::new(3);
+        primary.append(old, triggered(1)).unwrap();
+        primary.append(running, triggered(2)).unwrap();
+
+        let journal = DualWriteJournal::new(primary.clone(), secondary.clone());
+        journal.append(running, triggered(3)).unwrap();
+        journal.append(fresh, triggered(4)).unwrap();
+        assert_eq!(journal.read(running).unwrap().len(), 2);
+        assert_eq!(secondary.read(running).unwrap().len(), 1);
+        assert!(!journal.verify(running).unwrap());
+        assert!(journal.verify(fresh).unwrap());
+        assert_eq!(journal.diverged_sagas(), vec![running]);
+
+        let report = backfill(&*primary, &*secondary).unwrap();
+        assert_eq!(
+            report,
+            BackfillReport {
+                copied: 1,
+                replaced: 1,
+                in_sync: 1,
+                entries: 3,
+            }
+        );
+        assert!(journal.verify(running).unwrap());
+        assert!(journal.diverged_sagas().is_empty());
+        let mut copied = secondary.list_sagas().unwrap();
+        copied.sort();
+        assert_eq!(copied, vec![old, running, fresh]);
+    }
+
+    #[test]
+    fn secondary_failures_do_not_fail_the_primary_write() {
+        let primary = Arc::new(InMemoryJournal::new());
+        let secondary = Arc::new(FaultyJournal::new(
+            InMemoryJournal::new(),
+            FaultSchedule::always(),
+        ));
+        let journal = DualWriteJournal::new(primary.clone(), secondary);
+        let saga_id = SagaId::new(7);
+
+        journal.append(saga_id, triggered(1)).unwrap();
+
+        assert_eq!(primary.read(saga_id).unwrap().len(), 1);
+        assert_eq!(journal.diverged_sagas(), vec![saga_id]);
+    }
+}
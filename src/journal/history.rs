@@ -0,0 +1,421 @@
+//! Typed view of one saga's journal entries.
+//!
+//! [`ParticipantJournal::inspect`] folds the raw entries of a saga into a
+//! [`SagaJournalHistory`]: one record per execution attempt and per compensation
+//! attempt, the effects and checkpoints of the step and its quarantine, if
+//! any. Tools read those instead of matching on [`ParticipantEvent`] themselves:
+//!
+//! ```ignore
+//! let history = journal.inspect(saga_id)?;
+//! if let Some(ExecutionOutcome::Failed { error, .. }) =
+//!     history.last_execution().map(|execution| &execution.outcome)
+//! {
+//!     eprintln!("attempt {} failed: {error}", history.executions.len());
+//! }
+//! ```
+//!
+//! [`ParticipantJournal::inspect`]: crate::ParticipantJournal::inspect
+
+use crate::{JournalEntry, ParticipantEvent, SagaId, SagaType, StepName};
+
+/// A saga as recorded in one participant's journal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SagaJournalHistory {
+    pub saga_id: SagaId,
+    pub registration: Option<SagaRegistration>,
+    /// Event type that triggered the step, and when.
+    pub triggered_by: Option<(Box<str>, u64)>,
+    /// Execution attempts, in journal order.
+    pub executions: Vec<ExecutionRecord>,
+    /// Compensation attempts, in journal order.
+    pub compensations: Vec<CompensationRecord>,
+    /// External effects, in the order they were begun.
+    pub effects: Vec<EffectRecord>,
+    /// Phases finished by the step, in journal order.
+    pub checkpoints: Vec<CheckpointRecord>,
+    pub quarantine: Option<QuarantineRecord>,
+    /// Incoming events the participant refused: event type and reason.
+    pub rejected_events: Vec<(Box<str>, Box<str>)>,
+    /// Set while the saga is parked by a draining participant.
+    pub parked_at_millis: Option<u64>,
+    /// Number of journal entries folded.
+    pub entries: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SagaRegistration {
+    pub saga_type: SagaType,
+    pub step_name: StepName,
+    pub registered_at_millis: u64,
+}
+
+/// One attempt at executing the step.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExecutionRecord {
+    pub attempt: u32,
+    /// `None` when the outcome was journaled without a start record.
+    pub started_at_millis: Option<u64>,
+    pub outcome: ExecutionOutcome,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExecutionOutcome {
+    /// Started, with no outcome journaled (yet).
+    Running,
+    Completed {
+        output: Vec<u8>,
+        compensation_data: Vec<u8>,
+        completed_at_millis: u64,
+    },
+    Failed {
+        error: Box<str>,
+        requires_compensation: bool,
+        failed_at_millis: u64,
+    },
+}
+
+/// One attempt at compensating the step.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompensationRecord {
+    pub attempt: u32,
+    /// `None` when the outcome was journaled without a start record.
+    pub started_at_millis: Option<u64>,
+    pub outcome: CompensationOutcome,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CompensationOutcome {
+    Running,
+    Completed {
+        completed_at_millis: u64,
+    },
+    Failed {
+        error: Box<str>,
+        is_ambiguous: bool,
+        failed_at_millis: u64,
+    },
+}
+
+/// An external effect tracked by the effect ledger.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EffectRecord {
+    pub key: Box<str>,
+    pub begun_at_millis: u64,
+    /// What the external system returned, and when; `None` while in doubt.
+    pub confirmed: Option<(Vec<u8>, u64)>,
+}
+
+/// A phase of a multi-phase step, journaled by [`crate::checkpoint`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CheckpointRecord {
+    pub phase: Box<str>,
+    pub data: Vec<u8>,
+    pub checkpointed_at_millis: u64,
+}
+
+/// The saga's latest quarantine.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QuarantineRecord {
+    pub reason: Box<str>,
+    pub quarantined_at_millis: u64,
+    /// Times the saga was quarantined; more than one after failed retries.
+    pub times: u32,
+}
+
+impl SagaJournalHistory {
+    /// Folds `entries`, as returned by `ParticipantJournal::read`.
+    pub fn from_entries(saga_id: SagaId, entries: &[JournalEntry]) -> Self {
+        let mut history = Self {
+            saga_id,
+            registration: None,
+            triggered_by: None,
+            executions: Vec::new(),
+            compensations: Vec::new(),
+            effects: Vec::new(),
+            checkpoints: Vec::new(),
+            quarantine: None,
+            rejected_events: Vec::new(),
+            parked_at_millis: None,
+            entries: entries.len(),
+        };
+        for entry in entries {
+            history.apply(&entry.event);
+        }
+        history
+    }
+
+    fn apply(&mut self, event: &ParticipantEvent) {
+        match event {
+            ParticipantEvent::SagaRegistered {
+                saga_type,
+                step_name,
+                registered_at_millis,
+            } => {
+                self.registration = Some(SagaRegistration {
+                    saga_type: saga_type.clone(),
+                    step_name: step_name.clone(),
+                    registered_at_millis: *registered_at_millis,
+                });
+            }
+            ParticipantEvent::StepTriggered {
+                triggering_event,
+                triggered_at_millis,
+            } => self.triggered_by = Some((triggering_event.clone(), *triggered_at_millis)),
+            ParticipantEvent::StepExecutionStarted {
+                attempt,
+                started_at_millis,
+            } => {
+                self.parked_at_millis = None;
+                self.executions.push(ExecutionRecord {
+                    attempt: *attempt,
+                    started_at_millis: Some(*started_at_millis),
+                    outcome: ExecutionOutcome::Running,
+                });
+            }
+            ParticipantEvent::StepExecutionCompleted {
+                output,
+                compensation_data,
+                completed_at_millis,
+            } => {
+                self.running_execution().outcome = ExecutionOutcome::Completed {
+                    output: output.clone(),
+                    compensation_data: compensation_data.clone(),
+                    completed_at_millis: *completed_at_millis,
+                };
+            }
+            ParticipantEvent::StepExecutionFailed {
+                error,
+                requires_compensation,
+                failed_at_millis,
+                ..
+            } => {
+                self.running_execution().outcome = ExecutionOutcome::Failed {
+                    error: error.clone(),
+                    requires_compensation: *requires_compensation,
+                    failed_at_millis: *failed_at_millis,
+                };
+            }
+            ParticipantEvent::CompensationStarted {
+                attempt,
+                started_at_millis,
+            } => {
+                self.parked_at_millis = None;
+                self.compensations.push(CompensationRecord {
+                    attempt: *attempt,
+                    started_at_millis: Some(*started_at_millis),
+                    outcome: CompensationOutcome::Running,
+                });
+            }
+            ParticipantEvent::CompensationCompleted {
+                completed_at_millis,
+            } => {
+                self.running_compensation().outcome = CompensationOutcome::Completed {
+                    completed_at_millis: *completed_at_millis,
+                };
+            }
+            ParticipantEvent::CompensationFailed {
+                error,
+                is_ambiguous,
+                failed_at_millis,
+                ..
+            } => {
+                self.running_compensation().outcome = CompensationOutcome::Failed {
+                    error: error.clone(),
+                    is_ambiguous: *is_ambiguous,
+                    failed_at_millis: *failed_at_millis,
+                };
+            }
+            ParticipantEvent::Quarantined {
+                reason,
+                quarantined_at_millis,
+            } => {
+                let times = self.quarantine.as_ref().map_or(0, |record| record.times) + 1;
+                self.quarantine = Some(QuarantineRecord {
+                    reason: reason.clone(),
+                    quarantined_at_millis: *quarantined_at_millis,
+                    times,
+                });
+            }
+            ParticipantEvent::EventRejected {
+                event_type, reason, ..
+            } => self
+                .rejected_events
+                .push((event_type.clone(), reason.clone())),
+            ParticipantEvent::EffectBegun {
+                key,
+                begun_at_millis,
+            } => self.effects.push(EffectRecord {
+                key: key.clone(),
+                begun_at_millis: *begun_at_millis,
+                confirmed: None,
+            }),
+            ParticipantEvent::EffectConfirmed {
+                key,
+                result,
+                confirmed_at_millis,
+            } => {
+                if let Some(effect) = self
+                    .effects
+                    .iter_mut()
+                    .rev()
+                    .find(|effect| effect.key == *key)
+                {
+                    effect.confirmed = Some((result.clone(), *confirmed_at_millis));
+                }
+            }
+            ParticipantEvent::Parked {
+                parked_at_millis, ..
+            } => self.parked_at_millis = Some(*parked_at_millis),
+            ParticipantEvent::StepCheckpointed {
+                phase,
+                data,
+                checkpointed_at_millis,
+            } => self.checkpoints.push(CheckpointRecord {
+                phase: phase.clone(),
+                data: data.clone(),
+                checkpointed_at_millis: *checkpointed_at_millis,
+            }),
+            ParticipantEvent::SagaIdsReserved { .. } => {}
+        }
+    }
+
+    /// The open execution attempt, or a new one when the outcome was
+    /// journaled without a start.
+    fn running_execution(&mut self) -> &mut ExecutionRecord {
+        let open = matches!(
+            self.executions.last(),
+            Some(ExecutionRecord {
+                outcome: ExecutionOutcome::Running,
+                ..
+            })
+        );
+        if !open {
+            let attempt = self.executions.last().map_or(1, |last| last.attempt + 1);
+            self.executions.push(ExecutionRecord {
+                attempt,
+                started_at_millis: None,
+                outcome: ExecutionOutcome::Running,
+            });
+        }
+        self.executions
+            .last_mut()
+            .expect("an execution was just ensured")
+    }
+
+    fn running_compensation(&mut self) -> &mut CompensationRecord {
+        let open = matches!(
+            self.compensations.last(),
+            Some(CompensationRecord {
+                outcome: CompensationOutcome::Running,
+                ..
+            })
+        );
+        if !open {
+            let attempt = self.compensations.last().map_or(1, |last| last.attempt + 1);
+            self.compensations.push(CompensationRecord {
+                attempt,
+                started_at_millis: None,
+                outcome: CompensationOutcome::Running,
+            });
+        }
+        self.compensations
+            .last_mut()
+            .expect("a compensation was just ensured")
+    }
+
+    pub fn last_execution(&self) -> Option<&ExecutionRecord> {
+        self.executions.last()
+    }
+
+    /// Compensation data of the latest completed execution.
+    pub fn compensation_data(&self) -> Option<&[u8]> {
+        self.executions
+            .iter()
+            .rev()
+            .find_map(|execution| match &execution.outcome {
+                ExecutionOutcome::Completed {
+                    compensation_data, ..
+                } => Some(compensation_data.as_slice()),
+                _ => None,
+            })
+    }
+
+    /// Effects begun but never confirmed.
+    pub fn unconfirmed_effects(&self) -> impl Iterator<Item = &EffectRecord> {
+        self.effects
+            .iter()
+            .filter(|effect| effect.confirmed.is_none())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryJournal, ParticipantJournal};
+
+    #[test]
+    fn inspect_folds_attempts_compensations_and_quarantine() {
+        let journal = InMemoryJournal::new();
+        let saga_id = SagaId::new(9);
+        for event in [
+            ParticipantEvent::StepExecutionStarted {
+                attempt: 1,
+                started_at_millis: 10,
+            },
+            ParticipantEvent::StepExecutionFailed {
+                error: "timeout".into(),
+                requires_compensation: false,
+                failed_at_millis: 11,
+                details: Vec::new(),
+            },
+            ParticipantEvent::StepExecutionStarted {
+                attempt: 2,
+                started_at_millis: 20,
+            },
+            ParticipantEvent::EffectBegun {
+                key: "order-9".into(),
+                begun_at_millis: 21,
+            },
+            ParticipantEvent::StepExecutionCompleted {
+                output: b"ok".to_vec(),
+                compensation_data: b"undo".to_vec(),
+                completed_at_millis: 22,
+            },
+            ParticipantEvent::CompensationFailed {
+                error: "venue down".into(),
+                is_ambiguous: true,
+                failed_at_millis: 30,
+                details: Vec::new(),
+            },
+            ParticipantEvent::Quarantined {
+                reason: "venue down".into(),
+                quarantined_at_millis: 30,
+            },
+            ParticipantEvent::Quarantined {
+                reason: "retry failed".into(),
+                quarantined_at_millis: 40,
+            },
+        ] {
+            journal.append(saga_id, event).unwrap();
+        }
+
+        let history = journal.inspect(saga_id).unwrap();
+        assert_eq!(history.entries, 8);
+        assert_eq!(history.executions.len(), 2);
+        assert!(matches!(
+            history.executions[0].outcome,
+            ExecutionOutcome::Failed {
+                failed_at_millis: 11,
+                ..
+            }
+        ));
+        assert_eq!(history.last_execution().unwrap().attempt, 2);
+        assert_eq!(history.compensation_data(), Some(&b"undo"[..]));
+        assert_eq!(history.compensations.len(), 1);
+        assert_eq!(history.compensations[0].started_at_millis, None);
+        assert_eq!(history.unconfirmed_effects().count(), 1);
+        let quarantine = history.quarantine.unwrap();
+        assert_eq!(quarantine.reason.as_ref(), "retry failed");
+        assert_eq!(quarantine.times, 2);
+    }
+}
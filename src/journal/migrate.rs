@@ -0,0 +1,806 @@
+//! Versioned journal rows and migration between journal stores.
+//!
+//! Durable journals store each [`JournalEntry`] as an rkyv archive, whose
+//! layout follows the Rust types exactly. Rows written by
+//! [`encode_journal_entry`] therefore start with a small header naming the
+//! schema version they were written with, and [`decode_journal_entry`] keeps
+//! one decoder per version, converting older entries into the current
+//! [`ParticipantEvent`].
+//!
+//! Rows without a header predate versioning (schema version 1) and are read
+//! with a frozen copy of that release's types. [`migrate_store`] copies every
+//! saga from a journal opened on old data into a fresh one, which rewrites
+//! all rows in the current schema:
+//!
+//! ```ignore
+//! let old = LmdbJournal::open(Path::new("/var/lib/orders/journal"))?;
+//! let new = LmdbJournal::open(Path::new("/var/lib/orders/journal-v2"))?;
+//! let report = migrate_store(&old, &new)?;
+//! ```
+//!
+//! Only journal rows are migrated. Drain the inbox and outbox (inbox replay
+//! and outbox relay) on the old release before upgrading.
+
+use crate::{JournalEntry, JournalError, ParticipantEvent, ParticipantJournal};
+
+/// Schema version written by [`encode_journal_entry`].
+pub const JOURNAL_SCHEMA_VERSION: u16 = 4;
+
+const HEADER_MAGIC: &[u8; 6] = b"\xffSAGAJ";
+pub(crate) const HEADER_LEN: usize = HEADER_MAGIC.len() + 2;
+
+/// Encodes `entry` in the current schema, prefixed with its version header.
+pub fn encode_journal_entry(entry: &JournalEntry) -> Result<Vec<u8>, JournalError> {
+    let archived = rkyv::to_bytes::<rkyv::rancor::Error>(entry)
+        .map_err(|err| JournalError::Storage(err.to_string().into()))?;
+    let mut row = Vec::with_capacity(HEADER_LEN + archived.len());
+    row.extend_from_slice(HEADER_MAGIC);
+    row.extend_from_slice(&JOURNAL_SCHEMA_VERSION.to_le_bytes());
+    row.extend_from_slice(&archived);
+    Ok(row)
+}
+
+/// Schema version `row` was written with; `1` for rows without a header.
+pub fn journal_row_schema_version(row: &[u8]) -> u16 {
+    match row.strip_prefix(HEADER_MAGIC.as_slice()) {
+        Some([lo, hi, ..]) => u16::from_le_bytes([*lo, *hi]),
+        _ => 1,
+    }
+}
+
+/// Decodes a journal row of any known schema version into the current
+/// [`JournalEntry`].
+pub fn decode_journal_entry(row: &[u8]) -> Result<JournalEntry, JournalError> {
+    match journal_row_schema_version(row) {
+        1 => decode_unversioned(row),
+        2 => archived::<v2::JournalEntry>(&row[HEADER_LEN..]).map(JournalEntry::from),
+        3 => archived::<v3::JournalEntry>(&row[HEADER_LEN..]).map(JournalEntry::from),
+        JOURNAL_SCHEMA_VERSION => archived::<JournalEntry>(&row[HEADER_LEN..]),
+        version => Err(JournalError::Storage(
+            format!(
+                "journal row schema version {version} is newer than supported version {JOURNAL_SCHEMA_VERSION}"
+            )
+            .into(),
+        )),
+    }
+}
+
+/// Headerless rows were written either by the release before versioning or
+/// by builds that already had the version 2 types but no header yet.
+fn decode_unversioned(row: &[u8]) -> Result<JournalEntry, JournalError> {
+    archived::<v1::JournalEntry>(row)
+        .map(JournalEntry::from)
+        .or_else(|_| archived::<v2::JournalEntry>(row).map(JournalEntry::from))
+}
+
+pub(crate) fn archived<T>(bytes: &[u8]) -> Result<T, JournalError>
+where
+    T: rkyv::Archive,
+    T::Archived: for<'a> rkyv::bytecheck::CheckBytes<rkyv::api::high::HighValidator<'a, rkyv::rancor::Error>>
+        + rkyv::Deserialize<T, rkyv::api::high::HighDeserializer<rkyv::rancor::Error>>,
+{
+    // Archives must be aligned; the header shifts them off alignment.
+    let mut aligned = rkyv::util::AlignedVec::<16>::with_capacity(bytes.len());
+    aligned.extend_from_slice(bytes);
+    rkyv::from_bytes::<T, rkyv::rancor::Error>(&aligned)
+        .map_err(|err| JournalError::Storage(err.to_string().into()))
+}
+
+/// Outcome of [`migrate_store`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MigrationReport {
+    /// Sagas copied into the new journal.
+    pub sagas: usize,
+    /// Entries appended to the new journal.
+    pub entries: usize,
+    /// Sagas left alone because the new journal already had entries for them.
+    pub skipped_sagas: usize,
+}
+
+/// Copies every saga of `old` into `new` in the current schema.
+///
+/// Entries are appended in their original order, so `new` assigns fresh
+/// sequence numbers and recording times. Sagas that already have entries in
+/// `new` are skipped, which makes an interrupted migration safe to re-run
+/// once the saga it stopped in has been pruned from `new`.
+pub fn migrate_store<O, N>(old: &O, new: &N) -> Result<MigrationReport, JournalError>
+where
+    O: ParticipantJournal + ?Sized,
+    N: ParticipantJournal + ?Sized,
+{
+    let mut report = MigrationReport::default();
+    for saga_id in old.list_sagas()? {
+        if !new.read(saga_id)?.is_empty() {
+            report.skipped_sagas += 1;
+            continue;
+        }
+        for entry in old.read(saga_id)? {
+            new.append(saga_id, entry.event)?;
+            report.entries += 1;
+        }
+        report.sagas += 1;
+    }
+    tracing::info!(
+        target: "core::saga",
+        event = "saga_journal_migrated",
+        sagas = report.sagas,
+        entries = report.entries,
+        skipped_sagas = report.skipped_sagas
+    );
+    Ok(report)
+}
+
+/// Types as archived by schema version 1. Frozen: never change them.
+mod v1 {
+    #[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+    pub(super) struct JournalEntry {
+        pub(super) sequence: u64,
+        pub(super) recorded_at_millis: u64,
+        pub(super) event: ParticipantEvent,
+    }
+
+    #[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+    pub(super) enum ParticipantEvent {
+        SagaRegistered {
+            saga_type: Box<str>,
+            step_name: Box<str>,
+            registered_at_millis: u64,
+        },
+        StepTriggered {
+            triggering_event: Box<str>,
+            triggered_at_millis: u64,
+        },
+        StepExecutionStarted {
+            attempt: u32,
+            started_at_millis: u64,
+        },
+        StepExecutionCompleted {
+            output: Vec<u8>,
+            compensation_data: Vec<u8>,
+            completed_at_millis: u64,
+        },
+        StepExecutionFailed {
+            error: Box<str>,
+            requires_compensation: bool,
+            failed_at_millis: u64,
+        },
+        CompensationStarted {
+            attempt: u32,
+            started_at_millis: u64,
+        },
+        CompensationCompleted {
+            completed_at_millis: u64,
+        },
+        CompensationFailed {
+            error: Box<str>,
+            is_ambiguous: bool,
+            failed_at_millis: u64,
+        },
+        Quarantined {
+            reason: Box<str>,
+            quarantined_at_millis: u64,
+        },
+    }
+}
+
+/// Types as archived by schema version 2, before error details. Frozen.
+mod v2 {
+    #[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+    pub(super) struct JournalEntry {
+        pub(super) sequence: u64,
+        pub(super) recorded_at_millis: u64,
+        pub(super) event: ParticipantEvent,
+    }
+
+    #[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+    pub(super) enum ParticipantEvent {
+        SagaRegistered {
+            saga_type: crate::SagaType,
+            step_name: crate::StepName,
+            registered_at_millis: u64,
+        },
+        StepTriggered {
+            triggering_event: Box<str>,
+            triggered_at_millis: u64,
+        },
+        StepExecutionStarted {
+            attempt: u32,
+            started_at_millis: u64,
+        },
+        StepExecutionCompleted {
+            output: Vec<u8>,
+            compensation_data: Vec<u8>,
+            completed_at_millis: u64,
+        },
+        StepExecutionFailed {
+            error: Box<str>,
+            requires_compensation: bool,
+            failed_at_millis: u64,
+        },
+        CompensationStarted {
+            attempt: u32,
+            started_at_millis: u64,
+        },
+        CompensationCompleted {
+            completed_at_millis: u64,
+        },
+        CompensationFailed {
+            error: Box<str>,
+            is_ambiguous: bool,
+            failed_at_millis: u64,
+        },
+        Quarantined {
+            reason: Box<str>,
+            quarantined_at_millis: u64,
+        },
+        EventRejected {
+            event_type: Box<str>,
+            reason: Box<str>,
+            rejected_at_millis: u64,
+        },
+        EffectBegun {
+            key: Box<str>,
+            begun_at_millis: u64,
+        },
+        EffectConfirmed {
+            key: Box<str>,
+            result: Vec<u8>,
+            confirmed_at_millis: u64,
+        },
+        Parked {
+            reason: Box<str>,
+            parked_at_millis: u64,
+        },
+    }
+}
+
+/// Types as archived by schema version 3, before rejected events carried
+/// their dedupe key. Frozen.
+mod v3 {
+    #[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+    pub(super) struct JournalEntry {
+        pub(super) sequence: u64,
+        pub(super) recorded_at_millis: u64,
+        pub(super) event: ParticipantEvent,
+    }
+
+    #[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+    pub(super) enum ParticipantEvent {
+        SagaRegistered {
+            saga_type: crate::SagaType,
+            step_name: crate::StepName,
+            registered_at_millis: u64,
+        },
+        StepTriggered {
+            triggering_event: Box<str>,
+            triggered_at_millis: u64,
+        },
+        StepExecutionStarted {
+            attempt: u32,
+            started_at_millis: u64,
+        },
+        StepExecutionCompleted {
+            output: Vec<u8>,
+            compensation_data: Vec<u8>,
+            completed_at_millis: u64,
+        },
+        StepExecutionFailed {
+            error: Box<str>,
+            requires_compensation: bool,
+            failed_at_millis: u64,
+            details: Vec<u8>,
+        },
+        CompensationStarted {
+            attempt: u32,
+            started_at_millis: u64,
+        },
+        CompensationCompleted {
+            completed_at_millis: u64,
+        },
+        CompensationFailed {
+            error: Box<str>,
+            is_ambiguous: bool,
+            failed_at_millis: u64,
+            details: Vec<u8>,
+        },
+        Quarantined {
+            reason: Box<str>,
+            quarantined_at_millis: u64,
+        },
+        EventRejected {
+            event_type: Box<str>,
+            reason: Box<str>,
+            rejected_at_millis: u64,
+        },
+        EffectBegun {
+            key: Box<str>,
+            begun_at_millis: u64,
+        },
+        EffectConfirmed {
+            key: Box<str>,
+            result: Vec<u8>,
+            confirmed_at_millis: u64,
+        },
+        Parked {
+            reason: Box<str>,
+            parked_at_millis: u64,
+        },
+        StepCheckpointed {
+            phase: Box<str>,
+            data: Vec<u8>,
+            checkpointed_at_millis: u64,
+        },
+        SagaIdsReserved {
+            through: u64,
+            reserved_at_millis: u64,
+        },
+    }
+}
+
+impl From<v1::JournalEntry> for JournalEntry {
+    fn from(entry: v1::JournalEntry) -> Self {
+        Self {
+            sequence: entry.sequence,
+            recorded_at_millis: entry.recorded_at_millis,
+            event: entry.event.into(),
+        }
+    }
+}
+
+impl From<v1::ParticipantEvent> for ParticipantEvent {
+    fn from(event: v1::ParticipantEvent) -> Self {
+        use v1::ParticipantEvent as V1;
+
+        match event {
+            V1::SagaRegistered {
+                saga_type,
+                step_name,
+                registered_at_millis,
+            } => Self::SagaRegistered {
+                saga_type: saga_type.into(),
+                step_name: step_name.into(),
+                registered_at_millis,
+            },
+            V1::StepTriggered {
+                triggering_event,
+                triggered_at_millis,
+            } => Self::StepTriggered {
+                triggering_event,
+                triggered_at_millis,
+            },
+            V1::StepExecutionStarted {
+                attempt,
+                started_at_millis,
+            } => Self::StepExecutionStarted {
+                attempt,
+                started_at_millis,
+            },
+            V1::StepExecutionCompleted {
+                output,
+                compensation_data,
+                completed_at_millis,
+            } => Self::StepExecutionCompleted {
+                output,
+                compensation_data,
+                completed_at_millis,
+            },
+            V1::StepExecutionFailed {
+                error,
+                requires_compensation,
+                failed_at_millis,
+            } => Self::StepExecutionFailed {
+                error,
+                requires_compensation,
+                failed_at_millis,
+                details: Vec::new(),
+            },
+            V1::CompensationStarted {
+                attempt,
+                started_at_millis,
+            } => Self::CompensationStarted {
+                attempt,
+                started_at_millis,
+            },
+            V1::CompensationCompleted {
+                completed_at_millis,
+            } => Self::CompensationCompleted {
+                completed_at_millis,
+            },
+            V1::CompensationFailed {
+                error,
+                is_ambiguous,
+                failed_at_millis,
+            } => Self::CompensationFailed {
+                error,
+                is_ambiguous,
+                failed_at_millis,
+                details: Vec::new(),
+            },
+            V1::Quarantined {
+                reason,
+                quarantined_at_millis,
+            } => Self::Quarantined {
+                reason,
+                quarantined_at_millis,
+            },
+        }
+    }
+}
+
+impl From<v2::JournalEntry> for JournalEntry {
+    fn from(entry: v2::JournalEntry) -> Self {
+        Self {
+            sequence: entry.sequence,
+            recorded_at_millis: entry.recorded_at_millis,
+            event: entry.event.into(),
+        }
+    }
+}
+
+impl From<v2::ParticipantEvent> for ParticipantEvent {
+    fn from(event: v2::ParticipantEvent) -> Self {
+        use v2::ParticipantEvent as V2;
+
+        match event {
+            V2::SagaRegistered {
+                saga_type,
+                step_name,
+                registered_at_millis,
+            } => Self::SagaRegistered {
+                saga_type,
+                step_name,
+                registered_at_millis,
+            },
+            V2::StepTriggered {
+                triggering_event,
+                triggered_at_millis,
+            } => Self::StepTriggered {
+                triggering_event,
+                triggered_at_millis,
+            },
+            V2::StepExecutionStarted {
+                attempt,
+                started_at_millis,
+            } => Self::StepExecutionStarted {
+                attempt,
+                started_at_millis,
+            },
+            V2::StepExecutionCompleted {
+                output,
+                compensation_data,
+                completed_at_millis,
+            } => Self::StepExecutionCompleted {
+                output,
+                compensation_data,
+                completed_at_millis,
+            },
+            V2::StepExecutionFailed {
+                error,
+                requires_compensation,
+                failed_at_millis,
+            } => Self::StepExecutionFailed {
+                error,
+                requires_compensation,
+                failed_at_millis,
+                details: Vec::new(),
+            },
+            V2::CompensationStarted {
+                attempt,
+                started_at_millis,
+            } => Self::CompensationStarted {
+                attempt,
+                started_at_millis,
+            },
+            V2::CompensationCompleted {
+                completed_at_millis,
+            } => Self::CompensationCompleted {
+                completed_at_millis,
+            },
+            V2::CompensationFailed {
+                error,
+                is_ambiguous,
+                failed_at_millis,
+            } => Self::CompensationFailed {
+                error,
+                is_ambiguous,
+                failed_at_millis,
+                details: Vec::new(),
+            },
+            V2::Quarantined {
+                reason,
+                quarantined_at_millis,
+            } => Self::Quarantined {
+                reason,
+                quarantined_at_millis,
+            },
+            V2::EventRejected {
+                event_type,
+                reason,
+                rejected_at_millis,
+            } => Self::EventRejected {
+                event_type,
+                reason,
+                rejected_at_millis,
+                dedupe_key: None,
+            },
+            V2::EffectBegun {
+                key,
+                begun_at_millis,
+            } => Self::EffectBegun {
+                key,
+                begun_at_millis,
+            },
+            V2::EffectConfirmed {
+                key,
+                result,
+                confirmed_at_millis,
+            } => Self::EffectConfirmed {
+                key,
+                result,
+                confirmed_at_millis,
+            },
+            V2::Parked {
+                reason,
+                parked_at_millis,
+            } => Self::Parked {
+                reason,
+                parked_at_millis,
+            },
+        }
+    }
+}
+
+impl From<v3::JournalEntry> for JournalEntry {
+    fn from(entry: v3::JournalEntry) -> Self {
+        Self {
+            sequence: entry.sequence,
+            recorded_at_millis: entry.recorded_at_millis,
+            event: entry.event.into(),
+        }
+    }
+}
+
+impl From<v3::ParticipantEvent> for ParticipantEvent {
+    fn from(event: v3::ParticipantEvent) -> Self {
+        use v3::ParticipantEvent as V3;
+
+        match event {
+            V3::SagaRegistered {
+                saga_type,
+                step_name,
+                registered_at_millis,
+            } => Self::SagaRegistered {
+                saga_type,
+                step_name,
+                registered_at_millis,
+            },
+            V3::StepTriggered {
+                triggering_event,
+                triggered_at_millis,
+            } => Self::StepTriggered {
+                triggering_event,
+                triggered_at_millis,
+            },
+            V3::StepExecutionStarted {
+                attempt,
+                started_at_millis,
+            } => Self::StepExecutionStarted {
+                attempt,
+                started_at_millis,
+            },
+            V3::StepExecutionCompleted {
+                output,
+                compensation_data,
+                completed_at_millis,
+            } => Self::StepExecutionCompleted {
+                output,
+                compensation_data,
+                completed_at_millis,
+            },
+            V3::StepExecutionFailed {
+                error,
+                requires_compensation,
+                failed_at_millis,
+                details,
+            } => Self::StepExecutionFailed {
+                error,
+                requires_compensation,
+                failed_at_millis,
+                details,
+            },
+            V3::CompensationStarted {
+                attempt,
+                started_at_millis,
+            } => Self::CompensationStarted {
+                attempt,
+                started_at_millis,
+            },
+            V3::CompensationCompleted {
+                completed_at_millis,
+            } => Self::CompensationCompleted {
+                completed_at_millis,
+            },
+            V3::CompensationFailed {
+                error,
+                is_ambiguous,
+                failed_at_millis,
+                details,
+            } => Self::CompensationFailed {
+                error,
+                is_ambiguous,
+                failed_at_millis,
+                details,
+            },
+            V3::Quarantined {
+                reason,
+                quarantined_at_millis,
+            } => Self::Quarantined {
+                reason,
+                quarantined_at_millis,
+            },
+            V3::EventRejected {
+                event_type,
+                reason,
+                rejected_at_millis,
+            } => Self::EventRejected {
+                event_type,
+                reason,
+                rejected_at_millis,
+                dedupe_key: None,
+            },
+            V3::EffectBegun {
+                key,
+                begun_at_millis,
+            } => Self::EffectBegun {
+                key,
+                begun_at_millis,
+            },
+            V3::EffectConfirmed {
+                key,
+                result,
+                confirmed_at_millis,
+            } => Self::EffectConfirmed {
+                key,
+                result,
+                confirmed_at_millis,
+            },
+            V3::Parked {
+                reason,
+                parked_at_millis,
+            } => Self::Parked {
+                reason,
+                parked_at_millis,
+            },
+            V3::StepCheckpointed {
+                phase,
+                data,
+                checkpointed_at_millis,
+            } => Self::StepCheckpointed {
+                phase,
+                data,
+                checkpointed_at_millis,
+            },
+            V3::SagaIdsReserved {
+                through,
+                reserved_at_millis,
+            } => Self::SagaIdsReserved {
+                through,
+                reserved_at_millis,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryJournal, SagaId};
+
+    #[test]
+    fn unversioned_v1_rows_decode_into_current_schema() {
+        let legacy = rkyv::to_bytes::<rkyv::rancor::Error>(&v1::JournalEntry {
+            sequence: 3,
+            recorded_at_millis: 1_700,
+            event: v1::ParticipantEvent::CompensationFailed {
+                error: "venue timeout".into(),
+                is_ambiguous: true,
+                failed_at_millis: 1_699,
+            },
+        })
+        .unwrap();
+        assert_eq!(journal_row_schema_version(&legacy), 1);
+
+        let entry = decode_journal_entry(&legacy).unwrap();
+        assert_eq!(entry.sequence, 3);
+        assert!(matches!(
+            entry.event,
+            ParticipantEvent::CompensationFailed { ref error, is_ambiguous: true, .. }
+                if error.as_ref() == "venue timeout"
+        ));
+
+        let row = encode_journal_entry(&entry).unwrap();
+        assert_eq!(journal_row_schema_version(&row), JOURNAL_SCHEMA_VERSION);
+        assert_eq!(decode_journal_entry(&row).unwrap().sequence, 3);
+    }
+
+    #[test]
+    fn version_2_rows_decode_without_error_details() {
+        let archived = rkyv::to_bytes::<rkyv::rancor::Error>(&v2::JournalEntry {
+            sequence: 4,
+            recorded_at_millis: 1_800,
+            event: v2::ParticipantEvent::StepExecutionFailed {
+                error: "rejected".into(),
+                requires_compensation: true,
+                failed_at_millis: 1_799,
+            },
+        })
+        .unwrap();
+        let mut row = HEADER_MAGIC.to_vec();
+        row.extend_from_slice(&2_u16.to_le_bytes());
+        row.extend_from_slice(&archived);
+
+        let entry = decode_journal_entry(&row).unwrap();
+        assert!(matches!(
+            entry.event,
+            ParticipantEvent::StepExecutionFailed { requires_compensation: true, ref details, .. }
+                if details.is_empty()
+        ));
+    }
+
+    #[test]
+    fn version_3_rejections_decode_without_dedupe_key() {
+        let archived = rkyv::to_bytes::<rkyv::rancor::Error>(&v3::JournalEntry {
+            sequence: 5,
+            recorded_at_millis: 1_900,
+            event: v3::ParticipantEvent::EventRejected {
+                event_type: "compensation_requested".into(),
+                reason: "initiator peer is not trusted".into(),
+                rejected_at_millis: 1_899,
+            },
+        })
+        .unwrap();
+        let mut row = HEADER_MAGIC.to_vec();
+        row.extend_from_slice(&3_u16.to_le_bytes());
+        row.extend_from_slice(&archived);
+
+        let entry = decode_journal_entry(&row).unwrap();
+        assert!(matches!(
+            entry.event,
+            ParticipantEvent::EventRejected {
+                dedupe_key: None,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn migrate_store_copies_sagas_once() {
+        let old = InMemoryJournal::new();
+        for saga in [1, 2] {
+            old.append(
+                SagaId::new(saga),
+                ParticipantEvent::StepExecutionStarted {
+                    attempt: 1,
+                    started_at_millis: 10,
+                },
+            )
+            .unwrap();
+        }
+        old.append(
+            SagaId::new(2),
+            ParticipantEvent::CompensationCompleted {
+                completed_at_millis: 20,
+            },
+        )
+        .unwrap();
+        let new = InMemoryJournal::new();
+
+        let report = migrate_store(&old, &new).unwrap();
+        assert_eq!((report.sagas, report.entries), (2, 3));
+        assert_eq!(new.read(SagaId::new(2)).unwrap().len(), 2);
+
+        let rerun = migrate_store(&old, &new).unwrap();
+        assert_eq!((rerun.sagas, rerun.skipped_sagas), (0, 2));
+        assert_eq!(new.read(SagaId::new(2)).unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,320 @@
+//! One journal facade over several backends, chosen by saga type.
+//!
+//! Hot saga types can live on fast storage and low-volume ones on cheap
+//! storage while the participant keeps a single journal:
+//!
+//! ```ignore
+//! let journal = RoutingJournal::new(Arc::new(LmdbJournal::open(cheap)?))
+//!     .route("order_lifecycle", Arc::new(LmdbJournal::open(fast)?));
+//! let support = SagaParticipantSupport::new(journal, dedupe);
+//! ```
+//!
+//! A saga's backend is picked by the saga type of the first event seen for
+//! it: the incoming or outgoing choreography event, or a `SagaRegistered`
+//! append. Sagas first seen through an untyped record go to the default
+//! backend. After a restart the assignment is rebuilt lazily by asking each
+//! backend whether it holds the saga.
+//!
+//! Outbox and inbox ids are rewritten so they stay unique across backends
+//! (`local_id * backends + backend`); journal sequence numbers are the
+//! backend's own.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    DedupeKey, InboxEntry, JournalEntry, JournalError, OutboxEntry, ParticipantEvent,
+    ParticipantJournal, SagaChoreographyEvent, SagaId,
+};
+
+/// [`ParticipantJournal`] delegating each saga to a backend by saga type.
+pub struct RoutingJournal {
+    backends: Vec<Arc<dyn ParticipantJournal>>,
+    /// Saga type to index in `backends`; unlisted types use backend 0.
+    routes: HashMap<Box<str>, usize>,
+    assigned: Mutex<HashMap<SagaId, usize>>,
+}
+
+impl RoutingJournal {
+    /// Journal storing every saga type without a route in `default`.
+    pub fn new(default: Arc<dyn ParticipantJournal>) -> Self {
+        Self {
+            backends: vec![default],
+            routes: HashMap::new(),
+            assigned: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Stores sagas of `saga_type` in `backend`. Routing several types to
+    /// the same `Arc` shares one backend.
+    pub fn route(mut self, saga_type: &str, backend: Arc<dyn ParticipantJournal>) -> Self {
+        let index = self
+            .backends
+            .iter()
+            .position(|existing| same_backend(existing, &backend))
+            .unwrap_or_else(|| {
+                self.backends.push(backend);
+                self.backends.len() - 1
+            });
+        self.routes.insert(saga_type.into(), index);
+        self
+    }
+
+    /// Number of distinct backends, the default included.
+    pub fn backend_count(&self) -> usize {
+        self.backends.len()
+    }
+
+    fn assigned(&self) -> std::sync::MutexGuard<'_, HashMap<SagaId, usize>> {
+        self.assigned
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Backend of `saga_id`, assigning one on first sight: by `saga_type`
+    /// when known, else wherever the saga is already stored, else the
+    /// default.
+    fn backend_for(&self, saga_id: SagaId, saga_type: Option<&str>) -> Result<usize, JournalError> {
+        if let Some(index) = self.assigned().get(&saga_id) {
+            return Ok(*index);
+        }
+        let index = match saga_type {
+            Some(saga_type) => self.routes.get(saga_type).copied().unwrap_or(0),
+            None => match self.stored_in(saga_id)? {
+                Some(index) => index,
+                // Nothing stored yet: a typed event may still route it.
+                None => return Ok(0),
+            },
+        };
+        Ok(*self.assigned().entry(saga_id).or_insert(index))
+    }
+
+    fn stored_in(&self, saga_id: SagaId) -> Result<Option<usize>, JournalError> {
+        for (index, backend) in self.backends.iter().enumerate() {
+            if !backend.read(saga_id)?.is_empty() || !backend.incoming_history(saga_id)?.is_empty()
+            {
+                return Ok(Some(index));
+            }
+        }
+        Ok(None)
+    }
+
+    fn global_id(&self, index: usize, local_id: u64) -> Result<u64, JournalError> {
+        local_id
+            .checked_mul(self.backends.len() as u64)
+            .and_then(|id| id.checked_add(index as u64))
+            .ok_or_else(|| JournalError::Storage("routed journal id overflow".into()))
+    }
+
+    fn local_id(&self, global_id: u64) -> (usize, u64) {
+        let backends = self.backends.len() as u64;
+        ((global_id % backends) as usize, global_id / backends)
+    }
+}
+
+fn same_backend(a: &Arc<dyn ParticipantJournal>, b: &Arc<dyn ParticipantJournal>) -> bool {
+    std::ptr::addr_eq(Arc::as_ptr(a), Arc::as_ptr(b))
+}
+
+impl ParticipantJournal for RoutingJournal {
+    fn append(&self, saga_id: SagaId, event: ParticipantEvent) -> Result<u64, JournalError> {
+        let saga_type = match &event {
+            ParticipantEvent::SagaRegistered { saga_type, .. } => Some(saga_type.as_ref()),
+            _ => None,
+        };
+        let index = self.backend_for(saga_id, saga_type)?;
+        self.backends[index].append(saga_id, event)
+    }
+
+    fn read(&self, saga_id: SagaId) -> Result<Vec<JournalEntry>, JournalError> {
+        let index = self.backend_for(saga_id, None)?;
+        self.backends[index].read(saga_id)
+    }
+
+    #[cfg(feature = "rkyv")]
+    fn read_archived(
+        &self,
+        saga_id: SagaId,
+        visit: &mut dyn FnMut(&[crate::ArchivedJournalRow<'_>]),
+    ) -> Result<(), JournalError> {
+        let index = self.backend_for(saga_id, None)?;
+        self.backends[index].read_archived(saga_id, visit)
+    }
+
+    /// Sagas of every backend, ordered by id.
+    fn list_sagas(&self) -> Result<Vec<SagaId>, JournalError> {
+        let mut sagas = BTreeSet::new();
+        for backend in &self.backends {
+            sagas.extend(backend.list_sagas()?);
+        }
+        Ok(sagas.into_iter().collect())
+    }
+
+    fn prune(&self, saga_id: SagaId) -> Result<(), JournalError> {
+        let index = self.backend_for(saga_id, None)?;
+        self.backends[index].prune(saga_id)?;
+        self.assigned().remove(&saga_id);
+        Ok(())
+    }
+
+    fn record_outgoing(
+        &self,
+        saga_id: SagaId,
+        event: &SagaChoreographyEvent,
+    ) -> Result<Option<u64>, JournalError> {
+        let index = self.backend_for(saga_id, Some(&event.context().saga_type))?;
+        self.backends[index]
+            .record_outgoing(saga_id, event)?
+            .map(|outbox_id| self.global_id(index, outbox_id))
+            .transpose()
+    }
+
+    fn append_with_outgoing(
+        &self,
+        saga_id: SagaId,
+        event: ParticipantEvent,
+        outgoing: &[SagaChoreographyEvent],
+    ) -> Result<(u64, Vec<Option<u64>>), JournalError> {
+        let saga_type = match &event {
+            ParticipantEvent::SagaRegistered { saga_type, .. } => Some(saga_type.as_ref()),
+            _ => outgoing
+                .first()
+                .map(|outgoing| outgoing.context().saga_type.as_ref()),
+        };
+        let index = self.backend_for(saga_id, saga_type)?;
+        let (sequence, outbox_ids) =
+            self.backends[index].append_with_outgoing(saga_id, event, outgoing)?;
+        let outbox_ids = outbox_ids
+            .into_iter()
+            .map(|outbox_id| {
+                outbox_id
+                    .map(|outbox_id| self.global_id(index, outbox_id))
+                    .transpose()
+            })
+            .collect::<Result<_, _>>()?;
+        Ok((sequence, outbox_ids))
+    }
+
+    fn pending_outgoing(&self) -> Result<Vec<OutboxEntry>, JournalError> {
+        let mut pending = Vec::new();
+        for (index, backend) in self.backends.iter().enumerate() {
+            for mut entry in backend.pending_outgoing()? {
+                entry.outbox_id = self.global_id(index, entry.outbox_id)?;
+                pending.push(entry);
+            }
+        }
+        pending.sort_by_key(|entry| (entry.recorded_at_millis, entry.outbox_id));
+        Ok(pending)
+    }
+
+    fn mark_outgoing_sent(&self, outbox_id: u64) -> Result<(), JournalError> {
+        let (index, local_id) = self.local_id(outbox_id);
+        self.backends[index].mark_outgoing_sent(local_id)
+    }
+
+    fn record_incoming(
+        &self,
+        saga_id: SagaId,
+        dedupe_key: DedupeKey,
+        event: &SagaChoreographyEvent,
+    ) -> Result<Option<u64>, JournalError> {
+        let index = self.backend_for(saga_id, Some(&event.context().saga_type))?;
+        self.backends[index]
+            .record_incoming(saga_id, dedupe_key, event)?
+            .map(|inbox_id| self.global_id(index, inbox_id))
+            .transpose()
+    }
+
+    fn pending_incoming(&self) -> Result<Vec<InboxEntry>, JournalError> {
+        let mut pending = Vec::new();
+        for (index, backend) in self.backends.iter().enumerate() {
+            for mut entry in backend.pending_incoming()? {
+                entry.inbox_id = self.global_id(index, entry.inbox_id)?;
+                pending.push(entry);
+            }
+        }
+        pending.sort_by_key(|entry| (entry.recorded_at_millis, entry.inbox_id));
+        Ok(pending)
+    }
+
+    fn mark_incoming_processed(&self, inbox_id: u64) -> Result<(), JournalError> {
+        let (index, local_id) = self.local_id(inbox_id);
+        self.backends[index].mark_incoming_processed(local_id)
+    }
+
+    fn incoming_history(&self, saga_id: SagaId) -> Result<Vec<InboxEntry>, JournalError> {
+        let index = self.backend_for(saga_id, None)?;
+        let mut history = self.backends[index].incoming_history(saga_id)?;
+        for entry in &mut history {
+            entry.inbox_id = self.global_id(index, entry.inbox_id)?;
+        }
+        Ok(history)
+    }
+}
+
+impl std::fmt::Debug for RoutingJournal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RoutingJournal")
+            .field("backends", &self.backends.len())
+            .field("routes", &self.routes)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{saga_started, DeterministicContextBuilder, InMemoryJournal};
+
+    #[test]
+    fn routes_sagas_by_type_and_aggregates_across_backends() {
+        let cheap = Arc::new(InMemoryJournal::new());
+        let fast = Arc::new(InMemoryJournal::new());
+        let journal = RoutingJournal::new(cheap.clone())
+            .route("order_lifecycle", fast.clone())
+            .route("order_amend", fast.clone());
+        assert_eq!(journal.backend_count(), 2);
+
+        let order = DeterministicContextBuilder::default()
+            .with_saga_type("order_lifecycle")
+            .build();
+        let mut report = order.clone();
+        report.saga_id = SagaId::new(order.saga_id.get() + 1);
+        report.saga_type = "daily_report".into();
+
+        for context in [&order, &report] {
+            let started = saga_started(context.clone(), Vec::new());
+            journal
+                .record_incoming(context.saga_id, DedupeKey::from_event(&started), &started)
+                .unwrap();
+            journal
+                .append(
+                    context.saga_id,
+                    ParticipantEvent::StepTriggered {
+                        triggering_event: "saga_started".into(),
+                        triggered_at_millis: 0,
+                    },
+                )
+                .unwrap();
+        }
+        assert_eq!(fast.list_sagas().unwrap(), vec![order.saga_id]);
+        assert_eq!(cheap.list_sagas().unwrap(), vec![report.saga_id]);
+        assert_eq!(
+            journal.list_sagas().unwrap(),
+            vec![order.saga_id, report.saga_id]
+        );
+
+        let pending = journal.pending_incoming().unwrap();
+        assert_eq!(pending.len(), 2);
+        assert_ne!(pending[0].inbox_id, pending[1].inbox_id);
+        for entry in &pending {
+            journal.mark_incoming_processed(entry.inbox_id).unwrap();
+        }
+        assert!(journal.pending_incoming().unwrap().is_empty());
+
+        let restarted = RoutingJournal::new(cheap).route("order_lifecycle", fast);
+        assert_eq!(restarted.read(order.saga_id).unwrap().len(), 1);
+        restarted.prune(order.saga_id).unwrap();
+        assert_eq!(restarted.list_sagas().unwrap(), vec![report.saga_id]);
+    }
+}
@@ -38,38 +38,114 @@
 #![allow(missing_docs)]
 
 // === Core Types ===
+mod admission;
+#[cfg(feature = "amqp")]
+mod amqp;
+mod approval_step;
 mod binding;
 mod bus;
+mod chain;
+mod confirmation_step;
 mod context;
 pub mod durability;
+mod error_classifier;
 mod errors;
+mod event_filter;
 mod events;
+mod group;
+#[cfg(feature = "grpc")]
+mod grpc;
+#[cfg(feature = "http-step")]
+mod http_step;
 mod idempotency;
+mod initiator;
+mod rate_limit;
 mod state;
+mod sub_saga;
 mod support;
+mod symbol;
+mod timer_step;
 
 // === Traits ===
 mod state_ext;
 mod traits;
 
 // === Storage ===
+mod archive;
+mod async_storage;
+mod dead_letter;
 mod dedupe;
+mod effect_ledger;
+mod integrity;
 mod journal;
+mod payload;
+mod resource_lock;
+mod saga_id_allocator;
+mod saga_index;
+mod sensitive;
+mod state_store;
+mod step_lease;
 
 // === Observability ===
 mod observer;
+mod participant_registry;
+mod progress;
+mod projection;
+mod quarantine;
+mod span;
 mod stats;
+mod timeline;
+mod trace_policy;
+#[cfg(feature = "http")]
+mod webhook;
 
 // === Helpers ===
+mod ack_watchdog;
+#[cfg(feature = "saga-admin")]
+mod admin;
+#[cfg(feature = "admin-http")]
+mod admin_http;
+mod bulk_compensation;
+mod causality;
+#[cfg(any(test, feature = "test-harness"))]
+mod chaos;
+mod checkpoint;
+mod compensation_strategy;
+mod correlation;
+mod drain;
+mod effects;
+#[cfg(any(test, feature = "test-harness"))]
+mod fault;
 mod helpers;
+mod missing_state;
+mod partition;
+#[cfg(any(test, feature = "test-harness"))]
+mod recording;
+mod recovery;
+mod reorder;
+mod replay;
 mod reply_registry;
 mod resolver;
+#[cfg(any(test, feature = "saga-invariants"))]
+pub mod saga_invariants;
+mod scheduler;
+mod shadow;
+mod step_executor;
+mod takeover;
+#[cfg(any(test, feature = "test-harness"))]
+mod testing;
 mod testkit;
+mod verify;
 mod workflow_contract;
+mod workflow_diagram;
 
 // === Re-exports ===
 
 // Types
+pub use admission::{SagaAdmission, SagaAdmissionOverflow, SagaConcurrencyLimit, SerializationKey};
+#[cfg(feature = "amqp")]
+pub use amqp::{AmqpSagaBus, AmqpSagaBusConfig, AmqpSagaBusError, SagaEventCodec};
+pub use approval_step::{ApprovalError, ApprovalStep, PendingApproval};
 pub use binding::{
     bind_async_participant_channel, bind_async_participant_channel_lazy,
     bind_async_participant_tell, bind_async_workflow_participant_channel,
@@ -84,47 +160,200 @@ pub use binding::{
     SagaParticipantChannel,
 };
 pub use bus::{global_saga_choreography_bus, SagaBusPublishError, SagaChoreographyBus};
-pub use context::{PeerId, SagaContext, SagaId, StepId};
+pub use chain::{SagaChain, SagaChainInput};
+pub use confirmation_step::{ConfirmationMatcher, ConfirmationStep, PendingConfirmation};
+pub use context::{
+    EventSkewError, EventSkewWindow, PeerId, SagaContext, SagaId, StepId, DEFAULT_WORKFLOW_VERSION,
+};
 pub use durability::*;
+pub use group::{GroupCoordinator, SagaGroup, SagaGroupStatus};
+#[cfg(feature = "grpc")]
+pub use grpc::{GrpcParticipantClient, GrpcParticipantServer};
+#[cfg(feature = "http-step")]
+pub use http_step::HttpStepAdapter;
 pub use idempotency::IdempotencyKey;
+pub use initiator::{
+    OutstandingStart, SagaInitiation, SagaInitiator, SagaInitiatorStats, SagaRedeliveryPolicy,
+};
+pub use rate_limit::{RateLimitGate, RateLimited, TokenBucketGate};
+pub use symbol::{SagaType, StepName, Symbol};
 
 // State (typestate)
 pub use state::{
     Compensated, Compensating, Completed, Executing, Failed, Idle, Quarantined,
-    SagaParticipantState, SagaStateEntry, TimestampedEvent, Triggered,
+    SagaParticipantState, SagaStateEntry, StateKind, TimestampedEvent, Triggered,
 };
+pub use sub_saga::SubSagaStep;
 pub use support::{HasSagaParticipantSupport, SagaParticipantSupport, SagaParticipantSupportExt};
+pub use timer_step::TimerStep;
 
 // Events
+pub use event_filter::{
+    observe_saga_event, SagaEventFilter, SagaObserverParticipant, TERMINAL_EVENT_TYPES,
+};
+#[cfg(feature = "rkyv")]
+pub use events::ArchivedParticipantEvent;
 pub use events::{
     AckStatus, ParticipantEvent, SagaChoreographyEvent, SagaFailureDetails, SagaReplyTo,
     SagaTerminalOutcome,
 };
 
 // Errors
-pub use errors::{CompensationError, StepError, StepOutput};
+pub use error_classifier::{
+    classify_by_code, classify_by_substring, transport_errors, DefaultClassifier, ErrorClass,
+    ErrorClassifier, OrClassifier, TRANSPORT_ERROR_PATTERNS,
+};
+pub use errors::{
+    decode_error_details, encode_error_details, AuthError, CompensationError,
+    ErrorDetailsSerializer, ReasonCode, StepError, StepOutput,
+};
 
 // Traits
 pub use state_ext::SagaStateExt;
 pub use traits::{
-    AllowsSagaTellIngress, AsyncSagaParticipant, DependencySpec, HasSagaWorkflowParticipants,
-    SagaBoxFuture, SagaParticipant, SagaWorkflowParticipant,
+    saga_type_matches, AllowsSagaTellIngress, AsyncSagaParticipant, DependencySpec,
+    HasSagaWorkflowParticipants, SagaBoxFuture, SagaParticipant, SagaWorkflowParticipant,
 };
 
 // Storage
-pub use dedupe::{DedupeError, InMemoryDedupe, ParticipantDedupeStore};
-pub use journal::{InMemoryJournal, JournalEntry, JournalError, ParticipantJournal};
+pub use archive::{
+    archive_saga, archive_settled_sagas, copy_saga_to_archive, ArchiveError, ArchiveStore,
+    FileArchiveStore, InMemoryArchiveStore, SagaArchiveRecord,
+};
+pub use async_storage::{
+    AsyncDedupeAdapter, AsyncJournalAdapter, AsyncParticipantDedupeStore, AsyncParticipantJournal,
+};
+pub use dead_letter::{
+    dead_letter_event, dead_letter_raw_payload, replay_dead_letters, DeadLetterEntry,
+    DeadLetterError, DeadLetterPayload, DeadLetterReason, DeadLetterStore, InMemoryDeadLetterStore,
+};
+pub use dedupe::{DedupeError, DedupeIdentity, DedupeKey, InMemoryDedupe, ParticipantDedupeStore};
+pub use effect_ledger::{EffectGuard, EffectLedger};
+pub use integrity::{
+    verify_consistency, verify_consistency_at, ConsistencyError, ConsistencyIssue,
+    ConsistencyReport, RepairAction, RepairPlan, JOURNAL_GAP_QUARANTINE_REASON,
+};
+#[cfg(feature = "rkyv")]
+pub use journal::archived::ArchivedJournalRow;
+pub use journal::dual_write::{backfill, BackfillReport, DualWriteJournal};
+pub use journal::history::{
+    CheckpointRecord, CompensationOutcome, CompensationRecord, EffectRecord, ExecutionOutcome,
+    ExecutionRecord, QuarantineRecord, SagaJournalHistory, SagaRegistration,
+};
+pub use journal::migrate::{
+    decode_journal_entry, encode_journal_entry, journal_row_schema_version, migrate_store,
+    MigrationReport, JOURNAL_SCHEMA_VERSION,
+};
+pub use journal::routing::RoutingJournal;
+#[cfg(feature = "rkyv")]
+pub use journal::ArchivedJournalEntry;
+pub use journal::{
+    InMemoryJournal, InboxEntry, JournalEntry, JournalError, JournalFailurePolicy, OutboxEntry,
+    ParticipantJournal,
+};
+pub use payload::{
+    offload_payload, resolve_payload, InMemoryPayloadStore, PayloadError, PayloadRef, PayloadStore,
+    DEFAULT_PAYLOAD_OFFLOAD_THRESHOLD,
+};
+pub use resource_lock::{
+    InMemoryResourceLockJournal, ResourceLock, ResourceLockError, ResourceLockJournal,
+    ResourceLockManager,
+};
+pub use saga_id_allocator::{
+    JournalSagaIdAllocator, RandomSagaIdAllocator, SagaIdAllocator, SagaIdAllocatorError,
+    DEFAULT_SAGA_ID_BLOCK, SAGA_ID_ALLOCATOR_SAGA,
+};
+pub use saga_index::{InMemorySagaIndex, SagaIndex, SagaIndexError};
+#[cfg(feature = "encryption")]
+pub use sensitive::ChaCha20Poly1305Cipher;
+pub use sensitive::{PayloadCipher, SensitivePayload, SensitivePayloadError};
+pub use state_store::{InMemoryStateStore, ParticipantStateStore, ParticipantStateStoreError};
+pub use step_lease::{
+    InMemoryStepLeaseStore, StepLease, StepLeaseError, StepLeaseStore, StepLeases,
+};
 
 // Observability
 pub use observer::{NoOpObserver, SagaObserver, TracingObserver};
-pub use stats::{ParticipantStats, ParticipantStatsSnapshot};
+pub use participant_registry::{announce_participant, ParticipantAnnounced, ParticipantRegistry};
+pub use progress::{SagaProgress, SagaProgressAggregator, StepProgress, StepProgressStatus};
+pub use projection::{rebuild_projection, SagaProjection, SharedSagaProjection};
+pub use quarantine::{
+    QuarantineError, QuarantineManager, QuarantineResolution, QuarantineResolutionKind,
+    QuarantinedSaga,
+};
+pub use span::SagaSpanExt;
+pub use stats::{
+    ActiveSaga, InMemoryStatsPersistence, ParticipantStats, ParticipantStatsSnapshot,
+    SagaActivityTracker, SagaTypeStats, StatCounter, StatsPersistence, StatsPersistenceError,
+    DEFAULT_STATS_PERSIST_INTERVAL_MILLIS,
+};
+#[cfg(feature = "hdr")]
+pub use stats::{LatencyHandle, LatencyRecorder, LatencySnapshot};
+pub use timeline::{SagaTimeline, SagaTimelineBuilder, TimelineEntry, TimelineEntryKind};
+pub use trace_policy::{SagaPriority, SampledObserver, TracePolicy};
+#[cfg(feature = "http")]
+pub use webhook::WebhookObserver;
+
+// Scheduling
+pub use scheduler::{
+    CronSchedule, InMemorySagaScheduleJournal, SagaSchedule, SagaScheduleError,
+    SagaScheduleJournal, SagaScheduleTrigger, SagaScheduler,
+};
 
 // Helpers
-pub use helpers::{handle_async_saga_event_with_emit, handle_saga_event_with_emit};
+pub use ack_watchdog::StepAckWatchdog;
+#[cfg(feature = "saga-admin")]
+pub use admin::{
+    run_admin_command, AdminCli, AdminCommand, AdminError, SagaAdmin, SagaHistory, SagaSummary,
+};
+#[cfg(feature = "admin-http")]
+pub use admin_http::SagaAdminHttp;
+pub use bulk_compensation::{request_compensation_for_type, BulkCompensationReport};
+pub use causality::{merge_saga_histories, verify_causality, CausalityViolation, LamportClock};
+#[cfg(any(test, feature = "test-harness"))]
+pub use chaos::{
+    run_chaos, ChaosPolicy, ChaosReport, ChaosStats, ChaosViolation, ChaosViolationKind,
+};
+pub use checkpoint::{checkpoint, resume_from_checkpoint};
+pub use compensation_strategy::{CompensationScope, CompensationSelector, CompensationStrategy};
+pub use correlation::EffectCorrelationIndex;
+pub use drain::{
+    drain, drain_async, in_flight_sagas, DrainReport, InFlightPhase, InFlightSaga,
+    DRAIN_PARK_REASON,
+};
+pub use effects::{EffectDispatcher, EffectError, EffectInvocation, EffectRegistry};
+#[cfg(any(test, feature = "test-harness"))]
+pub use fault::{
+    FaultController, FaultSchedule, FaultyBus, FaultyDedupe, FaultyJournal, InjectedFault,
+};
+pub use helpers::{
+    flush_async_reorder_buffer_with_emit, flush_reorder_buffer_with_emit,
+    handle_async_saga_event_with_emit, handle_saga_event_with_emit, poll_offloaded_steps_with_emit,
+    poll_offloaded_steps_with_emit_async, replay_async_saga_inbox_with_emit,
+    replay_saga_inbox_with_emit, retry_compensation,
+};
+pub use missing_state::MissingStatePolicy;
+pub use partition::{
+    partition_of, ExternalPartitionAssigner, HashPartitionAssigner, PartitionAssigner,
+    PartitionReassigned,
+};
+#[cfg(any(test, feature = "test-harness"))]
+pub use recording::{assert_event_stream, canonical_event_stream, RecordingBus, RecordingObserver};
+pub use recovery::{
+    recover_sagas, restore_dedupe_state, restore_saga_states, SagaRecoveryError,
+    SagaRecoveryOnStart, SagaRecoveryReport,
+};
+pub use reorder::{ReorderExpiry, SagaReorderWindow};
+pub use replay::{replay_saga, SagaReplayDivergence, SagaReplayReport};
 pub use reply_registry::{SagaReplyToHandle, SagaReplyToResult};
 pub use resolver::{
     FailureAuthority, SuccessCriteria, TerminalPolicy, TerminalResolver, TERMINAL_RESOLVER_STEP,
 };
+pub use shadow::{ShadowComparison, ShadowOutcome, ShadowParticipant};
+pub use step_executor::{OffloadedStep, StepExecutor, ThreadStepExecutor, TokioStepExecutor};
+pub use takeover::{InitiatorHeartbeats, InitiatorTakeover, INITIATOR_TAKEOVER_STEP};
+#[cfg(any(test, feature = "test-harness"))]
+pub use testing::{MockClock, SagaTestHarness};
 #[cfg(any(test, feature = "test-harness"))]
 pub use testkit::AsyncSagaParticipantHandle;
 pub use testkit::{
@@ -133,6 +362,7 @@ pub use testkit::{
 };
 #[cfg(any(test, feature = "test-harness"))]
 pub use testkit::{SagaTestWorld, SyncSagaParticipantHandle};
+pub use verify::{verify_participant, ParticipantVerificationError, ParticipantWiringProblem};
 pub use workflow_contract::{
     required_steps_from_success_criteria, validate_workflow_contract, SagaWorkflowContract,
     SagaWorkflowStepContract, WorkflowDependencySpec,
@@ -0,0 +1,314 @@
+//! Transitions that find no state entry for their saga.
+//!
+//! Each helper transition (`Executing -> Completed`, `Completed ->
+//! Compensating`, ...) takes the saga's entry out of `saga_states`. When the
+//! entry is gone, e.g. because the participant restarted without
+//! [`crate::restore_saga_states`], the transition used to be skipped without
+//! a trace. The participant's [`MissingStatePolicy`] now decides what happens,
+//! and the attached [`crate::SagaObserver`] hears about it either way.
+
+use crate::async_storage::journal_read;
+use crate::state_ext::try_record_event_async;
+use crate::{
+    JournalEntry, ParticipantEvent, ParticipantJournal, Quarantined, QuarantinedSaga,
+    SagaChoreographyEvent, SagaContext, SagaId, SagaParticipantState, SagaStateEntry, SagaStateExt,
+    StateKind,
+};
+
+/// What a transition does when its saga has no entry in `saga_states`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MissingStatePolicy {
+    /// Log, notify the observer and go on without a state entry.
+    #[default]
+    Ignore,
+    /// Load the entry from the attached state store, else rebuild it from
+    /// the saga's journal, then transition as usual. Sagas neither has are
+    /// handled as [`Self::Ignore`]. The journal does not hold compensation
+    /// data, so a completed step rebuilt from it alone compensates with none.
+    RebuildFromJournal,
+    /// Move the saga to `Quarantined` for an operator instead of
+    /// transitioning.
+    Quarantine,
+    /// Panic in debug builds, to catch lost states in tests; same as
+    /// [`Self::Ignore`] in release builds.
+    PanicInDebug,
+}
+
+/// Reason journaled for sagas quarantined by [`MissingStatePolicy::Quarantine`].
+const MISSING_STATE_QUARANTINE_REASON: &str = "saga state entry missing";
+
+/// Applies the participant's [`MissingStatePolicy`] to a transition that
+/// expected the saga in `expected` but found no entry. Returns false when the
+/// transition must not go ahead.
+pub(crate) fn handle_missing_state<P, F>(
+    participant: &mut P,
+    context: &SagaContext,
+    step_name: &str,
+    participant_id: Box<str>,
+    expected: StateKind,
+    now: u64,
+    emit: &mut F,
+) -> bool
+where
+    P: SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let saga_id = context.saga_id;
+    match announce_missing_state(participant, context, step_name, expected) {
+        MissingStatePolicy::RebuildFromJournal => {
+            let entry = stored_saga_state(participant, saga_id).or_else(|| {
+                let entries = participant.saga_journal().read(saga_id).ok()?;
+                rebuilt_saga_state(context, step_name, &entries)
+            });
+            insert_rebuilt_state(participant, context, step_name, entry);
+            true
+        }
+        MissingStatePolicy::Quarantine => {
+            let (state, entry) = missing_state_quarantine(context, step_name, now);
+            participant.put_saga_state(saga_id, state);
+            participant.record_event(saga_id, entry);
+            report_missing_state_quarantine(
+                participant,
+                context,
+                step_name,
+                participant_id,
+                now,
+                emit,
+            );
+            false
+        }
+        MissingStatePolicy::Ignore | MissingStatePolicy::PanicInDebug => true,
+    }
+}
+
+/// [`handle_missing_state`] for async handlers, which await an async
+/// journal.
+pub(crate) async fn handle_missing_state_async<P, F>(
+    participant: &mut P,
+    context: &SagaContext,
+    step_name: &str,
+    participant_id: Box<str>,
+    expected: StateKind,
+    now: u64,
+    emit: &mut F,
+) -> bool
+where
+    P: SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let saga_id = context.saga_id;
+    match announce_missing_state(participant, context, step_name, expected) {
+        MissingStatePolicy::RebuildFromJournal => {
+            let mut entry = stored_saga_state(participant, saga_id);
+            if entry.is_none() {
+                if let Ok(entries) = journal_read(participant.saga_journal(), saga_id).await {
+                    entry = rebuilt_saga_state(context, step_name, &entries);
+                }
+            }
+            insert_rebuilt_state(participant, context, step_name, entry);
+            true
+        }
+        MissingStatePolicy::Quarantine => {
+            let (state, entry) = missing_state_quarantine(context, step_name, now);
+            participant.put_saga_state(saga_id, state);
+            try_record_event_async(participant, saga_id, entry).await;
+            report_missing_state_quarantine(
+                participant,
+                context,
+                step_name,
+                participant_id,
+                now,
+                emit,
+            );
+            false
+        }
+        MissingStatePolicy::Ignore | MissingStatePolicy::PanicInDebug => true,
+    }
+}
+
+/// Logs the missing entry, tells the observer and returns the policy to
+/// apply; [`MissingStatePolicy::PanicInDebug`] panics here in debug builds.
+fn announce_missing_state<P>(
+    participant: &P,
+    context: &SagaContext,
+    step_name: &str,
+    expected: StateKind,
+) -> MissingStatePolicy
+where
+    P: SagaStateExt,
+{
+    let saga_id = context.saga_id;
+    let policy = participant.saga_support().missing_state_policy;
+    tracing::warn!(
+        target: "core::saga",
+        event = "saga_state_missing",
+        saga_id = saga_id.get(),
+        step_name,
+        expected = ?expected,
+        policy = ?policy
+    );
+    if let Some(observer) = &participant.saga_support().observer {
+        observer.on_missing_state(context, step_name, expected);
+    }
+    if policy == MissingStatePolicy::PanicInDebug && cfg!(debug_assertions) {
+        panic!(
+            "saga {} step {step_name}: no state entry, expected {expected:?}",
+            saga_id.get()
+        );
+    }
+    policy
+}
+
+fn insert_rebuilt_state<P>(
+    participant: &mut P,
+    context: &SagaContext,
+    step_name: &str,
+    entry: Option<SagaStateEntry>,
+) where
+    P: SagaStateExt,
+{
+    let Some(entry) = entry else {
+        return;
+    };
+    tracing::info!(
+        target: "core::saga",
+        event = "saga_state_rebuilt",
+        saga_id = context.saga_id.get(),
+        step_name,
+        state = ?entry.kind()
+    );
+    participant.saga_states().insert(context.saga_id, entry);
+}
+
+/// The `Quarantined` entry and its journal entry for a saga quarantined by
+/// [`MissingStatePolicy::Quarantine`].
+fn missing_state_quarantine(
+    context: &SagaContext,
+    step_name: &str,
+    now: u64,
+) -> (SagaStateEntry, ParticipantEvent) {
+    let reason: Box<str> = MISSING_STATE_QUARANTINE_REASON.into();
+    let quarantined = SagaParticipantState {
+        saga_id: context.saga_id,
+        saga_type: context.saga_type.clone(),
+        step_name: step_name.into(),
+        correlation_id: context.correlation_id,
+        trace_id: context.trace_id,
+        initiator_peer_id: context.initiator_peer_id,
+        saga_started_at_millis: context.saga_started_at_millis,
+        last_updated_at_millis: now,
+        state: Quarantined {
+            quarantined_at_millis: now,
+            reason: reason.clone(),
+            compensation_data: Vec::new(),
+        },
+        events: Vec::new(),
+    };
+    (
+        SagaStateEntry::Quarantined(quarantined),
+        ParticipantEvent::Quarantined {
+            reason,
+            quarantined_at_millis: now,
+        },
+    )
+}
+
+fn report_missing_state_quarantine<P, F>(
+    participant: &P,
+    context: &SagaContext,
+    step_name: &str,
+    participant_id: Box<str>,
+    now: u64,
+    emit: &mut F,
+) where
+    P: SagaStateExt,
+    F: FnMut(SagaChoreographyEvent),
+{
+    let reason: Box<str> = MISSING_STATE_QUARANTINE_REASON.into();
+    participant
+        .saga_support()
+        .report_quarantined(QuarantinedSaga {
+            context: context.clone(),
+            step: step_name.into(),
+            participant_id: participant_id.clone(),
+            reason: reason.clone(),
+            quarantined_at_millis: now,
+            compensation_data: None,
+            failed_retries: 0,
+        });
+    emit(SagaChoreographyEvent::SagaQuarantined {
+        context: context.next_step(step_name.into()),
+        reason,
+        step: step_name.into(),
+        participant_id,
+    });
+}
+
+/// The saga's entry from the attached state store, if it has one.
+fn stored_saga_state<P>(participant: &P, saga_id: SagaId) -> Option<SagaStateEntry>
+where
+    P: SagaStateExt,
+{
+    let store = participant.saga_support().state_store.as_ref()?;
+    store.get(saga_id).ok().flatten()
+}
+
+/// The state the saga's last journaled progress event leads to.
+fn rebuilt_saga_state(
+    context: &SagaContext,
+    step_name: &str,
+    entries: &[JournalEntry],
+) -> Option<SagaStateEntry> {
+    let saga_id = context.saga_id;
+    let last = crate::journal::last_progress_entry(entries)?;
+    let idle = SagaParticipantState::new(
+        saga_id,
+        context.saga_type.clone(),
+        step_name.into(),
+        context.correlation_id,
+        context.trace_id,
+        context.initiator_peer_id,
+        context.saga_started_at_millis,
+    );
+    let at = last.recorded_at_millis;
+    let entry = match &last.event {
+        ParticipantEvent::StepTriggered {
+            triggering_event,
+            triggered_at_millis,
+        } => SagaStateEntry::Triggered(idle.trigger(triggering_event, *triggered_at_millis)),
+        ParticipantEvent::StepExecutionStarted {
+            attempt,
+            started_at_millis,
+        } => {
+            let mut executing = idle
+                .trigger("rebuilt", *started_at_millis)
+                .start_execution(*started_at_millis);
+            executing.state.attempt = *attempt;
+            SagaStateEntry::Executing(executing)
+        }
+        ParticipantEvent::StepExecutionCompleted {
+            output,
+            compensation_data,
+            completed_at_millis,
+        } => SagaStateEntry::Completed(idle.trigger("rebuilt", at).start_execution(at).complete(
+            output.clone(),
+            compensation_data.clone(),
+            *completed_at_millis,
+        )),
+        ParticipantEvent::CompensationStarted {
+            attempt,
+            started_at_millis,
+        } => {
+            let mut compensating = idle
+                .trigger("rebuilt", at)
+                .start_execution(at)
+                .complete(Vec::new(), Vec::new(), at)
+                .start_compensation(*started_at_millis);
+            compensating.state.attempt = *attempt;
+            SagaStateEntry::Compensating(compensating)
+        }
+        // Terminal or registration-only progress: no transition resumes from it.
+        _ => return None,
+    };
+    Some(entry)
+}
@@ -1,6 +1,8 @@
 //! Saga observer trait
 
-use super::SagaContext;
+use std::time::Duration;
+
+use super::{SagaContext, StateKind};
 
 /// Observer trait for external observability.
 ///
@@ -77,6 +79,74 @@ pub trait SagaObserver: Send + Sync + 'static {
     /// @param step - The name/identifier of the step that caused the quarantine
     /// @param reason - A description of why the saga was quarantined
     fn on_saga_quarantined(&self, context: &SagaContext, step: &str, reason: &str);
+
+    /// Called before a step is retried after a transient failure.
+    ///
+    /// @param context - The saga context
+    /// @param step - The name/identifier of the step being retried
+    /// @param attempt - The retry about to run (1 for the first retry)
+    /// @param delay - How long the participant waits before the retry
+    fn on_step_retry(&self, context: &SagaContext, step: &str, attempt: u32, delay: Duration) {
+        let _ = (context, step, attempt, delay);
+    }
+
+    /// Called when the journal rejected a record of a step's execution or
+    /// compensation and the append is retried under
+    /// [`JournalFailurePolicy::Retry`](crate::JournalFailurePolicy::Retry).
+    ///
+    /// @param context - The saga context
+    /// @param step - The name/identifier of the step whose record is retried
+    /// @param attempt - The retry about to run (1 for the first retry)
+    /// @param delay - How long the participant waits before the retry
+    fn on_journal_append_retry(
+        &self,
+        context: &SagaContext,
+        step: &str,
+        attempt: u32,
+        delay: Duration,
+    ) {
+        let _ = (context, step, attempt, delay);
+    }
+
+    /// Called when a terminal resolver times a saga out while a step is
+    /// still outstanding.
+    ///
+    /// @param context - The saga context
+    /// @param step - The name/identifier of the step that had not finished
+    /// @param elapsed - Time since saga start (overall timeout) or since the
+    ///   last progress event (stalled timeout)
+    fn on_step_timeout(&self, context: &SagaContext, step: &str, elapsed: Duration) {
+        let _ = (context, step, elapsed);
+    }
+
+    /// Called when an incoming event is dropped because its dedupe key was
+    /// already marked, i.e. it was redelivered or retransmitted.
+    ///
+    /// @param context - The context the duplicate carried
+    /// @param event_type - The event type, e.g. `step_completed`
+    fn on_duplicate_event(&self, context: &SagaContext, event_type: &str) {
+        let _ = (context, event_type);
+    }
+
+    /// Called when `execute_step` or `compensate_step` panicked. The panic
+    /// was contained and the step or compensation failed as terminal.
+    ///
+    /// @param context - The saga context
+    /// @param step - The name/identifier of the step that panicked
+    /// @param message - The panic message
+    fn on_step_panicked(&self, context: &SagaContext, step: &str, message: &str) {
+        let _ = (context, step, message);
+    }
+
+    /// Called when a transition finds no state entry for its saga, before
+    /// the participant's [`crate::MissingStatePolicy`] is applied.
+    ///
+    /// @param context - The saga context
+    /// @param step - The name/identifier of the step being transitioned
+    /// @param expected - The state the transition expected the saga in
+    fn on_missing_state(&self, context: &SagaContext, step: &str, expected: StateKind) {
+        let _ = (context, step, expected);
+    }
 }
 
 /// A no-operation observer that ignores all saga events.
@@ -101,8 +171,8 @@ impl SagaObserver for NoOpObserver {
 ///
 /// This observer logs all saga lifecycle events at appropriate log levels:
 /// - `INFO`: Normal operations (saga started, step started/completed, compensation events)
-/// - `WARN`: Step failures
-/// - `ERROR`: Saga failures and quarantines
+/// - `WARN`: Step failures, retries and duplicate events
+/// - `ERROR`: Saga failures, quarantines and step timeouts
 ///
 /// Each log event includes structured fields for `saga_id`, and where applicable,
 /// `step`, `duration_ms`, `error`, or `reason`.
@@ -144,4 +214,34 @@ impl SagaObserver for TracingObserver {
     fn on_compensation_completed(&self, context: &SagaContext, step: &str) {
         tracing::info!(saga_id = %context.saga_id.0, step = %step, "Compensation completed");
     }
+
+    fn on_step_retry(&self, context: &SagaContext, step: &str, attempt: u32, delay: Duration) {
+        tracing::warn!(saga_id = %context.saga_id.0, step = %step, attempt, delay_ms = delay.as_millis() as u64, "Step retry");
+    }
+
+    fn on_journal_append_retry(
+        &self,
+        context: &SagaContext,
+        step: &str,
+        attempt: u32,
+        delay: Duration,
+    ) {
+        tracing::warn!(saga_id = %context.saga_id.0, step = %step, attempt, delay_ms = delay.as_millis() as u64, "Journal append retry");
+    }
+
+    fn on_step_timeout(&self, context: &SagaContext, step: &str, elapsed: Duration) {
+        tracing::error!(saga_id = %context.saga_id.0, step = %step, elapsed_ms = elapsed.as_millis() as u64, "Step timed out");
+    }
+
+    fn on_duplicate_event(&self, context: &SagaContext, event_type: &str) {
+        tracing::warn!(saga_id = %context.saga_id.0, event_type = %event_type, "Duplicate event dropped");
+    }
+
+    fn on_step_panicked(&self, context: &SagaContext, step: &str, message: &str) {
+        tracing::error!(saga_id = %context.saga_id.0, step = %step, message = %message, "Step panicked");
+    }
+
+    fn on_missing_state(&self, context: &SagaContext, step: &str, expected: StateKind) {
+        tracing::warn!(saga_id = %context.saga_id.0, step = %step, expected = ?expected, "Saga state missing");
+    }
 }
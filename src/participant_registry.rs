@@ -0,0 +1,245 @@
+//! Discovery of which peers run which saga steps.
+//!
+//! A participant announces itself with [`announce_participant`], typically
+//! from its actor's start hook and then periodically as a heartbeat. The
+//! [`ParticipantAnnounced`] goes to the [`ParticipantRegistry`] attached to
+//! its bus with [`crate::SagaChoreographyBus::attach_participant_registry`], which
+//! remembers the latest announcement per peer:
+//!
+//! ```ignore
+//! bus.attach_participant_registry(ParticipantRegistry::new().with_ttl(Duration::from_secs(30)));
+//! announce_participant(&reserver, local_peer_id);
+//!
+//! let initiator = SagaInitiator::new(bus, dedupe).require_live_participants();
+//! ```
+//!
+//! A peer is live while its last announcement is younger than the registry's
+//! ttl, or forever when no ttl is set. Announcements travel on the in-process
+//! bus only.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::{PeerId, SagaContext, SagaParticipant, SagaStateExt, SagaType, StepName};
+
+/// A peer's claim to run `steps` for `saga_types`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParticipantAnnounced {
+    pub peer_id: PeerId,
+    pub saga_types: Vec<SagaType>,
+    pub steps: Vec<StepName>,
+    pub announced_at_millis: u64,
+}
+
+impl ParticipantAnnounced {
+    fn covers(&self, saga_type: &str, step: &str) -> bool {
+        self.saga_types.iter().any(|t| t.as_ref() == saga_type)
+            && self.steps.iter().any(|s| s.as_ref() == step)
+    }
+}
+
+/// Latest announcement of each peer. Clones share the same table.
+#[derive(Clone, Default)]
+pub struct ParticipantRegistry {
+    peers: Arc<Mutex<HashMap<PeerId, ParticipantAnnounced>>>,
+    ttl_millis: Option<u64>,
+}
+
+impl ParticipantRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Treats peers that have not announced for `ttl` as gone.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl_millis = Some(ttl.as_millis() as u64);
+        self
+    }
+
+    /// Records `announcement`, replacing the peer's previous one.
+    pub fn record(&self, announcement: ParticipantAnnounced) {
+        self.peers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(announcement.peer_id, announcement);
+    }
+
+    /// Forgets `peer_id`, e.g. when its actor stops. Returns whether it was
+    /// known.
+    pub fn withdraw(&self, peer_id: &PeerId) -> bool {
+        self.peers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(peer_id)
+            .is_some()
+    }
+
+    /// Live peers running `step` for `saga_type`.
+    pub fn live_peers(&self, saga_type: &str, step: &str) -> Vec<PeerId> {
+        let now = SagaContext::now_millis();
+        self.peers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .values()
+            .filter(|peer| self.is_live(peer, now) && peer.covers(saga_type, step))
+            .map(|peer| peer.peer_id)
+            .collect()
+    }
+
+    /// Those of `steps` that no live peer runs for `saga_type`.
+    pub fn missing_steps(&self, saga_type: &str, steps: &[&str]) -> Vec<StepName> {
+        let now = SagaContext::now_millis();
+        let peers = self
+            .peers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        steps
+            .iter()
+            .copied()
+            .filter(|step| {
+                !peers
+                    .values()
+                    .any(|peer| self.is_live(peer, now) && peer.covers(saga_type, step))
+            })
+            .map(StepName::from)
+            .collect()
+    }
+
+    /// Number of known peers, live or not.
+    pub fn len(&self) -> usize {
+        self.peers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn is_live(&self, peer: &ParticipantAnnounced, now: u64) -> bool {
+        self.ttl_millis
+            .is_none_or(|ttl| now.saturating_sub(peer.announced_at_millis) < ttl)
+    }
+}
+
+impl std::fmt::Debug for ParticipantRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParticipantRegistry")
+            .field("peers_len", &self.len())
+            .field("ttl_millis", &self.ttl_millis)
+            .finish()
+    }
+}
+
+/// Announces `participant` as `peer_id` on its attached bus. Returns the
+/// announcement, or `None` when no bus is attached.
+pub fn announce_participant<P>(participant: &P, peer_id: PeerId) -> Option<ParticipantAnnounced>
+where
+    P: SagaParticipant + SagaStateExt,
+{
+    let bus = participant.saga_support().bus.as_ref()?;
+    let announcement = ParticipantAnnounced {
+        peer_id,
+        saga_types: participant
+            .saga_types()
+            .iter()
+            .map(|saga_type| SagaType::from(*saga_type))
+            .collect(),
+        steps: vec![participant.step_name().into()],
+        announced_at_millis: participant.now_millis(),
+    };
+    bus.announce(announcement.clone());
+    Some(announcement)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        CompensationError, HasSagaParticipantSupport, InMemoryDedupe, InMemoryJournal,
+        SagaChoreographyBus, SagaParticipantSupport, StepError, StepOutput,
+    };
+
+    struct Reserver {
+        saga: SagaParticipantSupport<InMemoryJournal, InMemoryDedupe>,
+    }
+
+    impl HasSagaParticipantSupport for Reserver {
+        type Journal = InMemoryJournal;
+        type Dedupe = InMemoryDedupe;
+
+        fn saga_support(&self) -> &SagaParticipantSupport<Self::Journal, Self::Dedupe> {
+            &self.saga
+        }
+
+        fn saga_support_mut(&mut self) -> &mut SagaParticipantSupport<Self::Journal, Self::Dedupe> {
+            &mut self.saga
+        }
+    }
+
+    impl SagaParticipant for Reserver {
+        type Error = String;
+
+        fn step_name(&self) -> &str {
+            "reserve_funds"
+        }
+
+        fn saga_types(&self) -> &[&'static str] {
+            &["order_lifecycle"]
+        }
+
+        fn execute_step(
+            &mut self,
+            _context: &SagaContext,
+            _input: &[u8],
+        ) -> Result<StepOutput, StepError> {
+            Ok(StepOutput::Completed {
+                output: Vec::new(),
+                compensation_data: Vec::new(),
+            })
+        }
+
+        fn compensate_step(
+            &mut self,
+            _context: &SagaContext,
+            _compensation_data: &[u8],
+        ) -> Result<(), CompensationError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn announced_steps_are_live_until_ttl_expires() {
+        let bus = SagaChoreographyBus::new();
+        let registry = ParticipantRegistry::new().with_ttl(Duration::from_secs(30));
+        bus.attach_participant_registry(registry.clone());
+        let mut reserver = Reserver {
+            saga: SagaParticipantSupport::new(InMemoryJournal::new(), InMemoryDedupe::new()),
+        };
+        reserver.saga.attach_bus(bus.clone());
+
+        let announced = announce_participant(&reserver, [7; 32]).unwrap();
+        assert_eq!(
+            registry.live_peers("order_lifecycle", "reserve_funds"),
+            vec![[7; 32]]
+        );
+        assert_eq!(
+            registry.missing_steps("order_lifecycle", &["reserve_funds", "place_order"]),
+            vec![StepName::from("place_order")]
+        );
+
+        registry.record(ParticipantAnnounced {
+            announced_at_millis: announced.announced_at_millis - 30_000,
+            ..announced
+        });
+        assert_eq!(registry.len(), 1);
+        assert_eq!(
+            registry.missing_steps("order_lifecycle", &["reserve_funds"]),
+            vec![StepName::from("reserve_funds")]
+        );
+        assert!(registry.withdraw(&[7; 32]));
+        assert!(registry.is_empty());
+    }
+}
@@ -0,0 +1,323 @@
+//! Sharing sagas between replicas of a participant.
+//!
+//! N replicas of a participant all subscribe to the same events. With a
+//! [`PartitionAssigner`] attached
+//! ([`crate::SagaParticipantSupport::with_partition_assigner`]) each replica
+//! drops the events of sagas it does not own before they reach its inbox,
+//! so every saga is handled by exactly one replica:
+//!
+//! ```ignore
+//! let partitions = Arc::new(HashPartitionAssigner::new(replica_index, replica_count));
+//! partitions.on_reassigned(|change| recover_gained_partitions(change));
+//! let saga = SagaParticipantSupport::new(journal, dedupe).with_partition_assigner(partitions.clone());
+//!
+//! // A replica joined.
+//! partitions.reassign(replica_index, replica_count + 1);
+//! ```
+//!
+//! A saga belongs to partition [`partition_of`]`(saga_id, partitions)`.
+//! [`HashPartitionAssigner`] gives replica `i` of `n` partition `i` of `n`;
+//! [`ExternalPartitionAssigner`] owns whichever partitions a coordinator
+//! hands it. Either tells its listeners about every change with a
+//! [`PartitionReassigned`]; sagas of gained partitions are in flight
+//! elsewhere, so recover them from the shared journal or let their
+//! previous owner finish them.
+
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+
+use crate::{SagaContext, SagaId};
+
+/// Decides which sagas this replica processes.
+pub trait PartitionAssigner: Send + Sync + 'static {
+    fn owns(&self, saga_id: SagaId) -> bool;
+}
+
+impl<T> PartitionAssigner for Arc<T>
+where
+    T: PartitionAssigner + ?Sized,
+{
+    fn owns(&self, saga_id: SagaId) -> bool {
+        (**self).owns(saga_id)
+    }
+}
+
+/// Partition of `saga_id` among `partitions`, the same on every replica.
+/// Sequential ids are spread evenly.
+pub fn partition_of(saga_id: SagaId, partitions: u32) -> u32 {
+    // splitmix64 finalizer.
+    let mut mixed = saga_id.get().wrapping_add(0x9e37_79b9_7f4a_7c15);
+    mixed = (mixed ^ (mixed >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    mixed = (mixed ^ (mixed >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    mixed ^= mixed >> 31;
+    (mixed % u64::from(partitions.max(1))) as u32
+}
+
+/// A change of the partitions this replica owns.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PartitionReassigned {
+    /// Counts the changes, starting at 1.
+    pub generation: u64,
+    pub partitions_before: u32,
+    pub owned_before: BTreeSet<u32>,
+    pub partitions: u32,
+    pub owned: BTreeSet<u32>,
+    pub reassigned_at_millis: u64,
+}
+
+impl PartitionReassigned {
+    /// Whether this replica now owns `saga_id` but did not before.
+    pub fn gained(&self, saga_id: SagaId) -> bool {
+        self.owned.contains(&partition_of(saga_id, self.partitions))
+            && !self
+                .owned_before
+                .contains(&partition_of(saga_id, self.partitions_before))
+    }
+
+    /// Whether this replica owned `saga_id` before but no longer does.
+    pub fn lost(&self, saga_id: SagaId) -> bool {
+        self.owned_before
+            .contains(&partition_of(saga_id, self.partitions_before))
+            && !self.owned.contains(&partition_of(saga_id, self.partitions))
+    }
+}
+
+type ReassignmentListener = Arc<dyn Fn(&PartitionReassigned) + Send + Sync>;
+
+/// Owned partitions and the listeners told when they change.
+#[derive(Default)]
+struct Ownership {
+    partitions: u32,
+    owned: BTreeSet<u32>,
+    generation: u64,
+    listeners: Vec<ReassignmentListener>,
+}
+
+impl Ownership {
+    fn owns(&self, saga_id: SagaId) -> bool {
+        self.owned.contains(&partition_of(saga_id, self.partitions))
+    }
+
+    /// Switches to `owned` of `partitions` and tells the listeners, outside
+    /// the lock.
+    fn reassign(
+        ownership: &Mutex<Self>,
+        partitions: u32,
+        owned: BTreeSet<u32>,
+    ) -> PartitionReassigned {
+        let (change, listeners) = {
+            let mut current = ownership
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            current.generation += 1;
+            let change = PartitionReassigned {
+                generation: current.generation,
+                partitions_before: current.partitions,
+                owned_before: std::mem::replace(&mut current.owned, owned.clone()),
+                partitions,
+                owned,
+                reassigned_at_millis: SagaContext::now_millis(),
+            };
+            current.partitions = partitions;
+            (change, current.listeners.clone())
+        };
+        tracing::info!(
+            target: "core::saga",
+            event = "saga_partitions_reassigned",
+            generation = change.generation,
+            partitions = change.partitions,
+            owned = ?change.owned
+        );
+        for listener in listeners {
+            listener(&change);
+        }
+        change
+    }
+}
+
+/// Replica `replica_index` of `replica_count` owns partition
+/// `replica_index` of `replica_count`.
+pub struct HashPartitionAssigner {
+    ownership: Mutex<Ownership>,
+}
+
+impl HashPartitionAssigner {
+    pub fn new(replica_index: u32, replica_count: u32) -> Self {
+        let replica_count = replica_count.max(1);
+        Self {
+            ownership: Mutex::new(Ownership {
+                partitions: replica_count,
+                owned: BTreeSet::from([replica_index % replica_count]),
+                ..Ownership::default()
+            }),
+        }
+    }
+
+    /// Membership changed: this replica is now `replica_index` of
+    /// `replica_count`.
+    pub fn reassign(&self, replica_index: u32, replica_count: u32) -> PartitionReassigned {
+        let replica_count = replica_count.max(1);
+        Ownership::reassign(
+            &self.ownership,
+            replica_count,
+            BTreeSet::from([replica_index % replica_count]),
+        )
+    }
+
+    /// Calls `listener` with every later reassignment.
+    pub fn on_reassigned(&self, listener: impl Fn(&PartitionReassigned) + Send + Sync + 'static) {
+        self.ownership
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .listeners
+            .push(Arc::new(listener));
+    }
+
+    /// `(replica_index, replica_count)`.
+    pub fn assignment(&self) -> (u32, u32) {
+        let ownership = self
+            .ownership
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let index = ownership.owned.first().copied().unwrap_or_default();
+        (index, ownership.partitions)
+    }
+}
+
+impl PartitionAssigner for HashPartitionAssigner {
+    fn owns(&self, saga_id: SagaId) -> bool {
+        self.ownership
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .owns(saga_id)
+    }
+}
+
+impl std::fmt::Debug for HashPartitionAssigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (replica_index, replica_count) = self.assignment();
+        f.debug_struct("HashPartitionAssigner")
+            .field("replica_index", &replica_index)
+            .field("replica_count", &replica_count)
+            .finish()
+    }
+}
+
+/// Owns the partitions an external coordinator assigns; none until the
+/// first [`assign`](Self::assign).
+pub struct ExternalPartitionAssigner {
+    ownership: Mutex<Ownership>,
+}
+
+impl ExternalPartitionAssigner {
+    pub fn new(partitions: u32) -> Self {
+        Self {
+            ownership: Mutex::new(Ownership {
+                partitions: partitions.max(1),
+                ..Ownership::default()
+            }),
+        }
+    }
+
+    /// Replaces the owned partitions; those outside the partition count are
+    /// ignored.
+    pub fn assign(&self, owned: impl IntoIterator<Item = u32>) -> PartitionReassigned {
+        let partitions = self
+            .ownership
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .partitions;
+        let owned = owned
+            .into_iter()
+            .filter(|partition| *partition < partitions)
+            .collect();
+        Ownership::reassign(&self.ownership, partitions, owned)
+    }
+
+    /// Calls `listener` with every later assignment.
+    pub fn on_reassigned(&self, listener: impl Fn(&PartitionReassigned) + Send + Sync + 'static) {
+        self.ownership
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .listeners
+            .push(Arc::new(listener));
+    }
+
+    pub fn owned_partitions(&self) -> BTreeSet<u32> {
+        self.ownership
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .owned
+            .clone()
+    }
+}
+
+impl PartitionAssigner for ExternalPartitionAssigner {
+    fn owns(&self, saga_id: SagaId) -> bool {
+        self.ownership
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .owns(saga_id)
+    }
+}
+
+impl std::fmt::Debug for ExternalPartitionAssigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ownership = self
+            .ownership
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        f.debug_struct("ExternalPartitionAssigner")
+            .field("partitions", &ownership.partitions)
+            .field("owned", &ownership.owned)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_saga_has_one_owner_across_reassignment() {
+        let replicas: Vec<HashPartitionAssigner> = (0..2)
+            .map(|index| HashPartitionAssigner::new(index, 2))
+            .collect();
+        let owners = |replicas: &[HashPartitionAssigner], saga_id: u64| {
+            replicas
+                .iter()
+                .filter(|replica| replica.owns(SagaId::new(saga_id)))
+                .count()
+        };
+        assert!((1..=200).all(|saga_id| owners(&replicas, saga_id) == 1));
+        let first_share = (1..=200)
+            .filter(|saga_id| replicas[0].owns(SagaId::new(*saga_id)))
+            .count();
+        assert!((70..=130).contains(&first_share), "share {first_share}");
+
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let seen = changes.clone();
+        replicas[0].on_reassigned(move |change| seen.lock().unwrap().push(change.clone()));
+        // A third replica joins.
+        let mut replicas = replicas;
+        let change = replicas[0].reassign(0, 3);
+        replicas[1].reassign(1, 3);
+        replicas.push(HashPartitionAssigner::new(2, 3));
+        assert!((1..=200).all(|saga_id| owners(&replicas, saga_id) == 1));
+        assert_eq!(change.generation, 1);
+        assert_eq!(*changes.lock().unwrap(), vec![change.clone()]);
+        assert!((1..=200).all(|saga_id| {
+            let saga_id = SagaId::new(saga_id);
+            change.lost(saga_id)
+                == (!replicas[0].owns(saga_id)
+                    && change.owned_before.contains(&partition_of(saga_id, 2)))
+        }));
+
+        let external = ExternalPartitionAssigner::new(4);
+        assert!(!external.owns(SagaId::new(1)));
+        let assigned = external.assign([partition_of(SagaId::new(1), 4), 9]);
+        assert!(external.owns(SagaId::new(1)));
+        assert!(assigned.gained(SagaId::new(1)));
+        assert_eq!(assigned.owned.len(), 1);
+    }
+}
@@ -0,0 +1,276 @@
+//! Out-of-band storage for large payloads.
+//!
+//! Saga payloads travel inline in events, the journal and participant state.
+//! For order books or batch payloads of hundreds of KB that copying adds up,
+//! so a participant with a [`PayloadStore`] attached
+//! ([`crate::SagaParticipantSupport::with_payload_store`]) puts step outputs
+//! above its threshold into the store and forwards a [`PayloadRef`] in their
+//! place. The helpers resolve references before calling `execute_step`, so
+//! steps always see the original bytes.
+//!
+//! A reference is encoded into the ordinary payload bytes, which keeps the
+//! event format unchanged: a fixed magic prefix, the SHA-256 digest of the
+//! content and its length. Initiators offload large saga inputs with
+//! [`offload_payload`] before publishing `SagaStarted`.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use sha2::{Digest, Sha256};
+
+/// Payloads larger than this many bytes are offloaded unless the support
+/// sets its own threshold.
+pub const DEFAULT_PAYLOAD_OFFLOAD_THRESHOLD: usize = 64 * 1024;
+
+const PAYLOAD_REF_MAGIC: &[u8; 8] = b"SAGAREF\x01";
+const PAYLOAD_REF_ENCODED_LEN: usize = PAYLOAD_REF_MAGIC.len() + 32 + 8;
+
+/// Content address of a payload held in a [`PayloadStore`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PayloadRef {
+    digest: [u8; 32],
+    len: u64,
+}
+
+impl PayloadRef {
+    pub fn for_content(content: &[u8]) -> Self {
+        Self {
+            digest: Sha256::digest(content).into(),
+            len: content.len() as u64,
+        }
+    }
+
+    /// SHA-256 digest of the content.
+    pub fn digest(&self) -> &[u8; 32] {
+        &self.digest
+    }
+
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Encodes the reference as payload bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(PAYLOAD_REF_ENCODED_LEN);
+        bytes.extend_from_slice(PAYLOAD_REF_MAGIC);
+        bytes.extend_from_slice(&self.digest);
+        bytes.extend_from_slice(&self.len.to_le_bytes());
+        bytes
+    }
+
+    /// Decodes payload bytes produced by [`encode`](Self::encode); `None` for
+    /// any other payload.
+    pub fn decode(payload: &[u8]) -> Option<Self> {
+        if payload.len() != PAYLOAD_REF_ENCODED_LEN {
+            return None;
+        }
+        let rest = payload.strip_prefix(PAYLOAD_REF_MAGIC.as_slice())?;
+        let (digest, len) = rest.split_at(32);
+        Some(Self {
+            digest: digest.try_into().ok()?,
+            len: u64::from_le_bytes(len.try_into().ok()?),
+        })
+    }
+}
+
+impl std::fmt::Display for PayloadRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("sha256:")?;
+        for byte in self.digest {
+            write!(f, "{byte:02x}")?;
+        }
+        write!(f, " ({} bytes)", self.len)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PayloadError {
+    #[error("payload reference {0} received but no payload store is attached")]
+    NotAttached(PayloadRef),
+    #[error("payload {0} not found")]
+    Missing(PayloadRef),
+    #[error("payload {0} does not match its digest")]
+    DigestMismatch(PayloadRef),
+    #[error("payload storage error: {0}")]
+    Storage(Box<str>),
+}
+
+/// Content-addressed store for offloaded payloads.
+///
+/// Putting the same content twice yields the same reference, so stores may
+/// skip writes for keys they already hold.
+pub trait PayloadStore: Send + Sync + 'static {
+    fn put(&self, content: &[u8]) -> Result<PayloadRef, PayloadError>;
+
+    fn get(&self, payload: &PayloadRef) -> Result<Option<Vec<u8>>, PayloadError>;
+}
+
+impl<T> PayloadStore for Arc<T>
+where
+    T: PayloadStore + ?Sized,
+{
+    fn put(&self, content: &[u8]) -> Result<PayloadRef, PayloadError> {
+        (**self).put(content)
+    }
+
+    fn get(&self, payload: &PayloadRef) -> Result<Option<Vec<u8>>, PayloadError> {
+        (**self).get(payload)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct InMemoryPayloadStore {
+    payloads: Mutex<HashMap<PayloadRef, Arc<[u8]>>>,
+}
+
+impl InMemoryPayloadStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<PayloadRef, Arc<[u8]>>> {
+        self.payloads
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl PayloadStore for InMemoryPayloadStore {
+    fn put(&self, content: &[u8]) -> Result<PayloadRef, PayloadError> {
+        let payload = PayloadRef::for_content(content);
+        self.lock()
+            .entry(payload)
+            .or_insert_with(|| Arc::from(content));
+        Ok(payload)
+    }
+
+    fn get(&self, payload: &PayloadRef) -> Result<Option<Vec<u8>>, PayloadError> {
+        Ok(self.lock().get(payload).map(|content| content.to_vec()))
+    }
+}
+
+/// Puts `payload` into `store` when it is larger than `threshold` bytes and
+/// returns the encoded reference; smaller payloads are returned unchanged.
+pub fn offload_payload(
+    store: &dyn PayloadStore,
+    payload: Vec<u8>,
+    threshold: usize,
+) -> Result<Vec<u8>, PayloadError> {
+    if payload.len() <= threshold {
+        return Ok(payload);
+    }
+    Ok(store.put(&payload)?.encode())
+}
+
+/// Returns the content behind `payload` if it is an encoded [`PayloadRef`],
+/// or `payload` itself otherwise. Fetched content is checked against the
+/// reference's digest.
+pub fn resolve_payload<'a>(
+    store: &dyn PayloadStore,
+    payload: &'a [u8],
+) -> Result<Cow<'a, [u8]>, PayloadError> {
+    resolve_step_input(Some(store), payload)
+}
+
+pub(crate) fn resolve_step_input<'a>(
+    store: Option<&dyn PayloadStore>,
+    payload: &'a [u8],
+) -> Result<Cow<'a, [u8]>, PayloadError> {
+    let Some(reference) = PayloadRef::decode(payload) else {
+        return Ok(Cow::Borrowed(payload));
+    };
+    let store = store.ok_or(PayloadError::NotAttached(reference))?;
+    let content = store
+        .get(&reference)?
+        .ok_or(PayloadError::Missing(reference))?;
+    if PayloadRef::for_content(&content) != reference {
+        return Err(PayloadError::DigestMismatch(reference));
+    }
+    Ok(Cow::Owned(content))
+}
+
+/// Offloads a step output through the participant's store. A failed put
+/// keeps the output inline.
+pub(crate) fn offload_step_output(
+    store: Option<&dyn PayloadStore>,
+    threshold: usize,
+    output: Vec<u8>,
+    saga_id: crate::SagaId,
+    step_name: &str,
+) -> Vec<u8> {
+    let Some(store) = store else {
+        return output;
+    };
+    if output.len() <= threshold {
+        return output;
+    }
+    match store.put(&output) {
+        Ok(reference) => reference.encode(),
+        Err(err) => {
+            tracing::warn!(
+                target: "core::saga",
+                event = "saga_payload_offload_failed",
+                saga_id = saga_id.get(),
+                step_name,
+                bytes = output.len(),
+                error = %err
+            );
+            output
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offloaded_payload_round_trips_through_reference() {
+        let store = InMemoryPayloadStore::new();
+        let content = vec![5u8; 1024];
+
+        let small = offload_payload(&store, vec![1, 2, 3], 512).unwrap();
+        assert_eq!(small, vec![1, 2, 3]);
+        assert!(store.is_empty());
+
+        let encoded = offload_payload(&store, content.clone(), 512).unwrap();
+        let reference = PayloadRef::decode(&encoded).expect("offloaded payload is a reference");
+        assert_eq!(reference.len(), 1024);
+        assert_eq!(resolve_payload(&store, &encoded).unwrap().as_ref(), content);
+        assert_eq!(
+            resolve_payload(&store, &[1, 2, 3]).unwrap().as_ref(),
+            [1, 2, 3]
+        );
+
+        // Same content, same address.
+        offload_payload(&store, content, 512).unwrap();
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn resolving_unknown_or_unattached_reference_fails() {
+        let encoded = PayloadRef::for_content(b"order book").encode();
+
+        assert!(matches!(
+            resolve_step_input(None, &encoded),
+            Err(PayloadError::NotAttached(_))
+        ));
+        assert!(matches!(
+            resolve_payload(&InMemoryPayloadStore::new(), &encoded),
+            Err(PayloadError::Missing(_))
+        ));
+    }
+}
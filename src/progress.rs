@@ -0,0 +1,477 @@
+//! Saga progress as seen from the bus.
+//!
+//! Participants only know their own step. [`SagaProgressAggregator`] listens
+//! to the choreography events (and step acks) of every participant and keeps
+//! per-saga step status, so an initiator can ask how far a saga got and which
+//! steps it is waiting on. With a registered [`SagaWorkflowContract`] the
+//! aggregator also knows the steps that have not started yet, which is what
+//! makes `percent_complete` and the frontier meaningful.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use icanact_core::local::EventSubscription;
+
+use crate::workflow_contract::dependency_steps;
+use crate::{
+    AckStatus, SagaChoreographyBus, SagaChoreographyEvent, SagaId, SagaTerminalOutcome, SagaType,
+    SagaWorkflowContract, SagaWorkflowStepContract, StepName, WorkflowDependencySpec,
+    TERMINAL_RESOLVER_STEP,
+};
+
+const DEFAULT_TERMINAL_RETENTION_LIMIT: usize = 1024;
+
+/// Status of one step as observed on the bus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepProgressStatus {
+    /// Declared by the contract, not started yet.
+    Pending,
+    Started,
+    Completed,
+    Failed,
+    Compensating,
+    Compensated,
+    CompensationFailed,
+    Quarantined,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StepProgress {
+    pub step: StepName,
+    pub status: StepProgressStatus,
+    pub attempt: u32,
+    pub updated_at_millis: u64,
+}
+
+/// Snapshot returned by [`SagaProgressAggregator::progress`].
+#[derive(Clone, Debug)]
+pub struct SagaProgress {
+    pub saga_id: SagaId,
+    pub saga_type: SagaType,
+    /// Contract steps in declaration order, then steps seen on the bus that
+    /// the contract does not declare.
+    pub steps: Vec<StepProgress>,
+    /// Share of known steps that completed, from 0 to 100.
+    pub percent_complete: u8,
+    /// Steps running now plus pending steps whose dependencies completed.
+    pub frontier: Vec<StepName>,
+    pub terminal: Option<SagaTerminalOutcome>,
+}
+
+impl SagaProgress {
+    pub fn step(&self, step: &str) -> Option<&StepProgress> {
+        self.steps
+            .iter()
+            .find(|progress| progress.step.as_ref() == step)
+    }
+}
+
+struct TrackedSaga {
+    saga_type: SagaType,
+    steps: Vec<StepProgress>,
+    terminal: Option<SagaTerminalOutcome>,
+}
+
+#[derive(Default)]
+struct ProgressState {
+    workflows: HashMap<SagaType, &'static [SagaWorkflowStepContract]>,
+    sagas: BTreeMap<SagaId, TrackedSaga>,
+    terminal_order: VecDeque<SagaId>,
+}
+
+/// Per-saga step status aggregated from choreography events. Cloning shares
+/// the same state.
+#[derive(Clone)]
+pub struct SagaProgressAggregator {
+    state: Arc<Mutex<ProgressState>>,
+    terminal_retention_limit: usize,
+}
+
+impl Default for SagaProgressAggregator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SagaProgressAggregator {
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ProgressState::default())),
+            terminal_retention_limit: DEFAULT_TERMINAL_RETENTION_LIMIT,
+        }
+    }
+
+    /// Keeps the progress of at most `limit` terminal sagas; older ones are
+    /// dropped first.
+    pub fn with_terminal_retention(mut self, limit: usize) -> Self {
+        self.terminal_retention_limit = limit;
+        self
+    }
+
+    /// Declares the steps of `C`'s saga type so unstarted steps count toward
+    /// progress.
+    pub fn register_contract<C: SagaWorkflowContract>(&self) {
+        self.lock()
+            .workflows
+            .insert(C::saga_type().into(), C::steps());
+    }
+
+    /// Subscribes the aggregator to every event of `saga_type`.
+    pub fn subscribe(&self, bus: &SagaChoreographyBus, saga_type: &str) -> EventSubscription {
+        let aggregator = self.clone();
+        bus.subscribe_saga_type_fn(saga_type, move |event| {
+            aggregator.observe(event);
+            true
+        })
+    }
+
+    pub fn observe(&self, event: &SagaChoreographyEvent) {
+        let context = event.context();
+        let saga_id = context.saga_id;
+        let mut guard = self.lock();
+        let state = &mut *guard;
+        if let SagaChoreographyEvent::SagaStarted { .. } = event {
+            let steps = state
+                .workflows
+                .get(context.saga_type.as_ref())
+                .map(|steps| {
+                    steps
+                        .iter()
+                        .map(|step| StepProgress {
+                            step: step.step_name.into(),
+                            status: StepProgressStatus::Pending,
+                            attempt: 0,
+                            updated_at_millis: context.event_timestamp_millis,
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            state.terminal_order.retain(|id| *id != saga_id);
+            state.sagas.insert(
+                saga_id,
+                TrackedSaga {
+                    saga_type: context.saga_type.clone(),
+                    steps,
+                    terminal: None,
+                },
+            );
+            return;
+        }
+
+        if let Some(outcome) = event.terminal_outcome() {
+            let Some(saga) = state.sagas.get_mut(&saga_id) else {
+                return;
+            };
+            if saga.terminal.is_some() {
+                return;
+            }
+            if let SagaTerminalOutcome::Quarantined { step, .. } = &outcome {
+                saga.update(step, StepProgressStatus::Quarantined, context);
+            }
+            saga.terminal = Some(outcome);
+            state.terminal_order.push_back(saga_id);
+            self.evict_terminal(state);
+            return;
+        }
+
+        let status = match event {
+            SagaChoreographyEvent::StepStarted { .. } => StepProgressStatus::Started,
+            SagaChoreographyEvent::StepCompleted { .. } => StepProgressStatus::Completed,
+            SagaChoreographyEvent::StepFailed { .. } => StepProgressStatus::Failed,
+            SagaChoreographyEvent::CompensationStarted { .. } => StepProgressStatus::Compensating,
+            SagaChoreographyEvent::CompensationCompleted { .. } => StepProgressStatus::Compensated,
+            SagaChoreographyEvent::CompensationFailed { .. } => {
+                StepProgressStatus::CompensationFailed
+            }
+            SagaChoreographyEvent::StepAck { status, .. } => match status {
+                AckStatus::Accepted | AckStatus::AlreadyProcessing => StepProgressStatus::Started,
+                AckStatus::Completed => StepProgressStatus::Completed,
+                AckStatus::Failed => StepProgressStatus::Failed,
+                AckStatus::NotApplicable => return,
+            },
+            _ => return,
+        };
+        if context.step_name.as_ref() == TERMINAL_RESOLVER_STEP {
+            return;
+        }
+        let Some(saga) = state.sagas.get_mut(&saga_id) else {
+            return;
+        };
+        if saga.terminal.is_some() {
+            return;
+        }
+        if let SagaChoreographyEvent::StepAck { .. } = event {
+            // Acks race the events they acknowledge; only let them move a
+            // step forward from pending.
+            let current = saga
+                .steps
+                .iter()
+                .find(|progress| progress.step == context.step_name)
+                .map(|progress| progress.status);
+            if !matches!(current, None | Some(StepProgressStatus::Pending)) {
+                return;
+            }
+        }
+        saga.update(&context.step_name, status, context);
+    }
+
+    /// Progress of `saga_id`, or `None` if its start was never observed or it
+    /// was evicted.
+    pub fn progress(&self, saga_id: SagaId) -> Option<SagaProgress> {
+        let state = self.lock();
+        let saga = state.sagas.get(&saga_id)?;
+        let workflow = state.workflows.get(saga.saga_type.as_ref()).copied();
+        let completed = saga
+            .steps
+            .iter()
+            .filter(|progress| progress.status == StepProgressStatus::Completed)
+            .count();
+        let percent_complete = if saga.steps.is_empty() {
+            0
+        } else {
+            (completed * 100 / saga.steps.len()) as u8
+        };
+        Some(SagaProgress {
+            saga_id,
+            saga_type: saga.saga_type.clone(),
+            steps: saga.steps.clone(),
+            percent_complete,
+            frontier: saga.frontier(workflow),
+            terminal: saga.terminal.clone(),
+        })
+    }
+
+    /// Drops the progress of `saga_id`.
+    pub fn forget(&self, saga_id: SagaId) {
+        let mut state = self.lock();
+        state.sagas.remove(&saga_id);
+        state.terminal_order.retain(|id| *id != saga_id);
+    }
+
+    pub fn tracked(&self) -> usize {
+        self.lock().sagas.len()
+    }
+
+    fn evict_terminal(&self, state: &mut ProgressState) {
+        while state.terminal_order.len() > self.terminal_retention_limit {
+            if let Some(saga_id) = state.terminal_order.pop_front() {
+                state.sagas.remove(&saga_id);
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ProgressState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl std::fmt::Debug for SagaProgressAggregator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SagaProgressAggregator")
+            .field("tracked", &self.tracked())
+            .field("terminal_retention_limit", &self.terminal_retention_limit)
+            .finish()
+    }
+}
+
+impl TrackedSaga {
+    fn update(&mut self, step: &str, status: StepProgressStatus, context: &crate::SagaContext) {
+        let progress = match self.steps.iter_mut().position(|p| p.step.as_ref() == step) {
+            Some(index) => &mut self.steps[index],
+            None => {
+                self.steps.push(StepProgress {
+                    step: step.into(),
+                    status: StepProgressStatus::Pending,
+                    attempt: 0,
+                    updated_at_millis: 0,
+                });
+                self.steps.last_mut().expect("step was just pushed")
+            }
+        };
+        progress.status = status;
+        progress.attempt = progress.attempt.max(context.attempt);
+        progress.updated_at_millis = context.event_timestamp_millis;
+    }
+
+    fn status(&self, step: &str) -> StepProgressStatus {
+        self.steps
+            .iter()
+            .find(|progress| progress.step.as_ref() == step)
+            .map_or(StepProgressStatus::Pending, |progress| progress.status)
+    }
+
+    fn frontier(&self, workflow: Option<&'static [SagaWorkflowStepContract]>) -> Vec<StepName> {
+        if self.terminal.is_some() {
+            return Vec::new();
+        }
+        let mut frontier: Vec<StepName> = self
+            .steps
+            .iter()
+            .filter(|progress| {
+                matches!(
+                    progress.status,
+                    StepProgressStatus::Started | StepProgressStatus::Compensating
+                )
+            })
+            .map(|progress| progress.step.clone())
+            .collect();
+        for step in workflow.unwrap_or_default() {
+            if self.status(step.step_name) != StepProgressStatus::Pending {
+                continue;
+            }
+            let completed =
+                |dependency: &&str| self.status(dependency) == StepProgressStatus::Completed;
+            let dependencies = dependency_steps(step.depends_on);
+            let ready = match step.depends_on {
+                WorkflowDependencySpec::OnSagaStart => true,
+                WorkflowDependencySpec::AnyOf(_) => dependencies.iter().any(completed),
+                WorkflowDependencySpec::After(_) | WorkflowDependencySpec::AllOf(_) => {
+                    dependencies.iter().all(completed)
+                }
+            };
+            if ready {
+                frontier.push(step.step_name.into());
+            }
+        }
+        frontier
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        saga_started, step_completed, DeterministicContextBuilder, SagaContext, TerminalPolicy,
+    };
+
+    struct OpenPosition;
+
+    impl SagaWorkflowContract for OpenPosition {
+        fn saga_type() -> &'static str {
+            "order_lifecycle"
+        }
+
+        fn first_step() -> &'static str {
+            "risk_check"
+        }
+
+        fn steps() -> &'static [SagaWorkflowStepContract] {
+            &[
+                SagaWorkflowStepContract {
+                    step_name: "risk_check",
+                    participant_id: "risk",
+                    depends_on: WorkflowDependencySpec::OnSagaStart,
+                },
+                SagaWorkflowStepContract {
+                    step_name: "positions_check",
+                    participant_id: "positions",
+                    depends_on: WorkflowDependencySpec::OnSagaStart,
+                },
+                SagaWorkflowStepContract {
+                    step_name: "create_order",
+                    participant_id: "order-manager",
+                    depends_on: WorkflowDependencySpec::AllOf(&["risk_check", "positions_check"]),
+                },
+            ]
+        }
+
+        fn terminal_policy() -> TerminalPolicy {
+            TerminalPolicy::order_lifecycle_default()
+        }
+    }
+
+    fn at_step(context: &SagaContext, step: &str) -> SagaContext {
+        let mut context = context.clone();
+        context.step_name = step.into();
+        context
+    }
+
+    #[test]
+    fn progress_tracks_frontier_and_percent_complete() {
+        let aggregator = SagaProgressAggregator::new();
+        aggregator.register_contract::<OpenPosition>();
+        let context = DeterministicContextBuilder::default().build();
+        let saga_id = context.saga_id;
+        aggregator.observe(&saga_started(context.clone(), Vec::new()));
+
+        let progress = aggregator.progress(saga_id).unwrap();
+        assert_eq!(progress.percent_complete, 0);
+        assert_eq!(
+            progress.frontier,
+            vec!["risk_check".into(), "positions_check".into()] as Vec<StepName>
+        );
+
+        aggregator.observe(&SagaChoreographyEvent::StepStarted {
+            context: at_step(&context, "positions_check"),
+        });
+        aggregator.observe(&step_completed(
+            at_step(&context, "risk_check"),
+            Vec::new(),
+            Vec::new(),
+            true,
+        ));
+        let progress = aggregator.progress(saga_id).unwrap();
+        assert_eq!(progress.percent_complete, 33);
+        assert_eq!(
+            progress.step("positions_check").unwrap().status,
+            StepProgressStatus::Started
+        );
+        assert_eq!(
+            progress.frontier,
+            vec!["positions_check".into()] as Vec<StepName>
+        );
+
+        aggregator.observe(&step_completed(
+            at_step(&context, "positions_check"),
+            Vec::new(),
+            Vec::new(),
+            true,
+        ));
+        aggregator.observe(&SagaChoreographyEvent::StepAck {
+            context: at_step(&context, "positions_check"),
+            participant_id: [0; 32],
+            status: AckStatus::Accepted,
+        });
+        let progress = aggregator.progress(saga_id).unwrap();
+        assert_eq!(
+            progress.step("positions_check").unwrap().status,
+            StepProgressStatus::Completed
+        );
+        assert_eq!(
+            progress.frontier,
+            vec!["create_order".into()] as Vec<StepName>
+        );
+
+        aggregator.observe(&SagaChoreographyEvent::SagaCompleted {
+            context: at_step(&context, TERMINAL_RESOLVER_STEP),
+        });
+        let progress = aggregator.progress(saga_id).unwrap();
+        assert!(matches!(
+            progress.terminal,
+            Some(SagaTerminalOutcome::Completed { .. })
+        ));
+        assert!(progress.frontier.is_empty());
+    }
+
+    #[test]
+    fn terminal_sagas_are_evicted_past_the_retention_limit() {
+        let aggregator = SagaProgressAggregator::new().with_terminal_retention(1);
+        for saga_id in 1..=3 {
+            let context = DeterministicContextBuilder::default()
+                .with_saga_id(saga_id)
+                .build();
+            aggregator.observe(&saga_started(context.clone(), Vec::new()));
+            aggregator.observe(&SagaChoreographyEvent::StepStarted {
+                context: at_step(&context, "risk_check"),
+            });
+            aggregator.observe(&SagaChoreographyEvent::SagaCompleted { context });
+        }
+        assert_eq!(aggregator.tracked(), 1);
+        let last = aggregator.progress(SagaId::new(3)).unwrap();
+        assert_eq!(last.steps.len(), 1);
+        assert_eq!(last.percent_complete, 0);
+        assert!(aggregator.progress(SagaId::new(1)).is_none());
+    }
+}
@@ -0,0 +1,207 @@
+//! Read models built from the saga event stream.
+//!
+//! A [`SagaProjection`] attached with
+//! [`crate::SagaParticipantSupport::with_projection`] sees every distinct
+//! event the participant's handlers accept, right after the dedupe check and
+//! authorization, so it can answer "what state is order X in" without asking
+//! each participant.
+//! The journal inbox holds the same stream, which [`rebuild_projection`]
+//! replays after a restart:
+//!
+//! ```ignore
+//! let orders = Arc::new(Mutex::new(OrderStates::default()));
+//! rebuild_projection(&journal, &mut *orders.lock().unwrap())?;
+//! let support = SagaParticipantSupport::new(journal, dedupe).with_projection(orders.clone());
+//! ```
+//!
+//! An event the participant rejects is never applied. The inbox records it
+//! anyway, so the rebuild skips the inbox entries whose dedupe key the
+//! journal recorded as [`ParticipantEvent::EventRejected`]. Inbox history
+//! goes away when a settled saga is pruned, so a rebuild restores unfinished
+//! sagas only; read models that must answer for settled sagas keep their own
+//! copy of those rows.
+//!
+//! Projections see events in arrival order, before the reorder buffer
+//! ([`crate::SagaReorderWindow`]) holds back those that beat their saga's
+//! `SagaStarted`; a rebuild replays the inbox in the same order. A projection
+//! must therefore tolerate, say, a `StepCompleted` before the `SagaStarted`
+//! of its saga.
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use crate::{JournalError, ParticipantEvent, ParticipantJournal, SagaChoreographyEvent};
+
+/// Projection shared between a participant and the code that queries it.
+pub type SharedSagaProjection = Arc<Mutex<dyn SagaProjection + Send>>;
+
+/// A user-defined read model maintained from saga events.
+pub trait SagaProjection {
+    /// Folds `event` into the read model. Called at most once per distinct
+    /// event and saga, in the order the participant received them.
+    fn apply(&mut self, event: &SagaChoreographyEvent);
+
+    /// Clears the read model before [`rebuild_projection`].
+    fn reset(&mut self);
+}
+
+/// Resets `projection` and re-applies every event in the journal inbox
+/// history, skipping redeliveries and rejected events. Sagas are replayed one
+/// after another, each in inbox order.
+///
+/// Returns the number of events applied. Journals without an inbox leave the
+/// projection empty.
+pub fn rebuild_projection<J, P>(journal: &J, projection: &mut P) -> Result<usize, JournalError>
+where
+    J: ParticipantJournal + ?Sized,
+    P: SagaProjection + ?Sized,
+{
+    projection.reset();
+    let mut applied = 0;
+    for saga_id in journal.list_sagas()? {
+        // Rejected keys count as seen.
+        let mut seen: HashSet<Box<str>> = journal
+            .read(saga_id)?
+            .into_iter()
+            .filter_map(|entry| match entry.event {
+                ParticipantEvent::EventRejected { dedupe_key, .. } => dedupe_key,
+                _ => None,
+            })
+            .collect();
+        for entry in journal.incoming_history(saga_id)? {
+            if seen.insert(entry.dedupe_key) {
+                projection.apply(&entry.event);
+                applied += 1;
+            }
+        }
+    }
+    tracing::info!(
+        target: "core::saga",
+        event = "saga_projection_rebuilt",
+        applied
+    );
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        handle_saga_event_with_emit, saga_started, AuthError, CompensationError,
+        DeterministicContextBuilder, HasSagaParticipantSupport, InMemoryDedupe, InMemoryJournal,
+        SagaContext, SagaId, SagaParticipant, SagaParticipantSupport, StepError, StepOutput,
+    };
+
+    #[derive(Default)]
+    struct OrderStates {
+        by_saga: HashMap<SagaId, &'static str>,
+    }
+
+    impl SagaProjection for OrderStates {
+        fn apply(&mut self, event: &SagaChoreographyEvent) {
+            self.by_saga
+                .insert(event.context().saga_id, event.event_type());
+        }
+
+        fn reset(&mut self) {
+            self.by_saga.clear();
+        }
+    }
+
+    struct Listener {
+        saga: SagaParticipantSupport<Arc<InMemoryJournal>, InMemoryDedupe>,
+    }
+
+    impl HasSagaParticipantSupport for Listener {
+        type Journal = Arc<InMemoryJournal>;
+        type Dedupe = InMemoryDedupe;
+
+        fn saga_support(&self) -> &SagaParticipantSupport<Self::Journal, Self::Dedupe> {
+            &self.saga
+        }
+
+        fn saga_support_mut(&mut self) -> &mut SagaParticipantSupport<Self::Journal, Self::Dedupe> {
+            &mut self.saga
+        }
+    }
+
+    impl SagaParticipant for Listener {
+        type Error = String;
+
+        fn step_name(&self) -> &str {
+            "audit"
+        }
+
+        fn saga_types(&self) -> &[&'static str] {
+            &["order_lifecycle"]
+        }
+
+        fn execute_step(
+            &mut self,
+            _context: &SagaContext,
+            _input: &[u8],
+        ) -> Result<StepOutput, StepError> {
+            Ok(StepOutput::Completed {
+                output: Vec::new(),
+                compensation_data: Vec::new(),
+            })
+        }
+
+        fn compensate_step(
+            &mut self,
+            _context: &SagaContext,
+            _compensation_data: &[u8],
+        ) -> Result<(), CompensationError> {
+            Ok(())
+        }
+
+        fn authorize_event(
+            &self,
+            _context: &SagaContext,
+            event_type: &str,
+        ) -> Result<(), AuthError> {
+            match event_type {
+                "saga_failed" => Err(AuthError::Denied {
+                    reason: "failures come from the resolver".into(),
+                }),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    #[test]
+    fn projection_rebuilt_from_journal_matches_live_state() {
+        let journal = Arc::new(InMemoryJournal::new());
+        let live = Arc::new(Mutex::new(OrderStates::default()));
+        let mut listener = Listener {
+            saga: SagaParticipantSupport::new(Arc::clone(&journal), InMemoryDedupe::new())
+                .with_projection(live.clone()),
+        };
+        let context = DeterministicContextBuilder::default().build();
+        let completed = SagaChoreographyEvent::StepCompleted {
+            context: context.next_step("risk_check".into()),
+            output: Vec::new(),
+            saga_input: Vec::new(),
+            compensation_available: false,
+            completion_ratio: None,
+        };
+        for event in [
+            saga_started(context.clone(), Vec::new()),
+            completed.clone(),
+            completed,
+            SagaChoreographyEvent::saga_failed_default(context.clone(), "spoofed".into()),
+        ] {
+            handle_saga_event_with_emit(&mut listener, event, |_| {});
+        }
+        assert_eq!(
+            live.lock().unwrap().by_saga.get(&context.saga_id),
+            Some(&"step_completed")
+        );
+
+        let mut rebuilt = OrderStates::default();
+        assert_eq!(rebuild_projection(&*journal, &mut rebuilt).unwrap(), 2);
+        assert_eq!(rebuilt.by_saga, live.lock().unwrap().by_saga);
+    }
+}
@@ -0,0 +1,445 @@
+//! Process-wide view over quarantined sagas.
+//!
+//! A participant that cannot compensate moves its step to `Quarantined`, and
+//! the saga is pruned from the participant once the terminal event arrives.
+//! [`QuarantineManager`] keeps one record per quarantined saga and step
+//! across all participants it is attached to, so an operator can list and
+//! inspect them, retry the failed compensation, or resolve them manually.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, RwLock};
+
+use icanact_core::local::EventSubscription;
+
+use crate::sensitive::Redacted;
+use crate::{
+    CompensationError, JournalError, SagaChoreographyBus, SagaChoreographyEvent, SagaContext,
+    SagaId, StepName,
+};
+
+/// A saga held in quarantine.
+#[derive(Clone)]
+pub struct QuarantinedSaga {
+    pub context: SagaContext,
+    /// Step whose compensation failed.
+    pub step: StepName,
+    pub participant_id: Box<str>,
+    pub reason: Box<str>,
+    pub quarantined_at_millis: u64,
+    /// Compensation data of the failed step, when the quarantining
+    /// participant reported it. Needed to retry compensation.
+    pub compensation_data: Option<Vec<u8>>,
+    /// Number of operator-triggered compensation retries that failed.
+    pub failed_retries: u32,
+}
+
+impl QuarantinedSaga {
+    /// Short human-readable description of the retained compensation data.
+    pub fn compensation_summary(&self) -> String {
+        match &self.compensation_data {
+            Some(data) => format!("{} bytes", data.len()),
+            None => "unavailable".to_string(),
+        }
+    }
+}
+
+impl std::fmt::Debug for QuarantinedSaga {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QuarantinedSaga")
+            .field("context", &self.context)
+            .field("step", &self.step)
+            .field("participant_id", &self.participant_id)
+            .field("reason", &self.reason)
+            .field("quarantined_at_millis", &self.quarantined_at_millis)
+            .field(
+                "compensation_data",
+                &self.compensation_data.as_deref().map(Redacted),
+            )
+            .field("failed_retries", &self.failed_retries)
+            .finish()
+    }
+}
+
+/// How an operator closed a quarantine.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QuarantineResolutionKind {
+    /// Marked resolved after manual intervention.
+    Resolved,
+    /// Compensation was retried and succeeded.
+    CompensationRetried,
+}
+
+/// Emitted to resolution listeners when a quarantine is closed.
+#[derive(Clone, Debug)]
+pub struct QuarantineResolution {
+    pub saga: QuarantinedSaga,
+    pub kind: QuarantineResolutionKind,
+    pub operator: Box<str>,
+    pub note: Box<str>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum QuarantineError {
+    #[error("saga {0} is not quarantined")]
+    NotQuarantined(u64),
+    #[error("saga {0} has no compensation data to retry with")]
+    MissingCompensationData(u64),
+    #[error("compensation retry failed: {0:?}")]
+    RetryFailed(CompensationError),
+    #[error("Journal error: {0}")]
+    Journal(#[from] JournalError),
+}
+
+type ResolutionListener = Box<dyn Fn(&QuarantineResolution) + Send + Sync>;
+
+#[derive(Default)]
+struct QuarantineManagerInner {
+    sagas: RwLock<BTreeMap<(SagaId, StepName), QuarantinedSaga>>,
+    listeners: Mutex<Vec<ResolutionListener>>,
+}
+
+/// Aggregates quarantined sagas reported by participants or observed on the
+/// bus. Cloning shares the same underlying registry.
+#[derive(Clone, Default)]
+pub struct QuarantineManager {
+    inner: Arc<QuarantineManagerInner>,
+}
+
+impl QuarantineManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a quarantined step of a saga. A record already held for the
+    /// same saga and step keeps its compensation data when the new report
+    /// does not carry any; other steps of the saga keep their own records.
+    pub fn record(&self, saga: QuarantinedSaga) {
+        let Ok(mut sagas) = self.inner.sagas.write() else {
+            return;
+        };
+        let key = (saga.context.saga_id, saga.step.clone());
+        match sagas.get_mut(&key) {
+            Some(existing) => {
+                let compensation_data = saga
+                    .compensation_data
+                    .or_else(|| existing.compensation_data.take());
+                *existing = QuarantinedSaga {
+                    compensation_data,
+                    failed_retries: existing.failed_retries,
+                    ..saga
+                };
+            }
+            None => {
+                sagas.insert(key, saga);
+            }
+        }
+    }
+
+    /// Records `SagaQuarantined` events; every other event is ignored.
+    pub fn observe(&self, event: &SagaChoreographyEvent) {
+        if let SagaChoreographyEvent::SagaQuarantined {
+            context,
+            reason,
+            step,
+            participant_id,
+        } = event
+        {
+            self.record(QuarantinedSaga {
+                context: context.clone(),
+                step: step.clone(),
+                participant_id: participant_id.clone(),
+                reason: reason.clone(),
+                quarantined_at_millis: context.event_timestamp_millis,
+                compensation_data: None,
+                failed_retries: 0,
+            });
+        }
+    }
+
+    /// Subscribes the manager to `SagaQuarantined` events of `saga_type`.
+    pub fn subscribe(&self, bus: &SagaChoreographyBus, saga_type: &str) -> EventSubscription {
+        let manager = self.clone();
+        bus.subscribe_saga_type_fn(saga_type, move |event| {
+            manager.observe(event);
+            true
+        })
+    }
+
+    /// Registers a callback invoked whenever a quarantine is closed.
+    pub fn on_resolution<F>(&self, listener: F)
+    where
+        F: Fn(&QuarantineResolution) + Send + Sync + 'static,
+    {
+        if let Ok(mut listeners) = self.inner.listeners.lock() {
+            listeners.push(Box::new(listener));
+        }
+    }
+
+    pub fn list(&self) -> Vec<QuarantinedSaga> {
+        self.inner
+            .sagas
+            .read()
+            .map(|sagas| sagas.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// The record of `step` of `saga_id`, if that step is quarantined.
+    pub fn inspect(&self, saga_id: SagaId, step: &str) -> Option<QuarantinedSaga> {
+        self.inner
+            .sagas
+            .read()
+            .ok()
+            .and_then(|sagas| sagas.get(&(saga_id, StepName::from(step))).cloned())
+    }
+
+    /// The records of every quarantined step of `saga_id`, by step name.
+    pub fn inspect_saga(&self, saga_id: SagaId) -> Vec<QuarantinedSaga> {
+        self.inner
+            .sagas
+            .read()
+            .map(|sagas| {
+                sagas
+                    .iter()
+                    .filter(|((id, _), _)| *id == saga_id)
+                    .map(|(_, saga)| saga.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner
+            .sagas
+            .read()
+            .map(|sagas| sagas.len())
+            .unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Closes the quarantine of `step` of `saga_id` after manual
+    /// intervention.
+    pub fn resolve(
+        &self,
+        saga_id: SagaId,
+        step: &str,
+        operator: &str,
+        note: &str,
+    ) -> Result<QuarantineResolution, QuarantineError> {
+        let saga = self
+            .take(saga_id, step)
+            .ok_or(QuarantineError::NotQuarantined(saga_id.get()))?;
+        Ok(self.emit_resolution(saga, QuarantineResolutionKind::Resolved, operator, note))
+    }
+
+    /// Re-runs the failed compensation through `compensate` with the retained
+    /// compensation data. On success the quarantine is closed; on failure the
+    /// record stays and its reason is updated.
+    pub fn retry_compensation<F>(
+        &self,
+        saga_id: SagaId,
+        step: &str,
+        operator: &str,
+        compensate: F,
+    ) -> Result<QuarantineResolution, QuarantineError>
+    where
+        F: FnOnce(&SagaContext, &[u8]) -> Result<(), CompensationError>,
+    {
+        let saga = self
+            .inspect(saga_id, step)
+            .ok_or(QuarantineError::NotQuarantined(saga_id.get()))?;
+        let Some(data) = saga.compensation_data.as_deref() else {
+            return Err(QuarantineError::MissingCompensationData(saga_id.get()));
+        };
+        match compensate(&saga.context, data) {
+            Ok(()) => {
+                let saga = self
+                    .take(saga_id, step)
+                    .ok_or(QuarantineError::NotQuarantined(saga_id.get()))?;
+                Ok(self.emit_resolution(
+                    saga,
+                    QuarantineResolutionKind::CompensationRetried,
+                    operator,
+                    "compensation retry succeeded",
+                ))
+            }
+            Err(err) => {
+                if let Ok(mut sagas) = self.inner.sagas.write() {
+                    if let Some(existing) = sagas.get_mut(&(saga_id, StepName::from(step))) {
+                        existing.failed_retries = existing.failed_retries.saturating_add(1);
+                        existing.reason = err.reason().into();
+                    }
+                }
+                Err(QuarantineError::RetryFailed(err))
+            }
+        }
+    }
+
+    /// Closes the record of a step whose participant compensated it on a
+    /// retry of its own.
+    pub(crate) fn close_retried(&self, saga_id: SagaId, step: &str, participant_id: &str) {
+        if let Some(saga) = self.take(saga_id, step) {
+            self.emit_resolution(
+                saga,
+                QuarantineResolutionKind::CompensationRetried,
+                participant_id,
+                "compensation retry succeeded",
+            );
+        }
+    }
+
+    fn take(&self, saga_id: SagaId, step: &str) -> Option<QuarantinedSaga> {
+        self.inner
+            .sagas
+            .write()
+            .ok()
+            .and_then(|mut sagas| sagas.remove(&(saga_id, StepName::from(step))))
+    }
+
+    fn emit_resolution(
+        &self,
+        saga: QuarantinedSaga,
+        kind: QuarantineResolutionKind,
+        operator: &str,
+        note: &str,
+    ) -> QuarantineResolution {
+        let resolution = QuarantineResolution {
+            saga,
+            kind,
+            operator: operator.into(),
+            note: note.into(),
+        };
+        tracing::info!(
+            target: "core::saga",
+            event = "saga_quarantine_resolved",
+            saga_id = resolution.saga.context.saga_id.get(),
+            step = %resolution.saga.step,
+            kind = ?resolution.kind,
+            operator
+        );
+        if let Ok(listeners) = self.inner.listeners.lock() {
+            for listener in listeners.iter() {
+                listener(&resolution);
+            }
+        }
+        resolution
+    }
+}
+
+impl std::fmt::Debug for QuarantineManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QuarantineManager")
+            .field("quarantined", &self.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DeterministicContextBuilder;
+
+    fn quarantined(saga_id: u64, data: Option<Vec<u8>>) -> QuarantinedSaga {
+        quarantined_step(saga_id, "reserve_inventory", data)
+    }
+
+    fn quarantined_step(saga_id: u64, step: &str, data: Option<Vec<u8>>) -> QuarantinedSaga {
+        QuarantinedSaga {
+            context: DeterministicContextBuilder::default()
+                .with_saga_id(saga_id)
+                .build(),
+            step: step.into(),
+            participant_id: "inventory".into(),
+            reason: "release timed out".into(),
+            quarantined_at_millis: 10,
+            compensation_data: data,
+            failed_retries: 0,
+        }
+    }
+
+    #[test]
+    fn observed_quarantine_keeps_reported_compensation_data() {
+        let manager = QuarantineManager::new();
+        manager.record(quarantined(1, Some(vec![1, 2, 3])));
+        let event = SagaChoreographyEvent::SagaQuarantined {
+            context: DeterministicContextBuilder::default()
+                .with_saga_id(1)
+                .build(),
+            reason: "ambiguous release".into(),
+            step: "reserve_inventory".into(),
+            participant_id: "inventory".into(),
+        };
+        manager.observe(&event);
+
+        let saga = manager
+            .inspect(SagaId::new(1), "reserve_inventory")
+            .expect("saga should be held");
+        assert_eq!(saga.reason.as_ref(), "ambiguous release");
+        assert_eq!(saga.compensation_summary(), "3 bytes");
+    }
+
+    #[test]
+    fn retry_compensation_closes_quarantine_and_notifies_listeners() {
+        let manager = QuarantineManager::new();
+        manager.record(quarantined(2, Some(vec![9])));
+        let resolutions = Arc::new(Mutex::new(Vec::new()));
+        let seen = resolutions.clone();
+        manager.on_resolution(move |resolution| {
+            seen.lock().unwrap().push(resolution.kind.clone());
+        });
+
+        let err = manager
+            .retry_compensation(SagaId::new(2), "reserve_inventory", "ops", |_, _| {
+                Err(CompensationError::safe_to_retry("still down"))
+            })
+            .expect_err("first retry should fail");
+        assert!(matches!(err, QuarantineError::RetryFailed(_)));
+        assert_eq!(
+            manager
+                .inspect(SagaId::new(2), "reserve_inventory")
+                .unwrap()
+                .failed_retries,
+            1
+        );
+
+        let resolution = manager
+            .retry_compensation(SagaId::new(2), "reserve_inventory", "ops", |_, data| {
+                assert_eq!(data, [9]);
+                Ok(())
+            })
+            .expect("second retry should succeed");
+        assert_eq!(
+            resolution.kind,
+            QuarantineResolutionKind::CompensationRetried
+        );
+        assert!(manager.is_empty());
+        assert_eq!(
+            *resolutions.lock().unwrap(),
+            vec![QuarantineResolutionKind::CompensationRetried]
+        );
+        assert!(matches!(
+            manager.resolve(SagaId::new(2), "reserve_inventory", "ops", "again"),
+            Err(QuarantineError::NotQuarantined(2))
+        ));
+    }
+
+    #[test]
+    fn steps_of_one_saga_are_quarantined_separately() {
+        let manager = QuarantineManager::new();
+        manager.record(quarantined_step(3, "reserve_inventory", Some(vec![1])));
+        manager.record(quarantined_step(3, "charge_card", Some(vec![2, 2])));
+        assert_eq!(manager.len(), 2);
+        assert_eq!(manager.inspect_saga(SagaId::new(3)).len(), 2);
+
+        manager
+            .resolve(SagaId::new(3), "charge_card", "ops", "refunded by hand")
+            .expect("charge_card should be quarantined");
+        let remaining = manager
+            .inspect(SagaId::new(3), "reserve_inventory")
+            .expect("the other step keeps its record");
+        assert_eq!(remaining.compensation_data, Some(vec![1]));
+        assert!(manager.inspect(SagaId::new(3), "charge_card").is_none());
+    }
+}
@@ -0,0 +1,198 @@
+//! Outbound rate limiting of step execution.
+//!
+//! A participant that calls a rate-limited venue attaches a
+//! [`RateLimitGate`] with
+//! [`SagaParticipantSupport::with_rate_limit_gate`](crate::SagaParticipantSupport::with_rate_limit_gate).
+//! The step helpers take a token before each step starts. A step denied a
+//! token is parked in `Triggered`: nothing is journaled for it, and its
+//! triggering event stays pending in the journal inbox. Once a token frees
+//! up, at [`rate_limited_until`](crate::SagaParticipantSupport::rate_limited_until),
+//! `replay_saga_inbox_with_emit` (or its async twin) redelivers the trigger
+//! and the step asks again.
+//!
+//! [`TokenBucketGate`] is the stock gate: one token bucket per step, or per
+//! step and instrument with [`TokenBucketGate::with_rate_key`].
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::SagaContext;
+
+type RateKeyFn = dyn Fn(&SagaContext, &[u8]) -> Option<Box<str>> + Send + Sync;
+
+/// A step denied its token.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("rate limit {key} exhausted until {retry_at_millis}")]
+pub struct RateLimited {
+    /// Bucket that ran dry.
+    pub key: Box<str>,
+    /// Earliest time a token may be available again.
+    pub retry_at_millis: u64,
+}
+
+/// Decides whether a step may start now.
+pub trait RateLimitGate: Send + Sync {
+    /// Takes a token for running `step_name` of `context`'s saga on `input`,
+    /// the step input as received.
+    fn try_acquire(
+        &self,
+        step_name: &str,
+        context: &SagaContext,
+        input: &[u8],
+        now_millis: u64,
+    ) -> Result<(), RateLimited>;
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Bucket {
+    tokens: u32,
+    refilled_at_millis: u64,
+}
+
+/// Token buckets holding up to `capacity` tokens, each refilled one token
+/// per `refill_every`. Clones share the buckets.
+#[derive(Clone)]
+pub struct TokenBucketGate {
+    capacity: u32,
+    refill_every_millis: u64,
+    rate_key: Option<Arc<RateKeyFn>>,
+    buckets: Arc<Mutex<HashMap<Box<str>, Bucket>>>,
+}
+
+impl TokenBucketGate {
+    /// One bucket per step name.
+    pub fn new(capacity: u32, refill_every: Duration) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            refill_every_millis: (refill_every.as_millis() as u64).max(1),
+            rate_key: None,
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Splits each step's bucket by the key `rate_key` derives, e.g. the
+    /// instrument an order trades. Steps it returns `None` for share the
+    /// step's own bucket.
+    pub fn with_rate_key<F>(mut self, rate_key: F) -> Self
+    where
+        F: Fn(&SagaContext, &[u8]) -> Option<Box<str>> + Send + Sync + 'static,
+    {
+        self.rate_key = Some(Arc::new(rate_key));
+        self
+    }
+
+    /// Tokens left in `key`'s bucket at `now_millis`.
+    pub fn available(&self, key: &str, now_millis: u64) -> u32 {
+        let buckets = self
+            .buckets
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        buckets.get(key).map_or(self.capacity, |bucket| {
+            self.refilled(*bucket, now_millis).tokens
+        })
+    }
+
+    fn refilled(&self, mut bucket: Bucket, now_millis: u64) -> Bucket {
+        let elapsed = now_millis.saturating_sub(bucket.refilled_at_millis);
+        let earned = elapsed / self.refill_every_millis;
+        if earned == 0 {
+            return bucket;
+        }
+        let tokens = u64::from(bucket.tokens) + earned;
+        if tokens >= u64::from(self.capacity) {
+            bucket.tokens = self.capacity;
+            bucket.refilled_at_millis = now_millis;
+        } else {
+            bucket.tokens = tokens as u32;
+            bucket.refilled_at_millis += earned * self.refill_every_millis;
+        }
+        bucket
+    }
+}
+
+impl RateLimitGate for TokenBucketGate {
+    fn try_acquire(
+        &self,
+        step_name: &str,
+        context: &SagaContext,
+        input: &[u8],
+        now_millis: u64,
+    ) -> Result<(), RateLimited> {
+        let key: Box<str> = match self
+            .rate_key
+            .as_ref()
+            .and_then(|rate_key| rate_key(context, input))
+        {
+            Some(instrument) => format!("{step_name}/{instrument}").into(),
+            None => step_name.into(),
+        };
+        let mut buckets = self
+            .buckets
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let bucket = buckets.entry(key.clone()).or_insert(Bucket {
+            tokens: self.capacity,
+            refilled_at_millis: now_millis,
+        });
+        *bucket = self.refilled(*bucket, now_millis);
+        if bucket.tokens == 0 {
+            return Err(RateLimited {
+                key,
+                retry_at_millis: bucket.refilled_at_millis + self.refill_every_millis,
+            });
+        }
+        bucket.tokens -= 1;
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for TokenBucketGate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let buckets_len = self
+            .buckets
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .len();
+        f.debug_struct("TokenBucketGate")
+            .field("capacity", &self.capacity)
+            .field("refill_every_millis", &self.refill_every_millis)
+            .field("keyed", &self.rate_key.is_some())
+            .field("buckets_len", &buckets_len)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DeterministicContextBuilder;
+
+    #[test]
+    fn buckets_are_split_by_instrument_and_refill_over_time() {
+        let gate = TokenBucketGate::new(2, Duration::from_millis(500))
+            .with_rate_key(|_, input: &[u8]| Some(String::from_utf8_lossy(input).into()));
+        let context = DeterministicContextBuilder::default().build();
+
+        assert_eq!(gate.try_acquire("place_order", &context, b"BTC", 0), Ok(()));
+        assert_eq!(gate.try_acquire("place_order", &context, b"BTC", 0), Ok(()));
+        assert_eq!(
+            gate.try_acquire("place_order", &context, b"BTC", 200),
+            Err(RateLimited {
+                key: "place_order/BTC".into(),
+                retry_at_millis: 500,
+            })
+        );
+        assert_eq!(
+            gate.try_acquire("place_order", &context, b"ETH", 200),
+            Ok(())
+        );
+
+        assert_eq!(gate.available("place_order/BTC", 1_200), 2);
+        assert_eq!(
+            gate.try_acquire("place_order", &context, b"BTC", 1_200),
+            Ok(())
+        );
+        assert_eq!(gate.available("place_order/BTC", 1_200), 1);
+    }
+}
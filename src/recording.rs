@@ -0,0 +1,462 @@
+//! Golden event-stream recording for workflow regression tests.
+//!
+//! [`RecordingBus`] captures every event published for the given saga types
+//! and [`RecordingObserver`] captures [`SagaObserver`] callbacks. Both render
+//! to a canonical text snapshot: one line per event, saga ids renumbered
+//! `#1`, `#2`, ... by first appearance, and timestamps, trace ids and raw
+//! payload bytes left out. [`assert_event_stream`] compares a snapshot with
+//! the expected text and panics with a line diff.
+//!
+//! ```rust,ignore
+//! let recording = RecordingBus::attach(&bus, &["order_lifecycle"]);
+//! // ... run the saga ...
+//! recording.assert_snapshot(
+//!     "
+//!     #1 order_lifecycle saga_started step=risk_check attempt=0 payload=5B
+//!     #1 order_lifecycle step_completed step=risk_check attempt=0 output=0B compensation_available=true
+//!     #1 order_lifecycle saga_completed step=terminal_resolver attempt=0
+//!     ",
+//! );
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex};
+
+use icanact_core::local::EventSubscription;
+
+use crate::{
+    SagaChoreographyBus, SagaChoreographyEvent, SagaContext, SagaId, SagaObserver, StateKind,
+};
+
+/// Captures events published on a [`SagaChoreographyBus`], in publish order.
+pub struct RecordingBus {
+    bus: SagaChoreographyBus,
+    events: Arc<Mutex<Vec<SagaChoreographyEvent>>>,
+    subscriptions: Vec<EventSubscription>,
+}
+
+impl RecordingBus {
+    /// Starts recording every event published for `saga_types` on `bus`.
+    pub fn attach(bus: &SagaChoreographyBus, saga_types: &[&str]) -> Self {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let subscriptions = saga_types
+            .iter()
+            .map(|saga_type| {
+                let events = Arc::clone(&events);
+                bus.subscribe_saga_type_fn(saga_type, move |event| {
+                    events
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())
+                        .push(event.clone());
+                    true
+                })
+            })
+            .collect();
+        Self {
+            bus: bus.clone(),
+            events,
+            subscriptions,
+        }
+    }
+
+    pub fn events(&self) -> Vec<SagaChoreographyEvent> {
+        self.events
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    pub fn clear(&self) {
+        self.events
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clear();
+    }
+
+    /// Canonical text of the events recorded so far.
+    pub fn snapshot(&self) -> String {
+        canonical_event_stream(&self.events())
+    }
+
+    #[track_caller]
+    pub fn assert_snapshot(&self, expected: &str) {
+        assert_event_stream(&self.snapshot(), expected);
+    }
+}
+
+impl Drop for RecordingBus {
+    fn drop(&mut self) {
+        for subscription in self.subscriptions.drain(..) {
+            self.bus.unsubscribe(subscription);
+        }
+    }
+}
+
+impl std::fmt::Debug for RecordingBus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecordingBus")
+            .field(
+                "recorded",
+                &self
+                    .events
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .len(),
+            )
+            .finish()
+    }
+}
+
+/// [`SagaObserver`] that records its callbacks for snapshot assertions.
+/// Durations are left out of the snapshot.
+#[derive(Debug, Default)]
+pub struct RecordingObserver {
+    calls: Mutex<Vec<(SagaId, String)>>,
+}
+
+impl RecordingObserver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn snapshot(&self) -> String {
+        let calls = self
+            .calls
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut ids = SagaIdCanon::default();
+        let mut out = String::new();
+        for (saga_id, line) in calls.iter() {
+            let _ = writeln!(out, "{} {line}", ids.label(*saga_id));
+        }
+        out
+    }
+
+    #[track_caller]
+    pub fn assert_snapshot(&self, expected: &str) {
+        assert_event_stream(&self.snapshot(), expected);
+    }
+
+    fn record(&self, context: &SagaContext, line: String) {
+        self.calls
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push((context.saga_id, format!("{} {line}", context.saga_type)));
+    }
+}
+
+impl SagaObserver for RecordingObserver {
+    fn on_saga_started(&self, context: &SagaContext) {
+        self.record(context, "saga_started".into());
+    }
+
+    fn on_step_started(&self, context: &SagaContext, step: &str) {
+        self.record(context, format!("step_started step={step}"));
+    }
+
+    fn on_step_completed(&self, context: &SagaContext, step: &str, _duration_millis: u64) {
+        self.record(context, format!("step_completed step={step}"));
+    }
+
+    fn on_step_failed(&self, context: &SagaContext, step: &str, error: &str) {
+        self.record(context, format!("step_failed step={step} error={error:?}"));
+    }
+
+    fn on_compensation_started(&self, context: &SagaContext, step: &str) {
+        self.record(context, format!("compensation_started step={step}"));
+    }
+
+    fn on_compensation_completed(&self, context: &SagaContext, step: &str) {
+        self.record(context, format!("compensation_completed step={step}"));
+    }
+
+    fn on_saga_completed(&self, context: &SagaContext) {
+        self.record(context, "saga_completed".into());
+    }
+
+    fn on_saga_failed(&self, context: &SagaContext, reason: &str) {
+        self.record(context, format!("saga_failed reason={reason:?}"));
+    }
+
+    fn on_saga_quarantined(&self, context: &SagaContext, step: &str, reason: &str) {
+        self.record(
+            context,
+            format!("saga_quarantined step={step} reason={reason:?}"),
+        );
+    }
+
+    fn on_step_retry(
+        &self,
+        context: &SagaContext,
+        step: &str,
+        attempt: u32,
+        _delay: std::time::Duration,
+    ) {
+        self.record(context, format!("step_retry step={step} attempt={attempt}"));
+    }
+
+    fn on_journal_append_retry(
+        &self,
+        context: &SagaContext,
+        step: &str,
+        attempt: u32,
+        _delay: std::time::Duration,
+    ) {
+        self.record(
+            context,
+            format!("journal_append_retry step={step} attempt={attempt}"),
+        );
+    }
+
+    fn on_step_timeout(&self, context: &SagaContext, step: &str, _elapsed: std::time::Duration) {
+        self.record(context, format!("step_timeout step={step}"));
+    }
+
+    fn on_duplicate_event(&self, context: &SagaContext, event_type: &str) {
+        self.record(context, format!("duplicate_event type={event_type}"));
+    }
+
+    fn on_step_panicked(&self, context: &SagaContext, step: &str, message: &str) {
+        self.record(
+            context,
+            format!("step_panicked step={step} message={message:?}"),
+        );
+    }
+
+    fn on_missing_state(&self, context: &SagaContext, step: &str, expected: StateKind) {
+        self.record(
+            context,
+            format!("missing_state step={step} expected={expected:?}"),
+        );
+    }
+}
+
+#[derive(Default)]
+struct SagaIdCanon {
+    labels: HashMap<SagaId, usize>,
+}
+
+impl SagaIdCanon {
+    fn label(&mut self, saga_id: SagaId) -> String {
+        let next = self.labels.len() + 1;
+        format!("#{}", self.labels.entry(saga_id).or_insert(next))
+    }
+}
+
+/// Renders `events` as a canonical snapshot, one line per event.
+pub fn canonical_event_stream(events: &[SagaChoreographyEvent]) -> String {
+    let mut ids = SagaIdCanon::default();
+    let mut out = String::new();
+    for event in events {
+        let context = event.context();
+        let _ = write!(
+            out,
+            "{} {} {} step={} attempt={}",
+            ids.label(context.saga_id),
+            context.saga_type,
+            event.event_type(),
+            context.step_name,
+            context.attempt
+        );
+        match event {
+            SagaChoreographyEvent::SagaStarted { payload, .. } => {
+                let _ = write!(out, " payload={}B", payload.len());
+                if let Some(parent) = context.parent_saga_id {
+                    let _ = write!(out, " parent={}", ids.label(parent));
+                }
+            }
+            SagaChoreographyEvent::SagaFailed { reason, .. } => {
+                let _ = write!(out, " reason={reason:?}");
+            }
+            SagaChoreographyEvent::StepCompleted {
+                output,
+                compensation_available,
+                completion_ratio,
+                ..
+            } => {
+                let _ = write!(
+                    out,
+                    " output={}B compensation_available={compensation_available}",
+                    output.len()
+                );
+                if let Some(ratio) = completion_ratio {
+                    let _ = write!(out, " completion_ratio={ratio}");
+                }
+            }
+            SagaChoreographyEvent::StepFailed {
+                participant_id,
+                error_code,
+                error,
+                requires_compensation,
+                ..
+            } => {
+                let _ = write!(out, " participant={participant_id}");
+                if let Some(code) = error_code {
+                    let _ = write!(out, " code={code}");
+                }
+                let _ = write!(
+                    out,
+                    " error={error:?} requires_compensation={requires_compensation}"
+                );
+            }
+            SagaChoreographyEvent::CompensationRequested {
+                failed_step,
+                reason,
+                steps_to_compensate,
+                ..
+            } => {
+                let _ = write!(
+                    out,
+                    " failed_step={failed_step} steps=[{}] reason={reason:?}",
+                    steps_to_compensate.join(",")
+                );
+            }
+            SagaChoreographyEvent::CompensationFailed {
+                participant_id,
+                error,
+                is_ambiguous,
+                ..
+            } => {
+                let _ = write!(
+                    out,
+                    " participant={participant_id} error={error:?} ambiguous={is_ambiguous}"
+                );
+            }
+            SagaChoreographyEvent::SagaQuarantined {
+                reason,
+                step,
+                participant_id,
+                ..
+            } => {
+                let _ = write!(
+                    out,
+                    " failed_step={step} participant={participant_id} reason={reason:?}"
+                );
+            }
+            SagaChoreographyEvent::StepAck { status, .. } => {
+                let _ = write!(out, " status={status:?}");
+            }
+            SagaChoreographyEvent::ParticipantRecovered { participant_id, .. } => {
+                let _ = write!(out, " participant={participant_id}");
+            }
+            SagaChoreographyEvent::InitiatorTakenOver { successor_id, .. } => {
+                let _ = write!(out, " successor={successor_id}");
+            }
+            // The step is already on the line; the wait is wall-clock time.
+            SagaChoreographyEvent::SagaCompleted { .. }
+            | SagaChoreographyEvent::SagaStalled { .. }
+            | SagaChoreographyEvent::InitiatorHeartbeat { .. }
+            | SagaChoreographyEvent::StepStarted { .. }
+            | SagaChoreographyEvent::CompensationStarted { .. }
+            | SagaChoreographyEvent::CompensationCompleted { .. } => {}
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Compares a canonical snapshot with `expected`, ignoring indentation and
+/// blank lines, and panics with a line diff when they differ.
+///
+/// Diff lines start with `-` for expected lines that are missing and `+` for
+/// recorded lines that were not expected.
+#[track_caller]
+pub fn assert_event_stream(actual: &str, expected: &str) {
+    let actual = snapshot_lines(actual);
+    let expected = snapshot_lines(expected);
+    if actual == expected {
+        return;
+    }
+    panic!(
+        "event stream differs from snapshot (-expected +actual):\n{}",
+        line_diff(&expected, &actual)
+    );
+}
+
+fn snapshot_lines(text: &str) -> Vec<&str> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+fn line_diff(expected: &[&str], actual: &[&str]) -> String {
+    // Longest common subsequence table, filled from the end.
+    let mut lcs = vec![vec![0usize; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            lcs[i][j] = if expected[i] == actual[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut out = String::new();
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            let _ = writeln!(out, "  {}", expected[i]);
+            i += 1;
+            j += 1;
+        } else if j < actual.len() && (i == expected.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            let _ = writeln!(out, "+ {}", actual[j]);
+            j += 1;
+        } else {
+            let _ = writeln!(out, "- {}", expected[i]);
+            i += 1;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{step_completed, DeterministicContextBuilder};
+
+    fn ctx(saga_id: u64, step: &str) -> SagaContext {
+        DeterministicContextBuilder::default()
+            .with_saga_id(saga_id)
+            .with_step_name(step)
+            .build()
+    }
+
+    #[test]
+    fn snapshot_renumbers_sagas_and_diffs_lines() {
+        let bus = SagaChoreographyBus::new();
+        let recording = RecordingBus::attach(&bus, &["order_lifecycle"]);
+        bus.publish_to_saga_type(
+            "order_lifecycle",
+            step_completed(ctx(900, "risk_check"), vec![1, 2], Vec::new(), true),
+        );
+        bus.publish_to_saga_type(
+            "order_lifecycle",
+            SagaChoreographyEvent::SagaCompleted {
+                context: ctx(42, "place_order"),
+            },
+        );
+
+        recording.assert_snapshot(
+            "
+            #1 order_lifecycle step_completed step=risk_check attempt=0 output=2B compensation_available=true
+            #2 order_lifecycle saga_completed step=place_order attempt=0
+            ",
+        );
+
+        let diff = line_diff(&["a", "b", "c"], &["a", "x", "c"]);
+        assert_eq!(diff, "  a\n+ x\n- b\n  c\n");
+    }
+
+    #[test]
+    fn observer_snapshot_omits_durations() {
+        let observer = RecordingObserver::new();
+        observer.on_step_completed(&ctx(7, "risk_check"), "risk_check", 12);
+        observer.on_saga_failed(&ctx(8, "risk_check"), "limit");
+        assert_eq!(
+            observer.snapshot(),
+            "#1 order_lifecycle step_completed step=risk_check\n#2 order_lifecycle saga_failed reason=\"limit\"\n"
+        );
+    }
+}
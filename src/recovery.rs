@@ -0,0 +1,421 @@
+//! Recovery of a participant restarted by its supervisor.
+//!
+//! A supervisor that restarts a crashed actor builds it afresh: in-memory
+//! dedupe state is gone, the bus subscriptions that fed the old instance's
+//! mailbox are dead, and peers do not know the participant came back.
+//! [`SagaRecoveryOnStart`] is meant to be called from the actor's start
+//! hook (or its factory) and puts all of that back:
+//!
+//! ```ignore
+//! let mut recovery = SagaRecoveryOnStart::new().with_resubscribe(move |bus| {
+//!     bind_sync_participant_channel::<RiskActor, RiskCommand>(
+//!         bus, &actor_ref, &["order_lifecycle"], "saga", 256,
+//!     )
+//! });
+//! // In the actor's start hook, on every (re)start:
+//! let report = recovery.on_start(&mut actor)?;
+//! ```
+//!
+//! Events produced by [`recover_sagas`] are queued as startup recovery
+//! events; the actor publishes them as usual after
+//! `take_startup_recovery_events`.
+
+use std::sync::Arc;
+
+use icanact_core::local::EventSubscription;
+
+use crate::durability::{
+    classify_recovery, collect_startup_recovery_events_for_saga_type,
+    recovery_context_for_saga_type, RecoveryCollectionError, RecoveryDecision, RecoveryPolicy,
+    DEFAULT_RECOVERY_SAGA_TYPE,
+};
+use crate::{
+    DedupeError, JournalError, ParticipantDedupeStore, ParticipantJournal,
+    ParticipantStateStoreError, SagaBusPublishError, SagaChoreographyBus, SagaChoreographyEvent,
+    SagaContext, SagaId, SagaParticipant, SagaStateExt,
+};
+
+type ResubscribeFn =
+    dyn Fn(&SagaChoreographyBus) -> Result<Vec<EventSubscription>, String> + Send + Sync;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SagaRecoveryError {
+    #[error("startup recovery collection failed: {0:?}")]
+    Collect(RecoveryCollectionError),
+    #[error("Journal error: {0}")]
+    Journal(#[from] JournalError),
+    #[error("Dedupe error: {0}")]
+    Dedupe(#[from] DedupeError),
+    #[error("State store error: {0}")]
+    State(#[from] ParticipantStateStoreError),
+    #[error("resubscribe failed: {0}")]
+    Resubscribe(Box<str>),
+}
+
+/// Outcome of [`SagaRecoveryOnStart::on_start`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SagaRecoveryReport {
+    /// Startup recovery events queued by [`recover_sagas`].
+    pub recovery_events: usize,
+    /// Unfinished sagas the participant resumes; each got a
+    /// `ParticipantRecovered` event.
+    pub resumed: Vec<SagaId>,
+    /// Dedupe keys re-marked from the journal inbox history.
+    pub dedupe_keys_restored: usize,
+    /// Saga states loaded from the attached state store.
+    pub states_restored: usize,
+    /// Bus subscriptions re-created.
+    pub subscriptions: usize,
+    /// Outbox events left pending before the restart and relayed now.
+    pub outbox_relayed: usize,
+}
+
+/// Classifies the participant's journaled sagas, queues the resulting
+/// startup recovery events, and reports the sagas left to resume.
+///
+/// Recovery events carry the participant's first saga type.
+pub fn recover_sagas<P>(participant: &mut P) -> Result<SagaRecoveryReport, SagaRecoveryError>
+where
+    P: SagaParticipant + SagaStateExt,
+{
+    let saga_type = recovery_saga_type(participant);
+    let events = collect_startup_recovery_events_for_saga_type(
+        participant.saga_journal(),
+        participant.saga_dedupe(),
+        participant.step_name(),
+        saga_type,
+    )
+    .map_err(SagaRecoveryError::Collect)?;
+
+    let now = SagaContext::now_millis();
+    let mut resumed = Vec::new();
+    for saga_id in participant.saga_journal().list_sagas()? {
+        let entries = participant.saga_journal().read(saga_id)?;
+        if !entries.is_empty()
+            && classify_recovery(&entries, now, RecoveryPolicy::default())
+                == RecoveryDecision::Continue
+        {
+            resumed.push(saga_id);
+        }
+    }
+
+    let recovery_events = events.len();
+    participant
+        .saga_support_mut()
+        .startup_recovery_events
+        .extend(events);
+    Ok(SagaRecoveryReport {
+        recovery_events,
+        resumed,
+        ..SagaRecoveryReport::default()
+    })
+}
+
+/// Marks the dedupe key of every event in the journal inbox history, so a
+/// dedupe store that did not survive the restart still drops redeliveries.
+///
+/// Returns the number of keys that were missing.
+pub fn restore_dedupe_state<P>(participant: &P) -> Result<usize, SagaRecoveryError>
+where
+    P: SagaStateExt,
+{
+    let journal = participant.saga_journal();
+    let dedupe = participant.saga_dedupe();
+    let identity = &participant.saga_support().dedupe_identity;
+    let mut restored = 0;
+    for saga_id in journal.list_sagas()? {
+        for entry in journal.incoming_history(saga_id)? {
+            if dedupe.check_and_mark(saga_id, identity.key(&entry.event))? {
+                restored += 1;
+            }
+        }
+    }
+    Ok(restored)
+}
+
+/// Loads the current state of every journaled saga from the participant's
+/// state store, one `get` per saga instead of a journal replay. Sagas with
+/// a state already in memory are left alone.
+///
+/// Returns the number of states loaded; 0 without a state store.
+pub fn restore_saga_states<P>(participant: &mut P) -> Result<usize, SagaRecoveryError>
+where
+    P: SagaStateExt,
+{
+    let Some(store) = participant.saga_support().state_store.clone() else {
+        return Ok(0);
+    };
+    let mut restored = 0;
+    for saga_id in participant.saga_journal().list_sagas()? {
+        if participant.saga_states_ref().contains_key(&saga_id) {
+            continue;
+        }
+        if let Some(entry) = store.get(saga_id)? {
+            participant.saga_states().insert(saga_id, entry);
+            restored += 1;
+        }
+    }
+    Ok(restored)
+}
+
+/// Start hook for supervised saga participants; see the module docs.
+#[derive(Default)]
+pub struct SagaRecoveryOnStart {
+    resubscribe: Option<Arc<ResubscribeFn>>,
+    subscriptions: Vec<EventSubscription>,
+}
+
+impl SagaRecoveryOnStart {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Re-creates the participant's bus subscriptions on every start, e.g.
+    /// with one of the `bind_*_participant_channel` helpers. Subscriptions
+    /// of the previous instance are dropped first.
+    pub fn with_resubscribe<F>(mut self, resubscribe: F) -> Self
+    where
+        F: Fn(&SagaChoreographyBus) -> Result<Vec<EventSubscription>, String>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.resubscribe = Some(Arc::new(resubscribe));
+        self
+    }
+
+    /// Recovers `participant` after a (re)start: [`recover_sagas`],
+    /// [`restore_dedupe_state`], [`restore_saga_states`], resubscription on the attached bus, a
+    /// relay of the pending outbox, and a `ParticipantRecovered` event for each resumed saga.
+    pub fn on_start<P>(
+        &mut self,
+        participant: &mut P,
+    ) -> Result<SagaRecoveryReport, SagaRecoveryError>
+    where
+        P: SagaParticipant + SagaStateExt,
+    {
+        let mut report = recover_sagas(participant)?;
+        report.dedupe_keys_restored = restore_dedupe_state(participant)?;
+        report.states_restored = restore_saga_states(participant)?;
+
+        let bus = participant.saga_support().bus.clone();
+        if let Some(resubscribe) = &self.resubscribe {
+            let Some(bus) = &bus else {
+                return Err(SagaRecoveryError::Resubscribe(
+                    "saga bus is not attached".into(),
+                ));
+            };
+            self.subscriptions.clear();
+            self.subscriptions =
+                resubscribe(bus).map_err(|err| SagaRecoveryError::Resubscribe(err.into()))?;
+        }
+        report.subscriptions = self.subscriptions.len();
+
+        if let Some(bus) = &bus {
+            match participant.saga_support().relay_outbox() {
+                Ok(relayed) => report.outbox_relayed = relayed,
+                Err(error) => tracing::warn!(
+                    target: "core::saga",
+                    event = "saga_recovery_outbox_relay_failed",
+                    step_name = participant.step_name(),
+                    error = %error
+                ),
+            }
+            let saga_type = recovery_saga_type(participant);
+            for saga_id in &report.resumed {
+                let event = SagaChoreographyEvent::ParticipantRecovered {
+                    context: recovery_context_for_saga_type(
+                        *saga_id,
+                        participant.step_name(),
+                        saga_type,
+                    ),
+                    participant_id: participant.step_name().into(),
+                };
+                if let Err(err) = bus.publish_strict(event) {
+                    log_recovered_publish_failed(*saga_id, &err);
+                }
+            }
+        }
+
+        tracing::info!(
+            target: "core::saga",
+            event = "saga_participant_recovered",
+            step_name = participant.step_name(),
+            recovery_events = report.recovery_events,
+            resumed = report.resumed.len(),
+            dedupe_keys_restored = report.dedupe_keys_restored,
+            states_restored = report.states_restored,
+            subscriptions = report.subscriptions,
+            outbox_relayed = report.outbox_relayed
+        );
+        Ok(report)
+    }
+}
+
+impl std::fmt::Debug for SagaRecoveryOnStart {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SagaRecoveryOnStart")
+            .field("resubscribe", &self.resubscribe.is_some())
+            .field("subscriptions_len", &self.subscriptions.len())
+            .finish()
+    }
+}
+
+fn recovery_saga_type<P>(participant: &P) -> &'static str
+where
+    P: SagaParticipant,
+{
+    participant
+        .saga_types()
+        .first()
+        .copied()
+        .unwrap_or(DEFAULT_RECOVERY_SAGA_TYPE)
+}
+
+fn log_recovered_publish_failed(saga_id: SagaId, err: &SagaBusPublishError) {
+    tracing::warn!(
+        target: "core::saga",
+        event = "saga_participant_recovered_publish_failed",
+        saga_id = saga_id.get(),
+        error = ?err
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::{
+        handle_saga_event_with_emit, saga_started, CompensationError, DeterministicContextBuilder,
+        HasSagaParticipantSupport, InMemoryDedupe, InMemoryJournal, InMemoryStateStore,
+        SagaParticipantSupport, SagaStateEntry, StepError, StepOutput,
+    };
+
+    struct Reserver {
+        saga: SagaParticipantSupport<Arc<InMemoryJournal>, InMemoryDedupe>,
+        executed: usize,
+    }
+
+    impl Reserver {
+        fn new(journal: Arc<InMemoryJournal>) -> Self {
+            Self {
+                saga: SagaParticipantSupport::new(journal, InMemoryDedupe::new()),
+                executed: 0,
+            }
+        }
+    }
+
+    impl HasSagaParticipantSupport for Reserver {
+        type Journal = Arc<InMemoryJournal>;
+        type Dedupe = InMemoryDedupe;
+
+        fn saga_support(&self) -> &SagaParticipantSupport<Self::Journal, Self::Dedupe> {
+            &self.saga
+        }
+
+        fn saga_support_mut(&mut self) -> &mut SagaParticipantSupport<Self::Journal, Self::Dedupe> {
+            &mut self.saga
+        }
+    }
+
+    impl SagaParticipant for Reserver {
+        type Error = String;
+
+        fn step_name(&self) -> &str {
+            "reserve_funds"
+        }
+
+        fn saga_types(&self) -> &[&'static str] {
+            &["order_lifecycle"]
+        }
+
+        fn execute_step(
+            &mut self,
+            _context: &SagaContext,
+            _input: &[u8],
+        ) -> Result<StepOutput, StepError> {
+            self.executed += 1;
+            Ok(StepOutput::Completed {
+                output: Vec::new(),
+                compensation_data: vec![1],
+            })
+        }
+
+        fn compensate_step(
+            &mut self,
+            _context: &SagaContext,
+            _compensation_data: &[u8],
+        ) -> Result<(), CompensationError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn restarted_participant_recovers_dedupe_subscriptions_and_announces_itself() {
+        let journal = Arc::new(InMemoryJournal::new());
+        let context = DeterministicContextBuilder::default().build();
+        let started = saga_started(context.clone(), b"order".to_vec());
+        let mut first = Reserver::new(Arc::clone(&journal));
+        handle_saga_event_with_emit(&mut first, started.clone(), |_| {});
+        assert_eq!(first.executed, 1);
+
+        // The supervisor rebuilds the actor; only the journal survived.
+        let bus = SagaChoreographyBus::new();
+        let announced = Arc::new(Mutex::new(Vec::new()));
+        let mut restarted = Reserver::new(journal);
+        restarted.saga.attach_bus(bus.clone());
+        let sink = Arc::clone(&announced);
+        let mut recovery = SagaRecoveryOnStart::new().with_resubscribe(move |bus| {
+            let sink = Arc::clone(&sink);
+            Ok(vec![bus.subscribe_saga_type_fn(
+                "order_lifecycle",
+                move |event| {
+                    sink.lock().unwrap().push(event.clone());
+                    true
+                },
+            )])
+        });
+
+        let report = recovery.on_start(&mut restarted).unwrap();
+        assert_eq!(report.resumed, vec![context.saga_id]);
+        assert_eq!(report.recovery_events, 0);
+        assert_eq!(report.dedupe_keys_restored, 1);
+        assert_eq!(report.subscriptions, 1);
+        assert!(matches!(
+            announced.lock().unwrap().as_slice(),
+            [SagaChoreographyEvent::ParticipantRecovered { participant_id, .. }]
+                if &**participant_id == "reserve_funds"
+        ));
+
+        handle_saga_event_with_emit(&mut restarted, started, |_| {});
+        assert_eq!(restarted.executed, 0);
+    }
+
+    #[test]
+    fn restarted_participant_loads_saga_state_from_the_state_store() {
+        let journal = Arc::new(InMemoryJournal::new());
+        let states = Arc::new(InMemoryStateStore::new());
+        let context = DeterministicContextBuilder::default().build();
+        let mut first = Reserver::new(Arc::clone(&journal));
+        first.saga.attach_state_store(states.clone());
+        handle_saga_event_with_emit(
+            &mut first,
+            saga_started(context.clone(), b"order".to_vec()),
+            |_| {},
+        );
+        assert_eq!(states.len(), 1);
+
+        let mut restarted = Reserver::new(journal);
+        restarted.saga.attach_state_store(states.clone());
+        let report = SagaRecoveryOnStart::new().on_start(&mut restarted).unwrap();
+        assert_eq!(report.states_restored, 1);
+        assert!(matches!(
+            restarted.saga_states_ref().get(&context.saga_id),
+            Some(SagaStateEntry::Completed(state)) if state.state.compensation_data == [1]
+        ));
+
+        restarted.prune_saga(context.saga_id);
+        assert!(states.is_empty());
+    }
+}
@@ -0,0 +1,149 @@
+//! Per-saga reordering of early events.
+//!
+//! Brokers do not order events of one saga across publishers, so a
+//! participant can receive a saga's `StepCompleted` before its
+//! `SagaStarted`, and would then process it against state the start has not
+//! set up yet. With a [`SagaReorderWindow`] attached, the ingress helpers
+//! hold every event of a saga whose `SagaStarted` this participant has not
+//! seen. The start releases them, in logical clock order, right after it is
+//! processed. Events still held when the window runs out are processed
+//! anyway or dead-lettered as [`DeadLetterReason::OutOfOrder`], when
+//! `flush_reorder_buffer_with_emit` (or its async twin) runs on the
+//! participant's timer.
+//!
+//! Held events stay pending in the journal inbox, so an inbox replay after
+//! a restart processes them in arrival order.
+//!
+//! [`DeadLetterReason::OutOfOrder`]: crate::DeadLetterReason::OutOfOrder
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{SagaChoreographyEvent, SagaId};
+
+/// How long events may wait for their saga's `SagaStarted`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SagaReorderWindow {
+    pub hold_millis: u64,
+    pub on_expiry: ReorderExpiry,
+}
+
+impl SagaReorderWindow {
+    /// Holds events for `hold_millis`, then processes them anyway.
+    pub const fn new(hold_millis: u64) -> Self {
+        Self {
+            hold_millis,
+            on_expiry: ReorderExpiry::Process,
+        }
+    }
+
+    pub const fn dead_letter_on_expiry(mut self) -> Self {
+        self.on_expiry = ReorderExpiry::DeadLetter;
+        self
+    }
+}
+
+/// What happens to an event whose `SagaStarted` never came.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReorderExpiry {
+    /// Process it as if the start had been seen; later events of the saga
+    /// are no longer held.
+    Process,
+    /// Route it to the dead-letter store and close its inbox entry.
+    DeadLetter,
+}
+
+pub(crate) struct HeldEvent {
+    pub(crate) event: SagaChoreographyEvent,
+    pub(crate) inbox_id: Option<u64>,
+    pub(crate) held_at_millis: u64,
+}
+
+/// Sagas seen started and the events held for the others.
+#[derive(Default)]
+pub(crate) struct ReorderBuffer {
+    started: HashSet<SagaId>,
+    held: HashMap<SagaId, Vec<HeldEvent>>,
+}
+
+impl ReorderBuffer {
+    /// Holds `event` if it is early, handing it back otherwise. A terminal
+    /// event that is not held ends the saga's tracking.
+    pub(crate) fn hold(
+        &mut self,
+        event: SagaChoreographyEvent,
+        inbox_id: Option<u64>,
+        now_millis: u64,
+    ) -> Option<SagaChoreographyEvent> {
+        let saga_id = event.context().saga_id;
+        if matches!(event, SagaChoreographyEvent::SagaStarted { .. }) {
+            return Some(event);
+        }
+        if self.started.contains(&saga_id) {
+            if event.terminal_outcome().is_some() {
+                self.started.remove(&saga_id);
+            }
+            return Some(event);
+        }
+        self.held.entry(saga_id).or_default().push(HeldEvent {
+            event,
+            inbox_id,
+            held_at_millis: now_millis,
+        });
+        None
+    }
+
+    /// Marks `saga_id` started and returns its held events in logical order.
+    pub(crate) fn release(&mut self, saga_id: SagaId) -> Vec<HeldEvent> {
+        self.started.insert(saga_id);
+        let mut released = self.held.remove(&saga_id).unwrap_or_default();
+        released.sort_by_key(|held| held.event.context().logical_clock);
+        if released
+            .iter()
+            .any(|held| held.event.terminal_outcome().is_some())
+        {
+            self.started.remove(&saga_id);
+        }
+        released
+    }
+
+    /// Takes every saga whose oldest held event waited `hold_millis`. With
+    /// `give_up` its later events are no longer held.
+    pub(crate) fn take_expired(
+        &mut self,
+        now_millis: u64,
+        hold_millis: u64,
+        give_up: bool,
+    ) -> Vec<HeldEvent> {
+        let expired: Vec<SagaId> = self
+            .held
+            .iter()
+            .filter(|(_, held)| {
+                held.iter()
+                    .any(|held| now_millis.saturating_sub(held.held_at_millis) >= hold_millis)
+            })
+            .map(|(saga_id, _)| *saga_id)
+            .collect();
+        let mut taken = Vec::new();
+        for saga_id in expired {
+            if give_up {
+                taken.extend(self.release(saga_id));
+            } else if let Some(mut held) = self.held.remove(&saga_id) {
+                held.sort_by_key(|held| held.event.context().logical_clock);
+                taken.extend(held);
+            }
+        }
+        taken
+    }
+
+    /// Whether the inbox entry `inbox_id` is held.
+    pub(crate) fn holds(&self, inbox_id: u64) -> bool {
+        self.held
+            .values()
+            .flatten()
+            .any(|held| held.inbox_id == Some(inbox_id))
+    }
+
+    pub(crate) fn held_len(&self) -> usize {
+        self.held.values().map(Vec::len).sum()
+    }
+}
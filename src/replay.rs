@@ -0,0 +1,282 @@
+//! Journal replay for post-mortem debugging.
+//!
+//! [`replay_saga`] reads the incoming events a participant recorded for one
+//! saga and feeds them through another participant instance, typically a
+//! patched build of the same participant wired to in-memory stores. The
+//! outcomes the sandbox journals are compared with the ones the original
+//! participant journaled, so a fix for a quarantined saga can be checked
+//! against exactly the events that produced the quarantine.
+
+use crate::{
+    handle_saga_event_with_emit, JournalError, ParticipantEvent, ParticipantJournal,
+    SagaChoreographyEvent, SagaId, SagaParticipant, SagaStateExt,
+};
+
+/// One position where the replayed outcomes differ from the recorded ones.
+#[derive(Clone, Debug)]
+pub struct SagaReplayDivergence {
+    /// Position in the outcome sequence, counting from zero.
+    pub index: usize,
+    /// The outcome the original participant journaled, if any.
+    pub recorded: Option<ParticipantEvent>,
+    /// The outcome the sandboxed participant journaled, if any.
+    pub replayed: Option<ParticipantEvent>,
+}
+
+/// Result of [`replay_saga`].
+#[derive(Clone, Debug)]
+pub struct SagaReplayReport {
+    pub saga_id: SagaId,
+    /// Number of recorded incoming events fed to the sandbox.
+    pub replayed_events: usize,
+    /// Events the sandbox emitted. They are collected, never published.
+    pub emitted: Vec<SagaChoreographyEvent>,
+    /// Outcomes from the source journal, in order.
+    pub recorded: Vec<ParticipantEvent>,
+    /// Outcomes from the sandbox journal, in order.
+    pub replayed: Vec<ParticipantEvent>,
+    pub divergences: Vec<SagaReplayDivergence>,
+}
+
+impl SagaReplayReport {
+    pub fn diverged(&self) -> bool {
+        !self.divergences.is_empty()
+    }
+}
+
+/// Replays the incoming events `journal` recorded for `saga_id` through
+/// `sandbox` and reports where its outcomes diverge from the recorded ones.
+///
+/// Outcomes are step completions and failures, compensation results and
+/// quarantines; timestamps are ignored. `sandbox` should be a fresh instance
+/// whose journal and dedupe store are not shared with production, since
+/// replaying writes to them. Emitted events are returned in the report
+/// instead of being published.
+///
+/// An empty incoming history means `journal` does not keep one (see
+/// [`ParticipantJournal::incoming_history`]) or the saga was already pruned.
+pub fn replay_saga<J, P>(
+    journal: &J,
+    saga_id: SagaId,
+    sandbox: &mut P,
+) -> Result<SagaReplayReport, JournalError>
+where
+    J: ParticipantJournal + ?Sized,
+    P: SagaParticipant + SagaStateExt,
+{
+    let history = journal.incoming_history(saga_id)?;
+    let recorded = outcomes(journal.read(saga_id)?);
+
+    let replayed_events = history.len();
+    let mut emitted = Vec::new();
+    for entry in history {
+        handle_saga_event_with_emit(sandbox, entry.event, |event| emitted.push(event));
+    }
+    let replayed = outcomes(sandbox.saga_journal().read(saga_id)?);
+
+    let divergences = (0..recorded.len().max(replayed.len()))
+        .filter_map(|index| {
+            let recorded = recorded.get(index);
+            let replayed = replayed.get(index);
+            match (recorded, replayed) {
+                (Some(recorded), Some(replayed)) if same_outcome(recorded, replayed) => None,
+                _ => Some(SagaReplayDivergence {
+                    index,
+                    recorded: recorded.cloned(),
+                    replayed: replayed.cloned(),
+                }),
+            }
+        })
+        .collect::<Vec<_>>();
+    if !divergences.is_empty() {
+        tracing::info!(
+            target: "core::saga",
+            event = "saga_replay_diverged",
+            saga_id = saga_id.get(),
+            divergences = divergences.len()
+        );
+    }
+
+    Ok(SagaReplayReport {
+        saga_id,
+        replayed_events,
+        emitted,
+        recorded,
+        replayed,
+        divergences,
+    })
+}
+
+fn outcomes(entries: Vec<crate::JournalEntry>) -> Vec<ParticipantEvent> {
+    entries
+        .into_iter()
+        .map(|entry| entry.event)
+        .filter(|event| {
+            matches!(
+                event,
+                ParticipantEvent::StepExecutionCompleted { .. }
+                    | ParticipantEvent::StepExecutionFailed { .. }
+                    | ParticipantEvent::CompensationCompleted { .. }
+                    | ParticipantEvent::CompensationFailed { .. }
+                    | ParticipantEvent::Quarantined { .. }
+            )
+        })
+        .collect()
+}
+
+fn same_outcome(recorded: &ParticipantEvent, replayed: &ParticipantEvent) -> bool {
+    match (recorded, replayed) {
+        (
+            ParticipantEvent::StepExecutionCompleted {
+                output: a_output,
+                compensation_data: a_data,
+                ..
+            },
+            ParticipantEvent::StepExecutionCompleted {
+                output: b_output,
+                compensation_data: b_data,
+                ..
+            },
+        ) => a_output == b_output && a_data == b_data,
+        (
+            ParticipantEvent::StepExecutionFailed {
+                error: a_error,
+                requires_compensation: a_compensate,
+                ..
+            },
+            ParticipantEvent::StepExecutionFailed {
+                error: b_error,
+                requires_compensation: b_compensate,
+                ..
+            },
+        ) => a_error == b_error && a_compensate == b_compensate,
+        (
+            ParticipantEvent::CompensationCompleted { .. },
+            ParticipantEvent::CompensationCompleted { .. },
+        ) => true,
+        (
+            ParticipantEvent::CompensationFailed {
+                error: a_error,
+                is_ambiguous: a_ambiguous,
+                ..
+            },
+            ParticipantEvent::CompensationFailed {
+                error: b_error,
+                is_ambiguous: b_ambiguous,
+                ..
+            },
+        ) => a_error == b_error && a_ambiguous == b_ambiguous,
+        (
+            ParticipantEvent::Quarantined { reason: a, .. },
+            ParticipantEvent::Quarantined { reason: b, .. },
+        ) => a == b,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        saga_started, CompensationError, DeterministicContextBuilder, HasSagaParticipantSupport,
+        InMemoryDedupe, InMemoryJournal, SagaContext, SagaParticipantSupport, StepError,
+        StepOutput,
+    };
+
+    struct Placer {
+        saga: SagaParticipantSupport<InMemoryJournal, InMemoryDedupe>,
+        reject: bool,
+    }
+
+    impl Placer {
+        fn new(reject: bool) -> Self {
+            Self {
+                saga: SagaParticipantSupport::new(InMemoryJournal::new(), InMemoryDedupe::new()),
+                reject,
+            }
+        }
+    }
+
+    impl HasSagaParticipantSupport for Placer {
+        type Journal = InMemoryJournal;
+        type Dedupe = InMemoryDedupe;
+
+        fn saga_support(&self) -> &SagaParticipantSupport<Self::Journal, Self::Dedupe> {
+            &self.saga
+        }
+
+        fn saga_support_mut(&mut self) -> &mut SagaParticipantSupport<Self::Journal, Self::Dedupe> {
+            &mut self.saga
+        }
+    }
+
+    impl SagaParticipant for Placer {
+        type Error = String;
+
+        fn step_name(&self) -> &str {
+            "risk_check"
+        }
+
+        fn saga_types(&self) -> &[&'static str] {
+            &["order_lifecycle"]
+        }
+
+        fn execute_step(
+            &mut self,
+            _context: &SagaContext,
+            input: &[u8],
+        ) -> Result<StepOutput, StepError> {
+            if self.reject {
+                return Err(StepError::terminal("limit exceeded"));
+            }
+            Ok(StepOutput::Completed {
+                output: input.to_vec(),
+                compensation_data: Vec::new(),
+            })
+        }
+
+        fn compensate_step(
+            &mut self,
+            _context: &SagaContext,
+            _compensation_data: &[u8],
+        ) -> Result<(), CompensationError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn replay_reports_divergence_from_recorded_outcomes() {
+        let context = DeterministicContextBuilder::default().build();
+        let saga_id = context.saga_id;
+        let mut production = Placer::new(true);
+        let started = saga_started(context, b"order".to_vec());
+        handle_saga_event_with_emit(&mut production, started.clone(), |_| {});
+        handle_saga_event_with_emit(&mut production, started, |_| {});
+        let journal = &production.saga_support().journal;
+        assert_eq!(journal.incoming_history(saga_id).unwrap().len(), 2);
+
+        let same = replay_saga(journal, saga_id, &mut Placer::new(true)).unwrap();
+        assert_eq!(same.replayed_events, 2);
+        assert_eq!(same.recorded.len(), 1);
+        assert!(!same.diverged(), "{:?}", same.divergences);
+
+        let patched = replay_saga(journal, saga_id, &mut Placer::new(false)).unwrap();
+        assert!(patched.diverged());
+        assert_eq!(patched.divergences.len(), 1);
+        assert!(matches!(
+            patched.divergences[0].recorded,
+            Some(ParticipantEvent::StepExecutionFailed { .. })
+        ));
+        assert!(matches!(
+            patched.divergences[0].replayed,
+            Some(ParticipantEvent::StepExecutionCompleted { .. })
+        ));
+        assert!(patched
+            .emitted
+            .iter()
+            .any(|event| matches!(event, SagaChoreographyEvent::StepCompleted { .. })));
+
+        journal.prune(saga_id).unwrap();
+        assert!(journal.incoming_history(saga_id).unwrap().is_empty());
+    }
+}
@@ -1,8 +1,10 @@
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
 use std::time::Duration;
 
 use crate::{
-    SagaChoreographyEvent, SagaContext, SagaFailureDetails, SagaId, SagaWorkflowStepContract,
+    CompensationScope, CompensationStrategy, SagaChoreographyEvent, SagaContext,
+    SagaFailureDetails, SagaId, SagaObserver, SagaWorkflowStepContract, StepName,
     WorkflowDependencySpec,
 };
 
@@ -36,32 +38,32 @@ pub enum SuccessCriteria {
 }
 
 impl SuccessCriteria {
-    fn is_satisfied(&self, completed: &HashSet<Box<str>>) -> bool {
+    fn is_satisfied(&self, completed: &HashSet<StepName>) -> bool {
         match self {
-            Self::AllOf(steps) => steps.iter().all(|step| completed.contains(step)),
-            Self::AnyOf(steps) => steps.iter().any(|step| completed.contains(step)),
+            Self::AllOf(steps) => steps.iter().all(|step| completed.contains(step.as_ref())),
+            Self::AnyOf(steps) => steps.iter().any(|step| completed.contains(step.as_ref())),
             Self::Quorum {
                 group_steps,
                 required_count,
             } => {
                 let count = group_steps
                     .iter()
-                    .filter(|step| completed.contains(*step))
+                    .filter(|step| completed.contains(step.as_ref()))
                     .count();
                 count >= *required_count
             }
         }
     }
 
-    fn missing_required_steps(&self, completed: &HashSet<Box<str>>) -> Vec<Box<str>> {
+    fn missing_required_steps(&self, completed: &HashSet<StepName>) -> Vec<Box<str>> {
         match self {
             Self::AllOf(steps) => steps
                 .iter()
-                .filter(|step| !completed.contains(*step))
+                .filter(|step| !completed.contains(step.as_ref()))
                 .cloned()
                 .collect(),
             Self::AnyOf(steps) => {
-                if steps.iter().any(|step| completed.contains(step)) {
+                if steps.iter().any(|step| completed.contains(step.as_ref())) {
                     Vec::new()
                 } else {
                     steps.iter().cloned().collect()
@@ -73,14 +75,14 @@ impl SuccessCriteria {
             } => {
                 let count = group_steps
                     .iter()
-                    .filter(|step| completed.contains(*step))
+                    .filter(|step| completed.contains(step.as_ref()))
                     .count();
                 if count >= *required_count {
                     Vec::new()
                 } else {
                     group_steps
                         .iter()
-                        .filter(|step| !completed.contains(*step))
+                        .filter(|step| !completed.contains(step.as_ref()))
                         .cloned()
                         .collect()
                 }
@@ -145,13 +147,13 @@ impl TerminalPolicy {
 
 #[derive(Clone, Debug)]
 struct SagaResolutionState {
-    started_steps: HashSet<Box<str>>,
-    acked_steps: HashSet<Box<str>>,
-    completed_steps: HashSet<Box<str>>,
-    failed_steps: HashSet<Box<str>>,
-    compensable_steps: Vec<Box<str>>,
+    started_steps: HashSet<StepName>,
+    acked_steps: HashSet<StepName>,
+    completed_steps: HashSet<StepName>,
+    failed_steps: HashSet<StepName>,
+    compensable_steps: Vec<StepName>,
     compensation_requested: bool,
-    pending_compensation_steps: HashSet<Box<str>>,
+    pending_compensation_steps: HashSet<StepName>,
     pending_failure: Option<SagaFailureDetails>,
     started_at_millis: u64,
     last_progress_at_millis: u64,
@@ -180,13 +182,27 @@ impl SagaResolutionState {
     }
 }
 
-#[derive(Debug)]
 pub struct TerminalResolver {
     policy: TerminalPolicy,
     states: HashMap<SagaId, SagaResolutionState>,
     terminal_latched_order: VecDeque<SagaId>,
     terminal_latched_set: HashSet<SagaId>,
     terminal_latch_retention: usize,
+    observer: Option<Arc<dyn SagaObserver>>,
+    compensation_strategy: CompensationStrategy,
+}
+
+impl std::fmt::Debug for TerminalResolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TerminalResolver")
+            .field("policy", &self.policy)
+            .field("states", &self.states)
+            .field("terminal_latched_order", &self.terminal_latched_order)
+            .field("terminal_latched_set", &self.terminal_latched_set)
+            .field("terminal_latch_retention", &self.terminal_latch_retention)
+            .field("observer_attached", &self.observer.is_some())
+            .finish()
+    }
 }
 
 impl TerminalResolver {
@@ -197,9 +213,24 @@ impl TerminalResolver {
             terminal_latched_order: VecDeque::new(),
             terminal_latched_set: HashSet::new(),
             terminal_latch_retention: terminal_latch_retention_limit(),
+            observer: None,
+            compensation_strategy: CompensationStrategy::default(),
         }
     }
 
+    /// Reports the steps left outstanding by each timeout to `observer`.
+    pub fn with_observer(mut self, observer: Arc<dyn SagaObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Chooses the steps of the compensation requests this resolver emits,
+    /// against the policy's `workflow_steps`.
+    pub fn with_compensation_strategy(mut self, strategy: CompensationStrategy) -> Self {
+        self.compensation_strategy = strategy;
+        self
+    }
+
     pub fn policy(&self) -> &TerminalPolicy {
         &self.policy
     }
@@ -212,7 +243,7 @@ impl TerminalResolver {
         self.poll_timeouts_at(SagaContext::now_millis())
     }
 
-    fn ingest_at(
+    pub(crate) fn ingest_at(
         &mut self,
         event: &SagaChoreographyEvent,
         now_millis: u64,
@@ -239,7 +270,23 @@ impl TerminalResolver {
         }
 
         match event {
-            SagaChoreographyEvent::SagaStarted { .. } => {}
+            SagaChoreographyEvent::SagaStarted { .. }
+            | SagaChoreographyEvent::ParticipantRecovered { .. }
+            | SagaChoreographyEvent::SagaStalled { .. }
+            | SagaChoreographyEvent::InitiatorHeartbeat { .. } => {}
+            SagaChoreographyEvent::InitiatorTakenOver {
+                context,
+                successor_id,
+            } => {
+                // The successor resolves the saga from here on.
+                tracing::info!(
+                    target: "core::saga",
+                    event = "saga_resolver_stood_down",
+                    saga_id = context.saga_id.get(),
+                    successor_id = successor_id.as_ref()
+                );
+                state.terminal_latched = true;
+            }
             SagaChoreographyEvent::StepStarted { context } => {
                 state.started_steps.insert(context.step_name.clone());
             }
@@ -280,6 +327,7 @@ impl TerminalResolver {
                 error_code,
                 error,
                 requires_compensation,
+                error_details,
             } => {
                 state.started_steps.insert(context.step_name.clone());
                 state.failed_steps.insert(context.step_name.clone());
@@ -296,14 +344,19 @@ impl TerminalResolver {
                     participant_id: participant_id.clone(),
                     error_code: error_code.clone(),
                     error_message: error.clone(),
+                    error_details: error_details.clone(),
                     at_millis: context.event_timestamp_millis,
                 };
 
                 if *requires_compensation {
                     state.pending_failure = Some(failure.clone());
                     if !state.compensation_requested {
-                        let steps_to_compensate: Vec<Box<str>> =
-                            state.compensable_steps.iter().rev().cloned().collect();
+                        let steps_to_compensate =
+                            self.compensation_strategy.select(&CompensationScope {
+                                failed_step: context.step_name.as_str(),
+                                completed_steps: &state.compensable_steps,
+                                workflow_steps: self.policy.workflow_steps,
+                            });
                         state.pending_compensation_steps =
                             steps_to_compensate.iter().cloned().collect();
                         state.compensation_requested = true;
@@ -364,6 +417,7 @@ impl TerminalResolver {
                 participant_id,
                 error,
                 is_ambiguous,
+                ..
             } => {
                 if *is_ambiguous {
                     out.push(SagaChoreographyEvent::SagaQuarantined {
@@ -382,15 +436,47 @@ impl TerminalResolver {
                 }
                 state.terminal_latched = true;
             }
+            SagaChoreographyEvent::CompensationRequested {
+                context,
+                failed_step,
+                reason,
+                steps_to_compensate,
+            } => {
+                // A request published by someone else, e.g. a group
+                // coordinator aborting this saga; our own requests were
+                // already accounted for when they were emitted.
+                if !state.compensation_requested {
+                    state.compensation_requested = true;
+                    state.pending_compensation_steps =
+                        steps_to_compensate.iter().cloned().collect();
+                    state.pending_failure = Some(SagaFailureDetails {
+                        step_name: failed_step.clone(),
+                        participant_id: "".into(),
+                        error_code: None,
+                        error_message: reason.clone(),
+                        error_details: Vec::new(),
+                        at_millis: context.event_timestamp_millis,
+                    });
+                    if state.pending_compensation_steps.is_empty() {
+                        out.push(SagaChoreographyEvent::SagaFailed {
+                            context: terminal_context(context),
+                            reason: reason.clone(),
+                            failure: state.pending_failure.clone(),
+                        });
+                        state.terminal_latched = true;
+                    }
+                }
+            }
             SagaChoreographyEvent::SagaCompleted { .. }
             | SagaChoreographyEvent::SagaFailed { .. }
             | SagaChoreographyEvent::SagaQuarantined { .. }
-            | SagaChoreographyEvent::CompensationRequested { .. }
             | SagaChoreographyEvent::CompensationStarted { .. } => {}
         }
 
         if !state.terminal_latched {
-            if let Some(timeout_event) = timeout_terminal_event(&self.policy, state, now_millis) {
+            if let Some(timeout_event) =
+                timeout_terminal_event(&self.policy, state, now_millis, self.observer.as_deref())
+            {
                 out.push(timeout_event);
                 state.terminal_latched = true;
             }
@@ -407,14 +493,16 @@ impl TerminalResolver {
         out
     }
 
-    fn poll_timeouts_at(&mut self, now_millis: u64) -> Vec<SagaChoreographyEvent> {
+    pub(crate) fn poll_timeouts_at(&mut self, now_millis: u64) -> Vec<SagaChoreographyEvent> {
         let mut out = Vec::new();
         let mut newly_latched = Vec::new();
         for (saga_id, state) in self.states.iter_mut() {
             if state.terminal_latched {
                 continue;
             }
-            if let Some(timeout_event) = timeout_terminal_event(&self.policy, state, now_millis) {
+            if let Some(timeout_event) =
+                timeout_terminal_event(&self.policy, state, now_millis, self.observer.as_deref())
+            {
                 state.terminal_latched = true;
                 newly_latched.push(*saga_id);
                 out.push(timeout_event);
@@ -474,6 +562,7 @@ fn is_progress_event(event: &SagaChoreographyEvent) -> bool {
             | SagaChoreographyEvent::CompensationStarted { .. }
             | SagaChoreographyEvent::CompensationCompleted { .. }
             | SagaChoreographyEvent::CompensationFailed { .. }
+            | SagaChoreographyEvent::ParticipantRecovered { .. }
     )
 }
 
@@ -515,7 +604,7 @@ fn push_non_empty(parts: &mut Vec<String>, key: &str, value: &str) {
     }
 }
 
-fn sorted_set_values(values: &HashSet<Box<str>>) -> String {
+fn sorted_set_values(values: &HashSet<StepName>) -> String {
     let mut sorted = values
         .iter()
         .map(|value| value.as_ref())
@@ -540,7 +629,7 @@ fn dependency_names(depends_on: WorkflowDependencySpec) -> Vec<&'static str> {
 
 fn missing_dependencies(
     depends_on: WorkflowDependencySpec,
-    completed_steps: &HashSet<Box<str>>,
+    completed_steps: &HashSet<StepName>,
 ) -> Vec<&'static str> {
     match depends_on {
         WorkflowDependencySpec::OnSagaStart => Vec::new(),
@@ -689,16 +778,45 @@ fn emit_timeout_diagnostic(
     );
 }
 
+/// Reports every started step that neither completed nor failed, or the
+/// last step heard from when none is outstanding.
+fn notify_step_timeouts(
+    observer: Option<&dyn SagaObserver>,
+    state: &SagaResolutionState,
+    elapsed_ms: u64,
+) {
+    let Some(observer) = observer else {
+        return;
+    };
+    let mut outstanding: Vec<&str> = state
+        .started_steps
+        .iter()
+        .filter(|step| {
+            !state.completed_steps.contains(*step) && !state.failed_steps.contains(*step)
+        })
+        .map(|step| step.as_ref())
+        .collect();
+    if outstanding.is_empty() {
+        outstanding.push(state.last_context.step_name.as_ref());
+    }
+    outstanding.sort_unstable();
+    for step in outstanding {
+        observer.on_step_timeout(&state.last_context, step, Duration::from_millis(elapsed_ms));
+    }
+}
+
 fn timeout_terminal_event(
     policy: &TerminalPolicy,
     state: &SagaResolutionState,
     now_millis: u64,
+    observer: Option<&dyn SagaObserver>,
 ) -> Option<SagaChoreographyEvent> {
     let elapsed_ms = now_millis.saturating_sub(state.started_at_millis);
     let overall_timeout_ms = policy.overall_timeout.as_millis() as u64;
     if elapsed_ms > overall_timeout_ms {
         let diagnostic = timeout_diagnostics(policy, state);
         emit_timeout_diagnostic(policy, state, "overall_timeout", &diagnostic);
+        notify_step_timeouts(observer, state, elapsed_ms);
         let reason = diagnostic.reason(
             format!("overall_timeout after {overall_timeout_ms}ms"),
             &policy.policy_id,
@@ -715,6 +833,7 @@ fn timeout_terminal_event(
     if stalled_ms > stalled_timeout_ms {
         let diagnostic = timeout_diagnostics(policy, state);
         emit_timeout_diagnostic(policy, state, "stalled_timeout", &diagnostic);
+        notify_step_timeouts(observer, state, stalled_ms);
         let reason = diagnostic.reason(
             format!("stalled_timeout after {stalled_timeout_ms}ms without progress"),
             &policy.policy_id,
@@ -732,10 +851,11 @@ fn timeout_terminal_event(
 #[cfg(test)]
 mod tests {
     use std::collections::HashSet;
+    use std::sync::Arc;
     use std::time::Duration;
 
     use crate::{
-        SagaChoreographyEvent, SagaContext, SagaId, SagaWorkflowStepContract,
+        RecordingObserver, SagaChoreographyEvent, SagaContext, SagaId, SagaWorkflowStepContract,
         WorkflowDependencySpec,
     };
 
@@ -800,6 +920,9 @@ mod tests {
             initiator_peer_id: [0; 32],
             saga_started_at_millis: SagaContext::now_millis(),
             event_timestamp_millis: SagaContext::now_millis(),
+            parent_saga_id: None,
+            workflow_version: crate::DEFAULT_WORKFLOW_VERSION,
+            logical_clock: 1,
         }
     }
 
@@ -821,6 +944,9 @@ mod tests {
             initiator_peer_id: [0; 32],
             saga_started_at_millis: started_at_millis,
             event_timestamp_millis,
+            parent_saga_id: None,
+            workflow_version: crate::DEFAULT_WORKFLOW_VERSION,
+            logical_clock: 1,
         }
     }
 
@@ -845,6 +971,7 @@ mod tests {
             output: vec![],
             saga_input: vec![],
             compensation_available: false,
+            completion_ratio: None,
         });
         assert!(out1.is_empty());
 
@@ -853,6 +980,7 @@ mod tests {
             output: vec![],
             saga_input: vec![],
             compensation_available: false,
+            completion_ratio: None,
         });
         assert!(matches!(
             out2.first(),
@@ -869,6 +997,7 @@ mod tests {
             error_code: Some("TEMP".into()),
             error: "try again".into(),
             requires_compensation: false,
+            error_details: Vec::new(),
         });
         assert!(matches!(
             out.first(),
@@ -876,6 +1005,29 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn external_compensation_request_fails_saga_once_compensated() {
+        let mut resolver = TerminalResolver::new(TerminalPolicy::order_lifecycle_default());
+        let out = resolver.ingest(&SagaChoreographyEvent::CompensationRequested {
+            context: ctx("risk_check"),
+            failed_step: "place_leg".into(),
+            reason: "group aborted".into(),
+            steps_to_compensate: vec!["risk_check".into()],
+        });
+        assert!(out.is_empty());
+        let out = resolver.ingest(&SagaChoreographyEvent::CompensationCompleted {
+            context: ctx("risk_check"),
+        });
+        let [SagaChoreographyEvent::SagaFailed {
+            failure: Some(failure),
+            ..
+        }] = out.as_slice()
+        else {
+            panic!("expected SagaFailed: {out:?}");
+        };
+        assert_eq!(failure.error_message.as_ref(), "group aborted");
+    }
+
     #[test]
     fn unauthorized_step_failure_is_ignored() {
         let mut only_steps = HashSet::new();
@@ -896,6 +1048,7 @@ mod tests {
             error_code: None,
             error: "no".into(),
             requires_compensation: false,
+            error_details: Vec::new(),
         });
         assert!(out.is_empty());
     }
@@ -985,6 +1138,7 @@ mod tests {
                 output: Vec::new(),
                 saga_input: Vec::new(),
                 compensation_available: false,
+                completion_ratio: None,
             },
             1_010,
         );
@@ -994,6 +1148,7 @@ mod tests {
                 output: Vec::new(),
                 saga_input: Vec::new(),
                 compensation_available: false,
+                completion_ratio: None,
             },
             1_020,
         );
@@ -1056,4 +1211,39 @@ mod tests {
             "started blocker must not also be reported as never started: {reason}"
         );
     }
+
+    #[test]
+    fn stalled_timeout_notifies_observer_of_started_steps() {
+        let observer = Arc::new(RecordingObserver::new());
+        let mut resolver = TerminalResolver::new(open_position_policy(Duration::from_millis(100)))
+            .with_observer(observer.clone());
+        let _ = resolver.ingest_at(
+            &SagaChoreographyEvent::SagaStarted {
+                context: ctx_at("risk_check", 12, 1_000, 1_000),
+                payload: Vec::new(),
+            },
+            1_000,
+        );
+        for step in ["risk_check", "positions_check"] {
+            let _ = resolver.ingest_at(
+                &SagaChoreographyEvent::StepStarted {
+                    context: ctx_at(step, 12, 1_000, 1_010),
+                },
+                1_010,
+            );
+        }
+        let _ = resolver.ingest_at(
+            &SagaChoreographyEvent::StepCompleted {
+                context: ctx_at("positions_check", 12, 1_000, 1_020),
+                output: Vec::new(),
+                saga_input: Vec::new(),
+                compensation_available: false,
+                completion_ratio: None,
+            },
+            1_020,
+        );
+
+        assert!(!resolver.poll_timeouts_at(1_121).is_empty());
+        observer.assert_snapshot("#1 order_lifecycle step_timeout step=risk_check");
+    }
 }
@@ -0,0 +1,361 @@
+//! Saga-scoped locks on named resources.
+//!
+//! Initiators that must not run two sagas against the same resource at once
+//! (for example two orders on one instrument) take a lock per resource key
+//! from a [`ResourceLockManager`]. Locks belong to a saga, are released when
+//! that saga completes or fails, and are written through a
+//! [`ResourceLockJournal`] so a restarted process still knows who holds what.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use icanact_core::local::EventSubscription;
+
+use crate::{SagaChoreographyBus, SagaChoreographyEvent, SagaContext, SagaId, SagaType};
+
+/// A lock on one resource held by one saga.
+#[derive(Clone, Debug, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct ResourceLock {
+    pub resource: Box<str>,
+    pub saga_id: SagaId,
+    pub saga_type: SagaType,
+    /// The Unix timestamp in milliseconds when the lock was taken.
+    pub acquired_at_millis: u64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ResourceLockError {
+    #[error("resource {resource} is held by saga {holder}")]
+    Held { resource: Box<str>, holder: u64 },
+    #[error("Storage error: {0}")]
+    Storage(Box<str>),
+}
+
+/// Durable record of held locks.
+///
+/// Implementations must be `Send + Sync + 'static` so one journal can back a
+/// manager shared across actors.
+pub trait ResourceLockJournal: Send + Sync + 'static {
+    fn record_acquired(&self, lock: &ResourceLock) -> Result<(), ResourceLockError>;
+
+    fn record_released(&self, resource: &str) -> Result<(), ResourceLockError>;
+
+    /// Lists every lock that was acquired and not released.
+    fn held_locks(&self) -> Result<Vec<ResourceLock>, ResourceLockError>;
+}
+
+impl<T> ResourceLockJournal for Arc<T>
+where
+    T: ResourceLockJournal + ?Sized,
+{
+    fn record_acquired(&self, lock: &ResourceLock) -> Result<(), ResourceLockError> {
+        (**self).record_acquired(lock)
+    }
+
+    fn record_released(&self, resource: &str) -> Result<(), ResourceLockError> {
+        (**self).record_released(resource)
+    }
+
+    fn held_locks(&self) -> Result<Vec<ResourceLock>, ResourceLockError> {
+        (**self).held_locks()
+    }
+}
+
+/// In-memory lock journal for tests and single-process deployments.
+#[derive(Default)]
+pub struct InMemoryResourceLockJournal {
+    locks: std::sync::RwLock<BTreeMap<Box<str>, ResourceLock>>,
+}
+
+impl InMemoryResourceLockJournal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ResourceLockJournal for InMemoryResourceLockJournal {
+    fn record_acquired(&self, lock: &ResourceLock) -> Result<(), ResourceLockError> {
+        let mut locks = self
+            .locks
+            .write()
+            .map_err(|e| ResourceLockError::Storage(e.to_string().into()))?;
+        locks.insert(lock.resource.clone(), lock.clone());
+        Ok(())
+    }
+
+    fn record_released(&self, resource: &str) -> Result<(), ResourceLockError> {
+        let mut locks = self
+            .locks
+            .write()
+            .map_err(|e| ResourceLockError::Storage(e.to_string().into()))?;
+        locks.remove(resource);
+        Ok(())
+    }
+
+    fn held_locks(&self) -> Result<Vec<ResourceLock>, ResourceLockError> {
+        let locks = self
+            .locks
+            .read()
+            .map_err(|e| ResourceLockError::Storage(e.to_string().into()))?;
+        Ok(locks.values().cloned().collect())
+    }
+}
+
+struct ResourceLockManagerInner {
+    locks: Mutex<BTreeMap<Box<str>, ResourceLock>>,
+    journal: Option<Arc<dyn ResourceLockJournal>>,
+}
+
+/// Grants resource locks to sagas. Cloning shares the same lock table.
+#[derive(Clone)]
+pub struct ResourceLockManager {
+    inner: Arc<ResourceLockManagerInner>,
+}
+
+impl Default for ResourceLockManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ResourceLockManager {
+    /// Creates a manager whose locks live only in memory.
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(ResourceLockManagerInner {
+                locks: Mutex::new(BTreeMap::new()),
+                journal: None,
+            }),
+        }
+    }
+
+    /// Creates a manager backed by `journal`, restoring the locks it holds.
+    pub fn with_journal<J>(journal: J) -> Result<Self, ResourceLockError>
+    where
+        J: ResourceLockJournal,
+    {
+        let locks = journal
+            .held_locks()?
+            .into_iter()
+            .map(|lock| (lock.resource.clone(), lock))
+            .collect();
+        Ok(Self {
+            inner: Arc::new(ResourceLockManagerInner {
+                locks: Mutex::new(locks),
+                journal: Some(Arc::new(journal)),
+            }),
+        })
+    }
+
+    /// Locks `resource` for the saga in `context`. Acquiring a lock the saga
+    /// already holds succeeds.
+    pub fn try_acquire(
+        &self,
+        context: &SagaContext,
+        resource: &str,
+    ) -> Result<(), ResourceLockError> {
+        self.try_acquire_all(context, &[resource])
+    }
+
+    /// Locks every resource in `resources` for the saga in `context`, or none
+    /// of them if any is held by another saga.
+    pub fn try_acquire_all(
+        &self,
+        context: &SagaContext,
+        resources: &[&str],
+    ) -> Result<(), ResourceLockError> {
+        let mut locks = self.lock_table();
+        for resource in resources {
+            if let Some(existing) = locks.get(*resource) {
+                if existing.saga_id != context.saga_id {
+                    return Err(ResourceLockError::Held {
+                        resource: (*resource).into(),
+                        holder: existing.saga_id.get(),
+                    });
+                }
+            }
+        }
+        let acquired_at_millis = SagaContext::now_millis();
+        for resource in resources {
+            if locks.contains_key(*resource) {
+                continue;
+            }
+            let lock = ResourceLock {
+                resource: (*resource).into(),
+                saga_id: context.saga_id,
+                saga_type: context.saga_type.clone(),
+                acquired_at_millis,
+            };
+            if let Some(journal) = &self.inner.journal {
+                journal.record_acquired(&lock)?;
+            }
+            locks.insert(lock.resource.clone(), lock);
+        }
+        Ok(())
+    }
+
+    /// Releases `resource` if `saga_id` holds it.
+    pub fn release(&self, saga_id: SagaId, resource: &str) -> Result<bool, ResourceLockError> {
+        let mut locks = self.lock_table();
+        if locks.get(resource).map(|lock| lock.saga_id) != Some(saga_id) {
+            return Ok(false);
+        }
+        if let Some(journal) = &self.inner.journal {
+            journal.record_released(resource)?;
+        }
+        locks.remove(resource);
+        Ok(true)
+    }
+
+    /// Releases every lock held by `saga_id` and returns the freed resources.
+    pub fn release_saga(&self, saga_id: SagaId) -> Result<Vec<Box<str>>, ResourceLockError> {
+        let mut locks = self.lock_table();
+        let held: Vec<Box<str>> = locks
+            .values()
+            .filter(|lock| lock.saga_id == saga_id)
+            .map(|lock| lock.resource.clone())
+            .collect();
+        for resource in &held {
+            if let Some(journal) = &self.inner.journal {
+                journal.record_released(resource)?;
+            }
+            locks.remove(resource);
+        }
+        Ok(held)
+    }
+
+    pub fn holder(&self, resource: &str) -> Option<SagaId> {
+        self.lock_table().get(resource).map(|lock| lock.saga_id)
+    }
+
+    pub fn is_locked(&self, resource: &str) -> bool {
+        self.holder(resource).is_some()
+    }
+
+    pub fn held_by(&self, saga_id: SagaId) -> Vec<Box<str>> {
+        self.lock_table()
+            .values()
+            .filter(|lock| lock.saga_id == saga_id)
+            .map(|lock| lock.resource.clone())
+            .collect()
+    }
+
+    pub fn locks(&self) -> Vec<ResourceLock> {
+        self.lock_table().values().cloned().collect()
+    }
+
+    /// Releases the locks of sagas that complete or fail. Quarantined sagas
+    /// keep their locks until an operator calls [`Self::release_saga`].
+    pub fn observe(&self, event: &SagaChoreographyEvent) {
+        if !matches!(
+            event,
+            SagaChoreographyEvent::SagaCompleted { .. } | SagaChoreographyEvent::SagaFailed { .. }
+        ) {
+            return;
+        }
+        let saga_id = event.context().saga_id;
+        match self.release_saga(saga_id) {
+            Ok(released) if !released.is_empty() => {
+                tracing::debug!(
+                    target: "core::saga",
+                    event = "saga_resource_locks_released",
+                    saga_id = saga_id.get(),
+                    released = released.len()
+                );
+            }
+            Ok(_) => {}
+            Err(err) => {
+                tracing::error!(
+                    target: "core::saga",
+                    event = "saga_resource_lock_release_failed",
+                    saga_id = saga_id.get(),
+                    error = %err
+                );
+            }
+        }
+    }
+
+    /// Subscribes the manager to terminal events of `saga_type`.
+    pub fn subscribe(&self, bus: &SagaChoreographyBus, saga_type: &str) -> EventSubscription {
+        let manager = self.clone();
+        bus.subscribe_saga_type_fn(saga_type, move |event| {
+            manager.observe(event);
+            true
+        })
+    }
+
+    fn lock_table(&self) -> std::sync::MutexGuard<'_, BTreeMap<Box<str>, ResourceLock>> {
+        self.inner
+            .locks
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl std::fmt::Debug for ResourceLockManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResourceLockManager")
+            .field("held", &self.lock_table().len())
+            .field("journaled", &self.inner.journal.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DeterministicContextBuilder;
+
+    fn ctx(saga_id: u64) -> SagaContext {
+        DeterministicContextBuilder::default()
+            .with_saga_id(saga_id)
+            .with_saga_type("order")
+            .build()
+    }
+
+    #[test]
+    fn acquire_all_is_all_or_nothing() {
+        let manager = ResourceLockManager::new();
+        manager
+            .try_acquire(&ctx(1), "BTC-USD")
+            .expect("free resource should lock");
+        manager
+            .try_acquire(&ctx(1), "BTC-USD")
+            .expect("holder should re-acquire");
+
+        let err = manager
+            .try_acquire_all(&ctx(2), &["ETH-USD", "BTC-USD"])
+            .expect_err("held resource should block the batch");
+        assert!(matches!(err, ResourceLockError::Held { holder: 1, .. }));
+        assert!(!manager.is_locked("ETH-USD"));
+
+        assert!(!manager.release(SagaId::new(2), "BTC-USD").unwrap());
+        assert!(manager.release(SagaId::new(1), "BTC-USD").unwrap());
+        assert!(manager.locks().is_empty());
+    }
+
+    #[test]
+    fn terminal_events_release_and_journal_restores_locks() {
+        let journal = Arc::new(InMemoryResourceLockJournal::new());
+        let manager =
+            ResourceLockManager::with_journal(Arc::clone(&journal)).expect("journal should load");
+        manager.try_acquire(&ctx(1), "BTC-USD").unwrap();
+        manager.try_acquire(&ctx(2), "ETH-USD").unwrap();
+
+        let restored =
+            ResourceLockManager::with_journal(Arc::clone(&journal)).expect("journal should load");
+        assert_eq!(restored.holder("ETH-USD"), Some(SagaId::new(2)));
+
+        restored.observe(&SagaChoreographyEvent::SagaQuarantined {
+            context: ctx(1),
+            reason: "ambiguous".into(),
+            step: "place_order".into(),
+            participant_id: "exchange".into(),
+        });
+        assert!(restored.is_locked("BTC-USD"), "quarantine keeps locks");
+
+        restored.observe(&SagaChoreographyEvent::SagaCompleted { context: ctx(2) });
+        assert!(!restored.is_locked("ETH-USD"));
+        assert_eq!(journal.held_locks().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,222 @@
+//! Ids for new sagas.
+//!
+//! A process-local counter starts over on restart and hands out the ids of
+//! sagas still in the journals. A [`SagaIdAllocator`] does not:
+//!
+//! - [`JournalSagaIdAllocator`] counts up and journals a high-water mark,
+//!   reserving ids in blocks so that most allocations do not write. After a
+//!   restart it resumes above the mark and above every saga the journal
+//!   holds; ids reserved but not handed out before the restart are skipped.
+//! - [`RandomSagaIdAllocator`] draws random 64-bit ids and needs no storage,
+//!   at the price of ordering and a (tiny) chance of collision.
+//!
+//! ```ignore
+//! let ids = Arc::new(JournalSagaIdAllocator::open(journal.clone())?);
+//! let initiator = SagaInitiator::new(bus, InMemoryDedupe::new()).with_saga_id_allocator(ids);
+//! let context = initiator.new_saga_context("order_lifecycle", "risk_check", peer_id)?;
+//! initiator.start_saga(context, payload)?;
+//! ```
+
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::{ParticipantEvent, ParticipantJournal, SagaContext, SagaId};
+
+/// Saga under which [`JournalSagaIdAllocator`] journals its high-water mark.
+/// No allocator hands it out.
+pub const SAGA_ID_ALLOCATOR_SAGA: SagaId = SagaId(0);
+
+/// Ids [`JournalSagaIdAllocator`] reserves per journal write by default.
+pub const DEFAULT_SAGA_ID_BLOCK: u64 = 1024;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SagaIdAllocatorError {
+    #[error("Storage error: {0}")]
+    Storage(Box<str>),
+    #[error("Saga id space exhausted")]
+    Exhausted,
+}
+
+/// Source of ids for new sagas.
+pub trait SagaIdAllocator: Send + Sync + 'static {
+    /// An id no other saga started through this allocator has had.
+    fn allocate(&self) -> Result<SagaId, SagaIdAllocatorError>;
+}
+
+impl<T> SagaIdAllocator for Arc<T>
+where
+    T: SagaIdAllocator + ?Sized,
+{
+    fn allocate(&self) -> Result<SagaId, SagaIdAllocatorError> {
+        (**self).allocate()
+    }
+}
+
+/// Monotonic saga ids whose high-water mark is kept in a journal.
+pub struct JournalSagaIdAllocator {
+    journal: Arc<dyn ParticipantJournal>,
+    block: u64,
+    range: Mutex<ReservedRange>,
+}
+
+struct ReservedRange {
+    next: u64,
+    reserved_through: u64,
+}
+
+impl JournalSagaIdAllocator {
+    /// Resumes above the mark journaled under [`SAGA_ID_ALLOCATOR_SAGA`] and
+    /// above every saga in `journal`.
+    pub fn open(journal: Arc<dyn ParticipantJournal>) -> Result<Self, SagaIdAllocatorError> {
+        let storage =
+            |err: crate::JournalError| SagaIdAllocatorError::Storage(err.to_string().into());
+        let mut high_water = journal
+            .read(SAGA_ID_ALLOCATOR_SAGA)
+            .map_err(storage)?
+            .iter()
+            .filter_map(|entry| match entry.event {
+                ParticipantEvent::SagaIdsReserved { through, .. } => Some(through),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0);
+        for saga_id in journal.list_sagas().map_err(storage)? {
+            high_water = high_water.max(saga_id.get());
+        }
+        let next = high_water
+            .checked_add(1)
+            .ok_or(SagaIdAllocatorError::Exhausted)?;
+        Ok(Self {
+            journal,
+            block: DEFAULT_SAGA_ID_BLOCK,
+            range: Mutex::new(ReservedRange {
+                next,
+                reserved_through: high_water,
+            }),
+        })
+    }
+
+    /// Ids reserved per journal write; at least one.
+    pub fn with_block_size(mut self, ids: u64) -> Self {
+        self.block = ids.max(1);
+        self
+    }
+
+    /// The id the next allocation returns.
+    pub fn peek(&self) -> SagaId {
+        SagaId::new(self.range().next)
+    }
+
+    fn range(&self) -> std::sync::MutexGuard<'_, ReservedRange> {
+        self.range
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl SagaIdAllocator for JournalSagaIdAllocator {
+    fn allocate(&self) -> Result<SagaId, SagaIdAllocatorError> {
+        let mut range = self.range();
+        if range.next > range.reserved_through {
+            let through = range.next.saturating_add(self.block - 1);
+            self.journal
+                .append(
+                    SAGA_ID_ALLOCATOR_SAGA,
+                    ParticipantEvent::SagaIdsReserved {
+                        through,
+                        reserved_at_millis: SagaContext::now_millis(),
+                    },
+                )
+                .map_err(|err| SagaIdAllocatorError::Storage(err.to_string().into()))?;
+            tracing::debug!(
+                target: "core::saga",
+                event = "saga_ids_reserved",
+                from = range.next,
+                through
+            );
+            range.reserved_through = through;
+        }
+        let saga_id = range.next;
+        range.next = saga_id
+            .checked_add(1)
+            .ok_or(SagaIdAllocatorError::Exhausted)?;
+        Ok(SagaId::new(saga_id))
+    }
+}
+
+impl std::fmt::Debug for JournalSagaIdAllocator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let range = self.range();
+        f.debug_struct("JournalSagaIdAllocator")
+            .field("next", &range.next)
+            .field("reserved_through", &range.reserved_through)
+            .finish()
+    }
+}
+
+/// Random 64-bit saga ids, seeded from the OS.
+#[derive(Debug, Default)]
+pub struct RandomSagaIdAllocator {
+    seed: std::collections::hash_map::RandomState,
+    drawn: AtomicU64,
+}
+
+impl RandomSagaIdAllocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SagaIdAllocator for RandomSagaIdAllocator {
+    fn allocate(&self) -> Result<SagaId, SagaIdAllocatorError> {
+        loop {
+            let draw = self.drawn.fetch_add(1, Ordering::Relaxed);
+            let saga_id = self.seed.hash_one((draw, SagaContext::now_millis()));
+            if saga_id != SAGA_ID_ALLOCATOR_SAGA.get() {
+                return Ok(SagaId::new(saga_id));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryJournal;
+
+    #[test]
+    fn journal_allocator_resumes_above_reserved_and_journaled_ids() {
+        let journal = Arc::new(InMemoryJournal::new());
+        // A saga started before the allocator was introduced.
+        journal
+            .append(
+                SagaId::new(40),
+                ParticipantEvent::StepTriggered {
+                    triggering_event: "saga_started".into(),
+                    triggered_at_millis: 1,
+                },
+            )
+            .unwrap();
+
+        let ids = JournalSagaIdAllocator::open(journal.clone())
+            .unwrap()
+            .with_block_size(10);
+        let first: Vec<u64> = (0..12).map(|_| ids.allocate().unwrap().get()).collect();
+        assert_eq!(first, (41..53).collect::<Vec<_>>());
+        assert_eq!(journal.read(SAGA_ID_ALLOCATOR_SAGA).unwrap().len(), 2);
+
+        // Restart: ids 53..=60 were reserved but never handed out.
+        let restarted = JournalSagaIdAllocator::open(journal.clone()).unwrap();
+        assert_eq!(restarted.allocate().unwrap(), SagaId::new(61));
+    }
+
+    #[test]
+    fn random_allocator_draws_distinct_ids() {
+        let ids = RandomSagaIdAllocator::new();
+        let drawn: std::collections::HashSet<SagaId> =
+            (0..1000).map(|_| ids.allocate().unwrap()).collect();
+        assert_eq!(drawn.len(), 1000);
+        assert!(!drawn.contains(&SAGA_ID_ALLOCATOR_SAGA));
+    }
+}
@@ -0,0 +1,109 @@
+//! Lookup of sagas by business fields of their start payload.
+//!
+//! Support questions arrive phrased in business terms: "which saga handled
+//! client order id `my_client-42`?". A [`SagaIndex`] answers them without
+//! scanning journals. The [`crate::SagaInitiator`] maintains it from payload
+//! extractors registered per field:
+//!
+//! ```ignore
+//! let index = Arc::new(InMemorySagaIndex::new());
+//! let initiator = SagaInitiator::new(bus, InMemoryDedupe::new())
+//!     .with_saga_index(index.clone())
+//!     .index_field("client_order_id", |_, payload| {
+//!         Some(decode_order(payload).client_order_id.into())
+//!     });
+//! // Later, from the support tooling:
+//! let sagas = index.find("client_order_id", "my_client-42")?;
+//! ```
+//!
+//! Entries are written once the bus admits the start. A value may point to
+//! several sagas, e.g. one per retry of the same order.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+
+use crate::SagaId;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SagaIndexError {
+    #[error("Storage error: {0}")]
+    Storage(Box<str>),
+}
+
+/// Business field and value to saga id index.
+pub trait SagaIndex: Send + Sync + 'static {
+    /// Records that `saga_id` has `value` for `field`. Indexing the same
+    /// entry twice is a no-op.
+    fn index(&self, saga_id: SagaId, field: &str, value: &str) -> Result<(), SagaIndexError>;
+
+    /// Sagas indexed with `value` for `field`, ordered by saga id.
+    fn find(&self, field: &str, value: &str) -> Result<Vec<SagaId>, SagaIndexError>;
+}
+
+impl<T> SagaIndex for Arc<T>
+where
+    T: SagaIndex + ?Sized,
+{
+    fn index(&self, saga_id: SagaId, field: &str, value: &str) -> Result<(), SagaIndexError> {
+        (**self).index(saga_id, field, value)
+    }
+
+    fn find(&self, field: &str, value: &str) -> Result<Vec<SagaId>, SagaIndexError> {
+        (**self).find(field, value)
+    }
+}
+
+type IndexMap = HashMap<(Box<str>, Box<str>), BTreeSet<SagaId>>;
+
+/// In-memory saga index.
+#[derive(Default)]
+pub struct InMemorySagaIndex {
+    entries: Mutex<IndexMap>,
+}
+
+impl InMemorySagaIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of distinct field/value pairs indexed.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, IndexMap> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl SagaIndex for InMemorySagaIndex {
+    fn index(&self, saga_id: SagaId, field: &str, value: &str) -> Result<(), SagaIndexError> {
+        self.lock()
+            .entry((field.into(), value.into()))
+            .or_default()
+            .insert(saga_id);
+        Ok(())
+    }
+
+    fn find(&self, field: &str, value: &str) -> Result<Vec<SagaId>, SagaIndexError> {
+        Ok(self
+            .lock()
+            .get(&(field.into(), value.into()))
+            .map(|sagas| sagas.iter().copied().collect())
+            .unwrap_or_default())
+    }
+}
+
+impl std::fmt::Debug for InMemorySagaIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InMemorySagaIndex")
+            .field("entries_len", &self.len())
+            .finish()
+    }
+}
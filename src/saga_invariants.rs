@@ -0,0 +1,516 @@
+//! Property-based invariant checks for sync participants.
+//!
+//! [`event_sequences`] generates deliveries of one saga run as a participant
+//! might see them on a real bus: duplicated events, upstream completions and
+//! compensation requests re-sent under new trace ids, reordering after the
+//! `SagaStarted`, and late copies after the terminal event.
+//! [`check_participant_invariants`] feeds one sequence through
+//! [`handle_saga_event_with_emit`] and checks that the participant
+//!
+//! - executes its step at most once,
+//! - only compensates a step it executed successfully,
+//! - journals every execution and compensation it starts, and
+//! - only emits events for the saga it was given.
+//!
+//! [`assert_participant_invariants`] runs both under proptest, so a user's own
+//! participant can be checked with a one-line test:
+//!
+//! ```rust,ignore
+//! #[test]
+//! fn risk_participant_upholds_saga_invariants() {
+//!     saga_invariants::assert_participant_invariants(256, || {
+//!         RiskParticipant::new(InMemoryJournal::new(), InMemoryDedupe::new())
+//!     });
+//! }
+//! ```
+//!
+//! A `SagaStarted` delivered after the saga's terminal event starts a new run
+//! of the same saga id, so generated sequences keep every `SagaStarted` ahead
+//! of the terminal event.
+
+use proptest::prelude::*;
+use proptest::sample::Index;
+use proptest::test_runner::{Config, TestCaseError, TestRunner};
+
+use crate::{
+    handle_saga_event_with_emit, CompensationError, DependencySpec, HasSagaParticipantSupport,
+    ParticipantEvent, ParticipantJournal, SagaChoreographyEvent, SagaContext, SagaId,
+    SagaParticipant, SagaParticipantSupport, SagaType, StepError, StepName, StepOutput,
+};
+
+const INVARIANT_SAGA_ID: u64 = 1;
+const INVARIANT_FAILED_STEP: &str = "invariant_failed_step";
+
+/// Shape of the generated saga run, derived from the participant under test.
+#[derive(Clone, Debug)]
+pub struct InvariantScenario {
+    pub saga_type: SagaType,
+    pub step_name: StepName,
+    /// Step named in the `SagaStarted` context.
+    pub first_step: StepName,
+    /// Steps whose completions are delivered to the participant.
+    pub upstream_steps: Vec<StepName>,
+}
+
+impl InvariantScenario {
+    /// Uses the participant's first saga type, step name and dependencies.
+    pub fn for_participant<P: SagaParticipant>(participant: &P) -> Self {
+        let step_name: StepName = participant.step_name().into();
+        let upstream_steps = participant.depends_on().steps().to_vec();
+        Self {
+            saga_type: participant
+                .saga_types()
+                .first()
+                .copied()
+                .unwrap_or_default()
+                .into(),
+            first_step: upstream_steps
+                .first()
+                .cloned()
+                .unwrap_or_else(|| step_name.clone()),
+            step_name,
+            upstream_steps,
+        }
+    }
+}
+
+/// Strategy producing delivery sequences of one saga run for `scenario`.
+pub fn event_sequences(scenario: &InvariantScenario) -> BoxedStrategy<Vec<SagaChoreographyEvent>> {
+    let start = SagaContext::start(
+        SagaId::new(INVARIANT_SAGA_ID),
+        scenario.saga_type.clone(),
+        scenario.first_step.clone(),
+        [0; 32],
+    );
+    let payload = b"invariant-input".to_vec();
+    let started = SagaChoreographyEvent::SagaStarted {
+        context: start.clone(),
+        payload: payload.clone(),
+    };
+    let mut body: Vec<SagaChoreographyEvent> = scenario
+        .upstream_steps
+        .iter()
+        .map(|step| SagaChoreographyEvent::StepCompleted {
+            context: start.next_step(step.clone()),
+            output: b"upstream-output".to_vec(),
+            saga_input: payload.clone(),
+            compensation_available: true,
+            completion_ratio: None,
+        })
+        .collect();
+    let mut steps_to_compensate = vec![scenario.step_name.clone()];
+    steps_to_compensate.extend(scenario.upstream_steps.iter().cloned());
+    body.push(SagaChoreographyEvent::CompensationRequested {
+        context: start.next_step(INVARIANT_FAILED_STEP.into()),
+        failed_step: INVARIANT_FAILED_STEP.into(),
+        reason: "invariant check".into(),
+        steps_to_compensate,
+    });
+    let terminals = [
+        SagaChoreographyEvent::SagaCompleted {
+            context: start.next_step(crate::TERMINAL_RESOLVER_STEP.into()),
+        },
+        SagaChoreographyEvent::SagaFailed {
+            context: start.next_step(crate::TERMINAL_RESOLVER_STEP.into()),
+            reason: "invariant check".into(),
+            failure: None,
+        },
+    ];
+    let compensation_index = body.len() - 1;
+
+    (
+        proptest::collection::vec((0usize..3, any::<bool>()), body.len()),
+        0usize..3,
+        any::<bool>(),
+        0usize..3,
+        proptest::collection::vec(any::<Index>(), 0..3),
+    )
+        .prop_flat_map(
+            move |(copies, start_duplicates, with_compensation, terminal, late)| {
+                let mut shuffled = vec![started.clone(); start_duplicates];
+                for (index, (event, (extra, resend))) in body.iter().zip(copies).enumerate() {
+                    if index == compensation_index && !with_compensation {
+                        continue;
+                    }
+                    shuffled.extend(std::iter::repeat_n(event.clone(), extra + 1));
+                    if resend {
+                        shuffled.push(resent(event));
+                    }
+                }
+                let mut tail = Vec::new();
+                if let Some(terminal) = terminals.get(terminal) {
+                    tail.push(terminal.clone());
+                    if !shuffled.is_empty() {
+                        tail.extend(late.iter().map(|index| index.get(&shuffled).clone()));
+                        tail.retain(|event| {
+                            !matches!(event, SagaChoreographyEvent::SagaStarted { .. })
+                        });
+                    }
+                }
+                let started = started.clone();
+                Just(shuffled).prop_shuffle().prop_map(move |shuffled| {
+                    let mut sequence = Vec::with_capacity(shuffled.len() + tail.len() + 1);
+                    sequence.push(started.clone());
+                    sequence.extend(shuffled);
+                    sequence.extend(tail.iter().cloned());
+                    sequence
+                })
+            },
+        )
+        .boxed()
+}
+
+/// The same logical event re-sent under a new trace id, so dedupe does not
+/// catch it.
+fn resent(event: &SagaChoreographyEvent) -> SagaChoreographyEvent {
+    let mut event = event.clone();
+    let context = match &mut event {
+        SagaChoreographyEvent::StepCompleted { context, .. }
+        | SagaChoreographyEvent::CompensationRequested { context, .. } => context,
+        _ => return event,
+    };
+    *context = context.retry();
+    event
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum InvariantViolation {
+    #[error("step executed {executions} times for one saga run (event {event_index})")]
+    DoubleExecution {
+        executions: usize,
+        event_index: usize,
+    },
+    #[error("step compensated without a successful execution (event {event_index})")]
+    CompensatedUnexecutedStep { event_index: usize },
+    #[error("{expected} missing from the journal (event {event_index})")]
+    MissingJournalTrail {
+        expected: &'static str,
+        event_index: usize,
+    },
+    #[error("emitted {event_type} for saga {emitted_saga_id} while handling saga {saga_id}")]
+    ForeignSagaEmitted {
+        event_type: &'static str,
+        saga_id: u64,
+        emitted_saga_id: u64,
+    },
+}
+
+/// Wraps the participant under test and records what the helpers asked of it.
+struct Probe<P> {
+    inner: P,
+    executions: usize,
+    executed_ok: bool,
+    compensations: usize,
+    compensated_unexecuted: bool,
+}
+
+impl<P> HasSagaParticipantSupport for Probe<P>
+where
+    P: HasSagaParticipantSupport,
+{
+    type Journal = P::Journal;
+    type Dedupe = P::Dedupe;
+
+    fn saga_support(&self) -> &SagaParticipantSupport<Self::Journal, Self::Dedupe> {
+        self.inner.saga_support()
+    }
+
+    fn saga_support_mut(&mut self) -> &mut SagaParticipantSupport<Self::Journal, Self::Dedupe> {
+        self.inner.saga_support_mut()
+    }
+}
+
+impl<P> SagaParticipant for Probe<P>
+where
+    P: SagaParticipant,
+{
+    type Error = P::Error;
+
+    fn step_name(&self) -> &str {
+        self.inner.step_name()
+    }
+
+    fn participant_id(&self) -> &str {
+        self.inner.participant_id()
+    }
+
+    fn saga_types(&self) -> &[&'static str] {
+        self.inner.saga_types()
+    }
+
+    fn matches_saga_type(&self, saga_type: &str) -> bool {
+        self.inner.matches_saga_type(saga_type)
+    }
+
+    fn execute_step(
+        &mut self,
+        context: &SagaContext,
+        input: &[u8],
+    ) -> Result<StepOutput, StepError> {
+        self.executions += 1;
+        let result = self.inner.execute_step(context, input);
+        self.executed_ok |= result.is_ok();
+        result
+    }
+
+    fn compensate_step(
+        &mut self,
+        context: &SagaContext,
+        compensation_data: &[u8],
+    ) -> Result<(), CompensationError> {
+        self.compensations += 1;
+        self.compensated_unexecuted |= !self.executed_ok;
+        self.inner.compensate_step(context, compensation_data)
+    }
+
+    fn on_saga_completed(&mut self, context: &SagaContext) {
+        self.inner.on_saga_completed(context);
+    }
+
+    fn on_saga_failed(&mut self, context: &SagaContext, reason: &str) {
+        self.inner.on_saga_failed(context, reason);
+    }
+
+    fn on_compensation_completed(&mut self, context: &SagaContext) {
+        self.inner.on_compensation_completed(context);
+    }
+
+    fn on_quarantined(&mut self, context: &SagaContext, reason: &str) {
+        self.inner.on_quarantined(context, reason);
+    }
+
+    fn depends_on(&self) -> DependencySpec {
+        self.inner.depends_on()
+    }
+}
+
+/// Feeds `events` to `participant` one at a time and checks the invariants
+/// after each delivery.
+pub fn check_participant_invariants<P>(
+    participant: P,
+    events: &[SagaChoreographyEvent],
+) -> Result<(), InvariantViolation>
+where
+    P: SagaParticipant + HasSagaParticipantSupport,
+{
+    let mut probe = Probe {
+        inner: participant,
+        executions: 0,
+        executed_ok: false,
+        compensations: 0,
+        compensated_unexecuted: false,
+    };
+    let mut terminal_seen = false;
+    for (event_index, event) in events.iter().enumerate() {
+        let saga_id = event.context().saga_id;
+        let mut emitted = Vec::new();
+        terminal_seen |= event.terminal_outcome().is_some();
+        handle_saga_event_with_emit(&mut probe, event.clone(), |next| emitted.push(next));
+
+        if let Some(foreign) = emitted
+            .iter()
+            .find(|next| next.context().saga_id != saga_id)
+        {
+            return Err(InvariantViolation::ForeignSagaEmitted {
+                event_type: foreign.event_type(),
+                saga_id: saga_id.get(),
+                emitted_saga_id: foreign.context().saga_id.get(),
+            });
+        }
+        if probe.executions > 1 {
+            return Err(InvariantViolation::DoubleExecution {
+                executions: probe.executions,
+                event_index,
+            });
+        }
+        if probe.compensated_unexecuted {
+            return Err(InvariantViolation::CompensatedUnexecutedStep { event_index });
+        }
+        // Terminal events prune the journal, so the trail is only checked
+        // while the saga is live.
+        if !terminal_seen {
+            check_journal_trail(&probe, saga_id, event_index)?;
+        }
+    }
+    Ok(())
+}
+
+fn check_journal_trail<P>(
+    probe: &Probe<P>,
+    saga_id: SagaId,
+    event_index: usize,
+) -> Result<(), InvariantViolation>
+where
+    P: HasSagaParticipantSupport,
+{
+    let entries = probe
+        .saga_support()
+        .journal
+        .read(saga_id)
+        .unwrap_or_default();
+    let journaled =
+        |wanted: fn(&ParticipantEvent) -> bool| entries.iter().any(|entry| wanted(&entry.event));
+    if probe.executions > 0
+        && !journaled(|event| matches!(event, ParticipantEvent::StepExecutionStarted { .. }))
+    {
+        return Err(InvariantViolation::MissingJournalTrail {
+            expected: "StepExecutionStarted",
+            event_index,
+        });
+    }
+    if probe.compensations > 0
+        && !journaled(|event| matches!(event, ParticipantEvent::CompensationStarted { .. }))
+    {
+        return Err(InvariantViolation::MissingJournalTrail {
+            expected: "CompensationStarted",
+            event_index,
+        });
+    }
+    Ok(())
+}
+
+/// Checks the invariants over `cases` generated sequences, building a fresh
+/// participant with `make` for each one.
+///
+/// # Panics
+///
+/// Panics with the violation and the shrunk event sequence when a case fails.
+#[track_caller]
+pub fn assert_participant_invariants<P, F>(cases: u32, make: F)
+where
+    P: SagaParticipant + HasSagaParticipantSupport,
+    F: Fn() -> P,
+{
+    let scenario = InvariantScenario::for_participant(&make());
+    let mut runner = TestRunner::new(Config {
+        cases,
+        failure_persistence: None,
+        ..Config::default()
+    });
+    let result = runner.run(&event_sequences(&scenario), |events| {
+        check_participant_invariants(make(), &events)
+            .map_err(|violation| TestCaseError::fail(violation.to_string()))
+    });
+    if let Err(err) = result {
+        panic!(
+            "saga invariant violated for step {} of {}: {err}",
+            scenario.step_name, scenario.saga_type
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryDedupe, InMemoryJournal};
+
+    struct Step {
+        saga: SagaParticipantSupport<InMemoryJournal, InMemoryDedupe>,
+        name: &'static str,
+        depends_on: DependencySpec,
+        fail: bool,
+    }
+
+    impl Step {
+        fn new(name: &'static str, depends_on: DependencySpec, fail: bool) -> Self {
+            Self {
+                saga: SagaParticipantSupport::new(InMemoryJournal::new(), InMemoryDedupe::new()),
+                name,
+                depends_on,
+                fail,
+            }
+        }
+    }
+
+    impl HasSagaParticipantSupport for Step {
+        type Journal = InMemoryJournal;
+        type Dedupe = InMemoryDedupe;
+
+        fn saga_support(&self) -> &SagaParticipantSupport<Self::Journal, Self::Dedupe> {
+            &self.saga
+        }
+
+        fn saga_support_mut(&mut self) -> &mut SagaParticipantSupport<Self::Journal, Self::Dedupe> {
+            &mut self.saga
+        }
+    }
+
+    impl SagaParticipant for Step {
+        type Error = String;
+
+        fn step_name(&self) -> &str {
+            self.name
+        }
+
+        fn saga_types(&self) -> &[&'static str] {
+            &["order_lifecycle"]
+        }
+
+        fn depends_on(&self) -> DependencySpec {
+            self.depends_on.clone()
+        }
+
+        fn execute_step(
+            &mut self,
+            _context: &SagaContext,
+            _input: &[u8],
+        ) -> Result<StepOutput, StepError> {
+            if self.fail {
+                return Err(StepError::require_compensation("rejected"));
+            }
+            Ok(StepOutput::Completed {
+                output: Vec::new(),
+                compensation_data: vec![1],
+            })
+        }
+
+        fn compensate_step(
+            &mut self,
+            _context: &SagaContext,
+            _compensation_data: &[u8],
+        ) -> Result<(), CompensationError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn helpers_uphold_invariants_for_start_and_dependent_steps() {
+        assert_participant_invariants(64, || {
+            Step::new("risk_check", DependencySpec::OnSagaStart, false)
+        });
+        assert_participant_invariants(64, || {
+            Step::new("place_order", DependencySpec::after("risk_check"), false)
+        });
+        assert_participant_invariants(64, || {
+            Step::new("place_order", DependencySpec::after("risk_check"), true)
+        });
+    }
+
+    #[test]
+    fn resent_saga_start_is_reported_as_double_execution() {
+        let start = SagaContext::start(
+            SagaId::new(INVARIANT_SAGA_ID),
+            "order_lifecycle".into(),
+            "risk_check".into(),
+            [0; 32],
+        );
+        let events = [
+            SagaChoreographyEvent::SagaStarted {
+                context: start.clone(),
+                payload: Vec::new(),
+            },
+            SagaChoreographyEvent::SagaStarted {
+                context: start.retry(),
+                payload: Vec::new(),
+            },
+        ];
+        let participant = Step::new("risk_check", DependencySpec::OnSagaStart, false);
+        assert_eq!(
+            check_participant_invariants(participant, &events),
+            Err(InvariantViolation::DoubleExecution {
+                executions: 2,
+                event_index: 1,
+            })
+        );
+    }
+}
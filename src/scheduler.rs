@@ -0,0 +1,600 @@
+//! Scheduled and delayed saga starts.
+//!
+//! [`SagaScheduler`] holds "start saga type X with payload P" requests that
+//! fire at a point in time, after a delay, or on a cron expression, and
+//! publishes their `SagaStarted` through
+//! [`SagaChoreographyBus::start_saga`](crate::SagaChoreographyBus::start_saga)
+//! when due. Pending schedules are written through a [`SagaScheduleJournal`]
+//! so they survive restart.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::chain::derived_saga_id;
+use crate::{
+    PeerId, SagaBusPublishError, SagaChoreographyBus, SagaContext, SagaId, SagaType, StepName,
+};
+
+/// When a schedule fires.
+#[derive(Clone, Debug, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub enum SagaScheduleTrigger {
+    /// Fire once at the given Unix timestamp in milliseconds.
+    At { at_millis: u64 },
+    /// Fire on every match of a five-field cron expression, evaluated in UTC.
+    Cron { expression: Box<str> },
+}
+
+/// A pending scheduled saga start.
+#[derive(Clone, Debug, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct SagaSchedule {
+    pub schedule_id: u64,
+    pub saga_type: SagaType,
+    /// First step of the target workflow contract.
+    pub first_step: StepName,
+    pub payload: Vec<u8>,
+    pub trigger: SagaScheduleTrigger,
+    /// The Unix timestamp in milliseconds of the next firing.
+    pub next_fire_at_millis: u64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SagaScheduleError {
+    #[error("invalid cron expression {expression:?}: {reason}")]
+    InvalidCron {
+        expression: Box<str>,
+        reason: Box<str>,
+    },
+    #[error("cron expression {0:?} never fires")]
+    NeverFires(Box<str>),
+    #[error("Storage error: {0}")]
+    Storage(Box<str>),
+}
+
+/// Durable record of pending schedules.
+///
+/// Implementations must be `Send + Sync + 'static` so the scheduler thread can
+/// own them.
+pub trait SagaScheduleJournal: Send + Sync + 'static {
+    /// Inserts or replaces a schedule.
+    fn save(&self, schedule: &SagaSchedule) -> Result<(), SagaScheduleError>;
+
+    fn remove(&self, schedule_id: u64) -> Result<(), SagaScheduleError>;
+
+    /// Lists pending schedules ordered by schedule id.
+    fn pending(&self) -> Result<Vec<SagaSchedule>, SagaScheduleError>;
+}
+
+impl<T> SagaScheduleJournal for Arc<T>
+where
+    T: SagaScheduleJournal + ?Sized,
+{
+    fn save(&self, schedule: &SagaSchedule) -> Result<(), SagaScheduleError> {
+        (**self).save(schedule)
+    }
+
+    fn remove(&self, schedule_id: u64) -> Result<(), SagaScheduleError> {
+        (**self).remove(schedule_id)
+    }
+
+    fn pending(&self) -> Result<Vec<SagaSchedule>, SagaScheduleError> {
+        (**self).pending()
+    }
+}
+
+/// In-memory schedule journal for tests and single-process deployments.
+#[derive(Default)]
+pub struct InMemorySagaScheduleJournal {
+    schedules: std::sync::RwLock<BTreeMap<u64, SagaSchedule>>,
+}
+
+impl InMemorySagaScheduleJournal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SagaScheduleJournal for InMemorySagaScheduleJournal {
+    fn save(&self, schedule: &SagaSchedule) -> Result<(), SagaScheduleError> {
+        let mut schedules = self
+            .schedules
+            .write()
+            .map_err(|e| SagaScheduleError::Storage(e.to_string().into()))?;
+        schedules.insert(schedule.schedule_id, schedule.clone());
+        Ok(())
+    }
+
+    fn remove(&self, schedule_id: u64) -> Result<(), SagaScheduleError> {
+        let mut schedules = self
+            .schedules
+            .write()
+            .map_err(|e| SagaScheduleError::Storage(e.to_string().into()))?;
+        schedules.remove(&schedule_id);
+        Ok(())
+    }
+
+    fn pending(&self) -> Result<Vec<SagaSchedule>, SagaScheduleError> {
+        let schedules = self
+            .schedules
+            .read()
+            .map_err(|e| SagaScheduleError::Storage(e.to_string().into()))?;
+        Ok(schedules.values().cloned().collect())
+    }
+}
+
+/// Parsed five-field cron expression: minute, hour, day of month, month and
+/// day of week (0 or 7 is Sunday). Fields accept `*`, values, `a-b` ranges,
+/// comma lists and `/n` steps.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    day_of_month_restricted: bool,
+    day_of_week_restricted: bool,
+}
+
+const CRON_SEARCH_LIMIT: usize = 1_000_000;
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<Self, SagaScheduleError> {
+        let invalid = |reason: String| SagaScheduleError::InvalidCron {
+            expression: expression.into(),
+            reason: reason.into(),
+        };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields.as_slice() else {
+            return Err(invalid(format!("expected 5 fields, got {}", fields.len())));
+        };
+        let mut days_of_week = parse_cron_field(day_of_week, 0, 7).map_err(invalid)?;
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week & !(1 << 7)) | 1;
+        }
+        Ok(Self {
+            minutes: parse_cron_field(minute, 0, 59).map_err(invalid)?,
+            hours: parse_cron_field(hour, 0, 23).map_err(invalid)?,
+            days_of_month: parse_cron_field(day_of_month, 1, 31).map_err(invalid)?,
+            months: parse_cron_field(month, 1, 12).map_err(invalid)?,
+            days_of_week,
+            day_of_month_restricted: *day_of_month != "*",
+            day_of_week_restricted: *day_of_week != "*",
+        })
+    }
+
+    /// First matching minute strictly after `after_millis`, in Unix
+    /// milliseconds.
+    pub fn next_after(&self, after_millis: u64) -> Option<u64> {
+        let mut minute_ts = after_millis / 60_000 + 1;
+        for _ in 0..CRON_SEARCH_LIMIT {
+            let days = minute_ts / 1440;
+            let (_, month, day) = civil_from_days(days);
+            let weekday = (days + 4) % 7;
+            if !bit(self.months, month) || !self.day_matches(day, weekday) {
+                minute_ts = (days + 1) * 1440;
+                continue;
+            }
+            if !bit(self.hours, (minute_ts % 1440) / 60) {
+                minute_ts = (minute_ts / 60 + 1) * 60;
+                continue;
+            }
+            if !bit(self.minutes, minute_ts % 60) {
+                minute_ts += 1;
+                continue;
+            }
+            return Some(minute_ts * 60_000);
+        }
+        None
+    }
+
+    fn day_matches(&self, day_of_month: u64, day_of_week: u64) -> bool {
+        let dom = bit(self.days_of_month, day_of_month);
+        let dow = bit(self.days_of_week, day_of_week);
+        match (self.day_of_month_restricted, self.day_of_week_restricted) {
+            (true, true) => dom || dow,
+            (true, false) => dom,
+            (false, true) => dow,
+            (false, false) => true,
+        }
+    }
+}
+
+fn bit(mask: u64, value: u64) -> bool {
+    mask & (1 << value) != 0
+}
+
+fn parse_cron_field(field: &str, min: u64, max: u64) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step = step
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| format!("invalid step in {part:?}"))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let parse_value = |raw: &str| {
+            raw.parse::<u64>()
+                .ok()
+                .filter(|value| (min..=max).contains(value))
+                .ok_or_else(|| format!("{raw:?} is outside {min}-{max}"))
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (parse_value(start)?, parse_value(end)?)
+        } else {
+            let start = parse_value(range)?;
+            (start, if part.contains('/') { max } else { start })
+        };
+        if start > end {
+            return Err(format!("empty range {part:?}"));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+/// Days since 1970-01-01 to (year, month, day), proleptic Gregorian.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+struct SagaSchedulerInner {
+    bus: SagaChoreographyBus,
+    schedules: Mutex<BTreeMap<u64, SagaSchedule>>,
+    journal: Option<Arc<dyn SagaScheduleJournal>>,
+    next_schedule_id: AtomicU64,
+    stopped: AtomicBool,
+}
+
+/// Fires scheduled saga starts on a bus. Cloning shares the same schedules.
+#[derive(Clone)]
+pub struct SagaScheduler {
+    inner: Arc<SagaSchedulerInner>,
+    initiator_peer_id: PeerId,
+}
+
+impl SagaScheduler {
+    /// Creates a scheduler whose schedules live only in memory.
+    pub fn new(bus: &SagaChoreographyBus) -> Self {
+        Self::from_parts(bus, BTreeMap::new(), None)
+    }
+
+    /// Creates a scheduler backed by `journal`, restoring its pending
+    /// schedules. Schedules that came due while the process was down fire on
+    /// the next [`Self::fire_due`].
+    pub fn with_journal<J>(bus: &SagaChoreographyBus, journal: J) -> Result<Self, SagaScheduleError>
+    where
+        J: SagaScheduleJournal,
+    {
+        let schedules = journal
+            .pending()?
+            .into_iter()
+            .map(|schedule| (schedule.schedule_id, schedule))
+            .collect();
+        Ok(Self::from_parts(bus, schedules, Some(Arc::new(journal))))
+    }
+
+    /// Sets the initiator peer id recorded on started sagas.
+    pub fn with_initiator_peer_id(mut self, initiator_peer_id: PeerId) -> Self {
+        self.initiator_peer_id = initiator_peer_id;
+        self
+    }
+
+    fn from_parts(
+        bus: &SagaChoreographyBus,
+        schedules: BTreeMap<u64, SagaSchedule>,
+        journal: Option<Arc<dyn SagaScheduleJournal>>,
+    ) -> Self {
+        let next_schedule_id = schedules.keys().next_back().map_or(1, |id| id + 1);
+        Self {
+            inner: Arc::new(SagaSchedulerInner {
+                bus: bus.clone(),
+                schedules: Mutex::new(schedules),
+                journal,
+                next_schedule_id: AtomicU64::new(next_schedule_id),
+                stopped: AtomicBool::new(false),
+            }),
+            initiator_peer_id: [0; 32],
+        }
+    }
+
+    /// Starts a saga once at `at`.
+    pub fn schedule_at(
+        &self,
+        saga_type: &str,
+        first_step: &str,
+        payload: Vec<u8>,
+        at: SystemTime,
+    ) -> Result<u64, SagaScheduleError> {
+        let at_millis = at
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        self.insert(
+            saga_type,
+            first_step,
+            payload,
+            SagaScheduleTrigger::At { at_millis },
+            at_millis,
+        )
+    }
+
+    /// Starts a saga once after `delay`.
+    pub fn schedule_after(
+        &self,
+        saga_type: &str,
+        first_step: &str,
+        payload: Vec<u8>,
+        delay: Duration,
+    ) -> Result<u64, SagaScheduleError> {
+        self.schedule_at(saga_type, first_step, payload, SystemTime::now() + delay)
+    }
+
+    /// Starts a saga on every match of `expression` (UTC).
+    pub fn schedule_cron(
+        &self,
+        saga_type: &str,
+        first_step: &str,
+        payload: Vec<u8>,
+        expression: &str,
+    ) -> Result<u64, SagaScheduleError> {
+        let next_fire_at_millis = CronSchedule::parse(expression)?
+            .next_after(SagaContext::now_millis())
+            .ok_or_else(|| SagaScheduleError::NeverFires(expression.into()))?;
+        self.insert(
+            saga_type,
+            first_step,
+            payload,
+            SagaScheduleTrigger::Cron {
+                expression: expression.into(),
+            },
+            next_fire_at_millis,
+        )
+    }
+
+    /// Drops a pending schedule. Returns whether it existed.
+    pub fn cancel(&self, schedule_id: u64) -> Result<bool, SagaScheduleError> {
+        let mut schedules = self.schedule_table();
+        if !schedules.contains_key(&schedule_id) {
+            return Ok(false);
+        }
+        if let Some(journal) = &self.inner.journal {
+            journal.remove(schedule_id)?;
+        }
+        schedules.remove(&schedule_id);
+        Ok(true)
+    }
+
+    pub fn schedules(&self) -> Vec<SagaSchedule> {
+        self.schedule_table().values().cloned().collect()
+    }
+
+    /// Starts every saga due at `now_millis` and returns how many were
+    /// published. One-shot schedules are dropped once started; cron schedules
+    /// advance to their next match, so firings missed while the process was
+    /// down collapse into one. Starts rejected by a concurrency limit stay
+    /// due and are retried on the next call.
+    pub fn fire_due(&self, now_millis: u64) -> usize {
+        let due: Vec<SagaSchedule> = self
+            .schedule_table()
+            .values()
+            .filter(|schedule| schedule.next_fire_at_millis <= now_millis)
+            .cloned()
+            .collect();
+        let mut started = 0;
+        for schedule in due {
+            let saga_id = derived_saga_id(
+                SagaId::new(schedule.schedule_id),
+                &format!("{}:{}", schedule.saga_type, schedule.next_fire_at_millis),
+            );
+            let context = SagaContext::start(
+                saga_id,
+                schedule.saga_type.clone(),
+                schedule.first_step.clone(),
+                self.initiator_peer_id,
+            );
+            match self.inner.bus.start_saga(context, schedule.payload.clone()) {
+                Ok(_) => started += 1,
+                Err(err @ SagaBusPublishError::AdmissionRejected { .. }) => {
+                    tracing::warn!(
+                        target: "core::saga",
+                        event = "saga_schedule_start_deferred",
+                        schedule_id = schedule.schedule_id,
+                        saga_type = schedule.saga_type.as_ref(),
+                        saga_id = saga_id.get(),
+                        error = ?err
+                    );
+                    continue;
+                }
+                Err(err) => {
+                    tracing::error!(
+                        target: "core::saga",
+                        event = "saga_schedule_start_failed",
+                        schedule_id = schedule.schedule_id,
+                        saga_type = schedule.saga_type.as_ref(),
+                        saga_id = saga_id.get(),
+                        error = ?err
+                    );
+                }
+            }
+            if let Err(err) = self.advance(schedule, now_millis) {
+                tracing::error!(
+                    target: "core::saga",
+                    event = "saga_schedule_journal_failed",
+                    error = %err
+                );
+            }
+        }
+        started
+    }
+
+    /// Runs [`Self::fire_due`] every `tick` on a background thread until
+    /// [`Self::stop`] is called.
+    pub fn spawn(&self, tick: Duration) -> Result<(), String> {
+        let scheduler = self.clone();
+        thread::Builder::new()
+            .name("saga-scheduler".to_string())
+            .spawn(move || {
+                while !scheduler.inner.stopped.load(Ordering::Acquire) {
+                    scheduler.fire_due(SagaContext::now_millis());
+                    thread::sleep(tick);
+                }
+            })
+            .map(|_| ())
+            .map_err(|err| format!("saga scheduler spawn failed: {err}"))
+    }
+
+    pub fn stop(&self) {
+        self.inner.stopped.store(true, Ordering::Release);
+    }
+
+    fn insert(
+        &self,
+        saga_type: &str,
+        first_step: &str,
+        payload: Vec<u8>,
+        trigger: SagaScheduleTrigger,
+        next_fire_at_millis: u64,
+    ) -> Result<u64, SagaScheduleError> {
+        let schedule_id = self.inner.next_schedule_id.fetch_add(1, Ordering::Relaxed);
+        let schedule = SagaSchedule {
+            schedule_id,
+            saga_type: saga_type.into(),
+            first_step: first_step.into(),
+            payload,
+            trigger,
+            next_fire_at_millis,
+        };
+        if let Some(journal) = &self.inner.journal {
+            journal.save(&schedule)?;
+        }
+        self.schedule_table().insert(schedule_id, schedule);
+        Ok(schedule_id)
+    }
+
+    fn advance(
+        &self,
+        mut schedule: SagaSchedule,
+        now_millis: u64,
+    ) -> Result<(), SagaScheduleError> {
+        let next = match &schedule.trigger {
+            SagaScheduleTrigger::At { .. } => None,
+            SagaScheduleTrigger::Cron { expression } => {
+                CronSchedule::parse(expression)?.next_after(now_millis)
+            }
+        };
+        let mut schedules = self.schedule_table();
+        match next {
+            Some(next_fire_at_millis) => {
+                schedule.next_fire_at_millis = next_fire_at_millis;
+                if let Some(journal) = &self.inner.journal {
+                    journal.save(&schedule)?;
+                }
+                schedules.insert(schedule.schedule_id, schedule);
+            }
+            None => {
+                if let Some(journal) = &self.inner.journal {
+                    journal.remove(schedule.schedule_id)?;
+                }
+                schedules.remove(&schedule.schedule_id);
+            }
+        }
+        Ok(())
+    }
+
+    fn schedule_table(&self) -> std::sync::MutexGuard<'_, BTreeMap<u64, SagaSchedule>> {
+        self.inner
+            .schedules
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl std::fmt::Debug for SagaScheduler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SagaScheduler")
+            .field("pending", &self.schedule_table().len())
+            .field("journaled", &self.inner.journal.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SagaChoreographyEvent;
+
+    // 2024-03-01T00:00:00Z, a Friday.
+    const MARCH_1_2024: u64 = 1_709_251_200_000;
+
+    #[test]
+    fn cron_finds_next_weekday_close() {
+        let cron = CronSchedule::parse("55 20 * * 1-5").expect("cron should parse");
+        let next = cron.next_after(MARCH_1_2024).expect("cron should fire");
+        assert_eq!(next, MARCH_1_2024 + (20 * 60 + 55) * 60_000);
+
+        let after_friday_close = cron.next_after(next).expect("cron should fire");
+        let monday = MARCH_1_2024 + 3 * 86_400_000;
+        assert_eq!(after_friday_close, monday + (20 * 60 + 55) * 60_000);
+
+        assert!(CronSchedule::parse("61 * * * *").is_err());
+        assert!(CronSchedule::parse("* * *").is_err());
+        assert_eq!(
+            CronSchedule::parse("0 0 31 2 *").unwrap().next_after(0),
+            None
+        );
+    }
+
+    #[test]
+    fn due_schedules_start_once_and_survive_restart() {
+        let bus = SagaChoreographyBus::new();
+        let started = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&started);
+        let _sub = bus.subscribe_saga_type_fn("flatten_positions", move |event| {
+            // No contract is registered, so the bus fails each start
+            // immediately; the failure still carries the started saga id.
+            if let SagaChoreographyEvent::SagaFailed { context, .. } = event {
+                seen.lock().unwrap().push(context.saga_id);
+            }
+            true
+        });
+        let journal = Arc::new(InMemorySagaScheduleJournal::new());
+        let scheduler =
+            SagaScheduler::with_journal(&bus, Arc::clone(&journal)).expect("journal should load");
+        let at = UNIX_EPOCH + Duration::from_millis(MARCH_1_2024);
+        scheduler
+            .schedule_at("flatten_positions", "close_all", vec![7], at)
+            .expect("schedule should persist");
+
+        let restored =
+            SagaScheduler::with_journal(&bus, Arc::clone(&journal)).expect("journal should load");
+        assert_eq!(restored.fire_due(MARCH_1_2024 - 1), 0);
+        assert_eq!(restored.fire_due(MARCH_1_2024), 1);
+        assert_eq!(restored.fire_due(MARCH_1_2024 + 1), 0);
+
+        assert!(journal.pending().unwrap().is_empty());
+        let expected =
+            derived_saga_id(SagaId::new(1), &format!("flatten_positions:{MARCH_1_2024}"));
+        assert_eq!(*started.lock().unwrap(), vec![expected]);
+    }
+}
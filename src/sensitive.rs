@@ -0,0 +1,234 @@
+//! Encryption and redaction of sensitive payloads.
+//!
+//! Compensation data often carries order details (account, size, price) that
+//! should not sit in participant state, quarantine records or journals in
+//! clear text. A participant with a [`PayloadCipher`] attached
+//! ([`crate::SagaParticipantSupport::with_compensation_cipher`]) seals the
+//! compensation data of every completed step into a [`SensitivePayload`] and
+//! opens it again right before `compensate_step`. Quarantine records keep the
+//! sealed bytes; operator retries open them with
+//! [`SensitivePayload::open`].
+//!
+//! Independently of encryption, `Debug` output of [`StepOutput`],
+//! [`ParticipantEvent`] and [`QuarantinedSaga`] only shows the length of the
+//! compensation data.
+//!
+//! The key stays with the caller: implement [`PayloadCipher`] over a KMS or
+//! HSM, or enable the `encryption` feature for [`ChaCha20Poly1305Cipher`].
+//!
+//! [`StepOutput`]: crate::StepOutput
+//! [`ParticipantEvent`]: crate::ParticipantEvent
+//! [`QuarantinedSaga`]: crate::QuarantinedSaga
+
+#[derive(Debug, thiserror::Error)]
+pub enum SensitivePayloadError {
+    #[error("payload encryption failed: {0}")]
+    Encrypt(Box<str>),
+    #[error("payload decryption failed: {0}")]
+    Decrypt(Box<str>),
+}
+
+/// Encrypts and decrypts sensitive payloads with a caller-held key.
+///
+/// `encrypt` output must carry whatever `decrypt` needs besides the key,
+/// such as the nonce.
+pub trait PayloadCipher: Send + Sync + 'static {
+    fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, SensitivePayloadError>;
+
+    fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, SensitivePayloadError>;
+}
+
+impl<T> PayloadCipher for std::sync::Arc<T>
+where
+    T: PayloadCipher + ?Sized,
+{
+    fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, SensitivePayloadError> {
+        (**self).encrypt(plaintext)
+    }
+
+    fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, SensitivePayloadError> {
+        (**self).decrypt(ciphertext)
+    }
+}
+
+/// Encrypted payload bytes that never show up in `Debug` output.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SensitivePayload {
+    sealed: Vec<u8>,
+}
+
+impl SensitivePayload {
+    pub fn seal(
+        cipher: &dyn PayloadCipher,
+        plaintext: &[u8],
+    ) -> Result<Self, SensitivePayloadError> {
+        Ok(Self {
+            sealed: cipher.encrypt(plaintext)?,
+        })
+    }
+
+    /// Wraps bytes produced by [`into_sealed`](Self::into_sealed), e.g. the
+    /// compensation data held by a quarantine record.
+    pub fn from_sealed(sealed: Vec<u8>) -> Self {
+        Self { sealed }
+    }
+
+    pub fn open(&self, cipher: &dyn PayloadCipher) -> Result<Vec<u8>, SensitivePayloadError> {
+        cipher.decrypt(&self.sealed)
+    }
+
+    pub fn as_sealed(&self) -> &[u8] {
+        &self.sealed
+    }
+
+    pub fn into_sealed(self) -> Vec<u8> {
+        self.sealed
+    }
+}
+
+impl std::fmt::Debug for SensitivePayload {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SensitivePayload")
+            .field(&Redacted(&self.sealed))
+            .finish()
+    }
+}
+
+/// `Debug` stand-in for bytes that must not be printed.
+pub(crate) struct Redacted<'a>(pub(crate) &'a [u8]);
+
+impl std::fmt::Debug for Redacted<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<redacted {} bytes>", self.0.len())
+    }
+}
+
+/// ChaCha20-Poly1305 with a random nonce per payload, stored in front of the
+/// ciphertext.
+#[cfg(feature = "encryption")]
+pub struct ChaCha20Poly1305Cipher {
+    cipher: chacha20poly1305::ChaCha20Poly1305,
+}
+
+#[cfg(feature = "encryption")]
+impl ChaCha20Poly1305Cipher {
+    const NONCE_LEN: usize = 12;
+
+    pub fn new(key: &[u8; 32]) -> Self {
+        use chacha20poly1305::KeyInit;
+
+        Self {
+            cipher: chacha20poly1305::ChaCha20Poly1305::new(key.into()),
+        }
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl PayloadCipher for ChaCha20Poly1305Cipher {
+    fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, SensitivePayloadError> {
+        use chacha20poly1305::aead::{Aead, AeadCore, OsRng};
+
+        let nonce = chacha20poly1305::ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|err| SensitivePayloadError::Encrypt(err.to_string().into()))?;
+        let mut sealed = Vec::with_capacity(Self::NONCE_LEN + ciphertext.len());
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, SensitivePayloadError> {
+        use chacha20poly1305::aead::Aead;
+
+        if ciphertext.len() < Self::NONCE_LEN {
+            return Err(SensitivePayloadError::Decrypt("payload too short".into()));
+        }
+        let (nonce, ciphertext) = ciphertext.split_at(Self::NONCE_LEN);
+        self.cipher
+            .decrypt(nonce.into(), ciphertext)
+            .map_err(|err| SensitivePayloadError::Decrypt(err.to_string().into()))
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl std::fmt::Debug for ChaCha20Poly1305Cipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChaCha20Poly1305Cipher")
+            .finish_non_exhaustive()
+    }
+}
+
+/// Seals compensation data through the participant's cipher, if any. Empty
+/// data stays empty so `compensation_available` keeps its meaning.
+pub(crate) fn seal_compensation_data(
+    cipher: Option<&dyn PayloadCipher>,
+    data: Vec<u8>,
+) -> Result<Vec<u8>, SensitivePayloadError> {
+    match cipher {
+        Some(cipher) if !data.is_empty() => {
+            Ok(SensitivePayload::seal(cipher, &data)?.into_sealed())
+        }
+        _ => Ok(data),
+    }
+}
+
+pub(crate) fn open_compensation_data(
+    cipher: Option<&dyn PayloadCipher>,
+    data: &[u8],
+) -> Result<Vec<u8>, SensitivePayloadError> {
+    match cipher {
+        Some(cipher) if !data.is_empty() => cipher.decrypt(data),
+        _ => Ok(data.to_vec()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// XOR "cipher" so the tests do not depend on the `encryption` feature.
+    struct XorCipher(u8);
+
+    impl PayloadCipher for XorCipher {
+        fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, SensitivePayloadError> {
+            Ok(plaintext.iter().map(|byte| byte ^ self.0).collect())
+        }
+
+        fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, SensitivePayloadError> {
+            self.encrypt(ciphertext)
+        }
+    }
+
+    #[test]
+    fn sealed_payload_round_trips_and_redacts_debug() {
+        let cipher = XorCipher(0x5a);
+        let sealed = SensitivePayload::seal(&cipher, b"acct=42").unwrap();
+
+        assert_ne!(sealed.as_sealed(), b"acct=42");
+        assert_eq!(sealed.open(&cipher).unwrap(), b"acct=42");
+        assert_eq!(
+            format!("{sealed:?}"),
+            "SensitivePayload(<redacted 7 bytes>)"
+        );
+        let output = crate::StepOutput::Completed {
+            output: vec![1],
+            compensation_data: b"acct=42".to_vec(),
+        };
+        assert!(format!("{output:?}").contains("compensation_data: <redacted 7 bytes>"));
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn chacha_cipher_rejects_wrong_key() {
+        let sealed =
+            SensitivePayload::seal(&ChaCha20Poly1305Cipher::new(&[1; 32]), b"acct=42").unwrap();
+
+        assert_eq!(
+            sealed.open(&ChaCha20Poly1305Cipher::new(&[1; 32])).unwrap(),
+            b"acct=42"
+        );
+        assert!(sealed.open(&ChaCha20Poly1305Cipher::new(&[2; 32])).is_err());
+    }
+}
@@ -0,0 +1,300 @@
+//! Shadow execution of a rewritten participant next to the primary.
+//!
+//! [`ShadowParticipant`] wraps a candidate implementation of a step and is
+//! fed the same saga events as the primary participant, e.g. from its own
+//! bus subscription. The candidate runs its step against its own stores, and
+//! whatever it emits is kept instead of published. When the primary's
+//! outcome for the same step arrives on the bus, the two are compared and
+//! the result is appended to the comparison log, so a rewrite can be
+//! canaried on production traffic before cutover.
+//!
+//! The wrapper detaches the candidate's bus, effect dispatcher, step leases,
+//! quarantine manager and dead-letter store; its journal and dedupe store
+//! must not be shared with the primary. Anything else the candidate's step
+//! calls is up to its implementation and should be a sandbox too.
+
+use std::collections::HashMap;
+
+use crate::{
+    handle_saga_event_with_emit, SagaChoreographyEvent, SagaId, SagaParticipant, SagaStateExt,
+};
+
+/// Step outcome as seen on the bus, without timestamps.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShadowOutcome {
+    Completed {
+        output: Vec<u8>,
+    },
+    Failed {
+        error: Box<str>,
+        requires_compensation: bool,
+    },
+    Compensated,
+    CompensationFailed {
+        error: Box<str>,
+        is_ambiguous: bool,
+    },
+}
+
+impl ShadowOutcome {
+    fn from_event(event: &SagaChoreographyEvent) -> Option<Self> {
+        match event {
+            SagaChoreographyEvent::StepCompleted { output, .. } => Some(Self::Completed {
+                output: output.clone(),
+            }),
+            SagaChoreographyEvent::StepFailed {
+                error,
+                requires_compensation,
+                ..
+            } => Some(Self::Failed {
+                error: error.clone(),
+                requires_compensation: *requires_compensation,
+            }),
+            SagaChoreographyEvent::CompensationCompleted { .. } => Some(Self::Compensated),
+            SagaChoreographyEvent::CompensationFailed {
+                error,
+                is_ambiguous,
+                ..
+            } => Some(Self::CompensationFailed {
+                error: error.clone(),
+                is_ambiguous: *is_ambiguous,
+            }),
+            _ => None,
+        }
+    }
+
+    fn is_compensation(&self) -> bool {
+        matches!(self, Self::Compensated | Self::CompensationFailed { .. })
+    }
+}
+
+/// One entry of the comparison log.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShadowComparison {
+    pub saga_id: SagaId,
+    pub primary: ShadowOutcome,
+    pub shadow: ShadowOutcome,
+}
+
+impl ShadowComparison {
+    pub fn matches(&self) -> bool {
+        self.primary == self.shadow
+    }
+}
+
+#[derive(Default)]
+struct PendingOutcomes {
+    primary: Option<ShadowOutcome>,
+    shadow: Option<ShadowOutcome>,
+}
+
+/// Runs `P` in shadow of the primary participant for the same step.
+pub struct ShadowParticipant<P> {
+    candidate: P,
+    /// Keyed by saga and whether the outcome is a compensation result.
+    pending: HashMap<(SagaId, bool), PendingOutcomes>,
+    comparisons: Vec<ShadowComparison>,
+}
+
+impl<P> ShadowParticipant<P>
+where
+    P: SagaParticipant + SagaStateExt,
+{
+    pub fn new(mut candidate: P) -> Self {
+        let support = candidate.saga_support_mut();
+        support.bus = None;
+        support.effects = None;
+        support.step_leases = None;
+        support.quarantine = None;
+        support.dead_letters = None;
+        Self {
+            candidate,
+            pending: HashMap::new(),
+            comparisons: Vec::new(),
+        }
+    }
+
+    pub fn candidate(&self) -> &P {
+        &self.candidate
+    }
+
+    /// Feeds one bus event to the shadow. The primary's outcomes for this
+    /// step are recorded for comparison; everything else is handled by the
+    /// candidate, whose emitted events are dropped after their outcome is
+    /// recorded.
+    pub fn handle(&mut self, event: SagaChoreographyEvent) {
+        let saga_id = event.context().saga_id;
+        if event.context().step_name.as_ref() == self.candidate.step_name() {
+            if let Some(outcome) = ShadowOutcome::from_event(&event) {
+                self.record(saga_id, outcome, true);
+                return;
+            }
+        }
+
+        let mut emitted = Vec::new();
+        handle_saga_event_with_emit(&mut self.candidate, event, |event| emitted.push(event));
+        for event in emitted {
+            if let Some(outcome) = ShadowOutcome::from_event(&event) {
+                self.record(event.context().saga_id, outcome, false);
+            }
+        }
+    }
+
+    /// Comparisons made so far, oldest first.
+    pub fn comparisons(&self) -> &[ShadowComparison] {
+        &self.comparisons
+    }
+
+    pub fn take_comparisons(&mut self) -> Vec<ShadowComparison> {
+        std::mem::take(&mut self.comparisons)
+    }
+
+    /// Sagas where only one side has reported an outcome yet.
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    fn record(&mut self, saga_id: SagaId, outcome: ShadowOutcome, from_primary: bool) {
+        let key = (saga_id, outcome.is_compensation());
+        let pending = self.pending.entry(key).or_default();
+        if from_primary {
+            pending.primary = Some(outcome);
+        } else {
+            pending.shadow = Some(outcome);
+        }
+        let (Some(primary), Some(shadow)) = (&pending.primary, &pending.shadow) else {
+            return;
+        };
+        let comparison = ShadowComparison {
+            saga_id,
+            primary: primary.clone(),
+            shadow: shadow.clone(),
+        };
+        self.pending.remove(&key);
+        if !comparison.matches() {
+            tracing::warn!(
+                target: "core::saga",
+                event = "saga_shadow_mismatch",
+                saga_id = saga_id.get(),
+                step_name = self.candidate.step_name(),
+                primary = ?comparison.primary,
+                shadow = ?comparison.shadow
+            );
+        }
+        self.comparisons.push(comparison);
+    }
+}
+
+impl<P> std::fmt::Debug for ShadowParticipant<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShadowParticipant")
+            .field("pending_len", &self.pending.len())
+            .field("comparisons_len", &self.comparisons.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        saga_started, CompensationError, DeterministicContextBuilder, HasSagaParticipantSupport,
+        InMemoryDedupe, InMemoryJournal, SagaChoreographyBus, SagaContext, SagaParticipantSupport,
+        StepError, StepOutput,
+    };
+
+    struct Pricer {
+        saga: SagaParticipantSupport<InMemoryJournal, InMemoryDedupe>,
+        markup: u8,
+    }
+
+    impl HasSagaParticipantSupport for Pricer {
+        type Journal = InMemoryJournal;
+        type Dedupe = InMemoryDedupe;
+
+        fn saga_support(&self) -> &SagaParticipantSupport<Self::Journal, Self::Dedupe> {
+            &self.saga
+        }
+
+        fn saga_support_mut(&mut self) -> &mut SagaParticipantSupport<Self::Journal, Self::Dedupe> {
+            &mut self.saga
+        }
+    }
+
+    impl SagaParticipant for Pricer {
+        type Error = String;
+
+        fn step_name(&self) -> &str {
+            "price_order"
+        }
+
+        fn saga_types(&self) -> &[&'static str] {
+            &["order_lifecycle"]
+        }
+
+        fn execute_step(
+            &mut self,
+            _context: &SagaContext,
+            input: &[u8],
+        ) -> Result<StepOutput, StepError> {
+            Ok(StepOutput::Completed {
+                output: input.iter().map(|byte| byte + self.markup).collect(),
+                compensation_data: Vec::new(),
+            })
+        }
+
+        fn compensate_step(
+            &mut self,
+            _context: &SagaContext,
+            _compensation_data: &[u8],
+        ) -> Result<(), CompensationError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn shadow_compares_candidate_with_primary_without_publishing() {
+        let bus = SagaChoreographyBus::new();
+        let published = std::sync::Arc::new(std::sync::Mutex::new(0));
+        let count = std::sync::Arc::clone(&published);
+        let _sub = bus.subscribe_saga_type_fn("order_lifecycle", move |_| {
+            *count.lock().unwrap() += 1;
+            true
+        });
+        let mut primary = Pricer {
+            saga: SagaParticipantSupport::new(InMemoryJournal::new(), InMemoryDedupe::new()),
+            markup: 1,
+        };
+        let mut candidate = Pricer {
+            saga: SagaParticipantSupport::new(InMemoryJournal::new(), InMemoryDedupe::new()),
+            markup: 2,
+        };
+        candidate.saga.attach_bus(bus.clone());
+        let mut shadow = ShadowParticipant::new(candidate);
+
+        let started = saga_started(DeterministicContextBuilder::default().build(), vec![10]);
+        let mut primary_events = Vec::new();
+        handle_saga_event_with_emit(&mut primary, started.clone(), |event| {
+            primary_events.push(event)
+        });
+        shadow.handle(started);
+        for event in primary_events {
+            shadow.handle(event);
+        }
+
+        assert_eq!(*published.lock().unwrap(), 0);
+        assert_eq!(shadow.pending_len(), 0);
+        let [comparison] = shadow.comparisons() else {
+            panic!("expected one comparison: {:?}", shadow.comparisons());
+        };
+        assert!(!comparison.matches());
+        assert_eq!(
+            comparison.primary,
+            ShadowOutcome::Completed { output: vec![11] }
+        );
+        assert_eq!(
+            comparison.shadow,
+            ShadowOutcome::Completed { output: vec![12] }
+        );
+    }
+}
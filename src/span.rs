@@ -0,0 +1,94 @@
+//! Tracing spans carrying a saga's identity.
+//!
+//! The helper wrappers run `execute_step` and `compensate_step` inside
+//! [`SagaSpanExt::step_span`], so whatever step code logs is correlated with
+//! the saga without repeating its fields:
+//!
+//! ```ignore
+//! fn execute_step(&mut self, ctx: &SagaContext, input: &[u8]) -> Result<StepOutput, StepError> {
+//!     // Logged as `saga_step{saga_id=7 saga_type=order_lifecycle step=place_order ...}`.
+//!     tracing::info!(bytes = input.len(), "placing order");
+//!     ...
+//! }
+//! ```
+//!
+//! Spans do not follow work handed to other tasks or threads; enter
+//! `ctx.span()` there (or `.instrument(ctx.span())` a spawned future).
+
+use crate::SagaContext;
+
+/// Builds tracing spans pre-populated with a [`SagaContext`]'s identity.
+pub trait SagaSpanExt {
+    /// `saga_step` span for the context's own step.
+    fn span(&self) -> tracing::Span;
+
+    /// `saga_step` span for `step` handling the context, which names the
+    /// step that emitted it.
+    fn step_span(&self, step: &str) -> tracing::Span;
+}
+
+impl SagaSpanExt for SagaContext {
+    fn span(&self) -> tracing::Span {
+        self.step_span(self.step_name.as_ref())
+    }
+
+    fn step_span(&self, step: &str) -> tracing::Span {
+        tracing::info_span!(
+            target: "core::saga",
+            "saga_step",
+            saga_id = self.saga_id.get(),
+            saga_type = %self.saga_type,
+            step = %step,
+            attempt = self.attempt,
+            correlation_id = self.correlation_id
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::DeterministicContextBuilder;
+
+    #[test]
+    fn events_inside_the_span_carry_the_saga_fields() {
+        let logs = Arc::new(Mutex::new(Vec::new()));
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_writer(move || CapturedLogs(writer.clone()))
+            .finish();
+        let context = DeterministicContextBuilder::default().build();
+
+        tracing::subscriber::with_default(subscriber, || {
+            context
+                .step_span("place_order")
+                .in_scope(|| tracing::info!("placing order"));
+        });
+
+        let logs = String::from_utf8(logs.lock().unwrap().clone()).unwrap();
+        assert!(logs.contains(&format!(
+            "saga_step{{saga_id={} saga_type={} step=place_order attempt={} correlation_id={}}}",
+            context.saga_id.get(),
+            context.saga_type,
+            context.attempt,
+            context.correlation_id
+        )));
+        assert!(logs.contains("placing order"));
+    }
+
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+}
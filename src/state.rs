@@ -5,35 +5,55 @@ pub mod markers {
 }
 
 // State types
+#[derive(Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub struct Idle;
+#[derive(Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub struct Triggered {
     pub triggered_at_millis: u64,
     pub triggering_event: Box<str>,
 }
+#[derive(Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub struct Executing {
     pub started_at_millis: u64,
     pub attempt: u32,
 }
+#[derive(Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub struct Completed {
     pub completed_at_millis: u64,
     pub output: Vec<u8>,
     pub compensation_data: Vec<u8>,
+    /// False for steps completed through [`StepOutput::NoOp`]; compensation
+    /// requests for them are ignored.
+    ///
+    /// [`StepOutput::NoOp`]: crate::StepOutput::NoOp
+    pub compensatable: bool,
+    /// Set for steps completed through [`StepOutput::Partial`].
+    ///
+    /// [`StepOutput::Partial`]: crate::StepOutput::Partial
+    pub completion_ratio: Option<f64>,
 }
+#[derive(Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub struct Failed {
     pub failed_at_millis: u64,
     pub error: Box<str>,
     pub requires_compensation: bool,
 }
+#[derive(Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub struct Compensating {
     pub started_at_millis: u64,
     pub attempt: u32,
 }
+#[derive(Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub struct Compensated {
     pub completed_at_millis: u64,
 }
+#[derive(Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub struct Quarantined {
     pub quarantined_at_millis: u64,
     pub reason: Box<str>,
+    /// Compensation data of the completed step, as it was held while
+    /// completed, so the compensation can be retried.
+    pub compensation_data: Vec<u8>,
 }
 
 impl markers::StepState for Idle {}
@@ -48,16 +68,18 @@ impl markers::StepState for Quarantined {}
 use super::ParticipantEvent;
 
 /// Timestamped event for journal
+#[derive(Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub struct TimestampedEvent {
     pub recorded_at_millis: u64,
     pub event: ParticipantEvent,
 }
 
 /// State container with typestate
+#[derive(Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub struct SagaParticipantState<S: markers::StepState> {
     pub saga_id: super::SagaId,
-    pub saga_type: Box<str>,
-    pub step_name: Box<str>,
+    pub saga_type: crate::SagaType,
+    pub step_name: crate::StepName,
     pub correlation_id: u64,
     pub trace_id: u64,
     pub initiator_peer_id: super::PeerId,
@@ -70,8 +92,8 @@ pub struct SagaParticipantState<S: markers::StepState> {
 impl SagaParticipantState<Idle> {
     pub fn new(
         saga_id: super::SagaId,
-        saga_type: Box<str>,
-        step_name: Box<str>,
+        saga_type: crate::SagaType,
+        step_name: crate::StepName,
         correlation_id: u64,
         trace_id: u64,
         initiator_peer_id: super::PeerId,
@@ -154,11 +176,38 @@ impl SagaParticipantState<Executing> {
                 completed_at_millis: now_millis,
                 output,
                 compensation_data,
+                compensatable: true,
+                completion_ratio: None,
             },
             events: self.events,
         }
     }
 
+    /// Completes a step that returned [`StepOutput::NoOp`]: no payloads are
+    /// kept and the step is marked non-compensatable.
+    ///
+    /// [`StepOutput::NoOp`]: crate::StepOutput::NoOp
+    pub fn complete_noop(self, now_millis: u64) -> SagaParticipantState<Completed> {
+        let mut completed = self.complete(Vec::new(), Vec::new(), now_millis);
+        completed.state.compensatable = false;
+        completed
+    }
+
+    /// Completes a step that returned [`StepOutput::Partial`].
+    ///
+    /// [`StepOutput::Partial`]: crate::StepOutput::Partial
+    pub fn complete_partial(
+        self,
+        output: Vec<u8>,
+        compensation_data: Vec<u8>,
+        completion_ratio: f64,
+        now_millis: u64,
+    ) -> SagaParticipantState<Completed> {
+        let mut completed = self.complete(output, compensation_data, now_millis);
+        completed.state.completion_ratio = Some(completion_ratio);
+        completed
+    }
+
     pub fn fail(
         self,
         error: Box<str>,
@@ -239,13 +288,59 @@ impl SagaParticipantState<Compensating> {
             state: Quarantined {
                 quarantined_at_millis: now_millis,
                 reason,
+                compensation_data: Vec::new(),
             },
             events: self.events,
         }
     }
 }
 
+impl SagaParticipantState<Quarantined> {
+    pub fn with_compensation_data(mut self, compensation_data: Vec<u8>) -> Self {
+        self.state.compensation_data = compensation_data;
+        self
+    }
+
+    /// Back to compensating for another `attempt`, once the cause of the
+    /// quarantine is fixed.
+    pub fn retry_compensation(
+        self,
+        attempt: u32,
+        now_millis: u64,
+    ) -> SagaParticipantState<Compensating> {
+        SagaParticipantState {
+            saga_id: self.saga_id,
+            saga_type: self.saga_type,
+            step_name: self.step_name,
+            correlation_id: self.correlation_id,
+            trace_id: self.trace_id,
+            initiator_peer_id: self.initiator_peer_id,
+            saga_started_at_millis: self.saga_started_at_millis,
+            last_updated_at_millis: now_millis,
+            state: Compensating {
+                started_at_millis: now_millis,
+                attempt,
+            },
+            events: self.events,
+        }
+    }
+}
+
+/// Which state a [`SagaStateEntry`] is in, without its data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StateKind {
+    Idle,
+    Triggered,
+    Executing,
+    Completed,
+    Failed,
+    Compensating,
+    Compensated,
+    Quarantined,
+}
+
 /// Type-erased state entry for HashMap storage
+#[derive(Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub enum SagaStateEntry {
     Idle(SagaParticipantState<Idle>),
     Triggered(SagaParticipantState<Triggered>),
@@ -284,6 +379,45 @@ impl SagaStateEntry {
         }
     }
 
+    pub fn kind(&self) -> StateKind {
+        match self {
+            Self::Idle(_) => StateKind::Idle,
+            Self::Triggered(_) => StateKind::Triggered,
+            Self::Executing(_) => StateKind::Executing,
+            Self::Completed(_) => StateKind::Completed,
+            Self::Failed(_) => StateKind::Failed,
+            Self::Compensating(_) => StateKind::Compensating,
+            Self::Compensated(_) => StateKind::Compensated,
+            Self::Quarantined(_) => StateKind::Quarantined,
+        }
+    }
+
+    pub fn saga_type(&self) -> &str {
+        match self {
+            Self::Idle(s) => s.saga_type.as_str(),
+            Self::Triggered(s) => s.saga_type.as_str(),
+            Self::Executing(s) => s.saga_type.as_str(),
+            Self::Completed(s) => s.saga_type.as_str(),
+            Self::Failed(s) => s.saga_type.as_str(),
+            Self::Compensating(s) => s.saga_type.as_str(),
+            Self::Compensated(s) => s.saga_type.as_str(),
+            Self::Quarantined(s) => s.saga_type.as_str(),
+        }
+    }
+
+    pub fn saga_started_at_millis(&self) -> u64 {
+        match self {
+            Self::Idle(s) => s.saga_started_at_millis,
+            Self::Triggered(s) => s.saga_started_at_millis,
+            Self::Executing(s) => s.saga_started_at_millis,
+            Self::Completed(s) => s.saga_started_at_millis,
+            Self::Failed(s) => s.saga_started_at_millis,
+            Self::Compensating(s) => s.saga_started_at_millis,
+            Self::Compensated(s) => s.saga_started_at_millis,
+            Self::Quarantined(s) => s.saga_started_at_millis,
+        }
+    }
+
     pub fn is_terminal(&self) -> bool {
         matches!(self, Self::Compensated(_) | Self::Quarantined(_))
     }
@@ -7,9 +7,17 @@
 //! and implement [`crate::HasSagaParticipantSupport`]. This crate will then
 //! provide `SagaStateExt` automatically.
 
+use crate::async_storage::{
+    dedupe_check_and_mark, dedupe_prune, journal_append, journal_append_with_outgoing,
+    journal_incoming_history, journal_mark_incoming_processed, journal_prune, journal_read,
+    journal_record_incoming,
+};
 use crate::{
-    DedupeError, HasSagaParticipantSupport, JournalError, ParticipantDedupeStore, ParticipantEvent,
-    ParticipantJournal, SagaId, SagaStateEntry,
+    copy_saga_to_archive, ArchiveError, DedupeError, DedupeKey, HasSagaParticipantSupport,
+    JournalError, ParticipantDedupeStore, ParticipantEvent, ParticipantJournal,
+    ParticipantStateStoreError, Quarantined, QuarantinedSaga, SagaArchiveRecord,
+    SagaChoreographyEvent, SagaContext, SagaId, SagaParticipantState, SagaStateEntry, StateKind,
+    StepLeaseError,
 };
 use std::collections::{HashMap, HashSet, VecDeque};
 
@@ -17,6 +25,9 @@ use std::collections::{HashMap, HashSet, VecDeque};
 pub enum SagaStateStoreError {
     Dedupe(DedupeError),
     Journal(JournalError),
+    Archive(ArchiveError),
+    StepLease(StepLeaseError),
+    State(ParticipantStateStoreError),
 }
 
 /// Extension trait providing common saga state management operations.
@@ -72,6 +83,38 @@ pub trait SagaStateExt: HasSagaParticipantSupport {
         &self.saga_support().saga_states
     }
 
+    /// Sets the current state of a saga, writing it through to the attached
+    /// [`crate::ParticipantStateStore`].
+    fn put_saga_state(&mut self, saga_id: SagaId, entry: SagaStateEntry) {
+        if let Some(store) = &self.saga_support().state_store {
+            if let Err(err) = store.put(saga_id, &entry) {
+                tracing::warn!(
+                    target: "core::saga",
+                    event = "saga_state_store_put_failed",
+                    saga_id = saga_id.get(),
+                    error = %err
+                );
+            }
+        }
+        self.saga_states().insert(saga_id, entry);
+    }
+
+    /// Forgets the current state of a saga, in memory and in the attached
+    /// state store.
+    fn clear_saga_state(&mut self, saga_id: SagaId) {
+        self.saga_states().remove(&saga_id);
+        if let Some(store) = &self.saga_support().state_store {
+            if let Err(err) = store.delete(saga_id) {
+                tracing::warn!(
+                    target: "core::saga",
+                    event = "saga_state_store_delete_failed",
+                    saga_id = saga_id.get(),
+                    error = %err
+                );
+            }
+        }
+    }
+
     /// Returns mutable access to per-saga dependency completion tracking.
     fn dependency_completions(&mut self) -> &mut HashMap<SagaId, HashSet<Box<str>>> {
         &mut self.saga_support_mut().dependency_completions
@@ -148,6 +191,9 @@ pub trait SagaStateExt: HasSagaParticipantSupport {
     /// This should return a monotonically increasing value suitable for
     /// time-based operations such as timeouts and expiration checks.
     fn now_millis(&self) -> u64 {
+        if let Some(clock) = &self.saga_support().clock {
+            return clock();
+        }
         match std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
             Ok(duration) => duration.as_millis() as u64,
             Err(err) => {
@@ -177,13 +223,17 @@ pub trait SagaStateExt: HasSagaParticipantSupport {
     ///
     /// `true` if the operation is new and should proceed, `false` if it
     /// has already been processed.
-    fn check_dedupe_strict(&self, saga_id: SagaId, key: &str) -> Result<bool, SagaStateStoreError> {
+    fn check_dedupe_strict(
+        &self,
+        saga_id: SagaId,
+        key: DedupeKey,
+    ) -> Result<bool, SagaStateStoreError> {
         self.saga_dedupe()
             .check_and_mark(saga_id, key)
             .map_err(SagaStateStoreError::Dedupe)
     }
 
-    fn check_dedupe(&self, saga_id: SagaId, key: &str) -> bool {
+    fn check_dedupe(&self, saga_id: SagaId, key: DedupeKey) -> bool {
         match self.saga_dedupe().check_and_mark(saga_id, key) {
             Ok(value) => value,
             Err(err) => {
@@ -191,7 +241,7 @@ pub trait SagaStateExt: HasSagaParticipantSupport {
                     target: "core::saga",
                     event = "saga_state_dedupe_check_failed",
                     saga_id = saga_id.get(),
-                    key,
+                    key = %key,
                     error = %err
                 );
                 false
@@ -221,35 +271,161 @@ pub trait SagaStateExt: HasSagaParticipantSupport {
     }
 
     fn record_event(&self, saga_id: SagaId, event: ParticipantEvent) {
-        if let Err(err) = self.record_event_strict(saga_id, event) {
+        self.try_record_event(saga_id, event);
+    }
+
+    /// Like [`record_event_strict`](Self::record_event_strict), also staging
+    /// `outgoing`, the events the entry decides, in the journal outbox in the
+    /// same write while the bus ingress handles the event. Staged events are
+    /// stamped with the participant's Lamport clock first, so the relay
+    /// republishes exactly what the ingress publishes; the ingress marks them
+    /// sent, and whatever it does not get to stays pending for the relay.
+    fn record_event_with_outgoing_strict(
+        &self,
+        saga_id: SagaId,
+        event: ParticipantEvent,
+        outgoing: &mut [SagaChoreographyEvent],
+    ) -> Result<(), SagaStateStoreError> {
+        let support = self.saga_support();
+        if !support.stage_emitted || outgoing.is_empty() {
+            return self.record_event_strict(saga_id, event);
+        }
+        for outgoing_event in outgoing.iter_mut() {
+            support.logical_clock.stamp(outgoing_event.context_mut());
+        }
+        let (_, outbox_ids) = self
+            .saga_journal()
+            .append_with_outgoing(saga_id, event, outgoing)
+            .map_err(SagaStateStoreError::Journal)?;
+        support.staged_outgoing.stage(outgoing, outbox_ids);
+        Ok(())
+    }
+
+    /// Like [`try_record_event`](Self::try_record_event), staging `outgoing`
+    /// as [`record_event_with_outgoing_strict`](Self::record_event_with_outgoing_strict)
+    /// does.
+    fn try_record_event_with_outgoing(
+        &self,
+        saga_id: SagaId,
+        event: ParticipantEvent,
+        outgoing: &mut [SagaChoreographyEvent],
+    ) -> bool {
+        match self.record_event_with_outgoing_strict(saga_id, event, outgoing) {
+            Ok(()) => true,
+            Err(err) => {
+                tracing::error!(
+                    target: "core::saga",
+                    event = "saga_state_journal_append_failed",
+                    saga_id = saga_id.get(),
+                    error = ?err
+                );
+                false
+            }
+        }
+    }
+
+    /// Like [`record_event`](Self::record_event), but reports whether the
+    /// event reached the journal.
+    fn try_record_event(&self, saga_id: SagaId, event: ParticipantEvent) -> bool {
+        match self.record_event_strict(saga_id, event) {
+            Ok(()) => true,
+            Err(err) => {
+                tracing::error!(
+                    target: "core::saga",
+                    event = "saga_state_journal_append_failed",
+                    saga_id = saga_id.get(),
+                    error = ?err
+                );
+                false
+            }
+        }
+    }
+
+    /// Persists a raw incoming event in the journal inbox before it is
+    /// deduped and processed.
+    ///
+    /// Returns the inbox id to hand back to
+    /// [`mark_incoming_processed`](Self::mark_incoming_processed), or `None`
+    /// when the journal has no inbox or recording failed.
+    fn record_incoming(
+        &self,
+        saga_id: SagaId,
+        key: DedupeKey,
+        event: &SagaChoreographyEvent,
+    ) -> Option<u64> {
+        match self.saga_journal().record_incoming(saga_id, key, event) {
+            Ok(inbox_id) => inbox_id,
+            Err(err) => {
+                tracing::error!(
+                    target: "core::saga",
+                    event = "saga_state_inbox_record_failed",
+                    saga_id = saga_id.get(),
+                    key = %key,
+                    error = %err
+                );
+                None
+            }
+        }
+    }
+
+    fn mark_incoming_processed(&self, saga_id: SagaId, inbox_id: Option<u64>) {
+        let Some(inbox_id) = inbox_id else {
+            return;
+        };
+        if let Err(err) = self.saga_journal().mark_incoming_processed(inbox_id) {
             tracing::error!(
                 target: "core::saga",
-                event = "saga_state_journal_append_failed",
+                event = "saga_state_inbox_mark_failed",
                 saga_id = saga_id.get(),
-                error = ?err
+                inbox_id,
+                error = %err
             );
         }
     }
 
     /// Removes all state associated with a saga.
     ///
-    /// This removes the saga from the state map, durable journal, and
-    /// deduplication entries. Use this when a saga has completed and its state
+    /// This removes the saga from the state map and state store, durable
+    /// journal, and deduplication entries. Use this when a saga has completed and its state
     /// is no longer needed for recovery.
     ///
     /// # Arguments
     ///
     /// * `saga_id` - The unique identifier of the saga to prune
+    ///
+    /// With an archive store attached, the saga's history is archived first;
+    /// nothing is pruned if that fails.
     fn prune_saga_strict(&mut self, saga_id: SagaId) -> Result<(), SagaStateStoreError> {
+        if let Some(archive) = &self.saga_support().archive {
+            copy_saga_to_archive(self.saga_journal(), archive.as_ref(), saga_id)
+                .map_err(SagaStateStoreError::Archive)?;
+        }
         self.saga_states().remove(&saga_id);
         self.dependency_completions().remove(&saga_id);
         self.dependency_fired().remove(&saga_id);
+        let support = self.saga_support_mut();
+        support.journal_retries.remove(&saga_id);
+        support
+            .unjournaled_outcomes
+            .retain(|outcome| outcome.context.saga_id != saga_id);
         self.saga_journal()
             .prune(saga_id)
             .map_err(SagaStateStoreError::Journal)?;
         self.saga_dedupe()
             .prune(saga_id)
-            .map_err(SagaStateStoreError::Dedupe)
+            .map_err(SagaStateStoreError::Dedupe)?;
+        if let Some(leases) = &self.saga_support().step_leases {
+            leases
+                .release(saga_id)
+                .map_err(SagaStateStoreError::StepLease)?;
+        }
+        if let Some(store) = &self.saga_support().state_store {
+            store.delete(saga_id).map_err(SagaStateStoreError::State)?;
+        }
+        if let Some(index) = &self.saga_support().correlations {
+            index.remove_saga(saga_id);
+        }
+        Ok(())
     }
 
     fn prune_saga(&mut self, saga_id: SagaId) {
@@ -263,6 +439,45 @@ pub trait SagaStateExt: HasSagaParticipantSupport {
         }
     }
 
+    /// Drops terminal entries (`Compensated`, `Quarantined`) last updated
+    /// more than `age_millis` before [`now_millis`](Self::now_millis) from
+    /// the state map and state store. Quarantined entries are reported to
+    /// the attached [`crate::QuarantineManager`] first, with their
+    /// compensation data, so a retry does not depend on the entry. The
+    /// journal, dedupe keys and terminal latch are kept; use
+    /// [`prune_saga`](Self::prune_saga) to forget a saga entirely.
+    ///
+    /// Returns the number of entries dropped.
+    fn prune_terminal_older_than(&mut self, age_millis: u64) -> usize {
+        let cutoff = self.now_millis().saturating_sub(age_millis);
+        let expired: Vec<SagaId> = self
+            .saga_states_ref()
+            .iter()
+            .filter(|(_, entry)| entry.is_terminal() && entry.last_updated_at_millis() < cutoff)
+            .map(|(saga_id, _)| *saga_id)
+            .collect();
+        for saga_id in &expired {
+            if let Some(SagaStateEntry::Quarantined(state)) = self.saga_states_ref().get(saga_id) {
+                if let Some(saga) = unreported_quarantine(self, state) {
+                    self.saga_support().report_quarantined(saga);
+                }
+            }
+            self.clear_saga_state(*saga_id);
+            self.dependency_completions().remove(saga_id);
+            self.dependency_fired().remove(saga_id);
+            self.saga_support_mut().journal_retries.remove(saga_id);
+        }
+        if !expired.is_empty() {
+            tracing::debug!(
+                target: "core::saga",
+                event = "saga_terminal_states_pruned",
+                pruned = expired.len(),
+                age_millis
+            );
+        }
+        expired.len()
+    }
+
     /// Checks whether a saga is still actively running.
     ///
     /// Returns `true` if the saga exists and has not reached a terminal state,
@@ -312,15 +527,306 @@ pub trait SagaStateExt: HasSagaParticipantSupport {
             .filter(|e| !e.is_terminal())
             .count()
     }
+
+    /// Sagas whose entry is in state `kind`, in no particular order.
+    fn sagas_in_state(&self, kind: StateKind) -> impl Iterator<Item = &SagaStateEntry> + '_ {
+        self.saga_states_ref()
+            .values()
+            .filter(move |entry| entry.kind() == kind)
+    }
+
+    /// Sagas that started more than `age_millis` before
+    /// [`now_millis`](Self::now_millis), e.g. for a sweeper of stuck sagas.
+    fn sagas_older_than(&self, age_millis: u64) -> impl Iterator<Item = &SagaStateEntry> + '_ {
+        let cutoff = self.now_millis().saturating_sub(age_millis);
+        self.saga_states_ref()
+            .values()
+            .filter(move |entry| entry.saga_started_at_millis() < cutoff)
+    }
+
+    /// Sagas of `saga_type`, matched exactly.
+    fn sagas_for_type<'a>(
+        &'a self,
+        saga_type: &'a str,
+    ) -> impl Iterator<Item = &'a SagaStateEntry> + 'a {
+        self.saga_states_ref()
+            .values()
+            .filter(move |entry| entry.saga_type() == saga_type)
+    }
 }
 
 impl<T> SagaStateExt for T where T: HasSagaParticipantSupport {}
 
+/// Record to hand the quarantine manager before `state` is dropped;
+/// `None` without a manager or when it already holds the saga, as it
+/// does for sagas quarantined since it was attached.
+fn unreported_quarantine<P>(
+    participant: &P,
+    state: &SagaParticipantState<Quarantined>,
+) -> Option<QuarantinedSaga>
+where
+    P: SagaStateExt + ?Sized,
+{
+    let manager = participant.saga_support().quarantine.as_ref()?;
+    if manager
+        .inspect(state.saga_id, state.step_name.as_str())
+        .is_some()
+    {
+        return None;
+    }
+    let mut context = match participant.saga_journal().incoming_history(state.saga_id) {
+        Ok(mut history) => history.pop().map(|entry| entry.event.into_context()),
+        Err(_) => None,
+    }
+    .unwrap_or_else(|| {
+        SagaContext::start(
+            state.saga_id,
+            state.saga_type.clone(),
+            state.step_name.clone(),
+            state.initiator_peer_id,
+        )
+    });
+    context.correlation_id = state.correlation_id;
+    context.saga_started_at_millis = state.saga_started_at_millis;
+    Some(QuarantinedSaga {
+        context,
+        step: state.step_name.clone(),
+        // The state does not know the participant's id; the step name
+        // is what `SagaParticipant::participant_id` defaults to.
+        participant_id: state.step_name.as_str().into(),
+        reason: state.state.reason.clone(),
+        quarantined_at_millis: state.state.quarantined_at_millis,
+        compensation_data: Some(state.state.compensation_data.clone())
+            .filter(|data| !data.is_empty()),
+        failed_retries: 0,
+    })
+}
+
+/// [`SagaStateExt::record_event_strict`] for the async handlers, which
+/// await an async journal instead of calling it through its adapter.
+pub(crate) async fn record_event_strict_async<P>(
+    participant: &P,
+    saga_id: SagaId,
+    event: ParticipantEvent,
+) -> Result<(), SagaStateStoreError>
+where
+    P: SagaStateExt,
+{
+    journal_append(participant.saga_journal(), saga_id, event)
+        .await
+        .map(|_| ())
+        .map_err(SagaStateStoreError::Journal)
+}
+
+/// [`SagaStateExt::try_record_event`] for the async handlers.
+pub(crate) async fn try_record_event_async<P>(
+    participant: &P,
+    saga_id: SagaId,
+    event: ParticipantEvent,
+) -> bool
+where
+    P: SagaStateExt,
+{
+    match record_event_strict_async(participant, saga_id, event).await {
+        Ok(()) => true,
+        Err(err) => {
+            tracing::error!(
+                target: "core::saga",
+                event = "saga_state_journal_append_failed",
+                saga_id = saga_id.get(),
+                error = ?err
+            );
+            false
+        }
+    }
+}
+
+/// [`SagaStateExt::record_event_with_outgoing_strict`] for the async
+/// handlers.
+pub(crate) async fn record_event_with_outgoing_strict_async<P>(
+    participant: &P,
+    saga_id: SagaId,
+    event: ParticipantEvent,
+    outgoing: &mut [SagaChoreographyEvent],
+) -> Result<(), SagaStateStoreError>
+where
+    P: SagaStateExt,
+{
+    let support = participant.saga_support();
+    if !support.stage_emitted || outgoing.is_empty() {
+        return record_event_strict_async(participant, saga_id, event).await;
+    }
+    for outgoing_event in outgoing.iter_mut() {
+        support.logical_clock.stamp(outgoing_event.context_mut());
+    }
+    let (_, outbox_ids) =
+        journal_append_with_outgoing(participant.saga_journal(), saga_id, event, outgoing)
+            .await
+            .map_err(SagaStateStoreError::Journal)?;
+    support.staged_outgoing.stage(outgoing, outbox_ids);
+    Ok(())
+}
+
+/// [`SagaStateExt::try_record_event_with_outgoing`] for the async handlers.
+pub(crate) async fn try_record_event_with_outgoing_async<P>(
+    participant: &P,
+    saga_id: SagaId,
+    event: ParticipantEvent,
+    outgoing: &mut [SagaChoreographyEvent],
+) -> bool
+where
+    P: SagaStateExt,
+{
+    match record_event_with_outgoing_strict_async(participant, saga_id, event, outgoing).await {
+        Ok(()) => true,
+        Err(err) => {
+            tracing::error!(
+                target: "core::saga",
+                event = "saga_state_journal_append_failed",
+                saga_id = saga_id.get(),
+                error = ?err
+            );
+            false
+        }
+    }
+}
+
+/// [`SagaStateExt::check_dedupe_strict`] for the async handlers.
+pub(crate) async fn check_dedupe_strict_async<P>(
+    participant: &P,
+    saga_id: SagaId,
+    key: DedupeKey,
+) -> Result<bool, SagaStateStoreError>
+where
+    P: SagaStateExt,
+{
+    dedupe_check_and_mark(participant.saga_dedupe(), saga_id, key)
+        .await
+        .map_err(SagaStateStoreError::Dedupe)
+}
+
+/// [`SagaStateExt::record_incoming`] for the async handlers.
+pub(crate) async fn record_incoming_async<P>(
+    participant: &P,
+    saga_id: SagaId,
+    key: DedupeKey,
+    event: &SagaChoreographyEvent,
+) -> Option<u64>
+where
+    P: SagaStateExt,
+{
+    match journal_record_incoming(participant.saga_journal(), saga_id, key, event).await {
+        Ok(inbox_id) => inbox_id,
+        Err(err) => {
+            tracing::error!(
+                target: "core::saga",
+                event = "saga_state_inbox_record_failed",
+                saga_id = saga_id.get(),
+                key = %key,
+                error = %err
+            );
+            None
+        }
+    }
+}
+
+/// [`SagaStateExt::mark_incoming_processed`] for the async handlers.
+pub(crate) async fn mark_incoming_processed_async<P>(
+    participant: &P,
+    saga_id: SagaId,
+    inbox_id: Option<u64>,
+) where
+    P: SagaStateExt,
+{
+    let Some(inbox_id) = inbox_id else {
+        return;
+    };
+    if let Err(err) = journal_mark_incoming_processed(participant.saga_journal(), inbox_id).await {
+        tracing::error!(
+            target: "core::saga",
+            event = "saga_state_inbox_mark_failed",
+            saga_id = saga_id.get(),
+            inbox_id,
+            error = %err
+        );
+    }
+}
+
+/// [`SagaStateExt::prune_saga`] for the async handlers.
+pub(crate) async fn prune_saga_async<P>(participant: &mut P, saga_id: SagaId)
+where
+    P: SagaStateExt,
+{
+    if let Err(err) = prune_saga_strict_async(participant, saga_id).await {
+        tracing::error!(
+            target: "core::saga",
+            event = "saga_state_prune_failed",
+            saga_id = saga_id.get(),
+            error = ?err
+        );
+    }
+}
+
+async fn prune_saga_strict_async<P>(
+    participant: &mut P,
+    saga_id: SagaId,
+) -> Result<(), SagaStateStoreError>
+where
+    P: SagaStateExt,
+{
+    if let Some(archive) = participant.saga_support().archive.clone() {
+        let journal = participant.saga_journal();
+        let entries = journal_read(journal, saga_id)
+            .await
+            .map_err(|err| SagaStateStoreError::Archive(err.into()))?;
+        let incoming = journal_incoming_history(journal, saga_id)
+            .await
+            .map_err(|err| SagaStateStoreError::Archive(err.into()))?;
+        if !entries.is_empty() || !incoming.is_empty() {
+            archive
+                .store(SagaArchiveRecord {
+                    saga_id,
+                    archived_at_millis: SagaContext::now_millis(),
+                    journal: entries,
+                    incoming,
+                })
+                .map_err(SagaStateStoreError::Archive)?;
+        }
+    }
+    participant.saga_states().remove(&saga_id);
+    participant.dependency_completions().remove(&saga_id);
+    participant.dependency_fired().remove(&saga_id);
+    let support = participant.saga_support_mut();
+    support.journal_retries.remove(&saga_id);
+    support
+        .unjournaled_outcomes
+        .retain(|outcome| outcome.context.saga_id != saga_id);
+    journal_prune(participant.saga_journal(), saga_id)
+        .await
+        .map_err(SagaStateStoreError::Journal)?;
+    dedupe_prune(participant.saga_dedupe(), saga_id)
+        .await
+        .map_err(SagaStateStoreError::Dedupe)?;
+    if let Some(leases) = &participant.saga_support().step_leases {
+        leases
+            .release(saga_id)
+            .map_err(SagaStateStoreError::StepLease)?;
+    }
+    if let Some(store) = &participant.saga_support().state_store {
+        store.delete(saga_id).map_err(SagaStateStoreError::State)?;
+    }
+    if let Some(index) = &participant.saga_support().correlations {
+        index.remove_saga(saga_id);
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
         HasSagaParticipantSupport, InMemoryDedupe, InMemoryJournal, ParticipantEvent,
-        ParticipantJournal, SagaId, SagaParticipantSupport,
+        ParticipantJournal, SagaId, SagaParticipantState, SagaParticipantSupport, SagaStateEntry,
+        StateKind,
     };
 
     use super::SagaStateExt;
@@ -373,8 +879,101 @@ mod tests {
             1
         );
 
-        assert!(participant.check_dedupe(saga_id, "step_started"));
-        assert!(!participant.check_dedupe(saga_id, "step_started"));
+        assert!(participant.check_dedupe(saga_id, "step_started".into()));
+        assert!(!participant.check_dedupe(saga_id, "step_started".into()));
         assert_eq!(participant.active_saga_count(), 0);
     }
+
+    #[test]
+    fn state_queries_filter_entries_by_kind_age_and_type() {
+        let mut participant = DummyParticipant::new();
+        participant.saga.clock = Some(std::sync::Arc::new(|| 10_000));
+        for (id, saga_type, started_at) in [
+            (1, "order_lifecycle", 1_000),
+            (2, "order_lifecycle", 9_500),
+            (3, "hedge", 2_000),
+        ] {
+            let triggered = SagaParticipantState::new(
+                SagaId::new(id),
+                saga_type.into(),
+                "risk_check".into(),
+                id,
+                id,
+                crate::PeerId::default(),
+                started_at,
+            )
+            .trigger("saga_started", started_at);
+            let entry = if id == 3 {
+                SagaStateEntry::Executing(triggered.start_execution(started_at))
+            } else {
+                SagaStateEntry::Triggered(triggered)
+            };
+            participant.put_saga_state(SagaId::new(id), entry);
+        }
+
+        let ids = |entries: Vec<&SagaStateEntry>| {
+            let mut ids: Vec<u64> = entries.iter().map(|entry| entry.saga_id().get()).collect();
+            ids.sort_unstable();
+            ids
+        };
+        assert_eq!(
+            ids(participant.sagas_in_state(StateKind::Triggered).collect()),
+            [1, 2]
+        );
+        assert_eq!(ids(participant.sagas_older_than(5_000).collect()), [1, 3]);
+        assert_eq!(
+            ids(participant.sagas_for_type("order_lifecycle").collect()),
+            [1, 2]
+        );
+    }
+
+    #[test]
+    fn prune_terminal_older_than_exports_quarantined_entries_first() {
+        let mut participant = DummyParticipant::new();
+        let manager = crate::QuarantineManager::new();
+        participant.saga.attach_quarantine_manager(manager.clone());
+        participant.saga.clock = Some(std::sync::Arc::new(|| 10_000));
+        for (id, finished_at) in [(1, 1_000), (2, 1_000), (3, 9_500), (4, 1_000)] {
+            let compensating = SagaParticipantState::new(
+                SagaId::new(id),
+                "order_lifecycle".into(),
+                "risk_check".into(),
+                id,
+                id,
+                crate::PeerId::default(),
+                500,
+            )
+            .trigger("saga_started", 500)
+            .start_execution(500)
+            .complete(Vec::new(), b"undo".to_vec(), 600)
+            .start_compensation(700);
+            let entry = match id {
+                1 | 3 => {
+                    SagaStateEntry::Compensated(compensating.complete_compensation(finished_at))
+                }
+                2 => SagaStateEntry::Quarantined(
+                    compensating
+                        .quarantine("venue down".into(), finished_at)
+                        .with_compensation_data(b"undo".to_vec()),
+                ),
+                _ => SagaStateEntry::Compensating(compensating),
+            };
+            participant.put_saga_state(SagaId::new(id), entry);
+        }
+
+        assert_eq!(participant.prune_terminal_older_than(5_000), 2);
+        let mut left: Vec<u64> = participant
+            .saga_states_ref()
+            .keys()
+            .map(|saga_id| saga_id.get())
+            .collect();
+        left.sort_unstable();
+        assert_eq!(left, [3, 4]);
+        let exported = manager
+            .inspect(SagaId::new(2), "risk_check")
+            .expect("exported");
+        assert_eq!(exported.reason.as_ref(), "venue down");
+        assert_eq!(exported.compensation_data.as_deref(), Some(&b"undo"[..]));
+        assert_eq!(participant.prune_terminal_older_than(5_000), 0);
+    }
 }
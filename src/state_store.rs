@@ -0,0 +1,115 @@
+//! Current per-saga participant state, kept apart from the event journal.
+//!
+//! Rebuilding a participant's state from its journal means replaying every
+//! event it recorded for every saga. With a [`ParticipantStateStore`]
+//! attached ([`crate::SagaParticipantSupport::with_state_store`]) the helpers
+//! write each saga's [`SagaStateEntry`] on every transition and delete it
+//! when the saga is reset or pruned, so a restarted participant loads the
+//! current state of a saga with one `get`
+//! ([`crate::restore_saga_states`]). The journal stays the audit trail and
+//! remains what recovery classifies sagas by.
+//!
+//! Writes are best effort: a failed `put` is logged and the in-memory state
+//! moves on, since the journal still holds the transition.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::{SagaId, SagaStateEntry};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ParticipantStateStoreError {
+    #[error("Storage error: {0}")]
+    Storage(Box<str>),
+}
+
+/// Keyed store of the current state of each saga of one participant.
+pub trait ParticipantStateStore: Send + Sync + 'static {
+    /// Replaces the stored state of `saga_id`.
+    fn put(
+        &self,
+        saga_id: SagaId,
+        entry: &SagaStateEntry,
+    ) -> Result<(), ParticipantStateStoreError>;
+
+    fn get(&self, saga_id: SagaId) -> Result<Option<SagaStateEntry>, ParticipantStateStoreError>;
+
+    /// Drops the stored state of `saga_id`; a no-op when there is none.
+    fn delete(&self, saga_id: SagaId) -> Result<(), ParticipantStateStoreError>;
+}
+
+impl<T> ParticipantStateStore for Arc<T>
+where
+    T: ParticipantStateStore + ?Sized,
+{
+    fn put(
+        &self,
+        saga_id: SagaId,
+        entry: &SagaStateEntry,
+    ) -> Result<(), ParticipantStateStoreError> {
+        (**self).put(saga_id, entry)
+    }
+
+    fn get(&self, saga_id: SagaId) -> Result<Option<SagaStateEntry>, ParticipantStateStoreError> {
+        (**self).get(saga_id)
+    }
+
+    fn delete(&self, saga_id: SagaId) -> Result<(), ParticipantStateStoreError> {
+        (**self).delete(saga_id)
+    }
+}
+
+/// In-memory state store; survives actor restarts within one process when
+/// shared through an `Arc`.
+#[derive(Default)]
+pub struct InMemoryStateStore {
+    states: Mutex<HashMap<SagaId, SagaStateEntry>>,
+}
+
+impl InMemoryStateStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<SagaId, SagaStateEntry>> {
+        self.states
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl ParticipantStateStore for InMemoryStateStore {
+    fn put(
+        &self,
+        saga_id: SagaId,
+        entry: &SagaStateEntry,
+    ) -> Result<(), ParticipantStateStoreError> {
+        self.lock().insert(saga_id, entry.clone());
+        Ok(())
+    }
+
+    fn get(&self, saga_id: SagaId) -> Result<Option<SagaStateEntry>, ParticipantStateStoreError> {
+        Ok(self.lock().get(&saga_id).cloned())
+    }
+
+    fn delete(&self, saga_id: SagaId) -> Result<(), ParticipantStateStoreError> {
+        self.lock().remove(&saga_id);
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for InMemoryStateStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InMemoryStateStore")
+            .field("states_len", &self.len())
+            .finish()
+    }
+}
@@ -1,19 +1,73 @@
-//! Participant statistics
+//! Participant statistics and saga activity tracking
+//!
+//! [`ParticipantStats`] lives in memory. To keep long-term totals across
+//! restarts, attach a [`StatsPersistence`]
+//! (`SagaParticipantSupport::with_stats_persistence`): the persisted
+//! snapshot is added to the counters on startup and a fresh one is saved
+//! every `stats_persist_interval_millis` as events come in.
+//! [`ParticipantStats::since_start`] still reports what this process counted.
 
+use std::collections::BTreeMap;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use icanact_core::local::EventSubscription;
+
+use crate::{
+    ReasonCode, SagaChoreographyBus, SagaChoreographyEvent, SagaContext, SagaId, SagaType, StepName,
+};
+
+/// How often a participant saves its stats by default.
+pub const DEFAULT_STATS_PERSIST_INTERVAL_MILLIS: u64 = 10_000;
+
+/// An [`AtomicU64`] alone on its own cache line.
+///
+/// Counters of one [`ParticipantStats`] are bumped by different actors at a
+/// high rate; packed next to each other they share cache lines and every
+/// increment invalidates the line for all other writers. Aligning each
+/// counter to 128 bytes (two 64-byte lines, to also defeat adjacent-line
+/// prefetching) keeps writers of different counters independent. The counter
+/// derefs to the atomic, so `fetch_add`/`load` work as before.
+#[derive(Debug, Default)]
+#[repr(align(128))]
+pub struct StatCounter(AtomicU64);
+
+impl StatCounter {
+    pub const fn new(value: u64) -> Self {
+        Self(AtomicU64::new(value))
+    }
+
+    /// Adds one with relaxed ordering, which is all monitoring needs.
+    pub fn increment(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl std::ops::Deref for StatCounter {
+    type Target = AtomicU64;
+
+    fn deref(&self) -> &AtomicU64 {
+        &self.0
+    }
+}
 
 /// Thread-safe statistics tracker for a saga participant.
 ///
 /// Tracks counters for various saga lifecycle events, enabling monitoring
 /// of participant activity, success rates, and error conditions.
-/// All counters use atomic operations for safe concurrent access.
+/// All counters use atomic operations for safe concurrent access, and each
+/// sits on its own cache line (see [`StatCounter`]).
 ///
 /// # Example
 ///
 /// ```ignore
 /// let stats = ParticipantStats::new();
-/// stats.events_received.fetch_add(1, Ordering::Relaxed);
-/// stats.steps_completed.fetch_add(1, Ordering::Relaxed);
+/// stats.events_received.increment();
+/// stats.steps_completed.increment();
 ///
 /// let snapshot = stats.snapshot();
 /// println!("Completed {} of {} steps", snapshot.steps_completed, snapshot.steps_started);
@@ -21,57 +75,93 @@ use std::sync::atomic::{AtomicU64, Ordering};
 pub struct ParticipantStats {
     /// Total number of events received by this participant from the message broker.
     /// Includes all events regardless of whether they are relevant to this participant.
-    pub events_received: AtomicU64,
+    pub events_received: StatCounter,
 
     /// Number of events that were relevant to this participant.
     /// An event is relevant if it matches the participant's subscription criteria
     /// and triggers step execution or compensation.
-    pub events_relevant: AtomicU64,
+    pub events_relevant: StatCounter,
 
     /// Number of duplicate events detected and ignored.
     /// Duplicates can occur due to message broker redelivery or network retries.
-    pub duplicate_events: AtomicU64,
+    pub duplicate_events: StatCounter,
+
+    /// `duplicate_events` split by event type. Duplicates are rare, so a lock
+    /// is cheap enough here.
+    pub duplicate_events_by_type: Mutex<BTreeMap<&'static str, u64>>,
 
     /// Number of saga steps that have started execution.
     /// Increments when a participant begins processing a step handler.
-    pub steps_started: AtomicU64,
+    pub steps_started: StatCounter,
 
     /// Number of saga steps that completed successfully.
     /// A step is completed when its handler returns without error.
-    pub steps_completed: AtomicU64,
+    pub steps_completed: StatCounter,
 
     /// Number of saga steps that failed during execution.
     /// Step failures may trigger compensation in the saga.
-    pub steps_failed: AtomicU64,
+    pub steps_failed: StatCounter,
 
     /// Number of compensation handlers that have started execution.
     /// Compensation runs in reverse order when a saga needs to rollback.
-    pub compensations_started: AtomicU64,
+    pub compensations_started: StatCounter,
 
     /// Number of compensation handlers that completed successfully.
-    pub compensations_completed: AtomicU64,
+    pub compensations_completed: StatCounter,
 
     /// Number of sagas that have been quarantined by this participant.
     /// Quarantined sagas are paused and require manual intervention.
-    pub quarantined_sagas: AtomicU64,
+    pub quarantined_sagas: StatCounter,
+
+    /// Step and compensation failures by [`ReasonCode`], keyed by
+    /// [`ReasonCode::as_str`].
+    pub failures_by_reason_code: Mutex<BTreeMap<Box<str>, u64>>,
+
+    /// Totals restored from a [`StatsPersistence`], subtracted by
+    /// [`since_start`](Self::since_start).
+    restored: Mutex<ParticipantStatsSnapshot>,
 }
 
 impl ParticipantStats {
     /// Creates a new `ParticipantStats` instance with all counters initialized to zero.
     pub fn new() -> Self {
         Self {
-            events_received: AtomicU64::new(0),
-            events_relevant: AtomicU64::new(0),
-            duplicate_events: AtomicU64::new(0),
-            steps_started: AtomicU64::new(0),
-            steps_completed: AtomicU64::new(0),
-            steps_failed: AtomicU64::new(0),
-            compensations_started: AtomicU64::new(0),
-            compensations_completed: AtomicU64::new(0),
-            quarantined_sagas: AtomicU64::new(0),
+            events_received: StatCounter::new(0),
+            events_relevant: StatCounter::new(0),
+            duplicate_events: StatCounter::new(0),
+            duplicate_events_by_type: Mutex::new(BTreeMap::new()),
+            steps_started: StatCounter::new(0),
+            steps_completed: StatCounter::new(0),
+            steps_failed: StatCounter::new(0),
+            compensations_started: StatCounter::new(0),
+            compensations_completed: StatCounter::new(0),
+            quarantined_sagas: StatCounter::new(0),
+            failures_by_reason_code: Mutex::new(BTreeMap::new()),
+            restored: Mutex::new(ParticipantStatsSnapshot::default()),
         }
     }
 
+    /// Counts an incoming event of `event_type` dropped by the dedupe check.
+    pub fn record_duplicate(&self, event_type: &'static str) {
+        self.duplicate_events.increment();
+        *self
+            .duplicate_events_by_type
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry(event_type)
+            .or_default() += 1;
+    }
+
+    /// Counts a step or compensation failure classified as `code`.
+    pub fn record_failure(&self, code: &ReasonCode) {
+        *self
+            .failures_by_reason_code
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry(code.as_str().into())
+            .or_default() += 1;
+    }
+
     /// Creates an immutable snapshot of all current statistics.
     ///
     /// The snapshot captures consistent values across all counters at a point in time.
@@ -79,16 +169,101 @@ impl ParticipantStats {
     /// purposes where exact consistency with concurrent updates is not critical.
     pub fn snapshot(&self) -> ParticipantStatsSnapshot {
         ParticipantStatsSnapshot {
-            events_received: self.events_received.load(Ordering::Relaxed),
-            events_relevant: self.events_relevant.load(Ordering::Relaxed),
-            duplicate_events: self.duplicate_events.load(Ordering::Relaxed),
-            steps_started: self.steps_started.load(Ordering::Relaxed),
-            steps_completed: self.steps_completed.load(Ordering::Relaxed),
-            steps_failed: self.steps_failed.load(Ordering::Relaxed),
-            compensations_started: self.compensations_started.load(Ordering::Relaxed),
-            compensations_completed: self.compensations_completed.load(Ordering::Relaxed),
-            quarantined_sagas: self.quarantined_sagas.load(Ordering::Relaxed),
+            events_received: self.events_received.get(),
+            events_relevant: self.events_relevant.get(),
+            duplicate_events: self.duplicate_events.get(),
+            duplicate_events_by_type: self
+                .duplicate_events_by_type
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .clone(),
+            steps_started: self.steps_started.get(),
+            steps_completed: self.steps_completed.get(),
+            steps_failed: self.steps_failed.get(),
+            compensations_started: self.compensations_started.get(),
+            compensations_completed: self.compensations_completed.get(),
+            quarantined_sagas: self.quarantined_sagas.get(),
+            failures_by_reason_code: self
+                .failures_by_reason_code
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .clone(),
+        }
+    }
+
+    /// Adds totals persisted by an earlier process to the counters.
+    pub fn restore(&self, persisted: &ParticipantStatsSnapshot) {
+        let counters = [
+            (&self.events_received, persisted.events_received),
+            (&self.events_relevant, persisted.events_relevant),
+            (&self.duplicate_events, persisted.duplicate_events),
+            (&self.steps_started, persisted.steps_started),
+            (&self.steps_completed, persisted.steps_completed),
+            (&self.steps_failed, persisted.steps_failed),
+            (&self.compensations_started, persisted.compensations_started),
+            (
+                &self.compensations_completed,
+                persisted.compensations_completed,
+            ),
+            (&self.quarantined_sagas, persisted.quarantined_sagas),
+        ];
+        for (counter, value) in counters {
+            counter.fetch_add(value, Ordering::Relaxed);
+        }
+        let mut by_type = self
+            .duplicate_events_by_type
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        for (event_type, count) in &persisted.duplicate_events_by_type {
+            *by_type.entry(event_type).or_default() += count;
+        }
+        let mut by_code = self
+            .failures_by_reason_code
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        for (code, count) in &persisted.failures_by_reason_code {
+            *by_code.entry(code.clone()).or_default() += count;
+        }
+        self.restored
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .add(persisted);
+    }
+
+    /// Like [`snapshot`](Self::snapshot), but without restored totals: what
+    /// this process counted since it started.
+    pub fn since_start(&self) -> ParticipantStatsSnapshot {
+        let mut snapshot = self.snapshot();
+        let restored = self
+            .restored
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        snapshot.events_received -= restored.events_received;
+        snapshot.events_relevant -= restored.events_relevant;
+        snapshot.duplicate_events -= restored.duplicate_events;
+        snapshot.steps_started -= restored.steps_started;
+        snapshot.steps_completed -= restored.steps_completed;
+        snapshot.steps_failed -= restored.steps_failed;
+        snapshot.compensations_started -= restored.compensations_started;
+        snapshot.compensations_completed -= restored.compensations_completed;
+        snapshot.quarantined_sagas -= restored.quarantined_sagas;
+        for (event_type, count) in &restored.duplicate_events_by_type {
+            if let Some(total) = snapshot.duplicate_events_by_type.get_mut(event_type) {
+                *total -= count;
+                if *total == 0 {
+                    snapshot.duplicate_events_by_type.remove(event_type);
+                }
+            }
         }
+        for (code, count) in &restored.failures_by_reason_code {
+            if let Some(total) = snapshot.failures_by_reason_code.get_mut(code) {
+                *total -= count;
+                if *total == 0 {
+                    snapshot.failures_by_reason_code.remove(code);
+                }
+            }
+        }
+        snapshot
     }
 }
 
@@ -103,7 +278,7 @@ impl Default for ParticipantStats {
 /// This struct provides a copy of all counter values that can be used
 /// for reporting, logging, or comparison without holding references
 /// to the live statistics.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct ParticipantStatsSnapshot {
     /// Total number of events received by this participant.
     pub events_received: u64,
@@ -114,6 +289,9 @@ pub struct ParticipantStatsSnapshot {
     /// Number of duplicate events detected and ignored.
     pub duplicate_events: u64,
 
+    /// `duplicate_events` by event type.
+    pub duplicate_events_by_type: BTreeMap<&'static str, u64>,
+
     /// Number of saga steps that have started execution.
     pub steps_started: u64,
 
@@ -131,4 +309,603 @@ pub struct ParticipantStatsSnapshot {
 
     /// Number of sagas that have been quarantined.
     pub quarantined_sagas: u64,
+
+    /// Step and compensation failures by reason code.
+    pub failures_by_reason_code: BTreeMap<Box<str>, u64>,
+}
+
+impl ParticipantStatsSnapshot {
+    /// `name=value` lines, one per counter, one `duplicate.<event_type>`
+    /// line per duplicated event type and one `failure.<code>` line per
+    /// reason code.
+    pub fn encode(&self) -> String {
+        let mut encoded = String::new();
+        for (name, value) in self.counters() {
+            encoded.push_str(&format!("{name}={value}\n"));
+        }
+        for (event_type, count) in &self.duplicate_events_by_type {
+            encoded.push_str(&format!("duplicate.{event_type}={count}\n"));
+        }
+        for (code, count) in &self.failures_by_reason_code {
+            encoded.push_str(&format!("failure.{code}={count}\n"));
+        }
+        encoded
+    }
+
+    /// Parses [`encode`](Self::encode) output. Unknown counters and event
+    /// types are skipped; `None` if a line is malformed.
+    pub fn decode(encoded: &str) -> Option<Self> {
+        let mut snapshot = Self::default();
+        for line in encoded.lines().filter(|line| !line.is_empty()) {
+            let (name, value) = line.split_once('=')?;
+            let value: u64 = value.parse().ok()?;
+            if let Some(event_type) = name.strip_prefix("duplicate.") {
+                if let Some(event_type) = SagaChoreographyEvent::EVENT_TYPES
+                    .iter()
+                    .find(|known| **known == event_type)
+                {
+                    snapshot.duplicate_events_by_type.insert(event_type, value);
+                }
+                continue;
+            }
+            if let Some(code) = name.strip_prefix("failure.") {
+                snapshot.failures_by_reason_code.insert(code.into(), value);
+                continue;
+            }
+            let counter = match name {
+                "events_received" => &mut snapshot.events_received,
+                "events_relevant" => &mut snapshot.events_relevant,
+                "duplicate_events" => &mut snapshot.duplicate_events,
+                "steps_started" => &mut snapshot.steps_started,
+                "steps_completed" => &mut snapshot.steps_completed,
+                "steps_failed" => &mut snapshot.steps_failed,
+                "compensations_started" => &mut snapshot.compensations_started,
+                "compensations_completed" => &mut snapshot.compensations_completed,
+                "quarantined_sagas" => &mut snapshot.quarantined_sagas,
+                _ => continue,
+            };
+            *counter = value;
+        }
+        Some(snapshot)
+    }
+
+    fn counters(&self) -> [(&'static str, u64); 9] {
+        [
+            ("events_received", self.events_received),
+            ("events_relevant", self.events_relevant),
+            ("duplicate_events", self.duplicate_events),
+            ("steps_started", self.steps_started),
+            ("steps_completed", self.steps_completed),
+            ("steps_failed", self.steps_failed),
+            ("compensations_started", self.compensations_started),
+            ("compensations_completed", self.compensations_completed),
+            ("quarantined_sagas", self.quarantined_sagas),
+        ]
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.events_received += other.events_received;
+        self.events_relevant += other.events_relevant;
+        self.duplicate_events += other.duplicate_events;
+        self.steps_started += other.steps_started;
+        self.steps_completed += other.steps_completed;
+        self.steps_failed += other.steps_failed;
+        self.compensations_started += other.compensations_started;
+        self.compensations_completed += other.compensations_completed;
+        self.quarantined_sagas += other.quarantined_sagas;
+        for (event_type, count) in &other.duplicate_events_by_type {
+            *self.duplicate_events_by_type.entry(event_type).or_default() += count;
+        }
+        for (code, count) in &other.failures_by_reason_code {
+            *self
+                .failures_by_reason_code
+                .entry(code.clone())
+                .or_default() += count;
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum StatsPersistenceError {
+    #[error("Storage error: {0}")]
+    Storage(Box<str>),
+}
+
+/// Keeps the latest [`ParticipantStatsSnapshot`] of one participant across
+/// restarts. `LmdbJournal` implements it in its journal's metadata.
+pub trait StatsPersistence: Send + Sync + 'static {
+    /// Replaces the saved snapshot.
+    fn save_stats(&self, snapshot: &ParticipantStatsSnapshot) -> Result<(), StatsPersistenceError>;
+
+    /// The saved snapshot; `None` before the first save.
+    fn load_stats(&self) -> Result<Option<ParticipantStatsSnapshot>, StatsPersistenceError>;
+}
+
+impl<T> StatsPersistence for Arc<T>
+where
+    T: StatsPersistence + ?Sized,
+{
+    fn save_stats(&self, snapshot: &ParticipantStatsSnapshot) -> Result<(), StatsPersistenceError> {
+        (**self).save_stats(snapshot)
+    }
+
+    fn load_stats(&self) -> Result<Option<ParticipantStatsSnapshot>, StatsPersistenceError> {
+        (**self).load_stats()
+    }
+}
+
+/// In-memory [`StatsPersistence`]; survives actor restarts within one
+/// process when shared through an `Arc`.
+#[derive(Debug, Default)]
+pub struct InMemoryStatsPersistence {
+    saved: Mutex<Option<ParticipantStatsSnapshot>>,
+}
+
+impl InMemoryStatsPersistence {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl StatsPersistence for InMemoryStatsPersistence {
+    fn save_stats(&self, snapshot: &ParticipantStatsSnapshot) -> Result<(), StatsPersistenceError> {
+        *self
+            .saved
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(snapshot.clone());
+        Ok(())
+    }
+
+    fn load_stats(&self) -> Result<Option<ParticipantStatsSnapshot>, StatsPersistenceError> {
+        Ok(self
+            .saved
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone())
+    }
+}
+
+/// A saga started and not yet terminal, as seen by [`SagaActivityTracker`].
+#[derive(Clone, Debug)]
+pub struct ActiveSaga {
+    pub saga_id: SagaId,
+    pub saga_type: SagaType,
+    pub started_at_millis: u64,
+    /// Type of the latest event observed for the saga.
+    pub last_event: &'static str,
+    pub last_event_at_millis: u64,
+}
+
+/// Counters for one saga type kept by [`SagaActivityTracker`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SagaTypeStats {
+    pub saga_type: SagaType,
+    pub started: u64,
+    pub completed: u64,
+    pub failed: u64,
+    pub quarantined: u64,
+    /// Sagas of this type currently in flight.
+    pub active: usize,
+}
+
+/// What a compensation request for an in-flight saga needs.
+struct SagaTrail {
+    last_context: SagaContext,
+    /// Completed steps that can be compensated, in completion order.
+    compensable_steps: Vec<StepName>,
+    compensation_requested: bool,
+}
+
+#[derive(Default)]
+struct SagaActivity {
+    active: BTreeMap<SagaId, ActiveSaga>,
+    trails: BTreeMap<SagaId, SagaTrail>,
+    by_type: BTreeMap<SagaType, SagaTypeStats>,
+}
+
+/// Tracks in-flight sagas and per-type outcome counts from bus events.
+/// Cloning shares the same state.
+#[derive(Clone, Default)]
+pub struct SagaActivityTracker {
+    inner: Arc<Mutex<SagaActivity>>,
+}
+
+impl SagaActivityTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a start, progress or terminal event. Terminal events of sagas
+    /// that are not in flight (duplicates, or starts this tracker never saw)
+    /// are not counted.
+    pub fn observe(&self, event: &SagaChoreographyEvent) {
+        let context = event.context();
+        let mut activity = self.activity();
+        let SagaActivity {
+            active,
+            trails,
+            by_type,
+        } = &mut *activity;
+        let stats = by_type
+            .entry(context.saga_type.clone())
+            .or_insert_with(|| SagaTypeStats {
+                saga_type: context.saga_type.clone(),
+                ..SagaTypeStats::default()
+            });
+        if let SagaChoreographyEvent::SagaStarted { .. } = event {
+            stats.started += 1;
+            active.insert(
+                context.saga_id,
+                ActiveSaga {
+                    saga_id: context.saga_id,
+                    saga_type: context.saga_type.clone(),
+                    started_at_millis: context.saga_started_at_millis,
+                    last_event: event.event_type(),
+                    last_event_at_millis: context.event_timestamp_millis,
+                },
+            );
+            trails.insert(
+                context.saga_id,
+                SagaTrail {
+                    last_context: context.clone(),
+                    compensable_steps: Vec::new(),
+                    compensation_requested: false,
+                },
+            );
+        } else if event.terminal_outcome().is_some() {
+            trails.remove(&context.saga_id);
+            if active.remove(&context.saga_id).is_some() {
+                match event {
+                    SagaChoreographyEvent::SagaCompleted { .. } => stats.completed += 1,
+                    SagaChoreographyEvent::SagaFailed { .. } => stats.failed += 1,
+                    _ => stats.quarantined += 1,
+                }
+            }
+        } else if let Some(saga) = active.get_mut(&context.saga_id) {
+            saga.last_event = event.event_type();
+            saga.last_event_at_millis = context.event_timestamp_millis;
+            if let Some(trail) = trails.get_mut(&context.saga_id) {
+                trail.last_context = context.clone();
+                match event {
+                    SagaChoreographyEvent::StepCompleted {
+                        compensation_available: true,
+                        ..
+                    } if !trail.compensable_steps.contains(&context.step_name) => {
+                        trail.compensable_steps.push(context.step_name.clone());
+                    }
+                    SagaChoreographyEvent::CompensationRequested { .. } => {
+                        trail.compensation_requested = true;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        stats.active = active
+            .values()
+            .filter(|saga| saga.saga_type == context.saga_type)
+            .count();
+    }
+
+    /// Subscribes the tracker to every event of `saga_type`.
+    pub fn subscribe(&self, bus: &SagaChoreographyBus, saga_type: &str) -> EventSubscription {
+        let tracker = self.clone();
+        bus.subscribe_saga_type_fn(saga_type, move |event| {
+            tracker.observe(event);
+            true
+        })
+    }
+
+    /// In-flight sagas ordered by id.
+    pub fn active(&self) -> Vec<ActiveSaga> {
+        self.activity().active.values().cloned().collect()
+    }
+
+    /// A `CompensationRequested` for every in-flight saga of `saga_type`
+    /// that is not compensating yet, naming its compensable completed steps
+    /// in reverse completion order. The saga's latest step is reported as
+    /// the failed one.
+    pub fn compensation_requests(
+        &self,
+        saga_type: &str,
+        reason: &str,
+    ) -> Vec<SagaChoreographyEvent> {
+        self.activity()
+            .trails
+            .values()
+            .filter(|trail| {
+                !trail.compensation_requested && trail.last_context.saga_type.as_ref() == saga_type
+            })
+            .map(|trail| SagaChoreographyEvent::CompensationRequested {
+                context: trail.last_context.for_compensation(),
+                failed_step: trail.last_context.step_name.clone(),
+                reason: reason.into(),
+                steps_to_compensate: trail.compensable_steps.iter().rev().cloned().collect(),
+            })
+            .collect()
+    }
+
+    /// Counters per saga type, ordered by type.
+    pub fn stats(&self) -> Vec<SagaTypeStats> {
+        self.activity().by_type.values().cloned().collect()
+    }
+
+    fn activity(&self) -> std::sync::MutexGuard<'_, SagaActivity> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl std::fmt::Debug for SagaActivityTracker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SagaActivityTracker")
+            .field("active", &self.activity().active.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryDedupe, InMemoryJournal, SagaParticipantSupport};
+
+    #[test]
+    fn persisted_stats_survive_a_restart_and_since_start_excludes_them() {
+        let persistence = Arc::new(InMemoryStatsPersistence::new());
+        let now = Arc::new(AtomicU64::new(1_000));
+        let clock = {
+            let now = now.clone();
+            Arc::new(move || now.load(Ordering::Relaxed))
+        };
+        let support = SagaParticipantSupport::new(InMemoryJournal::new(), InMemoryDedupe::new())
+            .with_clock(clock.clone())
+            .with_stats_persistence(persistence.clone());
+        support.stats.steps_completed.increment();
+        support.stats.record_duplicate("step_completed");
+        support.persist_stats_if_due();
+        assert_eq!(persistence.load_stats().unwrap(), None);
+        now.fetch_add(DEFAULT_STATS_PERSIST_INTERVAL_MILLIS, Ordering::Relaxed);
+        support.persist_stats_if_due();
+        let saved = persistence.load_stats().unwrap().unwrap();
+        assert_eq!(saved.steps_completed, 1);
+        assert_eq!(
+            ParticipantStatsSnapshot::decode(&saved.encode()),
+            Some(saved)
+        );
+
+        let restarted = SagaParticipantSupport::new(InMemoryJournal::new(), InMemoryDedupe::new())
+            .with_clock(clock)
+            .with_stats_persistence(persistence);
+        restarted.stats.steps_completed.increment();
+        let totals = restarted.stats.snapshot();
+        assert_eq!(totals.steps_completed, 2);
+        assert_eq!(
+            totals.duplicate_events_by_type.get("step_completed"),
+            Some(&1)
+        );
+        let since_start = restarted.stats.since_start();
+        assert_eq!(since_start.steps_completed, 1);
+        assert_eq!(since_start.duplicate_events, 0);
+        assert!(since_start.duplicate_events_by_type.is_empty());
+    }
+}
+
+#[cfg(feature = "hdr")]
+pub use hdr::{LatencyHandle, LatencyRecorder, LatencySnapshot};
+
+/// HDR-histogram latency tracking (feature `hdr`).
+///
+/// Recording never waits on a lock: every participant owns a
+/// [`LatencyHandle`] with a private histogram and merges it into the shared
+/// one with `try_lock` every 100ms or 1024 samples, whichever comes first,
+/// and on drop. A snapshot therefore lags live recording by up to one flush
+/// interval per handle.
+#[cfg(feature = "hdr")]
+mod hdr {
+    use std::sync::{Arc, Mutex, TryLockError};
+    use std::time::{Duration, Instant};
+
+    use hdrhistogram::Histogram;
+
+    /// Largest latency tracked exactly; longer samples saturate to it.
+    const HIGHEST_TRACKABLE_MICROS: u64 = 60 * 60 * 1_000_000;
+    const SIGNIFICANT_FIGURES: u8 = 3;
+    const FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+    const FLUSH_SAMPLES: u64 = 1024;
+
+    fn empty_histogram() -> Histogram<u64> {
+        Histogram::new_with_bounds(1, HIGHEST_TRACKABLE_MICROS, SIGNIFICANT_FIGURES)
+            .expect("latency histogram bounds are valid")
+    }
+
+    /// Shared latency histogram, in microseconds.
+    ///
+    /// Clones share the same histogram. Hand one [`LatencyHandle`] to each
+    /// recording participant and read percentiles with
+    /// [`snapshot`](Self::snapshot).
+    #[derive(Clone)]
+    pub struct LatencyRecorder {
+        merged: Arc<Mutex<Histogram<u64>>>,
+    }
+
+    impl LatencyRecorder {
+        pub fn new() -> Self {
+            Self {
+                merged: Arc::new(Mutex::new(empty_histogram())),
+            }
+        }
+
+        /// Returns a new handle that records into this histogram.
+        pub fn handle(&self) -> LatencyHandle {
+            LatencyHandle {
+                local: empty_histogram(),
+                merged: Arc::clone(&self.merged),
+                flushed_at: Instant::now(),
+            }
+        }
+
+        /// Percentiles over every sample flushed so far.
+        pub fn snapshot(&self) -> LatencySnapshot {
+            let merged = self
+                .merged
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            LatencySnapshot::of(&merged)
+        }
+
+        /// Drops every flushed sample; handles keep their unflushed ones.
+        pub fn reset(&self) {
+            self.merged
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .reset();
+        }
+    }
+
+    impl Default for LatencyRecorder {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl std::fmt::Debug for LatencyRecorder {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("LatencyRecorder")
+                .field("snapshot", &self.snapshot())
+                .finish()
+        }
+    }
+
+    /// Per-participant writer for a [`LatencyRecorder`].
+    pub struct LatencyHandle {
+        local: Histogram<u64>,
+        merged: Arc<Mutex<Histogram<u64>>>,
+        flushed_at: Instant,
+    }
+
+    impl LatencyHandle {
+        pub fn record(&mut self, elapsed: Duration) {
+            let micros = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+            self.local.saturating_record(micros);
+            if self.local.len() >= FLUSH_SAMPLES || self.flushed_at.elapsed() >= FLUSH_INTERVAL {
+                self.try_flush();
+            }
+        }
+
+        /// Merges pending samples into the shared histogram, waiting for the
+        /// lock if a snapshot holds it.
+        pub fn flush(&mut self) {
+            let mut merged = self
+                .merged
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            Self::merge_into(&mut self.local, &mut merged);
+            self.flushed_at = Instant::now();
+        }
+
+        /// Like [`flush`](Self::flush), but leaves the samples pending if the
+        /// lock is taken.
+        fn try_flush(&mut self) {
+            let mut merged = match self.merged.try_lock() {
+                Ok(merged) => merged,
+                Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+                Err(TryLockError::WouldBlock) => return,
+            };
+            Self::merge_into(&mut self.local, &mut merged);
+            self.flushed_at = Instant::now();
+        }
+
+        fn merge_into(local: &mut Histogram<u64>, merged: &mut Histogram<u64>) {
+            if local.is_empty() {
+                return;
+            }
+            merged
+                .add(&*local)
+                .expect("handles share the recorder's histogram bounds");
+            local.reset();
+        }
+    }
+
+    impl Drop for LatencyHandle {
+        fn drop(&mut self) {
+            self.flush();
+        }
+    }
+
+    impl std::fmt::Debug for LatencyHandle {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("LatencyHandle")
+                .field("pending", &self.local.len())
+                .finish()
+        }
+    }
+
+    /// Latency percentiles at a point in time. All values are microseconds.
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    pub struct LatencySnapshot {
+        pub count: u64,
+        pub min: u64,
+        pub max: u64,
+        pub mean: f64,
+        pub p50: u64,
+        pub p90: u64,
+        pub p99: u64,
+        pub p999: u64,
+    }
+
+    impl LatencySnapshot {
+        fn of(histogram: &Histogram<u64>) -> Self {
+            if histogram.is_empty() {
+                return Self::default();
+            }
+            Self {
+                count: histogram.len(),
+                min: histogram.min(),
+                max: histogram.max(),
+                mean: histogram.mean(),
+                p50: histogram.value_at_quantile(0.5),
+                p90: histogram.value_at_quantile(0.9),
+                p99: histogram.value_at_quantile(0.99),
+                p999: histogram.value_at_quantile(0.999),
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn snapshot_reports_percentiles_of_flushed_samples() {
+            let recorder = LatencyRecorder::new();
+            let mut handle = recorder.handle();
+            for micros in 1..=1000 {
+                handle.record(Duration::from_micros(micros));
+            }
+            handle.flush();
+
+            let snapshot = recorder.snapshot();
+            assert_eq!(snapshot.count, 1000);
+            assert_eq!(snapshot.min, 1);
+            assert_eq!(snapshot.max, 1000);
+            assert_eq!(snapshot.p50, 500);
+            assert_eq!(snapshot.p90, 900);
+            assert_eq!(snapshot.p99, 990);
+            assert_eq!(snapshot.p999, 999);
+        }
+
+        #[test]
+        fn handles_merge_on_drop_and_saturate_oversized_samples() {
+            let recorder = LatencyRecorder::new();
+            let mut first = recorder.handle();
+            let mut second = recorder.handle();
+            first.record(Duration::from_micros(10));
+            second.record(Duration::from_secs(24 * 60 * 60));
+            drop(first);
+            drop(second);
+            let snapshot = recorder.snapshot();
+            assert_eq!(snapshot.count, 2);
+            assert!(snapshot.max >= HIGHEST_TRACKABLE_MICROS);
+        }
+    }
 }
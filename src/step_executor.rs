@@ -0,0 +1,205 @@
+//! Steps that run off the actor.
+//!
+//! `execute_step` runs on the actor handling the saga event, so a step that
+//! blocks, e.g. on an ask that never returns, holds up every event behind it,
+//! compensation requests included. A participant can hand the blocking part
+//! to a worker instead: it returns the work from
+//! [`SagaParticipant::offload_step`](crate::SagaParticipant::offload_step) and
+//! its support has a [`StepExecutor`] attached:
+//!
+//! ```ignore
+//! let saga = SagaParticipantSupport::new(journal, dedupe)
+//!     .with_step_executor(Arc::new(ThreadStepExecutor), Duration::from_secs(10));
+//!
+//! fn offload_step(&self, _ctx: &SagaContext, input: &[u8]) -> Option<OffloadedStep> {
+//!     let client = self.client.clone();
+//!     let order = input.to_vec();
+//!     Some(Box::new(move || client.place(&order).map_err(StepError::require_compensation)))
+//! }
+//! ```
+//!
+//! The step stays `Executing` while the worker runs. Its result is picked up
+//! by the next event the participant handles, or by
+//! [`crate::poll_offloaded_steps_with_emit`] from the actor's own timer.
+//! A step still running after the timeout fails with
+//! [`ReasonCode::Timeout`](crate::ReasonCode::Timeout). Workers are not
+//! cancelled, so the step may still apply its side effect: a late success
+//! is journaled as the step's completion and compensated right away, and a
+//! compensation that fails quarantines the saga as usual.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::{SagaContext, SagaId, StepError, StepOutput};
+
+/// Detached work of a step; see [`crate::SagaParticipant::offload_step`].
+pub type OffloadedStep = Box<dyn FnOnce() -> Result<StepOutput, StepError> + Send + 'static>;
+
+/// Runs offloaded steps somewhere other than the actor.
+pub trait StepExecutor: Send + Sync + 'static {
+    fn spawn(&self, job: Box<dyn FnOnce() + Send + 'static>);
+}
+
+impl<T> StepExecutor for Arc<T>
+where
+    T: StepExecutor + ?Sized,
+{
+    fn spawn(&self, job: Box<dyn FnOnce() + Send + 'static>) {
+        (**self).spawn(job)
+    }
+}
+
+/// One OS thread per offloaded step.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ThreadStepExecutor;
+
+impl StepExecutor for ThreadStepExecutor {
+    fn spawn(&self, job: Box<dyn FnOnce() + Send + 'static>) {
+        if let Err(err) = std::thread::Builder::new()
+            .name("saga-step".into())
+            .spawn(job)
+        {
+            // The step stays `Executing` until its timeout fails it.
+            tracing::error!(
+                target: "core::saga",
+                event = "saga_step_spawn_failed",
+                error = %err
+            );
+        }
+    }
+}
+
+/// Offloaded steps on a tokio runtime's blocking pool.
+#[derive(Clone, Debug)]
+pub struct TokioStepExecutor {
+    handle: tokio::runtime::Handle,
+}
+
+impl TokioStepExecutor {
+    pub fn new(handle: tokio::runtime::Handle) -> Self {
+        Self { handle }
+    }
+
+    /// Uses the runtime of the calling task; panics outside one.
+    pub fn current() -> Self {
+        Self::new(tokio::runtime::Handle::current())
+    }
+}
+
+impl StepExecutor for TokioStepExecutor {
+    fn spawn(&self, job: Box<dyn FnOnce() + Send + 'static>) {
+        drop(self.handle.spawn_blocking(job));
+    }
+}
+
+/// What an offloaded step returned, or the payload it panicked with.
+pub(crate) type StepResult = std::thread::Result<Result<StepOutput, StepError>>;
+
+type FinishedSteps = Arc<Mutex<Vec<(u64, StepResult)>>>;
+
+/// What [`OffloadedSteps::take`] picked up.
+#[derive(Default)]
+pub(crate) struct TakenSteps {
+    /// Steps that finished in time, and steps past their deadline with `None`.
+    pub(crate) settled: Vec<(RunningStep, Option<StepResult>)>,
+    /// Results of steps that had already timed out.
+    pub(crate) late: Vec<(RunningStep, StepResult)>,
+}
+
+/// Steps running on the executor and the results they handed back.
+pub(crate) struct OffloadedSteps {
+    executor: Arc<dyn StepExecutor>,
+    pub(crate) timeout_millis: u64,
+    next_ticket: u64,
+    pub(crate) running: HashMap<u64, RunningStep>,
+    /// Steps failed by their timeout whose worker has not returned yet.
+    timed_out: HashMap<u64, RunningStep>,
+    finished: FinishedSteps,
+}
+
+#[derive(Clone)]
+pub(crate) struct RunningStep {
+    pub(crate) context: SagaContext,
+    pub(crate) input: Vec<u8>,
+    pub(crate) deadline_millis: u64,
+}
+
+impl OffloadedSteps {
+    pub(crate) fn new(executor: Arc<dyn StepExecutor>, timeout_millis: u64) -> Self {
+        Self {
+            executor,
+            timeout_millis,
+            next_ticket: 0,
+            running: HashMap::new(),
+            timed_out: HashMap::new(),
+            finished: Arc::default(),
+        }
+    }
+
+    /// Hands `job` to the executor; its result is kept for [`Self::take`].
+    pub(crate) fn spawn(
+        &mut self,
+        context: SagaContext,
+        input: Vec<u8>,
+        job: OffloadedStep,
+        now: u64,
+    ) {
+        self.next_ticket += 1;
+        let ticket = self.next_ticket;
+        self.running.insert(
+            ticket,
+            RunningStep {
+                context,
+                input,
+                deadline_millis: now.saturating_add(self.timeout_millis),
+            },
+        );
+        let finished = Arc::clone(&self.finished);
+        self.executor.spawn(Box::new(move || {
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(job));
+            finished
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .push((ticket, result));
+        }));
+    }
+
+    /// Steps that finished, then steps past their deadline at `now`, with
+    /// `None` for the latter. Both stop running.
+    pub(crate) fn take(&mut self, now: u64) -> TakenSteps {
+        let finished = std::mem::take(
+            &mut *self
+                .finished
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()),
+        );
+        let mut taken = TakenSteps::default();
+        for (ticket, result) in finished {
+            if let Some(step) = self.running.remove(&ticket) {
+                taken.settled.push((step, Some(result)));
+            } else if let Some(step) = self.timed_out.remove(&ticket) {
+                taken.late.push((step, result));
+            }
+        }
+        let mut overdue: Vec<u64> = self
+            .running
+            .iter()
+            .filter(|(_, step)| step.deadline_millis <= now)
+            .map(|(ticket, _)| *ticket)
+            .collect();
+        overdue.sort_unstable();
+        for ticket in overdue {
+            if let Some(step) = self.running.remove(&ticket) {
+                self.timed_out.insert(ticket, step.clone());
+                taken.settled.push((step, None));
+            }
+        }
+        taken
+    }
+
+    pub(crate) fn is_running(&self, saga_id: SagaId) -> bool {
+        self.running
+            .values()
+            .any(|step| step.context.saga_id == saga_id)
+    }
+}
@@ -0,0 +1,254 @@
+//! Step ownership leases for replicated participants.
+//!
+//! Running the same participant in two processes for availability means both
+//! receive every saga event, and both would execute every step. With
+//! [`StepLeases`] attached ([`crate::SagaParticipantSupport::with_step_leases`])
+//! each replica claims `(saga_id, step_name)` in a shared [`StepLeaseStore`]
+//! before executing; the replica that loses the claim skips the step.
+//!
+//! A lease lasts `ttl` from its last claim. Long-running steps call
+//! [`crate::SagaParticipantSupport::renew_step_lease`] to keep it. The
+//! replica that skipped keeps the triggering event pending in its journal
+//! inbox; once the lease has expired, its next inbox replay takes the lease
+//! over and runs the step, which is how a step held by a crashed replica gets
+//! executed. A step that finished keeps its lease until the saga is pruned, so
+//! the TTL only needs to outlast the step, not the saga.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::{SagaId, StepName};
+
+/// A claim on one step of one saga by one replica.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StepLease {
+    pub saga_id: SagaId,
+    pub step_name: StepName,
+    pub holder: Box<str>,
+    /// The Unix timestamp in milliseconds after which other replicas may
+    /// take the lease over.
+    pub expires_at_millis: u64,
+}
+
+impl StepLease {
+    pub fn is_expired(&self, now_millis: u64) -> bool {
+        now_millis >= self.expires_at_millis
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum StepLeaseError {
+    #[error("step lease is held by {holder} until {expires_at_millis}")]
+    Held {
+        holder: Box<str>,
+        expires_at_millis: u64,
+    },
+    #[error("Storage error: {0}")]
+    Storage(Box<str>),
+}
+
+/// Shared table of step leases.
+///
+/// `claim` must be atomic across replicas: of two concurrent claims on a free
+/// or expired lease, exactly one succeeds.
+pub trait StepLeaseStore: Send + Sync + 'static {
+    /// Takes the lease if it is free, expired, or already held by `holder`
+    /// (which renews it), and returns the lease as stored.
+    fn claim(
+        &self,
+        saga_id: SagaId,
+        step_name: &str,
+        holder: &str,
+        ttl_millis: u64,
+        now_millis: u64,
+    ) -> Result<StepLease, StepLeaseError>;
+
+    /// Drops every lease of `saga_id` held by `holder`.
+    fn release(&self, saga_id: SagaId, holder: &str) -> Result<(), StepLeaseError>;
+}
+
+impl<T> StepLeaseStore for Arc<T>
+where
+    T: StepLeaseStore + ?Sized,
+{
+    fn claim(
+        &self,
+        saga_id: SagaId,
+        step_name: &str,
+        holder: &str,
+        ttl_millis: u64,
+        now_millis: u64,
+    ) -> Result<StepLease, StepLeaseError> {
+        (**self).claim(saga_id, step_name, holder, ttl_millis, now_millis)
+    }
+
+    fn release(&self, saga_id: SagaId, holder: &str) -> Result<(), StepLeaseError> {
+        (**self).release(saga_id, holder)
+    }
+}
+
+/// In-memory lease table, shared by cloning an `Arc` across participants in
+/// one process (tests, or replicas simulated as separate actors).
+#[derive(Debug, Default)]
+pub struct InMemoryStepLeaseStore {
+    leases: Mutex<BTreeMap<(SagaId, StepName), StepLease>>,
+}
+
+impl InMemoryStepLeaseStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn lease(&self, saga_id: SagaId, step_name: &str) -> Option<StepLease> {
+        self.lock().get(&(saga_id, step_name.into())).cloned()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<(SagaId, StepName), StepLease>> {
+        self.leases
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl StepLeaseStore for InMemoryStepLeaseStore {
+    fn claim(
+        &self,
+        saga_id: SagaId,
+        step_name: &str,
+        holder: &str,
+        ttl_millis: u64,
+        now_millis: u64,
+    ) -> Result<StepLease, StepLeaseError> {
+        let mut leases = self.lock();
+        let key = (saga_id, StepName::from(step_name));
+        if let Some(existing) = leases.get(&key) {
+            if &*existing.holder != holder && !existing.is_expired(now_millis) {
+                return Err(StepLeaseError::Held {
+                    holder: existing.holder.clone(),
+                    expires_at_millis: existing.expires_at_millis,
+                });
+            }
+        }
+        let lease = StepLease {
+            saga_id,
+            step_name: key.1.clone(),
+            holder: holder.into(),
+            expires_at_millis: now_millis.saturating_add(ttl_millis),
+        };
+        leases.insert(key, lease.clone());
+        Ok(lease)
+    }
+
+    fn release(&self, saga_id: SagaId, holder: &str) -> Result<(), StepLeaseError> {
+        self.lock()
+            .retain(|(id, _), lease| *id != saga_id || &*lease.holder != holder);
+        Ok(())
+    }
+}
+
+/// Lease settings of one replica: the shared store, this replica's holder
+/// id, and how long a claim lasts.
+#[derive(Clone)]
+pub struct StepLeases {
+    pub store: Arc<dyn StepLeaseStore>,
+    pub holder: Box<str>,
+    pub ttl: Duration,
+}
+
+impl StepLeases {
+    pub fn new(store: Arc<dyn StepLeaseStore>, holder: impl Into<Box<str>>, ttl: Duration) -> Self {
+        Self {
+            store,
+            holder: holder.into(),
+            ttl,
+        }
+    }
+
+    pub fn claim(
+        &self,
+        saga_id: SagaId,
+        step_name: &str,
+        now_millis: u64,
+    ) -> Result<StepLease, StepLeaseError> {
+        self.store.claim(
+            saga_id,
+            step_name,
+            &self.holder,
+            self.ttl.as_millis() as u64,
+            now_millis,
+        )
+    }
+
+    /// Claims the lease without expiry. Used once a step has finished, so
+    /// the lease outlives the TTL until the saga is released.
+    pub fn hold(
+        &self,
+        saga_id: SagaId,
+        step_name: &str,
+        now_millis: u64,
+    ) -> Result<StepLease, StepLeaseError> {
+        self.store
+            .claim(saga_id, step_name, &self.holder, u64::MAX, now_millis)
+    }
+
+    pub fn release(&self, saga_id: SagaId) -> Result<(), StepLeaseError> {
+        self.store.release(saga_id, &self.holder)
+    }
+}
+
+impl std::fmt::Debug for StepLeases {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StepLeases")
+            .field("holder", &self.holder)
+            .field("ttl", &self.ttl)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lease_is_exclusive_until_expiry_then_stealable() {
+        let store = Arc::new(InMemoryStepLeaseStore::new());
+        let a = StepLeases::new(store.clone(), "replica-a", Duration::from_millis(100));
+        let b = StepLeases::new(store.clone(), "replica-b", Duration::from_millis(100));
+        let saga_id = SagaId::new(4);
+
+        a.claim(saga_id, "reserve", 1_000).unwrap();
+        assert!(matches!(
+            b.claim(saga_id, "reserve", 1_050),
+            Err(StepLeaseError::Held { holder, .. }) if &*holder == "replica-a"
+        ));
+        // Renewal by the holder pushes the expiry out.
+        assert_eq!(
+            a.claim(saga_id, "reserve", 1_090)
+                .unwrap()
+                .expires_at_millis,
+            1_190
+        );
+        assert!(b.claim(saga_id, "reserve", 1_150).is_err());
+
+        let stolen = b.claim(saga_id, "reserve", 1_190).unwrap();
+        assert_eq!(&*stolen.holder, "replica-b");
+        assert!(a.claim(saga_id, "reserve", 1_200).is_err());
+    }
+
+    #[test]
+    fn release_drops_only_own_leases_of_the_saga() {
+        let store = Arc::new(InMemoryStepLeaseStore::new());
+        let a = StepLeases::new(store.clone(), "replica-a", Duration::from_secs(60));
+        let b = StepLeases::new(store.clone(), "replica-b", Duration::from_secs(60));
+
+        a.claim(SagaId::new(1), "reserve", 0).unwrap();
+        a.claim(SagaId::new(2), "reserve", 0).unwrap();
+        b.claim(SagaId::new(1), "settle", 0).unwrap();
+        a.release(SagaId::new(1)).unwrap();
+
+        assert!(store.lease(SagaId::new(1), "reserve").is_none());
+        assert!(store.lease(SagaId::new(2), "reserve").is_some());
+        assert!(store.lease(SagaId::new(1), "settle").is_some());
+    }
+}
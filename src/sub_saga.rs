@@ -0,0 +1,877 @@
+//! Nested sub-sagas: a participant step that runs a whole child saga.
+//!
+//! [`SubSagaStep`] starts a child saga from inside an async participant step
+//! and resolves once the child reaches a terminal state. The child context is
+//! linked to the parent through [`SagaContext::parent_saga_id`] and shares the
+//! parent's correlation id. The child saga id and, for a child that
+//! completed, its compensable steps are kept as the step's compensation data,
+//! which the parent participant journals with the step's completion.
+//!
+//! The child's participants hold a `SagaCompleted` naming a parent: they
+//! neither latch nor prune the child, so its compensation data outlives its
+//! completion. Compensating the parent step then sends the child a real
+//! `CompensationRequested` for those steps, newest first, followed by
+//! `SagaFailed`. Once the parent settles without rolling the child back, the
+//! step releases it with a `SagaCompleted` that no longer names the parent.
+//! Running and settled children are tracked in memory only, so a child whose
+//! parent settles across a restart of the step is not released.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use icanact_core::local::EventSubscription;
+use tokio::sync::oneshot;
+
+use crate::chain::derived_saga_id;
+use crate::{
+    CompensationError, SagaChoreographyBus, SagaChoreographyEvent, SagaContext, SagaId,
+    SagaTerminalOutcome, SagaType, StepError, StepName, StepOutput,
+};
+
+type OutputMapper = dyn Fn(&SagaTerminalOutcome) -> Result<Vec<u8>, StepError> + Send + Sync;
+
+/// Terminal outcome of a child and the steps it completed with
+/// compensation available, in completion order.
+struct FinishedChild {
+    outcome: SagaTerminalOutcome,
+    compensable_steps: Vec<StepName>,
+}
+
+/// A running child; dropped once it reaches a terminal outcome.
+struct ChildSagaState {
+    context: SagaContext,
+    compensable_steps: Vec<StepName>,
+    waiter: Option<oneshot::Sender<FinishedChild>>,
+}
+
+type ChildSagaMap = Arc<Mutex<HashMap<SagaId, ChildSagaState>>>;
+
+/// Completed children awaiting their parent, by parent saga id.
+type SettledChildMap = Arc<Mutex<HashMap<SagaId, Vec<SagaContext>>>>;
+
+/// Adapter running a child saga as one parent step.
+///
+/// Call [`execute`](Self::execute) from `AsyncSagaParticipant::execute_step`
+/// and [`compensate`](Self::compensate) from `compensate_step`.
+pub struct SubSagaStep {
+    bus: SagaChoreographyBus,
+    child_saga_type: SagaType,
+    child_first_step: StepName,
+    timeout: Option<Duration>,
+    map_output: Arc<OutputMapper>,
+    children: ChildSagaMap,
+    settled: SettledChildMap,
+    /// Watches each parent saga type for the outcomes releasing children.
+    parent_subscriptions: Mutex<HashMap<SagaType, EventSubscription>>,
+    _subscription: EventSubscription,
+}
+
+impl SubSagaStep {
+    /// Subscribes to `child_saga_type` on `bus` to track child outcomes.
+    pub fn new(
+        bus: &SagaChoreographyBus,
+        child_saga_type: impl Into<SagaType>,
+        child_first_step: impl Into<StepName>,
+    ) -> Self {
+        let child_saga_type = child_saga_type.into();
+        let children: ChildSagaMap = Arc::new(Mutex::new(HashMap::new()));
+        let tracked = Arc::clone(&children);
+        let subscription = bus.subscribe_saga_type_fn(child_saga_type.as_ref(), move |event| {
+            track_child_event(&tracked, event);
+            true
+        });
+        Self {
+            bus: bus.clone(),
+            child_saga_type,
+            child_first_step: child_first_step.into(),
+            timeout: None,
+            map_output: Arc::new(default_output_mapping),
+            children,
+            settled: Arc::new(Mutex::new(HashMap::new())),
+            parent_subscriptions: Mutex::new(HashMap::new()),
+            _subscription: subscription,
+        }
+    }
+
+    /// Cancels the child and fails the parent step when the child has not
+    /// reached a terminal state within `timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Overrides how the child outcome becomes the parent step output. The
+    /// default completes with an empty output on `SagaCompleted`, requires
+    /// compensation on `SagaFailed`, and fails terminally on quarantine.
+    pub fn with_output_mapping<F>(mut self, map_output: F) -> Self
+    where
+        F: Fn(&SagaTerminalOutcome) -> Result<Vec<u8>, StepError> + Send + Sync + 'static,
+    {
+        self.map_output = Arc::new(map_output);
+        self
+    }
+
+    /// Id of the child saga started for `parent` by this step.
+    pub fn child_saga_id(&self, parent: &SagaContext) -> SagaId {
+        derived_saga_id(
+            parent.saga_id,
+            &format!("{}:{}", self.child_saga_type, parent.step_name),
+        )
+    }
+
+    /// Starts the child saga with `payload` and waits for its terminal outcome.
+    pub async fn execute(
+        &self,
+        parent: &SagaContext,
+        payload: Vec<u8>,
+    ) -> Result<StepOutput, StepError> {
+        let child = parent.chained(
+            self.child_saga_id(parent),
+            self.child_saga_type.clone(),
+            self.child_first_step.clone(),
+        );
+        let child_saga_id = child.saga_id;
+        let (tx, rx) = oneshot::channel();
+        lock_children(&self.children).insert(
+            child_saga_id,
+            ChildSagaState {
+                context: child.clone(),
+                compensable_steps: Vec::new(),
+                waiter: Some(tx),
+            },
+        );
+
+        if let Err(err) = self.bus.publish_strict(SagaChoreographyEvent::SagaStarted {
+            context: child.clone(),
+            payload,
+        }) {
+            lock_children(&self.children).remove(&child_saga_id);
+            return Err(StepError::require_compensation(format!(
+                "sub_saga_start_failed: {err:?}"
+            )));
+        }
+
+        let outcome = match self.timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, rx).await {
+                Ok(received) => received,
+                Err(_) => {
+                    self.cancel_running_child(child_saga_id, "sub_saga_timed_out");
+                    return Err(StepError::require_compensation("sub_saga_timed_out"));
+                }
+            },
+            None => rx.await,
+        };
+        match outcome {
+            Ok(finished) => {
+                if matches!(finished.outcome, SagaTerminalOutcome::Completed { .. }) {
+                    self.hold_until_parent_settles(parent, child);
+                }
+                self.step_result(child_saga_id, &finished)
+            }
+            Err(_) => Err(StepError::require_compensation("sub_saga_tracking_dropped")),
+        }
+    }
+
+    /// Rolls back the child saga recorded in `compensation_data`; `parent`
+    /// is the context `compensate_step` was called with.
+    ///
+    /// A child that completed is sent a `CompensationRequested` for its
+    /// compensable steps, newest first, then `SagaFailed`; its participants
+    /// held its compensation data for this (see the module docs). This works
+    /// from the data alone, e.g. after a restart. Failed or quarantined
+    /// children ran their own compensation, so there is nothing left to do
+    /// for them.
+    pub fn compensate(
+        &self,
+        parent: &SagaContext,
+        compensation_data: &[u8],
+    ) -> Result<(), CompensationError> {
+        let Some(child_saga_id) = decode_child_saga_id(compensation_data) else {
+            return Err(CompensationError::terminal(
+                "sub_saga_compensation_data_invalid",
+            ));
+        };
+        let compensable_steps = match decode_finished_child(compensation_data) {
+            Some(FinishedKind::Completed(steps)) => steps,
+            Some(FinishedKind::Settled) => return Ok(()),
+            // Written before the child's steps were recorded.
+            None => {
+                return Err(CompensationError::terminal(format!(
+                    "sub_saga_{child_saga_id}_not_tracked"
+                )))
+            }
+        };
+        let child = self
+            .take_settled_child(parent.saga_id, child_saga_id)
+            .unwrap_or_else(|| {
+                parent.chained(
+                    child_saga_id,
+                    self.child_saga_type.clone(),
+                    self.child_first_step.clone(),
+                )
+            });
+        self.roll_back_child(&child, compensable_steps, "compensated_by_parent")
+            .map_err(|err| {
+                CompensationError::safe_to_retry(format!(
+                    "sub_saga_compensation_request_failed: {err:?}"
+                ))
+            })
+    }
+
+    fn step_result(
+        &self,
+        child_saga_id: SagaId,
+        finished: &FinishedChild,
+    ) -> Result<StepOutput, StepError> {
+        let output = (self.map_output)(&finished.outcome)?;
+        Ok(StepOutput::Completed {
+            output,
+            compensation_data: encode_compensation_data(child_saga_id, finished),
+        })
+    }
+
+    /// Keeps `child` until `parent` reaches its final outcome, watching the
+    /// parent's saga type for it.
+    fn hold_until_parent_settles(&self, parent: &SagaContext, child: SagaContext) {
+        lock_settled(&self.settled)
+            .entry(parent.saga_id)
+            .or_default()
+            .push(child);
+        let mut subscriptions = self
+            .parent_subscriptions
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if subscriptions.contains_key(&parent.saga_type) {
+            return;
+        }
+        let bus = self.bus.clone();
+        let settled = Arc::clone(&self.settled);
+        let subscription =
+            self.bus
+                .subscribe_saga_type_fn(parent.saga_type.as_ref(), move |event| {
+                    release_settled_children(&bus, &settled, event);
+                    true
+                });
+        subscriptions.insert(parent.saga_type.clone(), subscription);
+    }
+
+    fn take_settled_child(
+        &self,
+        parent_saga_id: SagaId,
+        child_saga_id: SagaId,
+    ) -> Option<SagaContext> {
+        let mut settled = lock_settled(&self.settled);
+        let children = settled.get_mut(&parent_saga_id)?;
+        let index = children
+            .iter()
+            .position(|child| child.saga_id == child_saga_id)?;
+        let child = children.remove(index);
+        if children.is_empty() {
+            settled.remove(&parent_saga_id);
+        }
+        Some(child)
+    }
+
+    fn cancel_running_child(&self, child_saga_id: SagaId, reason: &str) {
+        let (context, compensable_steps) = {
+            let mut children = lock_children(&self.children);
+            let Some(state) = children.get_mut(&child_saga_id) else {
+                return;
+            };
+            state.waiter = None;
+            (state.context.clone(), state.compensable_steps.clone())
+        };
+        if let Err(err) = self.roll_back_child(&context, compensable_steps, reason) {
+            tracing::error!(
+                target: "core::saga",
+                event = "sub_saga_cancel_publish_failed",
+                saga_id = child_saga_id.get(),
+                error = ?err
+            );
+        }
+    }
+
+    /// Asks the child's participants to compensate `compensable_steps`, then
+    /// fails the child. Errs only when the compensation request could not be
+    /// published.
+    fn roll_back_child(
+        &self,
+        child: &SagaContext,
+        mut compensable_steps: Vec<StepName>,
+        reason: &str,
+    ) -> Result<(), crate::SagaBusPublishError> {
+        let context = child.for_compensation();
+        if !compensable_steps.is_empty() {
+            compensable_steps.reverse();
+            self.bus
+                .publish_strict(SagaChoreographyEvent::CompensationRequested {
+                    context: context.clone(),
+                    failed_step: context.step_name.clone(),
+                    reason: reason.into(),
+                    steps_to_compensate: compensable_steps,
+                })?;
+        }
+        if let Err(err) = self.bus.publish_strict(SagaChoreographyEvent::SagaFailed {
+            context,
+            reason: reason.into(),
+            failure: None,
+        }) {
+            tracing::error!(
+                target: "core::saga",
+                event = "sub_saga_fail_publish_failed",
+                saga_id = child.saga_id.get(),
+                error = ?err
+            );
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for SubSagaStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SubSagaStep")
+            .field("child_saga_type", &self.child_saga_type)
+            .field("child_first_step", &self.child_first_step)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+fn track_child_event(children: &ChildSagaMap, event: &SagaChoreographyEvent) {
+    let context = event.context();
+    if context.parent_saga_id.is_none() {
+        return;
+    }
+    let mut children = lock_children(children);
+    let Some(state) = children.get_mut(&context.saga_id) else {
+        return;
+    };
+    if let SagaChoreographyEvent::StepCompleted {
+        compensation_available: true,
+        ..
+    } = event
+    {
+        state.compensable_steps.push(context.step_name.clone());
+        return;
+    }
+    let Some(outcome) = event.terminal_outcome() else {
+        return;
+    };
+    let Some(state) = children.remove(&context.saga_id) else {
+        return;
+    };
+    if let Some(waiter) = state.waiter {
+        let _ = waiter.send(FinishedChild {
+            outcome,
+            compensable_steps: state.compensable_steps,
+        });
+    }
+}
+
+fn lock_children(
+    children: &ChildSagaMap,
+) -> std::sync::MutexGuard<'_, HashMap<SagaId, ChildSagaState>> {
+    children
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn lock_settled(
+    settled: &SettledChildMap,
+) -> std::sync::MutexGuard<'_, HashMap<SagaId, Vec<SagaContext>>> {
+    settled
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Releases the children held for the saga `event` settles, if it does.
+fn release_settled_children(
+    bus: &SagaChoreographyBus,
+    settled: &SettledChildMap,
+    event: &SagaChoreographyEvent,
+) {
+    let context = event.context();
+    let settles = match event {
+        // Held itself: its own parent may still roll it back.
+        SagaChoreographyEvent::SagaCompleted { .. } => context.parent_saga_id.is_none(),
+        _ => event.terminal_outcome().is_some(),
+    };
+    if !settles {
+        return;
+    }
+    let Some(children) = lock_settled(settled).remove(&context.saga_id) else {
+        return;
+    };
+    for child in children {
+        if let Err(err) = bus.publish_strict(child_released(&child)) {
+            tracing::error!(
+                target: "core::saga",
+                event = "sub_saga_release_publish_failed",
+                saga_id = child.saga_id.get(),
+                error = ?err
+            );
+        }
+    }
+}
+
+/// The `SagaCompleted` releasing a completed `child` its participants hold:
+/// its context without the parent link, under a fresh trace id so it is not
+/// taken for a duplicate of the held completion.
+pub(crate) fn child_released(child: &SagaContext) -> SagaChoreographyEvent {
+    SagaChoreographyEvent::SagaCompleted {
+        context: SagaContext {
+            parent_saga_id: None,
+            ..child.for_compensation()
+        },
+    }
+}
+
+/// How a child recorded in compensation data finished.
+enum FinishedKind {
+    /// Completed with these compensable steps, in completion order.
+    Completed(Vec<StepName>),
+    /// Failed or quarantined.
+    Settled,
+}
+
+/// The child saga id, then `C` and the compensable steps, each followed by
+/// `\n`, for a completed child, or `S` for a failed or quarantined one.
+fn encode_compensation_data(child_saga_id: SagaId, finished: &FinishedChild) -> Vec<u8> {
+    let mut data = child_saga_id.get().to_le_bytes().to_vec();
+    match finished.outcome {
+        SagaTerminalOutcome::Completed { .. } => {
+            data.push(b'C');
+            for step in &finished.compensable_steps {
+                data.extend_from_slice(step.as_str().as_bytes());
+                data.push(b'\n');
+            }
+        }
+        _ => data.push(b'S'),
+    }
+    data
+}
+
+fn decode_child_saga_id(compensation_data: &[u8]) -> Option<SagaId> {
+    let bytes: [u8; 8] = compensation_data.get(..8)?.try_into().ok()?;
+    Some(SagaId::new(u64::from_le_bytes(bytes)))
+}
+
+fn decode_finished_child(compensation_data: &[u8]) -> Option<FinishedKind> {
+    match compensation_data.get(8..)? {
+        [b'C', steps @ ..] => {
+            let steps = std::str::from_utf8(steps).ok()?;
+            Some(FinishedKind::Completed(
+                steps.lines().map(StepName::from).collect(),
+            ))
+        }
+        [b'S'] => Some(FinishedKind::Settled),
+        _ => None,
+    }
+}
+
+fn default_output_mapping(outcome: &SagaTerminalOutcome) -> Result<Vec<u8>, StepError> {
+    match outcome {
+        SagaTerminalOutcome::Completed { .. } => Ok(Vec::new()),
+        SagaTerminalOutcome::Failed { reason, .. } => Err(StepError::require_compensation(
+            format!("sub_saga_failed: {reason}"),
+        )),
+        SagaTerminalOutcome::Quarantined { reason, .. } => Err(StepError::terminal(format!(
+            "sub_saga_quarantined: {reason}"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        DeterministicContextBuilder, ParticipantJournal, SagaStateExt, SagaWorkflowContract,
+        SagaWorkflowStepContract, TerminalPolicy, WorkflowDependencySpec,
+    };
+
+    fn parent() -> SagaContext {
+        DeterministicContextBuilder::default()
+            .with_saga_id(5)
+            .with_saga_type("order")
+            .with_step_name("ship")
+            .build()
+    }
+
+    #[test]
+    fn completed_child_is_rolled_back_from_its_compensation_data() {
+        let bus = SagaChoreographyBus::new();
+        let step = SubSagaStep::new(&bus, "shipping", "book_carrier");
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&recorded);
+        let _recorder = bus.subscribe_saga_type_fn("shipping", move |event| {
+            seen.lock().unwrap().push(event.clone());
+            true
+        });
+        let child_saga_id = step.child_saga_id(&parent());
+        let child = parent().chained(child_saga_id, "shipping".into(), "book_carrier".into());
+        let (tx, mut rx) = oneshot::channel();
+        lock_children(&step.children).insert(
+            child_saga_id,
+            ChildSagaState {
+                context: child.clone(),
+                compensable_steps: Vec::new(),
+                waiter: Some(tx),
+            },
+        );
+
+        for (step_name, compensation_available) in [
+            ("book_carrier", true),
+            ("notify_customer", false),
+            ("print_label", true),
+        ] {
+            track_child_event(
+                &step.children,
+                &crate::step_completed(
+                    SagaContext {
+                        step_name: step_name.into(),
+                        ..child.clone()
+                    },
+                    Vec::new(),
+                    Vec::new(),
+                    compensation_available,
+                ),
+            );
+        }
+        track_child_event(
+            &step.children,
+            &SagaChoreographyEvent::SagaCompleted {
+                context: child.clone(),
+            },
+        );
+        assert!(lock_children(&step.children).is_empty());
+
+        let finished = rx.try_recv().expect("completion should be delivered");
+        let Ok(StepOutput::Completed {
+            compensation_data, ..
+        }) = step.step_result(child_saga_id, &finished)
+        else {
+            panic!("completed child should complete the parent step");
+        };
+        assert_eq!(
+            decode_child_saga_id(&compensation_data),
+            Some(child_saga_id)
+        );
+
+        // A fresh step, as after a restart, works from the data alone.
+        let restarted = SubSagaStep::new(&bus, "shipping", "book_carrier");
+        assert!(restarted.compensate(&parent(), &compensation_data).is_ok());
+        let recorded = recorded.lock().unwrap();
+        let [SagaChoreographyEvent::CompensationRequested {
+            context,
+            steps_to_compensate,
+            ..
+        }, SagaChoreographyEvent::SagaFailed { .. }] = recorded.as_slice()
+        else {
+            panic!("expected the child to be rolled back, got {recorded:?}");
+        };
+        assert_eq!(context.saga_id, child_saga_id);
+        assert_eq!(
+            steps_to_compensate,
+            &[
+                StepName::from("print_label"),
+                StepName::from("book_carrier")
+            ]
+        );
+    }
+
+    #[test]
+    fn child_failure_maps_to_compensating_step_error() {
+        let bus = SagaChoreographyBus::new();
+        let step = SubSagaStep::new(&bus, "shipping", "book_carrier");
+        let child_saga_id = step.child_saga_id(&parent());
+        let child = parent().chained(child_saga_id, "shipping".into(), "book_carrier".into());
+        let (tx, mut rx) = oneshot::channel();
+        lock_children(&step.children).insert(
+            child_saga_id,
+            ChildSagaState {
+                context: child.clone(),
+                compensable_steps: Vec::new(),
+                waiter: Some(tx),
+            },
+        );
+
+        track_child_event(
+            &step.children,
+            &SagaChoreographyEvent::SagaFailed {
+                context: child,
+                reason: "no carrier".into(),
+                failure: None,
+            },
+        );
+
+        assert!(lock_children(&step.children).is_empty());
+        let finished = rx.try_recv().expect("failure should be delivered");
+        assert!(matches!(
+            step.step_result(child_saga_id, &finished),
+            Err(StepError::RequireCompensation { .. })
+        ));
+        let settled = encode_compensation_data(child_saga_id, &finished);
+        assert!(step.compensate(&parent(), &settled).is_ok());
+    }
+
+    struct CarrierParticipant {
+        saga: crate::SagaParticipantSupport<crate::InMemoryJournal, crate::InMemoryDedupe>,
+        compensated_with: Vec<Vec<u8>>,
+    }
+
+    impl crate::HasSagaParticipantSupport for CarrierParticipant {
+        type Journal = crate::InMemoryJournal;
+        type Dedupe = crate::InMemoryDedupe;
+
+        fn saga_support(&self) -> &crate::SagaParticipantSupport<Self::Journal, Self::Dedupe> {
+            &self.saga
+        }
+
+        fn saga_support_mut(
+            &mut self,
+        ) -> &mut crate::SagaParticipantSupport<Self::Journal, Self::Dedupe> {
+            &mut self.saga
+        }
+    }
+
+    impl crate::SagaParticipant for CarrierParticipant {
+        type Error = String;
+
+        fn step_name(&self) -> &str {
+            "book_carrier"
+        }
+
+        fn saga_types(&self) -> &[&'static str] {
+            &["shipping"]
+        }
+
+        fn execute_step(
+            &mut self,
+            _context: &SagaContext,
+            _input: &[u8],
+        ) -> Result<StepOutput, StepError> {
+            Ok(StepOutput::Completed {
+                output: Vec::new(),
+                compensation_data: vec![7],
+            })
+        }
+
+        fn compensate_step(
+            &mut self,
+            _context: &SagaContext,
+            compensation_data: &[u8],
+        ) -> Result<(), CompensationError> {
+            self.compensated_with.push(compensation_data.to_vec());
+            Ok(())
+        }
+    }
+
+    struct ShippingContract;
+
+    impl SagaWorkflowContract for ShippingContract {
+        fn saga_type() -> &'static str {
+            "shipping"
+        }
+
+        fn first_step() -> &'static str {
+            "book_carrier"
+        }
+
+        fn steps() -> &'static [SagaWorkflowStepContract] {
+            &[
+                SagaWorkflowStepContract {
+                    step_name: "book_carrier",
+                    participant_id: "carrier",
+                    depends_on: WorkflowDependencySpec::OnSagaStart,
+                },
+                SagaWorkflowStepContract {
+                    step_name: "print_label",
+                    participant_id: "labels",
+                    depends_on: WorkflowDependencySpec::After("book_carrier"),
+                },
+            ]
+        }
+
+        fn terminal_policy() -> TerminalPolicy {
+            shipping_policy(&["book_carrier", "print_label"])
+        }
+    }
+
+    fn shipping_policy(required: &[&str]) -> TerminalPolicy {
+        TerminalPolicy::new(
+            "shipping".into(),
+            "shipping/test".into(),
+            crate::FailureAuthority::AnyParticipant,
+            crate::SuccessCriteria::AllOf(required.iter().map(|step| (*step).into()).collect()),
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+            &[],
+        )
+    }
+
+    /// A bus running shipping sagas, with the carrier as their only
+    /// participant.
+    struct Shipping {
+        bus: SagaChoreographyBus,
+        recorded: Arc<Mutex<Vec<SagaChoreographyEvent>>>,
+        delivered: usize,
+        carrier: CarrierParticipant,
+        _subscriptions: Vec<EventSubscription>,
+    }
+
+    impl Shipping {
+        /// Shipping sagas complete once every step in `required` has.
+        fn new(required: &[&str]) -> Self {
+            let bus = SagaChoreographyBus::new();
+            bus.register_workflow_contract_provider::<ShippingContract>()
+                .expect("contract should register");
+            for step in ["book_carrier", "print_label"] {
+                bus.register_bound_workflow_step("shipping", step)
+                    .expect("step should bind");
+            }
+            let resolver = bus
+                .attach_terminal_resolver(shipping_policy(required), "shipping")
+                .expect("resolver should attach");
+            let recorded = Arc::new(Mutex::new(Vec::new()));
+            let seen = Arc::clone(&recorded);
+            let recorder = bus.subscribe_saga_type_fn("shipping", move |event| {
+                seen.lock().unwrap().push(event.clone());
+                true
+            });
+            Self {
+                bus,
+                recorded,
+                delivered: 0,
+                carrier: CarrierParticipant {
+                    saga: crate::SagaParticipantSupport::new(
+                        crate::InMemoryJournal::new(),
+                        crate::InMemoryDedupe::new(),
+                    ),
+                    compensated_with: Vec::new(),
+                },
+                _subscriptions: vec![resolver, recorder],
+            }
+        }
+
+        /// Hands the carrier each shipping event published since the last
+        /// call and publishes what it emits, until nothing new arrives: a
+        /// stand-in for the carrier's binding.
+        fn pump(&mut self) {
+            loop {
+                let next = self.recorded.lock().unwrap().get(self.delivered).cloned();
+                let Some(event) = next else {
+                    return;
+                };
+                self.delivered += 1;
+                let mut emitted = Vec::new();
+                crate::handle_saga_event_with_emit(&mut self.carrier, event, |next| {
+                    emitted.push(next)
+                });
+                for next in emitted {
+                    self.bus
+                        .publish_strict(next)
+                        .expect("child event should publish");
+                }
+            }
+        }
+
+        /// Runs `step` for `parent()`, the carrier handling the child.
+        async fn run(&mut self, step: &SubSagaStep) -> Result<StepOutput, StepError> {
+            let recorded = Arc::clone(&self.recorded);
+            let (result, ()) = tokio::join!(step.execute(&parent(), Vec::new()), async {
+                while recorded.lock().unwrap().is_empty() {
+                    tokio::task::yield_now().await;
+                }
+                self.pump();
+            });
+            result
+        }
+
+        fn holds(&self, saga_id: SagaId) -> bool {
+            !self.carrier.is_terminal_saga_latched(saga_id)
+                && !self
+                    .carrier
+                    .saga_journal()
+                    .read(saga_id)
+                    .expect("journal should read")
+                    .is_empty()
+        }
+    }
+
+    #[tokio::test]
+    async fn completed_child_is_rolled_back_by_its_participants() {
+        let mut shipping = Shipping::new(&["book_carrier"]);
+        let step = SubSagaStep::new(&shipping.bus, "shipping", "book_carrier");
+        let child_saga_id = step.child_saga_id(&parent());
+
+        let Ok(StepOutput::Completed {
+            compensation_data, ..
+        }) = shipping.run(&step).await
+        else {
+            panic!("completed child should complete the parent step");
+        };
+        // The carrier keeps the completed child, compensation data included.
+        assert!(shipping.holds(child_saga_id));
+
+        step.compensate(&parent().for_compensation(), &compensation_data)
+            .expect("the rollback should be requested");
+        shipping.pump();
+
+        assert_eq!(shipping.carrier.compensated_with, vec![vec![7]]);
+        assert!(!shipping.holds(child_saga_id));
+        assert!(shipping.carrier.is_terminal_saga_latched(child_saga_id));
+    }
+
+    #[tokio::test]
+    async fn completed_child_is_released_once_its_parent_completes() {
+        let mut shipping = Shipping::new(&["book_carrier"]);
+        let step = SubSagaStep::new(&shipping.bus, "shipping", "book_carrier");
+        let child_saga_id = step.child_saga_id(&parent());
+
+        assert!(shipping.run(&step).await.is_ok());
+        assert!(shipping.holds(child_saga_id));
+
+        shipping
+            .bus
+            .publish_strict(SagaChoreographyEvent::SagaCompleted { context: parent() })
+            .expect("parent completion should publish");
+        shipping.pump();
+
+        assert!(shipping
+            .recorded
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|event| matches!(
+                event,
+                SagaChoreographyEvent::SagaCompleted { context }
+                    if context.saga_id == child_saga_id && context.parent_saga_id.is_none()
+            )));
+        assert!(!shipping.holds(child_saga_id));
+        assert!(shipping.carrier.compensated_with.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timed_out_child_is_rolled_back_by_its_participants() {
+        // Nobody prints the label, so the child never completes.
+        let mut shipping = Shipping::new(&["book_carrier", "print_label"]);
+        let step = SubSagaStep::new(&shipping.bus, "shipping", "book_carrier")
+            .with_timeout(Duration::from_millis(50));
+
+        let result = shipping.run(&step).await;
+
+        assert!(matches!(result, Err(StepError::RequireCompensation { .. })));
+        assert!(shipping
+            .recorded
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|event| matches!(event, SagaChoreographyEvent::CompensationRequested { .. })));
+        shipping.pump();
+        assert_eq!(shipping.carrier.compensated_with, vec![vec![7]]);
+        assert!(shipping
+            .carrier
+            .is_terminal_saga_latched(step.child_saga_id(&parent())));
+    }
+}
@@ -4,9 +4,18 @@ use std::collections::{HashMap, HashSet, VecDeque};
 
 use icanact_core::local::PublishStats;
 
+use crate::async_storage::{
+    journal_mark_outgoing_sent, journal_pending_outgoing, journal_record_outgoing,
+};
 use crate::{
-    ParticipantDedupeStore, ParticipantJournal, ParticipantStats, SagaChoreographyBus,
-    SagaChoreographyEvent, SagaId, SagaStateEntry,
+    dead_letter_event, replay_dead_letters, ArchiveStore, DeadLetterPayload, DeadLetterReason,
+    DeadLetterStore, DedupeIdentity, EffectCorrelationIndex, EffectDispatcher, EffectLedger,
+    EventSkewWindow, JournalFailurePolicy, MissingStatePolicy, ParticipantDedupeStore,
+    ParticipantJournal, ParticipantStateStore, ParticipantStats, PartitionAssigner, PayloadCipher,
+    PayloadStore, QuarantineManager, QuarantinedSaga, RateLimitGate, SagaChoreographyBus,
+    SagaChoreographyEvent, SagaContext, SagaEventFilter, SagaId, SagaObserver, SagaReorderWindow,
+    SagaStateEntry, SharedSagaProjection, StatsPersistence, StatsPersistenceError, StepExecutor,
+    StepLease, StepLeaseError, StepLeases,
 };
 
 /// Embedded choreography capability owned by a saga-enabled participant.
@@ -26,9 +35,110 @@ where
     pub terminal_saga_order: VecDeque<SagaId>,
     pub journal: J,
     pub dedupe: D,
+    /// How incoming events are keyed for `dedupe`.
+    pub dedupe_identity: DedupeIdentity,
     pub stats: ParticipantStats,
+    /// Restores `stats` on attach and saves them every
+    /// `stats_persist_interval_millis` as events come in.
+    pub stats_persistence: Option<std::sync::Arc<dyn StatsPersistence>>,
+    pub stats_persist_interval_millis: u64,
+    pub(crate) stats_persisted_at_millis: std::sync::atomic::AtomicU64,
     pub startup_recovery_events: Vec<SagaChoreographyEvent>,
     pub bus: Option<SagaChoreographyBus>,
+    pub dead_letters: Option<std::sync::Arc<dyn DeadLetterStore>>,
+    /// Dead-letters incoming events of saga types the participant does not
+    /// handle as [`DeadLetterReason::UnknownSaga`] instead of dropping them.
+    /// Off by default: on a shared bus most such events belong to other
+    /// participants.
+    pub dead_letter_unknown_sagas: bool,
+    /// Receives each saga's history right before its terminal prune.
+    pub archive: Option<std::sync::Arc<dyn ArchiveStore>>,
+    /// Carries out effects of `StepOutput::CompletedWithEffect`.
+    pub effects: Option<std::sync::Arc<dyn EffectDispatcher>>,
+    /// Indexed with the correlation keys of completed steps.
+    pub correlations: Option<std::sync::Arc<EffectCorrelationIndex>>,
+    /// Holds step outputs larger than `payload_offload_threshold` bytes.
+    pub payloads: Option<std::sync::Arc<dyn PayloadStore>>,
+    pub payload_offload_threshold: usize,
+    /// Seals compensation data while the participant holds it.
+    pub compensation_cipher: Option<std::sync::Arc<dyn PayloadCipher>>,
+    pub quarantine: Option<QuarantineManager>,
+    pub journal_failure_policy: JournalFailurePolicy,
+    /// Consulted by transitions that find no state entry for their saga.
+    pub missing_state_policy: MissingStatePolicy,
+    /// Told about step retries.
+    pub observer: Option<std::sync::Arc<dyn SagaObserver>>,
+    /// Incoming events stamped outside this window are rejected; unset
+    /// accepts any timestamp.
+    pub event_skew_window: Option<EventSkewWindow>,
+    /// Incoming events that do not match are dropped before the journal
+    /// inbox and dedupe store see them.
+    pub event_filter: Option<SagaEventFilter>,
+    /// Incoming events of sagas another replica owns are dropped like
+    /// filtered ones.
+    pub partitions: Option<std::sync::Arc<dyn PartitionAssigner>>,
+    /// Read models fed every incoming event that passes the dedupe check.
+    pub projections: Vec<SharedSagaProjection>,
+    /// Claims each step in a store shared with other replicas before
+    /// executing it.
+    pub step_leases: Option<StepLeases>,
+    /// Receives each saga's current state on every transition, for recovery
+    /// without a journal replay.
+    pub state_store: Option<std::sync::Arc<dyn ParticipantStateStore>>,
+    /// Events parked by [`JournalFailurePolicy::Park`] or the rate limit gate
+    /// that the journal inbox could not hold either; redelivered by the inbox
+    /// replay helpers.
+    pub parked_events: Vec<SagaChoreographyEvent>,
+    pub(crate) park_requested: bool,
+    /// Set by the bus ingress while it handles an event, whose emitted
+    /// events it publishes with [`publish_emitted`](Self::publish_emitted);
+    /// only then are outcomes staged with their journal append.
+    pub(crate) stage_emitted: bool,
+    /// Emitted events staged in the outbox with the journal append that
+    /// decided them, until the bus ingress publishes them.
+    pub(crate) staged_outgoing: StagedOutgoing,
+    /// Journal append retries taken per saga under
+    /// [`JournalFailurePolicy::Retry`].
+    pub(crate) journal_retries: HashMap<SagaId, u32>,
+    pub(crate) journal_retry_at: Option<u64>,
+    /// Step and compensation outcomes held back until the journal records
+    /// them, under [`JournalFailurePolicy::Park`] or
+    /// [`JournalFailurePolicy::Retry`].
+    pub(crate) unjournaled_outcomes: Vec<crate::helpers::UnjournaledOutcome>,
+    /// Takes a token before each step starts; steps denied one are parked
+    /// until the inbox is replayed.
+    pub rate_limit: Option<std::sync::Arc<dyn RateLimitGate>>,
+    pub(crate) rate_limited_until: Option<u64>,
+    /// Most steps running on the step executor at once; further triggers
+    /// stay `Triggered` and are parked until one settles or times out.
+    pub max_in_flight_steps: Option<usize>,
+    pub(crate) park_in_flight: bool,
+    /// Triggers parked by the in-flight cap, replayed in order once a slot
+    /// frees up.
+    pub(crate) in_flight_parked: Vec<crate::helpers::PendingIncoming>,
+    /// How long terminal entries stay in `saga_states`; unset keeps them
+    /// until the saga is pruned.
+    pub terminal_state_ttl_millis: Option<u64>,
+    pub(crate) terminal_swept_at_millis: u64,
+    /// Holds events that arrive before their saga's `SagaStarted`.
+    pub reorder_window: Option<SagaReorderWindow>,
+    pub(crate) reorder: crate::reorder::ReorderBuffer,
+    /// Set by [`crate::drain`]; the handlers refuse new `SagaStarted` events
+    /// while it is.
+    pub draining: bool,
+    /// Advanced past every incoming event and stamped on every emitted one.
+    pub logical_clock: crate::LamportClock,
+    /// Runs steps that offload their work, failing those still running
+    /// after the timeout.
+    pub(crate) offloaded: Option<crate::step_executor::OffloadedSteps>,
+    /// Time source for [`crate::SagaStateExt::now_millis`]; wall clock when unset.
+    pub clock: Option<std::sync::Arc<dyn Fn() -> u64 + Send + Sync>>,
+    /// Receives the wall time of every `execute_step` call.
+    #[cfg(feature = "hdr")]
+    pub step_latency: Option<crate::LatencyHandle>,
+    /// Receives the wall time of every `compensate_step` call.
+    #[cfg(feature = "hdr")]
+    pub compensation_latency: Option<crate::LatencyHandle>,
 }
 
 impl<J, D> SagaParticipantSupport<J, D>
@@ -45,9 +155,55 @@ where
             terminal_saga_order: VecDeque::new(),
             journal,
             dedupe,
+            dedupe_identity: DedupeIdentity::default(),
             stats: ParticipantStats::new(),
+            stats_persistence: None,
+            stats_persist_interval_millis: crate::DEFAULT_STATS_PERSIST_INTERVAL_MILLIS,
+            stats_persisted_at_millis: std::sync::atomic::AtomicU64::new(0),
             startup_recovery_events: Vec::new(),
             bus: None,
+            dead_letters: None,
+            dead_letter_unknown_sagas: false,
+            archive: None,
+            effects: None,
+            correlations: None,
+            payloads: None,
+            payload_offload_threshold: crate::DEFAULT_PAYLOAD_OFFLOAD_THRESHOLD,
+            compensation_cipher: None,
+            quarantine: None,
+            journal_failure_policy: JournalFailurePolicy::Continue,
+            missing_state_policy: MissingStatePolicy::Ignore,
+            observer: None,
+            event_skew_window: None,
+            event_filter: None,
+            partitions: None,
+            projections: Vec::new(),
+            step_leases: None,
+            state_store: None,
+            parked_events: Vec::new(),
+            park_requested: false,
+            stage_emitted: false,
+            staged_outgoing: StagedOutgoing::default(),
+            journal_retries: HashMap::new(),
+            journal_retry_at: None,
+            unjournaled_outcomes: Vec::new(),
+            rate_limit: None,
+            rate_limited_until: None,
+            max_in_flight_steps: None,
+            park_in_flight: false,
+            in_flight_parked: Vec::new(),
+            terminal_state_ttl_millis: None,
+            terminal_swept_at_millis: 0,
+            reorder_window: None,
+            reorder: crate::reorder::ReorderBuffer::default(),
+            draining: false,
+            logical_clock: crate::LamportClock::new(),
+            offloaded: None,
+            clock: None,
+            #[cfg(feature = "hdr")]
+            step_latency: None,
+            #[cfg(feature = "hdr")]
+            compensation_latency: None,
         }
     }
 
@@ -64,6 +220,398 @@ where
         self.bus = Some(bus);
     }
 
+    pub fn with_dedupe_identity(mut self, identity: DedupeIdentity) -> Self {
+        self.dedupe_identity = identity;
+        self
+    }
+
+    pub fn with_dead_letter_store(mut self, store: std::sync::Arc<dyn DeadLetterStore>) -> Self {
+        self.dead_letters = Some(store);
+        self
+    }
+
+    pub fn attach_dead_letter_store(&mut self, store: std::sync::Arc<dyn DeadLetterStore>) {
+        self.dead_letters = Some(store);
+    }
+
+    /// Routes incoming events of unhandled saga types to the dead-letter
+    /// store; see [`Self::dead_letter_unknown_sagas`].
+    pub fn with_unknown_saga_dead_letters(mut self) -> Self {
+        self.dead_letter_unknown_sagas = true;
+        self
+    }
+
+    pub fn with_archive_store(mut self, store: std::sync::Arc<dyn ArchiveStore>) -> Self {
+        self.archive = Some(store);
+        self
+    }
+
+    pub fn attach_archive_store(&mut self, store: std::sync::Arc<dyn ArchiveStore>) {
+        self.archive = Some(store);
+    }
+
+    pub fn with_effect_dispatcher(
+        mut self,
+        dispatcher: std::sync::Arc<dyn EffectDispatcher>,
+    ) -> Self {
+        self.effects = Some(dispatcher);
+        self
+    }
+
+    pub fn attach_effect_dispatcher(&mut self, dispatcher: std::sync::Arc<dyn EffectDispatcher>) {
+        self.effects = Some(dispatcher);
+    }
+
+    pub fn with_correlation_index(mut self, index: std::sync::Arc<EffectCorrelationIndex>) -> Self {
+        self.correlations = Some(index);
+        self
+    }
+
+    pub fn attach_correlation_index(&mut self, index: std::sync::Arc<EffectCorrelationIndex>) {
+        self.correlations = Some(index);
+    }
+
+    /// Adds the snapshot saved in `store` to `stats` and keeps saving to it.
+    pub fn with_stats_persistence(mut self, store: std::sync::Arc<dyn StatsPersistence>) -> Self {
+        self.attach_stats_persistence(store);
+        self
+    }
+
+    pub fn attach_stats_persistence(&mut self, store: std::sync::Arc<dyn StatsPersistence>) {
+        match store.load_stats() {
+            Ok(Some(persisted)) => self.stats.restore(&persisted),
+            Ok(None) => {}
+            Err(err) => {
+                tracing::warn!(
+                    target: "core::saga",
+                    event = "saga_stats_restore_failed",
+                    error = %err
+                );
+            }
+        }
+        self.stats_persisted_at_millis
+            .store(self.now_millis(), std::sync::atomic::Ordering::Relaxed);
+        self.stats_persistence = Some(store);
+    }
+
+    pub fn with_stats_persist_interval(mut self, interval: std::time::Duration) -> Self {
+        self.stats_persist_interval_millis =
+            u64::try_from(interval.as_millis()).unwrap_or(u64::MAX);
+        self
+    }
+
+    /// Saves a snapshot of `stats` now, e.g. before shutdown. A no-op
+    /// without a [`StatsPersistence`] attached.
+    pub fn persist_stats(&self) -> Result<(), StatsPersistenceError> {
+        let Some(store) = &self.stats_persistence else {
+            return Ok(());
+        };
+        self.stats_persisted_at_millis
+            .store(self.now_millis(), std::sync::atomic::Ordering::Relaxed);
+        store.save_stats(&self.stats.snapshot())
+    }
+
+    fn now_millis(&self) -> u64 {
+        self.clock
+            .as_ref()
+            .map_or_else(SagaContext::now_millis, |clock| clock())
+    }
+
+    /// Saves `stats` if the persist interval has passed; failures are
+    /// logged and retried on the next interval.
+    pub(crate) fn persist_stats_if_due(&self) {
+        if self.stats_persistence.is_none() {
+            return;
+        }
+        let saved_at = self
+            .stats_persisted_at_millis
+            .load(std::sync::atomic::Ordering::Relaxed);
+        if self.now_millis().saturating_sub(saved_at) < self.stats_persist_interval_millis {
+            return;
+        }
+        if let Err(err) = self.persist_stats() {
+            tracing::warn!(
+                target: "core::saga",
+                event = "saga_stats_persist_failed",
+                error = %err
+            );
+        }
+    }
+
+    pub fn with_payload_store(mut self, store: std::sync::Arc<dyn PayloadStore>) -> Self {
+        self.payloads = Some(store);
+        self
+    }
+
+    pub fn attach_payload_store(&mut self, store: std::sync::Arc<dyn PayloadStore>) {
+        self.payloads = Some(store);
+    }
+
+    /// Offloads step outputs larger than `bytes` once a payload store is
+    /// attached.
+    pub fn with_payload_offload_threshold(mut self, bytes: usize) -> Self {
+        self.payload_offload_threshold = bytes;
+        self
+    }
+
+    pub fn with_compensation_cipher(mut self, cipher: std::sync::Arc<dyn PayloadCipher>) -> Self {
+        self.compensation_cipher = Some(cipher);
+        self
+    }
+
+    pub fn attach_compensation_cipher(&mut self, cipher: std::sync::Arc<dyn PayloadCipher>) {
+        self.compensation_cipher = Some(cipher);
+    }
+
+    pub fn with_quarantine_manager(mut self, manager: QuarantineManager) -> Self {
+        self.quarantine = Some(manager);
+        self
+    }
+
+    pub fn attach_quarantine_manager(&mut self, manager: QuarantineManager) {
+        self.quarantine = Some(manager);
+    }
+
+    pub fn with_journal_failure_policy(mut self, policy: JournalFailurePolicy) -> Self {
+        self.journal_failure_policy = policy;
+        self
+    }
+
+    pub fn with_missing_state_policy(mut self, policy: MissingStatePolicy) -> Self {
+        self.missing_state_policy = policy;
+        self
+    }
+
+    pub fn with_observer(mut self, observer: std::sync::Arc<dyn SagaObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    pub fn attach_observer(&mut self, observer: std::sync::Arc<dyn SagaObserver>) {
+        self.observer = Some(observer);
+    }
+
+    pub fn with_event_skew_window(mut self, window: EventSkewWindow) -> Self {
+        self.event_skew_window = Some(window);
+        self
+    }
+
+    pub fn with_event_filter(mut self, filter: SagaEventFilter) -> Self {
+        self.event_filter = Some(filter);
+        self
+    }
+
+    /// Processes only the sagas `assigner` gives this replica.
+    pub fn with_partition_assigner(
+        mut self,
+        assigner: std::sync::Arc<dyn PartitionAssigner>,
+    ) -> Self {
+        self.partitions = Some(assigner);
+        self
+    }
+
+    pub fn with_projection(mut self, projection: SharedSagaProjection) -> Self {
+        self.projections.push(projection);
+        self
+    }
+
+    pub fn attach_projection(&mut self, projection: SharedSagaProjection) {
+        self.projections.push(projection);
+    }
+
+    pub fn with_step_leases(mut self, leases: StepLeases) -> Self {
+        self.step_leases = Some(leases);
+        self
+    }
+
+    pub fn attach_step_leases(&mut self, leases: StepLeases) {
+        self.step_leases = Some(leases);
+    }
+
+    pub fn with_state_store(mut self, store: std::sync::Arc<dyn ParticipantStateStore>) -> Self {
+        self.state_store = Some(store);
+        self
+    }
+
+    pub fn attach_state_store(&mut self, store: std::sync::Arc<dyn ParticipantStateStore>) {
+        self.state_store = Some(store);
+    }
+
+    pub fn with_rate_limit_gate(mut self, gate: std::sync::Arc<dyn RateLimitGate>) -> Self {
+        self.rate_limit = Some(gate);
+        self
+    }
+
+    pub fn attach_rate_limit_gate(&mut self, gate: std::sync::Arc<dyn RateLimitGate>) {
+        self.rate_limit = Some(gate);
+    }
+
+    pub fn with_reorder_window(mut self, window: SagaReorderWindow) -> Self {
+        self.reorder_window = Some(window);
+        self
+    }
+
+    /// Events held for a `SagaStarted` that has not arrived yet.
+    pub fn held_out_of_order_len(&self) -> usize {
+        self.reorder.held_len()
+    }
+
+    /// Earliest time a step parked by the rate limit gate may get its token,
+    /// i.e. when to replay the inbox. `None` while no step is parked.
+    pub fn rate_limited_until(&self) -> Option<u64> {
+        self.rate_limited_until
+    }
+
+    /// When a step or outcome held back by a [`JournalFailurePolicy::Retry`]
+    /// backoff may retry its journal append, i.e. when to replay the inbox.
+    /// `None` while nothing waits on a retry.
+    pub fn journal_retry_at(&self) -> Option<u64> {
+        self.journal_retry_at
+    }
+
+    /// Caps the offloaded steps running at once at `max`; see
+    /// [`Self::with_step_executor`]. Steps run on the actor finish within
+    /// the event that started them, and `Executing` entries restored from
+    /// the journal have no worker, so neither takes a slot.
+    pub fn with_max_in_flight_steps(mut self, max: usize) -> Self {
+        self.max_in_flight_steps = Some(max);
+        self
+    }
+
+    /// Drops terminal entries from `saga_states` once they are older than
+    /// `ttl`. The handlers sweep at most once per `ttl`, so an entry goes
+    /// between one and two `ttl`s after the saga went terminal.
+    pub fn with_terminal_state_ttl(mut self, ttl: std::time::Duration) -> Self {
+        self.terminal_state_ttl_millis = Some(ttl.as_millis() as u64);
+        self
+    }
+
+    /// Extends this replica's lease on `step_name` of `saga_id`. Steps that
+    /// may outlast the lease TTL call this periodically; an error means
+    /// another replica has taken the step over and this run should stop.
+    /// Returns `None` without leases attached.
+    pub fn renew_step_lease(
+        &self,
+        saga_id: SagaId,
+        step_name: &str,
+    ) -> Option<Result<StepLease, StepLeaseError>> {
+        let leases = self.step_leases.as_ref()?;
+        let now = self
+            .clock
+            .as_ref()
+            .map_or_else(SagaContext::now_millis, |clock| clock());
+        Some(leases.claim(saga_id, step_name, now))
+    }
+
+    /// Runs the work of steps returned by
+    /// [`crate::SagaParticipant::offload_step`] on `executor`, failing each
+    /// one not done within `timeout`. A step that succeeds after its timeout
+    /// is compensated.
+    pub fn with_step_executor(
+        mut self,
+        executor: std::sync::Arc<dyn StepExecutor>,
+        timeout: std::time::Duration,
+    ) -> Self {
+        self.offloaded = Some(crate::step_executor::OffloadedSteps::new(
+            executor,
+            timeout.as_millis() as u64,
+        ));
+        self
+    }
+
+    /// Offloaded steps whose result has not been picked up yet.
+    pub fn offloaded_steps(&self) -> usize {
+        self.offloaded
+            .as_ref()
+            .map_or(0, |offloaded| offloaded.running.len())
+    }
+
+    pub fn with_clock(mut self, clock: std::sync::Arc<dyn Fn() -> u64 + Send + Sync>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Records step execution latency into `recorder`.
+    #[cfg(feature = "hdr")]
+    pub fn with_step_latency(mut self, recorder: &crate::LatencyRecorder) -> Self {
+        self.step_latency = Some(recorder.handle());
+        self
+    }
+
+    /// Records compensation latency into `recorder`.
+    #[cfg(feature = "hdr")]
+    pub fn with_compensation_latency(mut self, recorder: &crate::LatencyRecorder) -> Self {
+        self.compensation_latency = Some(recorder.handle());
+        self
+    }
+
+    /// Merges pending latency samples so the next snapshot includes them.
+    #[cfg(feature = "hdr")]
+    pub fn flush_latency(&mut self) {
+        for handle in [&mut self.step_latency, &mut self.compensation_latency]
+            .into_iter()
+            .flatten()
+        {
+            handle.flush();
+        }
+    }
+
+    /// Effect ledger of `saga_id`, journaled in this participant's journal.
+    pub fn effect_ledger(&self, saga_id: SagaId) -> EffectLedger<'_, J> {
+        EffectLedger::new(&self.journal, saga_id)
+    }
+
+    /// Reports a step that moved to `Quarantined` to the attached manager,
+    /// if any. This is where the compensation data is captured, since the
+    /// participant prunes it once the saga goes terminal.
+    pub fn report_quarantined(&self, saga: QuarantinedSaga) {
+        if let Some(manager) = &self.quarantine {
+            manager.record(saga);
+        }
+    }
+
+    /// Routes `event` to the attached dead-letter store. Without a store the
+    /// event is only logged, matching the behavior before dead-lettering.
+    pub fn dead_letter(
+        &self,
+        reason: DeadLetterReason,
+        detail: &str,
+        event: SagaChoreographyEvent,
+    ) -> Option<u64> {
+        match &self.dead_letters {
+            Some(store) => dead_letter_event(store.as_ref(), reason, detail, event),
+            None => {
+                tracing::warn!(
+                    target: "core::saga",
+                    event = "saga_event_dropped_without_dead_letter_store",
+                    saga_id = event.context().saga_id.get(),
+                    reason = reason.as_str(),
+                    detail
+                );
+                None
+            }
+        }
+    }
+
+    /// Re-publishes dead-lettered events on the attached bus and removes the
+    /// ones the bus accepted. Raw undecodable payloads are left in the store.
+    pub fn replay_dead_letters(&self) -> Result<usize, String> {
+        let Some(store) = &self.dead_letters else {
+            return Err("dead-letter store is not attached".to_string());
+        };
+        let Some(bus) = &self.bus else {
+            return Err("saga bus is not attached".to_string());
+        };
+        replay_dead_letters(store.as_ref(), |entry| match &entry.payload {
+            DeadLetterPayload::Event(event) => bus
+                .publish_strict(event.clone())
+                .map(|_| ())
+                .map_err(|err| format!("saga bus strict publish failed: {err:?}")),
+            DeadLetterPayload::Raw(_) => Err("raw payload needs a decoder to replay".to_string()),
+        })
+        .map_err(|err| format!("dead-letter replay failed: {err}"))
+    }
+
     pub fn publish(&self, event: SagaChoreographyEvent) -> Result<PublishStats, String> {
         if let Some(bus) = &self.bus {
             bus.publish_strict(event)
@@ -72,6 +620,273 @@ where
             Err("saga bus is not attached".to_string())
         }
     }
+
+    /// Publishes `event` through the journal outbox.
+    ///
+    /// The event is staged with [`ParticipantJournal::record_outgoing`] before
+    /// it reaches the bus and marked sent once the bus accepts it, so a crash
+    /// in between or a failed publish leaves it pending for
+    /// [`relay_outbox`](Self::relay_outbox) instead of losing it. Nothing is
+    /// published if staging fails.
+    pub fn publish_with_outbox(
+        &self,
+        event: SagaChoreographyEvent,
+    ) -> Result<PublishStats, String> {
+        let Some(bus) = &self.bus else {
+            return Err("saga bus is not attached".to_string());
+        };
+        let saga_id = event.context().saga_id;
+        let outbox_id = self
+            .journal
+            .record_outgoing(saga_id, &event)
+            .map_err(|err| format!("saga outbox record failed: {err}"))?;
+        let published = bus
+            .publish_strict(event)
+            .map_err(|err| format!("saga bus strict publish failed: {err:?}"));
+        if let (Ok(_), Some(outbox_id)) = (&published, outbox_id) {
+            self.mark_outgoing_sent(saga_id, outbox_id);
+        }
+        published
+    }
+
+    /// Publishes an event the ingress helpers emitted. One staged with the
+    /// journal append that decided it is marked sent once the bus accepts
+    /// it; any other goes through [`publish_with_outbox`](Self::publish_with_outbox).
+    pub(crate) fn publish_emitted(
+        &self,
+        event: SagaChoreographyEvent,
+    ) -> Result<PublishStats, String> {
+        let Some(outbox_id) = self.staged_outgoing.take(&event) else {
+            return self.publish_with_outbox(event);
+        };
+        let Some(bus) = &self.bus else {
+            return Err("saga bus is not attached".to_string());
+        };
+        let saga_id = event.context().saga_id;
+        let published = bus
+            .publish_strict(event)
+            .map_err(|err| format!("saga bus strict publish failed: {err:?}"));
+        if published.is_ok() {
+            self.mark_outgoing_sent(saga_id, outbox_id);
+        }
+        published
+    }
+
+    /// Drops the outbox entry staged for an emitted event that is not
+    /// published, e.g. an invalid transition sent to the dead letters.
+    pub(crate) fn discard_emitted(&self, event: &SagaChoreographyEvent) {
+        if let Some(outbox_id) = self.staged_outgoing.take(event) {
+            self.mark_outgoing_sent(event.context().saga_id, outbox_id);
+        }
+    }
+
+    /// Re-publishes every outbox event left pending by an interrupted
+    /// [`publish_with_outbox`](Self::publish_with_outbox) or a failed publish.
+    ///
+    /// Returns the number of relayed events; those the bus rejects again stay
+    /// pending. Receivers dedupe on the event identity, so relaying an event
+    /// that was in fact delivered is harmless. `SagaStarted` entries belong
+    /// to a [`crate::SagaInitiator`] sharing the journal, whose redelivery
+    /// tracks them until every step acknowledges, and are left alone.
+    pub fn relay_outbox(&self) -> Result<usize, String> {
+        let Some(bus) = &self.bus else {
+            return Err("saga bus is not attached".to_string());
+        };
+        let pending = self
+            .journal
+            .pending_outgoing()
+            .map_err(|err| format!("saga outbox read failed: {err}"))?;
+        let mut relayed = 0;
+        for entry in pending {
+            if matches!(entry.event, SagaChoreographyEvent::SagaStarted { .. }) {
+                continue;
+            }
+            match bus.publish_strict(entry.event) {
+                Ok(_) => {
+                    self.mark_outgoing_sent(entry.saga_id, entry.outbox_id);
+                    relayed += 1;
+                }
+                Err(err) => tracing::warn!(
+                    target: "core::saga",
+                    event = "saga_outbox_relay_publish_failed",
+                    saga_id = entry.saga_id.get(),
+                    outbox_id = entry.outbox_id,
+                    error = ?err
+                ),
+            }
+        }
+        Ok(relayed)
+    }
+
+    /// [`publish_with_outbox`](Self::publish_with_outbox) for async
+    /// participants, awaiting an async journal.
+    pub async fn publish_with_outbox_async(
+        &self,
+        event: SagaChoreographyEvent,
+    ) -> Result<PublishStats, String> {
+        let Some(bus) = &self.bus else {
+            return Err("saga bus is not attached".to_string());
+        };
+        let saga_id = event.context().saga_id;
+        let outbox_id = journal_record_outgoing(&self.journal, saga_id, &event)
+            .await
+            .map_err(|err| format!("saga outbox record failed: {err}"))?;
+        let published = bus
+            .publish_strict(event)
+            .map_err(|err| format!("saga bus strict publish failed: {err:?}"));
+        if let (Ok(_), Some(outbox_id)) = (&published, outbox_id) {
+            self.mark_outgoing_sent_async(saga_id, outbox_id).await;
+        }
+        published
+    }
+
+    /// [`publish_emitted`](Self::publish_emitted) for the async ingress.
+    pub(crate) async fn publish_emitted_async(
+        &self,
+        event: SagaChoreographyEvent,
+    ) -> Result<PublishStats, String> {
+        let Some(outbox_id) = self.staged_outgoing.take(&event) else {
+            return self.publish_with_outbox_async(event).await;
+        };
+        let Some(bus) = &self.bus else {
+            return Err("saga bus is not attached".to_string());
+        };
+        let saga_id = event.context().saga_id;
+        let published = bus
+            .publish_strict(event)
+            .map_err(|err| format!("saga bus strict publish failed: {err:?}"));
+        if published.is_ok() {
+            self.mark_outgoing_sent_async(saga_id, outbox_id).await;
+        }
+        published
+    }
+
+    /// [`discard_emitted`](Self::discard_emitted) for the async ingress.
+    pub(crate) async fn discard_emitted_async(&self, event: &SagaChoreographyEvent) {
+        if let Some(outbox_id) = self.staged_outgoing.take(event) {
+            self.mark_outgoing_sent_async(event.context().saga_id, outbox_id)
+                .await;
+        }
+    }
+
+    /// [`relay_outbox`](Self::relay_outbox) for async participants, awaiting
+    /// an async journal.
+    pub async fn relay_outbox_async(&self) -> Result<usize, String> {
+        let Some(bus) = &self.bus else {
+            return Err("saga bus is not attached".to_string());
+        };
+        let pending = journal_pending_outgoing(&self.journal)
+            .await
+            .map_err(|err| format!("saga outbox read failed: {err}"))?;
+        let mut relayed = 0;
+        for entry in pending {
+            if matches!(entry.event, SagaChoreographyEvent::SagaStarted { .. }) {
+                continue;
+            }
+            match bus.publish_strict(entry.event) {
+                Ok(_) => {
+                    self.mark_outgoing_sent_async(entry.saga_id, entry.outbox_id)
+                        .await;
+                    relayed += 1;
+                }
+                Err(err) => tracing::warn!(
+                    target: "core::saga",
+                    event = "saga_outbox_relay_publish_failed",
+                    saga_id = entry.saga_id.get(),
+                    outbox_id = entry.outbox_id,
+                    error = ?err
+                ),
+            }
+        }
+        Ok(relayed)
+    }
+
+    fn mark_outgoing_sent(&self, saga_id: SagaId, outbox_id: u64) {
+        let marked = self.journal.mark_outgoing_sent(outbox_id);
+        outgoing_marked(saga_id, outbox_id, marked);
+    }
+
+    async fn mark_outgoing_sent_async(&self, saga_id: SagaId, outbox_id: u64) {
+        let marked = journal_mark_outgoing_sent(&self.journal, outbox_id).await;
+        outgoing_marked(saga_id, outbox_id, marked);
+    }
+}
+
+fn outgoing_marked(saga_id: SagaId, outbox_id: u64, marked: Result<(), crate::JournalError>) {
+    if let Err(err) = marked {
+        tracing::error!(
+            target: "core::saga",
+            event = "saga_outbox_mark_sent_failed",
+            saga_id = saga_id.get(),
+            outbox_id,
+            error = %err
+        );
+    }
+}
+
+/// Emitted events staged in the journal outbox by
+/// [`crate::SagaStateExt::record_event_with_outgoing_strict`], keyed on the
+/// stamped event identity. Shared with the dispatch emit wrapper, which must
+/// not stamp a staged event again.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct StagedOutgoing(std::sync::Arc<std::sync::Mutex<Vec<StagedEvent>>>);
+
+#[derive(Debug)]
+struct StagedEvent {
+    key: StagedKey,
+    outbox_id: u64,
+}
+
+/// Saga, type, step and Lamport stamp; stamps are unique per clock, so two
+/// outcomes of one type for a saga never share a key.
+type StagedKey = (SagaId, &'static str, crate::StepName, u64);
+
+impl StagedOutgoing {
+    fn key(event: &SagaChoreographyEvent) -> StagedKey {
+        let context = event.context();
+        (
+            context.saga_id,
+            event.event_type(),
+            context.step_name.clone(),
+            context.logical_clock,
+        )
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<StagedEvent>> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub(crate) fn stage(&self, outgoing: &[SagaChoreographyEvent], outbox_ids: Vec<Option<u64>>) {
+        let mut staged = self.lock();
+        for (event, outbox_id) in outgoing.iter().zip(outbox_ids) {
+            if let Some(outbox_id) = outbox_id {
+                staged.push(StagedEvent {
+                    key: Self::key(event),
+                    outbox_id,
+                });
+            }
+        }
+    }
+
+    pub(crate) fn contains(&self, event: &SagaChoreographyEvent) -> bool {
+        let key = Self::key(event);
+        self.lock().iter().any(|staged| staged.key == key)
+    }
+
+    fn take(&self, event: &SagaChoreographyEvent) -> Option<u64> {
+        let key = Self::key(event);
+        let mut staged = self.lock();
+        let index = staged.iter().position(|staged| staged.key == key)?;
+        Some(staged.remove(index).outbox_id)
+    }
+
+    /// Forgets what the ingress did not publish; those entries stay pending
+    /// in the outbox for the relay.
+    pub(crate) fn clear(&self) {
+        self.lock().clear();
+    }
 }
 
 impl<J, D> std::fmt::Debug for SagaParticipantSupport<J, D>
@@ -93,8 +908,30 @@ where
                 "startup_recovery_events_len",
                 &self.startup_recovery_events.len(),
             )
+            .field("dedupe_identity", &self.dedupe_identity)
             .field("bus_attached", &self.bus.is_some())
+            .field("dead_letters_attached", &self.dead_letters.is_some())
+            .field("dead_letter_unknown_sagas", &self.dead_letter_unknown_sagas)
+            .field("quarantine_attached", &self.quarantine.is_some())
+            .field("observer_attached", &self.observer.is_some())
+            .field("partitions_attached", &self.partitions.is_some())
+            .field("projections_len", &self.projections.len())
+            .field("state_store_attached", &self.state_store.is_some())
+            .field("parked_events_len", &self.parked_events.len())
+            .field("rate_limit_attached", &self.rate_limit.is_some())
+            .field("rate_limited_until", &self.rate_limited_until)
+            .field("journal_retry_at", &self.journal_retry_at)
+            .field("unjournaled_outcomes_len", &self.unjournaled_outcomes.len())
+            .field("max_in_flight_steps", &self.max_in_flight_steps)
+            .field("terminal_state_ttl_millis", &self.terminal_state_ttl_millis)
+            .field("held_out_of_order_len", &self.reorder.held_len())
+            .field("draining", &self.draining)
+            .field("offloaded_steps", &self.offloaded_steps())
             .field("stats", &self.stats.snapshot())
+            .field(
+                "stats_persistence_attached",
+                &self.stats_persistence.is_some(),
+            )
             .finish()
     }
 }
@@ -121,13 +958,21 @@ pub trait SagaParticipantSupportExt: HasSagaParticipantSupport {
     fn take_startup_recovery_events(&mut self) -> Vec<SagaChoreographyEvent> {
         self.saga_support_mut().take_startup_recovery_events()
     }
+
+    fn relay_saga_outbox(&self) -> Result<usize, String> {
+        self.saga_support().relay_outbox()
+    }
+
+    fn replay_saga_dead_letters(&self) -> Result<usize, String> {
+        self.saga_support().replay_dead_letters()
+    }
 }
 
 impl<T> SagaParticipantSupportExt for T where T: HasSagaParticipantSupport {}
 
 #[cfg(test)]
 mod tests {
-    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
     use std::sync::Arc;
 
     use crate::{InMemoryDedupe, InMemoryJournal, PeerId, SagaContext, SagaId};
@@ -149,6 +994,9 @@ mod tests {
                 initiator_peer_id: PeerId::default(),
                 saga_started_at_millis: 100,
                 event_timestamp_millis: 100,
+                parent_saga_id: None,
+                workflow_version: crate::DEFAULT_WORKFLOW_VERSION,
+                logical_clock: 1,
             },
             reason: "startup quarantine".into(),
             failure: None,
@@ -191,10 +1039,175 @@ mod tests {
                 initiator_peer_id: PeerId::default(),
                 saga_started_at_millis: 200,
                 event_timestamp_millis: 300,
+                parent_saga_id: None,
+                workflow_version: crate::DEFAULT_WORKFLOW_VERSION,
+                logical_clock: 1,
             },
         });
         assert!(published.is_ok(), "publish should succeed: {published:?}");
 
         assert_eq!(delivered.load(Ordering::Relaxed), 1);
     }
+
+    #[test]
+    fn outbox_relay_republishes_events_left_pending() {
+        let bus = SagaChoreographyBus::new();
+        let delivered = Arc::new(AtomicUsize::new(0));
+        let delivered_clone = Arc::clone(&delivered);
+        let accepting = Arc::new(AtomicBool::new(true));
+        let accepting_clone = Arc::clone(&accepting);
+        let _sub = bus.subscribe_saga_type_fn("order_lifecycle", move |_event| {
+            if !accepting_clone.load(Ordering::Relaxed) {
+                return false;
+            }
+            delivered_clone.fetch_add(1, Ordering::Relaxed);
+            true
+        });
+
+        let mut support =
+            SagaParticipantSupport::new(InMemoryJournal::new(), InMemoryDedupe::new());
+        support.attach_bus(bus);
+        let event = SagaChoreographyEvent::StepStarted {
+            context: SagaContext {
+                saga_id: SagaId::new(12),
+                saga_type: "order_lifecycle".into(),
+                step_name: "risk_check".into(),
+                correlation_id: 12,
+                causation_id: 12,
+                trace_id: 12,
+                step_index: 1,
+                attempt: 0,
+                initiator_peer_id: PeerId::default(),
+                saga_started_at_millis: 200,
+                event_timestamp_millis: 300,
+                parent_saga_id: None,
+                workflow_version: crate::DEFAULT_WORKFLOW_VERSION,
+                logical_clock: 1,
+            },
+        };
+
+        support
+            .publish_with_outbox(event.clone())
+            .expect("outbox publish should succeed");
+        assert_eq!(delivered.load(Ordering::Relaxed), 1);
+        assert!(support
+            .journal
+            .pending_outgoing()
+            .expect("outbox should be readable")
+            .is_empty());
+
+        // Simulate a crash between staging and publishing.
+        support
+            .journal
+            .record_outgoing(SagaId::new(12), &event)
+            .expect("outbox record should succeed");
+        assert_eq!(support.relay_outbox(), Ok(1));
+        assert_eq!(delivered.load(Ordering::Relaxed), 2);
+        assert_eq!(support.relay_outbox(), Ok(0));
+
+        // A rejected publish stays pending until a relay gets it through.
+        accepting.store(false, Ordering::Relaxed);
+        assert!(support.publish_with_outbox(event).is_err());
+        assert_eq!(support.relay_outbox(), Ok(0));
+        accepting.store(true, Ordering::Relaxed);
+        assert_eq!(support.relay_outbox(), Ok(1));
+        assert_eq!(delivered.load(Ordering::Relaxed), 3);
+        assert_eq!(support.relay_outbox(), Ok(0));
+    }
+
+    #[test]
+    fn staged_outcomes_of_one_type_are_told_apart_by_their_stamp() {
+        let context = SagaContext {
+            saga_id: SagaId::new(14),
+            saga_type: "order_lifecycle".into(),
+            step_name: "risk_check".into(),
+            correlation_id: 14,
+            causation_id: 14,
+            trace_id: 14,
+            step_index: 1,
+            attempt: 0,
+            initiator_peer_id: PeerId::default(),
+            saga_started_at_millis: 200,
+            event_timestamp_millis: 300,
+            parent_saga_id: None,
+            workflow_version: crate::DEFAULT_WORKFLOW_VERSION,
+            logical_clock: 4,
+        };
+        let first = SagaChoreographyEvent::CompensationCompleted {
+            context: context.clone(),
+        };
+        let second = SagaChoreographyEvent::CompensationCompleted {
+            context: SagaContext {
+                logical_clock: 7,
+                ..context
+            },
+        };
+        let staged = StagedOutgoing::default();
+        staged.stage(&[first.clone(), second.clone()], vec![Some(1), Some(2)]);
+
+        assert_eq!(staged.take(&second), Some(2));
+        assert!(!staged.contains(&second));
+        assert!(staged.contains(&first));
+        assert_eq!(staged.take(&first), Some(1));
+    }
+
+    #[test]
+    fn outbox_relay_leaves_initiator_starts_pending() {
+        let bus = SagaChoreographyBus::new();
+        let delivered = Arc::new(AtomicUsize::new(0));
+        let delivered_clone = Arc::clone(&delivered);
+        let _sub = bus.subscribe_saga_type_fn("order_lifecycle", move |_event| {
+            delivered_clone.fetch_add(1, Ordering::Relaxed);
+            true
+        });
+
+        let mut support =
+            SagaParticipantSupport::new(InMemoryJournal::new(), InMemoryDedupe::new());
+        support.attach_bus(bus);
+        let context = SagaContext {
+            saga_id: SagaId::new(13),
+            saga_type: "order_lifecycle".into(),
+            step_name: "risk_check".into(),
+            correlation_id: 13,
+            causation_id: 13,
+            trace_id: 13,
+            step_index: 1,
+            attempt: 0,
+            initiator_peer_id: PeerId::default(),
+            saga_started_at_millis: 200,
+            event_timestamp_millis: 300,
+            parent_saga_id: None,
+            workflow_version: crate::DEFAULT_WORKFLOW_VERSION,
+            logical_clock: 1,
+        };
+        support
+            .journal
+            .record_outgoing(
+                SagaId::new(13),
+                &SagaChoreographyEvent::SagaStarted {
+                    context: context.clone(),
+                    payload: Vec::new(),
+                },
+            )
+            .expect("outbox record should succeed");
+        support
+            .journal
+            .record_outgoing(
+                SagaId::new(13),
+                &SagaChoreographyEvent::StepStarted { context },
+            )
+            .expect("outbox record should succeed");
+
+        assert_eq!(support.relay_outbox(), Ok(1));
+        assert_eq!(delivered.load(Ordering::Relaxed), 1);
+        let pending = support
+            .journal
+            .pending_outgoing()
+            .expect("outbox should be readable");
+        assert_eq!(pending.len(), 1);
+        assert!(matches!(
+            pending[0].event,
+            SagaChoreographyEvent::SagaStarted { .. }
+        ));
+    }
 }
@@ -0,0 +1,522 @@
+//! Interned saga-type and step names.
+//!
+//! Every context, state and event carries the saga type and step name, and
+//! they are cloned on each hop. A [`Symbol`] is an `Arc<str>`, so cloning is
+//! a reference-count bump. Names registered with [`Symbol::intern`] (the bus
+//! does so for workflow contracts and bound steps) share one allocation in a
+//! process-wide table, and converting or decoding such a name does not
+//! allocate. Any other name, e.g. one a peer sent, gets an allocation of its
+//! own and never enters the table, so peers cannot grow it; the table holds
+//! the small, fixed set of registered names and is never pruned.
+//!
+//! A symbol archives exactly like `Box<str>`; journals and wire payloads
+//! written before interning still decode.
+//!
+//! [`SagaType`] and [`StepName`] are distinct newtypes over a symbol, so a
+//! step name cannot be passed where a saga type is expected. Both have a
+//! `const` constructor for names declared up front:
+//!
+//! ```
+//! use icanact_saga_choreography::{step_names, DependencySpec, StepName};
+//!
+//! const RISK_CHECK: StepName = StepName::new("risk_check");
+//! let place_order = DependencySpec::After(RISK_CHECK);
+//! let hedge = DependencySpec::AllOf(step_names!["risk_check", "positions_check"]);
+//! assert!(place_order.is_satisfied_by("risk_check"));
+//! assert_eq!(hedge.steps().len(), 2);
+//! ```
+
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, OnceLock, RwLock};
+
+use rkyv::boxed::{ArchivedBox, BoxResolver};
+use rkyv::rancor::Fallible;
+use rkyv::ser::Writer;
+use rkyv::{Archive, Deserialize, Place, Serialize};
+
+/// An interned, cheaply clonable string.
+#[derive(Clone)]
+pub struct Symbol(Arc<str>);
+
+fn table() -> &'static RwLock<HashSet<Arc<str>>> {
+    static TABLE: OnceLock<RwLock<HashSet<Arc<str>>>> = OnceLock::new();
+    TABLE.get_or_init(|| RwLock::new(HashSet::new()))
+}
+
+impl Symbol {
+    /// Returns the shared symbol for `name`, adding it to the table on first
+    /// use. Only for names from a fixed set, such as workflow contracts.
+    pub fn intern(name: &str) -> Self {
+        if let Some(existing) = table()
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(name)
+        {
+            return Self(existing.clone());
+        }
+        let mut names = table()
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(existing) = names.get(name) {
+            return Self(existing.clone());
+        }
+        let interned: Arc<str> = Arc::from(name);
+        names.insert(interned.clone());
+        Self(interned)
+    }
+
+    /// The shared symbol for `name` if it was interned, else an owned one
+    /// outside the table.
+    pub fn lookup(name: &str) -> Self {
+        table()
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(name)
+            .map_or_else(|| Self(Arc::from(name)), |existing| Self(existing.clone()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for Symbol {
+    fn default() -> Self {
+        Self::intern("")
+    }
+}
+
+impl std::ops::Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Symbol {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0) || self.0 == other.0
+    }
+}
+
+impl Eq for Symbol {}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<Symbol> for str {
+    fn eq(&self, other: &Symbol) -> bool {
+        self == &*other.0
+    }
+}
+
+impl PartialEq<Symbol> for &str {
+    fn eq(&self, other: &Symbol) -> bool {
+        *self == &*other.0
+    }
+}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Must match `str` so maps keyed by symbols can be queried by `&str`.
+        self.0.hash(state);
+    }
+}
+
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl std::fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl std::fmt::Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Self::lookup(name)
+    }
+}
+
+impl From<&String> for Symbol {
+    fn from(name: &String) -> Self {
+        Self::lookup(name)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(name: String) -> Self {
+        Self::lookup(&name)
+    }
+}
+
+impl From<Box<str>> for Symbol {
+    fn from(name: Box<str>) -> Self {
+        Self::lookup(&name)
+    }
+}
+
+impl From<&Symbol> for Symbol {
+    fn from(name: &Symbol) -> Self {
+        name.clone()
+    }
+}
+
+impl From<Symbol> for Box<str> {
+    fn from(name: Symbol) -> Self {
+        Box::from(&*name.0)
+    }
+}
+
+impl From<&Symbol> for Box<str> {
+    fn from(name: &Symbol) -> Self {
+        Box::from(&*name.0)
+    }
+}
+
+impl From<Symbol> for String {
+    fn from(name: Symbol) -> Self {
+        String::from(&*name.0)
+    }
+}
+
+impl Archive for Symbol {
+    type Archived = ArchivedBox<str>;
+    type Resolver = BoxResolver;
+
+    fn resolve(&self, resolver: Self::Resolver, out: Place<Self::Archived>) {
+        ArchivedBox::resolve_from_ref(self.as_str(), resolver, out);
+    }
+}
+
+impl<S: Fallible + Writer + ?Sized> Serialize<S> for Symbol {
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        ArchivedBox::serialize_from_ref(self.as_str(), serializer)
+    }
+}
+
+impl<D: Fallible + ?Sized> Deserialize<Symbol, D> for ArchivedBox<str> {
+    fn deserialize(&self, _: &mut D) -> Result<Symbol, D::Error> {
+        Ok(Symbol::lookup(self.get()))
+    }
+}
+
+macro_rules! symbol_newtype {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Clone)]
+        pub struct $name(NameRepr);
+
+        impl $name {
+            /// Name known at compile time, kept as the `&'static str`; it
+            /// never enters the intern table.
+            pub const fn new(name: &'static str) -> Self {
+                Self(NameRepr::Static(name))
+            }
+
+            /// Returns the shared name for `name`, adding it to the intern
+            /// table on first use; see [`Symbol::intern`].
+            pub fn intern(name: &str) -> Self {
+                Self(NameRepr::Interned(Symbol::intern(name)))
+            }
+
+            /// The shared name for `name` if it was interned, else an owned
+            /// one; see [`Symbol::lookup`].
+            pub fn lookup(name: &str) -> Self {
+                Self(NameRepr::Interned(Symbol::lookup(name)))
+            }
+
+            pub fn as_str(&self) -> &str {
+                self.0.as_str()
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new("")
+            }
+        }
+
+        impl std::ops::Deref for $name {
+            type Target = str;
+
+            fn deref(&self) -> &str {
+                self.as_str()
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                self.as_str()
+            }
+        }
+
+        impl Borrow<str> for $name {
+            fn borrow(&self) -> &str {
+                self.as_str()
+            }
+        }
+
+        impl PartialEq for $name {
+            fn eq(&self, other: &Self) -> bool {
+                self.as_str() == other.as_str()
+            }
+        }
+
+        impl Eq for $name {}
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.as_str() == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.as_str() == *other
+            }
+        }
+
+        impl PartialEq<$name> for str {
+            fn eq(&self, other: &$name) -> bool {
+                self == other.as_str()
+            }
+        }
+
+        impl PartialEq<$name> for &str {
+            fn eq(&self, other: &$name) -> bool {
+                *self == other.as_str()
+            }
+        }
+
+        impl Hash for $name {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.as_str().hash(state);
+            }
+        }
+
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $name {
+            fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                self.as_str().cmp(other.as_str())
+            }
+        }
+
+        impl std::fmt::Debug for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                std::fmt::Debug::fmt(self.as_str(), f)
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(name: &str) -> Self {
+                Self::lookup(name)
+            }
+        }
+
+        impl From<&String> for $name {
+            fn from(name: &String) -> Self {
+                Self::lookup(name)
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(name: String) -> Self {
+                Self::lookup(&name)
+            }
+        }
+
+        impl From<Box<str>> for $name {
+            fn from(name: Box<str>) -> Self {
+                Self::lookup(&name)
+            }
+        }
+
+        impl From<&$name> for $name {
+            fn from(name: &$name) -> Self {
+                name.clone()
+            }
+        }
+
+        impl From<$name> for Box<str> {
+            fn from(name: $name) -> Self {
+                Box::from(name.as_str())
+            }
+        }
+
+        impl From<&$name> for Box<str> {
+            fn from(name: &$name) -> Self {
+                Box::from(name.as_str())
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(name: $name) -> Self {
+                String::from(name.as_str())
+            }
+        }
+
+        impl Archive for $name {
+            type Archived = ArchivedBox<str>;
+            type Resolver = BoxResolver;
+
+            fn resolve(&self, resolver: Self::Resolver, out: Place<Self::Archived>) {
+                ArchivedBox::resolve_from_ref(self.as_str(), resolver, out);
+            }
+        }
+
+        impl<S: Fallible + Writer + ?Sized> Serialize<S> for $name {
+            fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+                ArchivedBox::serialize_from_ref(self.as_str(), serializer)
+            }
+        }
+
+        impl<D: Fallible + ?Sized> Deserialize<$name, D> for ArchivedBox<str> {
+            fn deserialize(&self, _: &mut D) -> Result<$name, D::Error> {
+                Ok($name::lookup(self.get()))
+            }
+        }
+    };
+}
+
+/// `&'static [StepName]` of the given names, as taken by
+/// [`DependencySpec::AnyOf`](crate::DependencySpec::AnyOf) and
+/// [`DependencySpec::AllOf`](crate::DependencySpec::AllOf). Names must be
+/// constant expressions.
+#[macro_export]
+macro_rules! step_names {
+    ($($name:expr),* $(,)?) => {{
+        const STEP_NAMES: &[$crate::StepName] = &[$($crate::StepName::new($name)),*];
+        STEP_NAMES
+    }};
+}
+
+/// Storage of a [`SagaType`] or [`StepName`]: a `&'static str` from a
+/// `const` constructor, or a symbol.
+#[derive(Clone)]
+enum NameRepr {
+    Static(&'static str),
+    Interned(Symbol),
+}
+
+impl NameRepr {
+    fn as_str(&self) -> &str {
+        match self {
+            Self::Static(name) => name,
+            Self::Interned(symbol) => symbol.as_str(),
+        }
+    }
+}
+
+symbol_newtype!(
+    /// Name of a saga type (e.g. `"order_workflow"`).
+    SagaType
+);
+symbol_newtype!(
+    /// Name of a saga step (e.g. `"reserve_funds"`).
+    StepName
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interning_shares_one_allocation_per_name() {
+        let a = Symbol::intern("reserve_funds");
+        let b = Symbol::from(String::from("reserve_funds"));
+        assert!(Arc::ptr_eq(&a.0, &b.0));
+        assert_eq!(a, "reserve_funds");
+        assert_ne!(a, Symbol::from("release_funds"));
+
+        let mut map = std::collections::HashMap::new();
+        map.insert(a, 1);
+        assert_eq!(map.get("reserve_funds"), Some(&1));
+    }
+
+    #[test]
+    fn archives_like_boxed_str() {
+        let symbol = Symbol::intern("order_workflow");
+        let boxed: Box<str> = "order_workflow".into();
+        let from_symbol = rkyv::to_bytes::<rkyv::rancor::Error>(&symbol).unwrap();
+        let from_box = rkyv::to_bytes::<rkyv::rancor::Error>(&boxed).unwrap();
+        assert_eq!(from_symbol.as_slice(), from_box.as_slice());
+
+        let decoded = rkyv::from_bytes::<Symbol, rkyv::rancor::Error>(&from_box).unwrap();
+        assert!(Arc::ptr_eq(&decoded.0, &symbol.0));
+    }
+
+    #[test]
+    fn const_names_equal_interned_ones_and_archive_alike() {
+        const RESERVE_FUNDS: StepName = StepName::new("reserve_funds");
+        assert_eq!(RESERVE_FUNDS, StepName::from("reserve_funds"));
+        assert_eq!(RESERVE_FUNDS, "reserve_funds");
+
+        let archived = rkyv::to_bytes::<rkyv::rancor::Error>(&RESERVE_FUNDS).unwrap();
+        let boxed: Box<str> = "reserve_funds".into();
+        let from_box = rkyv::to_bytes::<rkyv::rancor::Error>(&boxed).unwrap();
+        assert_eq!(archived.as_slice(), from_box.as_slice());
+        let decoded = rkyv::from_bytes::<SagaType, rkyv::rancor::Error>(&archived).unwrap();
+        assert_eq!(decoded, SagaType::new("reserve_funds"));
+    }
+
+    #[test]
+    fn names_from_the_wire_stay_out_of_the_table() {
+        let boxed: Box<str> = "step_a_peer_made_up".into();
+        let archived = rkyv::to_bytes::<rkyv::rancor::Error>(&boxed).unwrap();
+        let decoded = rkyv::from_bytes::<StepName, rkyv::rancor::Error>(&archived).unwrap();
+        assert_eq!(decoded, "step_a_peer_made_up");
+        assert_eq!(Symbol::from("step_a_peer_made_up"), "step_a_peer_made_up");
+        assert!(!table().read().unwrap().contains("step_a_peer_made_up"));
+    }
+}
@@ -0,0 +1,496 @@
+//! Initiator heartbeats and completion-tracking takeover.
+//!
+//! The initiator of a saga usually runs its terminal resolver, the one
+//! component that turns step events into `SagaCompleted` or `SagaFailed`. If
+//! the initiating peer dies mid-saga, nobody emits either. The initiator
+//! therefore runs [`InitiatorHeartbeats`], publishing an
+//! [`SagaChoreographyEvent::InitiatorHeartbeat`] for each saga it started
+//! that is still in flight, and a designated backup runs an
+//! [`InitiatorTakeover`]:
+//!
+//! ```ignore
+//! // On the initiator, every `interval`:
+//! heartbeats.publish(&bus)?;
+//!
+//! // On the backup:
+//! let takeover = InitiatorTakeover::new(policy, leases, interval, 3);
+//! let _subscription = takeover.subscribe(&bus);
+//! // ...and on its timer:
+//! takeover.publish_takeovers(&bus)?;
+//! ```
+//!
+//! The backup shadows the terminal resolver for every saga it sees start.
+//! Once a saga's initiator has missed `missed_heartbeats` intervals, the
+//! backup holds the saga's [`INITIATOR_TAKEOVER_STEP`] lease in the shared
+//! [`StepLeaseStore`](crate::StepLeaseStore), publishes
+//! [`SagaChoreographyEvent::InitiatorTakenOver`] and from then on publishes
+//! what its resolver decides for that saga. The lease is held without expiry
+//! until the saga's terminal outcome, so of several backups exactly one takes
+//! a saga over; the others stand down when they see the takeover, and so does
+//! the resolver of an initiator that comes back, which latches the saga.
+//!
+//! The lease store is the only record of who took a saga over; participant
+//! journals do not record it.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use icanact_core::local::EventSubscription;
+
+use crate::{
+    PeerId, SagaBusPublishError, SagaChoreographyBus, SagaChoreographyEvent, SagaContext, SagaId,
+    StepLeaseError, StepLeases, TerminalPolicy, TerminalResolver,
+};
+
+/// Lease claimed by the backup that takes a saga over.
+pub const INITIATOR_TAKEOVER_STEP: &str = "initiator_takeover";
+
+/// Heartbeats of one initiator. Cloning shares the same state.
+#[derive(Clone)]
+pub struct InitiatorHeartbeats {
+    peer_id: PeerId,
+    in_flight: Arc<Mutex<BTreeMap<SagaId, SagaContext>>>,
+}
+
+impl InitiatorHeartbeats {
+    /// Heartbeats for sagas started with `peer_id` as their
+    /// `initiator_peer_id`.
+    pub fn new(peer_id: PeerId) -> Self {
+        Self {
+            peer_id,
+            in_flight: Arc::new(Mutex::new(BTreeMap::new())),
+        }
+    }
+
+    /// Subscribes to every event of `saga_type` to track the sagas in flight.
+    pub fn subscribe(&self, bus: &SagaChoreographyBus, saga_type: &str) -> EventSubscription {
+        let heartbeats = self.clone();
+        bus.subscribe_saga_type_fn(saga_type, move |event| {
+            heartbeats.ingest(event);
+            true
+        })
+    }
+
+    /// Tracks sagas this initiator started until they reach a terminal
+    /// outcome or a backup takes them over.
+    pub fn ingest(&self, event: &SagaChoreographyEvent) {
+        let context = event.context();
+        let mut in_flight = self.lock();
+        match event {
+            SagaChoreographyEvent::SagaStarted { .. }
+                if context.initiator_peer_id == self.peer_id =>
+            {
+                in_flight.insert(context.saga_id, context.clone());
+            }
+            SagaChoreographyEvent::InitiatorTakenOver { successor_id, .. } => {
+                if in_flight.remove(&context.saga_id).is_some() {
+                    tracing::warn!(
+                        target: "core::saga",
+                        event = "saga_initiator_superseded",
+                        saga_id = context.saga_id.get(),
+                        successor_id = successor_id.as_ref()
+                    );
+                }
+            }
+            _ if event.terminal_outcome().is_some() => {
+                in_flight.remove(&context.saga_id);
+            }
+            _ => {}
+        }
+    }
+
+    /// One heartbeat per saga in flight.
+    pub fn poll(&self) -> Vec<SagaChoreographyEvent> {
+        self.lock()
+            .values()
+            .map(|context| SagaChoreographyEvent::InitiatorHeartbeat {
+                context: context.clone(),
+            })
+            .collect()
+    }
+
+    /// Publishes [`InitiatorHeartbeats::poll`] on `bus`. Meant to run on the
+    /// initiator's timer, at the interval the backups expect.
+    pub fn publish(&self, bus: &SagaChoreographyBus) -> Result<usize, SagaBusPublishError> {
+        let heartbeats = self.poll();
+        let count = heartbeats.len();
+        for event in heartbeats {
+            bus.publish_strict(event)?;
+        }
+        Ok(count)
+    }
+
+    /// Sagas this initiator sends heartbeats for.
+    pub fn in_flight_len(&self) -> usize {
+        self.lock().len()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<SagaId, SagaContext>> {
+        self.in_flight
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl std::fmt::Debug for InitiatorHeartbeats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InitiatorHeartbeats")
+            .field("peer_id", &self.peer_id)
+            .field("in_flight", &self.in_flight_len())
+            .finish()
+    }
+}
+
+struct WatchedSaga {
+    context: SagaContext,
+    last_heartbeat_millis: u64,
+}
+
+struct TakeoverState {
+    resolver: TerminalResolver,
+    watched: BTreeMap<SagaId, WatchedSaga>,
+    taken_over: BTreeSet<SagaId>,
+}
+
+/// Backup that takes over the terminal resolution of sagas whose initiator
+/// stopped sending heartbeats. Cloning shares the same state.
+#[derive(Clone)]
+pub struct InitiatorTakeover {
+    state: Arc<Mutex<TakeoverState>>,
+    leases: StepLeases,
+    missed_after_millis: u64,
+}
+
+impl InitiatorTakeover {
+    /// Resolves sagas of `policy`'s saga type after `missed_heartbeats`
+    /// heartbeat intervals without one. `leases.holder` names this backup in
+    /// the takeover event.
+    pub fn new(
+        policy: TerminalPolicy,
+        leases: StepLeases,
+        heartbeat_interval: Duration,
+        missed_heartbeats: u32,
+    ) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(TakeoverState {
+                resolver: TerminalResolver::new(policy),
+                watched: BTreeMap::new(),
+                taken_over: BTreeSet::new(),
+            })),
+            leases,
+            missed_after_millis: (heartbeat_interval.as_millis() as u64)
+                .saturating_mul(u64::from(missed_heartbeats.max(1))),
+        }
+    }
+
+    /// Subscribes to the policy's saga type and publishes the terminal
+    /// events of sagas taken over as they resolve.
+    pub fn subscribe(&self, bus: &SagaChoreographyBus) -> EventSubscription {
+        let takeover = self.clone();
+        let saga_type = self.lock().resolver.policy().saga_type.clone();
+        let publisher = bus.clone();
+        bus.subscribe_saga_type_fn(saga_type.as_ref(), move |event| {
+            for terminal in takeover.ingest(event) {
+                if let Err(err) = publisher.publish_strict(terminal) {
+                    tracing::error!(
+                        target: "core::saga",
+                        event = "saga_takeover_publish_failed",
+                        error = ?err
+                    );
+                }
+            }
+            true
+        })
+    }
+
+    /// Feeds `event` to the shadow resolver. Returns the terminal events to
+    /// publish, only ever for sagas this backup took over.
+    pub fn ingest(&self, event: &SagaChoreographyEvent) -> Vec<SagaChoreographyEvent> {
+        self.ingest_at(event, SagaContext::now_millis())
+    }
+
+    /// Takes over every saga whose initiator missed its heartbeats and
+    /// returns the events to publish: an `InitiatorTakenOver` per saga, and
+    /// whatever the shadow resolver's timeouts decide for sagas taken over.
+    pub fn poll_takeovers(&self) -> Vec<SagaChoreographyEvent> {
+        self.poll_takeovers_at(SagaContext::now_millis())
+    }
+
+    /// Publishes [`InitiatorTakeover::poll_takeovers`] on `bus`. Meant to run
+    /// on the backup's timer.
+    pub fn publish_takeovers(
+        &self,
+        bus: &SagaChoreographyBus,
+    ) -> Result<usize, SagaBusPublishError> {
+        let events = self.poll_takeovers();
+        let count = events.len();
+        for event in events {
+            bus.publish_strict(event)?;
+        }
+        Ok(count)
+    }
+
+    /// Sagas this backup resolves.
+    pub fn taken_over_len(&self) -> usize {
+        self.lock().taken_over.len()
+    }
+
+    /// Sagas whose initiator is still watched for heartbeats.
+    pub fn watched_len(&self) -> usize {
+        self.lock().watched.len()
+    }
+
+    pub(crate) fn ingest_at(
+        &self,
+        event: &SagaChoreographyEvent,
+        now_millis: u64,
+    ) -> Vec<SagaChoreographyEvent> {
+        let context = event.context();
+        let saga_id = context.saga_id;
+        let mut guard = self.lock();
+        let state = &mut *guard;
+        let own_takeover = matches!(
+            event,
+            SagaChoreographyEvent::InitiatorTakenOver { successor_id, .. }
+                if *successor_id == self.leases.holder
+        );
+        // The shadow resolver stands down on every takeover but this
+        // backup's own.
+        let resolved = if own_takeover {
+            Vec::new()
+        } else {
+            state.resolver.ingest_at(event, now_millis)
+        };
+        match event {
+            SagaChoreographyEvent::SagaStarted { .. } => {
+                state.taken_over.remove(&saga_id);
+                state.watched.insert(
+                    saga_id,
+                    WatchedSaga {
+                        context: context.clone(),
+                        last_heartbeat_millis: now_millis,
+                    },
+                );
+            }
+            SagaChoreographyEvent::InitiatorHeartbeat { .. } => {
+                if let Some(watched) = state.watched.get_mut(&saga_id) {
+                    watched.last_heartbeat_millis = now_millis;
+                }
+            }
+            // Another backup won the lease.
+            SagaChoreographyEvent::InitiatorTakenOver { successor_id, .. }
+                if *successor_id != self.leases.holder =>
+            {
+                state.watched.remove(&saga_id);
+            }
+            _ if event.terminal_outcome().is_some() => {
+                state.watched.remove(&saga_id);
+                if state.taken_over.remove(&saga_id) {
+                    if let Err(err) = self.leases.release(saga_id) {
+                        tracing::warn!(
+                            target: "core::saga",
+                            event = "saga_initiator_takeover_release_failed",
+                            saga_id = saga_id.get(),
+                            error = %err
+                        );
+                    }
+                }
+            }
+            _ => {}
+        }
+        resolved
+            .into_iter()
+            .filter(|terminal| state.taken_over.contains(&terminal.context().saga_id))
+            .collect()
+    }
+
+    pub(crate) fn poll_takeovers_at(&self, now_millis: u64) -> Vec<SagaChoreographyEvent> {
+        let mut guard = self.lock();
+        let state = &mut *guard;
+        let missed: Vec<SagaId> = state
+            .watched
+            .iter()
+            .filter(|(saga_id, watched)| {
+                !state.taken_over.contains(saga_id)
+                    && now_millis.saturating_sub(watched.last_heartbeat_millis)
+                        > self.missed_after_millis
+            })
+            .map(|(saga_id, _)| *saga_id)
+            .collect();
+        let mut out = Vec::new();
+        for saga_id in missed {
+            match self
+                .leases
+                .hold(saga_id, INITIATOR_TAKEOVER_STEP, now_millis)
+            {
+                Ok(_) => {
+                    let Some(watched) = state.watched.get(&saga_id) else {
+                        continue;
+                    };
+                    tracing::warn!(
+                        target: "core::saga",
+                        event = "saga_initiator_taken_over",
+                        saga_id = saga_id.get(),
+                        initiator_peer_id = ?watched.context.initiator_peer_id,
+                        successor_id = self.leases.holder.as_ref(),
+                        silent_millis = now_millis.saturating_sub(watched.last_heartbeat_millis)
+                    );
+                    state.taken_over.insert(saga_id);
+                    out.push(SagaChoreographyEvent::InitiatorTakenOver {
+                        context: watched.context.clone(),
+                        successor_id: self.leases.holder.clone(),
+                    });
+                }
+                Err(StepLeaseError::Held { holder, .. }) => {
+                    tracing::info!(
+                        target: "core::saga",
+                        event = "saga_initiator_takeover_lost",
+                        saga_id = saga_id.get(),
+                        holder = holder.as_ref()
+                    );
+                    state.watched.remove(&saga_id);
+                }
+                Err(err) => {
+                    // Retried on the next poll.
+                    tracing::warn!(
+                        target: "core::saga",
+                        event = "saga_initiator_takeover_claim_failed",
+                        saga_id = saga_id.get(),
+                        error = %err
+                    );
+                }
+            }
+        }
+        let taken_over = &state.taken_over;
+        out.extend(
+            state
+                .resolver
+                .poll_timeouts_at(now_millis)
+                .into_iter()
+                .filter(|terminal| taken_over.contains(&terminal.context().saga_id)),
+        );
+        out
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, TakeoverState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl std::fmt::Debug for InitiatorTakeover {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InitiatorTakeover")
+            .field("holder", &self.leases.holder)
+            .field("missed_after_millis", &self.missed_after_millis)
+            .field("watched", &self.watched_len())
+            .field("taken_over", &self.taken_over_len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        saga_started, DeterministicContextBuilder, FailureAuthority, InMemoryStepLeaseStore,
+        SuccessCriteria,
+    };
+
+    fn policy() -> TerminalPolicy {
+        TerminalPolicy::new(
+            "order_lifecycle".into(),
+            "order_lifecycle/takeover".into(),
+            FailureAuthority::AnyParticipant,
+            SuccessCriteria::AllOf(["risk_check".into()].into()),
+            Duration::from_secs(30),
+            Duration::from_secs(30),
+            &[],
+        )
+    }
+
+    #[test]
+    fn one_backup_takes_over_after_missed_heartbeats_and_resolves() {
+        let store = Arc::new(InMemoryStepLeaseStore::new());
+        let leases: Arc<dyn crate::StepLeaseStore> = store.clone();
+        let interval = Duration::from_millis(100);
+        let backup = |holder: &str| {
+            InitiatorTakeover::new(
+                policy(),
+                StepLeases::new(leases.clone(), holder, Duration::from_secs(1)),
+                interval,
+                3,
+            )
+        };
+        let (first, second) = (backup("backup-a"), backup("backup-b"));
+        let context = DeterministicContextBuilder::default()
+            .with_saga_type("order_lifecycle")
+            .with_step_name("risk_check")
+            .build();
+        let heartbeats = InitiatorHeartbeats::new(context.initiator_peer_id);
+        let started = saga_started(context.clone(), Vec::new());
+        heartbeats.ingest(&started);
+        for takeover in [&first, &second] {
+            takeover.ingest_at(&started, 1_000);
+        }
+
+        let [heartbeat] = heartbeats.poll().try_into().expect("one saga in flight");
+        first.ingest_at(&heartbeat, 1_250);
+        assert!(first.poll_takeovers_at(1_500).is_empty());
+
+        let taken = first.poll_takeovers_at(1_600);
+        assert!(matches!(
+            taken.as_slice(),
+            [SagaChoreographyEvent::InitiatorTakenOver { successor_id, .. }]
+                if successor_id.as_ref() == "backup-a"
+        ));
+        // The lease is held: the second backup stands down.
+        assert!(second.poll_takeovers_at(1_600).is_empty());
+        assert_eq!(second.watched_len(), 0);
+        heartbeats.ingest(&taken[0]);
+        assert_eq!(heartbeats.in_flight_len(), 0);
+
+        let completed = crate::step_completed(
+            DeterministicContextBuilder::default()
+                .with_saga_id(context.saga_id.get())
+                .with_saga_type("order_lifecycle")
+                .with_step_name("risk_check")
+                .build(),
+            Vec::new(),
+            Vec::new(),
+            false,
+        );
+        let resolved = first.ingest_at(&completed, 1_700);
+        assert!(matches!(
+            resolved.as_slice(),
+            [SagaChoreographyEvent::SagaCompleted { .. }]
+        ));
+        assert!(second.ingest_at(&completed, 1_700).is_empty());
+        assert_eq!(first.taken_over_len(), 1);
+        first.ingest_at(&resolved[0], 1_700);
+        assert_eq!(first.taken_over_len(), 0);
+        assert!(store
+            .lease(context.saga_id, INITIATOR_TAKEOVER_STEP)
+            .is_none());
+    }
+
+    #[test]
+    fn returning_initiator_resolver_stands_down_on_takeover() {
+        let mut resolver = TerminalResolver::new(policy());
+        let context = DeterministicContextBuilder::default()
+            .with_saga_type("order_lifecycle")
+            .with_step_name("risk_check")
+            .build();
+        resolver.ingest_at(&saga_started(context.clone(), Vec::new()), 1_000);
+        let taken_over = SagaChoreographyEvent::InitiatorTakenOver {
+            context: context.clone(),
+            successor_id: "backup-a".into(),
+        };
+        assert!(resolver.ingest_at(&taken_over, 1_600).is_empty());
+
+        let completed = crate::step_completed(context, Vec::new(), Vec::new(), false);
+        assert!(resolver.ingest_at(&completed, 1_700).is_empty());
+    }
+}
@@ -0,0 +1,571 @@
+//! Deterministic in-process saga test harness.
+//!
+//! [`SagaTestHarness`] runs sync participants inline instead of as actors:
+//! events published on its [`SagaChoreographyBus`] are queued, and
+//! [`SagaTestHarness::drive_until_quiescent`] delivers them one at a time
+//! until no participant emits anything new. Participants and terminal
+//! resolvers read time from a shared [`MockClock`], so timeouts fire only when
+//! a test calls [`SagaTestHarness::advance_time`]. With
+//! [`SagaTestHarness::with_chaos`] delivery itself becomes unreliable; see
+//! [`crate::run_chaos`].
+//!
+//! ```rust,ignore
+//! let mut harness = SagaTestHarness::new();
+//! harness.with_terminal_policy(OrderContract::terminal_policy());
+//! let risk = harness.add_participant(RiskParticipant::new(InMemoryJournal::new(), InMemoryDedupe::new()));
+//! let saga_id = harness.start_saga("order_lifecycle", "risk_check", payload);
+//! harness.drive_until_quiescent();
+//! harness.assert_step_completed("risk_check");
+//! harness.assert_saga_completed(saga_id);
+//! ```
+
+use std::collections::{HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use icanact_core::local::EventSubscription;
+
+use crate::chaos::{ChaosAction, ChaosState};
+use crate::{
+    handle_saga_event_with_emit, ChaosPolicy, ChaosStats, ResourceLockManager, SagaChoreographyBus,
+    SagaChoreographyEvent, SagaContext, SagaId, SagaParticipant, SagaStateExt, SagaTerminalOutcome,
+    TerminalPolicy, TerminalResolver,
+};
+
+const DEFAULT_MOCK_CLOCK_START_MILLIS: u64 = 1_700_000_000_000;
+const QUIESCENCE_EVENT_LIMIT: usize = 100_000;
+
+/// Manually advanced millisecond clock. Clones share the same time.
+#[derive(Clone, Debug)]
+pub struct MockClock {
+    now_millis: Arc<AtomicU64>,
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new(DEFAULT_MOCK_CLOCK_START_MILLIS)
+    }
+}
+
+impl MockClock {
+    pub fn new(start_millis: u64) -> Self {
+        Self {
+            now_millis: Arc::new(AtomicU64::new(start_millis)),
+        }
+    }
+
+    pub fn now_millis(&self) -> u64 {
+        self.now_millis.load(Ordering::SeqCst)
+    }
+
+    pub fn advance(&self, millis: u64) {
+        self.now_millis.fetch_add(millis, Ordering::SeqCst);
+    }
+
+    /// Time source to install with
+    /// [`SagaParticipantSupport::with_clock`](crate::SagaParticipantSupport::with_clock).
+    pub fn source(&self) -> Arc<dyn Fn() -> u64 + Send + Sync> {
+        let now_millis = Arc::clone(&self.now_millis);
+        Arc::new(move || now_millis.load(Ordering::SeqCst))
+    }
+}
+
+type Delivery = Box<dyn FnMut(&SagaChoreographyEvent) -> Vec<SagaChoreographyEvent> + Send>;
+
+/// In-process harness wiring sync participants, terminal resolvers and a
+/// [`MockClock`] to one bus.
+pub struct SagaTestHarness {
+    bus: SagaChoreographyBus,
+    clock: MockClock,
+    queue: Arc<Mutex<VecDeque<SagaChoreographyEvent>>>,
+    ready: VecDeque<SagaChoreographyEvent>,
+    delayed: Vec<(u64, SagaChoreographyEvent)>,
+    chaos: Option<ChaosState>,
+    resource_locks: Option<ResourceLockManager>,
+    transcript: Vec<SagaChoreographyEvent>,
+    participants: Vec<Delivery>,
+    resolvers: Vec<TerminalResolver>,
+    subscribed_saga_types: HashSet<Box<str>>,
+    subscriptions: Vec<EventSubscription>,
+    next_saga_id: u64,
+}
+
+impl Default for SagaTestHarness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SagaTestHarness {
+    pub fn new() -> Self {
+        Self::with_clock(MockClock::default())
+    }
+
+    pub fn with_clock(clock: MockClock) -> Self {
+        Self {
+            bus: SagaChoreographyBus::new(),
+            clock,
+            queue: Arc::new(Mutex::new(VecDeque::new())),
+            ready: VecDeque::new(),
+            delayed: Vec::new(),
+            chaos: None,
+            resource_locks: None,
+            transcript: Vec::new(),
+            participants: Vec::new(),
+            resolvers: Vec::new(),
+            subscribed_saga_types: HashSet::new(),
+            subscriptions: Vec::new(),
+            next_saga_id: 1,
+        }
+    }
+
+    pub fn bus(&self) -> SagaChoreographyBus {
+        self.bus.clone()
+    }
+
+    pub fn clock(&self) -> MockClock {
+        self.clock.clone()
+    }
+
+    /// Resolves sagas of the policy's type to `SagaCompleted` / `SagaFailed`
+    /// in-process, using the mock clock for its timeouts.
+    pub fn with_terminal_policy(&mut self, policy: TerminalPolicy) -> &mut Self {
+        self.subscribe_saga_type(policy.saga_type.as_ref());
+        self.resolvers.push(TerminalResolver::new(policy));
+        self
+    }
+
+    /// Drops, duplicates, delays and reorders published events according to
+    /// `policy` from now on. Delayed events are held until
+    /// [`Self::advance_time`] passes their due time.
+    pub fn with_chaos(&mut self, policy: ChaosPolicy) -> &mut Self {
+        self.chaos = Some(ChaosState::new(policy));
+        self
+    }
+
+    /// Faults injected so far by [`Self::with_chaos`].
+    pub fn chaos_stats(&self) -> ChaosStats {
+        self.chaos
+            .as_ref()
+            .map(|chaos| chaos.stats)
+            .unwrap_or_default()
+    }
+
+    /// Events held back by a chaos delay and not yet released.
+    pub fn pending_delayed(&self) -> usize {
+        self.delayed.len()
+    }
+
+    /// Lets `manager` observe every delivered event, releasing locks when
+    /// sagas complete or fail. Participants should share a clone of it.
+    pub fn with_resource_locks(&mut self, manager: ResourceLockManager) -> &mut Self {
+        self.resource_locks = Some(manager);
+        self
+    }
+
+    pub fn resource_locks(&self) -> Option<&ResourceLockManager> {
+        self.resource_locks.as_ref()
+    }
+
+    /// Adds a participant and points its saga support at the mock clock. The
+    /// returned handle allows inspecting the participant between drives.
+    pub fn add_participant<P>(&mut self, mut participant: P) -> Arc<Mutex<P>>
+    where
+        P: SagaParticipant + SagaStateExt + Send + 'static,
+    {
+        participant.saga_support_mut().clock = Some(self.clock.source());
+        for saga_type in participant.saga_types() {
+            self.subscribe_saga_type(saga_type);
+        }
+        let participant = Arc::new(Mutex::new(participant));
+        let handle = Arc::clone(&participant);
+        self.participants.push(Box::new(move |event| {
+            let mut emitted = Vec::new();
+            let mut participant = participant
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            handle_saga_event_with_emit(&mut *participant, event.clone(), |next| {
+                emitted.push(next)
+            });
+            emitted
+        }));
+        handle
+    }
+
+    /// Queues `SagaStarted` for a new saga and returns its id. Nothing is
+    /// delivered until the harness is driven.
+    pub fn start_saga(&mut self, saga_type: &str, first_step: &str, payload: Vec<u8>) -> SagaId {
+        let saga_id = SagaId::new(self.next_saga_id);
+        self.next_saga_id += 1;
+        let mut context = SagaContext::start(saga_id, saga_type.into(), first_step.into(), [0; 32]);
+        context.saga_started_at_millis = self.clock.now_millis();
+        context.event_timestamp_millis = context.saga_started_at_millis;
+        self.publish(SagaChoreographyEvent::SagaStarted { context, payload });
+        saga_id
+    }
+
+    /// Publishes `event` on the harness bus.
+    pub fn publish(&mut self, event: SagaChoreographyEvent) {
+        let saga_type = event.context().saga_type.clone();
+        self.subscribe_saga_type(saga_type.as_ref());
+        self.bus.publish_to_saga_type(saga_type.as_ref(), event);
+    }
+
+    /// Delivers queued events until none are left and returns how many were
+    /// delivered.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the participants keep emitting past an internal event limit,
+    /// which indicates an event loop.
+    pub fn drive_until_quiescent(&mut self) -> usize {
+        let mut delivered = 0;
+        while let Some(event) = self.pop_queued() {
+            delivered += 1;
+            assert!(
+                delivered <= QUIESCENCE_EVENT_LIMIT,
+                "saga test harness did not quiesce after {QUIESCENCE_EVENT_LIMIT} events"
+            );
+            let now = self.clock.now_millis();
+            if let Some(locks) = &self.resource_locks {
+                locks.observe(&event);
+            }
+            let mut emitted = Vec::new();
+            for resolver in &mut self.resolvers {
+                emitted.extend(resolver.ingest_at(&event, now));
+            }
+            for participant in &mut self.participants {
+                emitted.extend(participant(&event));
+            }
+            self.transcript.push(event);
+            for next in emitted {
+                self.publish(next);
+            }
+        }
+        delivered
+    }
+
+    /// Moves the mock clock forward, releases delayed events and fires
+    /// resolver timeouts that came due, and drives until quiescent.
+    pub fn advance_time(&mut self, millis: u64) -> usize {
+        self.clock.advance(millis);
+        let now = self.clock.now_millis();
+        self.delayed.sort_by_key(|(due, _)| *due);
+        let due = self.delayed.partition_point(|(due, _)| *due <= now);
+        self.ready
+            .extend(self.delayed.drain(..due).map(|(_, event)| event));
+        let timed_out: Vec<SagaChoreographyEvent> = self
+            .resolvers
+            .iter_mut()
+            .flat_map(|resolver| resolver.poll_timeouts_at(now))
+            .collect();
+        for event in timed_out {
+            self.publish(event);
+        }
+        self.drive_until_quiescent()
+    }
+
+    /// Every event delivered so far, in delivery order.
+    pub fn events(&self) -> &[SagaChoreographyEvent] {
+        &self.transcript
+    }
+
+    pub fn events_for_saga(&self, saga_id: SagaId) -> Vec<&SagaChoreographyEvent> {
+        self.transcript
+            .iter()
+            .filter(|event| event.context().saga_id == saga_id)
+            .collect()
+    }
+
+    pub fn terminal_outcome(&self, saga_id: SagaId) -> Option<SagaTerminalOutcome> {
+        self.transcript
+            .iter()
+            .filter(|event| event.context().saga_id == saga_id)
+            .find_map(SagaChoreographyEvent::terminal_outcome)
+    }
+
+    #[track_caller]
+    pub fn assert_step_completed(&self, step: &str) {
+        self.assert_step_event(step, "StepCompleted", |event| {
+            matches!(event, SagaChoreographyEvent::StepCompleted { .. })
+        });
+    }
+
+    #[track_caller]
+    pub fn assert_step_failed(&self, step: &str) {
+        self.assert_step_event(step, "StepFailed", |event| {
+            matches!(event, SagaChoreographyEvent::StepFailed { .. })
+        });
+    }
+
+    #[track_caller]
+    pub fn assert_step_compensated(&self, step: &str) {
+        self.assert_step_event(step, "CompensationCompleted", |event| {
+            matches!(event, SagaChoreographyEvent::CompensationCompleted { .. })
+        });
+    }
+
+    #[track_caller]
+    pub fn assert_step_not_started(&self, step: &str) {
+        if let Some(event) = self.transcript.iter().find(|event| {
+            matches!(event, SagaChoreographyEvent::StepStarted { .. })
+                && event.context().step_name.as_ref() == step
+        }) {
+            panic!("expected step {step} not to start, got {event:?}");
+        }
+    }
+
+    #[track_caller]
+    pub fn assert_saga_completed(&self, saga_id: SagaId) {
+        match self.terminal_outcome(saga_id) {
+            Some(SagaTerminalOutcome::Completed { .. }) => {}
+            other => panic!("expected saga {} to complete, got {other:?}", saga_id.get()),
+        }
+    }
+
+    #[track_caller]
+    pub fn assert_saga_failed(&self, saga_id: SagaId) {
+        match self.terminal_outcome(saga_id) {
+            Some(SagaTerminalOutcome::Failed { .. }) => {}
+            other => panic!("expected saga {} to fail, got {other:?}", saga_id.get()),
+        }
+    }
+
+    #[track_caller]
+    fn assert_step_event(
+        &self,
+        step: &str,
+        expected: &str,
+        matches: impl Fn(&SagaChoreographyEvent) -> bool,
+    ) {
+        let found = self
+            .transcript
+            .iter()
+            .any(|event| matches(event) && event.context().step_name.as_ref() == step);
+        if !found {
+            let seen: Vec<String> = self
+                .transcript
+                .iter()
+                .map(|event| format!("{}({})", event.event_type(), event.context().step_name))
+                .collect();
+            panic!(
+                "expected {expected} for step {step}; transcript: [{}]",
+                seen.join(", ")
+            );
+        }
+    }
+
+    fn subscribe_saga_type(&mut self, saga_type: &str) {
+        if !self.subscribed_saga_types.insert(saga_type.into()) {
+            return;
+        }
+        let queue = Arc::clone(&self.queue);
+        self.subscriptions
+            .push(self.bus.subscribe_saga_type_fn(saga_type, move |event| {
+                queue
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .push_back(event.clone());
+                true
+            }));
+    }
+
+    fn pop_queued(&mut self) -> Option<SagaChoreographyEvent> {
+        let published: Vec<SagaChoreographyEvent> = self
+            .queue
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .drain(..)
+            .collect();
+        for event in published {
+            let Some(chaos) = &mut self.chaos else {
+                self.ready.push_back(event);
+                continue;
+            };
+            match chaos.decide(&event) {
+                ChaosAction::Deliver => self.ready.push_back(event),
+                ChaosAction::Drop => {}
+                ChaosAction::Duplicate => {
+                    self.ready.push_back(event.clone());
+                    self.ready.push_back(event);
+                }
+                ChaosAction::Delay { millis } => {
+                    let due = self.clock.now_millis() + millis;
+                    self.delayed.push((due, event));
+                }
+                ChaosAction::Reorder { position } => {
+                    let slots = self.ready.len() as u64 + 1;
+                    self.ready.insert((position % slots) as usize, event);
+                }
+            }
+        }
+        self.ready.pop_front()
+    }
+}
+
+impl Drop for SagaTestHarness {
+    fn drop(&mut self) {
+        for subscription in self.subscriptions.drain(..) {
+            self.bus.unsubscribe(subscription);
+        }
+    }
+}
+
+impl std::fmt::Debug for SagaTestHarness {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SagaTestHarness")
+            .field("now_millis", &self.clock.now_millis())
+            .field("participants", &self.participants.len())
+            .field("resolvers", &self.resolvers.len())
+            .field("delivered", &self.transcript.len())
+            .field("pending_delayed", &self.delayed.len())
+            .field("chaos", &self.chaos.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        CompensationError, DependencySpec, FailureAuthority, HasSagaParticipantSupport,
+        InMemoryDedupe, InMemoryJournal, SagaParticipantSupport, StepError, StepOutput,
+        SuccessCriteria,
+    };
+
+    struct Step {
+        saga: SagaParticipantSupport<InMemoryJournal, InMemoryDedupe>,
+        name: &'static str,
+        depends_on: DependencySpec,
+        fail: bool,
+        started_at_millis: Vec<u64>,
+    }
+
+    impl Step {
+        fn new(name: &'static str, depends_on: DependencySpec) -> Self {
+            Self {
+                saga: SagaParticipantSupport::new(InMemoryJournal::new(), InMemoryDedupe::new()),
+                name,
+                depends_on,
+                fail: false,
+                started_at_millis: Vec::new(),
+            }
+        }
+    }
+
+    impl HasSagaParticipantSupport for Step {
+        type Journal = InMemoryJournal;
+        type Dedupe = InMemoryDedupe;
+
+        fn saga_support(&self) -> &SagaParticipantSupport<Self::Journal, Self::Dedupe> {
+            &self.saga
+        }
+
+        fn saga_support_mut(&mut self) -> &mut SagaParticipantSupport<Self::Journal, Self::Dedupe> {
+            &mut self.saga
+        }
+    }
+
+    impl SagaParticipant for Step {
+        type Error = String;
+
+        fn step_name(&self) -> &str {
+            self.name
+        }
+
+        fn saga_types(&self) -> &[&'static str] {
+            &["order_lifecycle"]
+        }
+
+        fn depends_on(&self) -> DependencySpec {
+            self.depends_on.clone()
+        }
+
+        fn execute_step(
+            &mut self,
+            _context: &SagaContext,
+            _input: &[u8],
+        ) -> Result<StepOutput, StepError> {
+            self.started_at_millis.push(self.now_millis());
+            if self.fail {
+                return Err(StepError::require_compensation("exchange rejected"));
+            }
+            Ok(StepOutput::Completed {
+                output: Vec::new(),
+                compensation_data: vec![1],
+            })
+        }
+
+        fn compensate_step(
+            &mut self,
+            _context: &SagaContext,
+            _compensation_data: &[u8],
+        ) -> Result<(), CompensationError> {
+            Ok(())
+        }
+    }
+
+    fn policy(required: &[&str]) -> TerminalPolicy {
+        let required: HashSet<Box<str>> = required.iter().map(|step| (*step).into()).collect();
+        TerminalPolicy::new(
+            "order_lifecycle".into(),
+            "order_lifecycle/test".into(),
+            FailureAuthority::AnyParticipant,
+            SuccessCriteria::AllOf(required),
+            Duration::from_secs(60),
+            Duration::from_secs(5),
+            &[],
+        )
+    }
+
+    #[test]
+    fn drives_steps_to_terminal_outcomes_on_mock_time() {
+        let mut harness = SagaTestHarness::new();
+        harness.with_terminal_policy(policy(&["risk_check", "place_order"]));
+        let risk = harness.add_participant(Step::new("risk_check", DependencySpec::OnSagaStart));
+        let place = harness.add_participant(Step::new(
+            "place_order",
+            DependencySpec::after("risk_check"),
+        ));
+
+        let completed = harness.start_saga("order_lifecycle", "risk_check", Vec::new());
+        assert!(harness.drive_until_quiescent() > 0);
+        harness.assert_step_completed("risk_check");
+        harness.assert_step_completed("place_order");
+        harness.assert_saga_completed(completed);
+        let start = harness.clock().now_millis();
+        assert_eq!(risk.lock().unwrap().started_at_millis, vec![start]);
+
+        place.lock().unwrap().fail = true;
+        harness.advance_time(250);
+        let failed = harness.start_saga("order_lifecycle", "risk_check", Vec::new());
+        harness.drive_until_quiescent();
+        harness.assert_step_failed("place_order");
+        harness.assert_step_compensated("risk_check");
+        harness.assert_saga_failed(failed);
+        assert_eq!(
+            place.lock().unwrap().started_at_millis,
+            vec![start, start + 250]
+        );
+    }
+
+    #[test]
+    fn advance_time_fires_resolver_timeouts() {
+        let mut harness = SagaTestHarness::new();
+        harness.with_terminal_policy(policy(&["risk_check", "settle"]));
+        harness.add_participant(Step::new("risk_check", DependencySpec::OnSagaStart));
+
+        let saga_id = harness.start_saga("order_lifecycle", "risk_check", Vec::new());
+        harness.drive_until_quiescent();
+        harness.assert_step_not_started("settle");
+        assert!(harness.terminal_outcome(saga_id).is_none());
+
+        harness.advance_time(5_000);
+        assert!(harness.terminal_outcome(saga_id).is_none());
+        harness.advance_time(1);
+        harness.assert_saga_failed(saga_id);
+    }
+}
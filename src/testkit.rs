@@ -3,7 +3,7 @@
 use crate::{
     apply_sync_workflow_participant_saga_ingress, handle_saga_event_with_emit,
     HasSagaWorkflowParticipants, SagaChoreographyEvent, SagaContext, SagaId, SagaParticipant,
-    SagaStateExt,
+    SagaStateExt, StepName,
 };
 
 /// Small deterministic builder for saga test contexts.
@@ -17,6 +17,9 @@ pub struct DeterministicContextBuilder {
     trace_id: u64,
     started_at_millis: u64,
     event_at_millis: u64,
+    parent_saga_id: Option<u64>,
+    workflow_version: u32,
+    logical_clock: u64,
 }
 
 impl Default for DeterministicContextBuilder {
@@ -30,6 +33,9 @@ impl Default for DeterministicContextBuilder {
             trace_id: 1,
             started_at_millis: 1_700_000_000_000,
             event_at_millis: 1_700_000_000_000,
+            parent_saga_id: None,
+            workflow_version: crate::DEFAULT_WORKFLOW_VERSION,
+            logical_clock: 1,
         }
     }
 }
@@ -55,11 +61,26 @@ impl DeterministicContextBuilder {
         self
     }
 
+    pub fn with_parent_saga_id(mut self, parent_saga_id: u64) -> Self {
+        self.parent_saga_id = Some(parent_saga_id);
+        self
+    }
+
+    pub fn with_workflow_version(mut self, workflow_version: u32) -> Self {
+        self.workflow_version = workflow_version;
+        self
+    }
+
+    pub fn with_logical_clock(mut self, logical_clock: u64) -> Self {
+        self.logical_clock = logical_clock;
+        self
+    }
+
     pub fn build(self) -> SagaContext {
         SagaContext {
             saga_id: SagaId::new(self.saga_id),
-            saga_type: self.saga_type.into_boxed_str(),
-            step_name: self.step_name.into_boxed_str(),
+            saga_type: self.saga_type.into(),
+            step_name: self.step_name.into(),
             correlation_id: self.correlation_id,
             causation_id: self.causation_id,
             trace_id: self.trace_id,
@@ -68,6 +89,9 @@ impl DeterministicContextBuilder {
             initiator_peer_id: [0; 32],
             saga_started_at_millis: self.started_at_millis,
             event_timestamp_millis: self.event_at_millis,
+            parent_saga_id: self.parent_saga_id.map(SagaId::new),
+            workflow_version: self.workflow_version,
+            logical_clock: self.logical_clock,
         }
     }
 }
@@ -87,6 +111,7 @@ pub fn step_completed(
         output,
         saga_input,
         compensation_available,
+        completion_ratio: None,
     }
 }
 
@@ -101,6 +126,7 @@ pub fn step_failed(
         error_code: None,
         error: error.into().into_boxed_str(),
         requires_compensation,
+        error_details: Vec::new(),
     }
 }
 
@@ -112,11 +138,11 @@ pub fn compensation_requested(
 ) -> SagaChoreographyEvent {
     SagaChoreographyEvent::CompensationRequested {
         context,
-        failed_step: failed_step.into().into_boxed_str(),
+        failed_step: failed_step.into().into(),
         reason: reason.into().into_boxed_str(),
         steps_to_compensate: steps_to_compensate
             .into_iter()
-            .map(|step| step.into_boxed_str())
+            .map(StepName::from)
             .collect(),
     }
 }
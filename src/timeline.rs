@@ -0,0 +1,316 @@
+//! One saga's history across participants, for post-mortems.
+//!
+//! Each participant journals what it received (the inbox history) and what
+//! it did (its journal entries) on its own. [`SagaTimeline`] merges both
+//! from several participants' journals into one list ordered by wall time,
+//! then logical clock, and links every entry to the entry that caused it:
+//!
+//! ```ignore
+//! let timeline = SagaTimeline::builder(saga_id)
+//!     .with_journal("risk_check", &risk_journal)?
+//!     .with_journal("place_order", &order_journal)?
+//!     .build();
+//! std::fs::write("saga-42.json", timeline.to_json())?;
+//! ```
+//!
+//! A received event is caused by the received event whose `trace_id` is its
+//! `causation_id`, wherever that was received first; a journal entry is
+//! caused by the last event its participant received before it. Wall clocks
+//! of different hosts can disagree, so across participants the causal links
+//! are more reliable than the order.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+use crate::{
+    JournalError, ParticipantEvent, ParticipantJournal, SagaChoreographyEvent, SagaId, StepName,
+};
+
+/// Whether a [`TimelineEntry`] is an event a participant received or a
+/// record it journaled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimelineEntryKind {
+    Received,
+    Recorded,
+}
+
+impl TimelineEntryKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Received => "received",
+            Self::Recorded => "recorded",
+        }
+    }
+}
+
+/// One entry of a [`SagaTimeline`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimelineEntry {
+    /// Position in the timeline.
+    pub index: usize,
+    /// Name the participant's journal was added under.
+    pub participant: Box<str>,
+    pub kind: TimelineEntryKind,
+    /// `SagaChoreographyEvent::event_type` or `ParticipantEvent::event_type`.
+    pub event_type: &'static str,
+    /// When the participant journaled the entry.
+    pub at_millis: u64,
+    /// Step named by a received event's context.
+    pub step_name: Option<StepName>,
+    /// Set for received events.
+    pub trace_id: Option<u64>,
+    /// Set for received events.
+    pub logical_clock: Option<u64>,
+    /// Error or reason carried by failures and compensation requests.
+    pub detail: Option<Box<str>>,
+    /// Index of the entry that caused this one.
+    pub caused_by: Option<usize>,
+    causation_id: Option<u64>,
+    journal_order: (usize, u64),
+}
+
+/// A saga's entries from several participants, in order and causally
+/// linked.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SagaTimeline {
+    pub saga_id: SagaId,
+    pub entries: Vec<TimelineEntry>,
+}
+
+/// Collects the journals of a [`SagaTimeline`].
+#[derive(Debug)]
+pub struct SagaTimelineBuilder {
+    saga_id: SagaId,
+    entries: Vec<TimelineEntry>,
+    journals: usize,
+}
+
+impl SagaTimeline {
+    pub fn builder(saga_id: SagaId) -> SagaTimelineBuilder {
+        SagaTimelineBuilder {
+            saga_id,
+            entries: Vec::new(),
+            journals: 0,
+        }
+    }
+
+    /// Entries directly caused by the entry at `index`.
+    pub fn effects_of(&self, index: usize) -> impl Iterator<Item = &TimelineEntry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.caused_by == Some(index))
+    }
+
+    /// The timeline as a JSON object: `saga_id` and the `entries` array.
+    pub fn to_json(&self) -> String {
+        let mut json = format!("{{\"saga_id\":{},\"entries\":[", self.saga_id.get());
+        for (position, entry) in self.entries.iter().enumerate() {
+            if position > 0 {
+                json.push(',');
+            }
+            let _ = write!(
+                json,
+                "{{\"index\":{},\"participant\":{},\"kind\":\"{}\",\"event_type\":\"{}\",\"at_millis\":{}",
+                entry.index,
+                json_string(&entry.participant),
+                entry.kind.as_str(),
+                entry.event_type,
+                entry.at_millis
+            );
+            if let Some(step_name) = &entry.step_name {
+                let _ = write!(json, ",\"step_name\":{}", json_string(step_name.as_str()));
+            }
+            if let Some(trace_id) = entry.trace_id {
+                let _ = write!(json, ",\"trace_id\":{trace_id}");
+            }
+            if let Some(logical_clock) = entry.logical_clock {
+                let _ = write!(json, ",\"logical_clock\":{logical_clock}");
+            }
+            if let Some(detail) = &entry.detail {
+                let _ = write!(json, ",\"detail\":{}", json_string(detail));
+            }
+            if let Some(caused_by) = entry.caused_by {
+                let _ = write!(json, ",\"caused_by\":{caused_by}");
+            }
+            json.push('}');
+        }
+        json.push_str("]}");
+        json
+    }
+}
+
+impl SagaTimelineBuilder {
+    /// Adds what `participant` received and journaled for the saga.
+    pub fn with_journal<J>(mut self, participant: &str, journal: &J) -> Result<Self, JournalError>
+    where
+        J: ParticipantJournal + ?Sized,
+    {
+        let source = self.journals;
+        self.journals += 1;
+        for received in journal.incoming_history(self.saga_id)? {
+            let context = received.event.context();
+            self.entries.push(TimelineEntry {
+                index: 0,
+                participant: participant.into(),
+                kind: TimelineEntryKind::Received,
+                event_type: received.event.event_type(),
+                at_millis: received.recorded_at_millis,
+                step_name: Some(context.step_name.clone()),
+                trace_id: Some(context.trace_id),
+                logical_clock: Some(context.logical_clock),
+                detail: received_detail(&received.event),
+                caused_by: None,
+                causation_id: Some(context.causation_id),
+                journal_order: (source, received.inbox_id),
+            });
+        }
+        for recorded in journal.read(self.saga_id)? {
+            self.entries.push(TimelineEntry {
+                index: 0,
+                participant: participant.into(),
+                kind: TimelineEntryKind::Recorded,
+                event_type: recorded.event.event_type(),
+                at_millis: recorded.recorded_at_millis,
+                step_name: None,
+                trace_id: None,
+                logical_clock: None,
+                detail: match &recorded.event {
+                    ParticipantEvent::StepExecutionFailed { error, .. } => Some(error.clone()),
+                    _ => None,
+                },
+                caused_by: None,
+                causation_id: None,
+                journal_order: (source, recorded.sequence),
+            });
+        }
+        Ok(self)
+    }
+
+    pub fn build(self) -> SagaTimeline {
+        let mut entries = self.entries;
+        // Within a millisecond, a participant's receipt sorts before what it
+        // journaled in response.
+        entries.sort_by_key(|entry| {
+            (
+                entry.at_millis,
+                entry.logical_clock.unwrap_or(u64::MAX),
+                entry.kind == TimelineEntryKind::Recorded,
+                entry.journal_order,
+            )
+        });
+
+        let mut first_receipt: HashMap<u64, usize> = HashMap::new();
+        let mut last_receipt_of: HashMap<usize, usize> = HashMap::new();
+        for (index, entry) in entries.iter_mut().enumerate() {
+            let source = entry.journal_order.0;
+            entry.index = index;
+            entry.caused_by = match entry.kind {
+                TimelineEntryKind::Received => {
+                    let trace_id = entry.trace_id.unwrap_or_default();
+                    first_receipt.entry(trace_id).or_insert(index);
+                    last_receipt_of.insert(source, index);
+                    entry
+                        .causation_id
+                        .and_then(|cause| first_receipt.get(&cause).copied())
+                        .filter(|cause| *cause != index)
+                }
+                TimelineEntryKind::Recorded => last_receipt_of.get(&source).copied(),
+            };
+        }
+        SagaTimeline {
+            saga_id: self.saga_id,
+            entries,
+        }
+    }
+}
+
+fn received_detail(event: &SagaChoreographyEvent) -> Option<Box<str>> {
+    match event {
+        SagaChoreographyEvent::StepFailed { error, .. } => Some(error.clone()),
+        SagaChoreographyEvent::SagaFailed { reason, .. }
+        | SagaChoreographyEvent::SagaQuarantined { reason, .. }
+        | SagaChoreographyEvent::CompensationRequested { reason, .. } => Some(reason.clone()),
+        _ => None,
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        saga_started, step_completed, DedupeKey, DeterministicContextBuilder, InMemoryJournal,
+    };
+
+    #[test]
+    fn merges_participant_journals_into_a_causally_linked_timeline() {
+        let started_context = DeterministicContextBuilder::default().build();
+        let started = saga_started(started_context.clone(), b"order".to_vec());
+        let completed = step_completed(
+            started_context.next_step("risk_check".into()),
+            b"ok".to_vec(),
+            b"order".to_vec(),
+            false,
+        );
+        let saga_id = started_context.saga_id;
+
+        let risk = InMemoryJournal::new();
+        risk.record_incoming(saga_id, DedupeKey::from_event(&started), &started)
+            .unwrap();
+        risk.append(
+            saga_id,
+            ParticipantEvent::StepExecutionFailed {
+                error: "limit \"hit\"".into(),
+                requires_compensation: false,
+                failed_at_millis: 0,
+                details: Vec::new(),
+            },
+        )
+        .unwrap();
+        let order = InMemoryJournal::new();
+        order
+            .record_incoming(saga_id, DedupeKey::from_event(&completed), &completed)
+            .unwrap();
+
+        let timeline = SagaTimeline::builder(saga_id)
+            .with_journal("risk_check", &risk)
+            .unwrap()
+            .with_journal("place_order", &order)
+            .unwrap()
+            .build();
+
+        let find = |event_type: &str| {
+            timeline
+                .entries
+                .iter()
+                .find(|entry| entry.event_type == event_type)
+                .unwrap()
+        };
+        let started_at = find("saga_started").index;
+        assert_eq!(find("step_completed").caused_by, Some(started_at));
+        assert_eq!(find("step_execution_failed").caused_by, Some(started_at));
+        assert_eq!(timeline.effects_of(started_at).count(), 2);
+        let json = timeline.to_json();
+        assert!(json.starts_with(&format!("{{\"saga_id\":{},\"entries\":[", saga_id.get())));
+        assert!(json.contains("\"detail\":\"limit \\\"hit\\\"\""));
+    }
+}
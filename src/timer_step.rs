@@ -0,0 +1,227 @@
+//! A step that only waits.
+//!
+//! [`TimerStep`] is a ready-made async participant for "wait 30s after
+//! placing before checking fill": it completes its step a fixed delay after
+//! the step first started, passing its input through as output so the step
+//! after it sees what the step before produced.
+//!
+//! The delay is measured from the first `StepExecutionStarted` entry of the
+//! saga in the timer's journal. When the step is replayed after a restart,
+//! e.g. by `replay_async_saga_inbox_with_emit`, only the remaining time is
+//! waited, and a timer that already ran out completes right away. With a
+//! durable journal the wait thus survives a crash.
+
+use std::time::Duration;
+
+use crate::{
+    AsyncSagaParticipant, CompensationError, DependencySpec, HasSagaParticipantSupport,
+    ParticipantDedupeStore, ParticipantEvent, ParticipantJournal, SagaBoxFuture, SagaContext,
+    SagaParticipantSupport, SagaStateExt, StepError, StepOutput,
+};
+
+/// Async participant that completes `step_name` after `delay`.
+pub struct TimerStep<J, D>
+where
+    J: ParticipantJournal,
+    D: ParticipantDedupeStore,
+{
+    saga: SagaParticipantSupport<J, D>,
+    step_name: &'static str,
+    saga_types: &'static [&'static str],
+    depends_on: DependencySpec,
+    delay: Duration,
+}
+
+impl<J, D> TimerStep<J, D>
+where
+    J: ParticipantJournal,
+    D: ParticipantDedupeStore,
+{
+    /// Runs on saga start until [`after`](Self::after) says otherwise.
+    pub fn new(
+        step_name: &'static str,
+        saga_types: &'static [&'static str],
+        delay: Duration,
+        saga: SagaParticipantSupport<J, D>,
+    ) -> Self {
+        Self {
+            saga,
+            step_name,
+            saga_types,
+            depends_on: DependencySpec::OnSagaStart,
+            delay,
+        }
+    }
+
+    /// Starts the timer once `depends_on` is satisfied.
+    pub fn after(mut self, depends_on: DependencySpec) -> Self {
+        self.depends_on = depends_on;
+        self
+    }
+
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    /// Time left on the timer of `context`'s saga at `now_millis`, counted
+    /// from the first journaled start of the step.
+    pub fn remaining(&self, context: &SagaContext, now_millis: u64) -> Duration {
+        let started_at_millis = self
+            .saga
+            .journal
+            .read(context.saga_id)
+            .ok()
+            .and_then(|entries| {
+                entries.into_iter().find_map(|entry| match entry.event {
+                    ParticipantEvent::StepExecutionStarted {
+                        started_at_millis, ..
+                    } => Some(started_at_millis),
+                    _ => None,
+                })
+            })
+            .unwrap_or(now_millis);
+        let due_at_millis = started_at_millis.saturating_add(self.delay.as_millis() as u64);
+        Duration::from_millis(due_at_millis.saturating_sub(now_millis))
+    }
+}
+
+impl<J, D> HasSagaParticipantSupport for TimerStep<J, D>
+where
+    J: ParticipantJournal,
+    D: ParticipantDedupeStore,
+{
+    type Journal = J;
+    type Dedupe = D;
+
+    fn saga_support(&self) -> &SagaParticipantSupport<J, D> {
+        &self.saga
+    }
+
+    fn saga_support_mut(&mut self) -> &mut SagaParticipantSupport<J, D> {
+        &mut self.saga
+    }
+}
+
+impl<J, D> AsyncSagaParticipant for TimerStep<J, D>
+where
+    J: ParticipantJournal,
+    D: ParticipantDedupeStore,
+{
+    type Error = StepError;
+
+    fn step_name(&self) -> &str {
+        self.step_name
+    }
+
+    fn saga_types(&self) -> &[&'static str] {
+        self.saga_types
+    }
+
+    fn depends_on(&self) -> DependencySpec {
+        self.depends_on.clone()
+    }
+
+    fn execute_step<'a>(
+        &'a mut self,
+        context: &'a SagaContext,
+        input: &'a [u8],
+    ) -> SagaBoxFuture<'a, Result<StepOutput, StepError>> {
+        let remaining = self.remaining(context, self.now_millis());
+        Box::pin(async move {
+            if !remaining.is_zero() {
+                tokio::time::sleep(remaining).await;
+            }
+            Ok(StepOutput::Completed {
+                output: input.to_vec(),
+                compensation_data: Vec::new(),
+            })
+        })
+    }
+
+    fn compensate_step<'a>(
+        &'a mut self,
+        _context: &'a SagaContext,
+        _compensation_data: &'a [u8],
+    ) -> SagaBoxFuture<'a, Result<(), CompensationError>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+impl<J, D> std::fmt::Debug for TimerStep<J, D>
+where
+    J: ParticipantJournal,
+    D: ParticipantDedupeStore,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TimerStep")
+            .field("step_name", &self.step_name)
+            .field("depends_on", &self.depends_on)
+            .field("delay", &self.delay)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        handle_async_saga_event_with_emit, step_completed, DeterministicContextBuilder,
+        InMemoryDedupe, InMemoryJournal, SagaChoreographyEvent,
+    };
+
+    #[tokio::test]
+    async fn timer_resumes_from_the_journaled_start() {
+        let mut saga = SagaParticipantSupport::new(InMemoryJournal::new(), InMemoryDedupe::new());
+        saga.clock = Some(Arc::new(|| 1_000_000));
+        let mut timer = TimerStep::new(
+            "wait_for_fill",
+            &["order_lifecycle"],
+            Duration::from_secs(30),
+            saga,
+        )
+        .after(DependencySpec::after("place_order"));
+
+        let placed = DeterministicContextBuilder::default()
+            .with_step_name("place_order")
+            .build();
+        let waiting = placed.next_step("wait_for_fill".into());
+        assert_eq!(
+            timer.remaining(&waiting, 1_000_000),
+            Duration::from_secs(30)
+        );
+        // A start journaled before a restart, 29.99s ago.
+        timer
+            .saga
+            .journal
+            .append(
+                waiting.saga_id,
+                ParticipantEvent::StepExecutionStarted {
+                    attempt: 1,
+                    started_at_millis: 970_010,
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            timer.remaining(&waiting, 1_000_000),
+            Duration::from_millis(10)
+        );
+
+        let mut emitted = Vec::new();
+        handle_async_saga_event_with_emit(
+            &mut timer,
+            step_completed(placed, b"order-7".to_vec(), Vec::new(), true),
+            |event| emitted.push(event),
+        )
+        .await;
+        let Some(SagaChoreographyEvent::StepCompleted {
+            context, output, ..
+        }) = emitted.last()
+        else {
+            panic!("expected the timer to complete: {emitted:?}");
+        };
+        assert_eq!(context.step_name.as_ref(), "wait_for_fill");
+        assert_eq!(output, b"order-7");
+    }
+}
@@ -0,0 +1,239 @@
+//! Sampling of per-step telemetry by saga priority.
+//!
+//! Routine sagas can produce more step callbacks than a tracing backend
+//! wants to keep, while an exit or a liquidation must be traceable step by
+//! step. A [`TracePolicy`] maps saga types to a [`SagaPriority`] and samples
+//! routine sagas; [`SampledObserver`] wraps an observer and consults it
+//! before every per-step callback:
+//!
+//! ```ignore
+//! let policy = TracePolicy::sampled(0.05)
+//!     .with_priority("position_exit", SagaPriority::High)
+//!     .with_priority("liquidation", SagaPriority::High);
+//! support.attach_observer(Arc::new(SampledObserver::new(TracingObserver, policy)));
+//! ```
+//!
+//! The decision depends only on the saga id, so a sampled saga is traced
+//! in full on every participant. Saga outcomes, step failures, timeouts,
+//! panics and missing state entries are always passed on.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::{SagaContext, SagaId, SagaObserver, StateKind};
+
+/// How closely sagas of a type are traced.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SagaPriority {
+    /// Per-step telemetry is sampled.
+    #[default]
+    Routine,
+    /// Every step is traced.
+    High,
+}
+
+/// Which sagas get per-step telemetry.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TracePolicy {
+    priorities: HashMap<Box<str>, SagaPriority>,
+    routine_sample_rate: f64,
+}
+
+impl Default for TracePolicy {
+    fn default() -> Self {
+        Self::sampled(1.0)
+    }
+}
+
+impl TracePolicy {
+    /// Traces the given fraction of routine sagas, clamped to `0.0..=1.0`.
+    pub fn sampled(routine_sample_rate: f64) -> Self {
+        Self {
+            priorities: HashMap::new(),
+            routine_sample_rate: routine_sample_rate.clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn with_priority(mut self, saga_type: &str, priority: SagaPriority) -> Self {
+        self.priorities.insert(saga_type.into(), priority);
+        self
+    }
+
+    /// Priority of `saga_type`; [`SagaPriority::Routine`] unless set.
+    pub fn priority(&self, saga_type: &str) -> SagaPriority {
+        self.priorities.get(saga_type).copied().unwrap_or_default()
+    }
+
+    /// Whether `context`'s saga gets per-step telemetry.
+    pub fn traces(&self, context: &SagaContext) -> bool {
+        match self.priority(context.saga_type.as_str()) {
+            SagaPriority::High => true,
+            SagaPriority::Routine => sample_point(context.saga_id) < self.routine_sample_rate,
+        }
+    }
+}
+
+/// Uniform point in `[0, 1)` for `saga_id`.
+fn sample_point(saga_id: SagaId) -> f64 {
+    // Fibonacci hashing; unrelated to the partition hash, so sampling does
+    // not follow replica assignment.
+    let mixed = saga_id.get().wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    (mixed >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// [`SagaObserver`] that drops the per-step callbacks of sagas its
+/// [`TracePolicy`] does not trace.
+pub struct SampledObserver<O> {
+    inner: O,
+    policy: TracePolicy,
+}
+
+impl<O: SagaObserver> SampledObserver<O> {
+    pub fn new(inner: O, policy: TracePolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    pub fn inner(&self) -> &O {
+        &self.inner
+    }
+
+    pub fn policy(&self) -> &TracePolicy {
+        &self.policy
+    }
+}
+
+impl<O: SagaObserver> SagaObserver for SampledObserver<O> {
+    fn on_saga_started(&self, context: &SagaContext) {
+        self.inner.on_saga_started(context);
+    }
+
+    fn on_step_started(&self, context: &SagaContext, step: &str) {
+        if self.policy.traces(context) {
+            self.inner.on_step_started(context, step);
+        }
+    }
+
+    fn on_step_completed(&self, context: &SagaContext, step: &str, duration_millis: u64) {
+        if self.policy.traces(context) {
+            self.inner.on_step_completed(context, step, duration_millis);
+        }
+    }
+
+    fn on_step_failed(&self, context: &SagaContext, step: &str, error: &str) {
+        self.inner.on_step_failed(context, step, error);
+    }
+
+    fn on_compensation_started(&self, context: &SagaContext, step: &str) {
+        if self.policy.traces(context) {
+            self.inner.on_compensation_started(context, step);
+        }
+    }
+
+    fn on_compensation_completed(&self, context: &SagaContext, step: &str) {
+        if self.policy.traces(context) {
+            self.inner.on_compensation_completed(context, step);
+        }
+    }
+
+    fn on_saga_completed(&self, context: &SagaContext) {
+        self.inner.on_saga_completed(context);
+    }
+
+    fn on_saga_failed(&self, context: &SagaContext, reason: &str) {
+        self.inner.on_saga_failed(context, reason);
+    }
+
+    fn on_saga_quarantined(&self, context: &SagaContext, step: &str, reason: &str) {
+        self.inner.on_saga_quarantined(context, step, reason);
+    }
+
+    fn on_step_retry(&self, context: &SagaContext, step: &str, attempt: u32, delay: Duration) {
+        if self.policy.traces(context) {
+            self.inner.on_step_retry(context, step, attempt, delay);
+        }
+    }
+
+    fn on_journal_append_retry(
+        &self,
+        context: &SagaContext,
+        step: &str,
+        attempt: u32,
+        delay: Duration,
+    ) {
+        if self.policy.traces(context) {
+            self.inner
+                .on_journal_append_retry(context, step, attempt, delay);
+        }
+    }
+
+    fn on_step_timeout(&self, context: &SagaContext, step: &str, elapsed: Duration) {
+        self.inner.on_step_timeout(context, step, elapsed);
+    }
+
+    fn on_duplicate_event(&self, context: &SagaContext, event_type: &str) {
+        if self.policy.traces(context) {
+            self.inner.on_duplicate_event(context, event_type);
+        }
+    }
+
+    fn on_step_panicked(&self, context: &SagaContext, step: &str, message: &str) {
+        self.inner.on_step_panicked(context, step, message);
+    }
+
+    fn on_missing_state(&self, context: &SagaContext, step: &str, expected: StateKind) {
+        self.inner.on_missing_state(context, step, expected);
+    }
+}
+
+impl<O> std::fmt::Debug for SampledObserver<O> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SampledObserver")
+            .field("policy", &self.policy)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DeterministicContextBuilder, RecordingObserver};
+
+    #[test]
+    fn high_priority_sagas_are_traced_in_full() {
+        let policy = TracePolicy::sampled(0.25).with_priority("liquidation", SagaPriority::High);
+        let context = |saga_id: u64, saga_type: &str| {
+            DeterministicContextBuilder::default()
+                .with_saga_id(saga_id)
+                .with_saga_type(saga_type)
+                .build()
+        };
+        let traced = (1..=1_000)
+            .filter(|saga_id| policy.traces(&context(*saga_id, "order_lifecycle")))
+            .count();
+        assert!((200..=300).contains(&traced), "traced {traced}");
+        assert!((1..=1_000).all(|saga_id| policy.traces(&context(saga_id, "liquidation"))));
+
+        let untraced = (1..)
+            .map(|saga_id| context(saga_id, "order_lifecycle"))
+            .find(|context| !policy.traces(context))
+            .unwrap();
+        let observer = SampledObserver::new(RecordingObserver::new(), policy);
+        for context in [&untraced, &context(untraced.saga_id.get(), "liquidation")] {
+            observer.on_saga_started(context);
+            observer.on_step_started(context, "close_position");
+            observer.on_step_failed(context, "close_position", "venue down");
+            observer.on_missing_state(context, "close_position", StateKind::Executing);
+        }
+        observer.inner().assert_snapshot(
+            "
+            #1 order_lifecycle saga_started
+            #1 order_lifecycle step_failed step=close_position error=\"venue down\"
+            #1 order_lifecycle missing_state step=close_position expected=Executing
+            #1 liquidation saga_started
+            #1 liquidation step_started step=close_position
+            #1 liquidation step_failed step=close_position error=\"venue down\"
+            #1 liquidation missing_state step=close_position expected=Executing
+            ",
+        );
+    }
+}
@@ -3,10 +3,35 @@
 use std::future::Future;
 use std::pin::Pin;
 
-use crate::{CompensationError, SagaContext, StepError, StepOutput};
+use crate::{
+    AuthError, CompensationError, OffloadedStep, SagaContext, StepError, StepName, StepOutput,
+};
 
 use icanact_core::{ActorId, ActorIdError};
 
+/// Whether `saga_type` matches `pattern`, in which each `*` stands for any
+/// run of characters: `"deribit_*"` matches `"deribit_order"`, `"*"`
+/// matches every saga type.
+pub fn saga_type_matches(pattern: &str, saga_type: &str) -> bool {
+    let Some((prefix, rest)) = pattern.split_once('*') else {
+        return pattern == saga_type;
+    };
+    let Some(mut remaining) = saga_type.strip_prefix(prefix) else {
+        return false;
+    };
+    let mut parts = rest.split('*').peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            return remaining.ends_with(part);
+        }
+        match remaining.find(part) {
+            Some(at) => remaining = &remaining[at + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
 /// Trait for actors that participate in choreography-based sagas.
 ///
 /// Actors implementing this trait handle saga events alongside their
@@ -65,9 +90,19 @@ pub trait SagaParticipant {
         }
     }
 
-    /// Which saga types this participant joins
+    /// Which saga types this participant joins; entries may be patterns
+    /// such as `"deribit_*"` (see [`saga_type_matches`]).
     fn saga_types(&self) -> &[&'static str];
 
+    /// Whether events of `saga_type` are handled here. The helpers drop
+    /// events of other types.
+    /// Default: `saga_type` matches one of [`SagaParticipant::saga_types`]
+    fn matches_saga_type(&self, saga_type: &str) -> bool {
+        self.saga_types()
+            .iter()
+            .any(|pattern| saga_type_matches(pattern, saga_type))
+    }
+
     /// Execute the forward step
     ///
     /// Called when a triggering event is received (based on `depends_on`).
@@ -89,6 +124,16 @@ pub trait SagaParticipant {
 
     // === Optional Hooks ===
 
+    /// Work of `execute_step` detached from the participant. With a
+    /// [`StepExecutor`](crate::StepExecutor) attached to the support, the
+    /// helpers run it there instead of calling `execute_step`, so the actor
+    /// keeps handling events meanwhile and picks the result up later (see
+    /// [`crate::poll_offloaded_steps_with_emit`]).
+    /// Default: none, `execute_step` runs on the actor
+    fn offload_step(&self, _context: &SagaContext, _input: &[u8]) -> Option<OffloadedStep> {
+        None
+    }
+
     /// Called after saga completes successfully
     fn on_saga_completed(&mut self, _context: &SagaContext) {}
 
@@ -101,11 +146,44 @@ pub trait SagaParticipant {
     /// Called when saga is quarantined
     fn on_quarantined(&mut self, _context: &SagaContext, _reason: &str) {}
 
+    /// External identifiers (order id, client id) to index the saga under
+    /// once this step completed with `output`; see
+    /// [`EffectCorrelationIndex`](crate::EffectCorrelationIndex).
+    /// Default: none
+    fn correlation_keys(&self, _context: &SagaContext, _output: &[u8]) -> Vec<Box<str>> {
+        Vec::new()
+    }
+
+    /// Decides whether an incoming event of `event_type` (e.g.
+    /// `"compensation_requested"`) is processed. Rejected events are
+    /// journaled as `ParticipantEvent::EventRejected` and dropped.
+    /// Default: allow everything
+    fn authorize_event(&self, _context: &SagaContext, _event_type: &str) -> Result<(), AuthError> {
+        Ok(())
+    }
+
+    /// Whether this participant handles sagas running under
+    /// `workflow_version` of their contract. Events of other versions are
+    /// ignored, so during a rolling deploy an old participant leaves sagas
+    /// of the new version to the participants that support it.
+    /// Default: every version
+    fn supports_workflow_version(&self, _workflow_version: u32) -> bool {
+        true
+    }
+
     /// When does this participant execute?
     /// Default: execute when saga starts
     fn depends_on(&self) -> DependencySpec {
         DependencySpec::OnSagaStart
     }
+
+    /// Whether a terminal failure of this step fails the whole saga: the
+    /// helpers then emit `SagaFailed` right after the step's `StepFailed`
+    /// instead of leaving that to a terminal resolver.
+    /// Default: false
+    fn is_critical(&self) -> bool {
+        false
+    }
 }
 
 /// Workflow-scoped participant contract for actors that join multiple saga workflows.
@@ -135,9 +213,17 @@ pub trait SagaWorkflowParticipant<A>: Send + Sync + 'static {
         }
     }
 
-    /// Which saga types this workflow participant joins.
+    /// Which saga types this workflow participant joins; entries may be
+    /// patterns (see [`saga_type_matches`]).
     fn saga_types(&self) -> &[&'static str];
 
+    /// See [`SagaParticipant::matches_saga_type`].
+    fn matches_saga_type(&self, saga_type: &str) -> bool {
+        self.saga_types()
+            .iter()
+            .any(|pattern| saga_type_matches(pattern, saga_type))
+    }
+
     /// Execute the forward step for this workflow.
     fn execute_step(
         &self,
@@ -166,10 +252,41 @@ pub trait SagaWorkflowParticipant<A>: Send + Sync + 'static {
     /// Called when saga is quarantined.
     fn on_quarantined(&self, _actor: &mut A, _context: &SagaContext, _reason: &str) {}
 
+    /// See [`SagaParticipant::correlation_keys`].
+    fn correlation_keys(
+        &self,
+        _actor: &A,
+        _context: &SagaContext,
+        _output: &[u8],
+    ) -> Vec<Box<str>> {
+        Vec::new()
+    }
+
+    /// Decides whether an incoming event is processed; see
+    /// [`SagaParticipant::authorize_event`].
+    fn authorize_event(
+        &self,
+        _actor: &A,
+        _context: &SagaContext,
+        _event_type: &str,
+    ) -> Result<(), AuthError> {
+        Ok(())
+    }
+
+    /// See [`SagaParticipant::supports_workflow_version`].
+    fn supports_workflow_version(&self, _actor: &A, _workflow_version: u32) -> bool {
+        true
+    }
+
     /// When does this participant execute?
     fn depends_on(&self) -> DependencySpec {
         DependencySpec::OnSagaStart
     }
+
+    /// See [`SagaParticipant::is_critical`].
+    fn is_critical(&self) -> bool {
+        false
+    }
 }
 
 /// Access trait for actors that register distinct workflow-scoped participant contracts.
@@ -215,6 +332,12 @@ pub trait AsyncSagaParticipant {
 
     fn saga_types(&self) -> &[&'static str];
 
+    fn matches_saga_type(&self, saga_type: &str) -> bool {
+        self.saga_types()
+            .iter()
+            .any(|pattern| saga_type_matches(pattern, saga_type))
+    }
+
     fn execute_step<'a>(
         &'a mut self,
         context: &'a SagaContext,
@@ -227,6 +350,12 @@ pub trait AsyncSagaParticipant {
         compensation_data: &'a [u8],
     ) -> SagaBoxFuture<'a, Result<(), CompensationError>>;
 
+    /// See [`SagaParticipant::offload_step`]; results are picked up by
+    /// [`crate::poll_offloaded_steps_with_emit_async`].
+    fn offload_step(&self, _context: &SagaContext, _input: &[u8]) -> Option<OffloadedStep> {
+        None
+    }
+
     fn on_saga_completed(&mut self, _context: &SagaContext) {}
 
     fn on_saga_failed(&mut self, _context: &SagaContext, _reason: &str) {}
@@ -235,32 +364,66 @@ pub trait AsyncSagaParticipant {
 
     fn on_quarantined(&mut self, _context: &SagaContext, _reason: &str) {}
 
+    fn correlation_keys(&self, _context: &SagaContext, _output: &[u8]) -> Vec<Box<str>> {
+        Vec::new()
+    }
+
+    fn authorize_event(&self, _context: &SagaContext, _event_type: &str) -> Result<(), AuthError> {
+        Ok(())
+    }
+
+    fn supports_workflow_version(&self, _workflow_version: u32) -> bool {
+        true
+    }
+
     fn depends_on(&self) -> DependencySpec {
         DependencySpec::OnSagaStart
     }
+
+    fn is_critical(&self) -> bool {
+        false
+    }
 }
 
 /// Dependency specification - when does this step execute?
+///
+/// Step lists are built with [`step_names!`](crate::step_names):
+/// `DependencySpec::AllOf(step_names!["risk_check", "positions_check"])`.
 #[derive(Clone, Debug)]
 pub enum DependencySpec {
     /// Execute when saga starts (no dependencies)
     OnSagaStart,
     /// Execute after ANY of these steps complete
-    AnyOf(&'static [&'static str]),
+    AnyOf(&'static [StepName]),
     /// Execute after ALL of these steps complete
-    AllOf(&'static [&'static str]),
+    AllOf(&'static [StepName]),
     /// Execute after this specific step
-    After(&'static str),
+    After(StepName),
 }
 
 impl DependencySpec {
+    /// Execute after the step named `step`.
+    pub const fn after(step: &'static str) -> Self {
+        Self::After(StepName::new(step))
+    }
+
     /// Check if a completed step satisfies this dependency
     pub fn is_satisfied_by(&self, completed_step: &str) -> bool {
         match self {
             DependencySpec::OnSagaStart => false,
-            DependencySpec::After(step) => completed_step == *step,
-            DependencySpec::AnyOf(steps) => steps.contains(&completed_step),
-            DependencySpec::AllOf(steps) => steps.contains(&completed_step),
+            DependencySpec::After(step) => *step == completed_step,
+            DependencySpec::AnyOf(steps) | DependencySpec::AllOf(steps) => {
+                steps.iter().any(|step| *step == completed_step)
+            }
+        }
+    }
+
+    /// Steps this dependency names, in declaration order.
+    pub fn steps(&self) -> &[StepName] {
+        match self {
+            DependencySpec::OnSagaStart => &[],
+            DependencySpec::After(step) => std::slice::from_ref(step),
+            DependencySpec::AnyOf(steps) | DependencySpec::AllOf(steps) => steps,
         }
     }
 
@@ -282,9 +445,21 @@ mod tests {
 
     #[test]
     fn test_dependency_spec() {
-        let spec = DependencySpec::After("reserve_inventory");
+        let spec = DependencySpec::after("reserve_inventory");
         assert!(spec.is_satisfied_by("reserve_inventory"));
         assert!(!spec.is_satisfied_by("other_step"));
         assert!(!spec.is_on_saga_start());
     }
+
+    #[test]
+    fn saga_type_patterns_match_with_wildcards() {
+        assert!(saga_type_matches("order_lifecycle", "order_lifecycle"));
+        assert!(!saga_type_matches("order_lifecycle", "order_lifecycle_v2"));
+        assert!(saga_type_matches("deribit_*", "deribit_order"));
+        assert!(!saga_type_matches("deribit_*", "binance_order"));
+        assert!(saga_type_matches("*", "anything"));
+        assert!(saga_type_matches("*_order", "deribit_order"));
+        assert!(saga_type_matches("deribit_*_v*", "deribit_order_v2"));
+        assert!(!saga_type_matches("deribit_*_v*", "deribit_order"));
+    }
 }
@@ -0,0 +1,315 @@
+//! Start-up verification of a participant's wiring.
+//!
+//! A participant whose step name, dependencies or saga types do not match
+//! the registered workflow contracts never hears the events it waits for:
+//! its sagas stall until the terminal resolver times them out. Checking it
+//! against the bus once its contracts are registered turns such typos into
+//! start-up errors:
+//!
+//! ```ignore
+//! bus.register_workflow_contract_provider::<OrderLifecycleContract>()?;
+//! verify_participant(&reserver, &bus)?;
+//! ```
+//!
+//! Unlike [`SagaChoreographyBus::validate_participant_steps`], every problem
+//! is reported, and a declared step whose contract lists other dependencies
+//! than the participant's is one too.
+
+use std::collections::BTreeSet;
+
+use crate::{SagaChoreographyBus, SagaParticipant, StepName};
+
+/// One way a participant does not match the registered workflow contracts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParticipantWiringProblem {
+    /// No contract is registered for a saga type (pattern) the participant
+    /// joins.
+    UnknownSagaType { saga_type: Box<str> },
+    /// No version of the saga type's contract declares the step.
+    UndeclaredStep { saga_type: Box<str>, step: StepName },
+    /// No version of the saga type's contract declares a step the
+    /// participant depends on.
+    UndeclaredDependency {
+        saga_type: Box<str>,
+        dependency: StepName,
+    },
+    /// The contract declares the step after other steps than the
+    /// participant waits for.
+    DependencyMismatch {
+        saga_type: Box<str>,
+        declared: Vec<&'static str>,
+        participant: Vec<StepName>,
+    },
+}
+
+impl std::fmt::Display for ParticipantWiringProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownSagaType { saga_type } => {
+                write!(
+                    f,
+                    "no workflow contract registered for saga_type={saga_type}"
+                )
+            }
+            Self::UndeclaredStep { saga_type, step } => {
+                write!(f, "step not declared: saga_type={saga_type} step={step}")
+            }
+            Self::UndeclaredDependency {
+                saga_type,
+                dependency,
+            } => write!(
+                f,
+                "dependency not declared: saga_type={saga_type} dependency={dependency}"
+            ),
+            Self::DependencyMismatch {
+                saga_type,
+                declared,
+                participant,
+            } => {
+                let participant: Vec<&str> = participant.iter().map(StepName::as_str).collect();
+                write!(
+                    f,
+                    "dependencies differ from contract: saga_type={saga_type} declared={} participant={}",
+                    declared.join(","),
+                    participant.join(",")
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "participant {participant_id} step {step_name} does not match the workflow contracts: {}",
+    join_problems(.problems)
+)]
+pub struct ParticipantVerificationError {
+    pub participant_id: Box<str>,
+    pub step_name: StepName,
+    pub problems: Vec<ParticipantWiringProblem>,
+}
+
+fn join_problems(problems: &[ParticipantWiringProblem]) -> String {
+    let problems: Vec<String> = problems.iter().map(ToString::to_string).collect();
+    problems.join("; ")
+}
+
+/// Checks `participant` against the workflow contracts registered on `bus`:
+/// each of its saga types has a contract, which declares its step and the
+/// steps it depends on, with the same dependencies.
+pub fn verify_participant<P>(
+    participant: &P,
+    bus: &SagaChoreographyBus,
+) -> Result<(), ParticipantVerificationError>
+where
+    P: SagaParticipant,
+{
+    let step_name = participant.step_name();
+    let depends_on = participant.depends_on();
+    let dependencies: BTreeSet<&str> = depends_on.steps().iter().map(StepName::as_str).collect();
+    let mut problems = Vec::new();
+    for pattern in participant.saga_types() {
+        let contracts = bus.workflow_contracts_matching(pattern);
+        if contracts.is_empty() {
+            problems.push(ParticipantWiringProblem::UnknownSagaType {
+                saga_type: (*pattern).into(),
+            });
+        }
+        for (saga_type, versions) in contracts {
+            let declared = |name: &str| {
+                versions
+                    .iter()
+                    .flat_map(|steps| steps.iter())
+                    .any(|step| step.step_name == name)
+            };
+            if !declared(step_name) {
+                problems.push(ParticipantWiringProblem::UndeclaredStep {
+                    saga_type: saga_type.clone(),
+                    step: step_name.into(),
+                });
+            }
+            for dependency in &dependencies {
+                if !declared(dependency) {
+                    problems.push(ParticipantWiringProblem::UndeclaredDependency {
+                        saga_type: saga_type.clone(),
+                        dependency: (*dependency).into(),
+                    });
+                }
+            }
+            // Declarations of the step in each version; any one agreeing
+            // with the participant is enough during a rolling deploy.
+            let declarations: Vec<BTreeSet<&'static str>> = versions
+                .iter()
+                .flat_map(|steps| steps.iter())
+                .filter(|step| step.step_name == step_name)
+                .map(|step| {
+                    crate::workflow_contract::dependency_steps(step.depends_on)
+                        .into_iter()
+                        .collect()
+                })
+                .collect();
+            if let Some(first) = declarations.first() {
+                if !declarations.contains(&dependencies) {
+                    problems.push(ParticipantWiringProblem::DependencyMismatch {
+                        saga_type,
+                        declared: first.iter().copied().collect(),
+                        participant: depends_on.steps().to_vec(),
+                    });
+                }
+            }
+        }
+    }
+    if problems.is_empty() {
+        return Ok(());
+    }
+    let error = ParticipantVerificationError {
+        participant_id: participant.participant_id_owned(),
+        step_name: step_name.into(),
+        problems,
+    };
+    tracing::error!(
+        target: "core::saga",
+        event = "saga_participant_verification_failed",
+        participant_id = %error.participant_id,
+        step_name,
+        error = %error
+    );
+    Err(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        CompensationError, DependencySpec, FailureAuthority, SagaContext, SagaWorkflowContract,
+        SagaWorkflowStepContract, StepError, StepOutput, SuccessCriteria, TerminalPolicy,
+        WorkflowDependencySpec,
+    };
+
+    struct OrderLifecycle;
+
+    impl SagaWorkflowContract for OrderLifecycle {
+        fn saga_type() -> &'static str {
+            "order_lifecycle"
+        }
+
+        fn first_step() -> &'static str {
+            "risk_check"
+        }
+
+        fn steps() -> &'static [SagaWorkflowStepContract] {
+            &[
+                SagaWorkflowStepContract {
+                    step_name: "risk_check",
+                    participant_id: "risk",
+                    depends_on: WorkflowDependencySpec::OnSagaStart,
+                },
+                SagaWorkflowStepContract {
+                    step_name: "place_order",
+                    participant_id: "orders",
+                    depends_on: WorkflowDependencySpec::After("risk_check"),
+                },
+            ]
+        }
+
+        fn terminal_policy() -> TerminalPolicy {
+            TerminalPolicy::new(
+                "order_lifecycle".into(),
+                "order_lifecycle/default".into(),
+                FailureAuthority::AnyParticipant,
+                SuccessCriteria::AllOf(["place_order".into()].into_iter().collect()),
+                Duration::from_secs(30),
+                Duration::from_secs(30),
+                &[],
+            )
+        }
+    }
+
+    struct Step {
+        step_name: &'static str,
+        saga_types: &'static [&'static str],
+        depends_on: DependencySpec,
+    }
+
+    impl SagaParticipant for Step {
+        type Error = StepError;
+
+        fn step_name(&self) -> &str {
+            self.step_name
+        }
+
+        fn saga_types(&self) -> &[&'static str] {
+            self.saga_types
+        }
+
+        fn depends_on(&self) -> DependencySpec {
+            self.depends_on.clone()
+        }
+
+        fn execute_step(
+            &mut self,
+            _context: &SagaContext,
+            _input: &[u8],
+        ) -> Result<StepOutput, StepError> {
+            Ok(StepOutput::NoOp)
+        }
+
+        fn compensate_step(
+            &mut self,
+            _context: &SagaContext,
+            _compensation_data: &[u8],
+        ) -> Result<(), CompensationError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn wiring_typos_are_reported_together() {
+        let bus = SagaChoreographyBus::new();
+        bus.register_workflow_contract_provider::<OrderLifecycle>()
+            .expect("contract should register");
+
+        let wired = Step {
+            step_name: "place_order",
+            saga_types: &["order_lifecycle"],
+            depends_on: DependencySpec::after("risk_check"),
+        };
+        verify_participant(&wired, &bus).expect("matching participant should verify");
+
+        let miswired = Step {
+            step_name: "place_ordr",
+            saga_types: &["order_lifecycle", "order_lifecyle"],
+            depends_on: DependencySpec::after("risk_chek"),
+        };
+        let err = verify_participant(&miswired, &bus).expect_err("typos should be reported");
+        assert_eq!(
+            err.problems,
+            vec![
+                ParticipantWiringProblem::UndeclaredStep {
+                    saga_type: "order_lifecycle".into(),
+                    step: "place_ordr".into(),
+                },
+                ParticipantWiringProblem::UndeclaredDependency {
+                    saga_type: "order_lifecycle".into(),
+                    dependency: "risk_chek".into(),
+                },
+                ParticipantWiringProblem::UnknownSagaType {
+                    saga_type: "order_lifecyle".into(),
+                },
+            ]
+        );
+
+        let eager = Step {
+            depends_on: DependencySpec::OnSagaStart,
+            ..wired
+        };
+        let err = verify_participant(&eager, &bus).expect_err("dependencies should match");
+        assert!(
+            err.to_string()
+                .ends_with("declared=risk_check participant="),
+            "unexpected error: {err}"
+        );
+    }
+}
@@ -0,0 +1,327 @@
+//! Saga outcome webhooks.
+//!
+//! [`WebhookObserver`] tells systems outside the pubsub, e.g. a compliance
+//! service, how sagas ended: it POSTs a JSON notification to every
+//! configured URL when a saga completes, fails or is quarantined. It is a
+//! [`SagaObserver`], and [`WebhookObserver::observe_event`] feeds it the
+//! terminal events of a bus subscription:
+//!
+//! ```ignore
+//! let webhooks = Arc::new(
+//!     WebhookObserver::new(["https://compliance.internal/sagas"]).with_secret(secret),
+//! );
+//! let hook = Arc::clone(&webhooks);
+//! let _subscription = bus.subscribe_saga_type_fn("order_lifecycle", move |event| {
+//!     hook.observe_event(event);
+//!     true
+//! });
+//! ```
+//!
+//! The body looks like
+//! `{"event":"saga_failed","saga_id":7,"saga_type":"order_lifecycle","step":null,"reason":"limit","timestamp_millis":1700000000000}`.
+//! Each request carries `Idempotency-Key: saga:{id}:{event}`; with a secret
+//! set, `X-Saga-Signature: sha256={hex}` holds the HMAC-SHA256 of the body.
+//!
+//! Deliveries run on a worker thread, so callbacks never block the
+//! participant. A delivery that gets no response or a 5xx is retried with
+//! doubling backoff; a 4xx is not. Notifications still queued when the
+//! observer is dropped are delivered before the worker exits.
+
+use std::sync::{mpsc, OnceLock};
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::{SagaChoreographyEvent, SagaContext, SagaObserver};
+
+const DEFAULT_WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+struct Notification {
+    idempotency_key: String,
+    body: Vec<u8>,
+}
+
+#[derive(Clone)]
+struct Delivery {
+    urls: Vec<Box<str>>,
+    secret: Option<Vec<u8>>,
+    max_attempts: u32,
+    initial_backoff: Duration,
+    timeout: Duration,
+}
+
+/// [`SagaObserver`] that POSTs saga outcomes to HTTP endpoints.
+pub struct WebhookObserver {
+    delivery: Delivery,
+    /// Queue of the delivery worker, spawned with the first notification.
+    worker: OnceLock<mpsc::Sender<Notification>>,
+}
+
+impl WebhookObserver {
+    /// Notifies every URL in `urls`, three attempts each, unsigned.
+    pub fn new<I, U>(urls: I) -> Self
+    where
+        I: IntoIterator<Item = U>,
+        U: Into<Box<str>>,
+    {
+        Self {
+            delivery: Delivery {
+                urls: urls.into_iter().map(Into::into).collect(),
+                secret: None,
+                max_attempts: 3,
+                initial_backoff: Duration::from_millis(200),
+                timeout: DEFAULT_WEBHOOK_TIMEOUT,
+            },
+            worker: OnceLock::new(),
+        }
+    }
+
+    /// Signs each body with HMAC-SHA256 under `secret`.
+    pub fn with_secret(mut self, secret: impl Into<Vec<u8>>) -> Self {
+        self.delivery.secret = Some(secret.into());
+        self
+    }
+
+    /// Gives each endpoint up to `max_attempts` tries, the first retry
+    /// after `initial_backoff`.
+    pub fn with_retries(mut self, max_attempts: u32, initial_backoff: Duration) -> Self {
+        self.delivery.max_attempts = max_attempts.max(1);
+        self.delivery.initial_backoff = initial_backoff;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.delivery.timeout = timeout;
+        self
+    }
+
+    pub fn urls(&self) -> impl Iterator<Item = &str> {
+        self.delivery.urls.iter().map(AsRef::as_ref)
+    }
+
+    /// Notifies about `event` if it ends its saga; other events are ignored.
+    pub fn observe_event(&self, event: &SagaChoreographyEvent) {
+        match event {
+            SagaChoreographyEvent::SagaCompleted { context } => self.on_saga_completed(context),
+            SagaChoreographyEvent::SagaFailed {
+                context, reason, ..
+            } => self.on_saga_failed(context, reason),
+            SagaChoreographyEvent::SagaQuarantined {
+                context,
+                reason,
+                step,
+                ..
+            } => self.on_saga_quarantined(context, step, reason),
+            _ => {}
+        }
+    }
+
+    fn notify(&self, event: &'static str, context: &SagaContext, step: Option<&str>, reason: &str) {
+        let body = serde_json::json!({
+            "event": event,
+            "saga_id": context.saga_id.get(),
+            "saga_type": &*context.saga_type,
+            "step": step,
+            "reason": (!reason.is_empty()).then_some(reason),
+            "timestamp_millis": context.event_timestamp_millis,
+        });
+        let notification = Notification {
+            idempotency_key: format!("saga:{}:{event}", context.saga_id.get()),
+            body: body.to_string().into_bytes(),
+        };
+        if self.worker().send(notification).is_err() {
+            tracing::warn!(
+                target: "core::saga",
+                event = "saga_webhook_worker_gone",
+                saga_id = context.saga_id.get()
+            );
+        }
+    }
+
+    fn worker(&self) -> &mpsc::Sender<Notification> {
+        self.worker.get_or_init(|| {
+            let (sender, receiver) = mpsc::channel::<Notification>();
+            let delivery = self.delivery.clone();
+            std::thread::Builder::new()
+                .name("saga-webhooks".into())
+                .spawn(move || {
+                    let agent = ureq::AgentBuilder::new().timeout(delivery.timeout).build();
+                    for notification in receiver {
+                        delivery.deliver(&agent, &notification);
+                    }
+                })
+                .expect("failed to spawn the saga webhook worker");
+            sender
+        })
+    }
+}
+
+impl Delivery {
+    fn deliver(&self, agent: &ureq::Agent, notification: &Notification) {
+        let signature = self.secret.as_deref().map(|secret| {
+            let mut mac =
+                Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+            mac.update(&notification.body);
+            format!("sha256={}", to_hex(&mac.finalize().into_bytes()))
+        });
+        for url in &self.urls {
+            let mut backoff = self.initial_backoff;
+            for attempt in 1..=self.max_attempts {
+                let mut request = agent
+                    .post(url)
+                    .set("Content-Type", "application/json")
+                    .set("Idempotency-Key", &notification.idempotency_key);
+                if let Some(signature) = &signature {
+                    request = request.set("X-Saga-Signature", signature);
+                }
+                let retryable = match request.send_bytes(&notification.body) {
+                    Ok(_) => break,
+                    Err(ureq::Error::Status(status, _)) => status >= 500,
+                    Err(ureq::Error::Transport(_)) => true,
+                };
+                if !retryable || attempt == self.max_attempts {
+                    tracing::warn!(
+                        target: "core::saga",
+                        event = "saga_webhook_delivery_failed",
+                        url = %url,
+                        idempotency_key = %notification.idempotency_key,
+                        attempts = attempt
+                    );
+                    break;
+                }
+                std::thread::sleep(backoff);
+                backoff = backoff.saturating_mul(2);
+            }
+        }
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+    bytes.iter().fold(String::new(), |mut out, byte| {
+        let _ = write!(out, "{byte:02x}");
+        out
+    })
+}
+
+impl SagaObserver for WebhookObserver {
+    fn on_saga_started(&self, _context: &SagaContext) {}
+    fn on_step_started(&self, _context: &SagaContext, _step: &str) {}
+    fn on_step_completed(&self, _context: &SagaContext, _step: &str, _duration_millis: u64) {}
+    fn on_step_failed(&self, _context: &SagaContext, _step: &str, _error: &str) {}
+    fn on_compensation_started(&self, _context: &SagaContext, _step: &str) {}
+    fn on_compensation_completed(&self, _context: &SagaContext, _step: &str) {}
+
+    fn on_saga_completed(&self, context: &SagaContext) {
+        self.notify("saga_completed", context, None, "");
+    }
+
+    fn on_saga_failed(&self, context: &SagaContext, reason: &str) {
+        self.notify("saga_failed", context, None, reason);
+    }
+
+    fn on_saga_quarantined(&self, context: &SagaContext, step: &str, reason: &str) {
+        self.notify("saga_quarantined", context, Some(step), reason);
+    }
+}
+
+impl std::fmt::Debug for WebhookObserver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebhookObserver")
+            .field("urls", &self.delivery.urls)
+            .field("signed", &self.delivery.secret.is_some())
+            .field("max_attempts", &self.delivery.max_attempts)
+            .field("initial_backoff", &self.delivery.initial_backoff)
+            .field("timeout", &self.delivery.timeout)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    use super::*;
+    use crate::DeterministicContextBuilder;
+
+    /// Answers one request per listed status and reports each request's
+    /// `X-Saga-Signature` and body.
+    fn stub_endpoint(statuses: Vec<u16>) -> (String, mpsc::Receiver<(String, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hooks", listener.local_addr().unwrap());
+        let (requests, received) = mpsc::channel();
+        std::thread::spawn(move || {
+            for status in statuses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut signature = String::new();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(": ") {
+                        match name.to_ascii_lowercase().as_str() {
+                            "x-saga-signature" => signature = value.to_owned(),
+                            "content-length" => length = value.parse().unwrap(),
+                            _ => {}
+                        }
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                requests
+                    .send((signature, String::from_utf8(body).unwrap()))
+                    .unwrap();
+                write!(
+                    reader.into_inner(),
+                    "HTTP/1.1 {status} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                )
+                .unwrap();
+            }
+        });
+        (url, received)
+    }
+
+    #[test]
+    fn failed_saga_is_posted_signed_and_retried_after_a_5xx() {
+        let (url, requests) = stub_endpoint(vec![503, 200]);
+        let webhooks = WebhookObserver::new([url])
+            .with_secret("s3cret")
+            .with_retries(3, Duration::from_millis(1));
+        let context = DeterministicContextBuilder::default()
+            .with_saga_id(7)
+            .build();
+
+        webhooks.observe_event(&SagaChoreographyEvent::StepStarted {
+            context: context.clone(),
+        });
+        webhooks.observe_event(&SagaChoreographyEvent::SagaFailed {
+            context: context.clone(),
+            reason: "limit".into(),
+            failure: None,
+        });
+
+        let timeout = Duration::from_secs(5);
+        let (signature, body) = requests.recv_timeout(timeout).unwrap();
+        assert_eq!(
+            requests.recv_timeout(timeout).unwrap(),
+            (signature.clone(), body.clone())
+        );
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"s3cret").unwrap();
+        mac.update(body.as_bytes());
+        assert_eq!(
+            signature,
+            format!("sha256={}", to_hex(&mac.finalize().into_bytes()))
+        );
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["event"], "saga_failed");
+        assert_eq!(body["saga_id"], 7);
+        assert_eq!(body["reason"], "limit");
+    }
+}
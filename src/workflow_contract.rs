@@ -1,5 +1,6 @@
 use std::collections::{HashMap, HashSet};
 
+use crate::workflow_diagram::{render_dot, render_mermaid, WorkflowDiagram};
 use crate::{SuccessCriteria, TerminalPolicy};
 
 #[derive(Clone, Copy, Debug)]
@@ -19,6 +20,14 @@ pub struct SagaWorkflowStepContract {
 
 pub trait SagaWorkflowContract {
     fn saga_type() -> &'static str;
+
+    /// Version of this definition of the saga type. The bus keeps one
+    /// contract per version, so a rolling deploy can register the new
+    /// definition next to the old one while sagas of both run.
+    fn workflow_version() -> u32 {
+        crate::DEFAULT_WORKFLOW_VERSION
+    }
+
     fn first_step() -> &'static str;
     fn steps() -> &'static [SagaWorkflowStepContract];
     fn terminal_policy() -> TerminalPolicy;
@@ -32,6 +41,31 @@ pub trait SagaWorkflowContract {
             &policy,
         )
     }
+
+    /// Renders the workflow as a Mermaid flowchart: steps, dependencies and
+    /// compensation edges.
+    fn to_mermaid() -> String {
+        let policy = Self::terminal_policy();
+        let required = required_steps_from_success_criteria(&policy.success_criteria);
+        render_mermaid(&[WorkflowDiagram {
+            saga_type: Self::saga_type(),
+            first_step: Self::first_step(),
+            steps: Self::steps(),
+            required_steps: &required,
+        }])
+    }
+
+    /// Renders the workflow as a Graphviz DOT digraph.
+    fn to_dot() -> String {
+        let policy = Self::terminal_policy();
+        let required = required_steps_from_success_criteria(&policy.success_criteria);
+        render_dot(&[WorkflowDiagram {
+            saga_type: Self::saga_type(),
+            first_step: Self::first_step(),
+            steps: Self::steps(),
+            required_steps: &required,
+        }])
+    }
 }
 
 pub fn required_steps_from_success_criteria(criteria: &SuccessCriteria) -> HashSet<Box<str>> {
@@ -289,6 +323,7 @@ macro_rules! define_saga_workflow_contract {
         $(#[$meta:meta])*
         $vis:vis struct $name:ident {
             saga_type: $saga_type:literal,
+            $(workflow_version: $workflow_version:literal,)?
             first_step: $first_step:ident,
             failure_authority: $failure_authority:ident $failure_arg:tt,
             required_steps: [$($required_step:ident),+ $(,)?],
@@ -324,6 +359,12 @@ macro_rules! define_saga_workflow_contract {
                 $saga_type
             }
 
+            $(
+                fn workflow_version() -> u32 {
+                    $workflow_version
+                }
+            )?
+
             fn first_step() -> &'static str {
                 stringify!($first_step)
             }
@@ -0,0 +1,279 @@
+//! Mermaid and Graphviz DOT rendering of workflow contracts.
+//!
+//! Each saga type becomes one subgraph with a `start` node, a node per
+//! declared step (labelled with its participant), a solid edge per
+//! dependency, and a dashed `compensate` edge in the opposite direction: when
+//! a later step fails, the steps it depended on are compensated. Steps the
+//! terminal policy requires for success are drawn with a heavier border.
+//! Output is deterministic so generated diagrams can be diffed per release.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::{SagaWorkflowStepContract, WorkflowDependencySpec};
+
+/// Borrowed view of one contract as needed for rendering.
+pub(crate) struct WorkflowDiagram<'a> {
+    pub(crate) saga_type: &'a str,
+    pub(crate) first_step: &'a str,
+    pub(crate) steps: &'a [SagaWorkflowStepContract],
+    pub(crate) required_steps: &'a HashSet<Box<str>>,
+}
+
+struct Edge<'a> {
+    from: &'a str,
+    to: &'a str,
+    label: Option<&'static str>,
+}
+
+impl WorkflowDiagram<'_> {
+    fn start_edges(&self) -> Vec<&str> {
+        let mut starts: Vec<&str> = self
+            .steps
+            .iter()
+            .filter(|step| matches!(step.depends_on, WorkflowDependencySpec::OnSagaStart))
+            .map(|step| step.step_name)
+            .collect();
+        if !starts.contains(&self.first_step) {
+            starts.insert(0, self.first_step);
+        }
+        starts
+    }
+
+    fn dependency_edges(&self) -> Vec<Edge<'_>> {
+        let mut edges = Vec::new();
+        for step in self.steps {
+            let (dependencies, label): (&[&str], _) = match &step.depends_on {
+                WorkflowDependencySpec::OnSagaStart => (&[], None),
+                WorkflowDependencySpec::After(dependency) => {
+                    (std::slice::from_ref(dependency), None)
+                }
+                WorkflowDependencySpec::AllOf(dependencies) => (dependencies, Some("all of")),
+                WorkflowDependencySpec::AnyOf(dependencies) => (dependencies, Some("any of")),
+            };
+            for dependency in dependencies {
+                edges.push(Edge {
+                    from: dependency,
+                    to: step.step_name,
+                    label,
+                });
+            }
+        }
+        edges
+    }
+
+    fn is_required(&self, step: &str) -> bool {
+        self.required_steps.contains(step)
+    }
+}
+
+fn mermaid_id(saga_type: &str, node: &str) -> String {
+    let sanitize = |value: &str| -> String {
+        value
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect()
+    };
+    format!("{}__{}", sanitize(saga_type), sanitize(node))
+}
+
+pub(crate) fn render_mermaid(diagrams: &[WorkflowDiagram<'_>]) -> String {
+    let mut out = String::from("flowchart TD\n");
+    out.push_str("    classDef required stroke-width:3px\n");
+    for diagram in diagrams {
+        let id = |node: &str| mermaid_id(diagram.saga_type, node);
+        let start = id("start");
+        let _ = writeln!(
+            out,
+            "    subgraph {}[\"{}\"]",
+            id("saga"),
+            diagram.saga_type
+        );
+        let _ = writeln!(out, "        {start}((start))");
+        for step in diagram.steps {
+            let class = if diagram.is_required(step.step_name) {
+                ":::required"
+            } else {
+                ""
+            };
+            let _ = writeln!(
+                out,
+                "        {}[\"{}<br/><i>{}</i>\"]{class}",
+                id(step.step_name),
+                step.step_name,
+                step.participant_id
+            );
+        }
+        for step in diagram.start_edges() {
+            let _ = writeln!(out, "        {start} --> {}", id(step));
+        }
+        let edges = diagram.dependency_edges();
+        for edge in &edges {
+            match edge.label {
+                Some(label) => {
+                    let _ = writeln!(
+                        out,
+                        "        {} -->|{label}| {}",
+                        id(edge.from),
+                        id(edge.to)
+                    );
+                }
+                None => {
+                    let _ = writeln!(out, "        {} --> {}", id(edge.from), id(edge.to));
+                }
+            }
+        }
+        for edge in &edges {
+            let _ = writeln!(
+                out,
+                "        {} -.->|compensate| {}",
+                id(edge.to),
+                id(edge.from)
+            );
+        }
+        out.push_str("    end\n");
+    }
+    out
+}
+
+fn dot_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn dot_quote(value: &str) -> String {
+    format!("\"{}\"", dot_escape(value))
+}
+
+pub(crate) fn render_dot(diagrams: &[WorkflowDiagram<'_>]) -> String {
+    let mut out = String::from("digraph saga_workflows {\n");
+    out.push_str("    rankdir=LR;\n    node [shape=box];\n");
+    for diagram in diagrams {
+        let id = |node: &str| dot_quote(&format!("{}/{}", diagram.saga_type, node));
+        let start = id("start");
+        let _ = writeln!(
+            out,
+            "    subgraph {} {{",
+            dot_quote(&format!("cluster_{}", diagram.saga_type))
+        );
+        let _ = writeln!(out, "        label={};", dot_quote(diagram.saga_type));
+        let _ = writeln!(out, "        {start} [shape=circle, label=\"start\"];");
+        for step in diagram.steps {
+            let required = if diagram.is_required(step.step_name) {
+                ", penwidth=3"
+            } else {
+                ""
+            };
+            let _ = writeln!(
+                out,
+                "        {} [label=\"{}\\n{}\"{required}];",
+                id(step.step_name),
+                dot_escape(step.step_name),
+                dot_escape(step.participant_id)
+            );
+        }
+        for step in diagram.start_edges() {
+            let _ = writeln!(out, "        {start} -> {};", id(step));
+        }
+        let edges = diagram.dependency_edges();
+        for edge in &edges {
+            match edge.label {
+                Some(label) => {
+                    let _ = writeln!(
+                        out,
+                        "        {} -> {} [label={}];",
+                        id(edge.from),
+                        id(edge.to),
+                        dot_quote(label)
+                    );
+                }
+                None => {
+                    let _ = writeln!(out, "        {} -> {};", id(edge.from), id(edge.to));
+                }
+            }
+        }
+        for edge in &edges {
+            let _ = writeln!(
+                out,
+                "        {} -> {} [style=dashed, label=\"compensate\"];",
+                id(edge.to),
+                id(edge.from)
+            );
+        }
+        out.push_str("    }\n");
+    }
+    out.push_str("}\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEPS: &[SagaWorkflowStepContract] = &[
+        SagaWorkflowStepContract {
+            step_name: "risk_check",
+            participant_id: "risk",
+            depends_on: WorkflowDependencySpec::OnSagaStart,
+        },
+        SagaWorkflowStepContract {
+            step_name: "positions_check",
+            participant_id: "positions",
+            depends_on: WorkflowDependencySpec::OnSagaStart,
+        },
+        SagaWorkflowStepContract {
+            step_name: "create_order",
+            participant_id: "order-manager",
+            depends_on: WorkflowDependencySpec::AllOf(&["risk_check", "positions_check"]),
+        },
+    ];
+
+    fn diagram(required: &HashSet<Box<str>>) -> WorkflowDiagram<'_> {
+        WorkflowDiagram {
+            saga_type: "open_position",
+            first_step: "risk_check",
+            steps: STEPS,
+            required_steps: required,
+        }
+    }
+
+    #[test]
+    fn mermaid_renders_steps_dependencies_and_compensation_edges() {
+        let required: HashSet<Box<str>> = ["create_order".into()].into();
+        let mermaid = render_mermaid(&[diagram(&required)]);
+        let expected = "\
+flowchart TD
+    classDef required stroke-width:3px
+    subgraph open_position__saga[\"open_position\"]
+        open_position__start((start))
+        open_position__risk_check[\"risk_check<br/><i>risk</i>\"]
+        open_position__positions_check[\"positions_check<br/><i>positions</i>\"]
+        open_position__create_order[\"create_order<br/><i>order-manager</i>\"]:::required
+        open_position__start --> open_position__risk_check
+        open_position__start --> open_position__positions_check
+        open_position__risk_check -->|all of| open_position__create_order
+        open_position__positions_check -->|all of| open_position__create_order
+        open_position__create_order -.->|compensate| open_position__risk_check
+        open_position__create_order -.->|compensate| open_position__positions_check
+    end
+";
+        assert_eq!(mermaid, expected);
+    }
+
+    #[test]
+    fn dot_renders_one_cluster_per_saga_type() {
+        let required: HashSet<Box<str>> = ["create_order".into()].into();
+        let dot = render_dot(&[diagram(&required)]);
+        assert!(dot.starts_with("digraph saga_workflows {\n"));
+        assert!(dot.contains("subgraph \"cluster_open_position\" {"));
+        assert!(dot.contains(
+            "\"open_position/create_order\" [label=\"create_order\\norder-manager\", penwidth=3];"
+        ));
+        assert!(dot.contains(
+            "\"open_position/risk_check\" -> \"open_position/create_order\" [label=\"all of\"];"
+        ));
+        assert!(dot.contains(
+            "\"open_position/create_order\" -> \"open_position/risk_check\" [style=dashed, label=\"compensate\"];"
+        ));
+        assert!(dot.ends_with("    }\n}\n"));
+    }
+}
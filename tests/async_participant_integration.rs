@@ -1,8 +1,16 @@
-use icanact_saga_choreography::durability::apply_async_participant_saga_ingress_with_hooks;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use icanact_saga_choreography::durability::{
+    apply_async_participant_saga_ingress_with_hooks, handle_saga_event_async,
+};
 use icanact_saga_choreography::{
-    AsyncSagaParticipant, CompensationError, DependencySpec, DeterministicContextBuilder,
-    HasSagaParticipantSupport, InMemoryDedupe, InMemoryJournal, SagaChoreographyEvent, SagaContext,
-    SagaParticipantSupport, SagaStateEntry, SagaStateExt, StepError, StepOutput,
+    step_names, AsyncDedupeAdapter, AsyncJournalAdapter, AsyncParticipantDedupeStore,
+    AsyncParticipantJournal, AsyncSagaParticipant, CompensationError, DedupeError, DedupeKey,
+    DependencySpec, DeterministicContextBuilder, HasSagaParticipantSupport, InMemoryDedupe,
+    InMemoryJournal, JournalEntry, JournalError, ParticipantDedupeStore, ParticipantEvent,
+    ParticipantJournal, SagaBoxFuture, SagaChoreographyBus, SagaChoreographyEvent, SagaContext,
+    SagaId, SagaParticipantSupport, SagaStateEntry, SagaStateExt, StepError, StepOutput,
 };
 
 struct AsyncTestParticipant {
@@ -119,7 +127,7 @@ async fn async_ingress_executes_on_saga_start_and_emits_step_completed() {
 #[tokio::test]
 async fn async_ingress_waits_for_all_dependencies_before_execution() {
     let mut participant = AsyncTestParticipant {
-        dependency_spec: DependencySpec::AllOf(&["a", "b"]),
+        dependency_spec: DependencySpec::AllOf(step_names!["a", "b"]),
         ..AsyncTestParticipant::default()
     };
     let ctx = DeterministicContextBuilder::default().build();
@@ -131,6 +139,7 @@ async fn async_ingress_waits_for_all_dependencies_before_execution() {
             output: b"partial".to_vec(),
             saga_input: b"origin".to_vec(),
             compensation_available: false,
+            completion_ratio: None,
         },
         |_actor, _incoming| {},
         |_invalid| {},
@@ -148,6 +157,7 @@ async fn async_ingress_waits_for_all_dependencies_before_execution() {
             output: b"final".to_vec(),
             saga_input: b"origin".to_vec(),
             compensation_available: false,
+            completion_ratio: None,
         },
         |_actor, _incoming| {},
         |_invalid| {},
@@ -166,9 +176,7 @@ async fn async_ingress_waits_for_all_dependencies_before_execution() {
 #[tokio::test]
 async fn async_ingress_non_ambiguous_compensation_failure_keeps_local_quarantine_only() {
     let mut participant = AsyncTestParticipant {
-        compensation_result: Err(CompensationError::Terminal {
-            reason: "undo failed".into(),
-        }),
+        compensation_result: Err(CompensationError::terminal("undo failed")),
         ..AsyncTestParticipant::default()
     };
     let context = DeterministicContextBuilder::default().build();
@@ -211,3 +219,250 @@ async fn async_ingress_non_ambiguous_compensation_failure_keeps_local_quarantine
         Some(SagaStateEntry::Quarantined(_))
     ));
 }
+
+/// Async stores over the in-memory ones, logging the calls they serve.
+#[derive(Default)]
+struct AsyncMemoryStore {
+    journal: InMemoryJournal,
+    dedupe: InMemoryDedupe,
+    calls: Mutex<Vec<&'static str>>,
+}
+
+impl AsyncMemoryStore {
+    fn call<T: Send + 'static>(&self, name: &'static str, result: T) -> SagaBoxFuture<'_, T> {
+        self.calls.lock().unwrap().push(name);
+        Box::pin(async move {
+            tokio::task::yield_now().await;
+            result
+        })
+    }
+
+    fn calls(&self) -> Vec<&'static str> {
+        self.calls.lock().unwrap().clone()
+    }
+}
+
+impl AsyncParticipantJournal for AsyncMemoryStore {
+    fn append(
+        &self,
+        saga_id: SagaId,
+        event: ParticipantEvent,
+    ) -> SagaBoxFuture<'_, Result<u64, JournalError>> {
+        self.call("journal.append", self.journal.append(saga_id, event))
+    }
+
+    fn read(&self, saga_id: SagaId) -> SagaBoxFuture<'_, Result<Vec<JournalEntry>, JournalError>> {
+        self.call("journal.read", self.journal.read(saga_id))
+    }
+
+    fn list_sagas(&self) -> SagaBoxFuture<'_, Result<Vec<SagaId>, JournalError>> {
+        self.call("journal.list_sagas", self.journal.list_sagas())
+    }
+
+    fn prune(&self, saga_id: SagaId) -> SagaBoxFuture<'_, Result<(), JournalError>> {
+        self.call("journal.prune", self.journal.prune(saga_id))
+    }
+
+    fn record_outgoing<'a>(
+        &'a self,
+        saga_id: SagaId,
+        event: &'a SagaChoreographyEvent,
+    ) -> SagaBoxFuture<'a, Result<Option<u64>, JournalError>> {
+        self.call(
+            "journal.record_outgoing",
+            self.journal.record_outgoing(saga_id, event),
+        )
+    }
+
+    fn mark_outgoing_sent(&self, outbox_id: u64) -> SagaBoxFuture<'_, Result<(), JournalError>> {
+        self.call(
+            "journal.mark_outgoing_sent",
+            self.journal.mark_outgoing_sent(outbox_id),
+        )
+    }
+
+    fn record_incoming<'a>(
+        &'a self,
+        saga_id: SagaId,
+        dedupe_key: DedupeKey,
+        event: &'a SagaChoreographyEvent,
+    ) -> SagaBoxFuture<'a, Result<Option<u64>, JournalError>> {
+        self.call(
+            "journal.record_incoming",
+            self.journal.record_incoming(saga_id, dedupe_key, event),
+        )
+    }
+
+    fn mark_incoming_processed(
+        &self,
+        inbox_id: u64,
+    ) -> SagaBoxFuture<'_, Result<(), JournalError>> {
+        self.call(
+            "journal.mark_incoming_processed",
+            self.journal.mark_incoming_processed(inbox_id),
+        )
+    }
+}
+
+impl AsyncParticipantDedupeStore for AsyncMemoryStore {
+    fn check_and_mark(
+        &self,
+        saga_id: SagaId,
+        key: DedupeKey,
+    ) -> SagaBoxFuture<'_, Result<bool, DedupeError>> {
+        self.call(
+            "dedupe.check_and_mark",
+            self.dedupe.check_and_mark(saga_id, key),
+        )
+    }
+
+    fn contains(&self, saga_id: SagaId, key: DedupeKey) -> SagaBoxFuture<'_, bool> {
+        self.call("dedupe.contains", self.dedupe.contains(saga_id, key))
+    }
+
+    fn mark_processed(
+        &self,
+        saga_id: SagaId,
+        key: DedupeKey,
+    ) -> SagaBoxFuture<'_, Result<(), DedupeError>> {
+        self.call(
+            "dedupe.mark_processed",
+            self.dedupe.mark_processed(saga_id, key),
+        )
+    }
+
+    fn prune(&self, saga_id: SagaId) -> SagaBoxFuture<'_, Result<(), DedupeError>> {
+        self.call("dedupe.prune", self.dedupe.prune(saga_id))
+    }
+}
+
+type AsyncStoreSupport = SagaParticipantSupport<
+    AsyncJournalAdapter<Arc<AsyncMemoryStore>>,
+    AsyncDedupeAdapter<Arc<AsyncMemoryStore>>,
+>;
+
+struct AsyncStoreParticipant {
+    saga: AsyncStoreSupport,
+}
+
+impl HasSagaParticipantSupport for AsyncStoreParticipant {
+    type Journal = AsyncJournalAdapter<Arc<AsyncMemoryStore>>;
+    type Dedupe = AsyncDedupeAdapter<Arc<AsyncMemoryStore>>;
+
+    fn saga_support(&self) -> &AsyncStoreSupport {
+        &self.saga
+    }
+
+    fn saga_support_mut(&mut self) -> &mut AsyncStoreSupport {
+        &mut self.saga
+    }
+}
+
+impl AsyncSagaParticipant for AsyncStoreParticipant {
+    type Error = String;
+
+    fn step_name(&self) -> &str {
+        "async_step"
+    }
+
+    fn saga_types(&self) -> &[&'static str] {
+        &["order_lifecycle"]
+    }
+
+    fn execute_step<'a>(
+        &'a mut self,
+        _context: &'a SagaContext,
+        _input: &'a [u8],
+    ) -> SagaBoxFuture<'a, Result<StepOutput, StepError>> {
+        Box::pin(async {
+            Ok(StepOutput::Completed {
+                output: b"ok".to_vec(),
+                compensation_data: Vec::new(),
+            })
+        })
+    }
+
+    fn compensate_step<'a>(
+        &'a mut self,
+        _context: &'a SagaContext,
+        _compensation_data: &'a [u8],
+    ) -> SagaBoxFuture<'a, Result<(), CompensationError>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn handle_saga_event_async_awaits_async_stores_and_publishes() {
+    let store = Arc::new(AsyncMemoryStore::default());
+    let mut participant = AsyncStoreParticipant {
+        saga: SagaParticipantSupport::new(
+            AsyncJournalAdapter::new(store.clone()),
+            AsyncDedupeAdapter::new(store.clone()),
+        ),
+    };
+    let bus = SagaChoreographyBus::new();
+    let completed = Arc::new(AtomicUsize::new(0));
+    let seen = completed.clone();
+    let _sub = bus.subscribe_saga_type_fn("order_lifecycle", move |event| {
+        if matches!(event, SagaChoreographyEvent::StepCompleted { .. }) {
+            seen.fetch_add(1, Ordering::Relaxed);
+        }
+        true
+    });
+    participant.saga.attach_bus(bus);
+    let started = started_event();
+    let saga_id = started.context().saga_id;
+
+    handle_saga_event_async(&mut participant, started.clone()).await;
+    assert_eq!(
+        store.calls(),
+        [
+            "journal.record_incoming",
+            "dedupe.check_and_mark",
+            // StepExecutionStarted
+            "journal.append",
+            // StepExecutionCompleted, staging StepCompleted
+            "journal.append",
+            "journal.record_outgoing",
+            "journal.mark_incoming_processed",
+            // StepStarted goes out through the outbox
+            "journal.record_outgoing",
+            "journal.mark_outgoing_sent",
+            // StepCompleted was staged with its entry
+            "journal.mark_outgoing_sent",
+        ]
+    );
+
+    store.calls.lock().unwrap().clear();
+    handle_saga_event_async(&mut participant, started).await;
+    assert_eq!(
+        store.calls(),
+        [
+            "journal.record_incoming",
+            "dedupe.check_and_mark",
+            "journal.mark_incoming_processed",
+        ]
+    );
+
+    assert_eq!(completed.load(Ordering::Relaxed), 1);
+    assert!(matches!(
+        participant.saga_states_ref().get(&saga_id),
+        Some(SagaStateEntry::Completed(_))
+    ));
+    assert_eq!(store.journal.read(saga_id).unwrap().len(), 2);
+    assert!(store.journal.pending_incoming().unwrap().is_empty());
+    assert!(store.journal.pending_outgoing().unwrap().is_empty());
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn async_store_adapters_refuse_sync_calls() {
+    let store = Arc::new(AsyncMemoryStore::default());
+    let journal = AsyncJournalAdapter::new(store.clone());
+    let dedupe = AsyncDedupeAdapter::new(store.clone());
+
+    assert!(journal.list_sagas().is_err());
+    assert!(dedupe
+        .check_and_mark(SagaId::new(1), DedupeKey::named("k"))
+        .is_err());
+    assert!(store.calls().is_empty());
+}
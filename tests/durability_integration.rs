@@ -118,6 +118,9 @@ fn context(saga_id: u64, saga_type: &'static str, step_name: &'static str) -> Sa
         initiator_peer_id: [0; 32],
         saga_started_at_millis: now,
         event_timestamp_millis: now,
+        parent_saga_id: None,
+        workflow_version: 1,
+        logical_clock: 1,
     }
 }
 
@@ -260,7 +263,7 @@ fn panic_quarantine_records_journal_marks_dedupe_and_publishes() {
     assert!(participant
         .saga
         .dedupe
-        .contains(saga_context.saga_id, PANIC_QUARANTINE_PUBLISH_KEY,));
+        .contains(saga_context.saga_id, PANIC_QUARANTINE_PUBLISH_KEY.into()));
 }
 
 #[test]
@@ -615,6 +618,7 @@ fn helper_and_wrapper_apis_cover_default_branches() {
             output: vec![],
             saga_input: vec![],
             compensation_available: false,
+            completion_ratio: None,
         }
     ));
     assert!(!is_valid_emitted_transition(
@@ -624,6 +628,7 @@ fn helper_and_wrapper_apis_cover_default_branches() {
             output: vec![],
             saga_input: vec![],
             compensation_available: false,
+            completion_ratio: None,
         }
     ));
     let failed = failed_entry(SagaId::new(81), ORDER_LIFECYCLE, TEST_STEP);
@@ -635,6 +640,7 @@ fn helper_and_wrapper_apis_cover_default_branches() {
             error_code: None,
             error: "err".into(),
             requires_compensation: true,
+            error_details: Vec::new(),
         }
     ));
     let compensated = compensated_entry(SagaId::new(79), ORDER_LIFECYCLE, TEST_STEP);
@@ -652,6 +658,7 @@ fn helper_and_wrapper_apis_cover_default_branches() {
             participant_id: "pid".into(),
             error: "err".into(),
             is_ambiguous: false,
+            error_details: Vec::new(),
         }
     ));
     assert!(is_valid_emitted_transition(
@@ -790,7 +797,7 @@ fn helper_propagates_open_errors_and_honors_env_runtime_dir() {
     assert!(
         !support_without_bus
             .dedupe
-            .contains(no_bus_context.saga_id, PANIC_QUARANTINE_PUBLISH_KEY),
+            .contains(no_bus_context.saga_id, PANIC_QUARANTINE_PUBLISH_KEY.into()),
         "without bus delivery we should not mark panic quarantine dedupe key"
     );
 }
@@ -72,20 +72,77 @@ fn lmdb_journal_and_dedupe_roundtrip() {
     );
 
     assert!(dedupe
-        .check_and_mark(saga_a, "probe")
+        .check_and_mark(saga_a, "probe".into())
         .expect("first check_and_mark should succeed"));
     assert!(!dedupe
-        .check_and_mark(saga_a, "probe")
+        .check_and_mark(saga_a, "probe".into())
         .expect("second check_and_mark should succeed"));
-    assert!(dedupe.contains(saga_a, "probe"));
+    assert!(dedupe.contains(saga_a, "probe".into()));
 
     dedupe
-        .mark_processed(saga_b, "manual")
+        .mark_processed(saga_b, "manual".into())
         .expect("mark_processed should succeed");
-    assert!(dedupe.contains(saga_b, "manual"));
+    assert!(dedupe.contains(saga_b, "manual".into()));
 
     dedupe.prune(saga_a).expect("prune should succeed");
-    assert!(!dedupe.contains(saga_a, "probe"));
+    assert!(!dedupe.contains(saga_a, "probe".into()));
+}
+
+#[test]
+fn lmdb_journal_lists_sagas() {
+    let temp = tempfile::tempdir().expect("tempdir should open");
+    let journal_path = temp.path().join("journal");
+    let dedupe_path = temp.path().join("dedupe");
+
+    let journal = LmdbJournal::open(&journal_path).expect("journal should open");
+    let dedupe = LmdbDedupe::open(&dedupe_path).expect("dedupe should open");
+
+    let saga_a = SagaId::new(100);
+    let saga_b = SagaId::new(101);
+
+    journal
+        .append(
+            saga_b,
+            ParticipantEvent::StepExecutionStarted {
+                attempt: 1,
+                started_at_millis: 1000,
+            },
+        )
+        .expect("append should succeed");
+    journal
+        .append(
+            saga_a,
+            ParticipantEvent::StepExecutionStarted {
+                attempt: 1,
+                started_at_millis: 1100,
+            },
+        )
+        .expect("append should succeed");
+    let mut saga_ids = journal.list_sagas().expect("list_sagas should succeed");
+    saga_ids.sort_by_key(|id| id.get());
+    assert_eq!(saga_ids, vec![saga_a, saga_b]);
+
+    dedupe
+        .mark_processed(saga_b, "manual".into())
+        .expect("mark_processed should succeed");
+    dedupe
+        .mark_processed(saga_a, "probe".into())
+        .expect("mark_processed should succeed");
+    dedupe
+        .mark_processed(saga_a, "second".into())
+        .expect("mark_processed should succeed");
+    assert_eq!(
+        dedupe.list_sagas().expect("list_sagas should succeed"),
+        Some(vec![saga_a, saga_b])
+    );
+
+    dedupe.prune(saga_a).expect("prune should succeed");
+    assert_eq!(
+        dedupe
+            .list_sagas()
+            .expect("list after prune should succeed"),
+        Some(vec![saga_b])
+    );
 }
 
 struct LmdbParticipant {
@@ -132,7 +189,7 @@ fn prune_saga_removes_lmdb_journal_and_dedupe_state() {
         )
         .expect("append saga_b should succeed");
     dedupe
-        .mark_processed(saga_a, "started")
+        .mark_processed(saga_a, "started".into())
         .expect("dedupe mark should succeed");
 
     let mut actor = LmdbParticipant {
@@ -161,7 +218,7 @@ fn prune_saga_removes_lmdb_journal_and_dedupe_state() {
         "terminal cleanup must remove only the pruned saga index"
     );
     assert!(
-        !actor.saga.dedupe.contains(saga_a, "started"),
+        !actor.saga.dedupe.contains(saga_a, "started".into()),
         "terminal cleanup must still prune dedupe rows"
     );
 }
@@ -21,8 +21,8 @@ use icanact_saga_choreography::durability::{
     HasActiveSagaExecution,
 };
 use icanact_saga_choreography::{
-    bind_sync_participant_channel, handle_saga_event_with_emit, CompensationError, DependencySpec,
-    FailureAuthority, HasSagaParticipantSupport, InMemoryDedupe, InMemoryJournal,
+    bind_sync_participant_channel, handle_saga_event_with_emit, step_names, CompensationError,
+    DependencySpec, FailureAuthority, HasSagaParticipantSupport, InMemoryDedupe, InMemoryJournal,
     SagaChoreographyBus, SagaChoreographyEvent, SagaContext, SagaId, SagaParticipant,
     SagaParticipantChannel, SagaParticipantSupport, SagaWorkflowContract, SagaWorkflowStepContract,
     StepError, StepOutput, SuccessCriteria, TerminalPolicy, WorkflowDependencySpec,
@@ -334,19 +334,18 @@ fn context_for(saga_id: u64) -> SagaContext {
         initiator_peer_id: [0; 32],
         saga_started_at_millis: now,
         event_timestamp_millis: now,
+        parent_saga_id: None,
+        workflow_version: 1,
+        logical_clock: 1,
     }
 }
 
 fn terminal_error(reason: &str) -> StepError {
-    StepError::Terminal {
-        reason: reason.into(),
-    }
+    StepError::terminal(reason)
 }
 
 fn require_compensation_error(reason: &str) -> StepError {
-    StepError::RequireCompensation {
-        reason: reason.into(),
-    }
+    StepError::require_compensation(reason)
 }
 
 fn test_policy() -> TerminalPolicy {
@@ -547,7 +546,7 @@ fn all_succeed_saga_completed() {
         &bus,
         ConfigurableParticipant::new(
             STEP_ORDER,
-            DependencySpec::AllOf(&[STEP_POSITION, STEP_BALANCE]),
+            DependencySpec::AllOf(step_names![STEP_POSITION, STEP_BALANCE]),
         ),
     );
 
@@ -626,7 +625,7 @@ fn position_fails_terminal_no_compensation() {
         &bus,
         ConfigurableParticipant::new(
             STEP_ORDER,
-            DependencySpec::AllOf(&[STEP_POSITION, STEP_BALANCE]),
+            DependencySpec::AllOf(step_names![STEP_POSITION, STEP_BALANCE]),
         ),
     );
 
@@ -703,7 +702,7 @@ fn balance_fails_terminal_after_position_succeeds() {
         &bus,
         ConfigurableParticipant::new(
             STEP_ORDER,
-            DependencySpec::AllOf(&[STEP_POSITION, STEP_BALANCE]),
+            DependencySpec::AllOf(step_names![STEP_POSITION, STEP_BALANCE]),
         ),
     );
 
@@ -771,7 +770,7 @@ fn order_fails_terminal_after_both_succeed() {
         &bus,
         ConfigurableParticipant::new(
             STEP_ORDER,
-            DependencySpec::AllOf(&[STEP_POSITION, STEP_BALANCE]),
+            DependencySpec::AllOf(step_names![STEP_POSITION, STEP_BALANCE]),
         )
         .with_execute_result(Err(terminal_error("order rejected"))),
     );
@@ -840,7 +839,7 @@ fn order_fails_require_compensation_triggers_full_compensation() {
         &bus,
         ConfigurableParticipant::new(
             STEP_ORDER,
-            DependencySpec::AllOf(&[STEP_POSITION, STEP_BALANCE]),
+            DependencySpec::AllOf(step_names![STEP_POSITION, STEP_BALANCE]),
         )
         .with_execute_result(Err(require_compensation_error("order partially created"))),
     );
@@ -892,9 +891,7 @@ fn position_compensation_fails_terminal_causes_saga_failed() {
         &world,
         &bus,
         ConfigurableParticipant::new(STEP_POSITION, DependencySpec::OnSagaStart)
-            .with_compensate_result(Err(CompensationError::Terminal {
-                reason: "cannot undo position".into(),
-            })),
+            .with_compensate_result(Err(CompensationError::terminal("cannot undo position"))),
     );
     let ctx = context_for(6);
     let _ = bus.publish(SagaChoreographyEvent::SagaStarted {
@@ -910,6 +907,7 @@ fn position_compensation_fails_terminal_causes_saga_failed() {
         error_code: None,
         error: "balance check fatal".into(),
         requires_compensation: true,
+        error_details: Vec::new(),
     });
 
     wait_until(TIMEOUT, || query_terminal_counts(&terminal_ref).failed >= 1);
@@ -956,16 +954,14 @@ fn balance_compensation_fails_ambiguous_causes_quarantine() {
         &world,
         &bus,
         ConfigurableParticipant::new(STEP_BALANCE, DependencySpec::OnSagaStart)
-            .with_compensate_result(Err(CompensationError::Ambiguous {
-                reason: "partial rollback".into(),
-            })),
+            .with_compensate_result(Err(CompensationError::ambiguous("partial rollback"))),
     );
     let (_o_ref, o_h) = spawn_and_subscribe(
         &world,
         &bus,
         ConfigurableParticipant::new(
             STEP_ORDER,
-            DependencySpec::AllOf(&[STEP_POSITION, STEP_BALANCE]),
+            DependencySpec::AllOf(step_names![STEP_POSITION, STEP_BALANCE]),
         )
         .with_execute_result(Err(require_compensation_error("order failed"))),
     );
@@ -1008,16 +1004,14 @@ fn balance_compensation_fails_safe_to_retry_causes_failed() {
         &world,
         &bus,
         ConfigurableParticipant::new(STEP_BALANCE, DependencySpec::OnSagaStart)
-            .with_compensate_result(Err(CompensationError::SafeToRetry {
-                reason: "transient".into(),
-            })),
+            .with_compensate_result(Err(CompensationError::safe_to_retry("transient"))),
     );
     let (_o_ref, o_h) = spawn_and_subscribe(
         &world,
         &bus,
         ConfigurableParticipant::new(
             STEP_ORDER,
-            DependencySpec::AllOf(&[STEP_POSITION, STEP_BALANCE]),
+            DependencySpec::AllOf(step_names![STEP_POSITION, STEP_BALANCE]),
         )
         .with_execute_result(Err(require_compensation_error("order failed"))),
     );
@@ -1068,7 +1062,7 @@ fn position_panics_emits_quarantined() {
         &bus,
         ConfigurableParticipant::new(
             STEP_ORDER,
-            DependencySpec::AllOf(&[STEP_POSITION, STEP_BALANCE]),
+            DependencySpec::AllOf(step_names![STEP_POSITION, STEP_BALANCE]),
         ),
     );
 
@@ -1122,7 +1116,7 @@ fn balance_panics_after_position_succeeds() {
         &bus,
         ConfigurableParticipant::new(
             STEP_ORDER,
-            DependencySpec::AllOf(&[STEP_POSITION, STEP_BALANCE]),
+            DependencySpec::AllOf(step_names![STEP_POSITION, STEP_BALANCE]),
         ),
     );
 
@@ -1188,7 +1182,7 @@ fn order_panics_after_both_succeed() {
     // Order: NOT subscribed -- driven manually
     let mut order = ConfigurableParticipant::new(
         STEP_ORDER,
-        DependencySpec::AllOf(&[STEP_POSITION, STEP_BALANCE]),
+        DependencySpec::AllOf(step_names![STEP_POSITION, STEP_BALANCE]),
     )
     .with_panic();
     order.attach_bus(bus.clone());
@@ -1264,7 +1258,7 @@ fn duplicate_saga_started_is_deduped() {
         &bus,
         ConfigurableParticipant::new(
             STEP_ORDER,
-            DependencySpec::AllOf(&[STEP_POSITION, STEP_BALANCE]),
+            DependencySpec::AllOf(step_names![STEP_POSITION, STEP_BALANCE]),
         ),
     );
 
@@ -1304,7 +1298,7 @@ fn order_does_not_fire_on_partial_dependency() {
         &bus,
         ConfigurableParticipant::new(
             STEP_ORDER,
-            DependencySpec::AllOf(&[STEP_POSITION, STEP_BALANCE]),
+            DependencySpec::AllOf(step_names![STEP_POSITION, STEP_BALANCE]),
         ),
     );
 
@@ -1316,6 +1310,7 @@ fn order_does_not_fire_on_partial_dependency() {
         output: vec![],
         saga_input: vec![42],
         compensation_available: false,
+        completion_ratio: None,
     });
 
     std::thread::sleep(Duration::from_millis(100));
@@ -1331,6 +1326,7 @@ fn order_does_not_fire_on_partial_dependency() {
         output: vec![],
         saga_input: vec![42],
         compensation_available: false,
+        completion_ratio: None,
     });
 
     std::thread::sleep(Duration::from_millis(100));
@@ -1371,7 +1367,7 @@ fn terminal_latch_prevents_duplicate_events() {
         &bus,
         ConfigurableParticipant::new(
             STEP_ORDER,
-            DependencySpec::AllOf(&[STEP_POSITION, STEP_BALANCE]),
+            DependencySpec::AllOf(step_names![STEP_POSITION, STEP_BALANCE]),
         ),
     );
 
@@ -1392,6 +1388,7 @@ fn terminal_latch_prevents_duplicate_events() {
         output: vec![],
         saga_input: vec![],
         compensation_available: false,
+        completion_ratio: None,
     });
 
     std::thread::sleep(Duration::from_millis(100));
@@ -1435,7 +1432,7 @@ fn duplicate_dependency_completion_after_order_executes_is_deduped() {
         &bus,
         ConfigurableParticipant::new(
             STEP_ORDER,
-            DependencySpec::AllOf(&[STEP_POSITION, STEP_BALANCE]),
+            DependencySpec::AllOf(step_names![STEP_POSITION, STEP_BALANCE]),
         ),
     );
 
@@ -1455,6 +1452,7 @@ fn duplicate_dependency_completion_after_order_executes_is_deduped() {
         output: vec![],
         saga_input: vec![42],
         compensation_available: true,
+        completion_ratio: None,
     });
 
     std::thread::sleep(Duration::from_millis(100));
@@ -1544,7 +1542,7 @@ fn duplicate_position_approval_before_balance_keeps_order_exactly_once() {
         &bus,
         ConfigurableParticipant::new(
             STEP_ORDER,
-            DependencySpec::AllOf(&[STEP_POSITION, STEP_BALANCE]),
+            DependencySpec::AllOf(step_names![STEP_POSITION, STEP_BALANCE]),
         ),
     );
 
@@ -1554,12 +1552,14 @@ fn duplicate_position_approval_before_balance_keeps_order_exactly_once() {
         output: vec![],
         saga_input: vec![42],
         compensation_available: true,
+        completion_ratio: None,
     });
     let _ = bus.publish(SagaChoreographyEvent::StepCompleted {
         context: ctx.next_step(STEP_POSITION.into()),
         output: vec![],
         saga_input: vec![42],
         compensation_available: true,
+        completion_ratio: None,
     });
 
     std::thread::sleep(Duration::from_millis(100));
@@ -1570,6 +1570,7 @@ fn duplicate_position_approval_before_balance_keeps_order_exactly_once() {
         output: vec![],
         saga_input: vec![42],
         compensation_available: true,
+        completion_ratio: None,
     });
 
     wait_until(TIMEOUT, || query_state(&o_ref).executed_count >= 1);
@@ -1582,6 +1583,7 @@ fn duplicate_position_approval_before_balance_keeps_order_exactly_once() {
         output: vec![],
         saga_input: vec![42],
         compensation_available: true,
+        completion_ratio: None,
     });
 
     std::thread::sleep(Duration::from_millis(100));
@@ -1616,7 +1618,7 @@ fn duplicate_balance_approval_before_position_keeps_order_exactly_once() {
         &bus,
         ConfigurableParticipant::new(
             STEP_ORDER,
-            DependencySpec::AllOf(&[STEP_POSITION, STEP_BALANCE]),
+            DependencySpec::AllOf(step_names![STEP_POSITION, STEP_BALANCE]),
         ),
     );
 
@@ -1626,12 +1628,14 @@ fn duplicate_balance_approval_before_position_keeps_order_exactly_once() {
         output: vec![],
         saga_input: vec![42],
         compensation_available: true,
+        completion_ratio: None,
     });
     let _ = bus.publish(SagaChoreographyEvent::StepCompleted {
         context: ctx.next_step(STEP_BALANCE.into()),
         output: vec![],
         saga_input: vec![42],
         compensation_available: true,
+        completion_ratio: None,
     });
 
     std::thread::sleep(Duration::from_millis(100));
@@ -1642,6 +1646,7 @@ fn duplicate_balance_approval_before_position_keeps_order_exactly_once() {
         output: vec![],
         saga_input: vec![42],
         compensation_available: true,
+        completion_ratio: None,
     });
 
     wait_until(TIMEOUT, || query_state(&o_ref).executed_count >= 1);
@@ -1654,6 +1659,7 @@ fn duplicate_balance_approval_before_position_keeps_order_exactly_once() {
         output: vec![],
         saga_input: vec![42],
         compensation_available: true,
+        completion_ratio: None,
     });
 
     std::thread::sleep(Duration::from_millis(100));
@@ -1692,7 +1698,7 @@ fn duplicate_both_approvals_still_produce_single_order_execution() {
         &bus,
         ConfigurableParticipant::new(
             STEP_ORDER,
-            DependencySpec::AllOf(&[STEP_POSITION, STEP_BALANCE]),
+            DependencySpec::AllOf(step_names![STEP_POSITION, STEP_BALANCE]),
         ),
     );
 
@@ -1714,12 +1720,14 @@ fn duplicate_both_approvals_still_produce_single_order_execution() {
         output: vec![],
         saga_input: vec![42],
         compensation_available: true,
+        completion_ratio: None,
     };
     let duplicate_balance = SagaChoreographyEvent::StepCompleted {
         context: ctx.next_step(STEP_BALANCE.into()),
         output: vec![],
         saga_input: vec![42],
         compensation_available: true,
+        completion_ratio: None,
     };
     let _ = bus.publish(duplicate_position.clone());
     let _ = bus.publish(duplicate_balance.clone());
@@ -7,8 +7,8 @@ use std::time::Duration;
 use icanact_core::local_async::{self, AsyncActor};
 use icanact_core::local_sync::{self, SyncActor};
 use icanact_saga_choreography::{
-    define_saga_workflow_contract, AsyncSagaParticipant, CompensationError, DependencySpec,
-    DeterministicContextBuilder, FailureAuthority, HasSagaParticipantSupport,
+    define_saga_workflow_contract, AsyncSagaParticipant, CompensationError, DedupeKey,
+    DependencySpec, DeterministicContextBuilder, FailureAuthority, HasSagaParticipantSupport,
     HasSagaWorkflowParticipants, InMemoryDedupe, InMemoryJournal, JournalEntry,
     ParticipantDedupeStore, ParticipantJournal, SagaChoreographyEvent, SagaParticipant,
     SagaParticipantChannel, SagaParticipantSupport, SagaStateExt, SagaTerminalOutcome,
@@ -104,9 +104,10 @@ impl SagaParticipant for SyncParticipant {
     ) -> Result<StepOutput, StepError> {
         self.executed_inputs.push(input.to_vec());
         if self.fail_on_execute {
-            return Err(StepError::RequireCompensation {
-                reason: format!("{} failed", self.step_name).into(),
-            });
+            return Err(StepError::require_compensation(format!(
+                "{} failed",
+                self.step_name
+            )));
         }
         Ok(StepOutput::Completed {
             output: self.step_name.as_bytes().to_vec(),
@@ -757,7 +758,7 @@ fn sync_world_runs_real_saga_workflow_and_exposes_actor_state() {
     let step_b = world.spawn_sync_channel_participant(
         SyncParticipant::new(
             "step_b",
-            DependencySpec::After("step_a"),
+            DependencySpec::after("step_a"),
             Arc::new(InMemoryJournal::new()),
             Arc::new(InMemoryDedupe::new()),
         ),
@@ -771,6 +772,10 @@ fn sync_world_runs_real_saga_workflow_and_exposes_actor_state() {
         .with_step_name("start")
         .build();
     let saga_id = ctx.saga_id;
+    let started_key = DedupeKey::from_event(&SagaChoreographyEvent::SagaStarted {
+        context: ctx.clone(),
+        payload: b"payload".to_vec(),
+    });
 
     assert!(step_a.actor_ref().tell(SyncCmd::AddBusinessFlag("warm")));
     world.start_saga(ctx, b"payload".to_vec());
@@ -808,7 +813,7 @@ fn sync_world_runs_real_saga_workflow_and_exposes_actor_state() {
         "terminal processing should prune participant journal rows"
     );
     assert!(
-        !step_a_dedupe.contains(saga_id, "1:1700000000000:saga_started:start"),
+        !step_a_dedupe.contains(saga_id, started_key),
         "terminal processing should prune participant dedupe keys"
     );
 
@@ -895,7 +900,7 @@ fn sync_world_spawn_args_runs_real_saga_workflow() {
     let step_b = world.spawn_sync_channel_participant(
         SyncParticipant::new(
             "step_b",
-            DependencySpec::After("step_a"),
+            DependencySpec::after("step_a"),
             Arc::new(InMemoryJournal::new()),
             Arc::new(InMemoryDedupe::new()),
         ),
@@ -941,7 +946,7 @@ fn sync_world_drives_compensation_flow_without_changing_actor_contracts() {
     );
     let mut failing_step_b = SyncParticipant::new(
         "step_b",
-        DependencySpec::After("step_a"),
+        DependencySpec::after("step_a"),
         Arc::new(InMemoryJournal::new()),
         Arc::new(InMemoryDedupe::new()),
     );